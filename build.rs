@@ -0,0 +1,7 @@
+fn main() {
+    // The gRPC service is only generated with the `grpc` feature, so default
+    // builds don't need protoc.
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/multiai.proto")
+        .expect("Failed to compile proto/multiai.proto");
+}
@@ -8,6 +8,7 @@ import Settings from './components/Settings';
 import NoApiKeysBanner from './components/NoApiKeysBanner';
 import { comparisonResponses, clearResponses, setComparisonResponses, removeModelResponse } from './stores/comparisonStore';
 import { useStreamingChat } from './hooks/useStreamingChat';
+import { gatewayFetch } from './services/gateway';
 
 // Loading screen component with progress bar
 function LoadingScreen(props) {
@@ -53,6 +54,9 @@ function App() {
   const params = useParams();
   const navigate = useNavigate();
 
+  // Prompt handed over by a multiai://ask deep link
+  const initialPrompt = new URLSearchParams(window.location.search).get('q') || '';
+
   // Use the new streaming hook with retry, circuit breaker, and abort support
   const streaming = useStreamingChat();
 
@@ -318,7 +322,7 @@ function App() {
 
   async function checkModels() {
     try {
-      const res = await fetch('/v1/models');
+      const res = await gatewayFetch('/v1/models');
       const data = await res.json();
       const count = data.data?.length || 0;
       setModelCount(count);
@@ -331,7 +335,7 @@ function App() {
 
   async function loadProviderSettings() {
     try {
-      const res = await fetch('/api/settings');
+      const res = await gatewayFetch('/api/settings');
       const data = await res.json();
       setConfiguredProviders({
         openCodeZen: data.opencode_zen_configured || false,
@@ -345,7 +349,7 @@ function App() {
   // Auto-select models for panes on startup (with localStorage persistence)
   async function autoSelectModels() {
     try {
-      const res = await fetch('/v1/models/grouped');
+      const res = await gatewayFetch('/v1/models/grouped');
       const data = await res.json();
 
       // Store all models for pane dropdowns
@@ -484,7 +488,7 @@ function App() {
 
   async function loadChats() {
     try {
-      const res = await fetch('/api/chats');
+      const res = await gatewayFetch('/api/chats');
       const data = await res.json();
       setChats(data.chats || []);
     } catch (err) {
@@ -494,7 +498,7 @@ function App() {
 
   async function loadChat(id) {
     try {
-      const res = await fetch(`/api/chats/${id}`);
+      const res = await gatewayFetch(`/api/chats/${id}`);
       if (res.ok) {
         const data = await res.json();
         setCurrentChat({ id: data.id, title: data.title });
@@ -516,7 +520,7 @@ function App() {
 
   async function createNewChat() {
     try {
-      const res = await fetch('/api/chats', {
+      const res = await gatewayFetch('/api/chats', {
         method: 'POST',
         headers: { 'Content-Type': 'application/json' },
         body: JSON.stringify({})
@@ -533,7 +537,7 @@ function App() {
 
   async function deleteChat(id) {
     try {
-      await fetch(`/api/chats/${id}`, { method: 'DELETE' });
+      await gatewayFetch(`/api/chats/${id}`, { method: 'DELETE' });
       await loadChats();
       if (currentChat()?.id === id) {
         navigate('/');
@@ -553,7 +557,7 @@ function App() {
       console.log('Deleting chats:', chatList.length);
       for (const chat of chatList) {
         console.log('Deleting chat:', chat.id);
-        await fetch(`/api/chats/${chat.id}`, { method: 'DELETE' });
+        await gatewayFetch(`/api/chats/${chat.id}`, { method: 'DELETE' });
       }
       await loadChats();
       navigate('/');
@@ -583,7 +587,7 @@ function App() {
     // Create a new chat if we don't have one
     if (!chatId) {
       try {
-        const res = await fetch('/api/chats', {
+        const res = await gatewayFetch('/api/chats', {
           method: 'POST',
           headers: { 'Content-Type': 'application/json' },
           body: JSON.stringify({ title: content.slice(0, 50) })
@@ -605,7 +609,7 @@ function App() {
 
     // Add user message
     try {
-      const res = await fetch(`/api/chats/${chatId}/messages`, {
+      const res = await gatewayFetch(`/api/chats/${chatId}/messages`, {
         method: 'POST',
         headers: { 'Content-Type': 'application/json' },
         body: JSON.stringify({ content })
@@ -643,7 +647,7 @@ function App() {
     for (const result of successfulResults) {
       const model = models.find(m => m.id === result.modelId);
       try {
-        await fetch(`/api/chats/${chatId}/messages`, {
+        await gatewayFetch(`/api/chats/${chatId}/messages`, {
           method: 'POST',
           headers: { 'Content-Type': 'application/json' },
           body: JSON.stringify({
@@ -669,7 +673,7 @@ function App() {
     // Create a new chat if we don't have one
     if (!chatId) {
       try {
-        const res = await fetch('/api/chats', {
+        const res = await gatewayFetch('/api/chats', {
           method: 'POST',
           headers: { 'Content-Type': 'application/json' },
           body: JSON.stringify({ title: `Document: ${file.name}` })
@@ -688,7 +692,7 @@ function App() {
     const formData = new FormData();
     formData.append('file', file);
 
-    const res = await fetch(`/api/chats/${chatId}/upload`, {
+    const res = await gatewayFetch(`/api/chats/${chatId}/upload`, {
       method: 'POST',
       body: formData
     });
@@ -855,6 +859,7 @@ function App() {
 
           {/* Input */}
           <MessageInput
+            initialContent={initialPrompt}
             onSend={sendMessage}
             onUpload={uploadFile}
             isStreaming={isStreaming()}
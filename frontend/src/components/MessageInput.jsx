@@ -1,7 +1,7 @@
 import { createSignal, Show } from 'solid-js';
 
 function MessageInput(props) {
-  const [content, setContent] = createSignal('');
+  const [content, setContent] = createSignal(props.initialContent || '');
   const [isUploading, setIsUploading] = createSignal(false);
   const [uploadError, setUploadError] = createSignal(null);
   let textareaRef;
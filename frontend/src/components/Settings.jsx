@@ -1,4 +1,5 @@
 import { createSignal, onMount, Show } from 'solid-js';
+import { gatewayFetch, getGatewayInfo, getGatewayKey, setGatewayKey } from '../services/gateway';
 
 // Helper to open external URLs (works in both browser and Tauri)
 async function openExternal(url) {
@@ -29,6 +30,9 @@ export default function Settings(props) {
   const [showOpenRouter, setShowOpenRouter] = createSignal(false);
   const [showZen, setShowZen] = createSignal(false);
   const [theme, setTheme] = createSignal(localStorage.getItem('theme') || 'system');
+  const [startAtLogin, setStartAtLogin] = createSignal(false);
+  const [apiBaseUrl, setApiBaseUrl] = createSignal('');
+  const [gatewayKey, setGatewayKeyInput] = createSignal(getGatewayKey());
 
   // Refs for input focus
   let openRouterInputRef;
@@ -52,8 +56,10 @@ export default function Settings(props) {
 
   // Load current settings on mount
   onMount(async () => {
+    getGatewayInfo().then((info) => setApiBaseUrl(info.api_base_url));
+
     try {
-      const res = await fetch('/api/settings');
+      const res = await gatewayFetch('/api/settings');
       if (res.ok) {
         const data = await res.json();
         // Keys come back masked, so we show placeholders
@@ -63,6 +69,7 @@ export default function Settings(props) {
         if (data.opencode_zen_configured) {
           setOpenCodeZenKey('••••••••••••••••');
         }
+        setStartAtLogin(!!data.start_at_login);
       }
     } catch (err) {
       console.error('Failed to load settings:', err);
@@ -170,7 +177,7 @@ export default function Settings(props) {
     }
 
     try {
-      const res = await fetch('/api/settings', {
+      const res = await gatewayFetch('/api/settings', {
         method: 'PUT',
         headers: { 'Content-Type': 'application/json' },
         body: JSON.stringify(updates)
@@ -223,6 +230,12 @@ export default function Settings(props) {
     }
   }
 
+  function handleGatewayKeySave() {
+    setGatewayKey(gatewayKey().trim());
+    // Routes that were refused without the key can load now
+    props.onSettingsChanged?.();
+  }
+
   function handleThemeChange(newTheme) {
     setTheme(newTheme);
     localStorage.setItem('theme', newTheme);
@@ -230,6 +243,25 @@ export default function Settings(props) {
     props.onThemeChange?.(newTheme);
   }
 
+  async function handleStartAtLoginChange(enabled) {
+    setStartAtLogin(enabled);
+    try {
+      const res = await gatewayFetch('/api/settings', {
+        method: 'PUT',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ start_at_login: enabled })
+      });
+      if (!res.ok) {
+        const data = await res.json();
+        setError(data.error || 'Failed to save settings');
+        setStartAtLogin(!enabled);
+      }
+    } catch (err) {
+      setError(err.message);
+      setStartAtLogin(!enabled);
+    }
+  }
+
   function applyTheme(themeSetting) {
     const root = document.documentElement;
     root.classList.remove('light', 'dark');
@@ -352,6 +384,62 @@ export default function Settings(props) {
         </div>
       </div>
 
+      {/* Startup Section */}
+      <div class="bg-white dark:bg-gray-800 rounded-xl border border-gray-200 dark:border-gray-700 p-6 mb-6">
+        <label class="flex items-center justify-between gap-4 cursor-pointer">
+          <div>
+            <h3 class="text-lg font-medium text-gray-900 dark:text-gray-100">Start at login</h3>
+            <p class="text-sm text-gray-500 dark:text-gray-400">
+              Launch MultiAI in the tray when you log in (desktop app only)
+            </p>
+          </div>
+          <input
+            type="checkbox"
+            class="w-5 h-5 accent-blue-500"
+            checked={startAtLogin()}
+            onChange={(e) => handleStartAtLoginChange(e.currentTarget.checked)}
+          />
+        </label>
+      </div>
+
+      {/* API Endpoint Section */}
+      <div class="bg-white dark:bg-gray-800 rounded-xl border border-gray-200 dark:border-gray-700 p-6 mb-6">
+        <h3 class="text-lg font-medium text-gray-900 dark:text-gray-100 mb-1">API base URL</h3>
+        <p class="text-sm text-gray-500 dark:text-gray-400 mb-3">
+          Point OpenAI-compatible clients here
+        </p>
+        <div class="flex items-center gap-2">
+          <code data-context-menu class="flex-1 px-3 py-2 rounded-lg bg-gray-100 dark:bg-gray-900 text-sm text-gray-800 dark:text-gray-200 select-all">
+            {apiBaseUrl() || '…'}
+          </code>
+          <button
+            onClick={() => navigator.clipboard?.writeText(apiBaseUrl())}
+            class="px-3 py-2 text-sm rounded-lg border border-gray-200 dark:border-gray-700 text-gray-700 dark:text-gray-300 hover:bg-gray-50 dark:hover:bg-gray-700"
+          >
+            Copy
+          </button>
+        </div>
+        <h4 class="text-sm font-medium text-gray-700 dark:text-gray-300 mt-4 mb-1">Gateway API key</h4>
+        <p class="text-xs text-gray-500 dark:text-gray-400 mb-2">
+          Needed when the gateway sets <code>[gateway] api_key</code>; kept in this browser only
+        </p>
+        <div class="flex items-center gap-2">
+          <input
+            type="password"
+            value={gatewayKey()}
+            onInput={(e) => setGatewayKeyInput(e.currentTarget.value)}
+            placeholder="multiai-..."
+            class="flex-1 px-3 py-2 rounded-lg border border-gray-200 dark:border-gray-700 bg-white dark:bg-gray-900 text-sm text-gray-800 dark:text-gray-200"
+          />
+          <button
+            onClick={handleGatewayKeySave}
+            class="px-3 py-2 text-sm rounded-lg border border-gray-200 dark:border-gray-700 text-gray-700 dark:text-gray-300 hover:bg-gray-50 dark:hover:bg-gray-700"
+          >
+            Save
+          </button>
+        </div>
+      </div>
+
       {/* API Keys Section */}
       <div class="bg-white dark:bg-gray-800 rounded-xl border border-gray-200 dark:border-gray-700 p-6 mb-6">
         <h3 class="text-lg font-medium text-gray-900 dark:text-gray-100 mb-4 flex items-center gap-2">
@@ -0,0 +1,73 @@
+/**
+ * Gateway discovery and auth.
+ * The desktop gateway binds 11435+ when Ollama already holds 11434, so the
+ * app shell is asked for the real port; in a browser the page origin is it.
+ * A gateway with `[gateway] api_key` set wants it on every `/v1` and `/api`
+ * request: the app shell supplies it, and in a browser it is entered in
+ * Settings and kept in localStorage.
+ */
+
+const GATEWAY_KEY_STORAGE = 'gatewayApiKey';
+
+// Key supplied by the app shell; undefined until it has been asked
+let shellKey;
+
+/**
+ * Get where the gateway is listening.
+ * @returns {Promise<{port: number, api_base_url: string, external: boolean, api_key?: string}>}
+ */
+export async function getGatewayInfo() {
+  const invoke = window.__TAURI__?.core?.invoke;
+  if (invoke) {
+    try {
+      const info = await invoke('get_gateway');
+      shellKey = info.api_key || null;
+      return info;
+    } catch (err) {
+      console.error('Failed to get gateway info:', err);
+    }
+  }
+
+  const { location } = window;
+  const port = Number(location.port) || (location.protocol === 'https:' ? 443 : 80);
+  return { port, api_base_url: `${location.origin}/v1`, external: false };
+}
+
+/**
+ * The gateway API key entered in Settings, if any.
+ * @returns {string}
+ */
+export function getGatewayKey() {
+  return localStorage.getItem(GATEWAY_KEY_STORAGE) || '';
+}
+
+/**
+ * Remember the gateway API key, or forget it when empty.
+ * @param {string} key
+ */
+export function setGatewayKey(key) {
+  if (key) {
+    localStorage.setItem(GATEWAY_KEY_STORAGE, key);
+  } else {
+    localStorage.removeItem(GATEWAY_KEY_STORAGE);
+  }
+}
+
+/**
+ * `fetch` for gateway routes, sending the gateway API key when one is known.
+ * @param {string} url
+ * @param {RequestInit} [options]
+ * @returns {Promise<Response>}
+ */
+export async function gatewayFetch(url, options = {}) {
+  if (shellKey === undefined && window.__TAURI__) {
+    await getGatewayInfo();
+  }
+  const key = getGatewayKey() || shellKey;
+  if (!key) {
+    return fetch(url, options);
+  }
+  const headers = new Headers(options.headers);
+  headers.set('Authorization', `Bearer ${key}`);
+  return fetch(url, { ...options, headers });
+}
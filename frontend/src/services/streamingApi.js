@@ -3,6 +3,7 @@
  * Uses SSE (Server-Sent Events) for real-time streaming responses.
  */
 import { createSSEParser } from './sseParser';
+import { gatewayFetch } from './gateway';
 
 /**
  * Stream a chat completion request.
@@ -27,7 +28,7 @@ export async function streamChatCompletion({
   let fullContent = '';
 
   try {
-    const response = await fetch('/v1/chat/completions', {
+    const response = await gatewayFetch('/v1/chat/completions', {
       method: 'POST',
       headers: { 'Content-Type': 'application/json' },
       body: JSON.stringify({
@@ -0,0 +1,42 @@
+//! Start-at-login, driven by `AppConfig.start_at_login`.
+//!
+//! The config file is the source of truth: it can change through the
+//! settings API, the tray toggle or by hand, and `sync` brings the platform
+//! registration (launch agent, registry run key, XDG autostart entry) in line.
+
+use multiai::config::Config;
+use tauri::AppHandle;
+use tauri_plugin_autostart::ManagerExt;
+
+/// Register or unregister the app with the OS to match `enabled`.
+/// Returns whether the app is now registered.
+pub fn sync(app: &AppHandle, enabled: bool) -> bool {
+    let autolaunch = app.autolaunch();
+    let registered = autolaunch.is_enabled().unwrap_or(false);
+    if registered == enabled {
+        return registered;
+    }
+
+    let result = if enabled {
+        autolaunch.enable()
+    } else {
+        autolaunch.disable()
+    };
+    match result {
+        Ok(()) => enabled,
+        Err(e) => {
+            log::warn!("Failed to update start at login: {}", e);
+            registered
+        }
+    }
+}
+
+/// Save the start-at-login setting and apply it.
+pub fn set(app: &AppHandle, enabled: bool) -> bool {
+    let mut config = Config::load().unwrap_or_default();
+    config.app.start_at_login = enabled;
+    if let Err(e) = config.save() {
+        log::warn!("Failed to save start at login: {}", e);
+    }
+    sync(app, enabled)
+}
@@ -0,0 +1,78 @@
+//! Tauri IPC commands.
+//!
+//! Mirror the settings and model endpoints of the HTTP API so the webview
+//! keeps working when the gateway port moves or is blocked.
+
+use crate::{autostart, Gateway};
+use multiai::api::{
+    apply_settings_update, settings_from_config, SettingsResponse, UpdateSettingsRequest,
+};
+use multiai::config::Config;
+use multiai::scanner::{FreeModel, FreeModelScanner};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+/// Where the gateway is listening.
+#[derive(Serialize)]
+pub struct GatewayInfo {
+    pub port: u16,
+    pub api_base_url: String,
+    /// True when another MultiAI process serves the port
+    pub external: bool,
+    /// `[gateway] api_key`, which the UI sends on `/v1` and `/api` requests
+    pub api_key: Option<String>,
+}
+
+/// The gateway's actual port, which moves past 11434 when Ollama holds it.
+#[tauri::command]
+pub fn get_gateway(gateway: State<'_, Gateway>) -> GatewayInfo {
+    GatewayInfo {
+        port: gateway.port,
+        api_base_url: gateway.api_base_url(),
+        external: gateway.state.is_none(),
+        api_key: Config::load_with_env().gateway.api_key,
+    }
+}
+
+/// Current settings (keys are only reported as configured).
+#[tauri::command]
+pub fn get_settings() -> SettingsResponse {
+    settings_from_config(&Config::load_with_env())
+}
+
+/// Update API keys and app settings, then apply start-at-login.
+#[tauri::command]
+pub fn update_settings(
+    app: AppHandle,
+    request: UpdateSettingsRequest,
+) -> Result<SettingsResponse, String> {
+    let mut config = Config::load().unwrap_or_default();
+    let generated = apply_settings_update(&mut config, request);
+    config
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+    autostart::sync(&app, config.app.start_at_login);
+    let mut settings = settings_from_config(&config);
+    settings.gateway_api_key = generated;
+    Ok(settings)
+}
+
+/// Free models, from the scanner cache when it is fresh.
+#[tauri::command]
+pub async fn list_models(gateway: State<'_, Gateway>) -> Result<Vec<FreeModel>, String> {
+    Ok(scanner(&gateway).get_free_models(false).await)
+}
+
+/// Rescan all sources for free models.
+#[tauri::command]
+pub async fn refresh_models(gateway: State<'_, Gateway>) -> Result<Vec<FreeModel>, String> {
+    Ok(scanner(&gateway).get_free_models(true).await)
+}
+
+/// The gateway's scanner, or a standalone one when another process serves the API.
+fn scanner(gateway: &Gateway) -> FreeModelScanner {
+    match &gateway.state {
+        Some(state) => state.scanner.clone(),
+        None => FreeModelScanner::new(),
+    }
+}
@@ -0,0 +1,87 @@
+//! `multiai://` deep links.
+//!
+//! - `multiai://ask?q=<prompt>` opens a new chat with the prompt pre-filled
+//! - `multiai://chat/<id>` opens an existing chat
+//! - `multiai://new` opens a new chat
+//!
+//! Links are turned into webview routes; with the single-instance plugin,
+//! links opened while the app is running are forwarded to it.
+
+use crate::tray::show_window;
+use tauri::{App, AppHandle, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+/// URL scheme registered for deep links.
+pub const SCHEME: &str = "multiai";
+
+/// Start handling deep links, including the one the app was launched with.
+pub fn init(app: &App) {
+    let deep_link = app.deep_link();
+
+    // Installers register the scheme on macOS and Windows; AppImages and dev builds need it at runtime
+    #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+    if let Err(e) = deep_link.register_all() {
+        log::warn!("Failed to register {}:// links: {}", SCHEME, e);
+    }
+
+    let handle = app.handle().clone();
+    deep_link.on_open_url(move |event| {
+        for url in event.urls() {
+            open(&handle, &url);
+        }
+    });
+
+    if let Ok(Some(urls)) = deep_link.get_current() {
+        for url in urls {
+            open(app.handle(), &url);
+        }
+    }
+}
+
+/// Route a deep link to the main window.
+fn open(app: &AppHandle, url: &Url) {
+    let Some(route) = route_for(url) else {
+        log::warn!("Ignoring unsupported deep link: {}", url);
+        return;
+    };
+    show_window(app);
+    if let Some(window) = app.get_webview_window("main") {
+        let script = format!(
+            "window.location.assign({})",
+            serde_json::to_string(&route).unwrap_or_default()
+        );
+        if let Err(e) = window.eval(&script) {
+            log::warn!("Failed to open deep link: {}", e);
+        }
+    }
+}
+
+/// Webview route for a deep link, or None if it isn't one we handle.
+fn route_for(url: &Url) -> Option<String> {
+    if url.scheme() != SCHEME {
+        return None;
+    }
+    match url.host_str() {
+        Some("ask") => {
+            let prompt = url
+                .query_pairs()
+                .find(|(name, _)| name == "q")
+                .map(|(_, value)| value.into_owned())
+                .unwrap_or_default();
+            let query: String = url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("q", &prompt)
+                .finish();
+            Some(format!("/?{}", query))
+        }
+        Some("chat") => {
+            let id = url.path().trim_matches('/');
+            let valid = !id.is_empty()
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            valid.then(|| format!("/chat/{}", id))
+        }
+        Some("new") | Some("") | None => Some("/".to_string()),
+        Some(_) => None,
+    }
+}
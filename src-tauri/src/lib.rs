@@ -1,11 +1,67 @@
+mod autostart;
+mod commands;
+mod deep_link;
+mod notify;
+mod tray;
+
 use multiai::api::{create_router_with_state, AppState};
+use multiai::config::Config;
+use multiai::mcp::spending::SpendingTracker;
 use multiai::scanner::FreeModelScanner;
 use std::net::SocketAddr;
-use tauri::{
-    Manager, RunEvent, WindowEvent,
-    menu::{Menu, MenuItem},
-    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-};
+use std::sync::{Arc, RwLock};
+use tauri::{Manager, RunEvent, WindowEvent};
+
+/// The gateway backing this app instance.
+#[derive(Clone)]
+pub struct Gateway {
+    pub port: u16,
+    /// Backend state, or None when another MultiAI instance already serves the port
+    pub state: Option<AppState>,
+}
+
+impl Gateway {
+    /// Base URL of the OpenAI-compatible API.
+    pub fn api_base_url(&self) -> String {
+        format!("http://127.0.0.1:{}/v1", self.port)
+    }
+
+    /// URL of the web UI served by the gateway.
+    pub fn ui_url(&self) -> String {
+        format!("http://127.0.0.1:{}/", self.port)
+    }
+}
+
+/// Gateway state for the desktop app. Opens the spending tracker the event
+/// monitor watches for threshold notifications and budget webhooks.
+fn desktop_state(ollama_url: Option<&str>, config: &Config) -> AppState {
+    let state = match ollama_url {
+        Some(url) => AppState::with_ollama(url),
+        None => AppState::default(),
+    };
+    AppState {
+        spending: SpendingTracker::open_default(config.spending.clone()),
+        config: Arc::new(RwLock::new(config.clone())),
+        ..state
+    }
+}
+
+/// Point the main window at the port the gateway actually bound, which is
+/// not 11434 when Ollama already holds it.
+fn open_gateway_ui(app: &tauri::App, gateway: &Gateway) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let Ok(url) = tauri::Url::parse(&gateway.ui_url()) else {
+        return;
+    };
+    let current = window.url().ok();
+    if current.as_ref().and_then(|u| u.port()) != url.port() {
+        if let Err(e) = window.navigate(url) {
+            log::warn!("Failed to open the gateway UI: {}", e);
+        }
+    }
+}
 
 /// Result of port finding: listener, port, and optional Ollama URL if detected
 struct PortResult {
@@ -56,38 +112,60 @@ async fn find_available_port() -> Option<PortResult> {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Start backend server immediately in a separate thread
-    std::thread::spawn(|| {
-        let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
-        rt.block_on(async {
-            let result = find_available_port()
-                .await
-                .expect("No available port found (tried 11434-11443)");
+    // Start the backend on Tauri's runtime so the app shell can share its state
+    let gateway = tauri::async_runtime::block_on(async {
+        let result = find_available_port()
+            .await
+            .expect("No available port found (tried 11434-11443)");
 
-            // If MultiAI is already running, don't start another server
-            if result.multiai_already_running {
-                log::info!("MultiAI already running, skipping server start");
-                return;
-            }
-
-            // Create state with Ollama URL if detected
-            let state = if let Some(ollama_url) = result.ollama_url {
-                AppState::with_ollama(&ollama_url)
-            } else {
-                AppState::default()
+        // If MultiAI is already running, don't start another server
+        if result.multiai_already_running {
+            log::info!("MultiAI already running, skipping server start");
+            return Gateway {
+                port: result.port,
+                state: None,
             };
-            let router = create_router_with_state(state);
+        }
+
+        // Create state with Ollama URL if detected
+        let state = desktop_state(result.ollama_url.as_deref(), &Config::load_with_env());
+        let router = create_router_with_state(state.clone());
 
-            log::info!("MultiAI backend starting on http://127.0.0.1:{}", result.port);
+        log::info!("MultiAI backend starting on http://127.0.0.1:{}", result.port);
 
+        tauri::async_runtime::spawn(async move {
             axum::serve(result.listener, router)
                 .await
                 .expect("Server error");
         });
+
+        Gateway {
+            port: result.port,
+            state: Some(state),
+        }
     });
 
     let app = tauri::Builder::default()
-        .setup(|app| {
+        // Must come first so links opened while running reach this instance
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            tray::show_window(app);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_autostart::init(
+            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+            None,
+        ))
+        .manage(gateway.clone())
+        .invoke_handler(tauri::generate_handler![
+            commands::get_settings,
+            commands::update_settings,
+            commands::list_models,
+            commands::refresh_models,
+            commands::get_gateway,
+        ])
+        .setup(move |app| {
             if cfg!(debug_assertions) {
                 app.handle().plugin(
                     tauri_plugin_log::Builder::default()
@@ -96,42 +174,24 @@ pub fn run() {
                 )?;
             }
 
-            // Build tray menu
-            let quit = MenuItem::with_id(app, "quit", "Quit MultiAI", true, None::<&str>)?;
-            let show = MenuItem::with_id(app, "show", "Show Window", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&show, &quit])?;
-
-            // Build tray icon
-            let _tray = TrayIconBuilder::new()
-                .icon(app.default_window_icon().unwrap().clone())
-                .menu(&menu)
-                .on_menu_event(|app, event| match event.id.as_ref() {
-                    "quit" => {
-                        app.exit(0);
-                    }
-                    "show" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
-                    }
-                    _ => {}
-                })
-                .on_tray_icon_event(|tray, event| {
-                    if let TrayIconEvent::Click {
-                        button: MouseButton::Left,
-                        button_state: MouseButtonState::Up,
-                        ..
-                    } = event
-                    {
-                        let app = tray.app_handle();
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
-                    }
-                })
-                .build(app)?;
+            if let Some(state) = &gateway.state {
+                notify::start(app.handle().clone(), state);
+                let (alert_webhook_url, scan_webhook_url) = {
+                    let config = state.config.read().unwrap();
+                    (config.alert_webhook_url(), config.scanner.webhook_url.clone())
+                };
+                tauri::async_runtime::spawn(multiai::events::monitor(
+                    state.events.clone(),
+                    state.scanner.clone(),
+                    state.spending.clone(),
+                    state.http.clone(),
+                    alert_webhook_url,
+                    scan_webhook_url,
+                ));
+            }
+            open_gateway_ui(app, &gateway);
+            tray::build(app, gateway)?;
+            deep_link::init(app);
 
             Ok(())
         })
@@ -150,3 +210,15 @@ pub fn run() {
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn desktop_state_gives_the_event_monitor_a_spending_tracker() {
+        let config = Config::default();
+        assert!(desktop_state(None, &config).spending.is_some());
+        assert!(desktop_state(Some("http://127.0.0.1:11434"), &config).spending.is_some());
+    }
+}
@@ -0,0 +1,107 @@
+//! Native notifications for gateway events.
+//!
+//! Listens on the gateway's `EventBus` and shows a desktop notification for
+//! rejected API keys, missing free models, spending thresholds, failing
+//! sources, rate-limited models and free models appearing or disappearing. Repeats of the same
+//! event are suppressed for a cooldown so a failing client can't flood the
+//! notification center.
+
+use multiai::events::{Cooldown, GatewayEvent};
+use multiai::scanner::Source;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Minimum time between notifications for the same event.
+const COOLDOWN: Duration = Duration::from_secs(10 * 60);
+
+/// Start forwarding gateway events to native notifications.
+pub fn start(app: AppHandle, state: &multiai::api::AppState) {
+    let mut receiver = state.events.subscribe();
+    tauri::async_runtime::spawn(async move {
+        let mut cooldown = Cooldown::new(COOLDOWN);
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            };
+            if !cooldown.allow(&event) {
+                continue;
+            }
+
+            let (title, body) = message(&event);
+            if let Err(e) = app.notification().builder().title(title).body(body).show() {
+                log::warn!("Failed to show notification: {}", e);
+            }
+        }
+    });
+}
+
+fn message(event: &GatewayEvent) -> (String, String) {
+    match event {
+        GatewayEvent::KeyRejected { source, status } => (
+            format!("{} API key rejected", source_name(source)),
+            format!(
+                "The provider returned {}. Update the key in Settings.",
+                status
+            ),
+        ),
+        GatewayEvent::ModelsUnavailable => (
+            "No free models available".to_string(),
+            "Every source came back empty. Check your connection and API keys.".to_string(),
+        ),
+        GatewayEvent::SpendingWarning {
+            daily_percent,
+            monthly_percent,
+        } => (
+            "Spending warning".to_string(),
+            format!(
+                "Judge spending is at {:.0}% of the daily cap and {:.0}% of the monthly cap.",
+                daily_percent, monthly_percent
+            ),
+        ),
+        GatewayEvent::SpendingCapReached { period, used, cap } => (
+            "Spending cap reached".to_string(),
+            format!(
+                "Judge spending hit the {} cap (${:.2} of ${:.2}). Paid judges are paused until it resets.",
+                period, used, cap
+            ),
+        ),
+        GatewayEvent::SourceFailing { source, error } => (
+            format!("{} unavailable", source_name(source)),
+            format!("Model scans are failing: {}", error),
+        ),
+        GatewayEvent::CircuitOpen {
+            source,
+            model,
+            retry_after_secs,
+        } => (
+            format!("{} is rate limiting", source_name(source)),
+            format!("Skipping {} for {}s.", model, retry_after_secs),
+        ),
+        GatewayEvent::ModelsChanged { .. } => {
+            ("Free models changed".to_string(), event.summary())
+        }
+    }
+}
+
+fn source_name(source: &Source) -> &'static str {
+    match source {
+        Source::Ollama => "Ollama",
+        Source::LmStudio => "LM Studio",
+        Source::LlamaCpp => "llama.cpp",
+        Source::OpenCodeZen => "OpenCode Zen",
+        Source::OpenRouter => "OpenRouter",
+        Source::Groq => "Groq",
+        Source::Gemini => "Gemini",
+        Source::Cerebras => "Cerebras",
+        Source::Mistral => "Mistral",
+        Source::Together => "Together AI",
+        Source::SambaNova => "SambaNova",
+        Source::GitHubModels => "GitHub Models",
+        Source::Cloudflare => "Workers AI",
+        Source::Custom => "Custom",
+    }
+}
@@ -0,0 +1,314 @@
+//! Tray icon with live gateway status.
+//!
+//! The menu shows the gateway address, the number of free models, today's
+//! request count and API key health, refreshed from the shared `AppState`
+//! (or the running instance's HTTP API when another process owns the port),
+//! plus toggles for traffic capture, log verbosity and start-at-login.
+
+use crate::autostart;
+use crate::Gateway;
+use chrono::{DateTime, Local, Utc};
+use multiai::config::{Config, LogVerbosity};
+use multiai::inspector::CapturedTransaction;
+use multiai::scanner::{FreeModel, Source};
+use std::time::Duration;
+use tauri::{
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem},
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+    App, AppHandle, Manager, Wry,
+};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// How often the status items are refreshed.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Sources that need an API key, with their display names.
+const KEYED_SOURCES: [(Source, &str); 10] = [
+    (Source::OpenRouter, "OpenRouter"),
+    (Source::OpenCodeZen, "Zen"),
+    (Source::Groq, "Groq"),
+    (Source::Gemini, "Gemini"),
+    (Source::Cerebras, "Cerebras"),
+    (Source::Mistral, "Mistral"),
+    (Source::Together, "Together"),
+    (Source::SambaNova, "SambaNova"),
+    (Source::GitHubModels, "GitHub"),
+    (Source::Cloudflare, "Workers AI"),
+];
+
+/// Read-only status lines in the tray menu.
+struct StatusItems {
+    gateway: MenuItem<Wry>,
+    models: MenuItem<Wry>,
+    requests: MenuItem<Wry>,
+    keys: MenuItem<Wry>,
+    start_at_login: CheckMenuItem<Wry>,
+}
+
+/// Build the tray icon and start refreshing its status lines.
+pub fn build(app: &App, gateway: Gateway) -> tauri::Result<()> {
+    let status = StatusItems {
+        gateway: MenuItem::with_id(
+            app,
+            "status_gateway",
+            gateway_label(&gateway),
+            false,
+            None::<&str>,
+        )?,
+        models: MenuItem::with_id(
+            app,
+            "status_models",
+            "Models: scanning…",
+            false,
+            None::<&str>,
+        )?,
+        requests: MenuItem::with_id(
+            app,
+            "status_requests",
+            "Requests today: 0",
+            false,
+            None::<&str>,
+        )?,
+        keys: MenuItem::with_id(app, "status_keys", "Keys: checking…", false, None::<&str>)?,
+        start_at_login: CheckMenuItem::with_id(
+            app,
+            "start_at_login",
+            "Start at Login",
+            true,
+            autostart::sync(app.handle(), Config::load_with_env().app.start_at_login),
+            None::<&str>,
+        )?,
+    };
+    // Capture and verbosity live in this process's gateway only
+    let owns_gateway = gateway.state.is_some();
+    let capture = CheckMenuItem::with_id(
+        app,
+        "capture",
+        "Capture Traffic",
+        owns_gateway,
+        gateway
+            .state
+            .as_ref()
+            .is_some_and(|state| state.inspector.is_enabled()),
+        None::<&str>,
+    )?;
+    let verbosity = MenuItem::with_id(
+        app,
+        "verbosity",
+        verbosity_label(
+            &gateway
+                .state
+                .as_ref()
+                .map(|state| state.logs.verbosity())
+                .unwrap_or_default(),
+        ),
+        owns_gateway,
+        None::<&str>,
+    )?;
+    let copy_url = MenuItem::with_id(app, "copy_url", "Copy API Base URL", true, None::<&str>)?;
+    let show = MenuItem::with_id(app, "show", "Show Window", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit MultiAI", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status.gateway,
+            &status.models,
+            &status.requests,
+            &status.keys,
+            &PredefinedMenuItem::separator(app)?,
+            &capture,
+            &verbosity,
+            &status.start_at_login,
+            &copy_url,
+            &show,
+            &quit,
+        ],
+    )?;
+
+    let start_at_login = status.start_at_login.clone();
+    TrayIconBuilder::new()
+        .icon(app.default_window_icon().unwrap().clone())
+        .menu(&menu)
+        .on_menu_event(move |app, event| match event.id.as_ref() {
+            "quit" => {
+                app.exit(0);
+            }
+            "show" => show_window(app),
+            "capture" => {
+                if let Some(state) = &app.state::<Gateway>().state {
+                    // The item has already toggled itself
+                    let enabled = capture.is_checked().unwrap_or(true);
+                    state.inspector.set_enabled(enabled);
+                    log::info!(
+                        "Traffic capture {}",
+                        if enabled { "resumed" } else { "paused" }
+                    );
+                }
+            }
+            "verbosity" => {
+                if let Some(state) = &app.state::<Gateway>().state {
+                    let next = state.logs.verbosity().next();
+                    state.logs.set_verbosity(next.clone());
+                    let _ = verbosity.set_text(verbosity_label(&next));
+                }
+            }
+            "start_at_login" => {
+                // The item has already toggled itself
+                let wanted = start_at_login.is_checked().unwrap_or(false);
+                let _ = start_at_login.set_checked(autostart::set(app, wanted));
+            }
+            "copy_url" => {
+                let url = app.state::<Gateway>().api_base_url();
+                if let Err(e) = app.clipboard().write_text(url) {
+                    log::warn!("Failed to copy API base URL: {}", e);
+                }
+            }
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_window(tray.app_handle());
+            }
+        })
+        .build(app)?;
+
+    tauri::async_runtime::spawn(refresh_loop(app.handle().clone(), gateway, status));
+    Ok(())
+}
+
+/// Show and focus the main window.
+pub(crate) fn show_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn verbosity_label(verbosity: &LogVerbosity) -> String {
+    let level = match verbosity {
+        LogVerbosity::Minimal => "Minimal",
+        LogVerbosity::Compact => "Compact",
+        LogVerbosity::Verbose => "Verbose",
+    };
+    format!("Log Verbosity: {}", level)
+}
+
+fn gateway_label(gateway: &Gateway) -> String {
+    match gateway.state {
+        Some(_) => format!("Gateway: 127.0.0.1:{}", gateway.port),
+        None => format!("Gateway: 127.0.0.1:{} (external)", gateway.port),
+    }
+}
+
+async fn refresh_loop(app: AppHandle, gateway: Gateway, status: StatusItems) {
+    let client = multiai::http::create_client();
+    let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let config = Config::load_with_env();
+        let (models, requests, transactions) = match &gateway.state {
+            Some(state) => (
+                state.scanner.get_free_models(false).await,
+                Some(state.inspector.count_since(start_of_today())),
+                state.inspector.get_all(),
+            ),
+            None => {
+                let key = config.gateway.api_key.as_deref();
+                (fetch_models(&client, gateway.port, key).await, None, Vec::new())
+            }
+        };
+
+        let _ = status
+            .models
+            .set_text(format!("Models: {} free", models.len()));
+        let _ = status.requests.set_text(match requests {
+            Some(count) => format!("Requests today: {}", count),
+            None => "Requests today: n/a".to_string(),
+        });
+        let _ = status.keys.set_text(format!(
+            "Keys: {}",
+            key_health(&config, &models, &transactions)
+        ));
+        // Pick up changes made through the settings API
+        let _ = status
+            .start_at_login
+            .set_checked(autostart::sync(&app, config.app.start_at_login));
+    }
+}
+
+/// Local midnight, as UTC.
+fn start_of_today() -> DateTime<Utc> {
+    Local::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .map(|midnight| midnight.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now)
+}
+
+/// Fetch the model list from a MultiAI instance we don't own, sending the
+/// configured gateway key in case it requires one.
+async fn fetch_models(client: &reqwest::Client, port: u16, key: Option<&str>) -> Vec<FreeModel> {
+    let url = format!("http://127.0.0.1:{}/v1/models", port);
+    let mut request = client.get(&url);
+    if let Some(key) = key {
+        request = request.bearer_auth(key);
+    }
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(_) => return Vec::new(),
+    };
+    let body: serde_json::Value = match response.json().await {
+        Ok(body) => body,
+        Err(_) => return Vec::new(),
+    };
+    body["data"]
+        .as_array()
+        .map(|models| {
+            models
+                .iter()
+                .filter_map(|m| serde_json::from_value(m.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Summarize key health, e.g. "OpenRouter ✓ · Zen not set".
+///
+/// A configured key is reported as rejected when the latest request to one
+/// of its source's models came back 401 or 403.
+fn key_health(
+    config: &Config,
+    models: &[FreeModel],
+    transactions: &[CapturedTransaction],
+) -> String {
+    KEYED_SOURCES
+        .iter()
+        .map(|(source, name)| {
+            if config.get_api_key(source).is_none() {
+                return format!("{} not set", name);
+            }
+            let latest_status = transactions
+                .iter()
+                .rev()
+                .filter(|tx| {
+                    let model = tx.request.body.as_ref().and_then(|b| b["model"].as_str());
+                    models
+                        .iter()
+                        .any(|m| m.source == *source && Some(m.id.as_str()) == model)
+                })
+                .find_map(|tx| tx.response.as_ref().map(|r| r.status));
+            match latest_status {
+                Some(401 | 403) => format!("{} ✗", name),
+                _ => format!("{} ✓", name),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
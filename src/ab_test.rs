@@ -0,0 +1,223 @@
+//! A/B traffic splitting for `auto` requests.
+//!
+//! With `[routing.ab_test]` configured, `auto` completions go to one of two
+//! models in the configured proportion. Each transaction is tagged with its
+//! arm, and per-arm success, latency and client feedback are kept so a
+//! model migration can be judged on real traffic (`GET /v1/ab`).
+
+use crate::config::AbTestConfig;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// Recent transactions remembered for feedback.
+const FEEDBACK_WINDOW: usize = 1000;
+
+/// One side of the split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Arm {
+    A,
+    B,
+}
+
+impl Arm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Arm::A => "a",
+            Arm::B => "b",
+        }
+    }
+
+    /// Parse the tag stored on a transaction.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "a" => Some(Arm::A),
+            "b" => Some(Arm::B),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    requests: u64,
+    errors: u64,
+    latency_ms: u64,
+    positive: u64,
+    negative: u64,
+}
+
+#[derive(Debug, Default)]
+struct Tracking {
+    counters: HashMap<Arm, Counters>,
+    // Arm of each recent transaction, oldest first
+    recent: VecDeque<(String, Arm)>,
+}
+
+/// Statistics for one arm.
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct ArmStats {
+    pub arm: Arm,
+    pub model: String,
+    /// Share of `auto` traffic sent to this arm.
+    pub percent: u8,
+    pub requests: u64,
+    pub errors: u64,
+    pub success_rate: Option<f64>,
+    /// Mean latency of successful requests.
+    pub avg_latency_ms: Option<u64>,
+    pub positive_feedback: u64,
+    pub negative_feedback: u64,
+}
+
+/// Per-arm report served by `GET /v1/ab`.
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct AbReport {
+    pub arms: Vec<ArmStats>,
+}
+
+/// A running A/B split, shared across handlers.
+#[derive(Clone)]
+pub struct AbSplit {
+    config: AbTestConfig,
+    next: Arc<AtomicU64>,
+    tracking: Arc<Mutex<Tracking>>,
+}
+
+impl AbSplit {
+    pub fn new(config: AbTestConfig) -> Self {
+        Self {
+            config,
+            next: Arc::new(AtomicU64::new(0)),
+            tracking: Arc::new(Mutex::new(Tracking::default())),
+        }
+    }
+
+    pub fn model(&self, arm: Arm) -> &str {
+        match arm {
+            Arm::A => &self.config.model_a,
+            Arm::B => &self.config.model_b,
+        }
+    }
+
+    /// Pick the arm for the next request. Requests are interleaved so every
+    /// run of 100 sends exactly `percent_b` to B.
+    pub fn choose(&self) -> Arm {
+        let n = self.next.fetch_add(1, Ordering::Relaxed);
+        let percent = u64::from(self.config.percent_b.min(100));
+        if (n + 1) * percent / 100 > n * percent / 100 {
+            Arm::B
+        } else {
+            Arm::A
+        }
+    }
+
+    /// Record the outcome of a transaction sent to `arm`.
+    pub fn record(&self, transaction_id: &str, arm: Arm, success: bool, latency: Duration) {
+        let mut tracking = self.tracking.lock().unwrap();
+        let counters = tracking.counters.entry(arm).or_default();
+        counters.requests += 1;
+        if success {
+            counters.latency_ms += latency.as_millis() as u64;
+        } else {
+            counters.errors += 1;
+        }
+        tracking.recent.push_back((transaction_id.to_string(), arm));
+        while tracking.recent.len() > FEEDBACK_WINDOW {
+            tracking.recent.pop_front();
+        }
+    }
+
+    /// Count client feedback on a recent transaction. Returns the arm it was
+    /// sent to, or None if the transaction is unknown.
+    pub fn feedback(&self, transaction_id: &str, positive: bool) -> Option<Arm> {
+        let mut tracking = self.tracking.lock().unwrap();
+        let arm = tracking
+            .recent
+            .iter()
+            .find(|(id, _)| id == transaction_id)
+            .map(|(_, arm)| *arm)?;
+        let counters = tracking.counters.entry(arm).or_default();
+        if positive {
+            counters.positive += 1;
+        } else {
+            counters.negative += 1;
+        }
+        Some(arm)
+    }
+
+    pub fn report(&self) -> AbReport {
+        let tracking = self.tracking.lock().unwrap();
+        let percent_b = self.config.percent_b.min(100);
+        let arms = [(Arm::A, 100 - percent_b), (Arm::B, percent_b)]
+            .into_iter()
+            .map(|(arm, percent)| {
+                let empty = Counters::default();
+                let c = tracking.counters.get(&arm).unwrap_or(&empty);
+                let successes = c.requests - c.errors;
+                ArmStats {
+                    arm,
+                    model: self.model(arm).to_string(),
+                    percent,
+                    requests: c.requests,
+                    errors: c.errors,
+                    success_rate: (c.requests > 0).then(|| successes as f64 / c.requests as f64),
+                    avg_latency_ms: (successes > 0).then(|| c.latency_ms / successes),
+                    positive_feedback: c.positive,
+                    negative_feedback: c.negative,
+                }
+            })
+            .collect();
+        AbReport { arms }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(percent_b: u8) -> AbSplit {
+        AbSplit::new(AbTestConfig {
+            model_a: "old".to_string(),
+            model_b: "new".to_string(),
+            percent_b,
+        })
+    }
+
+    #[test]
+    fn splits_traffic_by_percentage() {
+        let ab = split(25);
+        let b = (0..100).filter(|_| ab.choose() == Arm::B).count();
+        assert_eq!(b, 25);
+
+        assert!((0..10).all(|_| split(0).choose() == Arm::A));
+        let all_b = split(100);
+        assert!((0..10).all(|_| all_b.choose() == Arm::B));
+    }
+
+    #[test]
+    fn reports_per_arm_outcomes_and_feedback() {
+        let ab = split(50);
+        ab.record("t1", Arm::A, true, Duration::from_millis(100));
+        ab.record("t2", Arm::A, false, Duration::from_millis(900));
+        ab.record("t3", Arm::B, true, Duration::from_millis(300));
+
+        assert_eq!(ab.feedback("t3", true), Some(Arm::B));
+        assert_eq!(ab.feedback("t1", false), Some(Arm::A));
+        assert_eq!(ab.feedback("unknown", true), None);
+
+        let report = ab.report();
+        let a = &report.arms[0];
+        assert_eq!((a.model.as_str(), a.requests, a.errors), ("old", 2, 1));
+        assert_eq!(a.success_rate, Some(0.5));
+        assert_eq!(a.avg_latency_ms, Some(100));
+        assert_eq!(a.negative_feedback, 1);
+        let b = &report.arms[1];
+        assert_eq!((b.model.as_str(), b.percent), ("new", 50));
+        assert_eq!(b.positive_feedback, 1);
+    }
+}
@@ -0,0 +1,240 @@
+//! Per-client rate limiting of the API.
+//!
+//! `[gateway] client_rpm` and `client_tpm` cap the requests and estimated
+//! prompt tokens each client may send to `/v1` routes per minute, so one
+//! runaway script can't burn through the free-tier quotas every client
+//! shares. Clients are told apart by the gateway or minted key they send,
+//! else by IP address; keys that don't verify count against the IP.
+//! Requests over a limit get an OpenAI-style 429 with `retry-after`.
+
+use super::gateway_auth::verified_token;
+use super::usage::estimate_tokens;
+use super::AppState;
+use crate::error::MultiAiError;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, HeaderValue};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Span of the rolling window both limits count over.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Clients tracked before idle windows are dropped.
+const MAX_TRACKED_CLIENTS: usize = 1024;
+
+/// Largest body buffered to count its tokens.
+const MAX_COUNTED_BODY: usize = 16 * 1024 * 1024;
+
+/// Each client's requests in the last minute, with their estimated tokens.
+type Windows = HashMap<String, VecDeque<(Instant, u32)>>;
+
+/// Requests and tokens per minute allowed per client; 0 disables a limit.
+#[derive(Clone)]
+pub struct ClientLimits {
+    rpm: u32,
+    tpm: u32,
+    windows: Arc<Mutex<Windows>>,
+}
+
+impl ClientLimits {
+    /// Limits from `[gateway]`, or None when both are off.
+    pub fn new(rpm: u32, tpm: u32) -> Option<Self> {
+        (rpm > 0 || tpm > 0).then(|| Self {
+            rpm,
+            tpm,
+            windows: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Count a request of `tokens` for `client`, or return how long it must
+    /// wait to fit. A request over the token limit on its own is let through
+    /// once the client's window is empty.
+    pub fn check(&self, client: &str, tokens: u32) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        if windows.len() > MAX_TRACKED_CLIENTS {
+            windows.retain(|_, window| {
+                window
+                    .back()
+                    .is_some_and(|(at, _)| now.duration_since(*at) < WINDOW)
+            });
+        }
+        let window = windows.entry(client.to_string()).or_default();
+        while window
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) >= WINDOW)
+        {
+            window.pop_front();
+        }
+        // Time until the request at `index` leaves the window
+        let expires = |index: usize| WINDOW.saturating_sub(now.duration_since(window[index].0));
+
+        let rpm = self.rpm as usize;
+        if rpm > 0 && window.len() >= rpm {
+            return Err(expires(window.len() - rpm));
+        }
+        if self.tpm > 0 && !window.is_empty() {
+            let used: u32 = window.iter().map(|(_, tokens)| tokens).sum();
+            if used.saturating_add(tokens) > self.tpm {
+                let mut freed = 0u32;
+                let index = window
+                    .iter()
+                    .position(|(_, old)| {
+                        freed += old;
+                        (used - freed).saturating_add(tokens) <= self.tpm
+                    })
+                    .unwrap_or(window.len() - 1);
+                return Err(expires(index));
+            }
+        }
+        window.push_back((now, tokens));
+        Ok(())
+    }
+}
+
+/// Who a request comes from: a hash of the verified key it carries, else
+/// its IP.
+fn client_id(state: &AppState, request: &Request) -> String {
+    if let Some(key) = verified_token(state, request.headers()) {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        return format!("key:{:016x}", hasher.finish());
+    }
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "unknown".to_string(),
+    }
+}
+
+/// Middleware enforcing [`ClientLimits`] on `/v1` and Azure-style routes.
+pub async fn enforce(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    let Some(limits) = &state.client_limits else {
+        return next.run(request).await;
+    };
+    if !path.starts_with("/v1/") && !path.starts_with("/openai/") {
+        return next.run(request).await;
+    }
+    let client = client_id(&state, &request);
+
+    // Only buffer the body when tokens are limited
+    let (request, tokens) = if limits.tpm > 0 {
+        let (parts, body) = request.into_parts();
+        let bytes = match axum::body::to_bytes(body, MAX_COUNTED_BODY).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return MultiAiError::InvalidRequest(format!("Failed to read body: {}", e))
+                    .into_response()
+            }
+        };
+        let tokens = estimate_tokens(&String::from_utf8_lossy(&bytes));
+        (Request::from_parts(parts, Body::from(bytes)), tokens)
+    } else {
+        (request, 0)
+    };
+
+    match limits.check(&client, tokens) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            let wait = wait.as_secs().max(1);
+            tracing::warn!("Rate limited client {} for {}s", client, wait);
+            let mut response = MultiAiError::ClientRateLimited(format!(
+                "Rate limit reached for this client; try again in {}s",
+                wait
+            ))
+            .into_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(wait));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::create_router_with_state;
+    use crate::virtual_keys::KeyStore;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+
+    #[test]
+    fn limits_are_off_when_both_are_zero() {
+        assert!(ClientLimits::new(0, 0).is_none());
+        assert!(ClientLimits::new(10, 0).is_some());
+    }
+
+    #[test]
+    fn requests_per_minute_are_counted_per_client() {
+        let limits = ClientLimits::new(2, 0).unwrap();
+
+        assert!(limits.check("a", 0).is_ok());
+        assert!(limits.check("a", 0).is_ok());
+        let wait = limits.check("a", 0).unwrap_err();
+        assert!(wait <= WINDOW && wait > WINDOW - Duration::from_secs(5));
+        assert!(limits.check("b", 0).is_ok());
+    }
+
+    #[test]
+    fn tokens_per_minute_are_counted_per_client() {
+        let limits = ClientLimits::new(0, 100).unwrap();
+
+        assert!(limits.check("a", 60).is_ok());
+        assert!(limits.check("a", 60).is_err());
+        assert!(limits.check("a", 40).is_ok());
+        // One request over the limit gets through only on an empty window
+        assert!(limits.check("a", 500).is_err());
+        assert!(limits.check("b", 500).is_ok());
+    }
+
+    #[tokio::test]
+    async fn clients_over_their_rate_limit_get_openai_style_429s() {
+        let keys = KeyStore::in_memory().unwrap();
+        let minted = keys
+            .mint(&crate::virtual_keys::NewKey {
+                name: "script".to_string(),
+                limits: Default::default(),
+            })
+            .unwrap()
+            .unwrap();
+        let state = AppState {
+            client_limits: ClientLimits::new(1, 0),
+            keys: Some(keys).into(),
+            ..AppState::default()
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        server.get("/v1/inspect").await.assert_status_ok();
+        let response = server.get("/v1/inspect").await;
+        response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key("retry-after"));
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"]["type"], "rate_limit_exceeded");
+
+        // Made-up keys share the address's window; real keys get their own
+        server
+            .get("/v1/inspect")
+            .add_header("Authorization", "Bearer other-client")
+            .await
+            .assert_status(StatusCode::TOO_MANY_REQUESTS);
+        server
+            .get("/v1/inspect")
+            .add_header("Authorization", format!("Bearer {}", minted.key))
+            .await
+            .assert_status_ok();
+        // Non-API routes are not limited
+        server.get("/health/live").await.assert_status_ok();
+    }
+}
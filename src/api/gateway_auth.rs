@@ -0,0 +1,315 @@
+//! Bearer-token auth for the whole gateway.
+//!
+//! With `[gateway] api_key` set, every `/v1` and `/api` route (and the
+//! Azure-style `/openai` ones) requires `Authorization: Bearer <key>`, so
+//! the gateway can be exposed on a LAN or shared machine. Gateway API keys
+//! minted through `/api/keys` are also accepted on the model routes (chat,
+//! embeddings, models and audio), where their own limits apply; the
+//! inspector, cache and management routes need the gateway key itself.
+//! Health checks, the docs and the UI stay open.
+//!
+//! Whether or not a key is set, browsers may only call the settings and key
+//! routes from a loopback page or the UI's own origin when it was opened by
+//! IP address, so a site the user happens to open (even one that rebinds
+//! its name to this machine) can't rewrite the config, mint itself a key or
+//! revoke the user's keys.
+
+use super::handlers::AZURE_KEY_HEADER;
+use super::AppState;
+use crate::error::MultiAiError;
+use crate::virtual_keys::KEY_PREFIX;
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// A new random gateway key.
+pub fn generate_key() -> String {
+    format!("multiai-{}", uuid::Uuid::new_v4().simple())
+}
+
+/// The bearer token or Azure `api-key` a request carries.
+fn request_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get(AZURE_KEY_HEADER).and_then(|v| v.to_str().ok()))
+        .map(str::trim)
+}
+
+/// Compare secrets without returning early on the first differing byte.
+fn same_secret(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a
+            .bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// Routes minted keys may call: chat, embeddings, models and audio, in the
+/// OpenAI, Azure and Ollama flavours.
+fn minted_key_route(path: &str) -> bool {
+    const PREFIXES: [&str; 5] = [
+        "/v1/chat/",
+        "/v1/embeddings",
+        "/v1/models",
+        "/v1/audio/",
+        "/openai/deployments/",
+    ];
+    PREFIXES.iter().any(|prefix| path.starts_with(prefix))
+        || matches!(path, "/api/chat" | "/api/generate" | "/api/tags")
+}
+
+/// Routes that change the gateway's config or hand out its keys.
+pub(super) fn management_route(path: &str) -> bool {
+    matches!(path, "/api/settings" | "/api/keys") || path.starts_with("/api/keys/")
+}
+
+/// The request's `Origin` when it is neither the gateway itself nor a
+/// loopback page. Requests without one come from non-browser clients.
+pub(super) fn foreign_origin(headers: &HeaderMap) -> Option<&str> {
+    let origin = headers.get(header::ORIGIN)?;
+    let origin = origin.to_str().unwrap_or("(invalid)");
+    // Opaque origins ("null") from sandboxed frames or files have no authority
+    let Some((_, authority)) = origin.split_once("://") else {
+        return Some(origin);
+    };
+    let hostname = match authority.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    let loopback = matches!(
+        hostname.to_ascii_lowercase().as_str(),
+        "localhost" | "127.0.0.1" | "::1" | "tauri.localhost"
+    );
+    // The UI's own origin only counts when reached by address: a page could
+    // rebind its own name to this machine and send a matching Host
+    let host = headers.get(header::HOST).and_then(|v| v.to_str().ok());
+    let same_origin = host.is_some_and(|host| host.eq_ignore_ascii_case(authority))
+        && hostname.parse::<IpAddr>().is_ok();
+    (!loopback && !same_origin).then_some(origin)
+}
+
+/// The token a request carries when it is the gateway key or a minted key,
+/// so per-client limits can't be dodged with made-up keys.
+pub(super) fn verified_token<'a>(state: &AppState, headers: &'a HeaderMap) -> Option<&'a str> {
+    let token = request_token(headers)?;
+    let gateway_key = state.gateway_key.read().unwrap();
+    let verified = gateway_key.as_deref().is_some_and(|key| same_secret(token, key))
+        || (token.starts_with(KEY_PREFIX)
+            && state
+                .keys
+                .get()
+                .is_some_and(|keys| matches!(keys.lookup(token), Ok(Some(_)))));
+    verified.then_some(token)
+}
+
+/// Check a request to `path` against the gateway key, if one is set.
+/// The gRPC service checks its calls under the matching REST paths.
+pub(super) fn authorize(
+    state: &AppState,
+    path: &str,
+    headers: &HeaderMap,
+) -> Result<(), MultiAiError> {
+    let Some(expected) = state.gateway_key.read().unwrap().clone() else {
+        return Ok(());
+    };
+    let protected = ["/v1/", "/openai/", "/api/"].iter().any(|p| path.starts_with(p));
+    if !protected {
+        return Ok(());
+    }
+
+    let allowed = match request_token(headers) {
+        Some(token) if same_secret(token, &expected) => true,
+        // Minted keys are checked against their limits by the handler
+        Some(token) if token.starts_with(KEY_PREFIX) && minted_key_route(path) => state
+            .keys
+            .get()
+            .is_some_and(|keys| matches!(keys.lookup(token), Ok(Some(_)))),
+        _ => false,
+    };
+    if !allowed {
+        return Err(MultiAiError::InvalidApiKey(
+            "this gateway requires `Authorization: Bearer <gateway.api_key>`".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Middleware rejecting requests without the gateway key once one is set,
+/// and management calls from foreign origins.
+pub async fn require_key(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if management_route(request.uri().path()) {
+        if let Some(origin) = foreign_origin(request.headers()) {
+            return MultiAiError::ForeignOrigin(origin.to_string()).into_response();
+        }
+    }
+    match authorize(&state, request.uri().path(), request.headers()) {
+        Ok(()) => next.run(request).await,
+        Err(e) => e.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::create_router_with_state;
+    use crate::virtual_keys::KeyStore;
+    use axum::http::{Method, StatusCode};
+    use axum_test::TestServer;
+    use std::sync::RwLock;
+
+    #[test]
+    fn generated_keys_are_unique_and_not_minted_keys() {
+        let key = generate_key();
+        assert!(key.starts_with("multiai-"));
+        assert!(!key.starts_with(KEY_PREFIX));
+        assert_ne!(key, generate_key());
+    }
+
+    #[test]
+    fn minted_keys_only_reach_model_routes() {
+        for path in ["/v1/chat/completions", "/v1/models/llama3", "/v1/embeddings", "/api/chat"] {
+            assert!(minted_key_route(path), "{}", path);
+        }
+        for path in ["/v1/inspect", "/v1/inspect/ws", "/v1/cache", "/v1/spending", "/api/keys"] {
+            assert!(!minted_key_route(path), "{}", path);
+        }
+    }
+
+    #[test]
+    fn only_the_gateway_and_loopback_pages_are_local_origins() {
+        let headers = |origin: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::ORIGIN, origin.parse().unwrap());
+            headers.insert(header::HOST, "192.168.1.5:11434".parse().unwrap());
+            headers
+        };
+        for origin in [
+            "http://192.168.1.5:11434",
+            "http://localhost:5173",
+            "http://127.0.0.1:11434",
+            "http://[::1]:11434",
+            "tauri://localhost",
+        ] {
+            assert_eq!(foreign_origin(&headers(origin)), None, "{}", origin);
+        }
+        for origin in ["https://evil.example", "http://localhost.evil.example", "null"] {
+            assert_eq!(foreign_origin(&headers(origin)), Some(origin));
+        }
+        assert_eq!(foreign_origin(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn rebound_names_are_not_the_gateway_origin() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ORIGIN, "http://evil.example:11434".parse().unwrap());
+        headers.insert(header::HOST, "evil.example:11434".parse().unwrap());
+        assert_eq!(foreign_origin(&headers), Some("http://evil.example:11434"));
+    }
+
+    #[tokio::test]
+    async fn settings_refuse_foreign_origins() {
+        let server = TestServer::new(create_router_with_state(AppState::default())).unwrap();
+        let preflight = |path: &'static str, origin: &'static str| {
+            server
+                .method(Method::OPTIONS, path)
+                .add_header("Origin", origin)
+                .add_header("Access-Control-Request-Method", "PUT")
+        };
+
+        let response = preflight("/api/settings", "https://evil.example").await;
+        assert!(response.headers().get("access-control-allow-origin").is_none());
+        let response = preflight("/api/settings", "http://localhost:5173").await;
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            "http://localhost:5173"
+        );
+        // Model routes stay open to browser clients on any origin
+        let response = preflight("/v1/chat/completions", "https://evil.example").await;
+        assert!(response.headers().get("access-control-allow-origin").is_some());
+
+        let response = server
+            .put("/api/settings")
+            .add_header("Origin", "https://evil.example")
+            .json(&serde_json::json!({ "generate_gateway_api_key": true }))
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"]["type"], "forbidden_origin");
+    }
+
+    #[test]
+    fn secrets_must_match_exactly() {
+        assert!(same_secret("multiai-abc", "multiai-abc"));
+        assert!(!same_secret("multiai-abc", "multiai-abd"));
+        assert!(!same_secret("multiai-abc", "multiai-ab"));
+    }
+
+    #[tokio::test]
+    async fn gateway_key_is_required_on_api_routes_once_set() {
+        let keys = KeyStore::in_memory().unwrap();
+        let minted = keys
+            .mint(&crate::virtual_keys::NewKey {
+                name: "editor".to_string(),
+                limits: Default::default(),
+            })
+            .unwrap()
+            .unwrap();
+        let state = AppState {
+            gateway_key: Arc::new(RwLock::new(Some("multiai-secret".to_string()))),
+            keys: Some(keys).into(),
+            ..AppState::default()
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let response = server.get("/v1/inspect").await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"]["type"], "invalid_api_key");
+        server
+            .get("/api/keys")
+            .add_header("Authorization", "Bearer wrong")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        server
+            .get("/api/keys")
+            .add_header("Authorization", "Bearer multiai-secret")
+            .await
+            .assert_status_ok();
+        server
+            .get("/v1/inspect")
+            .add_header("api-key", "multiai-secret")
+            .await
+            .assert_status_ok();
+
+        // Minted keys reach the model routes but can't inspect or manage the gateway
+        let minted = format!("Bearer {}", minted.key);
+        server
+            .get("/v1/models")
+            .add_header("Authorization", minted.clone())
+            .await
+            .assert_status_ok();
+        server
+            .get("/v1/inspect")
+            .add_header("Authorization", minted.clone())
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        server
+            .get("/api/keys")
+            .add_header("Authorization", minted)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        server.get("/health/live").await.assert_status_ok();
+    }
+}
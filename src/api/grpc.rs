@@ -0,0 +1,413 @@
+//! gRPC mirror of the chat, model listing and inspect APIs.
+//!
+//! Built with the `grpc` cargo feature and served on `[gateway] grpc_port`.
+//! Completions run through the same path as `POST /v1/chat/completions`, so
+//! key checks, routing, fallback, hooks and inspector capture behave exactly
+//! as they do over REST; only the wire format differs. With `[gateway]
+//! api_key` set, every call needs it in the `authorization` metadata, and
+//! minted keys only reach chat and model listing, as over REST. The service
+//! is defined in `proto/multiai.proto`.
+
+use super::gateway_auth;
+use super::handlers::complete_chat;
+use super::types::{ChatMessage, ChatRequest};
+use super::AppState;
+use crate::http::take_sse_data;
+use crate::inspector::CapturedTransaction;
+use axum::body::{to_bytes, Body};
+use axum::http::StatusCode;
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tonic::{Code, Request, Response, Status};
+
+pub mod proto {
+    tonic::include_proto!("multiai.v1");
+}
+
+use proto::gateway_server::{Gateway, GatewayServer};
+
+type GrpcStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// Largest error body read back from a failed completion.
+const MAX_ERROR_BYTES: usize = 64 * 1024;
+
+/// Largest non-streamed completion read back from the chat handler.
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Serve the gRPC API on `addr`.
+pub async fn serve(state: AppState, addr: SocketAddr) -> Result<(), tonic::transport::Error> {
+    tracing::info!("gRPC listening on {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(GatewayServer::new(GatewayService::new(state)))
+        .serve(addr)
+        .await
+}
+
+/// The `multiai.v1.Gateway` service.
+pub struct GatewayService {
+    state: Arc<AppState>,
+}
+
+impl GatewayService {
+    pub fn new(state: AppState) -> Self {
+        Self {
+            state: Arc::new(state),
+        }
+    }
+
+    /// Check a call's metadata against the gateway key the way the REST
+    /// route at `path` would.
+    fn authorize<T>(&self, request: &Request<T>, path: &str) -> Result<(), Status> {
+        let headers = request.metadata().clone().into_headers();
+        gateway_auth::authorize(&self.state, path, &headers)
+            .map_err(|e| Status::unauthenticated(e.to_string()))
+    }
+
+    /// Run a completion the way the REST handler does, returning the
+    /// response body or the gateway error as a gRPC status.
+    async fn complete(
+        &self,
+        request: Request<proto::ChatRequest>,
+        stream: bool,
+    ) -> Result<Body, Status> {
+        self.authorize(&request, "/v1/chat/completions")?;
+        let headers = request.metadata().clone().into_headers();
+        let chat = chat_request(request.into_inner(), stream);
+        let response = complete_chat(self.state.clone(), headers, chat, &mut None).await;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response.into_body());
+        }
+
+        let bytes = to_bytes(response.into_body(), MAX_ERROR_BYTES)
+            .await
+            .unwrap_or_default();
+        let message = serde_json::from_slice::<Value>(&bytes)
+            .ok()
+            .and_then(|body| body["error"]["message"].as_str().map(String::from))
+            .unwrap_or_else(|| String::from_utf8_lossy(&bytes).into_owned());
+        Err(Status::new(grpc_code(status), message))
+    }
+}
+
+#[tonic::async_trait]
+impl Gateway for GatewayService {
+    async fn chat_completion(
+        &self,
+        request: Request<proto::ChatRequest>,
+    ) -> Result<Response<proto::ChatResponse>, Status> {
+        let body = self.complete(request, false).await?;
+        let bytes = to_bytes(body, MAX_BODY_BYTES)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let body: Value = serde_json::from_slice(&bytes)
+            .map_err(|e| Status::internal(format!("Invalid completion: {}", e)))?;
+        Ok(Response::new(chat_response(&body)))
+    }
+
+    type StreamChatCompletionStream = GrpcStream<proto::ChatChunk>;
+
+    async fn stream_chat_completion(
+        &self,
+        request: Request<proto::ChatRequest>,
+    ) -> Result<Response<Self::StreamChatCompletionStream>, Status> {
+        let body = self.complete(request, true).await?;
+        let mut buffer = Vec::new();
+        let chunks = body
+            .into_data_stream()
+            .map(move |bytes| match bytes {
+                Ok(bytes) => {
+                    buffer.extend_from_slice(&bytes);
+                    take_sse_data(&mut buffer)
+                        .iter()
+                        .filter_map(|data| sse_chunk(data))
+                        .collect()
+                }
+                Err(e) => vec![Err(Status::internal(e.to_string()))],
+            })
+            .flat_map(futures::stream::iter);
+        Ok(Response::new(Box::pin(chunks)))
+    }
+
+    async fn list_models(
+        &self,
+        request: Request<proto::ListModelsRequest>,
+    ) -> Result<Response<proto::ListModelsResponse>, Status> {
+        self.authorize(&request, "/v1/models")?;
+        let created = chrono::Utc::now().timestamp();
+        let models = self
+            .state
+            .scanner
+            .get_free_models(false)
+            .await
+            .into_iter()
+            .map(|m| proto::Model {
+                id: m.id,
+                owned_by: m.provider,
+                created,
+            })
+            .collect();
+        Ok(Response::new(proto::ListModelsResponse { models }))
+    }
+
+    type StreamInspectStream = GrpcStream<proto::Transaction>;
+
+    async fn stream_inspect(
+        &self,
+        request: Request<proto::StreamInspectRequest>,
+    ) -> Result<Response<Self::StreamInspectStream>, Status> {
+        self.authorize(&request, "/v1/inspect/stream")?;
+        let receiver = self.state.inspector.subscribe();
+        let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(tx) => return Some((Ok(transaction(&tx)), receiver)),
+                    // Slow consumers skip transactions rather than disconnecting
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+        .take_until(self.state.streams.draining());
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+fn chat_request(request: proto::ChatRequest, stream: bool) -> ChatRequest {
+    ChatRequest {
+        model: request.model,
+        messages: request
+            .messages
+            .into_iter()
+            .map(|m| ChatMessage {
+                role: m.role,
+                content: m.content,
+                ..ChatMessage::default()
+            })
+            .collect(),
+        temperature: request.temperature,
+        max_tokens: request.max_tokens,
+        stream,
+        template: request.template,
+        variables: request.variables.into_iter().collect(),
+        routing_strategy: None,
+        tools: None,
+        tool_choice: None,
+        response_format: None,
+    }
+}
+
+/// The gRPC code closest to a gateway HTTP status.
+fn grpc_code(status: StatusCode) -> Code {
+    match status {
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+        StatusCode::FORBIDDEN => Code::PermissionDenied,
+        StatusCode::NOT_FOUND => Code::NotFound,
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::PAYMENT_REQUIRED => Code::ResourceExhausted,
+        StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => Code::DeadlineExceeded,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+        _ => Code::Internal,
+    }
+}
+
+fn text(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+fn usage(value: &Value) -> Option<proto::Usage> {
+    value.is_object().then(|| {
+        let prompt_tokens = value["prompt_tokens"].as_u64().unwrap_or(0);
+        let completion_tokens = value["completion_tokens"].as_u64().unwrap_or(0);
+        proto::Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: value["total_tokens"]
+                .as_u64()
+                .unwrap_or(prompt_tokens + completion_tokens),
+        }
+    })
+}
+
+/// Convert an OpenAI `chat.completion` object.
+fn chat_response(body: &Value) -> proto::ChatResponse {
+    proto::ChatResponse {
+        id: text(&body["id"]),
+        model: text(&body["model"]),
+        created: body["created"].as_i64().unwrap_or_default(),
+        choices: body["choices"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|choice| proto::Choice {
+                index: choice["index"].as_u64().unwrap_or_default() as u32,
+                message: Some(proto::ChatMessage {
+                    role: text(&choice["message"]["role"]),
+                    content: text(&choice["message"]["content"]),
+                }),
+                finish_reason: text(&choice["finish_reason"]),
+            })
+            .collect(),
+        usage: usage(&body["usage"]),
+    }
+}
+
+/// Convert one SSE `data:` payload; `[DONE]` and unparseable payloads are
+/// skipped, and error events end the stream with a status.
+fn sse_chunk(data: &str) -> Option<Result<proto::ChatChunk, Status>> {
+    let chunk: Value = serde_json::from_str(data).ok()?;
+    if let Some(error) = chunk.get("error") {
+        let message = error["message"].as_str().unwrap_or("Upstream stream error");
+        return Some(Err(Status::unavailable(message)));
+    }
+    let choice = &chunk["choices"][0];
+    Some(Ok(proto::ChatChunk {
+        id: text(&chunk["id"]),
+        model: text(&chunk["model"]),
+        delta: text(&choice["delta"]["content"]),
+        finish_reason: choice["finish_reason"].as_str().map(String::from),
+        usage: usage(&chunk["usage"]),
+    }))
+}
+
+fn transaction(tx: &CapturedTransaction) -> proto::Transaction {
+    proto::Transaction {
+        id: tx.id.clone(),
+        timestamp: tx.timestamp.to_rfc3339(),
+        method: tx.request.method.clone(),
+        url: tx.request.url.clone(),
+        status: tx.response.as_ref().map_or(0, |r| r.status.into()),
+        total_ms: tx.timing.total_ms,
+        json: serde_json::to_string(tx).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tests::test_state;
+
+    fn hello(model: &str) -> Request<proto::ChatRequest> {
+        Request::new(proto::ChatRequest {
+            model: model.to_string(),
+            messages: vec![proto::ChatMessage {
+                role: "user".to_string(),
+                content: "Hello".to_string(),
+            }],
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn sse_lines_split_across_reads_are_reassembled() {
+        let mut buffer = b"data: {\"choices\":[{\"delta\":{\"content\":\"Hel".to_vec();
+        assert!(take_sse_data(&mut buffer).is_empty());
+
+        buffer.extend_from_slice(b"lo\"}}]}\n\ndata: [DONE]\n\n");
+        let data = take_sse_data(&mut buffer);
+        assert_eq!(data.len(), 2);
+        assert!(buffer.is_empty());
+
+        let chunk = sse_chunk(&data[0]).unwrap().unwrap();
+        assert_eq!(chunk.delta, "Hello");
+        assert!(sse_chunk(&data[1]).is_none());
+        let error = sse_chunk(r#"{"error": {"message": "rate limited"}}"#).unwrap();
+        assert_eq!(error.unwrap_err().message(), "rate limited");
+    }
+
+    #[tokio::test]
+    async fn chat_completion_and_models_mirror_rest() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        upstream
+            .mock("POST", "/v1/chat/completions")
+            .with_body(
+                r#"{"id": "c1", "model": "llama3", "created": 1,
+                    "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"},
+                                 "finish_reason": "stop"}],
+                    "usage": {"prompt_tokens": 3, "completion_tokens": 1}}"#,
+            )
+            .create_async()
+            .await;
+        let service = GatewayService::new(test_state(&upstream));
+
+        let models = service
+            .list_models(Request::new(proto::ListModelsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(models.models.iter().any(|m| m.id == "llama3"));
+
+        let response = service.chat_completion(hello("llama3")).await.unwrap().into_inner();
+        assert_eq!(response.choices[0].message.as_ref().unwrap().content, "Hi");
+        assert_eq!(response.choices[0].finish_reason, "stop");
+        assert_eq!(response.usage.unwrap().total_tokens, 4);
+        assert_eq!(service.state.inspector.get_all().len(), 1);
+
+        let error = service.chat_completion(hello("not-free")).await.unwrap_err();
+        assert_eq!(error.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn calls_need_the_gateway_key_once_set() {
+        let state = AppState {
+            gateway_key: Arc::new(std::sync::RwLock::new(Some("multiai-secret".to_string()))),
+            ..AppState::default()
+        };
+        let service = GatewayService::new(state);
+
+        let error = service.chat_completion(hello("llama3")).await.unwrap_err();
+        assert_eq!(error.code(), Code::Unauthenticated);
+        let inspect = Request::new(proto::StreamInspectRequest {});
+        let error = service.stream_inspect(inspect).await.err().unwrap();
+        assert_eq!(error.code(), Code::Unauthenticated);
+
+        let mut inspect = Request::new(proto::StreamInspectRequest {});
+        inspect
+            .metadata_mut()
+            .insert("authorization", "Bearer multiai-secret".parse().unwrap());
+        assert!(service.stream_inspect(inspect).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn streamed_completion_yields_chunks() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        upstream
+            .mock("POST", "/v1/chat/completions")
+            .with_header("content-type", "text/event-stream")
+            .with_body(concat!(
+                "data: {\"id\":\"c1\",\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\n",
+                "data: {\"id\":\"c1\",\"choices\":[{\"delta\":{\"content\":\" there\"},",
+                "\"finish_reason\":\"stop\"}]}\n\n",
+                "data: [DONE]\n\n"
+            ))
+            .create_async()
+            .await;
+        let service = GatewayService::new(test_state(&upstream));
+
+        let chunks: Vec<proto::ChatChunk> = service
+            .stream_chat_completion(hello("llama3"))
+            .await
+            .unwrap()
+            .into_inner()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        let text: String = chunks.iter().map(|c| c.delta.as_str()).collect();
+        assert_eq!(text, "Hello there");
+        assert_eq!(chunks[1].finish_reason.as_deref(), Some("stop"));
+    }
+}
@@ -1,45 +1,183 @@
 //! HTTP handlers for the OpenAI-compatible API.
 
+use super::gateway_auth;
+use super::inflight;
+use super::inspect_ws;
+use super::json_mode;
+use super::memory::{self, Conversation};
+use super::ollama_compat::{self, OllamaApi};
+use super::ratelimit::{rate_limit_headers, retry_after};
+use super::shutdown::StreamGuard;
+use super::tools;
 use super::types::*;
+use super::usage::{estimate_prompt_tokens, UsageTee};
 use super::AppState;
+use crate::ab_test::{AbReport, Arm};
+use crate::bench::{BenchJob, BenchRequest, Benchmarks};
+use crate::cloudflare;
 use crate::config::Config;
+use crate::embedding_cache::EmbeddingCache;
 use crate::error::MultiAiError;
-use crate::http::create_client;
-use crate::inspector::{CapturedRequest, CapturedResponse, TrafficInspector};
-use crate::scanner::{FreeModel, Source};
+use crate::evals::{self, Dataset, EvalStore};
+use crate::events::GatewayEvent;
+use crate::hooks::Hooks;
+use crate::http;
+use crate::inspector::{
+    CapturedRequest, CapturedResponse, CapturedTransaction, ChunkCadence, TransactionFilter,
+};
+use crate::gemini;
+use crate::leaderboard;
+use crate::mcp::eval::{self, EvalParams, EvalResult};
+use crate::mcp::spending::SpendingReport;
+use crate::ollama;
+use crate::response_cache;
+use crate::routing::RoutingStrategy;
+use crate::scanner::{FreeModel, Source, SourceAuth, SourceRegistry};
+use crate::speech::{self, SpeechRequest};
+use crate::templates::{self, Expansion, PromptTemplate, TemplateStore};
+use crate::virtual_keys::{self, KeyDenied, KeyStore, MintedKey, NewKey, VirtualKey, KEY_PREFIX};
 use axum::{
     body::Body,
-    extract::{Query, State},
-    http::StatusCode,
-    response::{IntoResponse, Response},
+    extract::{ws::WebSocketUpgrade, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     Json,
 };
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 use regex::Regex;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
 use std::sync::{Arc, LazyLock};
+use tokio::sync::broadcast::error::RecvError;
 
 // ============================================================================
 // Health and Models handlers
 // ============================================================================
 
-pub async fn health_check() -> Json<HealthResponse> {
+#[utoipa::path(get, path = "/health", tag = "health",
+    responses((status = 200, description = "Per-component health", body = HealthResponse)))]
+pub async fn health_check(State(state): State<Arc<AppState>>) -> Json<HealthResponse> {
+    let keys = {
+        let config = state.config.read().unwrap();
+        state
+            .sources
+            .keyed()
+            .into_iter()
+            .map(|(name, source)| (name, config.get_api_keys(&source).len()))
+            .collect()
+    };
+    let sources = state.scanner.source_status();
+    let chat_db = match ping_chat_db(&state) {
+        Ok(()) => ComponentHealth { ok: true, error: None },
+        Err(e) => ComponentHealth {
+            ok: false,
+            error: Some(e),
+        },
+    };
+    let transactions = state.inspector.get_all();
+    let errors = transactions
+        .iter()
+        .filter(|tx| tx.response.as_ref().is_none_or(|r| r.status >= 400))
+        .count();
+
+    let degraded = !chat_db.ok || sources.values().any(|s| s.last_error.is_some());
+
     Json(HealthResponse {
-        status: "ok",
+        status: if degraded { "degraded" } else { "ok" },
         app: "multiai",
         version: env!("CARGO_PKG_VERSION"),
+        uptime_secs: state.started_at.elapsed().as_secs(),
+        sources,
+        chat_db,
+        inspector: InspectorHealth {
+            enabled: state.inspector.is_enabled(),
+            transactions: transactions.len(),
+            errors,
+        },
+        keys,
+        scheduler: state.scheduler.status(),
+        embedding_cache: state.embedding_cache.as_ref().map(EmbeddingCache::stats),
     })
 }
 
-pub async fn list_models(State(state): State<Arc<AppState>>) -> Json<ModelsResponse> {
-    let free_models = state.scanner.get_free_models(false).await;
+/// Check the chat database answers. A poisoned lock counts as a failure
+/// rather than panicking the health endpoints.
+fn ping_chat_db(state: &AppState) -> Result<(), String> {
+    let db = state
+        .chat
+        .db
+        .lock()
+        .map_err(|_| "chat database lock is poisoned".to_string())?;
+    db.ping().map_err(|e| e.to_string())
+}
+
+/// Liveness probe: the process is up and serving requests.
+#[utoipa::path(get, path = "/health/live", tag = "health",
+    responses((status = 200, body = ProbeResponse)))]
+pub async fn health_live() -> Json<ProbeResponse> {
+    Json(ProbeResponse {
+        status: "ok",
+        reasons: Vec::new(),
+    })
+}
+
+/// Readiness probe: the first model scan has completed and the chat DB is open.
+#[utoipa::path(get, path = "/health/ready", tag = "health",
+    responses(
+        (status = 200, description = "Ready", body = ProbeResponse),
+        (status = 503, description = "Not ready, with reasons", body = ProbeResponse)
+    ))]
+pub async fn health_ready(State(state): State<Arc<AppState>>) -> (StatusCode, Json<ProbeResponse>) {
+    let mut reasons = Vec::new();
+    if !state.scanner.has_scanned() {
+        reasons.push("model scan has not completed".to_string());
+    }
+    if let Err(e) = ping_chat_db(&state) {
+        reasons.push(format!("chat database unavailable: {}", e));
+    }
+
+    if reasons.is_empty() {
+        (StatusCode::OK, Json(ProbeResponse { status: "ready", reasons }))
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ProbeResponse {
+                status: "not_ready",
+                reasons,
+            }),
+        )
+    }
+}
+
+#[utoipa::path(get, path = "/v1/models", tag = "models",
+    params(ModelsQuery),
+    responses((status = 200, description = "Free models in OpenAI list format", body = ModelsResponse)))]
+pub async fn list_models(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ModelsQuery>,
+) -> Json<ModelsResponse> {
+    let mut free_models = state.scanner.get_free_models(false).await;
+    free_models.retain(|m| query.matches(m));
+    // Rate-limited models go last; the sort is stable
+    free_models.sort_by_key(|m| state.model_stats.cooldown(&m.id).is_some());
 
     let data: Vec<ModelInfo> = free_models
         .into_iter()
         .map(|m| ModelInfo {
+            rate_limited_for_secs: state
+                .model_stats
+                .cooldown(&m.id)
+                .map(|left| left.as_secs().max(1)),
             id: m.id,
             object: "model",
             created: chrono::Utc::now().timestamp(),
             owned_by: m.provider,
+            context_length: m.context_length,
+            modalities: m.modalities,
+            supports_tools: m.supports_tools,
         })
         .collect();
 
@@ -49,6 +187,8 @@ pub async fn list_models(State(state): State<Arc<AppState>>) -> Json<ModelsRespo
     })
 }
 
+#[utoipa::path(get, path = "/v1/models/grouped", tag = "models",
+    responses((status = 200, description = "Free models grouped by name across sources", body = GroupedModelsResponse)))]
 pub async fn list_models_grouped(State(state): State<Arc<AppState>>) -> Json<GroupedModelsResponse> {
     use std::collections::HashMap;
 
@@ -64,6 +204,9 @@ pub async fn list_models_grouped(State(state): State<Arc<AppState>>) -> Json<Gro
             id: model.id,
             source: model.source,
             endpoint: model.endpoint,
+            context_length: model.context_length,
+            modalities: model.modalities,
+            supports_tools: model.supports_tools,
         });
     }
 
@@ -71,7 +214,7 @@ pub async fn list_models_grouped(State(state): State<Arc<AppState>>) -> Json<Gro
     let mut models: Vec<GroupedModel> = grouped
         .into_iter()
         .map(|(name, mut providers)| {
-            providers.sort_by(|a, b| a.source.cmp(&b.source));
+            providers.sort_by_key(|p| p.source);
             GroupedModel { name, providers }
         })
         .collect();
@@ -81,20 +224,139 @@ pub async fn list_models_grouped(State(state): State<Arc<AppState>>) -> Json<Gro
     Json(GroupedModelsResponse { models })
 }
 
+/// One free model with its metadata, routing health and recent latency.
+/// IDs may contain slashes, e.g. `/v1/models/meta-llama/llama-3.3-70b-instruct:free`.
+#[utoipa::path(get, path = "/v1/models/{id}", tag = "models",
+    params(("id" = String, Path, description = "Model ID")),
+    responses(
+        (status = 200, body = ModelDetail),
+        (status = 404, description = "No free model has this ID")
+    ))]
+pub async fn get_model(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<ModelDetail>, JsonError> {
+    let free_models = state.scanner.get_free_models(false).await;
+    let model = free_models
+        .into_iter()
+        .find(|m| m.id == id)
+        .ok_or_else(|| {
+            json_error(StatusCode::NOT_FOUND, format!("Model '{}' is not a free model", id))
+        })?;
+
+    let requires_api_key = state.sources.auth(&model).is_none_or(SourceAuth::requires_key);
+    let api_key_configured = state.config.read().unwrap().get_model_api_key(&model).is_some();
+    let recent = recent_requests(&state.inspector.get_all(), &model.id);
+
+    Ok(Json(ModelDetail {
+        requires_api_key,
+        api_key_configured,
+        health: state.model_stats.score(&model.id),
+        rate_limited_for_secs: state
+            .model_stats
+            .cooldown(&model.id)
+            .map(|left| left.as_secs().max(1)),
+        recent,
+        id: model.id,
+        object: "model",
+        owned_by: model.provider,
+        source: model.source,
+        endpoint: model.endpoint,
+        context_length: model.context_length,
+        modalities: model.modalities,
+        supports_tools: model.supports_tools,
+    }))
+}
+
+/// Latency of the inspector's transactions that were routed to `model`.
+fn recent_requests(transactions: &[CapturedTransaction], model: &str) -> RecentRequests {
+    let routed: Vec<&CapturedTransaction> = transactions
+        .iter()
+        .filter(|tx| tx.routed_model.as_deref() == Some(model))
+        .collect();
+    let errors = routed
+        .iter()
+        .filter(|tx| tx.response.as_ref().is_none_or(|r| r.status >= 400))
+        .count();
+    let mut totals: Vec<u64> = routed.iter().map(|tx| tx.timing.total_ms).collect();
+    totals.sort_unstable();
+    // Nearest-rank percentiles
+    let percentile = |p: usize| {
+        (!totals.is_empty()).then(|| totals[(totals.len() * p).div_ceil(100) - 1])
+    };
+    let ttfbs: Vec<u64> = routed.iter().filter_map(|tx| tx.timing.ttfb_ms).collect();
+
+    RecentRequests {
+        requests: routed.len(),
+        errors,
+        p50_ms: percentile(50),
+        p95_ms: percentile(95),
+        avg_ttfb_ms: (!ttfbs.is_empty()).then(|| ttfbs.iter().sum::<u64>() / ttfbs.len() as u64),
+        last_used: routed.iter().map(|tx| tx.timestamp).max(),
+    }
+}
+
+/// Stream the free models each scan adds or removes.
+#[utoipa::path(get, path = "/v1/models/events", tag = "models",
+    responses((status = 200, description = "Server-sent `models_changed` events with the added and removed models", content_type = "text/event-stream", body = String)))]
+pub async fn model_events(
+    State(state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.scanner.subscribe_changes();
+
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(changes) => {
+                    let data = serde_json::to_string(&changes).unwrap_or_default();
+                    let event = Event::default().event("models_changed").data(data);
+                    return Some((Ok(event), receiver));
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+    .take_until(state.streams.draining());
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Per-source scan status, to see why a source's models are missing.
+#[utoipa::path(get, path = "/v1/sources", tag = "models",
+    responses((status = 200, description = "Last scan of each configured source", body = SourcesResponse)))]
+pub async fn list_sources(State(state): State<Arc<AppState>>) -> Json<SourcesResponse> {
+    Json(SourcesResponse {
+        scanned: state.scanner.has_scanned(),
+        sources: state.scanner.source_status(),
+    })
+}
+
 /// Normalize model ID to display name.
 /// "glm-4-7-free" -> "GLM 4.7", "grok-code-fast-1" -> "Grok Code Fast 1"
+///
+/// Vendor prefixes, `:free` suffixes and the case of parameter counts are
+/// dropped so the same model from different sources gets one name:
+/// "meta-llama/llama-3.3-70b-instruct:free" and "Meta-Llama-3.3-70B-Instruct"
+/// are both "Llama 3.3 70B Instruct".
 pub fn normalize_model_name(id: &str) -> String {
     // Regex to match consecutive digit groups separated by spaces (version numbers)
     static VERSION_REGEX: LazyLock<Regex> =
-        LazyLock::new(|| Regex::new(r"(\d+)((?:\s+\d+)+)").unwrap());
+        LazyLock::new(|| Regex::new(r"\b(\d+)((?:\s+\d+)+)\b").unwrap());
+    // Parameter counts such as "70b" or "1.5b"
+    static SIZE_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^\d+(\.\d+)?[bm]$").unwrap());
 
     // Known acronyms that should stay uppercase
     static ACRONYMS: &[&str] = &["glm", "gpt", "llm", "ai", "ml"];
 
-    let name = id
-        .replace("-free", "")
-        .replace("opencode/", "")
-        .replace("openrouter/", "");
+    let name = id.rsplit('/').next().unwrap_or(id);
+    let name = name.strip_suffix(":free").unwrap_or(name).replace("-free", "");
+    let name = match name.get(..11) {
+        // Meta's own model IDs carry the vendor name: "Meta-Llama-3.3-70B-Instruct"
+        Some(prefix) if prefix.eq_ignore_ascii_case("meta-llama-") => &name[5..],
+        _ => name.as_str(),
+    };
 
     // Split by hyphens and title case
     let spaced = name
@@ -102,7 +364,9 @@ pub fn normalize_model_name(id: &str) -> String {
         .map(|part| {
             if part.chars().all(|c| c.is_ascii_digit()) {
                 part.to_string()
-            } else if ACRONYMS.contains(&part.to_lowercase().as_str()) {
+            } else if SIZE_REGEX.is_match(&part.to_lowercase())
+                || ACRONYMS.contains(&part.to_lowercase().as_str())
+            {
                 part.to_uppercase()
             } else {
                 let mut chars = part.chars();
@@ -148,35 +412,101 @@ pub fn find_target_model<'a>(
         .ok_or_else(|| MultiAiError::ModelNotFree(requested.to_string()))
 }
 
-/// Build the upstream URL for a model.
-pub fn build_upstream_url(model: &FreeModel) -> String {
-    if model.source == Source::Ollama {
+/// Models to try for a request, in order: the target, the same model from
+/// other sources, then the other free models as ranked, up to
+/// `max_fallbacks` after the target. Fallbacks skip models the key doesn't
+/// allow and models whose source key isn't configured.
+fn fallback_candidates(
+    target: &FreeModel,
+    ranked: &[FreeModel],
+    max_fallbacks: usize,
+    key: Option<&VirtualKey>,
+    sources: &SourceRegistry,
+    config: &Config,
+) -> Vec<FreeModel> {
+    if max_fallbacks == 0 {
+        return vec![target.clone()];
+    }
+    let name = normalize_model_name(&target.id);
+    let (same_model, others): (Vec<&FreeModel>, Vec<&FreeModel>) = ranked
+        .iter()
+        .filter(|m| m.id != target.id || m.source != target.source)
+        .filter(|m| key.is_none_or(|k| k.allows(&m.id)))
+        .filter(|m| {
+            !sources.auth(m).is_none_or(SourceAuth::requires_key)
+                || config.get_model_api_key(m).is_some()
+        })
+        .partition(|m| normalize_model_name(&m.id) == name);
+
+    std::iter::once(target)
+        .chain(same_model.into_iter().chain(others).take(max_fallbacks))
+        .cloned()
+        .collect()
+}
+
+/// Whether an upstream error is worth retrying on another model: rate
+/// limits, overloads, timeouts and server errors are, while rejected
+/// requests, content filtering and auth failures would fail there too.
+fn should_fall_back(status: u16, body: &str) -> bool {
+    MultiAiError::from_upstream(status, body).is_some_and(|e| e.is_retryable())
+}
+
+/// How long an upstream response asks to wait before retrying: its
+/// `Retry-After`, else its rate-limit reset.
+fn upstream_retry_after(response: &reqwest::Response) -> Option<std::time::Duration> {
+    let now = chrono::Utc::now();
+    retry_after(&rate_limit_headers(response.headers(), now), now)
+}
+
+/// Note an attempt that failed and is being retried on another model.
+fn record_failed_attempt(
+    state: &AppState,
+    target: &FreeModel,
+    api_key: Option<&str>,
+    started: std::time::Instant,
+    status: Option<u16>,
+    rate_limits: &[(String, String)],
+) {
+    if let Some(status) = status {
+        tracing::warn!("{} answered {}, trying the next free model", target.id, status);
+        check_rate_limited(state, target, api_key, status, rate_limits);
+    }
+    state.model_stats.record(&target.id, false, started.elapsed());
+}
+
+/// Build the upstream URL for a model. Keyless local servers serve the
+/// OpenAI API under `/v1`; providers' endpoints already include it.
+pub fn build_upstream_url(sources: &SourceRegistry, model: &FreeModel) -> String {
+    if sources.auth(model) == Some(SourceAuth::None) {
         format!("{}/v1/chat/completions", model.endpoint)
     } else {
         format!("{}/chat/completions", model.endpoint)
     }
 }
 
-/// Get API key for a model's source, if required.
-pub fn get_api_key_for_model(model: &FreeModel) -> Result<Option<String>, MultiAiError> {
-    if model.source == Source::Ollama {
+/// API key for the next request to `model`, rotating through its source's
+/// pool and skipping benched keys.
+pub(super) fn pick_api_key(state: &AppState, model: &FreeModel) -> Result<Option<String>, MultiAiError> {
+    let auth = state.sources.auth(model);
+    if auth == Some(SourceAuth::None) {
         return Ok(None);
     }
 
-    let config = Config::load_with_env();
-    config
-        .get_api_key(&model.source)
-        .map(Some)
-        .ok_or_else(|| MultiAiError::ApiKeyMissing(format!("{:?}", model.source)))
+    let keys = state.config.read().unwrap().get_model_api_keys(model);
+    match state.key_pool.pick(model.source, &keys) {
+        Some(key) => Ok(Some(key)),
+        None if auth == Some(SourceAuth::OptionalBearerKey) => Ok(None),
+        None => Err(MultiAiError::ApiKeyMissing(format!("{:?}", model.source))),
+    }
 }
 
 /// Record an error in the inspector and return the error response.
 pub fn record_error_response(
-    inspector: &TrafficInspector,
+    state: &AppState,
     transaction: &mut crate::inspector::CapturedTransaction,
     error: &MultiAiError,
 ) -> Response {
-    inspector.complete_transaction(
+    state.inspector.complete_transaction(
         transaction,
         CapturedResponse {
             status: error.status_code().as_u16(),
@@ -189,17 +519,202 @@ pub fn record_error_response(
             })),
         },
     );
-    inspector.store(transaction.clone());
+    state.record_transaction(transaction.clone());
     error.clone().into_response()
 }
 
+/// Messages from the named template, filled in with `variables`.
+fn expand_template(
+    state: &AppState,
+    name: &str,
+    variables: &BTreeMap<String, String>,
+) -> Result<Vec<ChatMessage>, MultiAiError> {
+    let store = state
+        .templates
+        .get()
+        .ok_or_else(|| MultiAiError::InvalidRequest("Prompt templates are unavailable".into()))?;
+    let template = store
+        .get(name)
+        .map_err(|e| MultiAiError::Internal(e.to_string()))?
+        .ok_or_else(|| MultiAiError::InvalidRequest(format!("Unknown template '{}'", name)))?;
+    let expansion = template
+        .expand(variables)
+        .map_err(MultiAiError::InvalidRequest)?;
+    Ok(template_messages(expansion))
+}
+
+fn template_messages(expansion: Expansion) -> Vec<ChatMessage> {
+    let system = expansion.system.map(|content| ChatMessage {
+        role: "system".to_string(),
+        content,
+        ..ChatMessage::default()
+    });
+    let user = ChatMessage {
+        role: "user".to_string(),
+        content: expansion.prompt,
+        ..ChatMessage::default()
+    };
+    system.into_iter().chain(std::iter::once(user)).collect()
+}
+
 // ============================================================================
 // Chat completions handler
 // ============================================================================
 
+#[utoipa::path(post, path = "/v1/chat/completions", tag = "chat",
+    params(("x-multiai-chat-id" = Option<String>, Header,
+        description = "Continue this stored conversation (needs `conversation_memory`)")),
+    request_body = ChatRequest,
+    responses(
+        (status = 200, description = "OpenAI `chat.completion` object, or `chat.completion.chunk` events when `stream` is true",
+            content(
+                (serde_json::Value = "application/json"),
+                (String = "text/event-stream")
+            )),
+        (status = 404, description = "Requested model is not free"),
+        (status = 503, description = "No free models available")
+    ))]
 pub async fn chat_completions(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<ChatRequest>,
+) -> Response {
+    let mut ab_tag = None;
+    let response = complete_chat(state, headers, request, &mut ab_tag).await;
+    match ab_tag {
+        Some((arm, transaction_id)) => with_headers(
+            response,
+            &[
+                (AB_ARM_HEADER.to_string(), arm.as_str().to_string()),
+                (TRANSACTION_ID_HEADER.to_string(), transaction_id),
+            ],
+        ),
+        None => response,
+    }
+}
+
+/// Azure OpenAI-shaped chat completions, for tools locked to the Azure SDK.
+/// The deployment name stands in for the model, so deployments are mapped
+/// to free models with `[routing.aliases]`. The gateway key may be sent in
+/// Azure's `api-key` header.
+#[utoipa::path(post, path = "/openai/deployments/{deployment}/chat/completions", tag = "chat",
+    params(
+        ("deployment" = String, Path, description = "Deployment name, resolved like a model name"),
+        ("api-version" = Option<String>, Query, description = "Accepted and ignored"),
+        ("api-key" = Option<String>, Header, description = "Gateway API key")
+    ),
+    request_body = ChatRequest,
+    responses(
+        (status = 200, description = "Same as `/v1/chat/completions`",
+            content(
+                (serde_json::Value = "application/json"),
+                (String = "text/event-stream")
+            )),
+        (status = 400, description = "Malformed body, or the deployment is not a free model")
+    ))]
+pub async fn azure_chat_completions(
+    State(state): State<Arc<AppState>>,
+    Path(deployment): Path<String>,
+    headers: HeaderMap,
+    Json(mut body): Json<serde_json::Value>,
+) -> Response {
+    if let Some(fields) = body.as_object_mut() {
+        fields.insert("model".to_string(), deployment.into());
+    }
+    match serde_json::from_value(body) {
+        Ok(request) => chat_completions(State(state), headers, Json(request)).await,
+        Err(e) => MultiAiError::InvalidRequest(e.to_string()).into_response(),
+    }
+}
+
+// ============================================================================
+// Ollama-native API
+// ============================================================================
+
+/// Route an Ollama request through the chat completions handler and
+/// translate its reply. Models Ollama clients name that aren't free models
+/// or aliases, such as `llama3:latest`, go to `auto`.
+async fn ollama_completion(
+    state: Arc<AppState>,
+    headers: HeaderMap,
+    api: OllamaApi,
+    mut request: ChatRequest,
+) -> Response {
+    let model = request.model.clone();
+    let known = state.model_aliases.contains_key(&model)
+        || state
+            .scanner
+            .get_free_models(false)
+            .await
+            .iter()
+            .any(|m| m.id == model);
+    if !known {
+        request.model = "auto".to_string();
+    }
+    let response = chat_completions(State(state), headers, Json(request)).await;
+    ollama_compat::translate_response(api, model, response).await
+}
+
+#[utoipa::path(post, path = "/api/chat", tag = "ollama",
+    request_body = serde_json::Value,
+    responses(
+        (status = 200, description = "Ollama chat reply, or NDJSON chunks unless `stream` is false",
+            content(
+                (serde_json::Value = "application/json"),
+                (String = "application/x-ndjson")
+            )),
+        (status = 400, description = "The request uses tools or images, which the Ollama API doesn't carry")
+    ))]
+pub async fn ollama_chat(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> Response {
+    match ollama_compat::chat_request(&body) {
+        Ok(request) => ollama_completion(state, headers, OllamaApi::Chat, request).await,
+        Err(error) => ollama_compat::error_reply(&error),
+    }
+}
+
+#[utoipa::path(post, path = "/api/generate", tag = "ollama",
+    request_body = serde_json::Value,
+    responses(
+        (status = 200, description = "Ollama completion, or NDJSON chunks unless `stream` is false",
+            content(
+                (serde_json::Value = "application/json"),
+                (String = "application/x-ndjson")
+            )),
+        (status = 400, description = "The request uses images, which the Ollama API doesn't carry")
+    ))]
+pub async fn ollama_generate(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> Response {
+    // Ollama loads the model and answers nothing for an empty prompt
+    if body["prompt"].as_str().unwrap_or_default().is_empty() {
+        let model = body["model"].as_str().unwrap_or("auto");
+        return Json(ollama_compat::load_reply(model)).into_response();
+    }
+    match ollama_compat::generate_request(&body) {
+        Ok(request) => ollama_completion(state, headers, OllamaApi::Generate, request).await,
+        Err(error) => ollama_compat::error_reply(&error),
+    }
+}
+
+#[utoipa::path(get, path = "/api/tags", tag = "ollama",
+    responses((status = 200, description = "Free models in Ollama's `/api/tags` format", body = serde_json::Value)))]
+pub async fn ollama_tags(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    Json(ollama_compat::tags(&state.scanner.get_free_models(false).await))
+}
+
+/// Handle a completion request. `ab_tag` is set to the A/B arm and
+/// transaction ID when the request was part of an A/B split.
+pub(super) async fn complete_chat(
+    state: Arc<AppState>,
+    headers: HeaderMap,
+    mut request: ChatRequest,
+    ab_tag: &mut Option<(Arm, String)>,
 ) -> Response {
     // Start capturing the transaction
     let captured_request = CapturedRequest {
@@ -210,171 +725,3390 @@ pub async fn chat_completions(
     };
     let mut transaction = state.inspector.start_transaction(captured_request);
 
-    // Get free models and find target
-    let free_models = state.scanner.get_free_models(false).await;
-    let target = match find_target_model(&request.model, &free_models) {
-        Ok(t) => t,
-        Err(e) => return record_error_response(&state.inspector, &mut transaction, &e),
-    };
-
-    // Get API key
-    let api_key = match get_api_key_for_model(target) {
+    // Check the gateway API key, if the request carries one
+    let key = match authorize_key(&state, &headers) {
         Ok(key) => key,
-        Err(e) => return record_error_response(&state.inspector, &mut transaction, &e),
+        Err(e) => return record_error_response(&state, &mut transaction, &e),
     };
+    transaction.virtual_key = key.as_ref().map(|k| k.name.clone());
 
-    // Build upstream URL and request
-    let client = create_client();
-    let upstream_url = build_upstream_url(target);
+    // Expand a named prompt template in front of the request's messages
+    if let Some(name) = request.template.take() {
+        match expand_template(&state, &name, &request.variables) {
+            Ok(mut messages) => {
+                messages.append(&mut request.messages);
+                request.messages = messages;
+            }
+            Err(e) => return record_error_response(&state, &mut transaction, &e),
+        }
+    }
 
-    let upstream_request = serde_json::json!({
-        "model": target.id,
-        "messages": request.messages,
-        "temperature": request.temperature,
-        "max_tokens": request.max_tokens,
-        "stream": request.stream,
-    });
+    // Continue a gateway-managed conversation
+    let mut conversation = None;
+    if let Some(chat_id) = memory::chat_id(&headers).filter(|_| state.conversation_memory) {
+        let messages = std::mem::take(&mut request.messages);
+        match memory::recall(&state.chat, &chat_id, messages) {
+            Ok((messages, turn)) => {
+                request.messages = messages;
+                conversation = Some(turn);
+            }
+            Err(e) => return record_error_response(&state, &mut transaction, &e),
+        }
+    }
 
-    let mut req = client
-        .post(&upstream_url)
-        .header("Content-Type", "application/json");
+    // Let the request hook rewrite, annotate or drop the request
+    let mut hook_headers = BTreeMap::new();
+    if let Some(hooks) = &state.hooks {
+        match apply_request_hook(hooks, &mut request, &headers, &mut transaction) {
+            Ok(headers) => hook_headers = headers,
+            Err(e) => return record_error_response(&state, &mut transaction, &e),
+        }
+    }
+    let valid = tools::validate(&request)
+        .and_then(|_| request.response_format.as_ref().map_or(Ok(()), json_mode::validate_format));
+    if let Err(e) = valid {
+        return record_error_response(&state, &mut transaction, &MultiAiError::InvalidRequest(e));
+    }
 
-    if let Some(key) = &api_key {
-        req = req.header("Authorization", format!("Bearer {}", key));
+    // Resolve configured aliases, then send `auto` to an A/B arm when its
+    // model is currently free
+    let mut requested = state
+        .model_aliases
+        .get(&request.model)
+        .map_or(request.model.as_str(), String::as_str);
+    // Get free models in the order `auto` picks from; fallbacks for a named
+    // model go to the best recent performers first
+    let strategy = match requested {
+        "auto" => request.routing_strategy.unwrap_or(state.routing_strategy),
+        _ => RoutingStrategy::Adaptive,
+    };
+    let mut free_models = state
+        .model_stats
+        .order(&state.scanner.get_free_models(false).await, strategy);
+    // `auto` only picks from the models the key allows
+    if let Some(key) = key.as_ref().filter(|_| requested == "auto") {
+        free_models.retain(|m| key.allows(&m.id));
+    }
+    // Tool calls skip models known not to support them, fallbacks included;
+    // ones whose source doesn't say are worth a try
+    if tools::wants_tools(&request) {
+        let named = free_models.iter().any(|m| m.id == requested);
+        free_models.retain(|m| m.supports_tools != Some(false));
+        let error = if named && !free_models.iter().any(|m| m.id == requested) {
+            Some(format!("Model '{}' does not support tool calling", requested))
+        } else if free_models.is_empty() {
+            Some("No free model that supports tool calling is available".to_string())
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            return record_error_response(&state, &mut transaction, &MultiAiError::InvalidRequest(error));
+        }
+    }
+    // Replies can only be checked against response_format once complete, so
+    // streams go only to models that support it natively
+    let streamed_json = request.stream
+        && request.response_format.as_ref().is_some_and(json_mode::wants_json);
+    if streamed_json {
+        let named = free_models.iter().any(|m| m.id == requested);
+        free_models.retain(|m| m.supports_response_format == Some(true));
+        let error = if named && !free_models.iter().any(|m| m.id == requested) {
+            Some(format!(
+                "Model '{}' does not support response_format when streaming; send stream: false",
+                requested
+            ))
+        } else if free_models.is_empty() {
+            Some("No free model supports response_format when streaming".to_string())
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            return record_error_response(&state, &mut transaction, &MultiAiError::InvalidRequest(error));
+        }
+    }
+    if let Some(ab) = state.ab_split.as_ref().filter(|_| requested == "auto") {
+        let arm = ab.choose();
+        if free_models.iter().any(|m| m.id == ab.model(arm)) {
+            requested = ab.model(arm);
+            transaction.ab_arm = Some(arm.as_str().to_string());
+            *ab_tag = Some((arm, transaction.id.clone()));
+        }
+    }
+    let target = match find_target_model(requested, &free_models) {
+        Ok(t) => t,
+        Err(e) => {
+            if matches!(e, MultiAiError::NoModelsAvailable) {
+                state.events.publish(GatewayEvent::ModelsUnavailable);
+            }
+            return record_error_response(&state, &mut transaction, &e);
+        }
+    };
+    if key.as_ref().is_some_and(|k| !k.allows(&target.id)) {
+        let error = MultiAiError::ModelNotAllowed(target.id.clone());
+        return record_error_response(&state, &mut transaction, &error);
     }
+    // Try the target, then the same model from other sources, then other
+    // free models, while upstreams answer 429/5xx or can't be reached
+    let candidates = fallback_candidates(
+        target,
+        &free_models,
+        state.max_fallbacks,
+        key.as_ref(),
+        &state.sources,
+        &state.config.read().unwrap(),
+    );
+    let client = &state.http;
+    for (attempt, target) in candidates.iter().enumerate() {
+        let can_fall_back = attempt + 1 < candidates.len();
+        let model_used = (MODEL_USED_HEADER.to_string(), target.id.clone());
+        transaction.routed_model = Some(target.id.clone());
+        let started = std::time::Instant::now();
+
+        // Get API key
+        let api_key = match pick_api_key(&state, target) {
+            Ok(key) => key,
+            Err(e) => return record_error_response(&state, &mut transaction, &e),
+        };
+
+        // Build upstream URL and request
+        let key_header = match state.sources.auth(target) {
+            Some(SourceAuth::HeaderKey(name)) => Some(name),
+            _ => None,
+        };
+        // Sources with their own wire format get translated requests and replies
+        let translated = matches!(target.source, Source::Gemini | Source::Cloudflare);
+        let upstream_url = match target.source {
+            Source::Gemini => gemini::generate_url(&target.endpoint, &target.id, request.stream),
+            Source::Cloudflare => cloudflare::run_url(&target.endpoint, &target.id),
+            _ => build_upstream_url(&state.sources, target),
+        };
+
+        let mut upstream_request = serde_json::json!({
+            "model": target.id,
+            "messages": request.messages,
+            "temperature": request.temperature,
+            "max_tokens": request.max_tokens,
+            "stream": request.stream,
+        });
+        if let Some(tools) = &request.tools {
+            upstream_request["tools"] = serde_json::json!(tools);
+        }
+        if let Some(tool_choice) = &request.tool_choice {
+            upstream_request["tool_choice"] = tool_choice.clone();
+        }
+        // Models without `response_format` support are told the format
+        // instead, and their replies are checked below
+        let enforce_json = request
+            .response_format
+            .as_ref()
+            .filter(|f| json_mode::wants_json(f) && target.supports_response_format != Some(true));
+        match (&request.response_format, enforce_json) {
+            (_, Some(format)) => {
+                if let Some(messages) = upstream_request["messages"].as_array_mut() {
+                    let instruction = json_mode::instruction(format);
+                    messages.insert(
+                        0,
+                        serde_json::json!({"role": "system", "content": instruction}),
+                    );
+                }
+            }
+            (Some(format), None) if target.supports_response_format == Some(true) => {
+                upstream_request["response_format"] = format.clone();
+            }
+            _ => {}
+        }
+        // Keep local models loaded between requests
+        if let Some(keep_alive) = state.ollama_keep_alive.as_deref() {
+            if target.source == Source::Ollama {
+                upstream_request["keep_alive"] = ollama::keep_alive_value(keep_alive);
+            }
+        }
+
+        // Offer bridged MCP tools; calls to them are run once the model
+        // answers (the bridge speaks the OpenAI format, so translated sources
+        // answer without tools, and requests bringing their own tools get
+        // their tool calls back instead)
+        let bridged = !request.stream && !translated && request.tools.is_none();
+        let bridge = state.tool_bridge.as_ref().filter(|_| bridged);
+        if let Some(bridge) = bridge {
+            let tools = bridge.openai_tools().await;
+            if !tools.is_empty() {
+                upstream_request["tools"] = serde_json::Value::Array(tools);
+            }
+        }
+
+        let build_request = |body: &serde_json::Value, api_key: Option<&str>| {
+            let mut req = client
+                .post(&upstream_url)
+                .header("Content-Type", "application/json");
+
+            match (api_key, key_header) {
+                (Some(key), Some(name)) => req = req.header(name, key),
+                (Some(key), None) => req = req.header("Authorization", format!("Bearer {}", key)),
+                (None, _) => {}
+            }
+            for (name, value) in state.providers.headers(&target.source) {
+                req = req.header(name, value);
+            }
+            for (name, value) in &hook_headers {
+                req = req.header(name, value);
+            }
+
+            match target.source {
+                Source::Gemini => req.json(&gemini::to_gemini(body)),
+                Source::Cloudflare => req.json(&cloudflare::to_workers_ai(body)),
+                _ => req.json(body),
+            }
+        };
+        let req = build_request(&upstream_request, api_key.as_deref());
+        if !request.stream {
+            let cache_key = state
+                .cache
+                .as_ref()
+                .map(|_| response_cache::cache_key(&target.id, &upstream_request));
+            if let Some((cache, key)) = state.cache.as_ref().zip(cache_key.as_ref()) {
+                if let Some(body) = cache.get(key) {
+                    let hooked = apply_response_hook(&state, &request, 200, body, &mut transaction);
+                    let body = match hooked {
+                        Ok(body) => body,
+                        Err(e) => return record_error_response(&state, &mut transaction, &e),
+                    };
+                    let headers = vec![(CACHE_HEADER.to_string(), "hit".to_string()), model_used];
+                    state.inspector.complete_transaction(
+                        &mut transaction,
+                        CapturedResponse {
+                            status: 200,
+                            headers: headers.clone(),
+                            body: Some(body.clone()),
+                        },
+                    );
+                    state.record_transaction(transaction);
+                    remember_reply(&state, conversation.as_ref(), &body);
+                    return with_headers(Json(body).into_response(), &headers);
+                }
+            }
+
+            let retry = state.retry;
+            let send = async move {
+                let response = http::send_with_retry(retry, req, upstream_retry_after)
+                    .await
+                    .map_err(|e| MultiAiError::from_request_error(&e))?;
+                let status = response.status().as_u16();
+                let rate_limits = rate_limit_headers(response.headers(), chrono::Utc::now());
+                Ok((status, rate_limits, response.text().await.unwrap_or_default()))
+            };
+            let result = match &state.inflight {
+                Some(inflight) => {
+                    let key = inflight::request_key(&target.id, &upstream_request);
+                    let (result, shared) = inflight.run(key, send).await;
+                    if shared {
+                        tracing::debug!("Coalesced duplicate request for {}", target.id);
+                    }
+                    result
+                }
+                None => send.await,
+            };
 
-    match req.json(&upstream_request).send().await {
-        Ok(response) => {
-            let status = response.status();
+            let (status, mut rate_limits, response_text) = match result {
+                Ok((status, rate_limits, text)) if can_fall_back && should_fall_back(status, &text) => {
+                    record_failed_attempt(
+                        &state,
+                        target,
+                        api_key.as_deref(),
+                        started,
+                        Some(status),
+                        &rate_limits,
+                    );
+                    continue;
+                }
+                Ok(reply) => reply,
+                Err(error) if can_fall_back => {
+                    tracing::warn!("{} failed ({}), trying the next free model", target.id, error);
+                    record_failed_attempt(&state, target, api_key.as_deref(), started, None, &[]);
+                    continue;
+                }
+                Err(error) => {
+                    let response = record_error_response(&state, &mut transaction, &error);
+                    record_outcome(&state, &target.id, &transaction);
+                    return with_headers(response, &[model_used]);
+                }
+            };
+            check_key_rejected(&state, target, api_key.as_deref(), status);
+            check_rate_limited(&state, target, api_key.as_deref(), status, &rate_limits);
+            rate_limits.push(model_used);
+
+            return match serde_json::from_str::<serde_json::Value>(&response_text) {
+                Ok(body) => {
+                    // Error bodies are passed through as the provider sent them
+                    let mut body = match status {
+                        200 => translate_reply(target, body),
+                        _ => body,
+                    };
+                    if let Some(bridge) = bridge.filter(|_| status == 200) {
+                        let send = |body: &serde_json::Value| {
+                            send_tool_results(retry, build_request(body, api_key.as_deref()))
+                        };
+                        body = match bridge.resolve(upstream_request.clone(), body, send).await {
+                            Ok(body) => body,
+                            Err(e) if can_fall_back => {
+                                tracing::warn!(
+                                    "{} failed ({}), trying the next free model",
+                                    target.id,
+                                    e
+                                );
+                                let key = api_key.as_deref();
+                                record_failed_attempt(&state, target, key, started, None, &[]);
+                                continue;
+                            }
+                            Err(e) => {
+                                let error = MultiAiError::UpstreamError(e);
+                                let response =
+                                    record_error_response(&state, &mut transaction, &error);
+                                record_outcome(&state, &target.id, &transaction);
+                                return with_headers(response, &rate_limits);
+                            }
+                        };
+                    }
+                    if let Some(format) = enforce_json.filter(|_| status == 200) {
+                        let reply = enforce_response_format(
+                            &state,
+                            &build_request,
+                            target,
+                            format,
+                            upstream_request.clone(),
+                            body,
+                        )
+                        .await;
+                        body = match reply {
+                            Ok(body) => body,
+                            Err(e) => {
+                                let response = record_error_response(&state, &mut transaction, &e);
+                                record_outcome(&state, &target.id, &transaction);
+                                return with_headers(response, &rate_limits);
+                            }
+                        };
+                    }
+                    // Cache the upstream body; hooks run again on every hit
+                    if let Some((cache, key)) = state.cache.as_ref().zip(cache_key) {
+                        if status == 200 {
+                            if let Err(e) = cache.put(&key, &target.id, &body) {
+                                tracing::warn!("Failed to cache response: {}", e);
+                            }
+                        }
+                    }
+                    let hooked =
+                        apply_response_hook(&state, &request, status, body, &mut transaction);
+                    let body = match hooked {
+                        Ok(body) => body,
+                        Err(e) => {
+                            let response = record_error_response(&state, &mut transaction, &e);
+                            record_outcome(&state, &target.id, &transaction);
+                            return response;
+                        }
+                    };
+                    state.inspector.complete_transaction(
+                        &mut transaction,
+                        CapturedResponse {
+                            status,
+                            headers: rate_limits.clone(),
+                            body: Some(body.clone()),
+                        },
+                    );
+                    record_outcome(&state, &target.id, &transaction);
+                    state.record_transaction(transaction);
+                    if status < 400 {
+                        remember_reply(&state, conversation.as_ref(), &body);
+                    }
 
-            if request.stream {
-                state.inspector.complete_transaction(
-                    &mut transaction,
-                    CapturedResponse {
-                        status: status.as_u16(),
-                        headers: vec![("Content-Type".to_string(), "text/event-stream".to_string())],
-                        body: Some(serde_json::json!({"streaming": true})),
-                    },
+                    let response = (StatusCode::from_u16(status).unwrap_or(StatusCode::OK), Json(body))
+                        .into_response();
+                    with_headers(response, &rate_limits)
+                }
+                Err(e) => {
+                    let error = MultiAiError::ParseError(format!(
+                        "{} | Response: {}",
+                        e,
+                        &response_text[..response_text.len().min(500)]
+                    ));
+                    let response = record_error_response(&state, &mut transaction, &error);
+                    record_outcome(&state, &target.id, &transaction);
+                    with_headers(response, &rate_limits)
+                }
+            };
+        }
+
+        return match http::send_with_retry(state.retry, req, upstream_retry_after).await {
+            Ok(response) => {
+                let status = response.status();
+                let mut rate_limits = rate_limit_headers(response.headers(), chrono::Utc::now());
+                let upstream = match target.source {
+                    Source::Gemini if status.is_success() => {
+                        gemini::translate_stream(target.id.clone(), response.bytes_stream()).boxed()
+                    }
+                    Source::Cloudflare if status.is_success() => {
+                        cloudflare::translate_stream(target.id.clone(), response.bytes_stream())
+                            .boxed()
+                    }
+                    _ if status.is_success() => response.bytes_stream().boxed(),
+                    // Error bodies aren't streamed; read them to tell whether
+                    // another model is worth trying
+                    _ => {
+                        let text = response.text().await.unwrap_or_default();
+                        if can_fall_back && should_fall_back(status.as_u16(), &text) {
+                            record_failed_attempt(
+                                &state,
+                                target,
+                                api_key.as_deref(),
+                                started,
+                                Some(status.as_u16()),
+                                &rate_limits,
+                            );
+                            continue;
+                        }
+                        futures::stream::once(async move { Ok(axum::body::Bytes::from(text)) })
+                            .boxed()
+                    }
+                };
+                check_key_rejected(&state, target, api_key.as_deref(), status.as_u16());
+                check_rate_limited(
+                    &state,
+                    target,
+                    api_key.as_deref(),
+                    status.as_u16(),
+                    &rate_limits,
                 );
-                state.inspector.store(transaction);
+                rate_limits.push(model_used);
 
-                let stream = response.bytes_stream().map(|result| {
-                    result.map_err(std::io::Error::other)
-                });
+                // The transaction is completed and recorded when the stream ends
+                let tee = UsageTee::new(
+                    estimate_prompt_tokens(&request.messages),
+                    state.synthesize_stream_usage,
+                );
+                let recorder = StreamRecorder {
+                    state: state.clone(),
+                    model: target.id.clone(),
+                    transaction,
+                    status: status.as_u16(),
+                    tee,
+                    cadence: ChunkCadence::default(),
+                    conversation,
+                    _guard: state.streams.guard(),
+                };
+                // Cut at the end of the shutdown grace period, which drops
+                // the recorder and so records the transaction
+                let closed = state.streams.closed();
+                let stream = futures::stream::unfold(Some((upstream, recorder)), |next| async move {
+                    let (mut upstream, mut recorder) = next?;
+                    match upstream.next().await {
+                        Some(Ok(bytes)) => {
+                            recorder.chunk();
+                            let bytes = recorder.tee.process(bytes);
+                            Some((Ok(bytes), Some((upstream, recorder))))
+                        }
+                        Some(Err(e)) => Some((Err(std::io::Error::other(e)), None)),
+                        None => recorder.tee.finish().map(|rest| (Ok(rest), None)),
+                    }
+                })
+                .take_until(closed);
                 let body = Body::from_stream(stream);
 
-                Response::builder()
+                let response = Response::builder()
                     .status(StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::OK))
                     .header("Content-Type", "text/event-stream")
                     .header("Cache-Control", "no-cache")
                     .header("Connection", "keep-alive")
                     .body(body)
-                    .unwrap()
-                    .into_response()
-            } else {
-                let response_text = response.text().await.unwrap_or_default();
-                match serde_json::from_str::<serde_json::Value>(&response_text) {
-                    Ok(body) => {
-                        state.inspector.complete_transaction(
-                            &mut transaction,
-                            CapturedResponse {
-                                status: status.as_u16(),
-                                headers: vec![],
-                                body: Some(body.clone()),
-                            },
-                        );
-                        state.inspector.store(transaction);
-
-                        (StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::OK), Json(body)).into_response()
-                    }
-                    Err(e) => {
-                        let error = MultiAiError::ParseError(format!(
-                            "{} | Response: {}",
-                            e,
-                            &response_text[..response_text.len().min(500)]
-                        ));
-                        record_error_response(&state.inspector, &mut transaction, &error)
-                    }
-                }
+                    .unwrap();
+                with_headers(response, &rate_limits)
             }
-        }
-        Err(e) => {
-            let error = MultiAiError::UpstreamError(format!("Request failed: {}", e));
-            record_error_response(&state.inspector, &mut transaction, &error)
-        }
+            Err(e) if can_fall_back => {
+                tracing::warn!("{} failed ({}), trying the next free model", target.id, e);
+                record_failed_attempt(&state, target, api_key.as_deref(), started, None, &[]);
+                continue;
+            }
+            Err(e) => {
+                let error = MultiAiError::from_request_error(&e);
+                let response = record_error_response(&state, &mut transaction, &error);
+                record_outcome(&state, &target.id, &transaction);
+                with_headers(response, &[model_used])
+            }
+        };
     }
+    unreachable!("the last candidate always answers")
 }
 
-// ============================================================================
-// Inspect handlers
-// ============================================================================
+/// Run the request hook, applying a rewritten request and recording its
+/// annotations. Returns the headers to add to the upstream request.
+fn apply_request_hook(
+    hooks: &Hooks,
+    request: &mut ChatRequest,
+    headers: &HeaderMap,
+    transaction: &mut CapturedTransaction,
+) -> Result<BTreeMap<String, String>, MultiAiError> {
+    let client_headers = headers
+        .iter()
+        .filter(|(name, _)| *name != header::AUTHORIZATION)
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let body = serde_json::to_value(&*request).unwrap_or_default();
+    let result = hooks
+        .on_request(&body, &client_headers, transaction.virtual_key.as_deref())
+        .map_err(MultiAiError::Internal)?;
+    transaction.annotations.extend(result.annotations);
+    if let Some(reason) = result.drop {
+        return Err(MultiAiError::RequestBlocked(reason));
+    }
+    if let Some(rewritten) = result.request {
+        *request = serde_json::from_value(rewritten).map_err(|e| {
+            MultiAiError::Internal(format!("on_request returned an invalid request: {}", e))
+        })?;
+    }
+    Ok(result.headers)
+}
 
-pub async fn get_inspect(
-    State(state): State<Arc<AppState>>,
-    Query(query): Query<InspectQuery>,
-) -> Json<serde_json::Value> {
-    match query.format.as_deref() {
-        Some("har") => Json(state.inspector.export_har()),
-        _ => {
-            let transactions = state.inspector.get_all();
-            let count = transactions.len();
-            Json(serde_json::json!({
-                "transactions": transactions,
-                "count": count
-            }))
+/// Run the response hook on a non-streaming response body, recording its
+/// annotations.
+fn apply_response_hook(
+    state: &AppState,
+    request: &ChatRequest,
+    status: u16,
+    body: serde_json::Value,
+    transaction: &mut CapturedTransaction,
+) -> Result<serde_json::Value, MultiAiError> {
+    let Some(hooks) = &state.hooks else {
+        return Ok(body);
+    };
+    let request = serde_json::to_value(request).unwrap_or_default();
+    let result = hooks
+        .on_response(&request, status, &body)
+        .map_err(MultiAiError::Internal)?;
+    transaction.annotations.extend(result.annotations);
+    Ok(result.response.unwrap_or(body))
+}
+
+/// The gateway API key a request carries, checked against its rate limit
+/// and quota. Bearer tokens without the key prefix are provider keys some
+/// clients always send, and are ignored unless a key is required.
+fn authorize_key(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<Option<VirtualKey>, MultiAiError> {
+    let secret = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        // Azure SDKs send the key in `api-key` instead
+        .or_else(|| headers.get(AZURE_KEY_HEADER).and_then(|v| v.to_str().ok()))
+        .map(str::trim)
+        .filter(|token| token.starts_with(KEY_PREFIX));
+    let Some(secret) = secret else {
+        if state.require_api_key {
+            return Err(MultiAiError::InvalidApiKey("a gateway API key is required".to_string()));
+        }
+        return Ok(None);
+    };
+    let Some(keys) = state.keys.get() else {
+        return Err(MultiAiError::InvalidApiKey("key store is unavailable".to_string()));
+    };
+    match keys.authorize(secret) {
+        Ok(Ok(key)) => Ok(Some(key)),
+        Ok(Err(KeyDenied::Unknown)) => {
+            Err(MultiAiError::InvalidApiKey("unknown or revoked key".to_string()))
         }
+        Ok(Err(KeyDenied::RateLimited { rpm })) => Err(MultiAiError::KeyQuotaExceeded(format!(
+            "Rate limit of {} requests per minute reached for this API key",
+            rpm
+        ))),
+        Ok(Err(KeyDenied::QuotaExhausted { quota })) => Err(MultiAiError::KeyQuotaExceeded(
+            format!("Daily quota of {} tokens used up for this API key", quota),
+        )),
+        Err(e) => Err(MultiAiError::Internal(e.to_string())),
     }
 }
 
-pub async fn clear_inspect(State(state): State<Arc<AppState>>) -> Json<ClearResponse> {
-    let count = state.inspector.get_all().len();
-    state.inspector.clear();
-    Json(ClearResponse { cleared: true, count })
+/// Add forwarded upstream headers to a response, skipping invalid ones.
+fn with_headers(mut response: Response, headers: &[(String, String)]) -> Response {
+    for (name, value) in headers {
+        if let (Ok(name), Ok(value)) = (
+            header::HeaderName::from_bytes(name.as_bytes()),
+            header::HeaderValue::from_str(value),
+        ) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+    response
 }
 
-// ============================================================================
-// Settings handlers
-// ============================================================================
+/// Response header naming the A/B arm a request was routed to.
+const AB_ARM_HEADER: &str = "x-multiai-ab-arm";
 
-pub async fn get_settings() -> Json<SettingsResponse> {
-    let config = Config::load_with_env();
+/// Response header carrying the transaction ID, for A/B feedback.
+const TRANSACTION_ID_HEADER: &str = "x-multiai-transaction-id";
 
-    Json(SettingsResponse {
-        openrouter_configured: config.api_keys.openrouter.is_some(),
-        opencode_zen_configured: config.api_keys.opencode_zen.is_some(),
-    })
+/// Convert a successful reply from a source with its own wire format into a
+/// `chat.completion`.
+fn translate_reply(target: &FreeModel, body: serde_json::Value) -> serde_json::Value {
+    match target.source {
+        Source::Gemini => gemini::from_gemini(&target.id, &body),
+        Source::Cloudflare => cloudflare::from_workers_ai(&target.id, &body),
+        _ => body,
+    }
 }
 
-pub async fn update_settings(
-    Json(req): Json<UpdateSettingsRequest>,
-) -> Result<Json<SettingsResponse>, (StatusCode, Json<serde_json::Value>)> {
-    let mut config = Config::load().unwrap_or_default();
+/// Check a reply against a locally enforced `response_format`, sending it
+/// back with the problem up to `json_retries` times. Re-prompts pick their
+/// key and retry transient failures like any other upstream request.
+/// Returns the reply with its content trimmed to the JSON.
+async fn enforce_response_format(
+    state: &AppState,
+    build_request: impl Fn(&serde_json::Value, Option<&str>) -> reqwest::RequestBuilder,
+    target: &FreeModel,
+    format: &serde_json::Value,
+    mut upstream_request: serde_json::Value,
+    mut body: serde_json::Value,
+) -> Result<serde_json::Value, MultiAiError> {
+    let retries = state.json_retries;
+    let mut attempt = 0;
+    loop {
+        let content = body["choices"][0]["message"]["content"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let problem = match json_mode::check_reply(format, &content) {
+            Ok(json) => {
+                body["choices"][0]["message"]["content"] = json.into();
+                return Ok(body);
+            }
+            Err(problem) => problem,
+        };
+        if attempt == retries {
+            return Err(MultiAiError::ParseError(format!(
+                "{} did not answer in the requested response_format after {} retries: {}",
+                target.id, retries, problem
+            )));
+        }
+        attempt += 1;
+        tracing::debug!("{} ignored response_format ({}), re-prompting", target.id, problem);
 
-    if let Some(key) = req.openrouter_api_key {
-        if key.is_empty() {
-            config.api_keys.openrouter = None;
-        } else {
-            config.api_keys.openrouter = Some(key);
+        if let Some(messages) = upstream_request["messages"].as_array_mut() {
+            let correction = json_mode::correction(&problem);
+            messages.push(serde_json::json!({"role": "assistant", "content": content}));
+            messages.push(serde_json::json!({"role": "user", "content": correction}));
+        }
+        let api_key = pick_api_key(state, target)?;
+        let req = build_request(&upstream_request, api_key.as_deref());
+        let response = http::send_with_retry(state.retry, req, upstream_retry_after)
+            .await
+            .map_err(|e| MultiAiError::from_request_error(&e))?;
+        let status = response.status().as_u16();
+        let rate_limits = rate_limit_headers(response.headers(), chrono::Utc::now());
+        check_key_rejected(state, target, api_key.as_deref(), status);
+        check_rate_limited(state, target, api_key.as_deref(), status, &rate_limits);
+        if !response.status().is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(MultiAiError::from_upstream(status, &text).unwrap_or_else(|| {
+                MultiAiError::UpstreamError(format!(
+                    "{} answered {} to a response_format retry",
+                    target.id, status
+                ))
+            }));
         }
+        let reply = response
+            .json()
+            .await
+            .map_err(|e| MultiAiError::ParseError(e.to_string()))?;
+        body = translate_reply(target, reply);
     }
+}
 
-    if let Some(key) = req.opencode_zen_api_key {
-        if key.is_empty() {
-            config.api_keys.opencode_zen = None;
-        } else {
-            config.api_keys.opencode_zen = Some(key);
-        }
+/// Send bridged tool results back to the model, returning its next reply.
+async fn send_tool_results(
+    retry: http::RetryPolicy,
+    req: reqwest::RequestBuilder,
+) -> Result<serde_json::Value, String> {
+    let response = http::send_with_retry(retry, req, upstream_retry_after)
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Model returned status: {}", response.status()));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))
+}
+
+/// Response header marking completions served from the response cache.
+const CACHE_HEADER: &str = "x-multiai-cache";
+
+/// Response header naming the model that answered, after any fallback.
+const MODEL_USED_HEADER: &str = "x-multiai-model-used";
+
+/// Request header Azure OpenAI clients send their key in.
+pub(super) const AZURE_KEY_HEADER: &str = "api-key";
+
+/// Tell the app shell when a provider rejects the configured key, and bench
+/// the key so requests rotate to the rest of its pool.
+fn check_key_rejected(state: &AppState, target: &FreeModel, api_key: Option<&str>, status: u16) {
+    if let Some(key) = api_key.filter(|_| matches!(status, 401 | 403)) {
+        state.key_pool.report(key, status, None);
+        state.events.publish(GatewayEvent::KeyRejected {
+            source: target.source,
+            status,
+        });
     }
+}
 
-    if let Err(e) = config.save() {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({ "error": format!("Failed to save settings: {}", e) })),
-        ));
+/// Keep `auto` off a model, and requests off the key, while the provider
+/// is rate limiting them.
+fn check_rate_limited(
+    state: &AppState,
+    target: &FreeModel,
+    api_key: Option<&str>,
+    status: u16,
+    rate_limits: &[(String, String)],
+) {
+    if status == 429 {
+        let retry = retry_after(rate_limits, chrono::Utc::now());
+        if let Some(cooldown) = state.model_stats.rate_limited(&target.id, retry) {
+            state.events.publish(GatewayEvent::CircuitOpen {
+                source: target.source,
+                model: target.id.clone(),
+                retry_after_secs: cooldown.as_secs(),
+            });
+        }
+        if let Some(key) = api_key {
+            state.key_pool.report(key, status, retry);
+        }
     }
+}
 
-    Ok(Json(SettingsResponse {
-        openrouter_configured: config.api_keys.openrouter.is_some(),
-        opencode_zen_configured: config.api_keys.opencode_zen.is_some(),
-    }))
+/// Feed a finished transaction into the model's rolling stats. Streams are
+/// timed to the first byte, everything else to completion.
+fn record_outcome(state: &AppState, model: &str, transaction: &CapturedTransaction) {
+    let success = transaction
+        .response
+        .as_ref()
+        .is_some_and(|r| r.status < 400);
+    let timing = &transaction.timing;
+    let latency_ms = timing.ttfb_ms.unwrap_or(timing.total_ms);
+    state
+        .model_stats
+        .record(model, success, std::time::Duration::from_millis(latency_ms));
+    if let Some(telemetry) = &state.telemetry {
+        telemetry.record(success);
+    }
+    let arm = transaction.ab_arm.as_deref().and_then(Arm::from_tag);
+    if let Some((ab, arm)) = state.ab_split.as_ref().zip(arm) {
+        ab.record(
+            &transaction.id,
+            arm,
+            success,
+            std::time::Duration::from_millis(latency_ms),
+        );
+    }
+}
+
+/// Save a non-streaming reply to the request's conversation, if it has one.
+fn remember_reply(state: &AppState, conversation: Option<&Conversation>, body: &serde_json::Value) {
+    if let Some((conversation, reply)) = conversation.zip(memory::reply_content(body)) {
+        conversation.save(&state.chat, reply);
+    }
+}
+
+/// Owns a streaming transaction until the body is finished or dropped, then
+/// records it with the reply and token counts seen in the stream.
+struct StreamRecorder {
+    state: Arc<AppState>,
+    model: String,
+    transaction: CapturedTransaction,
+    status: u16,
+    tee: UsageTee,
+    cadence: ChunkCadence,
+    /// Conversation to save the streamed reply to.
+    conversation: Option<Conversation>,
+    // Count the stream as active until the body is dropped
+    _guard: StreamGuard,
+}
+
+impl StreamRecorder {
+    /// Note an upstream chunk, recording TTFB on the first.
+    fn chunk(&mut self) {
+        if self.transaction.timing.ttfb_ms.is_none() {
+            self.state.inspector.record_ttfb(&mut self.transaction);
+        }
+        self.cadence.tick();
+    }
+}
+
+impl Drop for StreamRecorder {
+    fn drop(&mut self) {
+        let inspector = &self.state.inspector;
+        inspector.complete_transaction(
+            &mut self.transaction,
+            CapturedResponse {
+                status: self.status,
+                headers: vec![("Content-Type".to_string(), "text/event-stream".to_string())],
+                body: Some(self.tee.completion()),
+            },
+        );
+        let usage = self.tee.usage();
+        inspector.record_tokens(&mut self.transaction, usage.prompt_tokens, usage.completion_tokens);
+        inspector.record_chunks(&mut self.transaction, &self.cadence);
+        record_outcome(&self.state, &self.model, &self.transaction);
+        self.state.record_transaction(self.transaction.clone());
+        if let Some(conversation) = self.conversation.as_ref().filter(|_| self.status < 400) {
+            if !self.tee.content().is_empty() {
+                conversation.save(&self.state.chat, self.tee.content());
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Embeddings handler
+// ============================================================================
+
+#[utoipa::path(get, path = "/v1/embeddings/models", tag = "embeddings",
+    responses((status = 200, description = "Free embedding models in OpenAI list format", body = ModelsResponse)))]
+pub async fn list_embedding_models(State(state): State<Arc<AppState>>) -> Json<ModelsResponse> {
+    let data = state
+        .scanner
+        .get_embedding_models(false)
+        .await
+        .into_iter()
+        .map(|m| ModelInfo {
+            id: m.id,
+            object: "model",
+            created: chrono::Utc::now().timestamp(),
+            owned_by: m.provider,
+            context_length: m.context_length,
+            modalities: m.modalities,
+            supports_tools: m.supports_tools,
+            rate_limited_for_secs: None,
+        })
+        .collect();
+
+    Json(ModelsResponse {
+        object: "list",
+        data,
+    })
+}
+
+/// Embed texts with a free embedding model from Ollama or OpenRouter;
+/// `auto` picks the first one the key allows, local models first, and
+/// overloaded or unreachable models fall back to the next. Texts already
+/// embedded by the model are answered from the embedding cache; only the
+/// rest go upstream.
+#[utoipa::path(post, path = "/v1/embeddings", tag = "embeddings",
+    request_body = EmbeddingRequest,
+    responses(
+        (status = 200, description = "OpenAI `list` of `embedding` objects, in input order", body = serde_json::Value),
+        (status = 400, description = "Empty input, or the model is not a free embedding model")
+    ))]
+pub async fn embeddings(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<EmbeddingRequest>,
+) -> Response {
+    let captured_request = CapturedRequest {
+        method: "POST".to_string(),
+        url: "/v1/embeddings".to_string(),
+        headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+        body: serde_json::to_value(&request).ok(),
+    };
+    let mut transaction = state.inspector.start_transaction(captured_request);
+
+    let key = match authorize_key(&state, &headers) {
+        Ok(key) => key,
+        Err(e) => return record_error_response(&state, &mut transaction, &e),
+    };
+    transaction.virtual_key = key.as_ref().map(|k| k.name.clone());
+    if request.model != "auto" && key.as_ref().is_some_and(|k| !k.allows(&request.model)) {
+        let error = MultiAiError::ModelNotAllowed(request.model.clone());
+        return record_error_response(&state, &mut transaction, &error);
+    }
+
+    let texts = request.input.into_texts();
+    if texts.is_empty() {
+        let error = MultiAiError::InvalidRequest("input must not be empty".to_string());
+        return record_error_response(&state, &mut transaction, &error);
+    }
+    let mut embedding_models = state.scanner.get_embedding_models(false).await;
+    // `auto` only picks from the models the key allows
+    if let Some(key) = key.as_ref() {
+        embedding_models.retain(|m| key.allows(&m.id));
+    }
+    let target = match request.model.as_str() {
+        "auto" => embedding_models.first(),
+        model => embedding_models.iter().find(|m| m.id == model),
+    };
+    let Some(target) = target else {
+        let error = MultiAiError::InvalidRequest(format!(
+            "'{}' is not a free embedding model; see /v1/embeddings/models",
+            request.model
+        ));
+        return record_error_response(&state, &mut transaction, &error);
+    };
+    if key.as_ref().is_some_and(|k| !k.allows(&target.id)) {
+        let error = MultiAiError::ModelNotAllowed(target.id.clone());
+        return record_error_response(&state, &mut transaction, &error);
+    }
+
+    // Try the target, then other embedding models while upstreams answer
+    // 429/5xx or can't be reached
+    let candidates = fallback_candidates(
+        target,
+        &embedding_models,
+        state.max_fallbacks,
+        key.as_ref(),
+        &state.sources,
+        &state.config.read().unwrap(),
+    );
+    let mut embedded = None;
+    for (attempt, target) in candidates.iter().enumerate() {
+        transaction.routed_model = Some(target.id.clone());
+        match embed_texts(&state, target, &texts).await {
+            Err(e) if attempt + 1 < candidates.len() && e.is_retryable() => {
+                tracing::warn!("{} failed ({}), trying the next free model", target.id, e);
+            }
+            result => {
+                embedded = Some((target.id.clone(), result));
+                break;
+            }
+        }
+    }
+    let Some((model, result)) = embedded else {
+        let error = MultiAiError::NoModelsAvailable;
+        return record_error_response(&state, &mut transaction, &error);
+    };
+    let (vectors, cached, prompt_tokens) = match result {
+        Ok(embedded) => embedded,
+        Err(e) => return record_error_response(&state, &mut transaction, &e),
+    };
+
+    // Vectors are left out of the capture; counts and usage are kept
+    let usage = serde_json::json!({ "prompt_tokens": prompt_tokens, "total_tokens": prompt_tokens });
+    state.inspector.complete_transaction(
+        &mut transaction,
+        CapturedResponse {
+            status: 200,
+            headers: vec![],
+            body: Some(serde_json::json!({
+                "model": model,
+                "embeddings": texts.len(),
+                "cached": cached,
+                "usage": usage.clone(),
+            })),
+        },
+    );
+    state.record_transaction(transaction);
+
+    let data: Vec<serde_json::Value> = vectors
+        .into_iter()
+        .enumerate()
+        .map(|(index, embedding)| {
+            serde_json::json!({
+                "object": "embedding",
+                "index": index,
+                "embedding": embedding,
+            })
+        })
+        .collect();
+    let body = serde_json::json!({
+        "object": "list",
+        "data": data,
+        "model": model,
+        "usage": usage,
+    });
+    let cache_status = match cached {
+        0 => None,
+        n if n == texts.len() => Some("hit"),
+        _ => Some("partial"),
+    };
+    let headers: Vec<_> = cache_status
+        .map(|status| (CACHE_HEADER.to_string(), status.to_string()))
+        .into_iter()
+        .collect();
+    with_headers(Json(body).into_response(), &headers)
+}
+
+/// Embed `texts` with `target`, answering what the embedding cache holds
+/// and fetching the rest. Returns the vectors in input order, how many came
+/// from the cache and the prompt tokens the upstream counted.
+async fn embed_texts(
+    state: &AppState,
+    target: &FreeModel,
+    texts: &[String],
+) -> Result<(Vec<Vec<f32>>, usize, u64), MultiAiError> {
+    let cache = state.embedding_cache.as_ref();
+    let mut vectors: Vec<Option<Vec<f32>>> = texts
+        .iter()
+        .map(|text| cache.and_then(|c| c.get(&target.id, text)))
+        .collect();
+    let missing: Vec<usize> = (0..texts.len()).filter(|&i| vectors[i].is_none()).collect();
+
+    let mut prompt_tokens = 0;
+    if !missing.is_empty() {
+        let body = serde_json::json!({
+            "model": target.id,
+            "input": missing.iter().map(|&i| &texts[i]).collect::<Vec<_>>(),
+        });
+        let fetched = fetch_embeddings(state, target, &body, missing.len()).await?;
+        prompt_tokens = fetched.usage.map_or(0, |u| u.prompt_tokens);
+        for item in fetched.data {
+            let i = missing[item.index];
+            if let Some(cache) = cache {
+                if let Err(e) = cache.put(&target.id, &texts[i], &item.embedding) {
+                    tracing::warn!("Failed to cache embedding: {}", e);
+                }
+            }
+            vectors[i] = Some(item.embedding);
+        }
+    }
+    let cached = texts.len() - missing.len();
+    let vectors = vectors.into_iter().map(Option::unwrap_or_default).collect();
+    Ok((vectors, cached, prompt_tokens))
+}
+
+/// Send an OpenAI-style embeddings request to `target` with the next key
+/// of its source, checking that a vector came back for each of the
+/// `expected` inputs. Transient failures are retried and rejected or
+/// rate-limited keys reported, as for chat requests.
+async fn fetch_embeddings(
+    state: &AppState,
+    target: &FreeModel,
+    body: &serde_json::Value,
+    expected: usize,
+) -> Result<UpstreamEmbeddings, MultiAiError> {
+    let url = match target.source {
+        Source::Ollama => format!("{}/v1/embeddings", target.endpoint),
+        _ => format!("{}/embeddings", target.endpoint),
+    };
+    let api_key = pick_api_key(state, target)?;
+    let key_header = match state.sources.auth(target) {
+        Some(SourceAuth::HeaderKey(name)) => Some(name),
+        _ => None,
+    };
+    let mut request = state.http.post(&url).json(body);
+    match (api_key.as_deref(), key_header) {
+        (Some(key), Some(name)) => request = request.header(name, key),
+        (Some(key), None) => request = request.header("Authorization", format!("Bearer {}", key)),
+        (None, _) => {}
+    }
+    let response = http::send_with_retry(state.retry, request, upstream_retry_after)
+        .await
+        .map_err(|e| MultiAiError::from_request_error(&e))?;
+    let status = response.status().as_u16();
+    let rate_limits = rate_limit_headers(response.headers(), chrono::Utc::now());
+    check_key_rejected(state, target, api_key.as_deref(), status);
+    check_rate_limited(state, target, api_key.as_deref(), status, &rate_limits);
+    let text = response
+        .text()
+        .await
+        .map_err(|e| MultiAiError::from_request_error(&e))?;
+    if let Some(error) = MultiAiError::from_upstream(status, &text) {
+        return Err(error);
+    }
+    let parsed: UpstreamEmbeddings =
+        serde_json::from_str(&text).map_err(|e| MultiAiError::ParseError(e.to_string()))?;
+    let mut seen = vec![false; expected];
+    for item in &parsed.data {
+        match seen.get_mut(item.index) {
+            Some(seen) => *seen = true,
+            None => {
+                return Err(MultiAiError::ParseError(format!(
+                    "embedding index {} out of range",
+                    item.index
+                )))
+            }
+        }
+    }
+    if seen.contains(&false) {
+        return Err(MultiAiError::ParseError(format!(
+            "expected {} embeddings, got {}",
+            expected,
+            parsed.data.len()
+        )));
+    }
+    Ok(parsed)
+}
+
+// ============================================================================
+// Audio handlers
+// ============================================================================
+
+/// Read text aloud through the `[[audio.speech]]` servers, streaming back
+/// their audio.
+#[utoipa::path(post, path = "/v1/audio/speech", tag = "audio",
+    request_body = SpeechRequest,
+    responses(
+        (status = 200, description = "Audio in the requested format", content_type = "application/octet-stream", body = Vec<u8>),
+        (status = 400, description = "Empty or too-long input, or no server provides the model"),
+        (status = 500, description = "No speech backend configured")
+    ))]
+pub async fn audio_speech(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<SpeechRequest>,
+) -> Response {
+    let captured_request = CapturedRequest {
+        method: "POST".to_string(),
+        url: "/v1/audio/speech".to_string(),
+        headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+        body: Some(serde_json::to_value(&request).unwrap_or_default()),
+    };
+    let mut transaction = state.inspector.start_transaction(captured_request);
+
+    let key = match authorize_key(&state, &headers) {
+        Ok(key) => key,
+        Err(e) => return record_error_response(&state, &mut transaction, &e),
+    };
+    transaction.virtual_key = key.as_ref().map(|k| k.name.clone());
+    if key.as_ref().is_some_and(|k| !k.allows(&request.model)) {
+        let error = MultiAiError::ModelNotAllowed(request.model.clone());
+        return record_error_response(&state, &mut transaction, &error);
+    }
+    if let Err(e) = request.validate() {
+        return record_error_response(&state, &mut transaction, &MultiAiError::InvalidRequest(e));
+    }
+
+    let upstream = match speech::synthesize(&state.http, &state.speech_backends, &request).await {
+        Ok(upstream) => upstream,
+        Err(e) => return record_error_response(&state, &mut transaction, &e),
+    };
+    let content_type = upstream
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("audio/mpeg")
+        .to_string();
+    state.inspector.complete_transaction(
+        &mut transaction,
+        CapturedResponse {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), content_type.clone())],
+            body: None,
+        },
+    );
+    state.record_transaction(transaction);
+
+    (
+        [(header::CONTENT_TYPE, content_type)],
+        Body::from_stream(upstream.bytes_stream()),
+    )
+        .into_response()
+}
+
+// ============================================================================
+// Inspect handlers
+// ============================================================================
+
+#[utoipa::path(get, path = "/v1/inspect", tag = "inspect",
+    params(InspectQuery),
+    responses((status = 200, description = "Captured transactions, or a HAR log with `format=har`", body = serde_json::Value)))]
+pub async fn get_inspect(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<InspectQuery>,
+) -> Json<serde_json::Value> {
+    match query.format.as_deref() {
+        Some("har") => Json(state.inspector.export_har()),
+        _ => {
+            let transactions = state.inspector.get_all();
+            let count = transactions.len();
+            Json(serde_json::json!({
+                "transactions": transactions,
+                "count": count
+            }))
+        }
+    }
+}
+
+#[utoipa::path(delete, path = "/v1/inspect", tag = "inspect",
+    responses((status = 200, body = ClearResponse)))]
+pub async fn clear_inspect(State(state): State<Arc<AppState>>) -> Json<ClearResponse> {
+    let count = state.inspector.get_all().len();
+    state.inspector.clear();
+    Json(ClearResponse { cleared: true, count })
+}
+
+/// Stream transactions as JSON events as they are captured.
+#[utoipa::path(get, path = "/v1/inspect/stream", tag = "inspect",
+    responses((status = 200, description = "Server-sent events, one JSON transaction each", content_type = "text/event-stream", body = String)))]
+pub async fn inspect_stream(
+    State(state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.inspector.subscribe();
+
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(tx) => {
+                    let data = serde_json::to_string(&tx).unwrap_or_default();
+                    return Some((Ok(Event::default().data(data)), receiver));
+                }
+                // Slow consumers skip transactions rather than disconnecting
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+    .take_until(state.streams.draining());
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Push matching transactions and per-second stats deltas over a WebSocket.
+/// The query string sets the initial filter; the client can send a new
+/// filter object as a text message at any time.
+#[utoipa::path(get, path = "/v1/inspect/ws", tag = "inspect",
+    params(TransactionFilter),
+    responses((status = 101, description = "WebSocket of transaction, stats and error messages")))]
+pub async fn inspect_ws(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<TransactionFilter>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let receiver = state.inspector.subscribe();
+    let draining = state.streams.draining();
+    ws.on_upgrade(move |socket| inspect_ws::serve(socket, receiver, filter, draining))
+}
+
+// ============================================================================
+// Cache and metrics handlers
+// ============================================================================
+
+/// Empty the response cache.
+#[utoipa::path(delete, path = "/v1/cache", tag = "admin",
+    responses(
+        (status = 200, body = ClearResponse),
+        (status = 503, description = "Response cache is disabled")
+    ))]
+pub async fn clear_cache(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ClearResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let Some(cache) = &state.cache else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "error": "Response cache is disabled" })),
+        ));
+    };
+    cache
+        .clear()
+        .map(|count| Json(ClearResponse { cleared: true, count }))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+        })
+}
+
+/// Prometheus text-format metrics.
+#[utoipa::path(get, path = "/metrics", tag = "admin",
+    responses((status = 200, description = "Prometheus text format", content_type = "text/plain", body = String)))]
+pub async fn metrics(State(state): State<Arc<AppState>>) -> Response {
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: u64| {
+        out.push_str(&format!(
+            "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}\n"
+        ));
+    };
+    metric(
+        "multiai_uptime_seconds",
+        "gauge",
+        "Seconds since the gateway started.",
+        state.started_at.elapsed().as_secs(),
+    );
+    if let Some(cache) = &state.cache {
+        let stats = cache.stats();
+        metric("multiai_cache_hits_total", "counter", "Response cache hits.", stats.hits);
+        metric("multiai_cache_misses_total", "counter", "Response cache misses.", stats.misses);
+        metric("multiai_cache_entries", "gauge", "Responses in the cache.", stats.entries);
+        metric("multiai_cache_bytes", "gauge", "Size of cached response bodies.", stats.bytes);
+    }
+    if let Some(cache) = &state.embedding_cache {
+        let stats = cache.stats();
+        metric("multiai_embedding_cache_hits_total", "counter", "Embedding cache hits.", stats.hits);
+        metric(
+            "multiai_embedding_cache_misses_total",
+            "counter",
+            "Embedding cache misses.",
+            stats.misses,
+        );
+        metric("multiai_embedding_cache_entries", "gauge", "Vectors in the cache.", stats.entries);
+    }
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        out,
+    )
+        .into_response()
+}
+
+// ============================================================================
+// Spending handlers
+// ============================================================================
+
+/// Default number of top spending models listed.
+const DEFAULT_TOP_SPENDERS: usize = 10;
+
+/// Judge spending totals, remaining budget and breakdown by model and provider.
+#[utoipa::path(get, path = "/v1/spending", tag = "admin",
+    params(SpendingQuery),
+    responses(
+        (status = 200, body = SpendingReport),
+        (status = 503, description = "Spending tracking is unavailable")
+    ))]
+pub async fn get_spending(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SpendingQuery>,
+) -> Result<Json<SpendingReport>, (StatusCode, Json<serde_json::Value>)> {
+    let Some(tracker) = &state.spending else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "error": "Spending tracking is unavailable" })),
+        ));
+    };
+    tracker
+        .get_report(query.top.unwrap_or(DEFAULT_TOP_SPENDERS))
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+        })
+}
+
+// ============================================================================
+// A/B test handlers
+// ============================================================================
+
+fn ab_unavailable() -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(serde_json::json!({ "error": "No A/B test is configured" })),
+    )
+}
+
+/// Per-arm requests, success rate, latency and feedback for the A/B test.
+#[utoipa::path(get, path = "/v1/ab", tag = "admin",
+    responses(
+        (status = 200, body = AbReport),
+        (status = 503, description = "No A/B test is configured")
+    ))]
+pub async fn get_ab_report(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<AbReport>, (StatusCode, Json<serde_json::Value>)> {
+    let ab = state.ab_split.as_ref().ok_or_else(ab_unavailable)?;
+    Ok(Json(ab.report()))
+}
+
+/// Rate a completion routed by the A/B test, by its `x-multiai-transaction-id`.
+#[utoipa::path(post, path = "/v1/ab/feedback", tag = "admin",
+    request_body = AbFeedbackRequest,
+    responses(
+        (status = 200, body = AbFeedbackResponse),
+        (status = 404, description = "Unknown or expired transaction"),
+        (status = 503, description = "No A/B test is configured")
+    ))]
+pub async fn ab_feedback(
+    State(state): State<Arc<AppState>>,
+    Json(feedback): Json<AbFeedbackRequest>,
+) -> Result<Json<AbFeedbackResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let ab = state.ab_split.as_ref().ok_or_else(ab_unavailable)?;
+    match ab.feedback(&feedback.transaction_id, feedback.positive) {
+        Some(arm) => Ok(Json(AbFeedbackResponse { arm })),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Unknown or expired transaction" })),
+        )),
+    }
+}
+
+// ============================================================================
+// Benchmark handlers
+// ============================================================================
+
+fn bench_jobs(state: &AppState) -> Result<&Benchmarks, JsonError> {
+    state
+        .bench
+        .get()
+        .ok_or_else(|| json_error(StatusCode::SERVICE_UNAVAILABLE, "Benchmarks are unavailable"))
+}
+
+/// Start a benchmark job in the background; poll `/v1/bench/{id}` for results.
+#[utoipa::path(post, path = "/v1/bench", tag = "eval",
+    request_body = BenchRequest,
+    responses(
+        (status = 202, body = BenchJob),
+        (status = 400, description = "No prompts or too many iterations")
+    ))]
+pub async fn start_bench(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<BenchRequest>,
+) -> Result<(StatusCode, Json<BenchJob>), JsonError> {
+    request
+        .validate()
+        .map_err(|e| json_error(StatusCode::BAD_REQUEST, e))?;
+    let bench = bench_jobs(&state)?.clone();
+    let job = bench.start(request);
+
+    let comparator = state.comparator();
+    let id = job.id.clone();
+    tokio::spawn(async move { bench.run(comparator, id).await });
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// Running jobs, then recently finished ones.
+#[utoipa::path(get, path = "/v1/bench", tag = "eval",
+    responses((status = 200, body = BenchJobsResponse)))]
+pub async fn list_bench_jobs(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<BenchJobsResponse>, JsonError> {
+    let jobs = bench_jobs(&state)?
+        .list()
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(BenchJobsResponse { jobs }))
+}
+
+#[utoipa::path(get, path = "/v1/bench/{id}", tag = "eval",
+    params(("id" = String, Path, description = "Job ID")),
+    responses(
+        (status = 200, body = BenchJob),
+        (status = 404, description = "No such job")
+    ))]
+pub async fn get_bench_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<BenchJob>, JsonError> {
+    bench_jobs(&state)?
+        .get(&id)
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .map(Json)
+        .ok_or_else(|| json_error(StatusCode::NOT_FOUND, format!("Benchmark '{}' not found", id)))
+}
+
+// ============================================================================
+// Log tail handlers
+// ============================================================================
+
+/// Default number of lines returned by the log tail endpoint.
+const DEFAULT_TAIL_LINES: usize = 200;
+
+#[utoipa::path(get, path = "/api/logs/tail", tag = "logs",
+    params(LogsTailQuery),
+    responses((status = 200, body = LogsTailResponse)))]
+pub async fn logs_tail(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LogsTailQuery>,
+) -> Json<LogsTailResponse> {
+    let lines = state.logs.tail(query.lines.unwrap_or(DEFAULT_TAIL_LINES));
+    Json(LogsTailResponse { lines })
+}
+
+#[utoipa::path(get, path = "/api/logs/stream", tag = "logs",
+    responses((status = 200, description = "Server-sent events, one log line each", content_type = "text/event-stream", body = String)))]
+pub async fn logs_stream(
+    State(state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.logs.subscribe();
+
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(line) => return Some((Ok(Event::default().data(line)), receiver)),
+                // Slow consumers skip lines rather than disconnecting
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+    .take_until(state.streams.draining());
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+// ============================================================================
+// Eval handlers
+// ============================================================================
+
+/// Run a prompt suite across models and return aggregated standings.
+#[utoipa::path(post, path = "/api/eval", tag = "eval",
+    request_body(content = serde_json::Value, description = "Eval parameters, as for the MCP `eval` tool"),
+    responses(
+        (status = 200, description = "Aggregated standings", body = serde_json::Value),
+        (status = 400, description = "Unknown suite or invalid parameters"),
+        (status = 502, description = "Eval failed")
+    ))]
+pub async fn run_eval(
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<EvalParams>,
+) -> Result<Json<EvalResult>, (StatusCode, Json<serde_json::Value>)> {
+    let valid = params
+        .resolve_prompts(&state.config.read().unwrap().mcp.suites)
+        .and_then(|_| params.validate());
+    if let Err(e) = valid {
+        return Err((StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))));
+    }
+    // Only upstream failures are worth a 502; unknown models are the caller's
+    if params.models.is_some() {
+        let unknown = params.unknown_models(&state.scanner.get_free_models(false).await);
+        if !unknown.is_empty() {
+            let error = format!("Not a free model: {}", unknown.join(", "));
+            return Err((StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": error }))));
+        }
+    }
+
+    let comparator = state.comparator();
+    eval::run_eval_with(&comparator, params, None).await.map(Json).map_err(|e| {
+        (
+            StatusCode::BAD_GATEWAY,
+            Json(serde_json::json!({ "error": e })),
+        )
+    })
+}
+
+// ============================================================================
+// Template handlers
+// ============================================================================
+
+/// An error status with a `{"error": ...}` body.
+type JsonError = (StatusCode, Json<serde_json::Value>);
+
+fn json_error(status: StatusCode, message: impl std::fmt::Display) -> JsonError {
+    (status, Json(serde_json::json!({ "error": message.to_string() })))
+}
+
+fn template_store(state: &AppState) -> Result<&TemplateStore, JsonError> {
+    state.templates.get().ok_or_else(|| {
+        json_error(StatusCode::SERVICE_UNAVAILABLE, "Prompt templates are unavailable")
+    })
+}
+
+fn find_template(store: &TemplateStore, name: &str) -> Result<PromptTemplate, JsonError> {
+    store
+        .get(name)
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| {
+            json_error(StatusCode::NOT_FOUND, format!("Template '{}' not found", name))
+        })
+}
+
+#[utoipa::path(get, path = "/api/templates", tag = "templates",
+    responses((status = 200, body = TemplatesResponse)))]
+pub async fn list_templates(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<TemplatesResponse>, JsonError> {
+    let templates = template_store(&state)?
+        .list()
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(TemplatesResponse { templates }))
+}
+
+#[utoipa::path(post, path = "/api/templates", tag = "templates",
+    request_body = PromptTemplate,
+    responses(
+        (status = 201, body = PromptTemplate),
+        (status = 400, description = "Invalid template name"),
+        (status = 409, description = "A template with this name exists")
+    ))]
+pub async fn create_template(
+    State(state): State<Arc<AppState>>,
+    Json(template): Json<PromptTemplate>,
+) -> Result<(StatusCode, Json<PromptTemplate>), JsonError> {
+    templates::validate_name(&template.name)
+        .map_err(|e| json_error(StatusCode::BAD_REQUEST, e))?;
+    let inserted = template_store(&state)?
+        .insert(&template)
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    if !inserted {
+        return Err(json_error(
+            StatusCode::CONFLICT,
+            format!("Template '{}' already exists", template.name),
+        ));
+    }
+    Ok((StatusCode::CREATED, Json(template)))
+}
+
+#[utoipa::path(get, path = "/api/templates/{name}", tag = "templates",
+    params(("name" = String, Path, description = "Template name")),
+    responses(
+        (status = 200, body = PromptTemplate),
+        (status = 404, description = "No such template")
+    ))]
+pub async fn get_template(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<PromptTemplate>, JsonError> {
+    find_template(template_store(&state)?, &name).map(Json)
+}
+
+#[utoipa::path(put, path = "/api/templates/{name}", tag = "templates",
+    params(("name" = String, Path, description = "Template name")),
+    request_body = UpdateTemplateRequest,
+    responses(
+        (status = 200, body = PromptTemplate),
+        (status = 404, description = "No such template")
+    ))]
+pub async fn update_template(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(update): Json<UpdateTemplateRequest>,
+) -> Result<Json<PromptTemplate>, JsonError> {
+    let template = PromptTemplate {
+        name,
+        description: update.description,
+        system: update.system,
+        prompt: update.prompt,
+    };
+    let updated = template_store(&state)?
+        .update(&template)
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    if !updated {
+        return Err(json_error(
+            StatusCode::NOT_FOUND,
+            format!("Template '{}' not found", template.name),
+        ));
+    }
+    Ok(Json(template))
+}
+
+#[utoipa::path(delete, path = "/api/templates/{name}", tag = "templates",
+    params(("name" = String, Path, description = "Template name")),
+    responses(
+        (status = 200, body = DeleteResponse),
+        (status = 404, description = "No such template")
+    ))]
+pub async fn delete_template(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<DeleteResponse>, JsonError> {
+    let deleted = template_store(&state)?
+        .delete(&name)
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    if !deleted {
+        return Err(json_error(
+            StatusCode::NOT_FOUND,
+            format!("Template '{}' not found", name),
+        ));
+    }
+    Ok(Json(DeleteResponse { deleted }))
+}
+
+/// Preview the messages a template expands to.
+#[utoipa::path(post, path = "/api/templates/{name}/expand", tag = "templates",
+    params(("name" = String, Path, description = "Template name")),
+    request_body = ExpandTemplateRequest,
+    responses(
+        (status = 200, body = ExpandTemplateResponse),
+        (status = 400, description = "Variables are missing"),
+        (status = 404, description = "No such template")
+    ))]
+pub async fn expand_template_handler(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(request): Json<ExpandTemplateRequest>,
+) -> Result<Json<ExpandTemplateResponse>, JsonError> {
+    let template = find_template(template_store(&state)?, &name)?;
+    let expansion = template
+        .expand(&request.variables)
+        .map_err(|e| json_error(StatusCode::BAD_REQUEST, e))?;
+    Ok(Json(ExpandTemplateResponse {
+        messages: template_messages(expansion),
+    }))
+}
+
+// ============================================================================
+// Eval dataset handlers
+// ============================================================================
+
+fn eval_store(state: &AppState) -> Result<&EvalStore, JsonError> {
+    state.evals.get().ok_or_else(|| {
+        json_error(StatusCode::SERVICE_UNAVAILABLE, "Eval datasets are unavailable")
+    })
+}
+
+fn find_dataset(store: &EvalStore, name: &str) -> Result<Dataset, JsonError> {
+    store
+        .get_dataset(name)
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| {
+            json_error(StatusCode::NOT_FOUND, format!("Dataset '{}' not found", name))
+        })
+}
+
+#[utoipa::path(get, path = "/api/evals/datasets", tag = "eval",
+    responses((status = 200, body = DatasetsResponse)))]
+pub async fn list_datasets(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<DatasetsResponse>, JsonError> {
+    let datasets = eval_store(&state)?
+        .list_datasets()
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(DatasetsResponse { datasets }))
+}
+
+#[utoipa::path(post, path = "/api/evals/datasets", tag = "eval",
+    request_body = Dataset,
+    responses(
+        (status = 201, body = Dataset),
+        (status = 400, description = "Invalid name or no prompts"),
+        (status = 409, description = "A dataset with this name exists")
+    ))]
+pub async fn create_dataset(
+    State(state): State<Arc<AppState>>,
+    Json(dataset): Json<Dataset>,
+) -> Result<(StatusCode, Json<Dataset>), JsonError> {
+    dataset
+        .validate()
+        .map_err(|e| json_error(StatusCode::BAD_REQUEST, e))?;
+    let inserted = eval_store(&state)?
+        .insert_dataset(&dataset)
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    if !inserted {
+        return Err(json_error(
+            StatusCode::CONFLICT,
+            format!("Dataset '{}' already exists", dataset.name),
+        ));
+    }
+    Ok((StatusCode::CREATED, Json(dataset)))
+}
+
+#[utoipa::path(get, path = "/api/evals/datasets/{name}", tag = "eval",
+    params(("name" = String, Path, description = "Dataset name")),
+    responses(
+        (status = 200, body = Dataset),
+        (status = 404, description = "No such dataset")
+    ))]
+pub async fn get_dataset(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<Dataset>, JsonError> {
+    find_dataset(eval_store(&state)?, &name).map(Json)
+}
+
+#[utoipa::path(delete, path = "/api/evals/datasets/{name}", tag = "eval",
+    params(("name" = String, Path, description = "Dataset name")),
+    responses(
+        (status = 200, body = DeleteResponse),
+        (status = 404, description = "No such dataset")
+    ))]
+pub async fn delete_dataset(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<DeleteResponse>, JsonError> {
+    let deleted = eval_store(&state)?
+        .delete_dataset(&name)
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    if !deleted {
+        return Err(json_error(
+            StatusCode::NOT_FOUND,
+            format!("Dataset '{}' not found", name),
+        ));
+    }
+    Ok(Json(DeleteResponse { deleted }))
+}
+
+/// Run a dataset across models and record each model's standing.
+#[utoipa::path(post, path = "/api/evals/datasets/{name}/run", tag = "eval",
+    params(("name" = String, Path, description = "Dataset name")),
+    request_body(content = serde_json::Value, description = "Models, judges and weights, as for `/api/eval`"),
+    responses(
+        (status = 200, description = "Aggregated standings", body = serde_json::Value),
+        (status = 404, description = "No such dataset"),
+        (status = 502, description = "Eval failed")
+    ))]
+pub async fn run_dataset_handler(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(params): Json<EvalParams>,
+) -> Result<Json<EvalResult>, JsonError> {
+    let store = eval_store(&state)?;
+    let dataset = find_dataset(store, &name)?;
+    let comparator = state.comparator();
+    evals::run_dataset(store, &comparator, &dataset, params)
+        .await
+        .map(Json)
+        .map_err(|e| json_error(StatusCode::BAD_GATEWAY, e))
+}
+
+/// Recorded dataset scores, oldest first.
+#[utoipa::path(get, path = "/api/evals/history", tag = "eval",
+    params(EvalHistoryQuery),
+    responses((status = 200, body = EvalHistoryResponse)))]
+pub async fn eval_history(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<EvalHistoryQuery>,
+) -> Result<Json<EvalHistoryResponse>, JsonError> {
+    let scores = eval_store(&state)?
+        .history(query.model.as_deref(), query.dataset.as_deref())
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(EvalHistoryResponse { scores }))
+}
+
+/// Per-model win rate, mean judge score and mean TTFB over recent comparisons.
+#[utoipa::path(get, path = "/v1/leaderboard", tag = "eval",
+    params(LeaderboardQuery),
+    responses(
+        (status = 200, body = LeaderboardResponse),
+        (status = 503, description = "The leaderboard is unavailable")
+    ))]
+pub async fn get_leaderboard(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<LeaderboardResponse>, JsonError> {
+    let leaderboard = state.leaderboard.get().ok_or_else(|| {
+        json_error(StatusCode::SERVICE_UNAVAILABLE, "The leaderboard is unavailable")
+    })?;
+    let days = query.days.unwrap_or(leaderboard::DEFAULT_DAYS);
+    let since = chrono::Utc::now() - chrono::Duration::days(days.into());
+    let models = leaderboard
+        .standings(since)
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(LeaderboardResponse { since, models }))
+}
+
+// ============================================================================
+// Gateway API key handlers
+// ============================================================================
+
+fn key_store(state: &AppState) -> Result<&KeyStore, JsonError> {
+    state
+        .keys
+        .get()
+        .ok_or_else(|| json_error(StatusCode::SERVICE_UNAVAILABLE, "API keys are unavailable"))
+}
+
+#[utoipa::path(get, path = "/api/keys", tag = "keys",
+    responses((status = 200, body = KeysResponse)))]
+pub async fn list_keys(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<KeysResponse>, JsonError> {
+    let keys = key_store(&state)?
+        .list()
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(KeysResponse { keys }))
+}
+
+/// Mint a key. The secret is only returned in this response.
+///
+/// Without a gateway key anyone who can reach the gateway could mint
+/// themselves keys, so minting is refused until one is set.
+#[utoipa::path(post, path = "/api/keys", tag = "keys",
+    request_body = NewKey,
+    responses(
+        (status = 201, body = MintedKey),
+        (status = 400, description = "Invalid key name"),
+        (status = 403, description = "No gateway API key is set"),
+        (status = 409, description = "A key with this name exists")
+    ))]
+pub async fn create_key(
+    State(state): State<Arc<AppState>>,
+    Json(new_key): Json<NewKey>,
+) -> Result<(StatusCode, Json<MintedKey>), JsonError> {
+    if state.gateway_key.read().unwrap().is_none() {
+        return Err(json_error(
+            StatusCode::FORBIDDEN,
+            "Set [gateway] api_key before minting keys",
+        ));
+    }
+    virtual_keys::validate_name(&new_key.name)
+        .map_err(|e| json_error(StatusCode::BAD_REQUEST, e))?;
+    let minted = key_store(&state)?
+        .mint(&new_key)
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| {
+            json_error(
+                StatusCode::CONFLICT,
+                format!("Key '{}' already exists", new_key.name),
+            )
+        })?;
+    Ok((StatusCode::CREATED, Json(minted)))
+}
+
+/// Revoke a key; its usage history is kept.
+#[utoipa::path(delete, path = "/api/keys/{name}", tag = "keys",
+    params(("name" = String, Path, description = "Key name")),
+    responses(
+        (status = 200, body = DeleteResponse),
+        (status = 404, description = "No such key")
+    ))]
+pub async fn delete_key(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<DeleteResponse>, JsonError> {
+    let deleted = key_store(&state)?
+        .revoke(&name)
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    if !deleted {
+        return Err(json_error(
+            StatusCode::NOT_FOUND,
+            format!("Key '{}' not found", name),
+        ));
+    }
+    Ok(Json(DeleteResponse { deleted }))
+}
+
+/// Requests and tokens per day for one key.
+#[utoipa::path(get, path = "/api/keys/{name}/usage", tag = "keys",
+    params(("name" = String, Path, description = "Key name"), KeyUsageQuery),
+    responses(
+        (status = 200, body = KeyUsageResponse),
+        (status = 404, description = "No such key")
+    ))]
+pub async fn key_usage(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(query): Query<KeyUsageQuery>,
+) -> Result<Json<KeyUsageResponse>, JsonError> {
+    let store = key_store(&state)?;
+    store
+        .get(&name)
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| json_error(StatusCode::NOT_FOUND, format!("Key '{}' not found", name)))?;
+    let usage = store
+        .usage(&name, query.days.unwrap_or(30))
+        .map_err(|e| json_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(KeyUsageResponse { name, usage }))
+}
+
+// ============================================================================
+// Settings handlers
+// ============================================================================
+
+/// Settings as reported to the UI; keys are only reported as configured.
+pub fn settings_from_config(config: &Config) -> SettingsResponse {
+    SettingsResponse {
+        openrouter_configured: !config.api_keys.openrouter.is_empty(),
+        opencode_zen_configured: !config.api_keys.opencode_zen.is_empty(),
+        start_at_login: config.app.start_at_login,
+        gateway_api_key_configured: config.gateway.api_key.is_some(),
+        gateway_api_key: None,
+    }
+}
+
+/// Apply a settings update; an empty key clears it and a new key replaces
+/// the whole pool. Returns the gateway key when a new one was generated.
+pub fn apply_settings_update(config: &mut Config, req: UpdateSettingsRequest) -> Option<String> {
+    if let Some(key) = req.openrouter_api_key {
+        if key.is_empty() {
+            config.api_keys.openrouter.clear();
+        } else {
+            config.api_keys.openrouter = vec![key];
+        }
+    }
+
+    if let Some(key) = req.opencode_zen_api_key {
+        if key.is_empty() {
+            config.api_keys.opencode_zen.clear();
+        } else {
+            config.api_keys.opencode_zen = vec![key];
+        }
+    }
+
+    if let Some(enabled) = req.start_at_login {
+        config.app.start_at_login = enabled;
+    }
+
+    if let Some(key) = req.gateway_api_key {
+        config.gateway.api_key = (!key.is_empty()).then_some(key);
+    }
+    if req.generate_gateway_api_key {
+        let key = gateway_auth::generate_key();
+        config.gateway.api_key = Some(key.clone());
+        return Some(key);
+    }
+    None
+}
+
+#[utoipa::path(get, path = "/api/settings", tag = "settings",
+    responses((status = 200, body = SettingsResponse)))]
+pub async fn get_settings(State(state): State<Arc<AppState>>) -> Json<SettingsResponse> {
+    Json(settings_from_config(&state.config.read().unwrap()))
+}
+
+#[utoipa::path(put, path = "/api/settings", tag = "settings",
+    request_body = UpdateSettingsRequest,
+    responses((status = 200, body = SettingsResponse)))]
+pub async fn update_settings(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<UpdateSettingsRequest>,
+) -> Result<Json<SettingsResponse>, (StatusCode, Json<serde_json::Value>)> {
+    // Start from the file rather than the live config, so keys given through
+    // the environment aren't written to disk, and refuse to overwrite a file
+    // that no longer parses
+    let mut config = Config::load().map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": format!("Failed to read settings: {}", e) })),
+        )
+    })?;
+    let generated = apply_settings_update(&mut config, req);
+
+    if let Err(e) = config.save() {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": format!("Failed to save settings: {}", e) })),
+        ));
+    }
+    *state.gateway_key.write().unwrap() = config.gateway.api_key.clone();
+    *state.config.write().unwrap() = config.clone().with_env_overrides();
+
+    // A generated key is only ever shown in this response
+    let mut settings = settings_from_config(&config);
+    settings.gateway_api_key = generated;
+    Ok(Json(settings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ab_test::AbSplit;
+    use crate::api::tests::test_state;
+    use crate::api::{create_router, create_router_with_state};
+    use crate::config::SpeechBackendConfig;
+    use crate::http::RetryPolicy;
+    use crate::leaderboard::Leaderboard;
+    use crate::mcp::client::ToolBridge;
+    use crate::mcp::spending::SpendingTracker;
+    use crate::response_cache::ResponseCache;
+    use crate::scanner::FreeModelScanner;
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn eval_without_prompts_is_rejected() {
+        let app = create_router();
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.post("/api/eval").json(&json!({})).await;
+
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert!(body["error"].as_str().unwrap().contains("prompts or suite"));
+    }
+
+    #[tokio::test]
+    async fn eval_with_bad_weights_or_models_is_rejected() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        let state = test_state(&upstream);
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        for params in [
+            json!({"prompts": ["Hi"], "weights": {"speed": -1.0}}),
+            json!({"prompts": ["Hi"], "metrics": []}),
+            json!({"prompts": ["Hi"], "models": ["gpt-9"]}),
+        ] {
+            server
+                .post("/api/eval")
+                .json(&params)
+                .await
+                .assert_status(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn chat_without_models_publishes_event() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", mockito::Matcher::Any)
+            .with_body(r#"{"data": []}"#)
+            .create_async()
+            .await;
+        let state = test_state(&upstream);
+        let mut events = state.events.subscribe();
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let response = server
+            .post("/v1/chat/completions")
+            .json(&json!({
+                "model": "auto",
+                "messages": [{"role": "user", "content": "Hello"}]
+            }))
+            .await;
+
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            events.try_recv().unwrap(),
+            crate::events::GatewayEvent::ModelsUnavailable
+        );
+    }
+
+    #[tokio::test]
+    async fn failing_models_fall_back_to_the_next_free_model() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}, {"name": "mistral"}]}"#)
+            .create_async()
+            .await;
+        let llama = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "llama3"})))
+            .with_status(503)
+            .with_body(r#"{"error": {"message": "overloaded"}}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let mistral = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "mistral"})))
+            .with_body(r#"{"choices": [{"message": {"role": "assistant", "content": "Hi"}}]}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let state = AppState {
+            // Fall back at once instead of retrying llama3
+            retry: RetryPolicy { max_attempts: 1, ..RetryPolicy::default() },
+            ..test_state(&upstream)
+        };
+        state.scanner.get_free_models(false).await;
+        let server = TestServer::new(create_router_with_state(state.clone())).unwrap();
+        let request = |stream: bool| {
+            json!({
+                "model": "llama3",
+                "messages": [{"role": "user", "content": "Hello"}],
+                "stream": stream
+            })
+        };
+
+        let response = server.post("/v1/chat/completions").json(&request(false)).await;
+        response.assert_status_ok();
+        response.assert_header("x-multiai-model-used", "mistral");
+        assert_eq!(response.json::<serde_json::Value>()["choices"][0]["message"]["content"], "Hi");
+
+        let response = server.post("/v1/chat/completions").json(&request(true)).await;
+        response.assert_status_ok();
+        response.assert_header("x-multiai-model-used", "mistral");
+
+        llama.assert_async().await;
+        mistral.assert_async().await;
+        assert_eq!(state.model_stats.score("llama3").unwrap().success_rate, 0.0);
+    }
+
+    #[tokio::test]
+    async fn tool_bridge_requests_fall_back_like_any_other() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}, {"name": "mistral"}]}"#)
+            .create_async()
+            .await;
+        upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "llama3"})))
+            .with_status(429)
+            .with_body(r#"{"error": {"message": "rate limited"}}"#)
+            .create_async()
+            .await;
+        upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "mistral"})))
+            .with_body(r#"{"choices": [{"message": {"role": "assistant", "content": "Hi"}}]}"#)
+            .create_async()
+            .await;
+        let state = AppState {
+            tool_bridge: Some(Arc::new(ToolBridge::new(Vec::new()))),
+            retry: RetryPolicy { max_attempts: 1, ..RetryPolicy::default() },
+            ..test_state(&upstream)
+        };
+        state.scanner.get_free_models(false).await;
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let response = server
+            .post("/v1/chat/completions")
+            .json(&json!({"model": "llama3", "messages": [{"role": "user", "content": "Hello"}]}))
+            .await;
+        response.assert_status_ok();
+        response.assert_header("x-multiai-model-used", "mistral");
+    }
+
+    #[tokio::test]
+    async fn rejected_requests_do_not_fall_back() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}, {"name": "mistral"}]}"#)
+            .create_async()
+            .await;
+        upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "llama3"})))
+            .with_status(500)
+            .with_body(r#"{"error": {"message": "flagged by content_filter"}}"#)
+            .create_async()
+            .await;
+        let mistral = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "mistral"})))
+            .expect(0)
+            .create_async()
+            .await;
+        let state = AppState {
+            retry: RetryPolicy { max_attempts: 1, ..RetryPolicy::default() },
+            ..test_state(&upstream)
+        };
+        state.scanner.get_free_models(false).await;
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        for stream in [false, true] {
+            let response = server
+                .post("/v1/chat/completions")
+                .json(&json!({
+                    "model": "llama3",
+                    "messages": [{"role": "user", "content": "Hello"}],
+                    "stream": stream
+                }))
+                .await;
+            response.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+            response.assert_header("x-multiai-model-used", "llama3");
+        }
+        mistral.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn auto_follows_the_configured_or_requested_strategy() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}, {"name": "mistral"}]}"#)
+            .create_async()
+            .await;
+        let reply = r#"{"choices": [{"message": {"role": "assistant", "content": "Hi"}}]}"#;
+        let llama = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "llama3"})))
+            .with_body(reply)
+            .expect(1)
+            .create_async()
+            .await;
+        let mistral = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "mistral"})))
+            .with_body(reply)
+            .expect(1)
+            .create_async()
+            .await;
+        let state = AppState {
+            routing_strategy: RoutingStrategy::Priority,
+            ..test_state(&upstream)
+        };
+        state.scanner.get_free_models(false).await;
+        state.model_stats.record("llama3", true, std::time::Duration::from_millis(900));
+        state.model_stats.record("mistral", true, std::time::Duration::from_millis(50));
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+        let messages = json!([{"role": "user", "content": "Hello"}]);
+
+        server
+            .post("/v1/chat/completions")
+            .json(&json!({"model": "auto", "messages": messages}))
+            .await
+            .assert_header("x-multiai-model-used", "llama3");
+        server
+            .post("/v1/chat/completions")
+            .json(&json!({"model": "auto", "messages": messages, "routing_strategy": "least_latency"}))
+            .await
+            .assert_header("x-multiai-model-used", "mistral");
+
+        llama.assert_async().await;
+        mistral.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn chat_request_expands_stored_template() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        let completion = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "messages": [
+                    {"role": "system", "content": "You review Rust code."},
+                    {"role": "user", "content": "Review: fn main() {}"}
+                ]
+            })))
+            .with_body(r#"{"choices": [{"message": {"content": "LGTM"}}]}"#)
+            .create_async()
+            .await;
+        let state = AppState {
+            templates: Some(TemplateStore::in_memory().unwrap()).into(),
+            ..test_state(&upstream)
+        };
+        state.scanner.get_free_models(false).await;
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        server
+            .post("/api/templates")
+            .json(&json!({
+                "name": "code-review",
+                "system": "You review {{language}} code.",
+                "prompt": "Review: {{code}}"
+            }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        server
+            .post("/v1/chat/completions")
+            .json(&json!({
+                "model": "llama3",
+                "template": "code-review",
+                "variables": {"language": "Rust", "code": "fn main() {}"}
+            }))
+            .await
+            .assert_status_ok();
+        completion.assert_async().await;
+
+        server
+            .post("/v1/chat/completions")
+            .json(&json!({"model": "llama3", "template": "code-review"}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn ab_test_splits_auto_traffic_and_collects_feedback() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "old"}, {"name": "new"}]}"#)
+            .create_async()
+            .await;
+        upstream
+            .mock("POST", "/v1/chat/completions")
+            .with_body(r#"{"choices": [{"message": {"content": "hi"}}]}"#)
+            .create_async()
+            .await;
+        let state = AppState {
+            ab_split: Some(AbSplit::new(crate::config::AbTestConfig {
+                model_a: "old".to_string(),
+                model_b: "new".to_string(),
+                percent_b: 50,
+            })),
+            ..test_state(&upstream)
+        };
+        state.scanner.get_free_models(false).await;
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+        let request = json!({
+            "model": "auto",
+            "messages": [{"role": "user", "content": "Hello"}]
+        });
+
+        let mut arms = Vec::new();
+        let mut last_id = String::new();
+        for _ in 0..4 {
+            let response = server.post("/v1/chat/completions").json(&request).await;
+            arms.push(response.header("x-multiai-ab-arm").to_str().unwrap().to_string());
+            last_id = response
+                .header("x-multiai-transaction-id")
+                .to_str()
+                .unwrap()
+                .to_string();
+        }
+        assert_eq!(arms, vec!["a", "b", "a", "b"]);
+
+        server
+            .post("/v1/ab/feedback")
+            .json(&json!({"transaction_id": last_id, "positive": true}))
+            .await
+            .assert_json(&json!({"arm": "b"}));
+
+        let report: serde_json::Value = server.get("/v1/ab").await.json();
+        assert_eq!(report["arms"][0]["model"], "old");
+        assert_eq!(report["arms"][0]["requests"], 2);
+        assert_eq!(report["arms"][1]["positive_feedback"], 1);
+    }
+
+    #[tokio::test]
+    async fn ollama_requests_carry_keep_alive() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        let completion = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({"keep_alive": -1})))
+            .with_body(r#"{"choices": [{"message": {"content": "hi"}}]}"#)
+            .create_async()
+            .await;
+        let state = AppState {
+            ollama_keep_alive: Some("-1".to_string()),
+            ..test_state(&upstream)
+        };
+        state.scanner.get_free_models(false).await;
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        server
+            .post("/v1/chat/completions")
+            .json(&json!({"model": "llama3", "messages": [{"role": "user", "content": "hi"}]}))
+            .await
+            .assert_status_ok();
+        completion.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn bench_job_is_started_and_polled() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": []}"#)
+            .create_async()
+            .await;
+        let state = AppState {
+            bench: Some(Benchmarks::in_memory().unwrap()).into(),
+            ..test_state(&upstream)
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        server
+            .post("/v1/bench")
+            .json(&json!({"prompts": ["hi"], "iterations": 0}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        let response = server
+            .post("/v1/bench")
+            .json(&json!({"prompts": ["hi", "bye"], "iterations": 2}))
+            .await;
+        response.assert_status(StatusCode::ACCEPTED);
+        let job: serde_json::Value = response.json();
+        assert_eq!(job["status"], "running");
+        assert_eq!(job["total"], 4);
+
+        let id = job["id"].as_str().unwrap();
+        let polled: serde_json::Value = server.get(&format!("/v1/bench/{}", id)).await.json();
+        assert_eq!(polled["id"], id);
+        server
+            .get("/v1/bench/missing")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn virtual_keys_enforce_limits_and_track_usage() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}, {"name": "mistral"}]}"#)
+            .create_async()
+            .await;
+        upstream
+            .mock("POST", "/v1/chat/completions")
+            .with_body(
+                r#"{"choices": [{"message": {"content": "hi"}}],
+                    "usage": {"prompt_tokens": 7, "completion_tokens": 3}}"#,
+            )
+            .create_async()
+            .await;
+        let gateway_key = Arc::new(std::sync::RwLock::new(Some("multiai-secret".to_string())));
+        let state = AppState {
+            keys: Some(KeyStore::in_memory().unwrap()).into(),
+            require_api_key: true,
+            gateway_key: gateway_key.clone(),
+            ..test_state(&upstream)
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let created = server
+            .post("/api/keys")
+            .add_header("authorization", "Bearer multiai-secret")
+            .json(&json!({"name": "editor", "allowed_models": ["llama3"]}))
+            .await;
+        created.assert_status(StatusCode::CREATED);
+        // Minting needs the gateway key; without it require_api_key does the rejecting
+        *gateway_key.write().unwrap() = None;
+        let secret = created.json::<serde_json::Value>()["key"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let bearer = format!("Bearer {}", secret);
+        let chat = |model: &str| {
+            json!({"model": model, "messages": [{"role": "user", "content": "Hi"}]})
+        };
+
+        server
+            .post("/v1/chat/completions")
+            .json(&chat("llama3"))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        server
+            .post("/v1/chat/completions")
+            .add_header("authorization", "Bearer mai-unknown")
+            .json(&chat("llama3"))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        server
+            .post("/v1/chat/completions")
+            .add_header("authorization", bearer.as_str())
+            .json(&chat("mistral"))
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+        server
+            .post("/v1/chat/completions")
+            .add_header("authorization", bearer.as_str())
+            .json(&chat("llama3"))
+            .await
+            .assert_status_ok();
+
+        let inspect: serde_json::Value = server.get("/v1/inspect").await.json();
+        assert_eq!(inspect["transactions"][3]["virtual_key"], "editor");
+        assert!(inspect["transactions"][0].get("virtual_key").is_none());
+
+        let usage: serde_json::Value = server.get("/api/keys/editor/usage").await.json();
+        // The rejected mistral request counts too, with no tokens
+        assert_eq!(usage["usage"][0]["requests"], 2);
+        assert_eq!(usage["usage"][0]["prompt_tokens"], 7);
+        assert_eq!(usage["usage"][0]["completion_tokens"], 3);
+
+        let keys: serde_json::Value = server.get("/api/keys").await.json();
+        assert_eq!(keys["keys"][0]["name"], "editor");
+        assert!(keys["keys"][0].get("key").is_none());
+        server.delete("/api/keys/editor").await.assert_status_ok();
+        server
+            .post("/v1/chat/completions")
+            .add_header("authorization", bearer.as_str())
+            .json(&chat("llama3"))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn settings_are_reported_from_the_live_config() {
+        let state = AppState {
+            config: Arc::new(std::sync::RwLock::new(Config {
+                api_keys: crate::config::ApiKeysConfig {
+                    openrouter: vec!["sk-or-test".to_string()],
+                    ..Default::default()
+                },
+                ..Config::default()
+            })),
+            ..AppState::default()
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let settings: serde_json::Value = server.get("/api/settings").await.json();
+        assert_eq!(settings["openrouter_configured"], true);
+        assert_eq!(settings["opencode_zen_configured"], false);
+    }
+
+    #[tokio::test]
+    async fn minting_keys_needs_a_gateway_key() {
+        let state = AppState {
+            keys: Some(KeyStore::in_memory().unwrap()).into(),
+            ..AppState::default()
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        server
+            .post("/api/keys")
+            .json(&json!({"name": "editor"}))
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+        let keys: serde_json::Value = server.get("/api/keys").await.json();
+        assert_eq!(keys["keys"], json!([]));
+        // Nor may another site's page reach the key routes
+        server
+            .delete("/api/keys/editor")
+            .add_header("origin", "https://evil.example")
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn hooks_rewrite_block_and_annotate_requests() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        let completion = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_header("x-team", "docs")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "messages": [{"role": "user", "content": "Be brief. Hi"}]
+            })))
+            .with_body(r#"{"choices": [{"message": {"content": "Hello"}}]}"#)
+            .create_async()
+            .await;
+        let hooks = Hooks::compile(
+            r#"
+            fn on_request(ctx) {
+                let prompt = ctx.request.messages[0].content;
+                if prompt.contains("password") { return #{ drop: "no passwords" }; }
+                ctx.request.messages[0].content = "Be brief. " + prompt;
+                #{ request: ctx.request, headers: #{ "x-team": "docs" } }
+            }
+            fn on_response(ctx) {
+                ctx.response.choices[0].message.content += "!";
+                #{ response: ctx.response, annotations: #{ "policy": "v1" } }
+            }
+            "#,
+        )
+        .unwrap();
+        let state = AppState {
+            hooks: Some(hooks),
+            ..test_state(&upstream)
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+        let chat = |content: &str| {
+            json!({"model": "llama3", "messages": [{"role": "user", "content": content}]})
+        };
+
+        let response = server.post("/v1/chat/completions").json(&chat("Hi")).await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["choices"][0]["message"]["content"], "Hello!");
+        completion.assert_async().await;
+
+        let blocked = server
+            .post("/v1/chat/completions")
+            .json(&chat("my password is hunter2"))
+            .await;
+        blocked.assert_status(StatusCode::FORBIDDEN);
+        assert_eq!(blocked.json::<serde_json::Value>()["error"]["type"], "request_blocked");
+
+        let inspect: serde_json::Value = server.get("/v1/inspect").await.json();
+        assert_eq!(inspect["transactions"][0]["annotations"]["policy"], "v1");
+    }
+
+    #[tokio::test]
+    async fn audio_speech_streams_backend_audio() {
+        let mut backend = mockito::Server::new_async().await;
+        let speech = backend
+            .mock("POST", "/v1/audio/speech")
+            .match_body(mockito::Matcher::PartialJson(
+                json!({"model": "kokoro", "input": "Hello there", "voice": "af_bella"}),
+            ))
+            .with_header("content-type", "audio/wav")
+            .with_body("RIFF....WAVE")
+            .create_async()
+            .await;
+        let state = AppState {
+            speech_backends: vec![SpeechBackendConfig {
+                url: format!("{}/v1", backend.url()),
+                api_key: None,
+                models: vec!["kokoro".to_string()],
+                default_model: Some("kokoro".to_string()),
+            }],
+            ..AppState::default()
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let response = server
+            .post("/v1/audio/speech")
+            .json(&json!({"model": "tts-1", "input": "Hello there", "voice": "af_bella"}))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.header("content-type"), "audio/wav");
+        assert_eq!(response.as_bytes().as_ref(), b"RIFF....WAVE");
+        speech.assert_async().await;
+
+        let empty = server
+            .post("/v1/audio/speech")
+            .json(&json!({"input": ""}))
+            .await;
+        empty.assert_status(StatusCode::BAD_REQUEST);
+
+        let inspect: serde_json::Value = server.get("/v1/inspect").await.json();
+        assert_eq!(inspect["transactions"][0]["request"]["url"], "/v1/audio/speech");
+        assert_eq!(inspect["count"], 2);
+    }
+
+    #[tokio::test]
+    async fn embeddings_are_cached_per_model_and_text() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "nomic-embed-text"}]}"#)
+            .create_async()
+            .await;
+        let first = upstream
+            .mock("POST", "/v1/embeddings")
+            .match_body(mockito::Matcher::PartialJson(json!({"input": ["alpha"]})))
+            .with_body(
+                json!({"data": [{"index": 0, "embedding": [0.5, 1.0]}], "usage": {"prompt_tokens": 1}})
+                    .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let second = upstream
+            .mock("POST", "/v1/embeddings")
+            .match_body(mockito::Matcher::PartialJson(json!({"input": ["beta"]})))
+            .with_body(r#"{"data": [{"index": 0, "embedding": [2.0, 3.0]}]}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let state = AppState {
+            embedding_cache: Some(EmbeddingCache::in_memory(100).unwrap()),
+            ..test_state(&upstream)
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let response = server
+            .post("/v1/embeddings")
+            .json(&json!({"model": "nomic-embed-text", "input": "alpha"}))
+            .await;
+        response.assert_status_ok();
+        assert!(response.headers().get("x-multiai-cache").is_none());
+
+        let response = server
+            .post("/v1/embeddings")
+            .json(&json!({"model": "nomic-embed-text", "input": ["alpha", "beta"]}))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.header("x-multiai-cache"), "partial");
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["data"][0]["embedding"], json!([0.5, 1.0]));
+        assert_eq!(body["data"][1]["embedding"], json!([2.0, 3.0]));
+
+        let response = server
+            .post("/v1/embeddings")
+            .json(&json!({"model": "nomic-embed-text", "input": ["beta", "alpha"]}))
+            .await;
+        assert_eq!(response.header("x-multiai-cache"), "hit");
+        first.assert_async().await;
+        second.assert_async().await;
+
+        let health: serde_json::Value = server.get("/health").await.json();
+        assert_eq!(health["embedding_cache"]["hits"], 3);
+        assert_eq!(health["embedding_cache"]["misses"], 2);
+        assert_eq!(health["embedding_cache"]["entries"], 2);
+
+        // `auto` picks the first free embedding model, local ones first
+        let response = server
+            .post("/v1/embeddings")
+            .json(&json!({"model": "auto", "input": "alpha"}))
+            .await;
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["model"], "nomic-embed-text");
+        let models: serde_json::Value = server.get("/v1/embeddings/models").await.json();
+        assert_eq!(models["data"][0]["id"], "nomic-embed-text");
+    }
+
+    #[tokio::test]
+    async fn embeddings_check_keys_after_auto_and_fall_back_on_failures() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "nomic-embed-text"}, {"name": "mxbai-embed-large"}]}"#)
+            .create_async()
+            .await;
+        let overloaded = upstream
+            .mock("POST", "/v1/embeddings")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "nomic-embed-text"})))
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        upstream
+            .mock("POST", "/v1/embeddings")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "mxbai-embed-large"})))
+            .with_body(r#"{"data": [{"index": 0, "embedding": [1.0]}]}"#)
+            .create_async()
+            .await;
+        let keys = KeyStore::in_memory().unwrap();
+        let minted = keys
+            .mint(&NewKey {
+                name: "search".to_string(),
+                limits: virtual_keys::KeyLimits {
+                    allowed_models: vec!["mxbai-*".to_string()],
+                    ..Default::default()
+                },
+            })
+            .unwrap()
+            .unwrap();
+        let state = AppState {
+            keys: Some(keys).into(),
+            gateway_key: Arc::new(std::sync::RwLock::new(Some("multiai-secret".to_string()))),
+            retry: RetryPolicy {
+                max_attempts: 1,
+                ..RetryPolicy::default()
+            },
+            ..test_state(&upstream)
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+        let minted = format!("Bearer {}", minted.key);
+
+        // `auto` resolves to a model the key allows
+        let response = server
+            .post("/v1/embeddings")
+            .add_header("Authorization", minted.clone())
+            .json(&json!({"model": "auto", "input": "alpha"}))
+            .await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["model"], "mxbai-embed-large");
+        server
+            .post("/v1/embeddings")
+            .add_header("Authorization", minted)
+            .json(&json!({"model": "nomic-embed-text", "input": "alpha"}))
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+
+        // An overloaded model falls back to the next embedding model
+        let response = server
+            .post("/v1/embeddings")
+            .add_header("Authorization", "Bearer multiai-secret")
+            .json(&json!({"model": "nomic-embed-text", "input": "beta"}))
+            .await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["model"], "mxbai-embed-large");
+        overloaded.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn leaderboard_lists_recorded_comparisons() {
+        use crate::mcp::{CompareResult, ModelFailure, ScoreWeights};
+
+        let leaderboard = Leaderboard::in_memory().unwrap();
+        leaderboard
+            .record(&CompareResult {
+                prompt: "p".to_string(),
+                compared_at: chrono::Utc::now(),
+                results: vec![],
+                failures: vec![ModelFailure {
+                    model: "flaky".to_string(),
+                    error: "Request timeout".to_string(),
+                    status: None,
+                }],
+                ranking: vec![],
+                weights: ScoreWeights::default(),
+                markdown_summary: String::new(),
+            })
+            .unwrap();
+        let state = AppState {
+            leaderboard: Some(leaderboard).into(),
+            ..AppState::default()
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let response = server.get("/v1/leaderboard?days=1").await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["models"][0]["model"], "flaky");
+        assert_eq!(body["models"][0]["failures"], 1);
+        assert_eq!(body["models"][0]["win_rate"], 0.0);
+
+        let server = TestServer::new(create_router_with_state(AppState::default())).unwrap();
+        server
+            .get("/v1/leaderboard")
+            .await
+            .assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn azure_routes_map_deployments_and_accept_api_key_header() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        let completion = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "llama3"})))
+            .with_body(r#"{"choices": [{"message": {"content": "hi"}}]}"#)
+            .create_async()
+            .await;
+        let state = AppState {
+            keys: Some(KeyStore::in_memory().unwrap()).into(),
+            require_api_key: true,
+            model_aliases: BTreeMap::from([("gpt-4o".to_string(), "llama3".to_string())]),
+            gateway_key: Arc::new(std::sync::RwLock::new(Some("multiai-secret".to_string()))),
+            ..test_state(&upstream)
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+        let secret = server
+            .post("/api/keys")
+            .add_header("authorization", "Bearer multiai-secret")
+            .json(&json!({"name": "azure-sdk"}))
+            .await
+            .json::<serde_json::Value>()["key"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let path = "/openai/deployments/gpt-4o/chat/completions?api-version=2024-10-21";
+        let body = json!({"messages": [{"role": "user", "content": "Hi"}]});
+
+        server
+            .post(path)
+            .json(&body)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        let response = server
+            .post(path)
+            .add_header("api-key", secret.as_str())
+            .json(&body)
+            .await;
+        response.assert_status_ok();
+        let reply: serde_json::Value = response.json();
+        assert_eq!(reply["choices"][0]["message"]["content"], "hi");
+        completion.assert_async().await;
+
+        server
+            .post("/openai/deployments/unknown/chat/completions")
+            .add_header("api-key", secret.as_str())
+            .json(&body)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn gemini_models_are_translated_to_and_from_openai_format() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/v1beta/models")
+            .with_body(
+                json!({"models": [{
+                    "name": "models/gemini-2.0-flash",
+                    "supportedGenerationMethods": ["generateContent"]
+                }]})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let generate = upstream
+            .mock("POST", "/v1beta/models/gemini-2.0-flash:generateContent")
+            .match_header("x-goog-api-key", "AIza-test")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "systemInstruction": {"parts": [{"text": "Be brief"}]},
+                "contents": [{"role": "user", "parts": [{"text": "Hi"}]}]
+            })))
+            .with_body(
+                json!({
+                    "candidates": [{
+                        "content": {"role": "model", "parts": [{"text": "Hello"}]},
+                        "finishReason": "STOP"
+                    }],
+                    "usageMetadata": {"promptTokenCount": 3, "candidatesTokenCount": 1}
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let state = AppState {
+            scanner: FreeModelScanner::new()
+                .with_openrouter_url(&upstream.url())
+                .with_opencode_zen_api_url(&upstream.url())
+                .with_opencode_zen_docs_url(&upstream.url())
+                .with_gemini_url(&format!("{}/v1beta/models", upstream.url()))
+                .with_gemini_api_key("AIza-test"),
+            config: Arc::new(std::sync::RwLock::new(Config {
+                api_keys: crate::config::ApiKeysConfig {
+                    gemini: vec!["AIza-test".to_string()],
+                    ..Default::default()
+                },
+                ..Config::default()
+            })),
+            ..AppState::default()
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let response = server
+            .post("/v1/chat/completions")
+            .json(&json!({
+                "model": "gemini-2.0-flash",
+                "messages": [
+                    {"role": "system", "content": "Be brief"},
+                    {"role": "user", "content": "Hi"}
+                ]
+            }))
+            .await;
+        response.assert_status_ok();
+        let reply: serde_json::Value = response.json();
+        assert_eq!(reply["object"], "chat.completion");
+        assert_eq!(reply["choices"][0]["message"]["content"], "Hello");
+        assert_eq!(reply["choices"][0]["finish_reason"], "stop");
+        assert_eq!(reply["usage"]["total_tokens"], 4);
+        generate.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn sources_report_scan_errors() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/openrouter")
+            .with_status(502)
+            .create_async()
+            .await;
+        upstream
+            .mock("GET", "/zen/docs")
+            .with_body("<table></table>")
+            .create_async()
+            .await;
+        upstream
+            .mock("GET", "/zen/models")
+            .with_body(r#"{"data": []}"#)
+            .create_async()
+            .await;
+        let state = AppState {
+            scanner: FreeModelScanner::new()
+                .with_openrouter_url(&format!("{}/openrouter", upstream.url()))
+                .with_opencode_zen_docs_url(&format!("{}/zen/docs", upstream.url()))
+                .with_opencode_zen_api_url(&format!("{}/zen/models", upstream.url())),
+            ..AppState::default()
+        };
+        let server = TestServer::new(create_router_with_state(state.clone())).unwrap();
+
+        let before: serde_json::Value = server.get("/v1/sources").await.json();
+        assert_eq!(before["scanned"], false);
+
+        state.scanner.get_free_models(true).await;
+        let response = server.get("/v1/sources").await;
+
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["scanned"], true);
+        let openrouter = &body["sources"]["open_router"];
+        assert!(openrouter["last_error"].as_str().unwrap().contains("502"));
+        assert!(openrouter["last_success"].is_null());
+        assert!(body["sources"]["open_code_zen"]["last_success"].is_string());
+        assert!(body["sources"].get("ollama").is_none());
+    }
+
+    #[tokio::test]
+    async fn models_are_filtered_by_capabilities() {
+        let mut upstream = mockito::Server::new_async().await;
+        let free = json!({"prompt": "0", "completion": "0"});
+        upstream
+            .mock("GET", "/openrouter")
+            .with_body(
+                json!({"data": [
+                    {"id": "agent:free", "context_length": 131072, "pricing": free,
+                     "architecture": {"input_modalities": ["text", "image"]},
+                     "supported_parameters": ["tools", "temperature"]},
+                    {"id": "chat:free", "context_length": 8192, "pricing": free,
+                     "architecture": {"input_modalities": ["text"]},
+                     "supported_parameters": ["temperature"]},
+                    {"id": "unknown:free", "pricing": free}
+                ]})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let state = AppState {
+            scanner: FreeModelScanner::new()
+                .with_openrouter_url(&format!("{}/openrouter", upstream.url()))
+                .with_opencode_zen_api_url(&upstream.url())
+                .with_opencode_zen_docs_url(&upstream.url()),
+            ..AppState::default()
+        };
+        state.scanner.get_free_models(false).await;
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+        let ids = |body: serde_json::Value| -> Vec<String> {
+            body["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|m| m["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let all = server.get("/v1/models").await.json();
+        assert_eq!(ids(all).len(), 3);
+        let tools = server.get("/v1/models?tools=true&vision=true").await.json();
+        assert_eq!(ids(tools), ["agent:free"]);
+        let no_tools = server.get("/v1/models?tools=false").await.json();
+        assert_eq!(ids(no_tools), ["chat:free", "unknown:free"]);
+        let long = server.get("/v1/models?min_context=32000").await.json();
+        assert_eq!(ids(long), ["agent:free"]);
+    }
+
+    #[tokio::test]
+    async fn model_detail_includes_source_health_and_recent_latency() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/openrouter")
+            .with_body(
+                json!({"data": [{
+                    "id": "meta-llama/llama-3:free",
+                    "context_length": 8192,
+                    "pricing": {"prompt": "0", "completion": "0"}
+                }]})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let state = AppState {
+            scanner: FreeModelScanner::new()
+                .with_openrouter_url(&format!("{}/openrouter", upstream.url()))
+                .with_opencode_zen_api_url(&upstream.url())
+                .with_opencode_zen_docs_url(&upstream.url()),
+            ..AppState::default()
+        };
+        state.scanner.get_free_models(false).await;
+        for total_ms in [100, 300] {
+            let mut tx = state.inspector.start_transaction(CapturedRequest {
+                method: "POST".to_string(),
+                url: "/v1/chat/completions".to_string(),
+                headers: vec![],
+                body: Some(json!({"model": "auto"})),
+            });
+            tx.routed_model = Some("meta-llama/llama-3:free".to_string());
+            tx.response = Some(CapturedResponse {
+                status: 200,
+                headers: vec![],
+                body: None,
+            });
+            tx.timing.total_ms = total_ms;
+            state.inspector.store(tx);
+        }
+        state
+            .model_stats
+            .record("meta-llama/llama-3:free", true, std::time::Duration::from_millis(100));
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let response = server.get("/v1/models/meta-llama/llama-3:free").await;
+
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["source"], "open_router");
+        assert_eq!(body["context_length"], 8192);
+        assert_eq!(body["requires_api_key"], true);
+        assert_eq!(body["health"]["requests"], 1);
+        assert_eq!(body["recent"]["requests"], 2);
+        assert_eq!(body["recent"]["p50_ms"], 100);
+        assert_eq!(body["recent"]["p95_ms"], 300);
+
+        server
+            .get("/v1/models/not-a-model")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        server.get("/v1/models/grouped").await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn eval_datasets_are_stored_and_scores_listed() {
+        let state = AppState {
+            evals: Some(EvalStore::in_memory().unwrap()).into(),
+            ..AppState::default()
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        server
+            .post("/api/evals/datasets")
+            .json(&json!({
+                "name": "rust-basics",
+                "items": [{"prompt": "Explain Box<T>", "criteria": "Mentions the heap"}]
+            }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        server
+            .post("/api/evals/datasets")
+            .json(&json!({"name": "empty", "items": []}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        let dataset: serde_json::Value = server.get("/api/evals/datasets/rust-basics").await.json();
+        assert_eq!(dataset["items"][0]["criteria"], "Mentions the heap");
+        let history: serde_json::Value = server.get("/api/evals/history?model=llama3").await.json();
+        assert_eq!(history["scores"], json!([]));
+
+        server
+            .post("/api/evals/datasets/missing/run")
+            .json(&json!({}))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        server
+            .delete("/api/evals/datasets/rust-basics")
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn repeated_request_is_served_from_cache() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        let completion = upstream
+            .mock("POST", "/v1/chat/completions")
+            .with_body(r#"{"choices": [{"message": {"content": "hi"}}]}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let cache = ResponseCache::in_memory(crate::config::CacheConfig {
+            enabled: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let state = AppState {
+            cache: Some(cache),
+            ..test_state(&upstream)
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+        let request = json!({
+            "model": "llama3",
+            "messages": [{"role": "user", "content": "Hello"}]
+        });
+
+        let first = server.post("/v1/chat/completions").json(&request).await;
+        let second = server.post("/v1/chat/completions").json(&request).await;
+
+        assert!(first.headers().get("x-multiai-cache").is_none());
+        assert_eq!(second.headers()["x-multiai-cache"], "hit");
+        assert_eq!(first.text(), second.text());
+        completion.assert_async().await;
+
+        let metrics = server.get("/metrics").await.text();
+        assert!(metrics.contains("multiai_cache_hits_total 1"));
+        assert!(metrics.contains("multiai_cache_misses_total 1"));
+
+        let cleared: serde_json::Value = server.delete("/v1/cache").await.json();
+        assert_eq!(cleared["count"], 1);
+    }
+
+    #[tokio::test]
+    async fn clearing_disabled_cache_is_unavailable() {
+        let server = TestServer::new(create_router()).unwrap();
+
+        server
+            .delete("/v1/cache")
+            .await
+            .assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn health_is_degraded_when_a_source_scan_fails() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", mockito::Matcher::Any)
+            .with_status(500)
+            .create_async()
+            .await;
+        let state = test_state(&upstream);
+        state.scanner.get_free_models(true).await;
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let body: serde_json::Value = server.get("/health").await.json();
+
+        assert_eq!(body["status"], "degraded");
+        assert!(body["sources"]["open_router"]["last_error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn health_reports_keys_per_source_and_survives_a_poisoned_chat_db() {
+        let state = AppState {
+            config: Arc::new(std::sync::RwLock::new(Config {
+                api_keys: crate::config::ApiKeysConfig {
+                    groq: vec!["gsk-one".to_string(), "gsk-two".to_string()],
+                    ..Default::default()
+                },
+                ..Config::default()
+            })),
+            ..AppState::default()
+        };
+        let chat = state.chat.clone();
+        let _ = std::thread::spawn(move || {
+            let _db = chat.db.lock().unwrap();
+            panic!("poison the chat database lock");
+        })
+        .join();
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let body: serde_json::Value = server.get("/health").await.json();
+        assert_eq!(body["keys"]["groq"], 2);
+        assert_eq!(body["keys"]["openrouter"], 0);
+        assert!(body["keys"].get("ollama").is_none());
+        assert_eq!(body["status"], "degraded");
+        assert_eq!(body["chat_db"]["ok"], false);
+
+        server
+            .get("/health/ready")
+            .await
+            .assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn spending_endpoint_reports_breakdown() {
+        let tracker =
+            SpendingTracker::in_memory(crate::config::SpendingConfig::default()).unwrap();
+        tracker.record_model_cost("openai/gpt-4o", "openai", 0.5).unwrap();
+        let state = AppState {
+            spending: Some(tracker),
+            ..AppState::default()
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let body: serde_json::Value = server.get("/v1/spending").await.json();
+
+        assert_eq!(body["daily"]["used"], 0.5);
+        assert_eq!(body["daily"]["remaining"], 4.5);
+        assert_eq!(body["top_models"][0]["model"], "openai/gpt-4o");
+        assert_eq!(body["monthly"]["by_provider"][0]["provider"], "openai");
+    }
+
+    #[tokio::test]
+    async fn spending_endpoint_without_tracker_is_unavailable() {
+        let server = TestServer::new(create_router()).unwrap();
+
+        let response = server.get("/v1/spending").await;
+
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn liveness_probe_is_always_ok() {
+        let server = TestServer::new(create_router()).unwrap();
+
+        let response = server.get("/health/live").await;
+
+        response.assert_status_ok();
+        response.assert_json(&json!({"status": "ok"}));
+    }
+
+    #[tokio::test]
+    async fn readiness_waits_for_first_model_scan() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", mockito::Matcher::Any)
+            .with_body(r#"{"data": []}"#)
+            .create_async()
+            .await;
+        let state = test_state(&upstream);
+        let server = TestServer::new(create_router_with_state(state.clone())).unwrap();
+
+        let response = server.get("/health/ready").await;
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["status"], "not_ready");
+
+        state.scanner.get_free_models(true).await;
+
+        let response = server.get("/health/ready").await;
+        response.assert_status_ok();
+        response.assert_json(&json!({"status": "ready"}));
+    }
+
+    #[tokio::test]
+    async fn logs_tail_returns_recorded_lines() {
+        let state = AppState::default();
+        state.logs.push_line("first");
+        state.logs.push_line("second");
+        state.logs.push_line("third");
+        let app = create_router_with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/api/logs/tail?lines=2").await;
+
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["lines"], json!(["second", "third"]));
+    }
 }
@@ -0,0 +1,221 @@
+//! Coalescing of identical in-flight requests.
+//!
+//! Flaky clients often retry while their first request is still running.
+//! With `[gateway] dedup_inflight` enabled, a request identical to one
+//! already in flight waits for that call's result instead of spending
+//! another upstream request from the free-tier quota.
+
+use crate::error::MultiAiError;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Status, forwarded rate-limit headers and body of a non-streaming upstream call.
+pub type UpstreamReply = Result<(u16, Vec<(String, String)>, String), MultiAiError>;
+
+/// Identifies a request for coalescing: the target model plus the exact
+/// body sent upstream.
+pub fn request_key(model: &str, upstream_request: &serde_json::Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    model.hash(&mut hasher);
+    upstream_request.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+type Calls<T> = HashMap<u64, (u64, Shared<BoxFuture<'static, T>>)>;
+
+/// Upstream calls currently running, shared by identical requests.
+pub struct InFlight<T: Clone> {
+    calls: Arc<Mutex<Calls<T>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl<T: Clone> Clone for InFlight<T> {
+    fn clone(&self) -> Self {
+        Self {
+            calls: self.calls.clone(),
+            next_id: self.next_id.clone(),
+        }
+    }
+}
+
+impl<T: Clone> Default for InFlight<T> {
+    fn default() -> Self {
+        Self {
+            calls: Arc::new(Mutex::new(HashMap::new())),
+            next_id: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> InFlight<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `call`, or wait for the result of an identical call already in
+    /// flight. Returns the result and whether it was shared.
+    pub async fn run<F>(&self, key: u64, call: F) -> (T, bool)
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        let (shared, leader) = {
+            let mut calls = self.calls.lock().unwrap();
+            match calls.get(&key) {
+                Some((_, shared)) => (shared.clone(), None),
+                None => {
+                    let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+                    let shared = call.boxed().shared();
+                    calls.insert(key, (id, shared.clone()));
+                    (shared, Some(id))
+                }
+            }
+        };
+
+        // Forget the call once the request that started it finishes or is dropped
+        let _cleanup = leader.map(|id| Cleanup {
+            calls: self.calls.clone(),
+            key,
+            id,
+        });
+        (shared.await, leader.is_none())
+    }
+
+    /// Number of distinct calls in flight.
+    pub fn len(&self) -> usize {
+        self.calls.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+struct Cleanup<T: Clone> {
+    calls: Arc<Mutex<Calls<T>>>,
+    key: u64,
+    id: u64,
+}
+
+impl<T: Clone> Drop for Cleanup<T> {
+    fn drop(&mut self) {
+        let mut calls = self.calls.lock().unwrap();
+        if calls.get(&self.key).is_some_and(|(id, _)| *id == self.id) {
+            calls.remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tests::test_state;
+    use crate::api::{create_router_with_state, AppState};
+    use axum_test::TestServer;
+    use serde_json::json;
+    use std::future::IntoFuture;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    async fn counted_call(calls: Arc<AtomicUsize>, value: u32) -> u32 {
+        calls.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        value
+    }
+
+    #[tokio::test]
+    async fn concurrent_identical_calls_share_one_result() {
+        let inflight = InFlight::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let (first, second) = tokio::join!(
+            inflight.run(1, counted_call(calls.clone(), 7)),
+            inflight.run(1, counted_call(calls.clone(), 8)),
+        );
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(first, (7, false));
+        assert_eq!(second, (7, true));
+        assert!(inflight.is_empty());
+    }
+
+    #[tokio::test]
+    async fn different_keys_and_later_calls_run_separately() {
+        let inflight = InFlight::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let (a, b) = tokio::join!(
+            inflight.run(1, counted_call(calls.clone(), 1)),
+            inflight.run(2, counted_call(calls.clone(), 2)),
+        );
+        let (again, shared) = inflight.run(1, counted_call(calls.clone(), 3)).await;
+
+        assert_eq!((a.0, b.0), (1, 2));
+        assert_eq!((again, shared), (3, false));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn cancelled_leader_leaves_no_entry() {
+        let inflight: InFlight<u32> = InFlight::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let leader = inflight.run(1, counted_call(calls, 1));
+        let _ = tokio::time::timeout(Duration::from_millis(5), leader).await;
+
+        assert!(inflight.is_empty());
+    }
+
+    #[test]
+    fn request_key_depends_on_model_and_body() {
+        let body = json!({"messages": [{"role": "user", "content": "hi"}]});
+        assert_eq!(request_key("a", &body), request_key("a", &body));
+        assert_ne!(request_key("a", &body), request_key("b", &body));
+        assert_ne!(
+            request_key("a", &body),
+            request_key("a", &json!({"messages": []}))
+        );
+    }
+
+    #[tokio::test]
+    async fn identical_concurrent_requests_share_one_upstream_call() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        let completion = upstream
+            .mock("POST", "/v1/chat/completions")
+            .with_chunked_body(|w| {
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                w.write_all(br#"{"choices": [{"message": {"content": "hi"}}]}"#)
+            })
+            .expect(1)
+            .create_async()
+            .await;
+        let state = AppState {
+            inflight: Some(InFlight::new()),
+            ..test_state(&upstream)
+        };
+        state.scanner.get_free_models(false).await;
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+        let request = json!({
+            "model": "llama3",
+            "messages": [{"role": "user", "content": "Hello"}]
+        });
+
+        let (first, second) = tokio::join!(
+            server.post("/v1/chat/completions").json(&request).into_future(),
+            server.post("/v1/chat/completions").json(&request).into_future(),
+        );
+
+        first.assert_status_ok();
+        assert_eq!(first.text(), second.text());
+        completion.assert_async().await;
+    }
+}
@@ -0,0 +1,186 @@
+//! WebSocket push channel for the inspector (`GET /v1/inspect/ws`).
+//!
+//! Pushes each captured transaction that matches the connection's filter,
+//! plus a stats message every second summarizing what matched since the
+//! last one. The initial filter comes from the query string; the client
+//! replaces it at any time by sending a filter object such as
+//! `{"model": "llama", "errors_only": true}`. Slow clients skip
+//! transactions rather than holding up the gateway; skipped counts are
+//! reported in the stats.
+
+use crate::inspector::{CapturedTransaction, TransactionFilter};
+use axum::extract::ws::{Message, WebSocket};
+use serde::Serialize;
+use serde_json::json;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+/// How often stats deltas are sent.
+const STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Matching transactions since the last stats message.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct StatsDelta {
+    requests: u64,
+    errors: u64,
+    total_ms: u64,
+    completion_tokens: u64,
+    dropped: u64,
+}
+
+/// Body of a `"type": "stats"` message.
+#[derive(Debug, Serialize, PartialEq)]
+struct StatsMessage {
+    r#type: &'static str,
+    requests: u64,
+    errors: u64,
+    mean_total_ms: u64,
+    completion_tokens: u64,
+    /// Transactions skipped because the client fell behind.
+    dropped: u64,
+}
+
+impl StatsDelta {
+    fn add(&mut self, tx: &CapturedTransaction) {
+        self.requests += 1;
+        let status = tx.response.as_ref().map(|r| r.status).unwrap_or(0);
+        if status == 0 || status >= 400 {
+            self.errors += 1;
+        }
+        self.total_ms += tx.timing.total_ms;
+        self.completion_tokens += u64::from(tx.timing.completion_tokens.unwrap_or(0));
+    }
+
+    /// The stats message for this interval, if anything happened, and reset.
+    fn take(&mut self) -> Option<StatsMessage> {
+        if self.requests == 0 && self.dropped == 0 {
+            return None;
+        }
+        let delta = std::mem::take(self);
+        Some(StatsMessage {
+            r#type: "stats",
+            requests: delta.requests,
+            errors: delta.errors,
+            mean_total_ms: delta.total_ms.checked_div(delta.requests).unwrap_or(0),
+            completion_tokens: delta.completion_tokens,
+            dropped: delta.dropped,
+        })
+    }
+}
+
+/// Serve one inspector WebSocket until the client disconnects or
+/// `shutdown` resolves, which closes the socket.
+pub async fn serve(
+    mut socket: WebSocket,
+    mut receiver: Receiver<CapturedTransaction>,
+    mut filter: TransactionFilter,
+    shutdown: impl Future<Output = ()>,
+) {
+    let mut ticker = tokio::time::interval(STATS_INTERVAL);
+    let mut stats = StatsDelta::default();
+    tokio::pin!(shutdown);
+
+    loop {
+        let outgoing = tokio::select! {
+            received = receiver.recv() => match received {
+                Ok(tx) if filter.matches(&tx) => {
+                    stats.add(&tx);
+                    json!({ "type": "transaction", "transaction": tx })
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    stats.dropped += skipped;
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    match serde_json::from_str::<TransactionFilter>(&text) {
+                        Ok(new_filter) => {
+                            filter = new_filter;
+                            continue;
+                        }
+                        Err(e) => {
+                            let error = format!("Invalid filter: {}", e);
+                            json!({ "type": "error", "error": error })
+                        }
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+            _ = ticker.tick() => match stats.take() {
+                Some(message) => json!(message),
+                None => continue,
+            },
+            _ = &mut shutdown => {
+                let _ = socket.send(Message::Close(None)).await;
+                return;
+            }
+        };
+        if socket
+            .send(Message::Text(outgoing.to_string().into()))
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inspector::{CapturedRequest, CapturedResponse, TrafficInspector};
+
+    fn transaction(status: Option<u16>, total_ms: u64) -> CapturedTransaction {
+        let mut tx = TrafficInspector::new().start_transaction(CapturedRequest {
+            method: "POST".to_string(),
+            url: "/v1/chat/completions".to_string(),
+            headers: vec![],
+            body: Some(json!({"model": "llama3"})),
+        });
+        tx.response = status.map(|status| CapturedResponse {
+            status,
+            headers: vec![],
+            body: None,
+        });
+        tx.timing.total_ms = total_ms;
+        tx.timing.completion_tokens = Some(10);
+        tx
+    }
+
+    #[test]
+    fn stats_summarize_interval_and_reset() {
+        let mut stats = StatsDelta::default();
+        assert_eq!(stats.take(), None);
+
+        stats.add(&transaction(Some(200), 100));
+        stats.add(&transaction(Some(502), 300));
+        stats.add(&transaction(None, 200));
+        stats.dropped = 4;
+
+        assert_eq!(
+            stats.take(),
+            Some(StatsMessage {
+                r#type: "stats",
+                requests: 3,
+                errors: 2,
+                mean_total_ms: 200,
+                completion_tokens: 30,
+                dropped: 4,
+            })
+        );
+        assert_eq!(stats, StatsDelta::default());
+    }
+
+    #[test]
+    fn client_filters_parse_with_defaults() {
+        let filter: TransactionFilter = serde_json::from_str(r#"{"errors_only": true}"#).unwrap();
+        assert!(filter.errors_only);
+        assert_eq!(filter.model, None);
+        assert!(!filter.matches(&transaction(Some(200), 0)));
+    }
+}
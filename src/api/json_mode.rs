@@ -0,0 +1,343 @@
+//! `response_format` (JSON mode) for every free model.
+//!
+//! Models whose listing reports `response_format` support get it as sent.
+//! Other models are told the format in a system message, and their replies
+//! are checked here: a reply that isn't JSON, or doesn't match the requested
+//! schema, goes back to the model with the problem until it answers properly
+//! or `[routing] json_retries` runs out. Streamed replies can't be checked
+//! before they reach the client, so streams only go to models with native
+//! support.
+//!
+//! Schemas are checked for `type`, `enum`, `const`, `properties`, `required`,
+//! `additionalProperties: false`, `items` and `anyOf`/`oneOf`; other keywords
+//! are ignored.
+
+use serde_json::Value;
+
+/// Check a request's `response_format`.
+pub fn validate_format(format: &Value) -> Result<(), String> {
+    match format["type"].as_str() {
+        Some("text" | "json_object") => Ok(()),
+        Some("json_schema") if format["json_schema"]["schema"].is_object() => Ok(()),
+        Some("json_schema") => {
+            Err("response_format.json_schema.schema must be a JSON Schema object".to_string())
+        }
+        _ => Err(
+            "response_format.type must be \"text\", \"json_object\" or \"json_schema\"".to_string(),
+        ),
+    }
+}
+
+/// Whether replies must be JSON; `{"type": "text"}` asks for nothing.
+pub fn wants_json(format: &Value) -> bool {
+    format["type"] != "text"
+}
+
+/// System message telling a model without `response_format` support what
+/// to answer.
+pub fn instruction(format: &Value) -> String {
+    let schema = &format["json_schema"]["schema"];
+    if schema.is_object() {
+        format!(
+            "Respond only with a JSON value matching this JSON Schema, without code fences \
+             or any other text:\n{}",
+            schema
+        )
+    } else {
+        "Respond only with a JSON object, without code fences or any other text.".to_string()
+    }
+}
+
+/// Message sent back to a model whose reply didn't match the format.
+pub fn correction(problem: &str) -> String {
+    format!(
+        "That reply is not valid: {}. Respond again with only the corrected JSON.",
+        problem
+    )
+}
+
+/// The JSON in a reply, without the code fences models like to add.
+fn strip_fences(content: &str) -> &str {
+    let content = content.trim();
+    content
+        .strip_prefix("```json")
+        .or_else(|| content.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map_or(content, str::trim)
+}
+
+/// Check a reply against the format, returning its JSON text when it matches.
+pub fn check_reply(format: &Value, content: &str) -> Result<String, String> {
+    let json = strip_fences(content);
+    let value: Value =
+        serde_json::from_str(json).map_err(|e| format!("it is not JSON ({})", e))?;
+    let schema = &format["json_schema"]["schema"];
+    if schema.is_object() {
+        check(schema, &value, "$")?;
+    } else if !value.is_object() {
+        return Err("it is not a JSON object".to_string());
+    }
+    Ok(json.to_string())
+}
+
+fn has_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+/// Check `value` against `schema`, naming the first mismatch by its `path`.
+fn check(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    let types: Vec<&str> = match &schema["type"] {
+        Value::String(ty) => vec![ty.as_str()],
+        Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|ty| has_type(value, ty)) {
+        return Err(format!("{} should be {}", path, types.join(" or ")));
+    }
+    if let Some(options) = schema["enum"].as_array() {
+        if !options.contains(value) {
+            return Err(format!("{} should be one of {}", path, schema["enum"]));
+        }
+    }
+    if !schema["const"].is_null() && schema["const"] != *value {
+        return Err(format!("{} should be {}", path, schema["const"]));
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(options) = schema[key].as_array() {
+            if !options.iter().any(|option| check(option, value, path).is_ok()) {
+                return Err(format!("{} matches none of the allowed schemas", path));
+            }
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        for name in schema["required"].as_array().into_iter().flatten() {
+            let name = name.as_str().unwrap_or_default();
+            if !object.contains_key(name) {
+                return Err(format!("{} is missing \"{}\"", path, name));
+            }
+        }
+        let properties = schema["properties"].as_object();
+        for (name, field) in object {
+            match properties.and_then(|p| p.get(name)) {
+                Some(property) => check(property, field, &format!("{}.{}", path, name))?,
+                None if schema["additionalProperties"] == false => {
+                    return Err(format!("{} has unexpected \"{}\"", path, name))
+                }
+                None => {}
+            }
+        }
+    }
+    let items = schema.get("items").filter(|items| items.is_object());
+    if let Some((items, array)) = items.zip(value.as_array()) {
+        for (i, item) in array.iter().enumerate() {
+            check(items, item, &format!("{}[{}]", path, i))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tests::test_state;
+    use crate::api::{create_router_with_state, AppState};
+    use crate::http::RetryPolicy;
+    use crate::mcp::client::ToolBridge;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn person_format() -> Value {
+        json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "person",
+                "schema": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "age": {"type": "integer"},
+                        "tags": {"type": "array", "items": {"enum": ["a", "b"]}}
+                    },
+                    "required": ["name", "age"],
+                    "additionalProperties": false
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn formats_are_validated() {
+        assert!(validate_format(&json!({"type": "json_object"})).is_ok());
+        assert!(validate_format(&person_format()).is_ok());
+        assert!(validate_format(&json!({"type": "json_schema", "json_schema": {"name": "x"}})).is_err());
+        assert!(validate_format(&json!({"type": "xml"})).is_err());
+        assert!(!wants_json(&json!({"type": "text"})));
+    }
+
+    #[test]
+    fn replies_are_checked_against_the_schema() {
+        let format = person_format();
+
+        assert_eq!(
+            check_reply(&format, "```json\n{\"name\": \"Ada\", \"age\": 36}\n```"),
+            Ok("{\"name\": \"Ada\", \"age\": 36}".to_string())
+        );
+        let problems = [
+            ("Sure! Here it is", "not JSON"),
+            (r#"{"name": "Ada"}"#, "missing \"age\""),
+            (r#"{"name": "Ada", "age": 36.5}"#, "$.age should be integer"),
+            (r#"{"name": "Ada", "age": 36, "tags": ["c"]}"#, "$.tags[0] should be one of"),
+            (r#"{"name": "Ada", "age": 36, "email": ""}"#, "unexpected \"email\""),
+        ];
+        for (reply, problem) in problems {
+            let error = check_reply(&format, reply).unwrap_err();
+            assert!(error.contains(problem), "{}: {}", reply, error);
+        }
+    }
+
+    #[test]
+    fn json_object_mode_needs_an_object() {
+        let format = json!({"type": "json_object"});
+        assert!(check_reply(&format, r#"{"ok": true}"#).is_ok());
+        assert!(check_reply(&format, "[1, 2]").is_err());
+    }
+
+    #[tokio::test]
+    async fn response_format_is_enforced_for_models_without_support() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        let retry = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::Regex("not valid".to_string()))
+            .with_body(json!({"choices": [{"message": {"content": "```json\n{\"name\": \"Ada\"}\n```"}}]}).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let first = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::Regex("Respond only with a JSON".to_string()))
+            .with_body(r#"{"choices": [{"message": {"content": "Sure! Her name is Ada."}}]}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let state = AppState {
+            // Replies the tool bridge handles are enforced too
+            tool_bridge: Some(Arc::new(ToolBridge::new(Vec::new()))),
+            ..test_state(&upstream)
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+        let format = json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "person",
+                "schema": {"type": "object", "properties": {"name": {"type": "string"}}, "required": ["name"]}
+            }
+        });
+
+        let response = server
+            .post("/v1/chat/completions")
+            .json(&json!({
+                "model": "llama3",
+                "messages": [{"role": "user", "content": "Who wrote the first program?"}],
+                "response_format": format
+            }))
+            .await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["choices"][0]["message"]["content"], "{\"name\": \"Ada\"}");
+        first.assert_async().await;
+        retry.assert_async().await;
+
+        server
+            .post("/v1/chat/completions")
+            .json(&json!({"model": "llama3", "messages": [], "response_format": {"type": "yaml"}}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        // Streamed replies can't be checked, so llama3 can't stream them
+        let response = server
+            .post("/v1/chat/completions")
+            .json(&json!({
+                "model": "llama3",
+                "messages": [{"role": "user", "content": "Who wrote the first program?"}],
+                "response_format": format,
+                "stream": true
+            }))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert!(body["error"]["message"].as_str().unwrap().contains("when streaming"));
+    }
+
+    #[tokio::test]
+    async fn reprompts_are_retried_on_transient_failures() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        // Mocks with hits left are matched first, so the re-prompt meets a 503 once
+        let unavailable = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::Regex("not valid".to_string()))
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        let retry = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::Regex("not valid".to_string()))
+            .with_body(json!({"choices": [{"message": {"content": "{\"ok\": true}"}}]}).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::Regex("Respond only with a JSON".to_string()))
+            .with_body(r#"{"choices": [{"message": {"content": "ok"}}]}"#)
+            .create_async()
+            .await;
+        let state = AppState {
+            retry: RetryPolicy {
+                max_attempts: 2,
+                base_delay: std::time::Duration::from_millis(1),
+                max_delay: std::time::Duration::from_secs(1),
+            },
+            ..test_state(&upstream)
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let response = server
+            .post("/v1/chat/completions")
+            .json(&json!({
+                "model": "llama3",
+                "messages": [{"role": "user", "content": "Are you ok?"}],
+                "response_format": {"type": "json_object"}
+            }))
+            .await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["choices"][0]["message"]["content"], "{\"ok\": true}");
+        unavailable.assert_async().await;
+        retry.assert_async().await;
+    }
+}
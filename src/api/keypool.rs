@@ -0,0 +1,137 @@
+//! Rotation through a provider's API keys.
+//!
+//! `[api_keys]` entries may list several keys, e.g.
+//! `openrouter = ["k1", "k2"]`, to spread free-tier quota. Each request
+//! takes the next key of its source's pool. A key the provider answers 429
+//! for is benched for the `retry-after` delay, and a key it rejects with
+//! 401/403 for an hour, so requests go to the pool's other keys meanwhile.
+
+use crate::scanner::Source;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Bench after a 429 without a usable `retry-after`.
+const DEFAULT_RATE_LIMIT_BENCH: Duration = Duration::from_secs(60);
+
+/// Upper bound on a 429 bench, whatever the upstream asks for.
+const MAX_RATE_LIMIT_BENCH: Duration = Duration::from_secs(15 * 60);
+
+/// Bench after the provider rejects a key.
+const REJECTED_BENCH: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Default)]
+struct PoolState {
+    /// Next position in each source's pool.
+    turns: HashMap<Source, usize>,
+    /// Benched keys and when they may be used again.
+    benched: HashMap<String, Instant>,
+}
+
+/// Per-source key rotation, shared by clones.
+#[derive(Clone, Default)]
+pub struct KeyPool {
+    state: Arc<Mutex<PoolState>>,
+}
+
+impl KeyPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Key for the next request to `source`, rotating through `keys` and
+    /// skipping benched ones. When every key is benched, the one whose
+    /// bench ends first is used rather than failing the request.
+    pub fn pick(&self, source: Source, keys: &[String]) -> Option<String> {
+        if keys.is_empty() {
+            return None;
+        }
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state.benched.retain(|_, until| *until > now);
+
+        let turn = state.turns.entry(source).or_default();
+        let start = *turn % keys.len();
+        *turn = start + 1;
+
+        let rotated = || (0..keys.len()).map(|i| &keys[(start + i) % keys.len()]);
+        rotated()
+            .find(|key| !state.benched.contains_key(*key))
+            .or_else(|| rotated().min_by_key(|key| state.benched[*key]))
+            .cloned()
+    }
+
+    /// Bench `key` if its provider answered `status` to it: 429 for
+    /// `retry_after` (a minute when absent), 401/403 for an hour.
+    pub fn report(&self, key: &str, status: u16, retry_after: Option<Duration>) {
+        let bench = match status {
+            429 => retry_after
+                .unwrap_or(DEFAULT_RATE_LIMIT_BENCH)
+                .min(MAX_RATE_LIMIT_BENCH),
+            401 | 403 => REJECTED_BENCH,
+            _ => return,
+        };
+        self.state
+            .lock()
+            .unwrap()
+            .benched
+            .insert(key.to_string(), Instant::now() + bench);
+    }
+
+    /// Time left on `key`'s bench, if it is benched.
+    pub fn benched(&self, key: &str) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        state
+            .benched
+            .get(key)?
+            .checked_duration_since(Instant::now())
+            .filter(|left| !left.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn rotates_through_keys_per_source() {
+        let pool = KeyPool::new();
+        let pool_keys = keys(&["k1", "k2"]);
+
+        assert_eq!(pool.pick(Source::OpenRouter, &pool_keys).as_deref(), Some("k1"));
+        assert_eq!(pool.pick(Source::OpenRouter, &pool_keys).as_deref(), Some("k2"));
+        assert_eq!(pool.pick(Source::OpenRouter, &pool_keys).as_deref(), Some("k1"));
+        assert_eq!(pool.pick(Source::Groq, &pool_keys).as_deref(), Some("k1"));
+        assert_eq!(pool.pick(Source::Groq, &[]), None);
+    }
+
+    #[test]
+    fn skips_benched_keys_until_all_are_benched() {
+        let pool = KeyPool::new();
+        let pool_keys = keys(&["k1", "k2"]);
+
+        pool.report("k1", 429, Some(Duration::from_secs(30)));
+        assert!(pool.benched("k1").unwrap() <= Duration::from_secs(30));
+        assert_eq!(pool.pick(Source::OpenRouter, &pool_keys).as_deref(), Some("k2"));
+        assert_eq!(pool.pick(Source::OpenRouter, &pool_keys).as_deref(), Some("k2"));
+
+        // With both benched, the one back soonest is used
+        pool.report("k2", 401, None);
+        assert_eq!(pool.pick(Source::OpenRouter, &pool_keys).as_deref(), Some("k1"));
+    }
+
+    #[test]
+    fn only_rejections_and_rate_limits_bench_a_key() {
+        let pool = KeyPool::new();
+
+        pool.report("k1", 500, None);
+        pool.report("k2", 429, Some(Duration::from_secs(24 * 60 * 60)));
+
+        assert_eq!(pool.benched("k1"), None);
+        assert!(pool.benched("k2").unwrap() <= MAX_RATE_LIMIT_BENCH);
+    }
+}
@@ -0,0 +1,222 @@
+//! Conversation memory for the OpenAI route.
+//!
+//! With `[gateway] conversation_memory` enabled, a completion request that
+//! carries `X-MultiAI-Chat-Id` is sent upstream with that chat's stored
+//! history in front of its messages, and the new user turn and the reply are
+//! saved to `ChatDb`. Clients send only the new turn; the chat shows up in
+//! the web UI like any other.
+
+use super::types::ChatMessage;
+use crate::chat::MessageRole;
+use crate::chat_api::ChatState;
+use crate::error::MultiAiError;
+use axum::http::HeaderMap;
+
+/// Request header naming the chat to remember the conversation in.
+pub const CHAT_ID_HEADER: &str = "x-multiai-chat-id";
+
+/// Chat ID from the request headers, if present and non-empty.
+pub fn chat_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(CHAT_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(String::from)
+}
+
+/// A new turn waiting for its reply before it is saved.
+#[derive(Debug, Clone)]
+pub struct Conversation {
+    pub chat_id: String,
+    turn: Vec<String>,
+}
+
+/// Put the chat's history between the request's system messages and its new
+/// messages, creating the chat on first use.
+pub fn recall(
+    chat: &ChatState,
+    chat_id: &str,
+    messages: Vec<ChatMessage>,
+) -> Result<(Vec<ChatMessage>, Conversation), MultiAiError> {
+    let db = chat
+        .db
+        .lock()
+        .map_err(|e| MultiAiError::Internal(format!("Database lock error: {}", e)))?;
+    let stored = match db.get_chat(chat_id).map_err(db_error)? {
+        Some(_) => db.get_messages(chat_id).map_err(db_error)?,
+        None => {
+            db.create_chat(chat_id, &title_for(&messages))
+                .map_err(db_error)?;
+            Vec::new()
+        }
+    };
+
+    let (system, new): (Vec<_>, Vec<_>) = messages.into_iter().partition(|m| m.role == "system");
+    let turn = new
+        .iter()
+        .filter(|m| m.role == "user")
+        .map(|m| m.content.clone())
+        .collect();
+    let history = stored.into_iter().map(|m| ChatMessage {
+        role: m.role.to_string(),
+        content: m.content,
+        ..ChatMessage::default()
+    });
+    let messages = system.into_iter().chain(history).chain(new).collect();
+
+    Ok((
+        messages,
+        Conversation {
+            chat_id: chat_id.to_string(),
+            turn,
+        },
+    ))
+}
+
+impl Conversation {
+    /// Save the turn's user messages followed by the assistant's reply.
+    pub fn save(&self, chat: &ChatState, reply: &str) {
+        let Ok(db) = chat.db.lock() else {
+            return;
+        };
+        let messages = self
+            .turn
+            .iter()
+            .map(|content| (MessageRole::User, content.as_str()))
+            .chain(std::iter::once((MessageRole::Assistant, reply)));
+        for (role, content) in messages {
+            let id = uuid::Uuid::new_v4().to_string();
+            if let Err(e) = db.add_message(&id, &self.chat_id, role, content) {
+                tracing::warn!("Failed to save message to chat {}: {}", self.chat_id, e);
+                return;
+            }
+        }
+    }
+}
+
+/// Text of the assistant's reply in a chat completion response.
+pub fn reply_content(body: &serde_json::Value) -> Option<&str> {
+    body["choices"][0]["message"]["content"].as_str()
+}
+
+fn title_for(messages: &[ChatMessage]) -> String {
+    messages
+        .iter()
+        .find(|m| m.role == "user")
+        .map(|m| m.content.chars().take(50).collect())
+        .unwrap_or_else(|| "API conversation".to_string())
+}
+
+fn db_error(e: rusqlite::Error) -> MultiAiError {
+    MultiAiError::Internal(format!("Chat database error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tests::test_state;
+    use crate::api::{create_router_with_state, AppState};
+    use crate::chat::ChatDb;
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    fn message(role: &str, content: &str) -> ChatMessage {
+        ChatMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+            ..ChatMessage::default()
+        }
+    }
+
+    #[test]
+    fn history_goes_between_system_prompt_and_new_turn() {
+        let chat = ChatState::new(ChatDb::in_memory().unwrap());
+        let (_, first) = recall(&chat, "c1", vec![message("user", "hi")]).unwrap();
+        first.save(&chat, "hello!");
+
+        let (messages, _) = recall(
+            &chat,
+            "c1",
+            vec![message("system", "be brief"), message("user", "again")],
+        )
+        .unwrap();
+
+        let roles: Vec<(&str, &str)> = messages
+            .iter()
+            .map(|m| (m.role.as_str(), m.content.as_str()))
+            .collect();
+        assert_eq!(
+            roles,
+            vec![
+                ("system", "be brief"),
+                ("user", "hi"),
+                ("assistant", "hello!"),
+                ("user", "again"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_chat_id_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(chat_id(&headers), None);
+        headers.insert(CHAT_ID_HEADER, " abc ".parse().unwrap());
+        assert_eq!(chat_id(&headers), Some("abc".to_string()));
+    }
+
+    #[tokio::test]
+    async fn chat_id_header_carries_conversation_history() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        let first = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "messages": [{"role": "user", "content": "My name is Ada"}]
+            })))
+            .with_body(r#"{"choices": [{"message": {"role": "assistant", "content": "Hi Ada"}}]}"#)
+            .create_async()
+            .await;
+        let second = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "messages": [
+                    {"role": "user", "content": "My name is Ada"},
+                    {"role": "assistant", "content": "Hi Ada"},
+                    {"role": "user", "content": "What is my name?"}
+                ]
+            })))
+            .with_body(r#"{"choices": [{"message": {"role": "assistant", "content": "Ada"}}]}"#)
+            .create_async()
+            .await;
+        let state = AppState {
+            conversation_memory: true,
+            ..test_state(&upstream)
+        };
+        state.scanner.get_free_models(false).await;
+        let chat = state.chat.clone();
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        for content in ["My name is Ada", "What is my name?"] {
+            server
+                .post("/v1/chat/completions")
+                .add_header("x-multiai-chat-id", "api-chat")
+                .json(&json!({
+                    "model": "llama3",
+                    "messages": [{"role": "user", "content": content}]
+                }))
+                .await
+                .assert_status_ok();
+        }
+
+        first.assert_async().await;
+        second.assert_async().await;
+        let stored = chat.db.lock().unwrap().get_messages("api-chat").unwrap();
+        assert_eq!(stored.len(), 4);
+        assert_eq!(stored[3].content, "Ada");
+    }
+}
@@ -1,34 +1,117 @@
 //! OpenAI-compatible API for free LLM routing.
 //!
 //! Endpoints:
-//! - GET /health - Health check
+//! - GET /health - Health check with per-component status
+//! - GET /health/live - Liveness probe
+//! - GET /health/ready - Readiness probe (first model scan done, chat DB open)
 //! - GET /v1/models - List free models
 //! - POST /v1/chat/completions - Chat completions
+//! - POST /openai/deployments/{deployment}/chat/completions - Azure OpenAI-shaped chat completions
+//! - POST /v1/embeddings - Embeddings from free Ollama and OpenRouter models, cached per model and text
+//! - GET /v1/embeddings/models - Free embedding models
+//! - POST /v1/audio/speech - Text to speech via the configured TTS servers
 //! - GET /v1/inspect - Get captured transactions
 //! - DELETE /v1/inspect - Clear captured transactions
-
+//! - GET /v1/inspect/stream - Live captured transactions (SSE)
+//! - GET /v1/inspect/ws - Filtered transactions and stats deltas (WebSocket)
+//! - GET /api/logs/tail - Recent formatted log lines
+//! - GET /api/logs/stream - Live log lines (SSE)
+//! - DELETE /v1/cache - Clear the response cache
+//! - GET /metrics - Prometheus metrics (uptime, response and embedding cache hits/misses)
+//! - GET /v1/spending - Judge spending totals, budget and breakdown
+//! - GET /v1/ab - Per-arm stats for the A/B test
+//! - POST /v1/ab/feedback - Rate an A/B-routed completion
+//! - GET/POST /v1/bench - List benchmark jobs or start one
+//! - GET /v1/bench/{id} - Progress and results of a benchmark job
+//! - GET /v1/leaderboard - Win rate, judge score and TTFB per model from past comparisons
+//! - POST /api/eval - Run a prompt suite across models
+//! - GET/POST /api/evals/datasets - List or create eval datasets
+//! - GET/DELETE /api/evals/datasets/{name} - Read or delete a dataset
+//! - POST /api/evals/datasets/{name}/run - Run a dataset and record its scores
+//! - GET /api/evals/history - Recorded scores per model
+//! - GET/POST /api/templates - List or create prompt templates
+//! - GET/PUT/DELETE /api/templates/{name} - Read, replace or delete a template
+//! - POST /api/templates/{name}/expand - Preview a template's messages
+//! - GET/POST /api/keys - List or mint gateway API keys
+//! - DELETE /api/keys/{name} - Revoke a key
+//! - GET /api/keys/{name}/usage - Daily requests and tokens for a key
+//! - GET /api/tags - Free models in Ollama's format
+//! - POST /api/chat - Ollama-native chat, routed to free models
+//! - POST /api/generate - Ollama-native completion, routed to free models
+//! - GET /openapi.json - OpenAPI document for the gateway and chat endpoints
+//! - GET /docs - Swagger UI
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
+mod client_limit;
+mod gateway_auth;
 mod handlers;
+mod inflight;
+mod inspect_ws;
+mod json_mode;
+mod keypool;
+mod memory;
+mod ollama_compat;
+mod openapi;
+mod ratelimit;
+mod shutdown;
+mod tools;
 mod types;
+mod usage;
 
 use axum::{
     http::{header, StatusCode},
+    middleware,
     response::IntoResponse,
     routing::{delete, get, post, put},
     Router,
 };
 use rust_embed::Embed;
-use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
-
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::ab_test::AbSplit;
+use crate::bench::Benchmarks;
 use crate::chat::ChatDb;
 use crate::chat_api::{create_chat_router, ChatState};
-use crate::inspector::TrafficInspector;
-use crate::scanner::FreeModelScanner;
+use crate::config::{AlertConfig, Config, ProvidersConfig, RoutingConfig, SpeechBackendConfig};
+use crate::embedding_cache::EmbeddingCache;
+use crate::evals::EvalStore;
+use crate::events::EventBus;
+use crate::hooks::Hooks;
+use crate::http::{create_shared_client, create_shared_client_with, RetryPolicy};
+use crate::inspector::{CapturedTransaction, TrafficInspector};
+use crate::leaderboard::Leaderboard;
+use crate::logger::{AlertHook, LogBuffer, SyslogSink};
+use crate::mcp::client::ToolBridge;
+use crate::mcp::spending::SpendingTracker;
+use crate::mcp::ModelComparator;
+use crate::response_cache::ResponseCache;
+use crate::routing::{ModelStats, RoutingStrategy};
+use crate::scanner::{FreeModelScanner, SourceRegistry};
+use crate::scheduler::Scheduler;
+use crate::storage::LazyStore;
+use crate::telemetry::Telemetry;
+use crate::templates::TemplateStore;
+use crate::traffic_log::TrafficLogWriter;
+use crate::transcript::TranscriptWriter;
+use crate::virtual_keys::KeyStore;
+use usage::token_usage;
 
 // Re-export commonly used types
 pub use handlers::{
-    build_upstream_url, find_target_model, get_api_key_for_model, normalize_model_name,
+    apply_settings_update, build_upstream_url, find_target_model, normalize_model_name,
+    settings_from_config,
 };
+pub use client_limit::ClientLimits;
+pub use inflight::{InFlight, UpstreamReply};
+pub use keypool::KeyPool;
+pub use shutdown::{serve_with_grace, ActiveStreams, StreamGuard};
 pub use types::*;
 
 #[derive(Embed)]
@@ -39,29 +122,316 @@ struct StaticAssets;
 #[derive(Clone)]
 pub struct AppState {
     pub scanner: FreeModelScanner,
+    /// Model sources the scanner scans; register a `ModelSource` here to
+    /// add a provider.
+    pub sources: SourceRegistry,
     pub inspector: TrafficInspector,
     pub chat: Arc<ChatState>,
+    pub logs: LogBuffer,
+    pub syslog: Option<SyslogSink>,
+    pub alerts: Option<AlertHook>,
+    /// Daily on-disk traffic logs.
+    pub traffic_log: Option<TrafficLogWriter>,
+    /// External MCP tools offered to models in non-streaming completions.
+    pub tool_bridge: Option<Arc<ToolBridge>>,
+    /// Events for the app shell (rejected keys, no models, spending).
+    pub events: EventBus,
+    pub started_at: Instant,
+    /// Streaming completions still being sent, drained on shutdown.
+    pub streams: ActiveStreams,
+    /// Pooled client for upstream requests, shared with the scanner.
+    pub http: reqwest::Client,
+    /// Judge spending ledger, if its database could be opened.
+    pub spending: Option<SpendingTracker>,
+    /// Rolling per-model success and latency, used to rank `auto` candidates.
+    pub model_stats: ModelStats,
+    /// Rotation through each provider's API keys, with benched keys skipped.
+    pub key_pool: KeyPool,
+    /// On-disk cache of non-streaming responses, when enabled.
+    pub cache: Option<ResponseCache>,
+    /// Identical non-streaming requests in flight, when deduplication is on.
+    pub inflight: Option<InFlight<UpstreamReply>>,
+    /// Add an estimated `usage` chunk to streams whose upstream sends none.
+    pub synthesize_stream_usage: bool,
+    /// Remember conversations for requests carrying `X-MultiAI-Chat-Id`.
+    pub conversation_memory: bool,
+    /// Directory whose files override the embedded UI assets.
+    pub static_dir: Option<PathBuf>,
+    /// Request counters for anonymous usage reports, when telemetry is on.
+    pub telemetry: Option<Telemetry>,
+    /// Named prompt templates, their database opened on first use.
+    pub templates: LazyStore<TemplateStore>,
+    /// A/B split of `auto` traffic, when one is configured.
+    pub ab_split: Option<AbSplit>,
+    /// Eval datasets and score history, opened on first use.
+    pub evals: LazyStore<EvalStore>,
+    /// Benchmark jobs, opened on first use.
+    pub bench: LazyStore<Benchmarks>,
+    /// `keep_alive` sent with requests to Ollama models.
+    pub ollama_keep_alive: Option<String>,
+    /// Attribution and other per-provider headers for upstream requests.
+    pub providers: ProvidersConfig,
+    /// Model names clients may send in place of model IDs.
+    pub model_aliases: BTreeMap<String, String>,
+    /// How `auto` picks a model unless the request says otherwise.
+    pub routing_strategy: RoutingStrategy,
+    /// Other free models tried when the chosen one fails.
+    pub max_fallbacks: usize,
+    /// Re-prompts for replies that don't match a locally enforced `response_format`.
+    pub json_retries: u32,
+    /// Retries of upstream requests that fail to connect or answer 429/5xx.
+    pub retry: RetryPolicy,
+    /// Status of the recurring jobs started from `[scheduler]`.
+    pub scheduler: Scheduler,
+    /// Gateway API keys minted through `/api/keys`, opened on first use.
+    pub keys: LazyStore<KeyStore>,
+    /// Reject chat completions without a gateway API key.
+    pub require_api_key: bool,
+    /// Per-client requests and tokens per minute, when limited.
+    pub client_limits: Option<ClientLimits>,
+    /// Bearer token every `/v1` and `/api` request must carry, when set.
+    /// Shared so a key generated through `/api/settings` applies at once.
+    pub gateway_key: Arc<RwLock<Option<String>>>,
+    /// Scripted `on_request`/`on_response` hooks from `[hooks]`.
+    pub hooks: Option<Hooks>,
+    /// Text-to-speech servers from `[[audio.speech]]`.
+    pub speech_backends: Vec<SpeechBackendConfig>,
+    /// On-disk cache of embedding vectors, when enabled.
+    pub embedding_cache: Option<EmbeddingCache>,
+    /// Per-model history of comparisons, for `/v1/leaderboard`, opened on
+    /// first use.
+    pub leaderboard: LazyStore<Leaderboard>,
+    /// The loaded config, for provider keys looked up per request. Shared
+    /// so keys saved through `/api/settings` apply at once.
+    pub config: Arc<RwLock<Config>>,
 }
 
 impl AppState {
     /// Create AppState with Ollama integration
     pub fn with_ollama(ollama_url: &str) -> Self {
-        let chat_db = ChatDb::in_memory().expect("Failed to create chat database");
+        let defaults = Self::default();
+        let scanner = FreeModelScanner::new()
+            .with_client(defaults.http.clone())
+            .with_ollama_url(ollama_url);
         Self {
-            scanner: FreeModelScanner::new().with_ollama_url(ollama_url),
-            inspector: TrafficInspector::new(),
-            chat: Arc::new(ChatState::new(chat_db)),
+            sources: scanner.registry().clone(),
+            scanner,
+            ..defaults
+        }
+    }
+
+    /// Create AppState with settings from the loaded config applied. Fails
+    /// when the chat database can't be opened.
+    pub fn from_config(config: &Config) -> rusqlite::Result<Self> {
+        let chat_db = if config.gateway.persist_chats {
+            ChatDb::open_default()?
+        } else {
+            ChatDb::in_memory()?
+        };
+        let chat = ChatState::new(
+            chat_db.with_transcripts(TranscriptWriter::from_config(&config.logging)),
+        );
+
+        let syslog = config.logging.syslog.then(|| SyslogSink::new("multiai"));
+        let traffic_log = config.logging.enabled.then(|| {
+            let writer = TrafficLogWriter::new(
+                config.logging.folder.join("traffic"),
+                config.logging.format.clone(),
+            );
+            if let Err(e) = writer.prune(config.logging.retention_days) {
+                tracing::warn!("Failed to prune traffic logs: {}", e);
+            }
+            writer
+        });
+        let cache = config.cache.enabled.then(|| {
+            ResponseCache::open_default(config.cache.clone())
+                .map_err(|e| tracing::warn!("Failed to open response cache: {}", e))
+                .ok()
+        });
+        let tool_bridge = (!config.mcp.servers.is_empty())
+            .then(|| Arc::new(ToolBridge::new(config.mcp.servers.clone())));
+        let http = create_shared_client_with(&config.http);
+        let mut scanner = FreeModelScanner::new()
+            .with_client(http.clone())
+            .with_keys_from(config)
+            .with_ttls_from(&config.scanner)
+            .with_disk_cache(FreeModelScanner::default_disk_cache_path());
+        if let Some(url) = &config.ollama.url {
+            scanner = scanner.with_ollama_url(url);
+        }
+        if let Some(url) = &config.lmstudio.url {
+            scanner = scanner.with_lmstudio_url(url);
+        }
+        if let Some(url) = &config.llamacpp.url {
+            scanner = scanner.with_llamacpp_url(url);
+        }
+
+        let alerts = config.logging.alerts.clone().map(|alerts| {
+            AlertHook::new(
+                AlertConfig {
+                    webhook_url: config.alert_webhook_url(),
+                    ..alerts
+                },
+                http.clone(),
+            )
+        });
+
+        Ok(Self {
+            sources: scanner.registry().clone(),
+            scanner,
+            http,
+            chat: Arc::new(chat),
+            syslog,
+            alerts,
+            traffic_log,
+            tool_bridge,
+            spending: SpendingTracker::open_default(config.spending.clone()),
+            cache: cache.flatten(),
+            inflight: config.gateway.dedup_inflight.then(InFlight::new),
+            synthesize_stream_usage: config.gateway.synthesize_stream_usage,
+            conversation_memory: config.gateway.conversation_memory,
+            static_dir: config.gateway.static_dir.clone(),
+            ollama_keep_alive: config.ollama.keep_alive.clone(),
+            providers: config.providers.clone(),
+            model_aliases: config.routing.aliases.clone(),
+            routing_strategy: config.routing.strategy,
+            max_fallbacks: config.routing.max_fallbacks,
+            json_retries: config.routing.json_retries,
+            retry: RetryPolicy::from_config(&config.http),
+            telemetry: config.telemetry.active_endpoint().map(|_| Telemetry::new()),
+            ab_split: config.routing.ab_test.clone().map(AbSplit::new),
+            templates: LazyStore::new("template store", TemplateStore::open_default),
+            evals: LazyStore::new("eval store", EvalStore::open_default),
+            bench: LazyStore::new("benchmark store", Benchmarks::open_default),
+            keys: LazyStore::new("key store", KeyStore::open_default),
+            require_api_key: config.gateway.require_api_key,
+            client_limits: ClientLimits::new(config.gateway.client_rpm, config.gateway.client_tpm),
+            gateway_key: Arc::new(RwLock::new(config.gateway.api_key.clone())),
+            hooks: Hooks::load(&config.hooks)
+                .map_err(|e| tracing::error!("Hooks disabled, failed to load script: {}", e))
+                .ok()
+                .flatten(),
+            speech_backends: config.audio.speech.clone(),
+            embedding_cache: config
+                .embeddings
+                .cache
+                .then(|| {
+                    EmbeddingCache::open_default(config.embeddings.cache_max_entries)
+                        .map_err(|e| tracing::warn!("Failed to open embedding cache: {}", e))
+                        .ok()
+                })
+                .flatten(),
+            leaderboard: LazyStore::new("leaderboard", Leaderboard::open_default),
+            config: Arc::new(RwLock::new(config.clone())),
+            ..Self::default()
+        })
+    }
+
+    /// Scan for models in the background now, so neither `/health/ready`
+    /// nor the first completion waits on discovery, then rescan every
+    /// `refresh_secs` (0 scans once).
+    pub fn spawn_model_refresh(&self, refresh_secs: u64) -> tokio::task::JoinHandle<()> {
+        let scanner = self.scanner.clone();
+        if refresh_secs == 0 {
+            return tokio::spawn(async move {
+                scanner.get_free_models(false).await;
+            });
         }
+        tokio::spawn(scanner.keep_warm(Duration::from_secs(refresh_secs)))
+    }
+
+    /// A comparator sharing the gateway's model cache, connection pool and
+    /// leaderboard.
+    pub fn comparator(&self) -> ModelComparator {
+        let config = self.config.read().unwrap();
+        ModelComparator::from_parts(self.scanner.clone(), self.http.clone(), &config)
+            .with_leaderboard(self.leaderboard.get().cloned())
+    }
+
+    /// Finish writing the logs: wait for a traffic log append in progress and
+    /// flush the console, so transactions recorded during shutdown are kept.
+    pub fn flush_logs(&self) {
+        if let Some(traffic_log) = &self.traffic_log {
+            traffic_log.flush();
+        }
+        let _ = std::io::stdout().flush();
+        let _ = std::io::stderr().flush();
+    }
+
+    /// Store a completed transaction and append its summary to the log buffer.
+    pub fn record_transaction(&self, transaction: CapturedTransaction) {
+        self.logs.push_transaction(&transaction);
+        if let Some(syslog) = &self.syslog {
+            syslog.log_transaction(&transaction);
+        }
+        if let Some(traffic_log) = &self.traffic_log {
+            if let Err(e) = traffic_log.append(&transaction) {
+                tracing::warn!("Failed to write traffic log: {}", e);
+            }
+        }
+        if let Some(alerts) = &self.alerts {
+            alerts.notify(&transaction, &self.logs);
+        }
+        let minted = transaction.virtual_key.as_deref();
+        if let Some((name, keys)) = minted.and_then(|name| Some((name, self.keys.get()?))) {
+            let (prompt, completion) = token_usage(&transaction);
+            if let Err(e) = keys.record_usage(name, prompt, completion) {
+                tracing::warn!("Failed to record usage for key '{}': {}", name, e);
+            }
+        }
+        self.inspector.store(transaction);
     }
 }
 
 impl Default for AppState {
     fn default() -> Self {
         let chat_db = ChatDb::in_memory().expect("Failed to create chat database");
+        let http = create_shared_client();
+        let scanner = FreeModelScanner::new().with_client(http.clone());
         Self {
-            scanner: FreeModelScanner::new(),
+            sources: scanner.registry().clone(),
+            scanner,
             inspector: TrafficInspector::new(),
             chat: Arc::new(ChatState::new(chat_db)),
+            logs: LogBuffer::default(),
+            syslog: None,
+            alerts: None,
+            traffic_log: None,
+            tool_bridge: None,
+            events: EventBus::new(),
+            started_at: Instant::now(),
+            streams: ActiveStreams::default(),
+            http,
+            spending: None,
+            model_stats: ModelStats::new(),
+            key_pool: KeyPool::new(),
+            cache: None,
+            inflight: None,
+            synthesize_stream_usage: false,
+            conversation_memory: false,
+            static_dir: None,
+            telemetry: None,
+            templates: None.into(),
+            ab_split: None,
+            evals: None.into(),
+            bench: None.into(),
+            ollama_keep_alive: None,
+            providers: ProvidersConfig::default(),
+            model_aliases: BTreeMap::new(),
+            routing_strategy: RoutingStrategy::default(),
+            max_fallbacks: RoutingConfig::default().max_fallbacks,
+            json_retries: RoutingConfig::default().json_retries,
+            retry: RetryPolicy::default(),
+            scheduler: Scheduler::new(),
+            keys: None.into(),
+            require_api_key: false,
+            client_limits: None,
+            gateway_key: Arc::new(RwLock::new(None)),
+            hooks: None,
+            speech_backends: Vec::new(),
+            embedding_cache: None,
+            leaderboard: None.into(),
+            config: Arc::default(),
         }
     }
 }
@@ -74,44 +444,128 @@ pub fn create_router() -> Router {
 /// Create the API router with custom state.
 pub fn create_router_with_state(state: AppState) -> Router {
     let chat_router = create_chat_router(state.chat.clone());
+    let static_dir = state.static_dir.clone();
+    let limit_clients = state.client_limits.is_some();
+    let state = Arc::new(state);
 
+    // Browser clients on any origin may use the model routes, but only the
+    // UI and loopback pages may manage the gateway
     let cors = CorsLayer::new()
-        .allow_origin(Any)
+        .allow_origin(AllowOrigin::predicate(|_, request| {
+            !gateway_auth::management_route(request.uri.path())
+                || gateway_auth::foreign_origin(&request.headers).is_none()
+        }))
         .allow_methods(Any)
         .allow_headers(Any);
 
-    Router::new()
+    let mut router = Router::new()
         .route("/health", get(handlers::health_check))
+        .route("/health/live", get(handlers::health_live))
+        .route("/health/ready", get(handlers::health_ready))
         .route("/v1/models", get(handlers::list_models))
         .route("/v1/models/grouped", get(handlers::list_models_grouped))
+        .route("/v1/models/events", get(handlers::model_events))
+        .route("/v1/models/{*id}", get(handlers::get_model))
+        .route("/v1/sources", get(handlers::list_sources))
         .route("/v1/chat/completions", post(handlers::chat_completions))
+        .route(
+            "/openai/deployments/{deployment}/chat/completions",
+            post(handlers::azure_chat_completions),
+        )
+        .route("/v1/embeddings", post(handlers::embeddings))
+        .route("/v1/embeddings/models", get(handlers::list_embedding_models))
+        .route("/v1/audio/speech", post(handlers::audio_speech))
         .route("/v1/inspect", get(handlers::get_inspect))
         .route("/v1/inspect", delete(handlers::clear_inspect))
+        .route("/v1/inspect/stream", get(handlers::inspect_stream))
+        .route("/v1/inspect/ws", get(handlers::inspect_ws))
+        .route("/v1/cache", delete(handlers::clear_cache))
+        .route("/v1/spending", get(handlers::get_spending))
+        .route("/v1/ab", get(handlers::get_ab_report))
+        .route("/v1/ab/feedback", post(handlers::ab_feedback))
+        .route(
+            "/v1/bench",
+            get(handlers::list_bench_jobs).post(handlers::start_bench),
+        )
+        .route("/v1/bench/{id}", get(handlers::get_bench_job))
+        .route("/v1/leaderboard", get(handlers::get_leaderboard))
+        .route("/metrics", get(handlers::metrics))
         .route("/api/settings", get(handlers::get_settings))
         .route("/api/settings", put(handlers::update_settings))
-        .with_state(Arc::new(state))
+        .route("/api/logs/tail", get(handlers::logs_tail))
+        .route("/api/logs/stream", get(handlers::logs_stream))
+        .route("/api/eval", post(handlers::run_eval))
+        .route(
+            "/api/evals/datasets",
+            get(handlers::list_datasets).post(handlers::create_dataset),
+        )
+        .route(
+            "/api/evals/datasets/{name}",
+            get(handlers::get_dataset).delete(handlers::delete_dataset),
+        )
+        .route(
+            "/api/evals/datasets/{name}/run",
+            post(handlers::run_dataset_handler),
+        )
+        .route("/api/evals/history", get(handlers::eval_history))
+        .route(
+            "/api/templates",
+            get(handlers::list_templates).post(handlers::create_template),
+        )
+        .route(
+            "/api/templates/{name}",
+            get(handlers::get_template)
+                .put(handlers::update_template)
+                .delete(handlers::delete_template),
+        )
+        .route(
+            "/api/templates/{name}/expand",
+            post(handlers::expand_template_handler),
+        )
+        .route(
+            "/api/keys",
+            get(handlers::list_keys).post(handlers::create_key),
+        )
+        .route("/api/keys/{name}", delete(handlers::delete_key))
+        .route("/api/keys/{name}/usage", get(handlers::key_usage))
+        .route("/api/tags", get(handlers::ollama_tags))
+        .route("/api/chat", post(handlers::ollama_chat))
+        .route("/api/generate", post(handlers::ollama_generate))
+        .with_state(state.clone());
+    if limit_clients {
+        router = router.layer(middleware::from_fn_with_state(state.clone(), client_limit::enforce));
+    }
+
+    router
         .merge(chat_router)
-        .fallback(static_handler)
+        .merge(SwaggerUi::new("/docs").url("/openapi.json", openapi::openapi()))
+        .layer(middleware::from_fn_with_state(state, gateway_auth::require_key))
+        .fallback(move |uri: axum::http::Uri| {
+            static_handler(static_dir.clone(), uri)
+        })
         .layer(cors)
 }
 
-/// Serve embedded static files
-async fn static_handler(uri: axum::http::Uri) -> impl IntoResponse {
+/// Serve static files from `static_dir` when set, else the embedded ones
+async fn static_handler(
+    static_dir: Option<PathBuf>,
+    uri: axum::http::Uri,
+) -> impl IntoResponse {
     let path = uri.path().trim_start_matches('/');
     let path = if path.is_empty() { "index.html" } else { path };
 
-    match StaticAssets::get(path) {
-        Some(content) => {
+    match static_asset(static_dir.as_deref(), path).await {
+        Some(data) => {
             let mime = mime_guess::from_path(path)
                 .first_or_octet_stream()
                 .to_string();
-            ([(header::CONTENT_TYPE, mime)], content.data.into_owned()).into_response()
+            ([(header::CONTENT_TYPE, mime)], data).into_response()
         }
         None => {
             // Fallback to index.html for SPA routing
-            match StaticAssets::get("index.html") {
-                Some(content) => {
-                    ([(header::CONTENT_TYPE, "text/html".to_string())], content.data.into_owned()).into_response()
+            match static_asset(static_dir.as_deref(), "index.html").await {
+                Some(data) => {
+                    ([(header::CONTENT_TYPE, "text/html".to_string())], data).into_response()
                 }
                 None => (StatusCode::NOT_FOUND, "Not Found").into_response(),
             }
@@ -119,6 +573,20 @@ async fn static_handler(uri: axum::http::Uri) -> impl IntoResponse {
     }
 }
 
+/// Read a UI file, preferring `static_dir` over the embedded assets.
+async fn static_asset(static_dir: Option<&Path>, path: &str) -> Option<Vec<u8>> {
+    // Only plain relative paths may reach the disk
+    let safe = Path::new(path)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)));
+    if let Some(dir) = static_dir.filter(|_| safe) {
+        if let Ok(data) = tokio::fs::read(dir.join(path)).await {
+            return Some(data);
+        }
+    }
+    StaticAssets::get(path).map(|content| content.data.into_owned())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,6 +594,18 @@ mod tests {
     use axum_test::TestServer;
     use serde_json::json;
 
+    /// Default state whose scanner finds its models on `upstream`.
+    pub(super) fn test_state(upstream: &mockito::ServerGuard) -> AppState {
+        AppState {
+            scanner: FreeModelScanner::new()
+                .with_openrouter_url(&upstream.url())
+                .with_opencode_zen_api_url(&upstream.url())
+                .with_opencode_zen_docs_url(&upstream.url())
+                .with_ollama_url(&upstream.url()),
+            ..AppState::default()
+        }
+    }
+
     // =========================================================================
     // normalize_model_name() tests
     // =========================================================================
@@ -159,6 +639,14 @@ mod tests {
         assert_eq!(normalize_model_name("grok-code-fast"), "Grok Code Fast");
     }
 
+    #[test]
+    fn normalize_gives_one_name_to_the_same_model_across_sources() {
+        let name = "Llama 3.3 70B Instruct";
+        assert_eq!(normalize_model_name("meta-llama/llama-3.3-70b-instruct:free"), name);
+        assert_eq!(normalize_model_name("Meta-Llama-3.3-70B-Instruct"), name);
+        assert_eq!(normalize_model_name("qwen/qwen-2.5-coder-1.5b"), "Qwen 2.5 Coder 1.5B");
+    }
+
     // =========================================================================
     // Helper function tests
     // =========================================================================
@@ -171,12 +659,22 @@ mod tests {
                 provider: "provider".to_string(),
                 endpoint: "http://example.com".to_string(),
                 source: Source::OpenRouter,
+                context_length: None,
+                modalities: Vec::new(),
+                supports_tools: None,
+                supports_response_format: None,
+                source_index: None,
             },
             FreeModel {
                 id: "model-b".to_string(),
                 provider: "provider".to_string(),
                 endpoint: "http://example.com".to_string(),
                 source: Source::OpenRouter,
+                context_length: None,
+                modalities: Vec::new(),
+                supports_tools: None,
+                supports_response_format: None,
+                source_index: None,
             },
         ];
 
@@ -193,12 +691,22 @@ mod tests {
                 provider: "provider".to_string(),
                 endpoint: "http://example.com".to_string(),
                 source: Source::OpenRouter,
+                context_length: None,
+                modalities: Vec::new(),
+                supports_tools: None,
+                supports_response_format: None,
+                source_index: None,
             },
             FreeModel {
                 id: "model-b".to_string(),
                 provider: "provider".to_string(),
                 endpoint: "http://example.com".to_string(),
                 source: Source::OpenRouter,
+                context_length: None,
+                modalities: Vec::new(),
+                supports_tools: None,
+                supports_response_format: None,
+                source_index: None,
             },
         ];
 
@@ -214,6 +722,11 @@ mod tests {
             provider: "provider".to_string(),
             endpoint: "http://example.com".to_string(),
             source: Source::OpenRouter,
+            context_length: None,
+            modalities: Vec::new(),
+            supports_tools: None,
+            supports_response_format: None,
+            source_index: None,
         }];
 
         let result = find_target_model("gpt-4", &models);
@@ -227,6 +740,29 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn settings_update_sets_and_clears_values() {
+        let mut config = Config::default();
+        config.api_keys.opencode_zen = vec!["zen-key".to_string()];
+
+        apply_settings_update(
+            &mut config,
+            UpdateSettingsRequest {
+                openrouter_api_key: Some("sk-or-key".to_string()),
+                opencode_zen_api_key: Some(String::new()),
+                start_at_login: Some(true),
+                gateway_api_key: None,
+                generate_gateway_api_key: true,
+            },
+        );
+
+        let settings = settings_from_config(&config);
+        assert!(settings.openrouter_configured);
+        assert!(!settings.opencode_zen_configured);
+        assert!(settings.gateway_api_key_configured);
+        assert!(settings.start_at_login);
+    }
+
     #[test]
     fn build_upstream_url_uses_correct_path_for_ollama() {
         let model = FreeModel {
@@ -234,11 +770,42 @@ mod tests {
             provider: "ollama".to_string(),
             endpoint: "http://localhost:11434".to_string(),
             source: Source::Ollama,
+            context_length: None,
+            modalities: Vec::new(),
+            supports_tools: None,
+            supports_response_format: None,
+            source_index: None,
         };
-        let url = build_upstream_url(&model);
+        let url = build_upstream_url(&SourceRegistry::builtin(), &model);
         assert_eq!(url, "http://localhost:11434/v1/chat/completions");
     }
 
+    #[test]
+    fn build_upstream_url_uses_v1_path_for_local_servers() {
+        for (source, endpoint) in [
+            (Source::LmStudio, "http://localhost:1234"),
+            (Source::LlamaCpp, "http://localhost:8080"),
+        ] {
+            let model = FreeModel {
+                id: "local".to_string(),
+                provider: "local".to_string(),
+                endpoint: endpoint.to_string(),
+                source,
+                context_length: None,
+                modalities: Vec::new(),
+                supports_tools: None,
+                supports_response_format: None,
+                source_index: None,
+            };
+            let state = AppState::default();
+            assert_eq!(
+                build_upstream_url(&state.sources, &model),
+                format!("{}/v1/chat/completions", endpoint)
+            );
+            assert_eq!(handlers::pick_api_key(&state, &model).unwrap(), None);
+        }
+    }
+
     #[test]
     fn build_upstream_url_uses_correct_path_for_cloud_providers() {
         let model = FreeModel {
@@ -246,8 +813,13 @@ mod tests {
             provider: "openrouter".to_string(),
             endpoint: "https://openrouter.ai/api/v1".to_string(),
             source: Source::OpenRouter,
+            context_length: None,
+            modalities: Vec::new(),
+            supports_tools: None,
+            supports_response_format: None,
+            source_index: None,
         };
-        let url = build_upstream_url(&model);
+        let url = build_upstream_url(&SourceRegistry::builtin(), &model);
         assert_eq!(url, "https://openrouter.ai/api/v1/chat/completions");
     }
 
@@ -311,7 +883,40 @@ mod tests {
         let body: serde_json::Value = response.json();
         let transactions = body["transactions"].as_array().unwrap();
 
-        assert!(transactions.len() >= 1, "Expected at least 1 transaction, got {}", transactions.len());
+        assert!(!transactions.is_empty(), "Expected at least 1 transaction, got {}", transactions.len());
+    }
+
+    #[tokio::test]
+    async fn serves_openapi_document_and_swagger_ui() {
+        let server = TestServer::new(create_router()).unwrap();
+
+        let response = server.get("/openapi.json").await;
+        response.assert_status_ok();
+        let doc: serde_json::Value = response.json();
+        assert!(doc["paths"]["/v1/chat/completions"]["post"].is_object());
+        assert!(doc["paths"]["/api/chats/{id}"]["get"].is_object());
+
+        server.get("/docs/").await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn static_dir_overrides_embedded_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("index.html"), "<h1>branded</h1>").unwrap();
+        let state = AppState {
+            static_dir: Some(dir.path().to_path_buf()),
+            ..AppState::default()
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        server.get("/").await.assert_text("<h1>branded</h1>");
+        // Files missing from the directory come from the embedded assets
+        server
+            .get("/multiai-logo.svg")
+            .await
+            .assert_header("content-type", "image/svg+xml");
+        // Paths can't escape the directory
+        server.get("/../Cargo.toml").await.assert_text("<h1>branded</h1>");
     }
 
     #[tokio::test]
@@ -322,11 +927,12 @@ mod tests {
         let response = server.get("/health").await;
 
         response.assert_status_ok();
-        response.assert_json(&json!({
-            "app": "multiai",
-            "status": "ok",
-            "version": "0.1.0"
-        }));
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["app"], "multiai");
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["version"], "0.1.0");
+        assert_eq!(body["chat_db"]["ok"], true);
+        assert_eq!(body["inspector"]["transactions"], 0);
     }
 
     #[tokio::test]
@@ -0,0 +1,514 @@
+//! Ollama's native API on top of the free-model router.
+//!
+//! MultiAI listens on Ollama's port, so clients written for Ollama call
+//! `/api/chat`, `/api/generate` and `/api/tags` rather than the OpenAI
+//! routes. Those requests are turned into chat completions and the replies
+//! back into Ollama's shapes: one JSON object, or newline-delimited JSON
+//! chunks when streaming (Ollama's default), ending in a `done` chunk.
+
+use super::types::{ChatMessage, ChatRequest};
+use crate::http::take_sse_data;
+use crate::scanner::FreeModel;
+use axum::body::{Body, Bytes};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use futures::{Stream, StreamExt};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Content type of streamed Ollama replies.
+const NDJSON: &str = "application/x-ndjson";
+
+/// Which Ollama endpoint a reply is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OllamaApi {
+    /// `/api/chat`: text in `message.content`.
+    Chat,
+    /// `/api/generate`: text in `response`.
+    Generate,
+}
+
+/// Whether an Ollama request streams; Ollama streams unless told not to.
+pub fn streams(body: &Value) -> bool {
+    body["stream"].as_bool().unwrap_or(true)
+}
+
+fn options_request(body: &Value, messages: Vec<ChatMessage>) -> ChatRequest {
+    let options = &body["options"];
+    let response_format = response_format(&body["format"]);
+    ChatRequest {
+        model: body["model"].as_str().unwrap_or("auto").to_string(),
+        messages,
+        temperature: options["temperature"].as_f64().map(|t| t as f32),
+        // Ollama uses -1 and -2 for "until done"
+        max_tokens: options["num_predict"]
+            .as_i64()
+            .and_then(|n| u32::try_from(n).ok()),
+        // Replies in a `format` are checked once complete, so they come back
+        // whole: the one `done` chunk a stream would end with
+        stream: streams(body) && response_format.is_none(),
+        template: None,
+        variables: BTreeMap::new(),
+        routing_strategy: None,
+        tools: None,
+        tool_choice: None,
+        response_format,
+    }
+}
+
+/// Ollama's `format`, "json" or a JSON Schema, as a `response_format`.
+fn response_format(format: &Value) -> Option<Value> {
+    match format {
+        Value::String(format) if format == "json" => Some(json!({ "type": "json_object" })),
+        Value::Object(_) => Some(json!({
+            "type": "json_schema",
+            "json_schema": { "name": "response", "schema": format },
+        })),
+        _ => None,
+    }
+}
+
+fn message(role: &str, content: &str) -> ChatMessage {
+    ChatMessage {
+        role: role.to_string(),
+        content: content.to_string(),
+        ..ChatMessage::default()
+    }
+}
+
+/// Whether a request field is set to something other than null or `[]`.
+fn present(value: &Value) -> bool {
+    value.as_array().map_or(!value.is_null(), |items| !items.is_empty())
+}
+
+/// Refuse the parts of an Ollama request the chat completion can't carry,
+/// tool calling and images, rather than quietly answering without them.
+fn check_supported(body: &Value) -> Result<(), String> {
+    let messages = body["messages"].as_array().into_iter().flatten();
+    let field = if present(&body["tools"]) {
+        Some("tools")
+    } else if present(&body["images"]) || messages.clone().any(|m| present(&m["images"])) {
+        Some("images")
+    } else if messages.clone().any(|m| present(&m["tool_calls"])) {
+        Some("tool_calls")
+    } else {
+        None
+    };
+    match field {
+        Some(field) => Err(format!(
+            "`{}` is not supported on the Ollama API; use /v1/chat/completions",
+            field
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Chat completion request for an `/api/chat` body.
+pub fn chat_request(body: &Value) -> Result<ChatRequest, String> {
+    check_supported(body)?;
+    let messages = body["messages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|m| {
+            message(
+                m["role"].as_str().unwrap_or("user"),
+                m["content"].as_str().unwrap_or_default(),
+            )
+        })
+        .collect();
+    Ok(options_request(body, messages))
+}
+
+/// Chat completion request for an `/api/generate` body: its `system` and
+/// `prompt` become a system and a user message.
+pub fn generate_request(body: &Value) -> Result<ChatRequest, String> {
+    check_supported(body)?;
+    let system = body["system"].as_str().filter(|s| !s.is_empty());
+    let messages = system
+        .map(|system| message("system", system))
+        .into_iter()
+        .chain(std::iter::once(message(
+            "user",
+            body["prompt"].as_str().unwrap_or_default(),
+        )))
+        .collect();
+    Ok(options_request(body, messages))
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// A reply chunk carrying `text`.
+fn chunk(api: OllamaApi, model: &str, text: &str, done: bool) -> Value {
+    let mut chunk = json!({ "model": model, "created_at": now(), "done": done });
+    match api {
+        OllamaApi::Chat => chunk["message"] = json!({ "role": "assistant", "content": text }),
+        OllamaApi::Generate => chunk["response"] = json!(text),
+    }
+    chunk
+}
+
+/// Add the `done_reason` and token counts of a finished reply.
+fn finish(chunk: &mut Value, finish_reason: Option<&str>, usage: &Value) {
+    chunk["done_reason"] = json!(match finish_reason {
+        Some("length") => "length",
+        _ => "stop",
+    });
+    chunk["prompt_eval_count"] = json!(usage["prompt_tokens"].as_u64().unwrap_or(0));
+    chunk["eval_count"] = json!(usage["completion_tokens"].as_u64().unwrap_or(0));
+}
+
+/// Ollama errors are a bare message: `{"error": "..."}`.
+fn error_body(body: &Value) -> Value {
+    let message = body["error"]["message"]
+        .as_str()
+        .or_else(|| body["error"].as_str())
+        .unwrap_or("request failed");
+    json!({ "error": message })
+}
+
+/// A 400 in Ollama's error shape, for requests that can't be served.
+pub fn error_reply(message: &str) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": message }))).into_response()
+}
+
+/// Convert a `chat.completion` into an Ollama reply.
+pub fn from_completion(api: OllamaApi, model: &str, completion: &Value) -> Value {
+    let choice = &completion["choices"][0];
+    let text = choice["message"]["content"].as_str().unwrap_or_default();
+    let mut reply = chunk(api, model, text, true);
+    finish(&mut reply, choice["finish_reason"].as_str(), &completion["usage"]);
+    reply
+}
+
+/// Rewrites an OpenAI SSE stream into Ollama's NDJSON chunks.
+struct NdjsonTranslator {
+    api: OllamaApi,
+    model: String,
+    pending: Vec<u8>,
+    finish_reason: Option<String>,
+    usage: Value,
+    done: bool,
+}
+
+impl NdjsonTranslator {
+    /// The final `done` chunk, once per stream.
+    fn done_line(&mut self) -> String {
+        if std::mem::replace(&mut self.done, true) {
+            return String::new();
+        }
+        let mut done = chunk(self.api, &self.model, "", true);
+        finish(&mut done, self.finish_reason.as_deref(), &self.usage);
+        format!("{}\n", done)
+    }
+
+    /// Translate the complete lines received so far, keeping a partial line
+    /// for the next read.
+    fn push(&mut self, bytes: &[u8]) -> Bytes {
+        self.pending.extend_from_slice(bytes);
+        let mut out = String::new();
+        for data in take_sse_data(&mut self.pending) {
+            if data == "[DONE]" {
+                out.push_str(&self.done_line());
+                continue;
+            }
+            let Ok(body) = serde_json::from_str::<Value>(&data) else {
+                continue;
+            };
+            if body.get("error").is_some() {
+                out.push_str(&format!("{}\n", error_body(&body)));
+                continue;
+            }
+            if !body["usage"].is_null() {
+                self.usage = body["usage"].clone();
+            }
+            let choice = &body["choices"][0];
+            if let Some(reason) = choice["finish_reason"].as_str() {
+                self.finish_reason = Some(reason.to_string());
+            }
+            if let Some(text) = choice["delta"]["content"].as_str().filter(|t| !t.is_empty()) {
+                out.push_str(&format!("{}\n", chunk(self.api, &self.model, text, false)));
+            }
+        }
+        Bytes::from(out)
+    }
+}
+
+/// Translate an OpenAI SSE body into Ollama NDJSON chunks, ending in a
+/// `done` chunk even when the upstream closes without `[DONE]`.
+pub fn translate_stream<S, E>(
+    api: OllamaApi,
+    model: String,
+    upstream: S,
+) -> impl Stream<Item = Result<Bytes, E>>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+{
+    let translator = Arc::new(Mutex::new(NdjsonTranslator {
+        api,
+        model,
+        pending: Vec::new(),
+        finish_reason: None,
+        usage: Value::Null,
+        done: false,
+    }));
+    let last = translator.clone();
+    upstream
+        .map(move |bytes| bytes.map(|bytes| translator.lock().unwrap().push(&bytes)))
+        .chain(futures::stream::once(async move {
+            Ok(Bytes::from(last.lock().unwrap().done_line()))
+        }))
+}
+
+/// Turn a chat completion response into the Ollama reply for `api`,
+/// keeping its status and headers.
+pub async fn translate_response(api: OllamaApi, model: String, response: Response) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let streamed = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"text/event-stream"));
+    parts.headers.remove(header::CONTENT_LENGTH);
+
+    if streamed {
+        parts
+            .headers
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(NDJSON));
+        let stream = translate_stream(api, model, body.into_data_stream());
+        return Response::from_parts(parts, Body::from_stream(stream));
+    }
+
+    let bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .unwrap_or_default();
+    let body: Value = serde_json::from_slice(&bytes).unwrap_or_default();
+    let reply = if parts.status.is_success() {
+        from_completion(api, &model, &body)
+    } else {
+        error_body(&body)
+    };
+    parts
+        .headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Response::from_parts(parts, Body::from(reply.to_string()))
+}
+
+/// `/api/generate` with an empty prompt only loads the model in Ollama.
+pub fn load_reply(model: &str) -> Value {
+    let mut reply = chunk(OllamaApi::Generate, model, "", true);
+    reply["done_reason"] = json!("load");
+    reply
+}
+
+/// `/api/tags` listing for the free models.
+pub fn tags(models: &[FreeModel]) -> Value {
+    let modified_at = now();
+    let models: Vec<Value> = models
+        .iter()
+        .map(|m| {
+            json!({
+                "name": m.id,
+                "model": m.id,
+                "modified_at": modified_at,
+                "size": 0,
+                "digest": "",
+                "details": { "family": m.provider, "format": "" },
+            })
+        })
+        .collect();
+    json!({ "models": models })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::create_router_with_state;
+    use crate::api::tests::test_state;
+    use axum_test::TestServer;
+
+    #[test]
+    fn requests_map_options_and_prompts() {
+        let request = chat_request(&json!({
+            "model": "llama3",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "options": {"temperature": 0.5, "num_predict": 64}
+        }))
+        .unwrap();
+        assert_eq!(request.model, "llama3");
+        assert_eq!(request.messages[0].content, "Hi");
+        assert_eq!(request.temperature, Some(0.5));
+        assert_eq!(request.max_tokens, Some(64));
+        assert!(request.stream);
+
+        let request = generate_request(&json!({
+            "model": "llama3",
+            "system": "Be brief",
+            "prompt": "Why is the sky blue?",
+            "stream": false,
+            "options": {"num_predict": -1}
+        }))
+        .unwrap();
+        assert_eq!(request.messages.len(), 2);
+        assert_eq!(request.messages[0].role, "system");
+        assert_eq!(request.messages[1].content, "Why is the sky blue?");
+        assert_eq!(request.max_tokens, None);
+        assert!(!request.stream);
+        assert!(request.response_format.is_none());
+
+        let request =
+            chat_request(&json!({"model": "llama3", "messages": [], "format": "json"})).unwrap();
+        assert_eq!(request.response_format, Some(json!({"type": "json_object"})));
+        assert!(!request.stream);
+    }
+
+    #[test]
+    fn tools_and_images_are_refused_rather_than_dropped() {
+        let tool = json!({"type": "function", "function": {"name": "get_weather"}});
+        let error = chat_request(&json!({"model": "llama3", "messages": [], "tools": [tool]}));
+        assert!(error.unwrap_err().contains("`tools`"));
+        let error = chat_request(&json!({
+            "model": "llama3",
+            "messages": [{"role": "user", "content": "What is this?", "images": ["aGk="]}]
+        }));
+        assert!(error.unwrap_err().contains("`images`"));
+        let error = generate_request(&json!({"model": "llava", "prompt": "Hi", "images": ["aGk="]}));
+        assert!(error.unwrap_err().contains("`images`"));
+        let error = chat_request(&json!({
+            "model": "llama3",
+            "messages": [{"role": "assistant", "tool_calls": [{"function": {"name": "f"}}]}]
+        }));
+        assert!(error.unwrap_err().contains("`tool_calls`"));
+
+        let empty = chat_request(&json!({"model": "llama3", "messages": [], "tools": []}));
+        assert!(empty.is_ok());
+    }
+
+    #[test]
+    fn completions_become_ollama_replies() {
+        let completion = json!({
+            "choices": [{"message": {"role": "assistant", "content": "Hello"}, "finish_reason": "length"}],
+            "usage": {"prompt_tokens": 3, "completion_tokens": 1}
+        });
+
+        let reply = from_completion(OllamaApi::Chat, "llama3", &completion);
+        assert_eq!(reply["message"]["content"], "Hello");
+        assert_eq!(reply["done"], true);
+        assert_eq!(reply["done_reason"], "length");
+        assert_eq!(reply["eval_count"], 1);
+
+        let reply = from_completion(OllamaApi::Generate, "llama3", &completion);
+        assert_eq!(reply["response"], "Hello");
+        assert_eq!(reply["prompt_eval_count"], 3);
+    }
+
+    #[tokio::test]
+    async fn streams_are_rewritten_as_ndjson() {
+        let upstream = futures::stream::iter(vec![
+            Ok::<_, std::io::Error>(Bytes::from(
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\ndata: {\"choices\":[{\"delta\":{\"con",
+            )),
+            Ok(Bytes::from(
+                "tent\":\"lo\"},\"finish_reason\":\"stop\"}],\"usage\":{\"completion_tokens\":2}}\n\ndata: [DONE]\n\n",
+            )),
+        ]);
+
+        let chunks: Vec<Bytes> = translate_stream(OllamaApi::Generate, "llama3".to_string(), upstream)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        let text = String::from_utf8(chunks.concat()).unwrap();
+        let lines: Vec<Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["response"], "Hel");
+        assert_eq!(lines[1]["response"], "lo");
+        assert_eq!(lines[2]["done"], true);
+        assert_eq!(lines[2]["done_reason"], "stop");
+        assert_eq!(lines[2]["eval_count"], 2);
+
+        // A stream cut off before `[DONE]` still ends in a `done` chunk
+        let upstream = futures::stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+        ))]);
+        let chunks: Vec<Bytes> = translate_stream(OllamaApi::Chat, "llama3".to_string(), upstream)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        let text = String::from_utf8(chunks.concat()).unwrap();
+        let lines: Vec<Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["message"]["content"], "Hel");
+        assert_eq!(lines[1]["done"], true);
+    }
+
+    #[tokio::test]
+    async fn ollama_native_routes_are_served_by_free_models() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        let completion = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "model": "llama3",
+                "max_tokens": 32,
+                "stream": false
+            })))
+            .with_body(
+                r#"{"choices": [{"message": {"content": "hi"}, "finish_reason": "stop"}],
+                    "usage": {"prompt_tokens": 4, "completion_tokens": 1}}"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        let state = test_state(&upstream);
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let tags: serde_json::Value = server.get("/api/tags").await.json();
+        assert_eq!(tags["models"][0]["name"], "llama3");
+
+        // Models the gateway doesn't know are routed as `auto`
+        let response = server
+            .post("/api/chat")
+            .json(&json!({
+                "model": "llama3.2:latest",
+                "messages": [{"role": "user", "content": "Hi"}],
+                "stream": false,
+                "options": {"num_predict": 32}
+            }))
+            .await;
+        response.assert_status_ok();
+        let reply: serde_json::Value = response.json();
+        assert_eq!(reply["model"], "llama3.2:latest");
+        assert_eq!(reply["message"]["content"], "hi");
+        assert_eq!(reply["done"], true);
+        assert_eq!(reply["eval_count"], 1);
+
+        let reply: serde_json::Value = server
+            .post("/api/generate")
+            .json(&json!({
+                "model": "llama3",
+                "prompt": "Hi",
+                "stream": false,
+                "options": {"num_predict": 32}
+            }))
+            .await
+            .json();
+        assert_eq!(reply["response"], "hi");
+        assert_eq!(reply["done_reason"], "stop");
+        completion.assert_async().await;
+
+        let reply: serde_json::Value = server
+            .post("/api/generate")
+            .json(&json!({"model": "llama3"}))
+            .await
+            .json();
+        assert_eq!(reply["done_reason"], "load");
+    }
+}
@@ -0,0 +1,109 @@
+//! OpenAPI document for the gateway, served at `/openapi.json`.
+//!
+//! Request and response schemas are collected from the handlers' `#[utoipa::path]`
+//! annotations.
+
+use super::handlers;
+use crate::chat_api::ChatApiDoc;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "MultiAI gateway",
+        description = "OpenAI-compatible routing to free LLMs, plus the chat API used by the web UI."
+    ),
+    paths(
+        handlers::health_check,
+        handlers::health_live,
+        handlers::health_ready,
+        handlers::list_models,
+        handlers::list_models_grouped,
+        handlers::get_model,
+        handlers::model_events,
+        handlers::list_sources,
+        handlers::chat_completions,
+        handlers::azure_chat_completions,
+        handlers::ollama_tags,
+        handlers::ollama_chat,
+        handlers::ollama_generate,
+        handlers::list_embedding_models,
+        handlers::embeddings,
+        handlers::audio_speech,
+        handlers::get_inspect,
+        handlers::clear_inspect,
+        handlers::inspect_stream,
+        handlers::inspect_ws,
+        handlers::clear_cache,
+        handlers::metrics,
+        handlers::get_spending,
+        handlers::get_ab_report,
+        handlers::ab_feedback,
+        handlers::start_bench,
+        handlers::list_bench_jobs,
+        handlers::get_bench_job,
+        handlers::get_settings,
+        handlers::update_settings,
+        handlers::logs_tail,
+        handlers::logs_stream,
+        handlers::run_eval,
+        handlers::list_datasets,
+        handlers::create_dataset,
+        handlers::get_dataset,
+        handlers::delete_dataset,
+        handlers::run_dataset_handler,
+        handlers::eval_history,
+        handlers::get_leaderboard,
+        handlers::list_templates,
+        handlers::create_template,
+        handlers::get_template,
+        handlers::update_template,
+        handlers::delete_template,
+        handlers::expand_template_handler,
+        handlers::list_keys,
+        handlers::create_key,
+        handlers::delete_key,
+        handlers::key_usage,
+    ),
+    tags(
+        (name = "health", description = "Liveness, readiness and component status"),
+        (name = "models", description = "Free model discovery"),
+        (name = "chat", description = "OpenAI-compatible chat completions"),
+        (name = "ollama", description = "Ollama's native chat, generate and tags API"),
+        (name = "embeddings", description = "Embeddings with an on-disk vector cache"),
+        (name = "audio", description = "Text to speech through configured TTS servers"),
+        (name = "inspect", description = "Captured upstream traffic"),
+        (name = "admin", description = "Cache, metrics, spending and A/B tests"),
+        (name = "logs", description = "Gateway log lines"),
+        (name = "eval", description = "Prompt suites, eval datasets, scores, benchmarks and the leaderboard"),
+        (name = "settings", description = "UI settings"),
+        (name = "templates", description = "Named prompt templates"),
+        (name = "keys", description = "Gateway API keys with per-key limits and usage"),
+    )
+)]
+struct ApiDoc;
+
+/// The full OpenAPI document, including the chat endpoints.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    let mut doc = ApiDoc::openapi();
+    doc.merge(ChatApiDoc::openapi());
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_covers_gateway_and_chat_paths() {
+        let doc = openapi();
+        for path in [
+            "/v1/chat/completions",
+            "/v1/models",
+            "/api/chats/{id}/export",
+        ] {
+            assert!(doc.paths.paths.contains_key(path), "missing {}", path);
+        }
+        assert!(doc.components.unwrap().schemas.contains_key("ChatRequest"));
+    }
+}
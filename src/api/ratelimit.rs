@@ -0,0 +1,295 @@
+//! Rate-limit headers forwarded from upstream responses.
+//!
+//! Client libraries back off based on OpenAI's `x-ratelimit-*` headers and
+//! `retry-after`. Providers that already use OpenAI's names are passed
+//! through; OpenRouter's `X-RateLimit-Limit/Remaining/Reset` are mapped to
+//! the request-count equivalents, with its epoch reset turned into a delay.
+
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use std::time::Duration;
+
+/// OpenAI-style headers passed through unchanged.
+const PASSTHROUGH: &[&str] = &[
+    "x-ratelimit-limit-requests",
+    "x-ratelimit-limit-tokens",
+    "x-ratelimit-remaining-requests",
+    "x-ratelimit-remaining-tokens",
+    "x-ratelimit-reset-requests",
+    "x-ratelimit-reset-tokens",
+    "retry-after",
+];
+
+/// OpenRouter-style headers and their OpenAI-style names.
+const ALIASES: &[(&str, &str)] = &[
+    ("x-ratelimit-limit", "x-ratelimit-limit-requests"),
+    ("x-ratelimit-remaining", "x-ratelimit-remaining-requests"),
+    ("x-ratelimit-reset", "x-ratelimit-reset-requests"),
+];
+
+/// Rate-limit headers from an upstream response, normalized to OpenAI's names.
+pub fn rate_limit_headers(headers: &HeaderMap, now: DateTime<Utc>) -> Vec<(String, String)> {
+    let value = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    let mut forwarded: Vec<(String, String)> = PASSTHROUGH
+        .iter()
+        .filter_map(|name| Some((name.to_string(), value(name)?.to_string())))
+        .collect();
+    for (alias, name) in ALIASES {
+        if forwarded.iter().any(|(existing, _)| existing == name) {
+            continue;
+        }
+        if let Some(v) = value(alias) {
+            let v = if name.starts_with("x-ratelimit-reset") {
+                reset_delay(v, now)
+            } else {
+                v.to_string()
+            };
+            forwarded.push((name.to_string(), v));
+        }
+    }
+    forwarded
+}
+
+/// Turn an epoch reset time (seconds or milliseconds) into a delay like
+/// `"12s"`; other values are already delays and are kept.
+fn reset_delay(value: &str, now: DateTime<Utc>) -> String {
+    let Ok(n) = value.trim().parse::<i64>() else {
+        return value.to_string();
+    };
+    let reset_ms = if n >= 1_000_000_000_000 {
+        n
+    } else if n >= 1_000_000_000 {
+        n * 1000
+    } else {
+        return format!("{}s", n);
+    };
+    let delay_secs = ((reset_ms - now.timestamp_millis()).max(0) as u64).div_ceil(1000);
+    format!("{}s", delay_secs)
+}
+
+/// How long to wait before retrying, from normalized rate-limit headers:
+/// `retry-after` (seconds or an HTTP date), else the request-count reset.
+pub fn retry_after(headers: &[(String, String)], now: DateTime<Utc>) -> Option<Duration> {
+    let value = |name: &str| {
+        headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, v)| v.trim())
+    };
+    if let Some(v) = value("retry-after") {
+        if let Ok(secs) = v.parse::<u64>() {
+            return Some(Duration::from_secs(secs));
+        }
+        if let Ok(at) = DateTime::parse_from_rfc2822(v) {
+            let secs = (at.timestamp() - now.timestamp()).max(0) as u64;
+            return Some(Duration::from_secs(secs));
+        }
+    }
+    value("x-ratelimit-reset-requests").and_then(parse_delay)
+}
+
+/// Parse a delay like `"13s"`, `"1m30s"`, `"250ms"` or `"1h"`.
+fn parse_delay(value: &str) -> Option<Duration> {
+    let mut total = Duration::ZERO;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+        let amount: f64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit_len = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let secs = match &rest[..unit_len] {
+            "ms" => amount / 1000.0,
+            "s" => amount,
+            "m" => amount * 60.0,
+            "h" => amount * 60.0 * 60.0,
+            _ => return None,
+        };
+        total += Duration::from_secs_f64(secs);
+        rest = &rest[unit_len..];
+    }
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tests::test_state;
+    use crate::api::{create_router_with_state, AppState};
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, value.parse().unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn passes_through_openai_style_headers() {
+        let map = headers(&[
+            ("x-ratelimit-remaining-requests", "29"),
+            ("x-ratelimit-reset-tokens", "6m0s"),
+            ("retry-after", "2"),
+            ("content-type", "application/json"),
+        ]);
+
+        let forwarded = rate_limit_headers(&map, Utc::now());
+        assert_eq!(
+            forwarded,
+            vec![
+                ("x-ratelimit-remaining-requests".into(), "29".into()),
+                ("x-ratelimit-reset-tokens".into(), "6m0s".into()),
+                ("retry-after".into(), "2".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn maps_openrouter_headers_and_epoch_reset() {
+        let now = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let map = headers(&[
+            ("x-ratelimit-limit", "20"),
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "1700000012500"),
+        ]);
+
+        let forwarded = rate_limit_headers(&map, now);
+        assert_eq!(
+            forwarded,
+            vec![
+                ("x-ratelimit-limit-requests".into(), "20".into()),
+                ("x-ratelimit-remaining-requests".into(), "0".into()),
+                ("x-ratelimit-reset-requests".into(), "13s".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reset_delay_handles_seconds_and_past_times() {
+        let now = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        assert_eq!(reset_delay("1700000030", now), "30s");
+        assert_eq!(reset_delay("1600000000", now), "0s");
+        assert_eq!(reset_delay("5", now), "5s");
+        assert_eq!(reset_delay("1m30s", now), "1m30s");
+    }
+
+    #[test]
+    fn retry_after_reads_seconds_dates_and_resets() {
+        let now = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let header = |name: &str, value: &str| vec![(name.to_string(), value.to_string())];
+
+        assert_eq!(
+            retry_after(&header("retry-after", "7"), now),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(
+            retry_after(&header("retry-after", "Tue, 14 Nov 2023 22:14:20 GMT"), now),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            retry_after(&header("x-ratelimit-reset-requests", "1m30s"), now),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(
+            retry_after(&header("x-ratelimit-reset-requests", "250ms"), now),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(retry_after(&header("x-ratelimit-remaining-requests", "0"), now), None);
+    }
+
+    #[tokio::test]
+    async fn forwards_normalized_rate_limit_headers() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        upstream
+            .mock("POST", "/v1/chat/completions")
+            .with_status(429)
+            .with_header("X-RateLimit-Remaining", "0")
+            .with_header("Retry-After", "7")
+            .with_body(r#"{"error": {"message": "rate limited"}}"#)
+            .create_async()
+            .await;
+        let state = test_state(&upstream);
+        state.scanner.get_free_models(false).await;
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+
+        let response = server
+            .post("/v1/chat/completions")
+            .json(&json!({
+                "model": "llama3",
+                "messages": [{"role": "user", "content": "Hello"}]
+            }))
+            .await;
+
+        response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        response.assert_header("x-ratelimit-remaining-requests", "0");
+        response.assert_header("retry-after", "7");
+    }
+
+    #[tokio::test]
+    async fn rate_limited_models_are_skipped_by_auto_until_retry_after() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}, {"name": "mistral"}]}"#)
+            .create_async()
+            .await;
+        upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "llama3"})))
+            .with_status(429)
+            .with_header("Retry-After", "30")
+            .with_body(r#"{"error": {"message": "rate limited"}}"#)
+            .create_async()
+            .await;
+        let mistral = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "mistral"})))
+            .with_body(r#"{"choices": [{"message": {"role": "assistant", "content": "Hi"}}]}"#)
+            .create_async()
+            .await;
+        let state = AppState {
+            // Pass the 429 through instead of falling back to mistral
+            max_fallbacks: 0,
+            ..test_state(&upstream)
+        };
+        state.scanner.get_free_models(false).await;
+        let mut events = state.events.subscribe();
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+        let request = json!({
+            "model": "auto",
+            "messages": [{"role": "user", "content": "Hello"}]
+        });
+
+        server
+            .post("/v1/chat/completions")
+            .json(&request)
+            .await
+            .assert_status(StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            events.try_recv().unwrap(),
+            crate::events::GatewayEvent::CircuitOpen {
+                source: crate::scanner::Source::Ollama,
+                model: "llama3".to_string(),
+                retry_after_secs: 30,
+            }
+        );
+        server.post("/v1/chat/completions").json(&request).await.assert_status_ok();
+        mistral.assert_async().await;
+
+        let models: serde_json::Value = server.get("/v1/models").await.json();
+        let data = models["data"].as_array().unwrap();
+        assert_eq!(data[0]["id"], "mistral");
+        let llama = data.iter().find(|m| m["id"] == "llama3").unwrap();
+        assert!(llama["rate_limited_for_secs"].as_u64().unwrap() <= 30);
+    }
+}
@@ -0,0 +1,228 @@
+//! Graceful shutdown that lets streaming completions finish.
+//!
+//! Once the shutdown signal fires the listener stops accepting connections
+//! and live feeds (model events, log and inspector streams and sockets)
+//! end. In-flight requests, including streaming completions, get a grace
+//! period to finish; streams still open after that are cut, and their
+//! transactions recorded, before the server returns.
+
+use axum::Router;
+use std::future::{Future, IntoFuture};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
+
+/// How long cut streams get to record their transactions and close.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Counts streaming responses that are still being sent, and tells streams
+/// when to stop.
+#[derive(Clone, Default)]
+pub struct ActiveStreams {
+    count: Arc<AtomicUsize>,
+    draining: CancellationToken,
+    closed: CancellationToken,
+}
+
+impl ActiveStreams {
+    /// Mark a stream as active until the returned guard is dropped.
+    pub fn guard(&self) -> StreamGuard {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        StreamGuard {
+            count: self.count.clone(),
+        }
+    }
+
+    /// Number of streams currently being sent.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Resolves when shutdown starts. Feeds that never end on their own
+    /// stop here rather than holding up the grace period.
+    pub fn draining(&self) -> WaitForCancellationFutureOwned {
+        self.draining.clone().cancelled_owned()
+    }
+
+    /// Resolves when the grace period elapses. Streaming completions stop
+    /// here so their transactions are recorded before the server returns.
+    pub fn closed(&self) -> WaitForCancellationFutureOwned {
+        self.closed.clone().cancelled_owned()
+    }
+}
+
+/// Keeps a stream counted as active while alive.
+pub struct StreamGuard {
+    count: Arc<AtomicUsize>,
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        self.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Serve `router` until `signal` resolves, then drain in-flight requests for
+/// up to `grace`. Streams still open after that are cut through `streams`,
+/// and their transactions recorded, before returning.
+pub async fn serve_with_grace(
+    listener: TcpListener,
+    router: Router,
+    signal: impl Future<Output = ()> + Send + 'static,
+    grace: Duration,
+    streams: ActiveStreams,
+) -> std::io::Result<()> {
+    let stop_accepting = CancellationToken::new();
+    // Connection addresses let per-client rate limits tell clients apart
+    let router = router.into_make_service_with_connect_info::<SocketAddr>();
+    let server = axum::serve(listener, router)
+        .with_graceful_shutdown(stop_accepting.clone().cancelled_owned())
+        .into_future();
+    tokio::pin!(server);
+
+    tokio::select! {
+        result = &mut server => return result,
+        _ = signal => {}
+    }
+
+    stop_accepting.cancel();
+    streams.draining.cancel();
+    if streams.count() > 0 {
+        tracing::info!(
+            "Waiting up to {}s for {} active stream(s)",
+            grace.as_secs(),
+            streams.count()
+        );
+    }
+
+    if let Ok(result) = tokio::time::timeout(grace, &mut server).await {
+        return result;
+    }
+    tracing::warn!(
+        "Grace period elapsed, closing {} active stream(s)",
+        streams.count()
+    );
+    streams.closed.cancel();
+    tokio::time::timeout(FLUSH_TIMEOUT, server)
+        .await
+        .unwrap_or(Ok(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get};
+    use futures::StreamExt;
+
+    /// A router whose single route streams `chunks` chunks, `interval` apart.
+    fn streaming_router(streams: ActiveStreams, chunks: usize, interval: Duration) -> Router {
+        Router::new().route(
+            "/stream",
+            get(move || {
+                let guard = streams.guard();
+                let closed = streams.closed();
+                async move {
+                    let body = futures::stream::iter(0..chunks)
+                        .then(move |i| {
+                            let _ = &guard;
+                            async move {
+                                tokio::time::sleep(interval).await;
+                                Ok::<_, std::io::Error>(format!("chunk {}\n", i))
+                            }
+                        })
+                        .take_until(closed);
+                    Body::from_stream(body)
+                }
+            }),
+        )
+    }
+
+    async fn start(
+        router: Router,
+        grace: Duration,
+        streams: ActiveStreams,
+    ) -> (
+        String,
+        tokio::sync::oneshot::Sender<()>,
+        tokio::task::JoinHandle<std::io::Result<()>>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/stream", listener.local_addr().unwrap());
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(serve_with_grace(
+            listener,
+            router,
+            async {
+                let _ = stopped.await;
+            },
+            grace,
+            streams,
+        ));
+        (url, stop, server)
+    }
+
+    #[tokio::test]
+    async fn in_flight_stream_finishes_after_signal() {
+        let streams = ActiveStreams::default();
+        let router = streaming_router(streams.clone(), 3, Duration::from_millis(50));
+        let (url, stop, server) = start(router, Duration::from_secs(5), streams.clone()).await;
+
+        let response = reqwest::get(&url).await.unwrap();
+        stop.send(()).unwrap();
+        let body = response.text().await.unwrap();
+
+        assert_eq!(body, "chunk 0\nchunk 1\nchunk 2\n");
+        server.await.unwrap().unwrap();
+        assert_eq!(streams.count(), 0);
+    }
+
+    #[tokio::test]
+    async fn stream_is_cut_when_grace_period_elapses() {
+        let streams = ActiveStreams::default();
+        let router = streaming_router(streams.clone(), 1000, Duration::from_millis(20));
+        let (url, stop, server) = start(router, Duration::from_millis(100), streams.clone()).await;
+
+        let _response = reqwest::get(&url).await.unwrap();
+        stop.send(()).unwrap();
+
+        let result = tokio::time::timeout(Duration::from_secs(5), server).await;
+        assert!(result.is_ok(), "server should stop after the grace period");
+        assert_eq!(
+            streams.count(),
+            0,
+            "cut streams are dropped before returning"
+        );
+    }
+
+    #[tokio::test]
+    async fn live_feeds_end_when_shutdown_starts() {
+        let streams = ActiveStreams::default();
+        let feed_streams = streams.clone();
+        let router = Router::new().route(
+            "/stream",
+            get(move || {
+                let draining = feed_streams.draining();
+                async move {
+                    let feed = futures::stream::pending::<Result<String, std::io::Error>>()
+                        .take_until(draining);
+                    Body::from_stream(feed)
+                }
+            }),
+        );
+        let (url, stop, server) = start(router, Duration::from_secs(30), streams).await;
+
+        let response = reqwest::get(&url).await.unwrap();
+        stop.send(()).unwrap();
+
+        let body = tokio::time::timeout(Duration::from_secs(5), response.text()).await;
+        assert_eq!(body.expect("feed should end at the signal").unwrap(), "");
+        let result = tokio::time::timeout(Duration::from_secs(5), server).await;
+        assert!(
+            result.is_ok(),
+            "server should not wait out the grace period"
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Validation of tool calling requests.
+//!
+//! `tools`, `tool_choice` and tool messages are forwarded as-is, so they are
+//! checked here first: a malformed tool definition would otherwise fail
+//! differently at each provider, or fall back through every free model.
+
+use super::types::ChatRequest;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Whether the request lets the model call its tools, so models known not
+/// to support them are skipped.
+pub fn wants_tools(request: &ChatRequest) -> bool {
+    request.tools.as_ref().is_some_and(|tools| !tools.is_empty())
+        && request.tool_choice.as_ref().and_then(Value::as_str) != Some("none")
+}
+
+/// Function names providers accept: letters, digits, `_` and `-`, up to 64.
+fn valid_name(name: &str) -> bool {
+    (1..=64).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Check the tools, tool choice and tool messages of a request.
+pub fn validate(request: &ChatRequest) -> Result<(), String> {
+    let mut names = HashSet::new();
+    if let Some(tools) = &request.tools {
+        if tools.is_empty() {
+            return Err("tools must not be empty".to_string());
+        }
+        for (i, tool) in tools.iter().enumerate() {
+            if tool["type"] != "function" {
+                return Err(format!("tools[{}].type must be \"function\"", i));
+            }
+            let function = &tool["function"];
+            let name = function["name"].as_str().unwrap_or_default();
+            if !valid_name(name) {
+                return Err(format!(
+                    "tools[{}].function.name must be 1-64 letters, digits, '_' or '-'",
+                    i
+                ));
+            }
+            if !names.insert(name) {
+                return Err(format!("tools[{}]: duplicate function '{}'", i, name));
+            }
+            if !function["parameters"].is_null() && !function["parameters"].is_object() {
+                return Err(format!("tools[{}].function.parameters must be a JSON Schema object", i));
+            }
+        }
+    }
+
+    match &request.tool_choice {
+        None => {}
+        Some(_) if request.tools.is_none() => {
+            return Err("tool_choice is only allowed with tools".to_string())
+        }
+        Some(Value::String(choice)) if ["none", "auto", "required"].contains(&choice.as_str()) => {}
+        Some(choice) if choice["type"] == "function" => {
+            let name = choice["function"]["name"].as_str().unwrap_or_default();
+            if !names.contains(name) {
+                return Err(format!("tool_choice names unknown function '{}'", name));
+            }
+        }
+        Some(_) => {
+            return Err(
+                "tool_choice must be \"none\", \"auto\", \"required\" or a function".to_string(),
+            )
+        }
+    }
+
+    for (i, message) in request.messages.iter().enumerate() {
+        if message.role == "tool" && message.tool_call_id.as_deref().is_none_or(str::is_empty) {
+            return Err(format!("messages[{}]: tool messages need a tool_call_id", i));
+        }
+        for call in message.tool_calls.iter().flatten() {
+            if call["id"].as_str().is_none_or(str::is_empty)
+                || call["function"]["name"].as_str().is_none()
+            {
+                return Err(format!(
+                    "messages[{}].tool_calls entries need an id and a function name",
+                    i
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{create_router_with_state, AppState};
+    use crate::scanner::FreeModelScanner;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    fn request(body: Value) -> ChatRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    fn weather_tool() -> Value {
+        json!({
+            "type": "function",
+            "function": {
+                "name": "get_weather",
+                "parameters": {"type": "object", "properties": {"city": {"type": "string"}}}
+            }
+        })
+    }
+
+    #[test]
+    fn accepts_tools_and_a_tool_call_round_trip() {
+        let request = request(json!({
+            "model": "auto",
+            "tools": [weather_tool()],
+            "tool_choice": {"type": "function", "function": {"name": "get_weather"}},
+            "messages": [
+                {"role": "user", "content": "Weather in Paris?"},
+                {"role": "assistant", "content": null, "tool_calls": [{
+                    "id": "call_1", "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+                }]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "18C"}
+            ]
+        }));
+
+        assert_eq!(validate(&request), Ok(()));
+        assert!(wants_tools(&request));
+        assert_eq!(request.messages[1].content, "");
+    }
+
+    #[test]
+    fn rejects_malformed_tools() {
+        let bad = [
+            json!({"model": "auto", "messages": [], "tools": []}),
+            json!({"model": "auto", "messages": [], "tools": [{"type": "retrieval"}]}),
+            json!({"model": "auto", "messages": [], "tools": [{"type": "function", "function": {"name": "get weather"}}]}),
+            json!({"model": "auto", "messages": [], "tools": [weather_tool(), weather_tool()]}),
+            json!({"model": "auto", "messages": [], "tool_choice": "auto"}),
+            json!({"model": "auto", "messages": [], "tools": [weather_tool()], "tool_choice": "always"}),
+            json!({"model": "auto", "messages": [], "tools": [weather_tool()],
+                "tool_choice": {"type": "function", "function": {"name": "get_time"}}}),
+            json!({"model": "auto", "messages": [{"role": "tool", "content": "18C"}]}),
+        ];
+        for body in bad {
+            assert!(validate(&request(body.clone())).is_err(), "accepted {}", body);
+        }
+    }
+
+    #[test]
+    fn tool_choice_none_needs_no_tool_model() {
+        let request = request(json!({
+            "model": "auto",
+            "messages": [],
+            "tools": [weather_tool()],
+            "tool_choice": "none"
+        }));
+        assert_eq!(validate(&request), Ok(()));
+        assert!(!wants_tools(&request));
+    }
+
+    #[tokio::test]
+    async fn tool_requests_are_validated_and_skip_models_without_tools() {
+        let mut upstream = mockito::Server::new_async().await;
+        let free = json!({"prompt": "0", "completion": "0"});
+        upstream
+            .mock("GET", "/openrouter")
+            .with_body(
+                json!({"data": [
+                    {"id": "chat:free", "pricing": free, "supported_parameters": ["temperature"]}
+                ]})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        // Ollama's listing doesn't say whether a model can call tools
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        let chat = upstream
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::Regex("get_weather".to_string()))
+            .with_body(r#"{"choices": [{"message": {"role": "assistant", "content": "Sunny"}}]}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let state = AppState {
+            scanner: FreeModelScanner::new()
+                .with_openrouter_url(&format!("{}/openrouter", upstream.url()))
+                .with_opencode_zen_api_url(&upstream.url())
+                .with_opencode_zen_docs_url(&upstream.url())
+                .with_ollama_url(&upstream.url()),
+            ..AppState::default()
+        };
+        let server = TestServer::new(create_router_with_state(state)).unwrap();
+        let tool = json!({"type": "function", "function": {"name": "get_weather"}});
+        let messages = json!([{"role": "user", "content": "Weather?"}]);
+
+        let response = server
+            .post("/v1/chat/completions")
+            .json(&json!({"model": "chat:free", "messages": messages, "tools": [tool]}))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("does not support tool calling"));
+
+        // Models that don't report tool support are still tried
+        let response = server
+            .post("/v1/chat/completions")
+            .json(&json!({"model": "auto", "messages": messages, "tools": [tool]}))
+            .await;
+        response.assert_status_ok();
+        response.assert_header("x-multiai-model-used", "llama3");
+        chat.assert_async().await;
+
+        let response = server
+            .post("/v1/chat/completions")
+            .json(&json!({"model": "auto", "messages": messages, "tools": [{"type": "function"}]}))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert!(body["error"]["message"].as_str().unwrap().contains("tools[0]"));
+    }
+}
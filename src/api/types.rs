@@ -1,50 +1,191 @@
 //! Request and response types for the OpenAI-compatible API.
 
-use crate::scanner::Source;
+use crate::ab_test::Arm;
+use crate::bench::BenchJob;
+use crate::embedding_cache::EmbeddingCacheStats;
+use crate::evals::{Dataset, EvalScore};
+use crate::leaderboard::LeaderboardEntry;
+use crate::routing::{ModelScore, RoutingStrategy};
+use crate::scanner::{FreeModel, Source, SourceStatus};
+use crate::scheduler::JobStatus;
+use crate::templates::PromptTemplate;
+use crate::virtual_keys::{KeyUsage, VirtualKey};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct HealthResponse {
+    /// "ok", or "degraded" when a source scan or the chat DB is failing.
     pub status: &'static str,
     pub app: &'static str,
     pub version: &'static str,
+    pub uptime_secs: u64,
+    pub sources: BTreeMap<Source, SourceStatus>,
+    pub chat_db: ComponentHealth,
+    pub inspector: InspectorHealth,
+    /// API keys configured for each source that sends one, by source name.
+    pub keys: BTreeMap<String, usize>,
+    /// Last-run status of each `[scheduler]` job, by name.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub scheduler: BTreeMap<String, JobStatus>,
+    /// Embedding cache hit rate and size, when the cache is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding_cache: Option<EmbeddingCacheStats>,
 }
 
-#[derive(Serialize)]
+/// Liveness/readiness probe result.
+#[derive(Serialize, ToSchema)]
+pub struct ProbeResponse {
+    pub status: &'static str,
+    /// Why the gateway is not ready.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ComponentHealth {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct InspectorHealth {
+    pub enabled: bool,
+    pub transactions: usize,
+    /// Transactions with no response or a 4xx/5xx status.
+    pub errors: usize,
+}
+
+/// Capability filters for `/v1/models`. Models whose source doesn't report
+/// a capability are left out when it is asked for.
+#[derive(Deserialize, IntoParams, Default)]
+pub struct ModelsQuery {
+    /// Only models that accept `tools` (true) or that don't (false).
+    pub tools: Option<bool>,
+    /// Only models that take image input (true) or that don't (false).
+    pub vision: Option<bool>,
+    /// Only models with at least this many tokens of context.
+    pub min_context: Option<u64>,
+}
+
+impl ModelsQuery {
+    pub fn matches(&self, model: &FreeModel) -> bool {
+        let vision = model.modalities.iter().any(|m| m == "image");
+        self.tools.is_none_or(|tools| match model.supports_tools {
+            Some(supported) => supported == tools,
+            None => !tools,
+        }) && self.vision.is_none_or(|wanted| vision == wanted)
+            && self
+                .min_context
+                .is_none_or(|min| model.context_length.is_some_and(|c| c >= min))
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct ModelsResponse {
     pub object: &'static str,
     pub data: Vec<ModelInfo>,
 }
 
-#[derive(Serialize)]
+/// Scan status of each model source, from `/v1/sources`.
+#[derive(Serialize, ToSchema)]
+pub struct SourcesResponse {
+    /// Whether a full scan has completed since startup.
+    pub scanned: bool,
+    /// Sources that are configured, by name. Sources without a key or
+    /// server are not scanned and are absent.
+    pub sources: BTreeMap<Source, SourceStatus>,
+}
+
+/// Everything the gateway knows about one free model, from `/v1/models/{id}`.
+#[derive(Serialize, ToSchema)]
+pub struct ModelDetail {
+    pub id: String,
+    pub object: &'static str,
+    pub owned_by: String,
+    pub source: Source,
+    pub endpoint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_length: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub modalities: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_tools: Option<bool>,
+    /// Whether the source needs an API key; local servers and custom
+    /// endpoints don't.
+    pub requires_api_key: bool,
+    pub api_key_configured: bool,
+    /// Rolling success rate and latency used to rank `auto` routing, when
+    /// the model has had requests in the last hour.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<ModelScore>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limited_for_secs: Option<u64>,
+    pub recent: RecentRequests,
+}
+
+/// Requests in the traffic inspector that were routed to one model.
+#[derive(Serialize, ToSchema, Debug, PartialEq)]
+pub struct RecentRequests {
+    pub requests: usize,
+    /// Requests with no response or a 4xx/5xx status.
+    pub errors: usize,
+    pub p50_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    pub avg_ttfb_ms: Option<u64>,
+    pub last_used: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct GroupedModelsResponse {
     pub models: Vec<GroupedModel>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct GroupedModel {
     pub name: String,
     pub providers: Vec<ProviderOption>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct ProviderOption {
     pub id: String,
     pub source: Source,
     pub endpoint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_length: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modalities: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supports_tools: Option<bool>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ModelInfo {
     pub id: String,
     pub object: &'static str,
     pub created: i64,
     pub owned_by: String,
+    /// Maximum context window in tokens, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_length: Option<u64>,
+    /// Input modalities such as "text" and "image", when known.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub modalities: Vec<String>,
+    /// Whether the model accepts `tools`, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_tools: Option<bool>,
+    /// Seconds left in the model's cooldown after its provider answered 429.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limited_for_secs: Option<u64>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 pub struct ChatRequest {
     pub model: String,
+    #[serde(default)]
     pub messages: Vec<ChatMessage>,
     #[serde(default)]
     pub temperature: Option<f32>,
@@ -52,33 +193,235 @@ pub struct ChatRequest {
     pub max_tokens: Option<u32>,
     #[serde(default)]
     pub stream: bool,
+    /// Prompt template to expand in front of `messages`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Values for the template's placeholders.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+    /// How `auto` picks a model for this request, overriding `[routing] strategy`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing_strategy: Option<RoutingStrategy>,
+    /// OpenAI function tools, forwarded to models that support tool calling.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<serde_json::Value>>,
+    /// "none", "auto", "required" or `{"type": "function", "function": {"name": ...}}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<serde_json::Value>,
+    /// `{"type": "json_object"}` or `{"type": "json_schema", ...}`; enforced by
+    /// the gateway for models that don't support it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<serde_json::Value>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema, Default)]
 pub struct ChatMessage {
     pub role: String,
+    /// Null on assistant messages that only call tools.
+    #[serde(default, deserialize_with = "null_as_empty")]
     pub content: String,
+    /// Tool calls made by an assistant message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<serde_json::Value>>,
+    /// The call a `tool` message answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+fn null_as_empty<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Body of `POST /v1/embeddings`.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct EmbeddingRequest {
+    pub model: String,
+    pub input: EmbeddingInput,
+}
+
+/// One text or a batch of texts to embed.
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum EmbeddingInput {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// OpenAI-style embeddings response from an upstream.
+#[derive(Deserialize)]
+pub struct UpstreamEmbeddings {
+    pub data: Vec<UpstreamEmbedding>,
+    #[serde(default)]
+    pub usage: Option<EmbeddingUsage>,
+}
+
+#[derive(Deserialize)]
+pub struct UpstreamEmbedding {
+    pub index: usize,
+    pub embedding: Vec<f32>,
 }
 
 #[derive(Deserialize)]
+pub struct EmbeddingUsage {
+    #[serde(default)]
+    pub prompt_tokens: u64,
+}
+
+impl EmbeddingInput {
+    pub fn into_texts(self) -> Vec<String> {
+        match self {
+            Self::One(text) => vec![text],
+            Self::Many(texts) => texts,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TemplatesResponse {
+    pub templates: Vec<PromptTemplate>,
+}
+
+/// Body of `PUT /api/templates/{name}`; the name comes from the path.
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateTemplateRequest {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub system: Option<String>,
+    pub prompt: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ExpandTemplateRequest {
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExpandTemplateResponse {
+    pub messages: Vec<ChatMessage>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BenchJobsResponse {
+    pub jobs: Vec<BenchJob>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DatasetsResponse {
+    pub datasets: Vec<Dataset>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct EvalHistoryQuery {
+    /// Only scores for this model.
+    pub model: Option<String>,
+    /// Only scores from this dataset.
+    pub dataset: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EvalHistoryResponse {
+    pub scores: Vec<EvalScore>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct LeaderboardQuery {
+    /// Days of comparisons to aggregate (default 7).
+    pub days: Option<u32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LeaderboardResponse {
+    /// Start of the aggregated window.
+    pub since: chrono::DateTime<chrono::Utc>,
+    /// Best first: by win rate, then mean judge score.
+    pub models: Vec<LeaderboardEntry>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct KeysResponse {
+    pub keys: Vec<VirtualKey>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct KeyUsageQuery {
+    /// Days of history, counting today (default 30).
+    pub days: Option<u32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct KeyUsageResponse {
+    pub name: String,
+    /// Daily usage, newest first; days without requests are omitted.
+    pub usage: Vec<KeyUsage>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteResponse {
+    pub deleted: bool,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AbFeedbackRequest {
+    pub transaction_id: String,
+    /// True for a good answer, false for a bad one.
+    pub positive: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AbFeedbackResponse {
+    pub arm: Arm,
+}
+
+#[derive(Deserialize, IntoParams)]
 pub struct InspectQuery {
     pub format: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ClearResponse {
     pub cleared: bool,
     pub count: usize,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct SettingsResponse {
     pub openrouter_configured: bool,
     pub opencode_zen_configured: bool,
+    pub start_at_login: bool,
+    /// Whether `/v1` and `/api` routes require the gateway key.
+    pub gateway_api_key_configured: bool,
+    /// The gateway key, only in the response that generated it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway_api_key: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateSettingsRequest {
     pub openrouter_api_key: Option<String>,
     pub opencode_zen_api_key: Option<String>,
+    pub start_at_login: Option<bool>,
+    /// Set the gateway key; an empty string turns auth off.
+    #[serde(default)]
+    pub gateway_api_key: Option<String>,
+    /// Generate a new random gateway key, returned once in the response.
+    #[serde(default)]
+    pub generate_gateway_api_key: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct SpendingQuery {
+    /// How many of this month's top spending models to list.
+    pub top: Option<usize>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct LogsTailQuery {
+    pub lines: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LogsTailResponse {
+    pub lines: Vec<String>,
 }
@@ -0,0 +1,475 @@
+//! Token accounting for streamed completions.
+//!
+//! Many free providers omit `usage` from streamed responses. `UsageTee` sits
+//! between the upstream body and the client, counting generated text as it
+//! passes so the inspector always has token counts, and rebuilding the
+//! completion so the inspector can show the reply. When enabled it also
+//! injects an OpenAI-style usage chunk (empty `choices`, `usage` set) just
+//! before `data: [DONE]` if the upstream never sent one.
+
+use super::types::ChatMessage;
+use crate::inspector::CapturedTransaction;
+use axum::body::Bytes;
+use serde_json::{json, Value};
+
+/// Most tool calls kept from one streamed reply; fragments with a higher
+/// index are ignored rather than growing the list to match.
+const MAX_TOOL_CALLS: usize = 64;
+
+/// Approximate token count for `text` (about four characters per token).
+pub fn estimate_tokens(text: &str) -> u32 {
+    (text.chars().count() as u32).div_ceil(4)
+}
+
+/// Approximate prompt tokens for a chat request.
+pub fn estimate_prompt_tokens(messages: &[ChatMessage]) -> u32 {
+    messages
+        .iter()
+        .map(|m| estimate_tokens(&m.role) + estimate_tokens(&m.content))
+        .sum()
+}
+
+/// Prompt and completion tokens of a finished transaction: the counts
+/// recorded for streams, else the `usage` of a non-streaming response body.
+pub fn token_usage(transaction: &CapturedTransaction) -> (u64, u64) {
+    let timing = &transaction.timing;
+    if let (Some(prompt), Some(completion)) = (timing.prompt_tokens, timing.completion_tokens) {
+        return (prompt.into(), completion.into());
+    }
+    let usage = transaction
+        .response
+        .as_ref()
+        .and_then(|r| r.body.as_ref())
+        .map(|body| &body["usage"]);
+    let count = |field: &str| usage.and_then(|u| u[field].as_u64()).unwrap_or(0);
+    (count("prompt_tokens"), count("completion_tokens"))
+}
+
+/// Token counts for a finished stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    /// False when the counts are our own estimate.
+    pub reported: bool,
+}
+
+/// Watches an SSE stream of chat completion chunks and counts tokens.
+pub struct UsageTee {
+    pending: Vec<u8>,
+    completion_chars: usize,
+    content: String,
+    prompt_tokens: u32,
+    reported: Option<(u32, u32)>,
+    synthesize: bool,
+    injected: bool,
+    id: Option<String>,
+    model: Option<String>,
+    finish_reason: Option<String>,
+    /// Tool calls assembled from their streamed fragments, by index.
+    tool_calls: Vec<Value>,
+}
+
+impl UsageTee {
+    /// `prompt_tokens` is the estimate used when the upstream reports none.
+    pub fn new(prompt_tokens: u32, synthesize: bool) -> Self {
+        Self {
+            pending: Vec::new(),
+            completion_chars: 0,
+            content: String::new(),
+            prompt_tokens,
+            reported: None,
+            synthesize,
+            injected: false,
+            id: None,
+            model: None,
+            finish_reason: None,
+            tool_calls: Vec::new(),
+        }
+    }
+
+    /// Inspect a chunk from the upstream and return the bytes to forward.
+    ///
+    /// Without synthesis the chunk is forwarded untouched. With it, only
+    /// complete lines are forwarded so the usage chunk can be placed before
+    /// `[DONE]`.
+    pub fn process(&mut self, chunk: Bytes) -> Bytes {
+        self.pending.extend_from_slice(&chunk);
+        let mut out = Vec::new();
+        while let Some(pos) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=pos).collect();
+            let done = self.inspect_line(&String::from_utf8_lossy(&line));
+            if self.synthesize {
+                if done {
+                    out.extend_from_slice(&self.usage_event());
+                }
+                out.extend_from_slice(&line);
+            }
+        }
+        if self.synthesize {
+            Bytes::from(out)
+        } else {
+            chunk
+        }
+    }
+
+    /// Flush whatever is left when the upstream ends, adding the usage chunk
+    /// if the stream ended without `[DONE]`.
+    pub fn finish(&mut self) -> Option<Bytes> {
+        let rest = std::mem::take(&mut self.pending);
+        if !rest.is_empty() {
+            self.inspect_line(&String::from_utf8_lossy(&rest));
+        }
+        if !self.synthesize {
+            return None;
+        }
+        let mut out = rest;
+        if !out.is_empty() && !out.ends_with(b"\n") {
+            out.extend_from_slice(b"\n\n");
+        }
+        out.extend_from_slice(&self.usage_event());
+        (!out.is_empty()).then(|| Bytes::from(out))
+    }
+
+    /// Token counts seen so far, preferring those reported by the upstream.
+    pub fn usage(&self) -> StreamUsage {
+        match self.reported {
+            Some((prompt_tokens, completion_tokens)) => StreamUsage {
+                prompt_tokens,
+                completion_tokens,
+                reported: true,
+            },
+            None => StreamUsage {
+                prompt_tokens: self.prompt_tokens,
+                completion_tokens: (self.completion_chars as u32).div_ceil(4),
+                reported: false,
+            },
+        }
+    }
+
+    /// Reply text streamed so far, without reasoning.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// The streamed reply as the `chat.completion` a non-streaming request
+    /// would have returned, with the token counts from [`Self::usage`].
+    pub fn completion(&self) -> Value {
+        let usage = self.usage();
+        let mut message = json!({ "role": "assistant", "content": self.content });
+        if !self.tool_calls.is_empty() {
+            message["tool_calls"] = json!(self.tool_calls);
+        }
+        json!({
+            "id": self.id,
+            "object": "chat.completion",
+            "model": self.model,
+            "choices": [{
+                "index": 0,
+                "message": message,
+                "finish_reason": self.finish_reason,
+            }],
+            "usage": {
+                "prompt_tokens": usage.prompt_tokens,
+                "completion_tokens": usage.completion_tokens,
+                "total_tokens": usage.prompt_tokens + usage.completion_tokens,
+            },
+        })
+    }
+
+    /// Merge a streamed tool call fragment into the call at its index.
+    fn add_tool_call(&mut self, fragment: &Value) {
+        let index = fragment["index"].as_u64().unwrap_or(0);
+        let Some(index) = usize::try_from(index).ok().filter(|&i| i < MAX_TOOL_CALLS) else {
+            return;
+        };
+        while self.tool_calls.len() <= index {
+            self.tool_calls.push(json!({
+                "id": null,
+                "type": "function",
+                "function": { "name": "", "arguments": "" },
+            }));
+        }
+        let call = &mut self.tool_calls[index];
+        if let Some(id) = fragment["id"].as_str() {
+            call["id"] = json!(id);
+        }
+        let function = &fragment["function"];
+        for field in ["name", "arguments"] {
+            if let Some(part) = function[field].as_str() {
+                if let Value::String(so_far) = &mut call["function"][field] {
+                    so_far.push_str(part);
+                }
+            }
+        }
+    }
+
+    /// Record what a line carries. Returns true for the `[DONE]` marker.
+    fn inspect_line(&mut self, line: &str) -> bool {
+        let Some(data) = line.trim().strip_prefix("data:") else {
+            return false;
+        };
+        let data = data.trim();
+        if data == "[DONE]" {
+            return true;
+        }
+        let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+            return false;
+        };
+
+        if self.id.is_none() {
+            self.id = chunk["id"].as_str().map(String::from);
+        }
+        if self.model.is_none() {
+            self.model = chunk["model"].as_str().map(String::from);
+        }
+        if let Some(usage) = chunk.get("usage").filter(|u| u.is_object()) {
+            let count = |field: &str| usage[field].as_u64().unwrap_or(0) as u32;
+            self.reported = Some((count("prompt_tokens"), count("completion_tokens")));
+        }
+        for choice in chunk["choices"].as_array().into_iter().flatten() {
+            let delta = &choice["delta"];
+            if let Some(text) = delta["content"].as_str() {
+                self.content.push_str(text);
+            }
+            if let Some(reason) = choice["finish_reason"].as_str() {
+                self.finish_reason = Some(reason.to_string());
+            }
+            for fragment in delta["tool_calls"].as_array().into_iter().flatten() {
+                self.add_tool_call(fragment);
+            }
+            for field in ["content", "reasoning", "reasoning_content"] {
+                if let Some(text) = delta[field].as_str() {
+                    self.completion_chars += text.chars().count();
+                }
+            }
+        }
+        false
+    }
+
+    /// The synthetic usage chunk, once, and only if the upstream sent none.
+    fn usage_event(&mut self) -> Vec<u8> {
+        if !self.synthesize || self.injected || self.reported.is_some() {
+            return Vec::new();
+        }
+        self.injected = true;
+        let usage = self.usage();
+        let chunk = json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "model": self.model,
+            "choices": [],
+            "usage": {
+                "prompt_tokens": usage.prompt_tokens,
+                "completion_tokens": usage.completion_tokens,
+                "total_tokens": usage.prompt_tokens + usage.completion_tokens,
+            },
+        });
+        format!("data: {}\n\n", chunk).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tests::test_state;
+    use crate::api::{create_router_with_state, AppState};
+    use axum_test::TestServer;
+
+    fn content_chunk(text: &str) -> String {
+        format!(
+            "data: {}\n\n",
+            json!({"id": "gen-1", "model": "m", "choices": [{"delta": {"content": text}}]})
+        )
+    }
+
+    fn run(tee: &mut UsageTee, chunks: &[&str]) -> String {
+        let mut out = Vec::new();
+        for chunk in chunks {
+            out.extend_from_slice(&tee.process(Bytes::from(chunk.to_string())));
+        }
+        if let Some(rest) = tee.finish() {
+            out.extend_from_slice(&rest);
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn estimates_tokens_from_characters() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+
+    #[test]
+    fn counts_content_split_across_chunks() {
+        let mut tee = UsageTee::new(10, false);
+        let stream = format!(
+            "{}{}data: [DONE]\n\n",
+            content_chunk("Hello"),
+            content_chunk(" world!")
+        );
+        let (a, b) = stream.split_at(20);
+        let out = run(&mut tee, &[a, b]);
+
+        assert_eq!(out, stream);
+        let usage = tee.usage();
+        assert_eq!(usage.prompt_tokens, 10);
+        assert_eq!(usage.completion_tokens, 3);
+        assert!(!usage.reported);
+        assert_eq!(tee.content(), "Hello world!");
+    }
+
+    #[test]
+    fn injects_usage_before_done_when_enabled() {
+        let mut tee = UsageTee::new(7, true);
+        let out = run(&mut tee, &[&content_chunk("abcdefgh"), "data: [DONE]\n\n"]);
+
+        let usage_at = out.find("\"usage\"").expect("usage chunk injected");
+        assert!(usage_at < out.find("[DONE]").unwrap());
+        assert!(out.contains("\"completion_tokens\":2"));
+        assert!(out.contains("\"total_tokens\":9"));
+        assert!(out.contains("\"id\":\"gen-1\""));
+    }
+
+    #[test]
+    fn keeps_upstream_usage() {
+        let mut tee = UsageTee::new(7, true);
+        let reported = format!(
+            "data: {}\n\n",
+            json!({"choices": [], "usage": {"prompt_tokens": 12, "completion_tokens": 34}})
+        );
+        let out = run(
+            &mut tee,
+            &[&content_chunk("hi"), &reported, "data: [DONE]\n\n"],
+        );
+
+        assert_eq!(out.matches("\"usage\"").count(), 1);
+        let usage = tee.usage();
+        assert_eq!((usage.prompt_tokens, usage.completion_tokens), (12, 34));
+        assert!(usage.reported);
+    }
+
+    #[test]
+    fn rebuilds_the_completion_with_tool_calls() {
+        let mut tee = UsageTee::new(5, false);
+        let call = |delta: Value| format!("data: {}\n\n", json!({"choices": [{"delta": delta}]}));
+        let finish = format!(
+            "data: {}\n\n",
+            json!({"choices": [{"delta": {}, "finish_reason": "tool_calls"}]})
+        );
+        run(
+            &mut tee,
+            &[
+                &content_chunk("Checking"),
+                &call(json!({"tool_calls": [{
+                    "index": 0, "id": "call_1",
+                    "function": {"name": "get_weather", "arguments": "{\"ci"}
+                }]})),
+                &call(json!({"tool_calls": [{
+                    "index": 0,
+                    "function": {"arguments": "ty\":\"Paris\"}"}
+                }]})),
+                // A bogus index doesn't allocate a call per index
+                &call(json!({"tool_calls": [{
+                    "index": 4_000_000_000u64,
+                    "function": {"name": "flood"}
+                }]})),
+                &finish,
+                "data: [DONE]\n\n",
+            ],
+        );
+
+        let completion = tee.completion();
+        assert_eq!(completion["id"], "gen-1");
+        assert_eq!(completion["object"], "chat.completion");
+        let choice = &completion["choices"][0];
+        assert_eq!(choice["message"]["content"], "Checking");
+        assert_eq!(choice["finish_reason"], "tool_calls");
+        assert_eq!(choice["message"]["tool_calls"].as_array().unwrap().len(), 1);
+        let call = &choice["message"]["tool_calls"][0];
+        assert_eq!(call["id"], "call_1");
+        assert_eq!(call["function"]["name"], "get_weather");
+        assert_eq!(call["function"]["arguments"], "{\"city\":\"Paris\"}");
+        assert_eq!(completion["usage"]["prompt_tokens"], 5);
+    }
+
+    #[test]
+    fn injects_usage_when_stream_ends_without_done() {
+        let mut tee = UsageTee::new(1, true);
+        let out = run(&mut tee, &[&content_chunk("abcd")]);
+        assert!(out.ends_with("\n\n"));
+        assert!(out.contains("\"completion_tokens\":1"));
+    }
+
+    #[test]
+    fn token_usage_prefers_recorded_counts_then_response_usage() {
+        use crate::inspector::{CapturedRequest, CapturedResponse, TrafficInspector};
+
+        let inspector = TrafficInspector::new();
+        let mut tx = inspector.start_transaction(CapturedRequest {
+            method: "POST".to_string(),
+            url: "/v1/chat/completions".to_string(),
+            headers: vec![],
+            body: None,
+        });
+        assert_eq!(token_usage(&tx), (0, 0));
+
+        tx.response = Some(CapturedResponse {
+            status: 200,
+            headers: vec![],
+            body: Some(json!({"usage": {"prompt_tokens": 12, "completion_tokens": 5}})),
+        });
+        assert_eq!(token_usage(&tx), (12, 5));
+
+        inspector.record_tokens(&mut tx, 20, 8);
+        assert_eq!(token_usage(&tx), (20, 8));
+    }
+
+    #[tokio::test]
+    async fn streaming_chat_records_and_synthesizes_usage() {
+        let mut upstream = mockito::Server::new_async().await;
+        upstream
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models": [{"name": "llama3"}]}"#)
+            .create_async()
+            .await;
+        upstream
+            .mock("POST", "/v1/chat/completions")
+            .with_header("content-type", "text/event-stream")
+            .with_body(concat!(
+                "data: {\"id\":\"c1\",\"choices\":[{\"delta\":{\"content\":\"Hello there\"}}]}\n\n",
+                "data: [DONE]\n\n"
+            ))
+            .create_async()
+            .await;
+        let state = AppState {
+            synthesize_stream_usage: true,
+            ..test_state(&upstream)
+        };
+        let server = TestServer::new(create_router_with_state(state.clone())).unwrap();
+
+        let response = server
+            .post("/v1/chat/completions")
+            .json(&json!({
+                "model": "llama3",
+                "messages": [{"role": "user", "content": "Hello"}],
+                "stream": true
+            }))
+            .await;
+
+        let body = response.text();
+        assert!(body.contains("\"completion_tokens\":3"), "{}", body);
+        assert!(body.ends_with("data: [DONE]\n\n"));
+        let transactions = state.inspector.get_all();
+        let timing = &transactions.last().unwrap().timing;
+        assert_eq!(timing.completion_tokens, Some(3));
+        assert!(timing.prompt_tokens.is_some());
+        assert!(timing.ttfb_ms.is_some());
+        assert!(timing.chunks.is_some_and(|chunks| chunks.count >= 1));
+        let recorded = transactions.last().unwrap().response.as_ref().unwrap();
+        let completion = recorded.body.as_ref().unwrap();
+        assert_eq!(completion["choices"][0]["message"]["content"], "Hello there");
+        assert_eq!(completion["usage"]["completion_tokens"], 3);
+    }
+}
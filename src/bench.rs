@@ -0,0 +1,433 @@
+//! Benchmark jobs behind `POST /v1/bench`.
+//!
+//! A suite runs each prompt against the chosen models `iterations` times
+//! through the comparator. Jobs run in the background and clients poll
+//! `GET /v1/bench/{id}` for progress. Finished jobs are saved to SQLite so
+//! the web UI can chart latency and quality across past runs.
+
+use crate::mcp::{CompareParams, CompareResult, ModelComparator};
+use crate::storage;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+/// Most iterations a single job may run.
+pub const MAX_ITERATIONS: usize = 20;
+
+/// Finished jobs returned by `GET /v1/bench`.
+const RECENT_JOBS: usize = 50;
+
+/// A benchmark suite submitted to `POST /v1/bench`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct BenchRequest {
+    pub prompts: Vec<String>,
+    /// Models to benchmark (default: the comparator's pick of free models).
+    #[serde(default)]
+    pub models: Option<Vec<String>>,
+    #[serde(default)]
+    pub max_models: Option<usize>,
+    /// Times each prompt is run against every model.
+    #[serde(default = "default_iterations")]
+    pub iterations: usize,
+    /// Score answers with the judge panel (slower, may cost judge credits).
+    #[serde(default)]
+    pub quality: bool,
+}
+
+fn default_iterations() -> usize {
+    1
+}
+
+impl BenchRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.prompts.is_empty() {
+            return Err("Benchmark needs at least one prompt".to_string());
+        }
+        if self.iterations == 0 || self.iterations > MAX_ITERATIONS {
+            return Err(format!(
+                "iterations must be between 1 and {}",
+                MAX_ITERATIONS
+            ));
+        }
+        Ok(())
+    }
+
+    /// Comparisons the job will run.
+    pub fn steps(&self) -> usize {
+        self.prompts.len() * self.iterations
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BenchStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Latency and quality of one model across a job.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ModelBench {
+    pub model: String,
+    /// Successful responses.
+    pub samples: usize,
+    pub failures: usize,
+    pub mean_ttft_ms: u64,
+    pub p50_total_ms: u64,
+    pub p95_total_ms: u64,
+    pub mean_tokens_per_sec: f64,
+    /// Mean judge score, when quality scoring was requested.
+    pub mean_quality: Option<f64>,
+    /// Total latency of every successful response, in run order.
+    pub total_ms: Vec<u64>,
+}
+
+/// A benchmark job and, once finished, its results.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct BenchJob {
+    pub id: String,
+    pub status: BenchStatus,
+    pub request: BenchRequest,
+    /// Comparisons finished so far, out of `total`.
+    pub completed: usize,
+    pub total: usize,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub results: Vec<ModelBench>,
+    /// Comparisons that failed outright.
+    pub errors: Vec<String>,
+}
+
+/// One model response, or failure, during a job.
+#[derive(Debug, Clone, PartialEq)]
+struct Sample {
+    model: String,
+    outcome: Option<Timing>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Timing {
+    ttft_ms: u64,
+    total_ms: u64,
+    tokens_per_sec: f64,
+    quality: Option<f64>,
+}
+
+fn samples_from(result: &CompareResult) -> impl Iterator<Item = Sample> + '_ {
+    let answered = result.results.iter().map(|r| Sample {
+        model: r.model.clone(),
+        outcome: Some(Timing {
+            ttft_ms: r.metrics.ttft_ms,
+            total_ms: r.metrics.total_ms,
+            tokens_per_sec: r.metrics.tokens_per_sec,
+            quality: (!r.judge_scores.is_empty()).then_some(r.scores.quality),
+        }),
+    });
+    let failed = result.failures.iter().map(|f| Sample {
+        model: f.model.clone(),
+        outcome: None,
+    });
+    answered.chain(failed)
+}
+
+/// Per-model statistics, by model name.
+fn summarize(samples: &[Sample]) -> Vec<ModelBench> {
+    let mut by_model: BTreeMap<&str, Vec<&Sample>> = BTreeMap::new();
+    for sample in samples {
+        by_model.entry(&sample.model).or_default().push(sample);
+    }
+
+    by_model
+        .into_iter()
+        .map(|(model, samples)| {
+            let timings: Vec<&Timing> = samples.iter().filter_map(|s| s.outcome.as_ref()).collect();
+            let n = timings.len();
+            let total_ms: Vec<u64> = timings.iter().map(|t| t.total_ms).collect();
+            let mut sorted = total_ms.clone();
+            sorted.sort_unstable();
+            let qualities: Vec<f64> = timings.iter().filter_map(|t| t.quality).collect();
+            ModelBench {
+                model: model.to_string(),
+                samples: n,
+                failures: samples.len() - n,
+                mean_ttft_ms: if n > 0 {
+                    timings.iter().map(|t| t.ttft_ms).sum::<u64>() / n as u64
+                } else {
+                    0
+                },
+                p50_total_ms: percentile(&sorted, 50),
+                p95_total_ms: percentile(&sorted, 95),
+                mean_tokens_per_sec: if n > 0 {
+                    timings.iter().map(|t| t.tokens_per_sec).sum::<f64>() / n as f64
+                } else {
+                    0.0
+                },
+                mean_quality: (!qualities.is_empty())
+                    .then(|| qualities.iter().sum::<f64>() / qualities.len() as f64),
+                total_ms,
+            }
+        })
+        .collect()
+}
+
+/// Nearest-rank percentile of sorted values, 0 when there are none.
+fn percentile(sorted: &[u64], p: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Running jobs in memory and finished ones in SQLite, shared across handlers.
+#[derive(Clone)]
+pub struct Benchmarks {
+    running: Arc<Mutex<HashMap<String, BenchJob>>>,
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Benchmarks {
+    /// Open the store in MultiAI's data directory.
+    pub fn open_default() -> SqlResult<Self> {
+        Self::with_connection(storage::open_data_db("bench.db")?)
+    }
+
+    /// Open or create a benchmark database.
+    pub fn open<P: AsRef<Path>>(path: P) -> SqlResult<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Create an in-memory store (for testing).
+    pub fn in_memory() -> SqlResult<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> SqlResult<Self> {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS jobs (
+                id TEXT PRIMARY KEY,
+                created_at TEXT NOT NULL,
+                job TEXT NOT NULL
+            );
+            "#,
+        )?;
+        Ok(Self {
+            running: Arc::new(Mutex::new(HashMap::new())),
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Register a new job for `request`.
+    pub fn start(&self, request: BenchRequest) -> BenchJob {
+        let job = BenchJob {
+            id: uuid::Uuid::new_v4().to_string(),
+            status: BenchStatus::Running,
+            total: request.steps(),
+            request,
+            completed: 0,
+            created_at: Utc::now(),
+            finished_at: None,
+            results: Vec::new(),
+            errors: Vec::new(),
+        };
+        self.running
+            .lock()
+            .unwrap()
+            .insert(job.id.clone(), job.clone());
+        job
+    }
+
+    /// A running or finished job.
+    pub fn get(&self, id: &str) -> SqlResult<Option<BenchJob>> {
+        if let Some(job) = self.running.lock().unwrap().get(id) {
+            return Ok(Some(job.clone()));
+        }
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT job FROM jobs WHERE id = ?", params![id], row_to_job)
+            .optional()
+    }
+
+    /// Running jobs, then recent finished ones, newest first.
+    pub fn list(&self) -> SqlResult<Vec<BenchJob>> {
+        let mut jobs: Vec<BenchJob> = self.running.lock().unwrap().values().cloned().collect();
+        jobs.sort_by_key(|j| std::cmp::Reverse(j.created_at));
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT job FROM jobs ORDER BY created_at DESC LIMIT ?")?;
+        let finished = stmt.query_map(params![RECENT_JOBS as i64], row_to_job)?;
+        for job in finished {
+            jobs.push(job?);
+        }
+        Ok(jobs)
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut BenchJob)) {
+        if let Some(job) = self.running.lock().unwrap().get_mut(id) {
+            f(job);
+        }
+    }
+
+    /// Persist a job and stop tracking it as running.
+    fn finish(&self, mut job: BenchJob) -> SqlResult<()> {
+        job.finished_at = Some(Utc::now());
+        let json = serde_json::to_string(&job)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        self.conn.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO jobs (id, created_at, job) VALUES (?, ?, ?)",
+            params![job.id, job.created_at.to_rfc3339(), json],
+        )?;
+        self.running.lock().unwrap().remove(&job.id);
+        Ok(())
+    }
+
+    /// Run a started job to completion, updating its progress as it goes.
+    pub async fn run(&self, comparator: ModelComparator, id: String) {
+        let Some(job) = self.running.lock().unwrap().get(&id).cloned() else {
+            return;
+        };
+        let request = job.request;
+        let mut samples = Vec::new();
+        let mut errors = Vec::new();
+
+        for _ in 0..request.iterations {
+            for prompt in &request.prompts {
+                let params = CompareParams {
+                    prompt: prompt.clone(),
+                    models: request.models.clone(),
+                    max_models: request.max_models,
+                    include_ranking: request.quality,
+                    judges: None,
+                    weights: None,
+                    metrics: None,
+                    criteria: None,
+                    concurrency: None,
+                    timeout_secs: None,
+                    min_results: None,
+                };
+                match comparator.compare(params, None).await {
+                    Ok(result) => samples.extend(samples_from(&result)),
+                    Err(e) => errors.push(e),
+                }
+                let results = summarize(&samples);
+                self.update(&id, |job| {
+                    job.completed += 1;
+                    job.results = results;
+                    job.errors = errors.clone();
+                });
+            }
+        }
+
+        let Some(mut job) = self.running.lock().unwrap().get(&id).cloned() else {
+            return;
+        };
+        job.status = if samples.iter().any(|s| s.outcome.is_some()) {
+            BenchStatus::Completed
+        } else {
+            BenchStatus::Failed
+        };
+        if let Err(e) = self.finish(job) {
+            tracing::warn!("Failed to save benchmark {}: {}", id, e);
+        }
+    }
+}
+
+fn row_to_job(row: &rusqlite::Row) -> SqlResult<BenchJob> {
+    let json: String = row.get(0)?;
+    serde_json::from_str(&json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(model: &str, total_ms: Option<u64>, quality: Option<f64>) -> Sample {
+        Sample {
+            model: model.to_string(),
+            outcome: total_ms.map(|total_ms| Timing {
+                ttft_ms: total_ms / 10,
+                total_ms,
+                tokens_per_sec: 50.0,
+                quality,
+            }),
+        }
+    }
+
+    fn request(iterations: usize) -> BenchRequest {
+        BenchRequest {
+            prompts: vec!["a".to_string(), "b".to_string()],
+            models: None,
+            max_models: None,
+            iterations,
+            quality: false,
+        }
+    }
+
+    #[test]
+    fn summarizes_latency_and_quality_per_model() {
+        let samples = vec![
+            sample("m1", Some(100), Some(8.0)),
+            sample("m1", Some(300), Some(6.0)),
+            sample("m1", Some(200), None),
+            sample("m2", None, None),
+            sample("m2", Some(1000), None),
+        ];
+
+        let results = summarize(&samples);
+        let m1 = &results[0];
+        assert_eq!((m1.model.as_str(), m1.samples, m1.failures), ("m1", 3, 0));
+        assert_eq!(m1.mean_ttft_ms, 20);
+        assert_eq!((m1.p50_total_ms, m1.p95_total_ms), (200, 300));
+        assert_eq!(m1.mean_quality, Some(7.0));
+        assert_eq!(m1.total_ms, vec![100, 300, 200]);
+
+        let m2 = &results[1];
+        assert_eq!((m2.samples, m2.failures), (1, 1));
+        assert_eq!(m2.mean_quality, None);
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        assert_eq!(percentile(&[], 50), 0);
+        assert_eq!(percentile(&[5], 95), 5);
+        let values: Vec<u64> = (1..=20).collect();
+        assert_eq!(percentile(&values, 50), 10);
+        assert_eq!(percentile(&values, 95), 19);
+    }
+
+    #[test]
+    fn validates_requests() {
+        assert!(request(3).validate().is_ok());
+        assert!(request(0).validate().is_err());
+        assert!(request(MAX_ITERATIONS + 1).validate().is_err());
+        let mut empty = request(1);
+        empty.prompts.clear();
+        assert!(empty.validate().is_err());
+    }
+
+    #[test]
+    fn finished_jobs_are_persisted() {
+        let bench = Benchmarks::in_memory().unwrap();
+        let job = bench.start(request(2));
+        assert_eq!(job.total, 4);
+        assert_eq!(bench.list().unwrap().len(), 1);
+
+        let mut done = job.clone();
+        done.status = BenchStatus::Completed;
+        bench.finish(done).unwrap();
+
+        let stored = bench.get(&job.id).unwrap().unwrap();
+        assert_eq!(stored.status, BenchStatus::Completed);
+        assert!(stored.finished_at.is_some());
+        assert_eq!(bench.list().unwrap().len(), 1);
+        assert_eq!(bench.get("missing").unwrap(), None);
+    }
+}
@@ -5,6 +5,8 @@
 //! - Message management
 //! - Attachment handling
 
+use crate::storage;
+use crate::transcript::TranscriptWriter;
 use chrono::{DateTime, Utc};
 use rusqlite::{Connection, Result as SqlResult};
 use serde::{Deserialize, Serialize};
@@ -70,27 +72,50 @@ pub struct Attachment {
 }
 
 /// Chat database operations.
+#[derive(Debug)]
 pub struct ChatDb {
     conn: Connection,
+    transcripts: Option<TranscriptWriter>,
 }
 
 impl ChatDb {
+    /// Open the chat database in MultiAI's data directory.
+    pub fn open_default() -> SqlResult<Self> {
+        Self::with_connection(storage::open_data_db("chats.db")?)
+    }
+
     /// Open or create a chat database.
     pub fn open<P: AsRef<Path>>(path: P) -> SqlResult<Self> {
-        let conn = Connection::open(path)?;
-        let db = Self { conn };
-        db.init_schema()?;
-        Ok(db)
+        Self::with_connection(Connection::open(path)?)
     }
 
     /// Create an in-memory database (for testing).
     pub fn in_memory() -> SqlResult<Self> {
-        let conn = Connection::open_in_memory()?;
-        let db = Self { conn };
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> SqlResult<Self> {
+        let db = Self {
+            conn,
+            transcripts: None,
+        };
         db.init_schema()?;
         Ok(db)
     }
 
+    /// Also append every new message to a per-chat transcript file, when
+    /// given a writer.
+    pub fn with_transcripts(mut self, writer: Option<TranscriptWriter>) -> Self {
+        self.transcripts = writer;
+        self
+    }
+
+    /// Check that the database answers queries.
+    pub fn ping(&self) -> SqlResult<()> {
+        self.conn
+            .query_row("SELECT COUNT(*) FROM chats", [], |_| Ok(()))
+    }
+
     fn init_schema(&self) -> SqlResult<()> {
         self.conn.execute_batch(
             r#"
@@ -238,13 +263,19 @@ impl ChatDb {
             [&now_str, chat_id],
         )?;
 
-        Ok(Message {
+        let message = Message {
             id: id.to_string(),
             chat_id: chat_id.to_string(),
             role,
             content: content.to_string(),
             created_at: now,
-        })
+        };
+        if let Some(writer) = &self.transcripts {
+            if let Err(e) = writer.append(&message) {
+                tracing::warn!("Failed to write transcript for chat {}: {}", chat_id, e);
+            }
+        }
+        Ok(message)
     }
 
     /// Get all messages for a chat.
@@ -293,6 +324,12 @@ mod tests {
         assert!(chats.is_empty());
     }
 
+    #[test]
+    fn ping_succeeds_on_open_database() {
+        let db = ChatDb::in_memory().unwrap();
+        assert!(db.ping().is_ok());
+    }
+
     #[test]
     fn creates_and_retrieves_chat() {
         let db = ChatDb::in_memory().unwrap();
@@ -4,7 +4,7 @@ use super::types::*;
 use super::ChatState;
 use crate::chat::MessageRole;
 use crate::document::{extract_text, DocumentType};
-use crate::export::{export_chat, ExportChat, ExportFormat, ExportMessage};
+use crate::export::{export_chat, ExportChat, ExportFormat};
 use axum::{
     extract::{Multipart, Path, Query, State},
     http::{header, StatusCode},
@@ -21,6 +21,8 @@ fn lock_db(state: &ChatState) -> Result<MutexGuard<'_, crate::chat::ChatDb>, Res
     })
 }
 
+#[utoipa::path(get, path = "/api/chats", tag = "chats",
+    responses((status = 200, body = ChatsListResponse)))]
 pub async fn list_chats(State(state): State<Arc<ChatState>>) -> impl IntoResponse {
     let db = match lock_db(&state) {
         Ok(guard) => guard,
@@ -44,6 +46,9 @@ pub async fn list_chats(State(state): State<Arc<ChatState>>) -> impl IntoRespons
     }
 }
 
+#[utoipa::path(post, path = "/api/chats", tag = "chats",
+    request_body = CreateChatRequest,
+    responses((status = 201, body = CreateChatResponse)))]
 pub async fn create_chat(
     State(state): State<Arc<ChatState>>,
     Json(request): Json<CreateChatRequest>,
@@ -61,6 +66,12 @@ pub async fn create_chat(
     }
 }
 
+#[utoipa::path(get, path = "/api/chats/{id}", tag = "chats",
+    params(("id" = String, Path, description = "Chat ID")),
+    responses(
+        (status = 200, body = ChatDetailResponse),
+        (status = 404, body = ErrorResponse)
+    ))]
 pub async fn get_chat(
     State(state): State<Arc<ChatState>>,
     Path(id): Path<String>,
@@ -98,6 +109,12 @@ pub async fn get_chat(
     }
 }
 
+#[utoipa::path(delete, path = "/api/chats/{id}", tag = "chats",
+    params(("id" = String, Path, description = "Chat ID")),
+    responses(
+        (status = 200, body = DeleteResponse),
+        (status = 404, body = ErrorResponse)
+    ))]
 pub async fn delete_chat(
     State(state): State<Arc<ChatState>>,
     Path(id): Path<String>,
@@ -119,6 +136,13 @@ pub async fn delete_chat(
     }
 }
 
+#[utoipa::path(patch, path = "/api/chats/{id}", tag = "chats",
+    params(("id" = String, Path, description = "Chat ID")),
+    request_body = UpdateChatRequest,
+    responses(
+        (status = 200, body = DeleteResponse),
+        (status = 404, body = ErrorResponse)
+    ))]
 pub async fn update_chat(
     State(state): State<Arc<ChatState>>,
     Path(id): Path<String>,
@@ -141,6 +165,13 @@ pub async fn update_chat(
     }
 }
 
+#[utoipa::path(post, path = "/api/chats/{id}/messages", tag = "chats",
+    params(("id" = String, Path, description = "Chat ID")),
+    request_body = SendMessageRequest,
+    responses(
+        (status = 201, body = SendMessageResponse),
+        (status = 404, body = ErrorResponse)
+    ))]
 pub async fn send_message(
     State(state): State<Arc<ChatState>>,
     Path(chat_id): Path<String>,
@@ -175,6 +206,15 @@ pub async fn send_message(
     }
 }
 
+#[utoipa::path(delete, path = "/api/chats/{id}/messages/{mid}", tag = "chats",
+    params(
+        ("id" = String, Path, description = "Chat ID"),
+        ("mid" = String, Path, description = "Message ID")
+    ),
+    responses(
+        (status = 200, body = DeleteResponse),
+        (status = 404, body = ErrorResponse)
+    ))]
 pub async fn delete_message(
     State(state): State<Arc<ChatState>>,
     Path((chat_id, msg_id)): Path<(String, String)>,
@@ -203,6 +243,14 @@ pub async fn delete_message(
     }
 }
 
+#[utoipa::path(post, path = "/api/chats/{id}/upload", tag = "chats",
+    params(("id" = String, Path, description = "Chat ID")),
+    request_body(content_type = "multipart/form-data", description = "A `file` field holding a PDF, DOCX or TXT document"),
+    responses(
+        (status = 201, body = UploadResponse),
+        (status = 400, body = ErrorResponse),
+        (status = 422, body = ErrorResponse)
+    ))]
 pub async fn upload_document(
     State(state): State<Arc<ChatState>>,
     Path(chat_id): Path<String>,
@@ -292,6 +340,12 @@ pub async fn upload_document(
     }
 }
 
+#[utoipa::path(get, path = "/api/chats/{id}/export", tag = "chats",
+    params(("id" = String, Path, description = "Chat ID"), ExportQuery),
+    responses(
+        (status = 200, description = "The exported file", content_type = "application/octet-stream", body = Vec<u8>),
+        (status = 404, body = ErrorResponse)
+    ))]
 pub async fn export_chat_handler(
     State(state): State<Arc<ChatState>>,
     Path(chat_id): Path<String>,
@@ -318,26 +372,14 @@ pub async fn export_chat_handler(
         Some(f) => f,
         None => {
             return ApiError::bad_request(format!(
-                "Unsupported format: {}. Use pdf, docx, or md",
+                "Unsupported format: {}. Use pdf, docx, md, or json",
                 format_str
             ))
             .into_response()
         }
     };
 
-    // Build export chat structure
-    let export = ExportChat {
-        title: chat.title.clone(),
-        created_at: chat.created_at.to_rfc3339(),
-        messages: messages
-            .into_iter()
-            .map(|m| ExportMessage {
-                role: m.role.to_string(),
-                content: m.content,
-                created_at: m.created_at.to_rfc3339(),
-            })
-            .collect(),
-    };
+    let export = ExportChat::from_chat(&chat, messages);
 
     // Generate export
     match export_chat(&export, format) {
@@ -10,6 +10,9 @@
 //! - DELETE /api/chats/:id/messages/:mid - Delete message
 //! - POST /api/chats/:id/upload - Upload document (PDF, DOCX, TXT)
 //! - GET /api/chats/:id/export - Export chat (PDF, DOCX, MD)
+//!
+//! The endpoints are described by [`ChatApiDoc`], which the gateway merges
+//! into its OpenAPI document.
 
 mod handlers;
 #[cfg(test)]
@@ -21,6 +24,7 @@ use axum::{
     Router,
 };
 use std::sync::{Arc, Mutex};
+use utoipa::OpenApi;
 
 use crate::chat::ChatDb;
 
@@ -35,6 +39,24 @@ impl ChatState {
     }
 }
 
+/// OpenAPI description of the chat endpoints.
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        handlers::list_chats,
+        handlers::create_chat,
+        handlers::get_chat,
+        handlers::delete_chat,
+        handlers::update_chat,
+        handlers::send_message,
+        handlers::delete_message,
+        handlers::upload_document,
+        handlers::export_chat_handler,
+    ),
+    tags((name = "chats", description = "Chat history used by the web UI"))
+)]
+pub struct ChatApiDoc;
+
 /// Create the chat API router (nested under /api).
 pub fn create_chat_router(state: Arc<ChatState>) -> Router<()> {
     Router::new()
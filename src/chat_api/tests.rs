@@ -508,3 +508,35 @@ async fn list_chats_returns_500_when_mutex_poisoned() {
     let body: serde_json::Value = response.json();
     assert!(body["error"].as_str().unwrap().contains("lock"));
 }
+
+// =========================================================================
+// Transcript Tests
+// =========================================================================
+
+#[tokio::test]
+async fn send_message_appends_to_transcript() {
+    use crate::config::TranscriptFormat;
+    use crate::transcript::TranscriptWriter;
+
+    let dir = tempfile::tempdir().unwrap();
+    let writer = TranscriptWriter::new(dir.path(), TranscriptFormat::Jsonl);
+    let db = ChatDb::in_memory().unwrap().with_transcripts(Some(writer.clone()));
+    let state = Arc::new(ChatState::new(db));
+    let app = create_chat_router(state);
+    let server = TestServer::new(app).unwrap();
+
+    let create_response = server.post("/api/chats").json(&json!({})).await;
+    let chat_id = create_response.json::<serde_json::Value>()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    server
+        .post(&format!("/api/chats/{}/messages", chat_id))
+        .json(&json!({"content": "Remember this"}))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    let transcript = std::fs::read_to_string(writer.path_for(&chat_id)).unwrap();
+    assert!(transcript.contains("Remember this"));
+}
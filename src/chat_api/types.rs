@@ -1,30 +1,31 @@
 //! Request and response types for the Chat API.
 
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ChatsListResponse {
     pub chats: Vec<ChatSummary>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ChatSummary {
     pub id: String,
     pub title: String,
     pub updated_at: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateChatRequest {
     pub title: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct CreateChatResponse {
     pub id: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ChatDetailResponse {
     pub id: String,
     pub title: String,
@@ -33,7 +34,7 @@ pub struct ChatDetailResponse {
     pub messages: Vec<MessageResponse>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct MessageResponse {
     pub id: String,
     pub role: String,
@@ -41,17 +42,17 @@ pub struct MessageResponse {
     pub created_at: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateChatRequest {
     pub title: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct SendMessageRequest {
     pub content: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct SendMessageResponse {
     pub id: String,
     pub role: String,
@@ -59,17 +60,17 @@ pub struct SendMessageResponse {
     pub created_at: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct DeleteResponse {
     pub deleted: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ErrorResponse {
     pub error: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct UploadResponse {
     pub id: String,
     pub role: String,
@@ -80,7 +81,7 @@ pub struct UploadResponse {
     pub created_at: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct ExportQuery {
     pub format: Option<String>,
 }
@@ -0,0 +1,104 @@
+//! `multiai ask` - one-shot prompt from the command line.
+//!
+//! When stdin is piped (`cat error.log | multiai ask "explain this"`),
+//! its contents are appended to the prompt, up to a size limit.
+
+use super::chat::stream_completion;
+use super::models::load_models;
+use crate::api::find_target_model;
+use crate::config::Config;
+use crate::http::{create_client_with_timeout, LONG_TIMEOUT};
+use serde_json::json;
+use std::io::{IsTerminal, Write};
+use tokio::io::AsyncReadExt;
+
+/// Maximum stdin bytes appended to the prompt.
+pub const MAX_STDIN_BYTES: usize = 100 * 1024;
+
+/// Options for `multiai ask`.
+#[derive(Debug, Clone)]
+pub struct AskOptions {
+    pub prompt: String,
+    /// Model to ask (`auto` picks the first available).
+    pub model: String,
+    /// Gateway port to read the model list from.
+    pub port: u16,
+}
+
+/// Run `multiai ask`.
+pub async fn run(options: AskOptions) -> anyhow::Result<()> {
+    let input = if std::io::stdin().is_terminal() {
+        None
+    } else {
+        Some(read_stdin(MAX_STDIN_BYTES).await?)
+    };
+    let prompt = build_prompt(
+        &options.prompt,
+        input.as_ref().map(|(text, _)| text.as_str()),
+    );
+    if prompt.trim().is_empty() {
+        anyhow::bail!("Nothing to ask: pass a prompt or pipe input on stdin");
+    }
+    if let Some((_, true)) = input {
+        eprintln!("Note: input truncated to {} KiB", MAX_STDIN_BYTES / 1024);
+    }
+
+    let models = load_models(options.port, false).await;
+    let model = find_target_model(&options.model, &models).map_err(|e| anyhow::anyhow!("{}", e))?;
+    let api_key = Config::load_with_env().get_model_api_key(model);
+
+    let client = create_client_with_timeout(LONG_TIMEOUT);
+    let messages = [json!({ "role": "user", "content": prompt })];
+    stream_completion(&client, model, api_key.as_deref(), &messages, |delta| {
+        print!("{}", delta);
+        let _ = std::io::stdout().flush();
+    })
+    .await?;
+    println!();
+    Ok(())
+}
+
+/// Read stdin up to `limit` bytes. Returns the text and whether it was truncated.
+async fn read_stdin(limit: usize) -> anyhow::Result<(String, bool)> {
+    let mut bytes = Vec::new();
+    tokio::io::stdin()
+        .take(limit as u64 + 1)
+        .read_to_end(&mut bytes)
+        .await?;
+    let truncated = bytes.len() > limit;
+    bytes.truncate(limit);
+    // A cut may split a multi-byte character; lossy decoding drops it
+    Ok((String::from_utf8_lossy(&bytes).into_owned(), truncated))
+}
+
+/// Combine the prompt with piped input.
+pub fn build_prompt(prompt: &str, input: Option<&str>) -> String {
+    match input.map(str::trim_end).filter(|i| !i.trim().is_empty()) {
+        Some(input) if prompt.trim().is_empty() => input.to_string(),
+        Some(input) => format!("{}\n\n```\n{}\n```", prompt.trim(), input),
+        None => prompt.trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_without_input_is_unchanged() {
+        assert_eq!(build_prompt("What is Rust?", None), "What is Rust?");
+        assert_eq!(build_prompt("What is Rust?", Some("  \n")), "What is Rust?");
+    }
+
+    #[test]
+    fn piped_input_is_fenced_after_prompt() {
+        let prompt = build_prompt("explain this", Some("error: boom\n"));
+
+        assert_eq!(prompt, "explain this\n\n```\nerror: boom\n```");
+    }
+
+    #[test]
+    fn piped_input_alone_becomes_the_prompt() {
+        assert_eq!(build_prompt("", Some("summarize me\n")), "summarize me");
+    }
+}
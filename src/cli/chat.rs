@@ -0,0 +1,446 @@
+//! `multiai chat` - interactive terminal chat.
+//!
+//! Streams replies as they arrive and stores every turn in the same
+//! `ChatDb` the web UI uses, so terminal and GUI history (and transcripts)
+//! are shared.
+
+use super::models::load_models;
+use crate::api::{build_upstream_url, find_target_model};
+use crate::chat::{ChatDb, MessageRole};
+use crate::config::Config;
+use crate::export::{export_chat, ExportChat, ExportFormat};
+use crate::http::{create_client_with_timeout, LONG_TIMEOUT};
+use crate::mcp::{CompareParams, ModelComparator};
+use crate::scanner::{FreeModel, SourceRegistry};
+use crate::transcript::TranscriptWriter;
+use futures::StreamExt;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::PathBuf;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// Options for `multiai chat`.
+#[derive(Debug, Clone)]
+pub struct ChatOptions {
+    /// Model to start with (`auto` picks the first available).
+    pub model: String,
+    /// Resume an existing chat by ID.
+    pub chat_id: Option<String>,
+    /// Gateway port to read the model list from.
+    pub port: u16,
+}
+
+/// A line of REPL input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    Message(String),
+    /// `/model [id]` - show or switch the model.
+    Model(Option<String>),
+    /// `/system [prompt]` - set or clear the system prompt.
+    System(Option<String>),
+    /// `/save [path]` - export the chat (format from the extension).
+    Save(Option<PathBuf>),
+    /// `/compare [prompt]` - compare models on a prompt (default: last message).
+    Compare(Option<String>),
+    Help,
+    Quit,
+    Unknown(String),
+}
+
+const HELP: &str = "\
+Commands:
+  /model [id]        Show or switch the model
+  /system [prompt]   Set the system prompt (empty clears it)
+  /save [path]       Export the chat (.md, .pdf or .docx)
+  /compare [prompt]  Compare models on a prompt (default: last message)
+  /help              Show this help
+  /quit              Exit";
+
+/// Parse one line of input.
+pub fn parse_input(line: &str) -> Input {
+    let line = line.trim();
+    let Some(command) = line.strip_prefix('/') else {
+        return Input::Message(line.to_string());
+    };
+
+    let (name, arg) = match command.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, Some(arg.trim().to_string()).filter(|a| !a.is_empty())),
+        None => (command, None),
+    };
+
+    match name {
+        "model" => Input::Model(arg),
+        "system" => Input::System(arg),
+        "save" => Input::Save(arg.map(PathBuf::from)),
+        "compare" => Input::Compare(arg),
+        "help" | "?" => Input::Help,
+        "quit" | "exit" | "q" => Input::Quit,
+        other => Input::Unknown(other.to_string()),
+    }
+}
+
+/// Extract the content delta from one server-sent event line.
+/// Returns `None` for non-data lines, `[DONE]` and events without content.
+pub fn parse_sse_delta(line: &str) -> Option<String> {
+    let data = line.strip_prefix("data:")?.trim();
+    if data == "[DONE]" {
+        return None;
+    }
+    let event: Value = serde_json::from_str(data).ok()?;
+    event["choices"][0]["delta"]["content"]
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Stream a chat completion, calling `on_delta` with each content chunk.
+/// Returns the full reply.
+pub async fn stream_completion(
+    client: &Client,
+    model: &FreeModel,
+    api_key: Option<&str>,
+    messages: &[Value],
+    mut on_delta: impl FnMut(&str),
+) -> anyhow::Result<String> {
+    let url = build_upstream_url(&SourceRegistry::builtin(), model);
+    let mut req = client.post(url).json(&json!({
+        "model": model.id,
+        "messages": messages,
+        "stream": true,
+    }));
+    if let Some(key) = api_key {
+        req = req.header("Authorization", format!("Bearer {}", key));
+    }
+
+    let response = req.send().await?;
+    if !response.status().is_success() {
+        anyhow::bail!("{} returned status {}", model.id, response.status());
+    }
+
+    let mut reply = String::new();
+    let mut buffer = String::new();
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        buffer.push_str(&String::from_utf8_lossy(&chunk?));
+        // Handle complete lines; keep any partial line for the next chunk
+        while let Some(newline) = buffer.find('\n') {
+            let line: String = buffer.drain(..=newline).collect();
+            if let Some(delta) = parse_sse_delta(line.trim_end()) {
+                on_delta(&delta);
+                reply.push_str(&delta);
+            }
+        }
+    }
+    if let Some(delta) = parse_sse_delta(buffer.trim_end()) {
+        on_delta(&delta);
+        reply.push_str(&delta);
+    }
+
+    Ok(reply)
+}
+
+/// Run the interactive chat loop.
+pub async fn run(options: ChatOptions) -> anyhow::Result<()> {
+    let config = Config::load_with_env();
+    let db = ChatDb::open_default()?
+        .with_transcripts(TranscriptWriter::from_config(&config.logging));
+    let client = create_client_with_timeout(LONG_TIMEOUT);
+
+    let models = load_models(options.port, false).await;
+    let mut model = find_target_model(&options.model, &models)
+        .map_err(|e| anyhow::anyhow!("{}", e))?
+        .clone();
+
+    let chat_id = match options.chat_id {
+        Some(id) => {
+            if db.get_chat(&id)?.is_none() {
+                anyhow::bail!("Chat not found: {}", id);
+            }
+            id
+        }
+        None => {
+            let id = uuid::Uuid::new_v4().to_string();
+            db.create_chat(&id, "Terminal chat")?;
+            id
+        }
+    };
+
+    let mut system: Option<String> = None;
+    let mut last_prompt: Option<String> = None;
+
+    println!(
+        "Chatting with {} (chat {}). Type /help for commands.",
+        model.id, chat_id
+    );
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        print!("> ");
+        std::io::stdout().flush()?;
+        let Some(line) = lines.next_line().await? else {
+            break;
+        };
+
+        match parse_input(&line) {
+            Input::Message(text) if text.is_empty() => {}
+            Input::Message(text) => {
+                let first = db.get_messages(&chat_id)?.is_empty();
+                db.add_message(
+                    &uuid::Uuid::new_v4().to_string(),
+                    &chat_id,
+                    MessageRole::User,
+                    &text,
+                )?;
+                if first {
+                    db.update_chat_title(&chat_id, &title_from(&text))?;
+                }
+                last_prompt = Some(text);
+
+                let messages = history(&db, &chat_id, system.as_deref())?;
+                let api_key = config.get_model_api_key(&model);
+                let result =
+                    stream_completion(&client, &model, api_key.as_deref(), &messages, |delta| {
+                        print!("{}", delta);
+                        let _ = std::io::stdout().flush();
+                    })
+                    .await;
+                println!();
+
+                match result {
+                    Ok(reply) => {
+                        db.add_message(
+                            &uuid::Uuid::new_v4().to_string(),
+                            &chat_id,
+                            MessageRole::Assistant,
+                            &reply,
+                        )?;
+                    }
+                    Err(e) => eprintln!("Error: {}", e),
+                }
+            }
+            Input::Model(None) => println!("Model: {}", model.id),
+            Input::Model(Some(id)) => match find_target_model(&id, &models) {
+                Ok(found) => {
+                    model = found.clone();
+                    println!("Switched to {}", model.id);
+                }
+                Err(e) => eprintln!("{}", e),
+            },
+            Input::System(prompt) => {
+                println!(
+                    "{}",
+                    if prompt.is_some() {
+                        "System prompt set."
+                    } else {
+                        "System prompt cleared."
+                    }
+                );
+                system = prompt;
+            }
+            Input::Save(path) => match save_chat(&db, &chat_id, path) {
+                Ok(path) => println!("Saved to {}", path.display()),
+                Err(e) => eprintln!("Error: {}", e),
+            },
+            Input::Compare(prompt) => {
+                let Some(prompt) = prompt.or_else(|| last_prompt.clone()) else {
+                    eprintln!("Nothing to compare yet; pass a prompt: /compare <prompt>");
+                    continue;
+                };
+                let comparator = ModelComparator::new().await;
+                let params = CompareParams {
+                    prompt,
+                    models: None,
+                    max_models: None,
+                    include_ranking: true,
+                    judges: None,
+                    weights: None,
+                    metrics: None,
+                    criteria: None,
+                    concurrency: None,
+                    timeout_secs: None,
+                    min_results: None,
+                };
+                match comparator.compare(params, None).await {
+                    Ok(result) => println!("{}", result.markdown_summary),
+                    Err(e) => eprintln!("Error: {}", e),
+                }
+            }
+            Input::Help => println!("{}", HELP),
+            Input::Quit => break,
+            Input::Unknown(name) => eprintln!("Unknown command /{} (try /help)", name),
+        }
+    }
+
+    Ok(())
+}
+
+/// Build the upstream message list from the stored chat.
+fn history(db: &ChatDb, chat_id: &str, system: Option<&str>) -> anyhow::Result<Vec<Value>> {
+    let mut messages = Vec::new();
+    if let Some(system) = system {
+        messages.push(json!({ "role": "system", "content": system }));
+    }
+    for message in db.get_messages(chat_id)? {
+        messages.push(json!({ "role": message.role.to_string(), "content": message.content }));
+    }
+    Ok(messages)
+}
+
+/// Chat title from the first message.
+fn title_from(text: &str) -> String {
+    let title: String = text.chars().take(50).collect();
+    if title.len() < text.len() {
+        format!("{}…", title.trim_end())
+    } else {
+        title
+    }
+}
+
+/// Export the chat to `path` (default `<chat id>.md`).
+fn save_chat(db: &ChatDb, chat_id: &str, path: Option<PathBuf>) -> anyhow::Result<PathBuf> {
+    let path = path.unwrap_or_else(|| PathBuf::from(format!("{}.md", chat_id)));
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("md");
+    let format = ExportFormat::from_extension(extension)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported format: .{}", extension))?;
+
+    let chat = db
+        .get_chat(chat_id)?
+        .ok_or_else(|| anyhow::anyhow!("Chat not found: {}", chat_id))?;
+    let export = ExportChat::from_chat(&chat, db.get_messages(chat_id)?);
+
+    let data = export_chat(&export, format).map_err(|e| anyhow::anyhow!(e))?;
+    std::fs::write(&path, data)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_messages_and_commands() {
+        assert_eq!(
+            parse_input("hello there"),
+            Input::Message("hello there".to_string())
+        );
+        assert_eq!(parse_input("/model"), Input::Model(None));
+        assert_eq!(
+            parse_input("/model llama3 "),
+            Input::Model(Some("llama3".to_string()))
+        );
+        assert_eq!(
+            parse_input("/system Be terse."),
+            Input::System(Some("Be terse.".to_string()))
+        );
+        assert_eq!(
+            parse_input("/save out.pdf"),
+            Input::Save(Some(PathBuf::from("out.pdf")))
+        );
+        assert_eq!(parse_input("/compare"), Input::Compare(None));
+        assert_eq!(parse_input("/quit"), Input::Quit);
+        assert_eq!(parse_input("/bogus"), Input::Unknown("bogus".to_string()));
+    }
+
+    #[test]
+    fn parses_sse_content_deltas() {
+        assert_eq!(
+            parse_sse_delta(r#"data: {"choices":[{"delta":{"content":"Hel"}}]}"#),
+            Some("Hel".to_string())
+        );
+        assert_eq!(
+            parse_sse_delta(r#"data: {"choices":[{"delta":{"role":"assistant"}}]}"#),
+            None
+        );
+        assert_eq!(parse_sse_delta("data: [DONE]"), None);
+        assert_eq!(parse_sse_delta(": keep-alive"), None);
+    }
+
+    #[test]
+    fn history_includes_system_prompt_and_turns() {
+        let db = ChatDb::in_memory().unwrap();
+        db.create_chat("c", "t").unwrap();
+        db.add_message("m1", "c", MessageRole::User, "Hi").unwrap();
+        db.add_message("m2", "c", MessageRole::Assistant, "Hello!")
+            .unwrap();
+
+        let messages = history(&db, "c", Some("Be terse.")).unwrap();
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(messages[2]["role"], "assistant");
+        assert_eq!(messages[2]["content"], "Hello!");
+    }
+
+    #[test]
+    fn terminal_turns_are_written_to_transcripts() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.logging.folder = dir.path().to_path_buf();
+        config.logging.transcripts = true;
+        let db = ChatDb::in_memory()
+            .unwrap()
+            .with_transcripts(TranscriptWriter::from_config(&config.logging));
+        db.create_chat("c", "Terminal chat").unwrap();
+        db.add_message("m1", "c", MessageRole::User, "What is Rust?")
+            .unwrap();
+        db.add_message("m2", "c", MessageRole::Assistant, "A language.")
+            .unwrap();
+
+        let transcript =
+            std::fs::read_to_string(dir.path().join("transcripts").join("c.md")).unwrap();
+        assert!(transcript.contains("What is Rust?"));
+        assert!(transcript.contains("A language."));
+    }
+
+    #[test]
+    fn saves_chat_as_markdown() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = ChatDb::in_memory().unwrap();
+        db.create_chat("c", "Terminal chat").unwrap();
+        db.add_message("m1", "c", MessageRole::User, "What is Rust?")
+            .unwrap();
+
+        let path = save_chat(&db, "c", Some(dir.path().join("chat.md"))).unwrap();
+
+        let saved = std::fs::read_to_string(path).unwrap();
+        assert!(saved.contains("# Terminal chat"));
+        assert!(saved.contains("What is Rust?"));
+    }
+
+    #[tokio::test]
+    async fn streams_completion_deltas() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/chat/completions")
+            .with_status(200)
+            .with_header("content-type", "text/event-stream")
+            .with_body(concat!(
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\n\n",
+                "data: [DONE]\n\n"
+            ))
+            .create_async()
+            .await;
+
+        let model = FreeModel {
+            id: "llama3".to_string(),
+            provider: "ollama".to_string(),
+            endpoint: server.url(),
+            source: crate::scanner::Source::Ollama,
+            context_length: None,
+            modalities: Vec::new(),
+            supports_tools: None,
+            supports_response_format: None,
+            source_index: None,
+        };
+        let mut seen = Vec::new();
+        let reply = stream_completion(&Client::new(), &model, None, &[], |d| {
+            seen.push(d.to_string())
+        })
+        .await
+        .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(reply, "Hello");
+        assert_eq!(seen, vec!["Hel", "lo"]);
+    }
+}
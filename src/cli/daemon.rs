@@ -0,0 +1,238 @@
+//! `multiai serve --daemon`, `multiai stop` and `multiai status`.
+//!
+//! The daemon is the same binary re-launched in the background with its
+//! output sent to a log file. Its PID goes in a pidfile so `stop` can
+//! signal it; `status` asks the gateway's `/health` endpoint.
+
+use crate::http::{create_client_with_timeout, DETECTION_TIMEOUT};
+use reqwest::Client;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// How long `stop` waits for the gateway to go down.
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long `start` waits for the gateway to come up.
+const START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between health checks while waiting.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Directory holding the pidfile and daemon log.
+fn state_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("multiai")
+}
+
+/// Path of the daemon pidfile.
+pub fn pid_path() -> PathBuf {
+    state_dir().join("multiai.pid")
+}
+
+/// Path of the daemon log file.
+pub fn log_path() -> PathBuf {
+    state_dir().join("multiai.log")
+}
+
+/// Read a PID from a pidfile.
+pub fn read_pid(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Write a PID to a pidfile, creating parent directories.
+pub fn write_pid(path: &Path, pid: u32) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, format!("{}\n", pid))
+}
+
+/// Ask a gateway's `/health` endpoint for its version.
+/// Returns `None` when nothing healthy is listening.
+pub async fn check_health(client: &Client, base_url: &str) -> Option<String> {
+    let response = client
+        .get(format!("{}/health", base_url))
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let body: serde_json::Value = response.json().await.ok()?;
+    if body["app"] != "multiai" {
+        return None;
+    }
+    Some(body["version"].as_str().unwrap_or("unknown").to_string())
+}
+
+/// Launch `multiai serve` in the background with `serve_args`.
+pub async fn start(port: u16, serve_args: Vec<String>) -> anyhow::Result<()> {
+    let base_url = format!("http://127.0.0.1:{}", port);
+    let client = create_client_with_timeout(DETECTION_TIMEOUT);
+    if check_health(&client, &base_url).await.is_some() {
+        anyhow::bail!("A gateway is already running on port {}", port);
+    }
+
+    let log = log_path();
+    if let Some(parent) = log.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let log_file = OpenOptions::new().create(true).append(true).open(&log)?;
+
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .arg("serve")
+        .args(&serve_args)
+        .stdin(Stdio::null())
+        .stdout(log_file.try_clone()?)
+        .stderr(log_file);
+    // Own process group, so the terminal's Ctrl-C doesn't reach the daemon
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let child = command.spawn()?;
+    write_pid(&pid_path(), child.id())?;
+
+    let started = tokio::time::Instant::now();
+    while started.elapsed() < START_TIMEOUT {
+        if check_health(&client, &base_url).await.is_some() {
+            println!(
+                "multiai started on {} (pid {}, log {})",
+                base_url,
+                child.id(),
+                log.display()
+            );
+            return Ok(());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    anyhow::bail!(
+        "Gateway did not become healthy within {}s; see {}",
+        START_TIMEOUT.as_secs(),
+        log.display()
+    )
+}
+
+/// Stop the daemon recorded in the pidfile.
+pub async fn stop(port: u16) -> anyhow::Result<()> {
+    let path = pid_path();
+    let Some(pid) = read_pid(&path) else {
+        anyhow::bail!("No pidfile at {}; is the daemon running?", path.display());
+    };
+
+    terminate(pid)?;
+
+    let base_url = format!("http://127.0.0.1:{}", port);
+    let client = create_client_with_timeout(DETECTION_TIMEOUT);
+    let started = tokio::time::Instant::now();
+    while started.elapsed() < STOP_TIMEOUT {
+        if check_health(&client, &base_url).await.is_none() {
+            let _ = std::fs::remove_file(&path);
+            println!("multiai stopped (pid {})", pid);
+            return Ok(());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    anyhow::bail!(
+        "Gateway (pid {}) is still running after {}s",
+        pid,
+        STOP_TIMEOUT.as_secs()
+    )
+}
+
+/// Print whether a gateway is running. Returns false when it isn't.
+pub async fn status(port: u16) -> anyhow::Result<bool> {
+    let base_url = format!("http://127.0.0.1:{}", port);
+    let client = create_client_with_timeout(DETECTION_TIMEOUT);
+    let pid = read_pid(&pid_path());
+
+    match check_health(&client, &base_url).await {
+        Some(version) => {
+            match pid {
+                Some(pid) => println!("multiai {} running on {} (pid {})", version, base_url, pid),
+                None => println!("multiai {} running on {}", version, base_url),
+            }
+            Ok(true)
+        }
+        None => {
+            if pid.is_some() {
+                // The daemon exited without `stop`; the pidfile is stale
+                let _ = std::fs::remove_file(pid_path());
+            }
+            println!("multiai is not running on port {}", port);
+            Ok(false)
+        }
+    }
+}
+
+/// Ask a process to shut down gracefully.
+fn terminate(pid: u32) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    let status = Command::new("kill")
+        .args(["-TERM", &pid.to_string()])
+        .status()?;
+    #[cfg(not(unix))]
+    let status = Command::new("taskkill")
+        .args(["/PID", &pid.to_string()])
+        .status()?;
+
+    if !status.success() {
+        anyhow::bail!("Could not signal process {}", pid);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pidfile_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("multiai.pid");
+
+        assert_eq!(read_pid(&path), None);
+        write_pid(&path, 4242).unwrap();
+        assert_eq!(read_pid(&path), Some(4242));
+    }
+
+    #[tokio::test]
+    async fn check_health_reads_version() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/health")
+            .with_status(200)
+            .with_body(r#"{"status":"ok","app":"multiai","version":"1.2.3"}"#)
+            .create_async()
+            .await;
+
+        let client = create_client_with_timeout(DETECTION_TIMEOUT);
+
+        assert_eq!(
+            check_health(&client, &server.url()).await,
+            Some("1.2.3".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn check_health_ignores_other_services() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/health")
+            .with_status(200)
+            .with_body(r#"{"status":"ok"}"#)
+            .create_async()
+            .await;
+
+        let client = create_client_with_timeout(DETECTION_TIMEOUT);
+
+        assert_eq!(check_health(&client, &server.url()).await, None);
+    }
+}
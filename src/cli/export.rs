@@ -0,0 +1,122 @@
+//! `multiai export` - export a chat from the command line.
+//!
+//! Reads the persistent `ChatDb` directly, so no server needs to be running.
+
+use crate::chat::{Chat, ChatDb};
+use crate::export::{export_chat, ExportChat, ExportFormat};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Options for `multiai export`.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Chat to export; `None` with `latest` picks the most recently updated.
+    pub chat_id: Option<String>,
+    pub latest: bool,
+    /// Format name (md, pdf, docx, json); defaults to the output extension.
+    pub format: Option<String>,
+    /// Output file; `-` writes to stdout. Defaults to `<title>.<ext>`.
+    pub output: Option<PathBuf>,
+}
+
+/// Run `multiai export`.
+pub fn run(options: ExportOptions) -> anyhow::Result<()> {
+    let db = ChatDb::open_default()?;
+    let chat = resolve_chat(&db, options.chat_id.as_deref(), options.latest)?;
+    let format = resolve_format(options.format.as_deref(), options.output.as_deref())?;
+
+    let export = ExportChat::from_chat(&chat, db.get_messages(&chat.id)?);
+    let data = export_chat(&export, format).map_err(|e| anyhow::anyhow!(e))?;
+
+    let output = options
+        .output
+        .unwrap_or_else(|| PathBuf::from(default_filename(&chat.title, format)));
+    if output.as_os_str() == "-" {
+        std::io::stdout().write_all(&data)?;
+    } else {
+        std::fs::write(&output, data)?;
+        eprintln!("Exported \"{}\" to {}", chat.title, output.display());
+    }
+    Ok(())
+}
+
+/// Find the chat to export by ID, or the most recently updated one.
+pub fn resolve_chat(db: &ChatDb, chat_id: Option<&str>, latest: bool) -> anyhow::Result<Chat> {
+    match (chat_id, latest) {
+        (Some(id), _) => db
+            .get_chat(id)?
+            .ok_or_else(|| anyhow::anyhow!("Chat not found: {}", id)),
+        (None, true) => db
+            .list_chats()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No chats to export")),
+        (None, false) => anyhow::bail!("Pass a chat ID or --latest"),
+    }
+}
+
+/// Pick the format from `--format`, else the output extension, else Markdown.
+pub fn resolve_format(format: Option<&str>, output: Option<&Path>) -> anyhow::Result<ExportFormat> {
+    let name = format.or_else(|| output.and_then(|p| p.extension()).and_then(|e| e.to_str()));
+    match name {
+        Some(name) => ExportFormat::from_extension(name).ok_or_else(|| {
+            anyhow::anyhow!("Unsupported format: {}. Use pdf, docx, md, or json", name)
+        }),
+        None => Ok(ExportFormat::Markdown),
+    }
+}
+
+/// Filename derived from the chat title, matching the web UI's downloads.
+fn default_filename(title: &str, format: ExportFormat) -> String {
+    let stem: String = title
+        .chars()
+        .take(50)
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}.{}", stem, format.extension())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_chat_by_id_or_latest() {
+        let db = ChatDb::in_memory().unwrap();
+        db.create_chat("a", "First").unwrap();
+        db.create_chat("b", "Second").unwrap();
+        db.update_chat_title("a", "First (edited)").unwrap();
+
+        assert_eq!(resolve_chat(&db, Some("b"), false).unwrap().id, "b");
+        assert_eq!(resolve_chat(&db, None, true).unwrap().id, "a");
+        assert!(resolve_chat(&db, Some("missing"), false).is_err());
+        assert!(resolve_chat(&db, None, false).is_err());
+    }
+
+    #[test]
+    fn resolves_format_from_flag_then_extension() {
+        let pdf = Path::new("chat.pdf");
+
+        assert_eq!(
+            resolve_format(Some("json"), Some(pdf)).unwrap(),
+            ExportFormat::Json
+        );
+        assert_eq!(resolve_format(None, Some(pdf)).unwrap(), ExportFormat::Pdf);
+        assert_eq!(resolve_format(None, None).unwrap(), ExportFormat::Markdown);
+        assert!(resolve_format(Some("exe"), None).is_err());
+    }
+
+    #[test]
+    fn default_filename_is_filesystem_safe() {
+        assert_eq!(
+            default_filename("Rust vs Go: which?", ExportFormat::Docx),
+            "Rust_vs_Go__which_.docx"
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! `multiai config import` - migrate another gateway's configuration.
+//!
+//! Reads a LiteLLM proxy `config.yaml` and maps what MultiAI can use into
+//! its own config: OpenRouter keys, the Ollama server, and `model_list`
+//! names as `[routing.aliases]`. Entries for providers MultiAI doesn't route
+//! to are listed as skipped rather than dropped silently.
+
+use crate::config::Config;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Config formats that can be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    LiteLlm,
+}
+
+/// Options for `multiai config import`.
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    pub format: ImportFormat,
+    pub file: PathBuf,
+    /// Print what would change without writing the config file.
+    pub dry_run: bool,
+}
+
+/// What an import changed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportReport {
+    pub openrouter_key: bool,
+    pub ollama_url: Option<String>,
+    /// Alias name and the model ID it now points to.
+    pub aliases: Vec<(String, String)>,
+    /// Entries that were not imported, with the reason.
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LiteLlmConfig {
+    #[serde(default)]
+    model_list: Vec<LiteLlmModel>,
+    #[serde(default)]
+    router_settings: Option<LiteLlmRouterSettings>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LiteLlmModel {
+    model_name: String,
+    litellm_params: LiteLlmParams,
+}
+
+#[derive(Debug, Deserialize)]
+struct LiteLlmParams {
+    model: String,
+    #[serde(default)]
+    api_key: Option<String>,
+    #[serde(default)]
+    api_base: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LiteLlmRouterSettings {
+    #[serde(default)]
+    model_group_alias: BTreeMap<String, String>,
+}
+
+/// Run `multiai config import`.
+pub fn run(options: ImportOptions) -> anyhow::Result<()> {
+    let content = std::fs::read_to_string(&options.file)?;
+    let path = Config::default_path();
+    let mut config = Config::load_from(path.clone())?;
+    let report = match options.format {
+        ImportFormat::LiteLlm => import_litellm(&content, &mut config)?,
+    };
+
+    if report.openrouter_key {
+        println!("Key: OpenRouter");
+    }
+    if let Some(url) = &report.ollama_url {
+        println!("Ollama server: {}", url);
+    }
+    for (alias, model) in &report.aliases {
+        println!("Alias: {} -> {}", alias, model);
+    }
+    for skipped in &report.skipped {
+        println!("Skipped: {}", skipped);
+    }
+
+    if options.dry_run {
+        println!("Dry run; {} not changed", path.display());
+    } else {
+        config.save_to(path.clone())?;
+        println!("Imported into {}", path.display());
+    }
+    Ok(())
+}
+
+/// Merge a LiteLLM proxy config into `config`.
+pub fn import_litellm(yaml: &str, config: &mut Config) -> anyhow::Result<ImportReport> {
+    let litellm: LiteLlmConfig = serde_yaml::from_str(yaml)?;
+    let mut report = ImportReport::default();
+    // model_list names that were imported, and their model IDs
+    let mut imported = BTreeMap::new();
+
+    for entry in &litellm.model_list {
+        let params = &entry.litellm_params;
+        let Some((provider, model)) = params.model.split_once('/') else {
+            report.skipped.push(format!(
+                "{}: no provider prefix in '{}'",
+                entry.model_name, params.model
+            ));
+            continue;
+        };
+        match provider {
+            "openrouter" => {
+                if let Some(key) = params.api_key.as_deref() {
+                    if let Some(var) = key.strip_prefix("os.environ/") {
+                        if var != "OPENROUTER_API_KEY" {
+                            report.skipped.push(format!(
+                                "{}: key is read from ${}; set OPENROUTER_API_KEY instead",
+                                entry.model_name, var
+                            ));
+                        }
+                    } else if !config.api_keys.openrouter.iter().any(|k| k == key) {
+                        config.api_keys.openrouter.push(key.to_string());
+                        report.openrouter_key = true;
+                    }
+                }
+            }
+            "ollama" | "ollama_chat" => {
+                if let Some(base) = params.api_base.as_deref() {
+                    let base = base.trim_end_matches('/').to_string();
+                    if config.ollama.url.as_deref() != Some(&base) {
+                        config.ollama.url = Some(base.clone());
+                        report.ollama_url = Some(base);
+                    }
+                }
+            }
+            other => {
+                report.skipped.push(format!(
+                    "{}: provider '{}' is not supported",
+                    entry.model_name, other
+                ));
+                continue;
+            }
+        }
+        if entry.model_name != model {
+            add_alias(config, &mut report, &entry.model_name, model);
+        }
+        imported.insert(entry.model_name.as_str(), model);
+    }
+
+    // Group aliases point at a model_list name; resolve them to the model ID
+    let groups = litellm
+        .router_settings
+        .map(|s| s.model_group_alias)
+        .unwrap_or_default();
+    for (alias, group) in groups {
+        match imported.get(group.as_str()) {
+            Some(model) => add_alias(config, &mut report, &alias, model),
+            None => report.skipped.push(format!(
+                "{}: alias of unknown model group '{}'",
+                alias, group
+            )),
+        }
+    }
+
+    Ok(report)
+}
+
+fn add_alias(config: &mut Config, report: &mut ImportReport, alias: &str, model: &str) {
+    config
+        .routing
+        .aliases
+        .insert(alias.to_string(), model.to_string());
+    report.aliases.push((alias.to_string(), model.to_string()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LITELLM: &str = r#"
+model_list:
+  - model_name: fast
+    litellm_params:
+      model: openrouter/meta-llama/llama-3.1-8b-instruct:free
+      api_key: sk-or-imported
+  - model_name: local
+    litellm_params:
+      model: ollama/llama3
+      api_base: http://127.0.0.1:11435/
+  - model_name: llama3
+    litellm_params:
+      model: ollama_chat/llama3
+  - model_name: gpt-4o
+    litellm_params:
+      model: openai/gpt-4o
+      api_key: os.environ/OPENAI_API_KEY
+router_settings:
+  model_group_alias:
+    default: fast
+    llama: llama3
+    smart: gpt-4o
+"#;
+
+    #[test]
+    fn maps_litellm_models_keys_and_aliases() {
+        let mut config = Config::default();
+        let report = import_litellm(LITELLM, &mut config).unwrap();
+
+        assert_eq!(config.api_keys.openrouter, vec!["sk-or-imported"]);
+        assert_eq!(config.ollama.url.as_deref(), Some("http://127.0.0.1:11435"));
+        assert_eq!(
+            config.routing.aliases.get("fast").map(String::as_str),
+            Some("meta-llama/llama-3.1-8b-instruct:free")
+        );
+        assert_eq!(
+            config.routing.aliases.get("local").map(String::as_str),
+            Some("llama3")
+        );
+        assert_eq!(
+            config.routing.aliases.get("default"),
+            config.routing.aliases.get("fast")
+        );
+        assert_eq!(
+            config.routing.aliases.get("llama").map(String::as_str),
+            Some("llama3")
+        );
+        // Names that already match the model ID need no alias
+        assert!(!config.routing.aliases.contains_key("llama3"));
+
+        assert!(report.openrouter_key);
+        assert_eq!(report.aliases.len(), 4);
+        assert_eq!(report.skipped.len(), 2);
+        assert!(report.skipped[0].starts_with("gpt-4o: provider 'openai'"));
+        assert!(report.skipped[1].starts_with("smart: alias of unknown model group"));
+    }
+
+    #[test]
+    fn environment_keys_are_not_copied() {
+        let yaml = r#"
+model_list:
+  - model_name: fast
+    litellm_params:
+      model: openrouter/qwen/qwen-2.5-7b-instruct:free
+      api_key: os.environ/OPENROUTER_API_KEY
+"#;
+        let mut config = Config::default();
+        let report = import_litellm(yaml, &mut config).unwrap();
+
+        assert!(config.api_keys.openrouter.is_empty());
+        assert!(!report.openrouter_key);
+        assert!(report.skipped.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_yaml() {
+        assert!(import_litellm("model_list: [", &mut Config::default()).is_err());
+    }
+}
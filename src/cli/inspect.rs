@@ -0,0 +1,153 @@
+//! `multiai inspect` - view a running gateway's captured traffic.
+//!
+//! With `--tail`, follows the gateway's inspect stream and prints each
+//! transaction as it completes, like `tail -f` for LLM traffic.
+
+use super::with_gateway_key;
+use crate::config::LogVerbosity;
+use crate::http::create_client;
+use crate::inspector::CapturedTransaction;
+pub use crate::inspector::TransactionFilter;
+use crate::logger::format_transaction;
+use futures::StreamExt;
+use reqwest::Client;
+
+/// Options for `multiai inspect`.
+#[derive(Debug, Clone)]
+pub struct InspectOptions {
+    /// Follow new transactions instead of printing the captured ones.
+    pub tail: bool,
+    pub filter: TransactionFilter,
+    pub verbosity: LogVerbosity,
+    /// Gateway port to connect to.
+    pub port: u16,
+}
+
+/// Run `multiai inspect`.
+pub async fn run(options: InspectOptions) -> anyhow::Result<()> {
+    let gateway = format!("http://127.0.0.1:{}", options.port);
+    if options.tail {
+        tail(&gateway, &options).await
+    } else {
+        list(&gateway, &options).await
+    }
+}
+
+/// Print the transactions the gateway has captured so far.
+async fn list(gateway: &str, options: &InspectOptions) -> anyhow::Result<()> {
+    let request = create_client().get(format!("{}/v1/inspect", gateway));
+    let body: serde_json::Value = with_gateway_key(request)
+        .send()
+        .await
+        .map_err(|e| not_running(gateway, e))?
+        .json()
+        .await?;
+    let transactions: Vec<CapturedTransaction> =
+        serde_json::from_value(body["transactions"].clone())?;
+
+    for tx in transactions.iter().filter(|tx| options.filter.matches(tx)) {
+        println!("{}", format_transaction(tx, &options.verbosity));
+    }
+    Ok(())
+}
+
+/// Follow the gateway's inspect stream until it closes.
+async fn tail(gateway: &str, options: &InspectOptions) -> anyhow::Result<()> {
+    // No client timeout: the stream stays open indefinitely
+    let request = Client::new().get(format!("{}/v1/inspect/stream", gateway));
+    let response = with_gateway_key(request)
+        .send()
+        .await
+        .map_err(|e| not_running(gateway, e))?;
+    if !response.status().is_success() {
+        anyhow::bail!("Gateway returned status {}", response.status());
+    }
+
+    eprintln!("Tailing traffic on {} (Ctrl-C to stop)", gateway);
+    let mut buffer = String::new();
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        buffer.push_str(&String::from_utf8_lossy(&chunk?));
+        while let Some(newline) = buffer.find('\n') {
+            let line: String = buffer.drain(..=newline).collect();
+            if let Some(tx) = parse_event(line.trim_end()) {
+                if options.filter.matches(&tx) {
+                    println!("{}", format_transaction(&tx, &options.verbosity));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse a transaction from one server-sent event line.
+fn parse_event(line: &str) -> Option<CapturedTransaction> {
+    let data = line.strip_prefix("data:")?.trim();
+    serde_json::from_str(data).ok()
+}
+
+fn not_running(gateway: &str, error: reqwest::Error) -> anyhow::Error {
+    anyhow::anyhow!("No gateway running at {} ({})", gateway, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inspector::{CapturedRequest, CapturedResponse, TimingMetrics};
+
+    fn transaction(model: &str, status: Option<u16>) -> CapturedTransaction {
+        CapturedTransaction {
+            id: "tx".to_string(),
+            timestamp: chrono::Utc::now(),
+            request: CapturedRequest {
+                method: "POST".to_string(),
+                url: "/v1/chat/completions".to_string(),
+                headers: vec![],
+                body: Some(serde_json::json!({ "model": model })),
+            },
+            response: status.map(|status| CapturedResponse {
+                status,
+                headers: vec![],
+                body: None,
+            }),
+            timing: TimingMetrics::default(),
+            ab_arm: None,
+            virtual_key: None,
+            routed_model: None,
+            annotations: Default::default(),
+            start_time: None,
+        }
+    }
+
+    #[test]
+    fn filters_by_model_substring() {
+        let filter = TransactionFilter {
+            model: Some("llama".to_string()),
+            errors_only: false,
+        };
+
+        assert!(filter.matches(&transaction("meta-llama/llama-3-8b:free", Some(200))));
+        assert!(!filter.matches(&transaction("mistral-7b", Some(200))));
+    }
+
+    #[test]
+    fn errors_only_keeps_failures_and_missing_responses() {
+        let filter = TransactionFilter {
+            model: None,
+            errors_only: true,
+        };
+
+        assert!(!filter.matches(&transaction("m", Some(200))));
+        assert!(filter.matches(&transaction("m", Some(502))));
+        assert!(filter.matches(&transaction("m", None)));
+    }
+
+    #[test]
+    fn parses_transaction_events() {
+        let tx = transaction("m", Some(200));
+        let line = format!("data: {}", serde_json::to_string(&tx).unwrap());
+
+        assert_eq!(parse_event(&line).unwrap().id, "tx");
+        assert!(parse_event(": keep-alive").is_none());
+    }
+}
@@ -0,0 +1,321 @@
+//! `multiai keys` - manage provider API keys.
+//!
+//! Keys are written to the config file; values are masked on display and
+//! can be checked against the provider before relying on them.
+
+use crate::config::Config;
+use crate::http::create_client_with_timeout;
+use crate::scanner::Source;
+use reqwest::{Client, StatusCode};
+use std::path::Path;
+use std::time::Duration;
+
+/// Timeout for a key validation request.
+const VALIDATE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Providers that take an API key.
+const PROVIDERS: [Source; 10] = [
+    Source::OpenRouter,
+    Source::OpenCodeZen,
+    Source::Groq,
+    Source::Gemini,
+    Source::Cerebras,
+    Source::Mistral,
+    Source::Together,
+    Source::SambaNova,
+    Source::GitHubModels,
+    Source::Cloudflare,
+];
+
+/// A `multiai keys` action.
+#[derive(Debug, Clone)]
+pub enum KeysAction {
+    /// Store a key (read from stdin when `key` is None), or clear it.
+    Set {
+        provider: String,
+        key: Option<String>,
+        clear: bool,
+    },
+    /// Show masked keys for one provider or all of them.
+    Show { provider: Option<String> },
+    /// Check a stored key against the provider.
+    Test { provider: String },
+}
+
+/// Result of checking a key against its provider.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyCheck {
+    Valid,
+    /// The provider refused the key.
+    Rejected(u16),
+    /// The provider couldn't be reached or answered unexpectedly.
+    Unknown(String),
+}
+
+/// Run `multiai keys`.
+pub async fn run(action: KeysAction) -> anyhow::Result<()> {
+    let path = Config::default_path();
+    match action {
+        KeysAction::Set {
+            provider,
+            key,
+            clear,
+        } => {
+            let source = parse_provider(&provider)?;
+            let key = match (clear, key) {
+                (true, _) => None,
+                (false, Some(key)) => Some(key),
+                (false, None) => Some(read_key_from_stdin(&provider)?),
+            };
+            set_key(&path, source, key.clone())?;
+            match key {
+                Some(key) => println!("Saved {} key {}", provider, mask_key(&key)),
+                None => println!("Cleared {} key", provider),
+            }
+        }
+        KeysAction::Show { provider } => {
+            let sources = match provider {
+                Some(name) => vec![parse_provider(&name)?],
+                None => PROVIDERS.to_vec(),
+            };
+            let config = Config::load_from(path.clone()).unwrap_or_default();
+            for source in sources {
+                println!("{}", describe_key(&config, source));
+            }
+        }
+        KeysAction::Test { provider } => {
+            let source = parse_provider(&provider)?;
+            let config = Config::load_with_env();
+            let Some(key) = config.get_api_key(&source) else {
+                anyhow::bail!(
+                    "No {} key set (try: multiai keys set {})",
+                    provider,
+                    provider
+                );
+            };
+            let client = create_client_with_timeout(VALIDATE_TIMEOUT);
+            match validate_key(&client, source, verify_url(source), &key).await {
+                KeyCheck::Valid => println!("{} key {} is valid", provider, mask_key(&key)),
+                KeyCheck::Rejected(status) => {
+                    anyhow::bail!("{} rejected key {} ({})", provider, mask_key(&key), status)
+                }
+                KeyCheck::Unknown(reason) => {
+                    anyhow::bail!("Could not verify {} key: {}", provider, reason)
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse a provider name as accepted on the command line.
+pub fn parse_provider(name: &str) -> anyhow::Result<Source> {
+    match name.to_lowercase().as_str() {
+        "openrouter" => Ok(Source::OpenRouter),
+        "opencode-zen" | "opencode_zen" | "zen" => Ok(Source::OpenCodeZen),
+        "groq" => Ok(Source::Groq),
+        "gemini" | "google" => Ok(Source::Gemini),
+        "cerebras" => Ok(Source::Cerebras),
+        "mistral" => Ok(Source::Mistral),
+        "together" | "together-ai" | "togetherai" => Ok(Source::Together),
+        "sambanova" | "samba-nova" | "samba" => Ok(Source::SambaNova),
+        "github" | "github-models" | "github_models" => Ok(Source::GitHubModels),
+        "cloudflare" | "workers-ai" => Ok(Source::Cloudflare),
+        "ollama" => anyhow::bail!("Ollama runs locally and does not use an API key"),
+        "lmstudio" | "lm-studio" => {
+            anyhow::bail!("LM Studio runs locally and does not use an API key")
+        }
+        "llamacpp" | "llama.cpp" | "llama-server" => {
+            anyhow::bail!("llama.cpp runs locally and does not use an API key")
+        }
+        "custom" => {
+            anyhow::bail!("Custom providers take their key from [[providers.custom]] api_key")
+        }
+        other => anyhow::bail!(
+            "Unknown provider '{}' (expected openrouter, opencode-zen, groq, gemini, cerebras, mistral, together, sambanova, github or cloudflare)",
+            other
+        ),
+    }
+}
+
+/// Mask a key for display, keeping a short prefix and suffix.
+pub fn mask_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 12 {
+        return "*".repeat(chars.len());
+    }
+    let prefix: String = chars[..6].iter().collect();
+    let suffix: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", prefix, suffix)
+}
+
+/// Store or clear a key in the config file at `path`.
+pub fn set_key(path: &Path, source: Source, key: Option<String>) -> anyhow::Result<()> {
+    let mut config = Config::load_from(path.to_path_buf())?;
+    config.set_api_key(&source, key);
+    config.save_to(path.to_path_buf())?;
+    Ok(())
+}
+
+/// Environment variable that overrides the configured key.
+fn env_var(source: Source) -> &'static str {
+    match source {
+        Source::OpenRouter => "OPENROUTER_API_KEY",
+        Source::OpenCodeZen => "OPENCODE_ZEN_API_KEY",
+        Source::Groq => "GROQ_API_KEY",
+        Source::Gemini => "GEMINI_API_KEY",
+        Source::Cerebras => "CEREBRAS_API_KEY",
+        Source::Mistral => "MISTRAL_API_KEY",
+        Source::Together => "TOGETHER_API_KEY",
+        Source::SambaNova => "SAMBANOVA_API_KEY",
+        Source::GitHubModels => "GITHUB_TOKEN",
+        Source::Cloudflare => "CLOUDFLARE_API_TOKEN",
+        Source::Ollama | Source::LmStudio | Source::LlamaCpp | Source::Custom => "",
+    }
+}
+
+/// Endpoint that answers 401/403 for a bad key.
+fn verify_url(source: Source) -> &'static str {
+    match source {
+        Source::OpenCodeZen => "https://opencode.ai/zen/v1/models",
+        Source::Groq => "https://api.groq.com/openai/v1/models",
+        Source::Gemini => "https://generativelanguage.googleapis.com/v1beta/models",
+        Source::Cerebras => "https://api.cerebras.ai/v1/models",
+        Source::Mistral => "https://api.mistral.ai/v1/models",
+        Source::Together => "https://api.together.xyz/v1/models",
+        Source::SambaNova => "https://api.sambanova.ai/v1/models",
+        Source::GitHubModels => "https://api.github.com/user",
+        Source::Cloudflare => "https://api.cloudflare.com/client/v4/user/tokens/verify",
+        _ => "https://openrouter.ai/api/v1/key",
+    }
+}
+
+/// One line describing where a provider's key comes from.
+fn describe_key(config: &Config, source: Source) -> String {
+    let name = match source {
+        Source::OpenRouter => "openrouter",
+        Source::Groq => "groq",
+        Source::Gemini => "gemini",
+        Source::Cerebras => "cerebras",
+        Source::Mistral => "mistral",
+        Source::Together => "together",
+        Source::SambaNova => "sambanova",
+        Source::GitHubModels => "github",
+        Source::Cloudflare => "cloudflare",
+        _ => "opencode-zen",
+    };
+    if let Ok(key) = std::env::var(env_var(source)) {
+        return format!("{:<14} {} (from {})", name, mask_key(&key), env_var(source));
+    }
+    match config.get_api_key(&source) {
+        Some(key) => format!("{:<14} {}", name, mask_key(&key)),
+        None => format!("{:<14} not set", name),
+    }
+}
+
+/// Check a key by making an authenticated request to `url`, sending it
+/// the way `source` expects.
+pub async fn validate_key(client: &Client, source: Source, url: &str, key: &str) -> KeyCheck {
+    let request = match source {
+        Source::Gemini => client.get(url).header(crate::gemini::API_KEY_HEADER, key),
+        _ => client.get(url).bearer_auth(key),
+    };
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => return KeyCheck::Unknown(e.to_string()),
+    };
+
+    match response.status() {
+        status if status.is_success() => KeyCheck::Valid,
+        status @ (StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => {
+            KeyCheck::Rejected(status.as_u16())
+        }
+        // Google answers an invalid key with 400 API_KEY_INVALID
+        StatusCode::BAD_REQUEST if source == Source::Gemini => KeyCheck::Rejected(400),
+        status => KeyCheck::Unknown(format!("unexpected status {}", status)),
+    }
+}
+
+/// Read a key from stdin so it stays out of shell history.
+fn read_key_from_stdin(provider: &str) -> anyhow::Result<String> {
+    eprint!("Enter {} API key: ", provider);
+    let mut key = String::new();
+    std::io::stdin().read_line(&mut key)?;
+    let key = key.trim().to_string();
+    if key.is_empty() {
+        anyhow::bail!("No key entered");
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::DETECTION_TIMEOUT;
+
+    #[test]
+    fn parses_provider_aliases() {
+        assert_eq!(parse_provider("OpenRouter").unwrap(), Source::OpenRouter);
+        assert_eq!(parse_provider("opencode-zen").unwrap(), Source::OpenCodeZen);
+        assert_eq!(parse_provider("zen").unwrap(), Source::OpenCodeZen);
+        assert_eq!(parse_provider("Groq").unwrap(), Source::Groq);
+        assert_eq!(parse_provider("google").unwrap(), Source::Gemini);
+        assert_eq!(parse_provider("Cerebras").unwrap(), Source::Cerebras);
+        assert_eq!(parse_provider("mistral").unwrap(), Source::Mistral);
+        assert_eq!(parse_provider("togetherai").unwrap(), Source::Together);
+        assert_eq!(parse_provider("samba").unwrap(), Source::SambaNova);
+        assert_eq!(parse_provider("github-models").unwrap(), Source::GitHubModels);
+        assert_eq!(parse_provider("workers-ai").unwrap(), Source::Cloudflare);
+        assert!(parse_provider("ollama").is_err());
+        assert!(parse_provider("bogus").is_err());
+    }
+
+    #[test]
+    fn masks_keys() {
+        assert_eq!(mask_key("sk-or-v1-abcdef123456"), "sk-or-…3456");
+        assert_eq!(mask_key("short"), "*****");
+    }
+
+    #[test]
+    fn set_key_writes_and_clears_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        set_key(&path, Source::OpenRouter, Some("sk-or-test".to_string())).unwrap();
+        let config = Config::load_from(path.clone()).unwrap();
+        assert_eq!(config.api_keys.openrouter, vec!["sk-or-test"]);
+
+        set_key(&path, Source::OpenRouter, None).unwrap();
+        let config = Config::load_from(path).unwrap();
+        assert!(config.api_keys.openrouter.is_empty());
+    }
+
+    #[tokio::test]
+    async fn validates_keys_by_status() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/key")
+            .match_header("authorization", "Bearer good")
+            .with_status(200)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/key")
+            .match_header("authorization", "Bearer bad")
+            .with_status(401)
+            .create_async()
+            .await;
+
+        let client = create_client_with_timeout(DETECTION_TIMEOUT);
+        let url = format!("{}/key", server.url());
+
+        assert_eq!(
+            validate_key(&client, Source::OpenRouter, &url, "good").await,
+            KeyCheck::Valid
+        );
+        assert_eq!(
+            validate_key(&client, Source::OpenRouter, &url, "bad").await,
+            KeyCheck::Rejected(401)
+        );
+    }
+}
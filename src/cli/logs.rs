@@ -0,0 +1,242 @@
+//! `multiai logs` - browse the on-disk traffic logs.
+//!
+//! Lists and pretty-prints the daily JSONL/HAR files written by the
+//! gateway, and converts HAR files to Markdown for sharing.
+
+use super::inspect::TransactionFilter;
+use crate::config::LogVerbosity;
+use crate::inspector::CapturedTransaction;
+use crate::logger::format_transaction;
+use crate::traffic_log::{
+    list_files, read_transactions, transaction_from_har, LogFile, LogFileKind,
+};
+use chrono::NaiveDate;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Options for `multiai logs`.
+#[derive(Debug, Clone)]
+pub struct LogsOptions {
+    /// Folder holding the daily log files.
+    pub folder: PathBuf,
+    /// List files instead of printing transactions.
+    pub list: bool,
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+    pub filter: TransactionFilter,
+    pub verbosity: LogVerbosity,
+    /// Convert this HAR file to Markdown instead.
+    pub har_to_md: Option<PathBuf>,
+}
+
+/// Run `multiai logs`.
+pub fn run(options: LogsOptions) -> anyhow::Result<()> {
+    if let Some(path) = &options.har_to_md {
+        let har: Value = serde_json::from_slice(&std::fs::read(path)?)?;
+        print!("{}", har_to_markdown(&har, &file_title(path)));
+        return Ok(());
+    }
+
+    let files = select_files(list_files(&options.folder)?, options.since, options.until);
+    if files.is_empty() {
+        eprintln!("No traffic logs in {}", options.folder.display());
+        return Ok(());
+    }
+
+    for file in files {
+        let transactions = read_transactions(&file)?;
+        if options.list {
+            let size = std::fs::metadata(&file.path).map(|m| m.len()).unwrap_or(0);
+            println!(
+                "{}  {:>6} requests  {:>8}  {}",
+                file.date,
+                transactions.len(),
+                format_size(size),
+                file.path.display()
+            );
+            continue;
+        }
+        for tx in transactions.iter().filter(|tx| options.filter.matches(tx)) {
+            println!("{}", format_transaction(tx, &options.verbosity));
+        }
+    }
+    Ok(())
+}
+
+/// Keep files within the date range, one per day (JSONL preferred over HAR).
+pub fn select_files(
+    files: Vec<LogFile>,
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+) -> Vec<LogFile> {
+    let mut selected: Vec<LogFile> = Vec::new();
+    for file in files {
+        if since.is_some_and(|d| file.date < d) || until.is_some_and(|d| file.date > d) {
+            continue;
+        }
+        match selected.iter_mut().find(|f| f.date == file.date) {
+            Some(existing) if file.kind == LogFileKind::Jsonl => *existing = file,
+            Some(_) => {}
+            None => selected.push(file),
+        }
+    }
+    selected
+}
+
+/// Render a HAR document as a Markdown report.
+pub fn har_to_markdown(har: &Value, title: &str) -> String {
+    let transactions: Vec<CapturedTransaction> = har["log"]["entries"]
+        .as_array()
+        .map(|entries| entries.iter().filter_map(transaction_from_har).collect())
+        .unwrap_or_default();
+
+    let mut md = format!("# Traffic log: {}\n\n", title);
+    md.push_str("| Time | Model | Status | Duration | Tokens |\n");
+    md.push_str("|------|-------|--------|----------|--------|\n");
+    for tx in &transactions {
+        md.push_str(&format!(
+            "| {} | {} | {} | {}ms | {} |\n",
+            tx.timestamp.format("%H:%M:%S"),
+            model_of(tx),
+            status_of(tx),
+            tx.timing.total_ms,
+            tx.timing
+                .completion_tokens
+                .map(|t| t.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+        ));
+    }
+
+    for (i, tx) in transactions.iter().enumerate() {
+        md.push_str(&format!(
+            "\n## {}. {} ({})\n\n",
+            i + 1,
+            model_of(tx),
+            status_of(tx)
+        ));
+        if let Some(prompt) = last_user_message(tx) {
+            md.push_str(&format!(
+                "**Prompt:**\n\n> {}\n\n",
+                prompt.replace('\n', "\n> ")
+            ));
+        }
+        if let Some(reply) = reply_of(tx) {
+            md.push_str(&format!("**Response:**\n\n{}\n", reply));
+        }
+    }
+    md
+}
+
+fn model_of(tx: &CapturedTransaction) -> &str {
+    tx.request
+        .body
+        .as_ref()
+        .and_then(|b| b["model"].as_str())
+        .unwrap_or("unknown")
+}
+
+fn status_of(tx: &CapturedTransaction) -> String {
+    tx.response
+        .as_ref()
+        .map(|r| r.status.to_string())
+        .unwrap_or_else(|| "no response".to_string())
+}
+
+fn last_user_message(tx: &CapturedTransaction) -> Option<&str> {
+    tx.request.body.as_ref()?["messages"]
+        .as_array()?
+        .iter()
+        .rev()
+        .find(|m| m["role"] == "user")?["content"]
+        .as_str()
+}
+
+fn reply_of(tx: &CapturedTransaction) -> Option<&str> {
+    tx.response.as_ref()?.body.as_ref()?["choices"][0]["message"]["content"].as_str()
+}
+
+fn file_title(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("traffic")
+        .to_string()
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.1} KB", b as f64 / 1024.0),
+        b => format!("{} B", b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inspector::{har_entry, har_log, CapturedRequest, CapturedResponse, TimingMetrics};
+
+    fn file(date: &str, kind: LogFileKind) -> LogFile {
+        LogFile {
+            path: PathBuf::from(format!("{}.{:?}", date, kind)),
+            date: date.parse().unwrap(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn selects_files_in_range_preferring_jsonl() {
+        let files = vec![
+            file("2026-01-01", LogFileKind::Jsonl),
+            file("2026-01-02", LogFileKind::Har),
+            file("2026-01-02", LogFileKind::Jsonl),
+            file("2026-01-03", LogFileKind::Har),
+        ];
+
+        let selected = select_files(files, "2026-01-02".parse().ok(), None);
+
+        assert_eq!(selected.len(), 2);
+        assert_eq!(selected[0].kind, LogFileKind::Jsonl);
+        assert_eq!(selected[1].date.to_string(), "2026-01-03");
+    }
+
+    #[test]
+    fn converts_har_to_markdown() {
+        let tx = CapturedTransaction {
+            id: "tx".to_string(),
+            timestamp: chrono::Utc::now(),
+            request: CapturedRequest {
+                method: "POST".to_string(),
+                url: "/v1/chat/completions".to_string(),
+                headers: vec![],
+                body: Some(serde_json::json!({
+                    "model": "llama3",
+                    "messages": [{"role": "user", "content": "Why is the sky blue?"}]
+                })),
+            },
+            response: Some(CapturedResponse {
+                status: 200,
+                headers: vec![],
+                body: Some(serde_json::json!({
+                    "choices": [{"message": {"role": "assistant", "content": "Rayleigh scattering."}}]
+                })),
+            }),
+            timing: TimingMetrics {
+                total_ms: 850,
+                ..TimingMetrics::default()
+            },
+            ab_arm: None,
+            virtual_key: None,
+            routed_model: None,
+            annotations: Default::default(),
+            start_time: None,
+        };
+        let har = har_log(vec![har_entry(&tx)]);
+
+        let md = har_to_markdown(&har, "2026-01-02");
+
+        assert!(md.starts_with("# Traffic log: 2026-01-02"));
+        assert!(md.contains("| llama3 | 200 | 850ms | - |"));
+        assert!(md.contains("> Why is the sky blue?"));
+        assert!(md.contains("Rayleigh scattering."));
+    }
+}
@@ -0,0 +1,27 @@
+//! Subcommands of the `multiai` command-line tool.
+//!
+//! `main.rs` parses arguments; each module here implements one command.
+
+pub mod ask;
+pub mod chat;
+pub mod daemon;
+pub mod export;
+pub mod import;
+pub mod inspect;
+pub mod keys;
+pub mod logs;
+pub mod models;
+pub mod service;
+pub mod top;
+
+use crate::config::Config;
+use reqwest::RequestBuilder;
+
+/// Authenticate a request to the local gateway with its `[gateway] api_key`,
+/// when one is set.
+pub(crate) fn with_gateway_key(request: RequestBuilder) -> RequestBuilder {
+    match Config::load_with_env().gateway.api_key {
+        Some(key) => request.bearer_auth(key),
+        None => request,
+    }
+}
@@ -0,0 +1,307 @@
+//! `multiai models` - list discovered free models.
+//!
+//! Reads the model list from a running gateway when one is up (its cache
+//! is warm), otherwise scans the providers directly.
+
+use crate::api::{build_upstream_url, GroupedModelsResponse};
+use super::with_gateway_key;
+use crate::config::Config;
+use crate::http::{create_client_with_timeout, DETECTION_TIMEOUT};
+use crate::scanner::{FreeModel, FreeModelScanner, Source, SourceRegistry};
+use futures::future::join_all;
+use reqwest::Client;
+use serde::Serialize;
+use std::time::Duration;
+
+/// Default Ollama URL to check for local models.
+const DEFAULT_OLLAMA_URL: &str = "http://127.0.0.1:11434";
+
+/// Timeout for a single health probe.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Options for `multiai models`.
+#[derive(Debug, Clone)]
+pub struct ModelsOptions {
+    /// Print JSON instead of a table.
+    pub json: bool,
+    /// Scan providers directly instead of using a running gateway's cache.
+    pub refresh: bool,
+    /// Probe each model with a one-token request to fill in health.
+    pub check: bool,
+    /// Gateway port to look for.
+    pub port: u16,
+}
+
+/// One row of the models listing.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ModelRow {
+    pub id: String,
+    pub source: Source,
+    pub provider: String,
+    pub context_length: Option<u64>,
+    pub health: Option<String>,
+}
+
+impl From<&FreeModel> for ModelRow {
+    fn from(model: &FreeModel) -> Self {
+        // OpenRouter IDs carry the upstream vendor as a prefix
+        let provider = match (model.source, model.id.split_once('/')) {
+            (Source::OpenRouter, Some((vendor, _))) => vendor.to_string(),
+            _ => model.provider.clone(),
+        };
+        Self {
+            id: model.id.clone(),
+            source: model.source,
+            provider,
+            context_length: model.context_length,
+            health: None,
+        }
+    }
+}
+
+/// Run `multiai models`.
+pub async fn run(options: ModelsOptions) -> anyhow::Result<()> {
+    let models = load_models(options.port, options.refresh).await;
+    let mut rows: Vec<ModelRow> = models.iter().map(ModelRow::from).collect();
+
+    if options.check {
+        let config = Config::load_with_env();
+        let client = create_client_with_timeout(PROBE_TIMEOUT);
+        let probes = models.iter().map(|m| probe_model(&client, m, &config));
+        for (row, health) in rows.iter_mut().zip(join_all(probes).await) {
+            row.health = Some(health);
+        }
+    }
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+    } else if rows.is_empty() {
+        println!("No free models found.");
+    } else {
+        print!("{}", format_table(&rows));
+    }
+    Ok(())
+}
+
+/// Get models from a running gateway, falling back to a direct scan.
+pub(super) async fn load_models(port: u16, refresh: bool) -> Vec<FreeModel> {
+    let gateway = format!("http://127.0.0.1:{}", port);
+    if !refresh && FreeModelScanner::detect_multiai(&gateway).await {
+        if let Ok(models) = fetch_from_gateway(&gateway).await {
+            return models;
+        }
+    }
+
+    let scanner = FreeModelScanner::new().with_keys_from(&Config::load_with_env());
+    let scanner = if FreeModelScanner::detect_ollama(DEFAULT_OLLAMA_URL).await {
+        scanner.with_ollama_url(DEFAULT_OLLAMA_URL)
+    } else {
+        scanner
+    };
+    let lmstudio = FreeModelScanner::DEFAULT_LMSTUDIO_URL;
+    let scanner = if FreeModelScanner::detect_lmstudio(lmstudio).await {
+        scanner.with_lmstudio_url(lmstudio)
+    } else {
+        scanner
+    };
+    let llamacpp = FreeModelScanner::DEFAULT_LLAMACPP_URL;
+    let scanner = if FreeModelScanner::detect_llamacpp(llamacpp).await {
+        scanner.with_llamacpp_url(llamacpp)
+    } else {
+        scanner
+    };
+    scanner.get_free_models(true).await
+}
+
+/// Read the gateway's cached model list from `/v1/models/grouped`.
+async fn fetch_from_gateway(gateway: &str) -> Result<Vec<FreeModel>, reqwest::Error> {
+    let client = create_client_with_timeout(DETECTION_TIMEOUT * 5);
+    let request = client.get(format!("{}/v1/models/grouped", gateway));
+    let grouped: GroupedModelsResponse = with_gateway_key(request)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let mut models: Vec<FreeModel> = grouped
+        .models
+        .into_iter()
+        .flat_map(|group| group.providers)
+        .map(|option| FreeModel {
+            id: option.id,
+            provider: provider_name(option.source).to_string(),
+            endpoint: option.endpoint,
+            source: option.source,
+            context_length: option.context_length,
+            modalities: option.modalities,
+            supports_tools: option.supports_tools,
+            supports_response_format: None,
+            source_index: None,
+        })
+        .collect();
+    models.sort_by(|a, b| a.source.cmp(&b.source).then_with(|| a.id.cmp(&b.id)));
+    Ok(models)
+}
+
+/// Provider name the scanner assigns to models from a source.
+fn provider_name(source: Source) -> &'static str {
+    match source {
+        Source::Ollama => "ollama",
+        Source::LmStudio => "lmstudio",
+        Source::LlamaCpp => "llamacpp",
+        Source::OpenCodeZen => "opencode-zen",
+        Source::OpenRouter => "openrouter",
+        Source::Groq => "groq",
+        Source::Gemini => "gemini",
+        Source::Cerebras => "cerebras",
+        Source::Mistral => "mistral",
+        Source::Together => "together",
+        Source::SambaNova => "sambanova",
+        Source::GitHubModels => "github",
+        Source::Cloudflare => "cloudflare",
+        Source::Custom => "custom",
+    }
+}
+
+/// Send a one-token completion and describe the outcome.
+async fn probe_model(client: &Client, model: &FreeModel, config: &Config) -> String {
+    let body = serde_json::json!({
+        "model": model.id,
+        "messages": [{"role": "user", "content": "ping"}],
+        "max_tokens": 1,
+    });
+
+    let mut req = client.post(build_upstream_url(&SourceRegistry::builtin(), model)).json(&body);
+    if let Some(key) = config.get_model_api_key(model) {
+        req = req.header("Authorization", format!("Bearer {}", key));
+    }
+
+    match req.send().await {
+        Ok(response) if response.status().is_success() => "ok".to_string(),
+        Ok(response) => format!("HTTP {}", response.status().as_u16()),
+        Err(e) if e.is_timeout() => "timeout".to_string(),
+        Err(_) => "unreachable".to_string(),
+    }
+}
+
+/// Render rows as an aligned plain-text table.
+fn format_table(rows: &[ModelRow]) -> String {
+    let headers = ["ID", "SOURCE", "PROVIDER", "CONTEXT", "HEALTH"];
+    let cells: Vec<[String; 5]> = rows
+        .iter()
+        .map(|r| {
+            [
+                r.id.clone(),
+                provider_name(r.source).to_string(),
+                r.provider.clone(),
+                r.context_length.map(|c| c.to_string()).unwrap_or_else(|| "-".to_string()),
+                r.health.clone().unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+
+    let mut widths = headers.map(str::len);
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    let mut push_line = |values: Vec<&str>| {
+        let line: Vec<String> = values
+            .iter()
+            .zip(widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect();
+        out.push_str(line.join("  ").trim_end());
+        out.push('\n');
+    };
+    push_line(headers.to_vec());
+    for row in &cells {
+        push_line(row.iter().map(String::as_str).collect());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(id: &str, source: Source, context_length: Option<u64>) -> FreeModel {
+        FreeModel {
+            id: id.to_string(),
+            provider: provider_name(source).to_string(),
+            endpoint: "http://example.com".to_string(),
+            source,
+            context_length,
+            modalities: Vec::new(),
+            supports_tools: None,
+            supports_response_format: None,
+            source_index: None,
+        }
+    }
+
+    #[test]
+    fn openrouter_rows_use_vendor_prefix_as_provider() {
+        let row = ModelRow::from(&model("meta-llama/llama-3:free", Source::OpenRouter, Some(8192)));
+        assert_eq!(row.provider, "meta-llama");
+        assert_eq!(row.context_length, Some(8192));
+
+        let local = ModelRow::from(&model("llama3", Source::Ollama, None));
+        assert_eq!(local.provider, "ollama");
+    }
+
+    #[test]
+    fn table_aligns_columns_and_fills_missing_values() {
+        let mut rows = vec![
+            ModelRow::from(&model("meta-llama/llama-3:free", Source::OpenRouter, Some(8192))),
+            ModelRow::from(&model("llama3", Source::Ollama, None)),
+        ];
+        rows[0].health = Some("ok".to_string());
+
+        let table = format_table(&rows);
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert!(lines[0].starts_with("ID"));
+        assert!(lines[1].contains("8192"));
+        assert!(lines[1].ends_with("ok"));
+        assert!(lines[2].starts_with("llama3 "));
+        assert!(lines[2].ends_with('-'));
+        // Every column starts at the same offset on every line
+        let source_col = lines[0].find("SOURCE").unwrap();
+        assert_eq!(&lines[1][source_col..source_col + 10], "openrouter");
+    }
+
+    #[tokio::test]
+    async fn reads_models_from_gateway() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/v1/models/grouped")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "models": [{
+                        "name": "Llama 3",
+                        "providers": [
+                            {"id": "meta-llama/llama-3:free", "source": "open_router", "endpoint": "https://openrouter.ai/api/v1", "context_length": 8192},
+                            {"id": "llama3", "source": "ollama", "endpoint": "http://127.0.0.1:11434"}
+                        ]
+                    }]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let models = fetch_from_gateway(&server.url()).await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].source, Source::Ollama);
+        assert_eq!(models[1].provider, "openrouter");
+        assert_eq!(models[1].context_length, Some(8192));
+    }
+}
@@ -0,0 +1,302 @@
+//! `multiai service install|uninstall` - run the gateway at startup.
+//!
+//! Registers a per-user service so the gateway shares the user's config
+//! and data directories: a systemd user unit on Linux, a launchd agent on
+//! macOS and a logon task on Windows.
+
+use super::daemon::log_path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// launchd label and Windows task name.
+const SERVICE_LABEL: &str = "ai.multiai.gateway";
+
+/// systemd unit name.
+const UNIT_NAME: &str = "multiai.service";
+
+/// A `multiai service` action.
+#[derive(Debug, Clone)]
+pub enum ServiceAction {
+    /// Write and register the service definition.
+    Install {
+        port: u16,
+        config_path: PathBuf,
+        /// Print the definition instead of installing it.
+        dry_run: bool,
+    },
+    /// Stop and remove the service.
+    Uninstall,
+}
+
+/// What the service runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceSpec {
+    pub executable: PathBuf,
+    pub port: u16,
+    pub config_path: PathBuf,
+    pub log_path: PathBuf,
+}
+
+impl ServiceSpec {
+    /// Arguments passed to the executable.
+    pub fn args(&self) -> Vec<String> {
+        vec![
+            "serve".to_string(),
+            "--port".to_string(),
+            self.port.to_string(),
+            "--config".to_string(),
+            self.config_path.display().to_string(),
+        ]
+    }
+}
+
+/// Run `multiai service`.
+pub fn run(action: ServiceAction) -> anyhow::Result<()> {
+    match action {
+        ServiceAction::Install {
+            port,
+            config_path,
+            dry_run,
+        } => {
+            let spec = ServiceSpec {
+                executable: std::env::current_exe()?,
+                port,
+                config_path,
+                log_path: log_path(),
+            };
+            install(&spec, dry_run)
+        }
+        ServiceAction::Uninstall => uninstall(),
+    }
+}
+
+/// Render a systemd user unit.
+pub fn systemd_unit(spec: &ServiceSpec) -> String {
+    let exec = std::iter::once(spec.executable.display().to_string())
+        .chain(spec.args())
+        .map(|arg| systemd_quote(&arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "[Unit]\n\
+         Description=MultiAI gateway\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         ExecStart={}\n\
+         Restart=on-failure\n\
+         RestartSec=5\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        exec
+    )
+}
+
+/// Render a launchd agent property list.
+pub fn launchd_plist(spec: &ServiceSpec) -> String {
+    let arguments: String = std::iter::once(spec.executable.display().to_string())
+        .chain(spec.args())
+        .map(|arg| format!("        <string>{}</string>\n", xml_escape(&arg)))
+        .collect();
+    let log = xml_escape(&spec.log_path.display().to_string());
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = SERVICE_LABEL,
+        arguments = arguments,
+        log = log
+    )
+}
+
+/// Arguments to `schtasks` that register a logon task.
+pub fn schtasks_args(spec: &ServiceSpec) -> Vec<String> {
+    let command = std::iter::once(spec.executable.display().to_string())
+        .chain(spec.args())
+        .map(|arg| {
+            if arg.contains(' ') {
+                format!("\"{}\"", arg)
+            } else {
+                arg
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    vec![
+        "/Create".to_string(),
+        "/F".to_string(),
+        "/SC".to_string(),
+        "ONLOGON".to_string(),
+        "/TN".to_string(),
+        SERVICE_LABEL.to_string(),
+        "/TR".to_string(),
+        command,
+    ]
+}
+
+fn systemd_quote(arg: &str) -> String {
+    if arg.contains([' ', '"', '\\']) {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        arg.to_string()
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn unit_path() -> anyhow::Result<PathBuf> {
+    dirs::config_dir()
+        .map(|dir| dir.join("systemd").join("user").join(UNIT_NAME))
+        .ok_or_else(|| anyhow::anyhow!("Could not find the user config directory"))
+}
+
+fn plist_path() -> anyhow::Result<PathBuf> {
+    dirs::home_dir()
+        .map(|dir| {
+            dir.join("Library")
+                .join("LaunchAgents")
+                .join(format!("{}.plist", SERVICE_LABEL))
+        })
+        .ok_or_else(|| anyhow::anyhow!("Could not find the home directory"))
+}
+
+fn install(spec: &ServiceSpec, dry_run: bool) -> anyhow::Result<()> {
+    if cfg!(target_os = "macos") {
+        let path = plist_path()?;
+        let plist = launchd_plist(spec);
+        if dry_run {
+            print!("{}", plist);
+            return Ok(());
+        }
+        if let Some(parent) = spec.log_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_definition(&path, &plist)?;
+        run_tool("launchctl", &["load", "-w", &path.display().to_string()])?;
+        println!("Installed launchd agent {}", path.display());
+    } else if cfg!(windows) {
+        let args = schtasks_args(spec);
+        if dry_run {
+            println!("schtasks {}", args.join(" "));
+            return Ok(());
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run_tool("schtasks", &args)?;
+        println!("Installed logon task {}", SERVICE_LABEL);
+    } else {
+        let path = unit_path()?;
+        let unit = systemd_unit(spec);
+        if dry_run {
+            print!("{}", unit);
+            return Ok(());
+        }
+        write_definition(&path, &unit)?;
+        run_tool("systemctl", &["--user", "daemon-reload"])?;
+        run_tool("systemctl", &["--user", "enable", "--now", UNIT_NAME])?;
+        println!("Installed systemd user unit {}", path.display());
+        println!("To start it at boot without logging in: loginctl enable-linger");
+    }
+    println!("Gateway will listen on http://127.0.0.1:{}", spec.port);
+    Ok(())
+}
+
+fn uninstall() -> anyhow::Result<()> {
+    if cfg!(target_os = "macos") {
+        let path = plist_path()?;
+        run_tool("launchctl", &["unload", "-w", &path.display().to_string()])?;
+        std::fs::remove_file(&path)?;
+        println!("Removed launchd agent {}", path.display());
+    } else if cfg!(windows) {
+        run_tool("schtasks", &["/Delete", "/F", "/TN", SERVICE_LABEL])?;
+        println!("Removed logon task {}", SERVICE_LABEL);
+    } else {
+        let path = unit_path()?;
+        run_tool("systemctl", &["--user", "disable", "--now", UNIT_NAME])?;
+        std::fs::remove_file(&path)?;
+        run_tool("systemctl", &["--user", "daemon-reload"])?;
+        println!("Removed systemd user unit {}", path.display());
+    }
+    Ok(())
+}
+
+fn write_definition(path: &Path, content: &str) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+fn run_tool(program: &str, args: &[&str]) -> anyhow::Result<()> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Could not run {}: {}", program, e))?;
+    if !status.success() {
+        anyhow::bail!("{} {} failed ({})", program, args.join(" "), status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> ServiceSpec {
+        ServiceSpec {
+            executable: PathBuf::from("/opt/multi ai/multiai"),
+            port: 8080,
+            config_path: PathBuf::from("/home/me/.config/freetier/config.toml"),
+            log_path: PathBuf::from("/home/me/.local/share/multiai/multiai.log"),
+        }
+    }
+
+    #[test]
+    fn systemd_unit_runs_serve_with_port_and_config() {
+        let unit = systemd_unit(&spec());
+
+        assert!(unit.contains(
+            "ExecStart=\"/opt/multi ai/multiai\" serve --port 8080 --config /home/me/.config/freetier/config.toml"
+        ));
+        assert!(unit.contains("WantedBy=default.target"));
+    }
+
+    #[test]
+    fn launchd_plist_lists_arguments_and_log() {
+        let plist = launchd_plist(&spec());
+
+        assert!(plist.contains("<string>ai.multiai.gateway</string>"));
+        assert!(plist.contains("<string>8080</string>"));
+        assert!(plist.contains("<string>/home/me/.local/share/multiai/multiai.log</string>"));
+    }
+
+    #[test]
+    fn schtasks_quotes_paths_with_spaces() {
+        let args = schtasks_args(&spec());
+
+        assert_eq!(args[3], "ONLOGON");
+        assert!(args[7].starts_with("\"/opt/multi ai/multiai\" serve --port 8080"));
+    }
+}
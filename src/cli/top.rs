@@ -0,0 +1,412 @@
+//! `multiai top` - live terminal dashboard for a running gateway.
+//!
+//! Follows the gateway's inspect stream and shows recent requests,
+//! per-model latency sparklines, error counts, model health and the
+//! spending status from the local tracker.
+
+use super::with_gateway_key;
+use crate::config::{Config, LogVerbosity};
+use crate::http::{create_client, create_client_with_timeout, DETECTION_TIMEOUT};
+use crate::inspector::CapturedTransaction;
+use crate::logger::format_transaction;
+use crate::mcp::spending::{SpendingStatus, SpendingTracker};
+use futures::StreamExt;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Cell, List, ListItem, Paragraph, Row, Table};
+use ratatui::Frame;
+use reqwest::Client;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+/// Recent requests kept for the request pane.
+const RECENT_REQUESTS: usize = 100;
+
+/// Latency samples kept per model for its sparkline.
+const LATENCY_SAMPLES: usize = 30;
+
+/// Outcomes considered when judging model health.
+const HEALTH_WINDOW: usize = 5;
+
+/// How often the screen is redrawn.
+const TICK: Duration = Duration::from_millis(250);
+
+/// How often the model count and spending are refreshed.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Per-model request statistics.
+#[derive(Debug, Clone, Default)]
+pub struct ModelStats {
+    pub requests: u64,
+    pub errors: u64,
+    pub latencies_ms: VecDeque<u64>,
+    /// Recent outcomes, newest last (`true` = success).
+    pub outcomes: VecDeque<bool>,
+}
+
+impl ModelStats {
+    fn record(&mut self, latency_ms: u64, ok: bool) {
+        self.requests += 1;
+        if !ok {
+            self.errors += 1;
+        }
+        push_bounded(&mut self.latencies_ms, latency_ms, LATENCY_SAMPLES);
+        push_bounded(&mut self.outcomes, ok, HEALTH_WINDOW);
+    }
+
+    /// Mean latency over the retained samples.
+    pub fn mean_latency_ms(&self) -> Option<u64> {
+        if self.latencies_ms.is_empty() {
+            return None;
+        }
+        Some(self.latencies_ms.iter().sum::<u64>() / self.latencies_ms.len() as u64)
+    }
+
+    /// Health from the most recent outcomes.
+    pub fn health(&self) -> &'static str {
+        if self.outcomes.is_empty() {
+            "unknown"
+        } else if self.outcomes.iter().all(|ok| !ok) {
+            "down"
+        } else if self.outcomes.iter().any(|ok| !ok) {
+            "flaky"
+        } else {
+            "ok"
+        }
+    }
+}
+
+/// Everything the dashboard shows.
+#[derive(Debug, Default)]
+pub struct Dashboard {
+    pub gateway: String,
+    pub version: Option<String>,
+    pub models_available: usize,
+    pub spending: Option<SpendingStatus>,
+    pub recent: VecDeque<CapturedTransaction>,
+    pub models: BTreeMap<String, ModelStats>,
+}
+
+impl Dashboard {
+    /// Fold a completed transaction into the statistics.
+    pub fn record(&mut self, tx: CapturedTransaction) {
+        let status = tx.response.as_ref().map(|r| r.status).unwrap_or(0);
+        let ok = (200..400).contains(&status);
+        let model = tx
+            .request
+            .body
+            .as_ref()
+            .and_then(|b| b["model"].as_str())
+            .unwrap_or("unknown")
+            .to_string();
+        self.models
+            .entry(model)
+            .or_default()
+            .record(tx.timing.total_ms, ok);
+        push_bounded(&mut self.recent, tx, RECENT_REQUESTS);
+    }
+
+    /// Total error count across models.
+    pub fn total_errors(&self) -> u64 {
+        self.models.values().map(|s| s.errors).sum()
+    }
+}
+
+fn push_bounded<T>(queue: &mut VecDeque<T>, value: T, capacity: usize) {
+    if queue.len() >= capacity {
+        queue.pop_front();
+    }
+    queue.push_back(value);
+}
+
+/// Render values as a block-character sparkline scaled to the maximum.
+pub fn sparkline(values: &[u64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|&v| BARS[((v * (BARS.len() as u64 - 1)) / max) as usize])
+        .collect()
+}
+
+/// Run `multiai top`.
+pub async fn run(port: u16) -> anyhow::Result<()> {
+    let gateway = format!("http://127.0.0.1:{}", port);
+    let client = create_client_with_timeout(DETECTION_TIMEOUT);
+    let mut dashboard = Dashboard {
+        gateway: gateway.clone(),
+        ..Dashboard::default()
+    };
+
+    // Seed with what the gateway has already captured
+    let request = create_client().get(format!("{}/v1/inspect", gateway));
+    let body: serde_json::Value = with_gateway_key(request)
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("No gateway running at {} ({})", gateway, e))?
+        .json()
+        .await?;
+    let captured: Vec<CapturedTransaction> =
+        serde_json::from_value(body["transactions"].clone()).unwrap_or_default();
+    for tx in captured {
+        dashboard.record(tx);
+    }
+
+    let (sender, mut receiver) = unbounded_channel();
+    tokio::spawn(follow_stream(gateway.clone(), sender));
+
+    let tracker = SpendingTracker::open_default(Config::load_with_env().spending);
+    let mut terminal = ratatui::init();
+    let mut last_refresh: Option<Instant> = None;
+
+    let result = loop {
+        if last_refresh.is_none_or(|t| t.elapsed() >= REFRESH_INTERVAL) {
+            refresh(&client, &mut dashboard, tracker.as_ref()).await;
+            last_refresh = Some(Instant::now());
+        }
+        while let Ok(tx) = receiver.try_recv() {
+            dashboard.record(tx);
+        }
+
+        if let Err(e) = terminal.draw(|frame| draw(frame, &dashboard)) {
+            break Err(e.into());
+        }
+
+        let quit = tokio::task::block_in_place(|| -> std::io::Result<bool> {
+            if event::poll(TICK)? {
+                if let Event::Key(key) = event::read()? {
+                    return Ok(key.kind == KeyEventKind::Press
+                        && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc));
+                }
+            }
+            Ok(false)
+        });
+        match quit {
+            Ok(true) => break Ok(()),
+            Ok(false) => {}
+            Err(e) => break Err(e.into()),
+        }
+    };
+
+    ratatui::restore();
+    result
+}
+
+/// Refresh gateway health, the model count and spending.
+async fn refresh(client: &Client, dashboard: &mut Dashboard, tracker: Option<&SpendingTracker>) {
+    dashboard.version = super::daemon::check_health(client, &dashboard.gateway).await;
+
+    let models = async {
+        let request = client.get(format!("{}/v1/models", dashboard.gateway));
+        let body: serde_json::Value = with_gateway_key(request)
+            .send()
+            .await
+            .ok()?
+            .json()
+            .await
+            .ok()?;
+        body["data"].as_array().map(Vec::len)
+    };
+    if let Some(count) = models.await {
+        dashboard.models_available = count;
+    }
+
+    dashboard.spending = tracker.map(SpendingTracker::get_status);
+}
+
+/// Forward transactions from the gateway's inspect stream.
+async fn follow_stream(gateway: String, sender: UnboundedSender<CapturedTransaction>) {
+    let request = Client::new().get(format!("{}/v1/inspect/stream", gateway));
+    let Ok(response) = with_gateway_key(request)
+        .send()
+        .await
+    else {
+        return;
+    };
+
+    let mut buffer = String::new();
+    let mut body = response.bytes_stream();
+    while let Some(Ok(chunk)) = body.next().await {
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(newline) = buffer.find('\n') {
+            let line: String = buffer.drain(..=newline).collect();
+            let Some(data) = line.trim_end().strip_prefix("data:") else {
+                continue;
+            };
+            if let Ok(tx) = serde_json::from_str(data.trim()) {
+                if sender.send(tx).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, dashboard: &Dashboard) {
+    let [header, models, requests] = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Min(6),
+        Constraint::Percentage(40),
+    ])
+    .areas(frame.area());
+
+    let status = match &dashboard.version {
+        Some(version) => format!("multiai {} on {}", version, dashboard.gateway).green(),
+        None => format!("gateway unreachable at {}", dashboard.gateway).red(),
+    };
+    let spending = match &dashboard.spending {
+        Some(s) => format!(
+            "Spending: ${:.2}/${:.2} today ({:.0}%), ${:.2}/${:.2} this month{}",
+            s.daily_used,
+            s.daily_cap,
+            s.daily_percent,
+            s.monthly_used,
+            s.monthly_cap,
+            if s.at_warning { "  ⚠ near cap" } else { "" }
+        ),
+        None => "Spending: unavailable".to_string(),
+    };
+    let summary = format!(
+        "{} free models · {} requests seen · {} errors",
+        dashboard.models_available,
+        dashboard.models.values().map(|s| s.requests).sum::<u64>(),
+        dashboard.total_errors()
+    );
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from(status),
+            Line::from(summary),
+            Line::from(spending),
+        ])
+        .block(Block::bordered().title(" multiai top (q to quit) ")),
+        header,
+    );
+
+    let rows = dashboard.models.iter().map(|(model, stats)| {
+        let health_style = match stats.health() {
+            "ok" => Style::new().fg(Color::Green),
+            "flaky" => Style::new().fg(Color::Yellow),
+            "down" => Style::new().fg(Color::Red),
+            _ => Style::new(),
+        };
+        let samples: Vec<u64> = stats.latencies_ms.iter().copied().collect();
+        Row::new(vec![
+            Cell::from(model.clone()),
+            Cell::from(stats.requests.to_string()),
+            Cell::from(stats.errors.to_string()),
+            Cell::from(
+                stats
+                    .mean_latency_ms()
+                    .map(|ms| format!("{}ms", ms))
+                    .unwrap_or_default(),
+            ),
+            Cell::from(sparkline(&samples)),
+            Cell::from(stats.health()).style(health_style),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(2),
+            Constraint::Length(6),
+            Constraint::Length(6),
+            Constraint::Length(9),
+            Constraint::Length(LATENCY_SAMPLES as u16),
+            Constraint::Length(8),
+        ],
+    )
+    .header(Row::new(["MODEL", "REQS", "ERRS", "AVG", "LATENCY", "HEALTH"]).bold())
+    .block(Block::bordered().title(" Models "));
+    frame.render_widget(table, models);
+
+    let visible = requests.height.saturating_sub(2) as usize;
+    let items: Vec<ListItem> = dashboard
+        .recent
+        .iter()
+        .rev()
+        .take(visible)
+        .map(|tx| {
+            let line = format_transaction(tx, &LogVerbosity::Minimal);
+            let status = tx.response.as_ref().map(|r| r.status).unwrap_or(0);
+            if (200..400).contains(&status) {
+                ListItem::new(line)
+            } else {
+                ListItem::new(line).red()
+            }
+        })
+        .collect();
+    frame.render_widget(
+        List::new(items).block(Block::bordered().title(" Recent requests ")),
+        requests,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inspector::{CapturedRequest, CapturedResponse, TimingMetrics};
+
+    fn transaction(model: &str, status: u16, total_ms: u64) -> CapturedTransaction {
+        CapturedTransaction {
+            id: "tx".to_string(),
+            timestamp: chrono::Utc::now(),
+            request: CapturedRequest {
+                method: "POST".to_string(),
+                url: "/v1/chat/completions".to_string(),
+                headers: vec![],
+                body: Some(serde_json::json!({ "model": model })),
+            },
+            response: Some(CapturedResponse {
+                status,
+                headers: vec![],
+                body: None,
+            }),
+            timing: TimingMetrics {
+                total_ms,
+                ..TimingMetrics::default()
+            },
+            ab_arm: None,
+            virtual_key: None,
+            routed_model: None,
+            annotations: Default::default(),
+            start_time: None,
+        }
+    }
+
+    #[test]
+    fn records_per_model_stats() {
+        let mut dashboard = Dashboard::default();
+        dashboard.record(transaction("a", 200, 100));
+        dashboard.record(transaction("a", 502, 300));
+        dashboard.record(transaction("b", 200, 50));
+
+        let a = &dashboard.models["a"];
+        assert_eq!(a.requests, 2);
+        assert_eq!(a.errors, 1);
+        assert_eq!(a.mean_latency_ms(), Some(200));
+        assert_eq!(a.health(), "flaky");
+        assert_eq!(dashboard.models["b"].health(), "ok");
+        assert_eq!(dashboard.total_errors(), 1);
+        assert_eq!(dashboard.recent.len(), 3);
+    }
+
+    #[test]
+    fn model_is_down_when_recent_requests_all_fail() {
+        let mut stats = ModelStats::default();
+        stats.record(100, true);
+        for _ in 0..HEALTH_WINDOW {
+            stats.record(100, false);
+        }
+
+        assert_eq!(stats.health(), "down");
+    }
+
+    #[test]
+    fn sparkline_scales_to_maximum() {
+        assert_eq!(sparkline(&[0, 50, 100]), "▁▄█");
+        assert_eq!(sparkline(&[]), "");
+    }
+}
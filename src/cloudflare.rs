@@ -0,0 +1,190 @@
+//! Cloudflare Workers AI translation.
+//!
+//! Workers AI runs models at `/accounts/{account_id}/ai/run/{model}` and
+//! wraps replies in Cloudflare's `{"result": ..., "success": ...}` envelope,
+//! so completions routed to a `Source::Cloudflare` model are converted on the
+//! way out and back: the OpenAI body is trimmed to the fields Workers AI
+//! takes, and `result.response` (or the streamed `response` pieces) becomes
+//! a `chat.completion` (or `chat.completion.chunk`).
+
+use crate::http::take_sse_data;
+use axum::body::Bytes;
+use futures::{Stream, StreamExt};
+use serde_json::{json, Value};
+
+/// Run URL for `model` under the account's AI base `endpoint`.
+pub fn run_url(endpoint: &str, model: &str) -> String {
+    format!("{}/run/{}", endpoint, model)
+}
+
+/// Convert an OpenAI chat completion body to a Workers AI run request.
+pub fn to_workers_ai(request: &Value) -> Value {
+    let mut body = json!({
+        "messages": request["messages"],
+        "stream": request["stream"].as_bool().unwrap_or(false),
+    });
+    for field in ["temperature", "max_tokens"] {
+        if !request[field].is_null() {
+            body[field] = request[field].clone();
+        }
+    }
+    body
+}
+
+fn usage(usage: &Value) -> Option<Value> {
+    let prompt = usage["prompt_tokens"].as_u64()?;
+    let completion = usage["completion_tokens"].as_u64().unwrap_or(0);
+    Some(json!({
+        "prompt_tokens": prompt,
+        "completion_tokens": completion,
+        "total_tokens": usage["total_tokens"].as_u64().unwrap_or(prompt + completion),
+    }))
+}
+
+/// Convert a Workers AI run response to a `chat.completion`.
+pub fn from_workers_ai(model: &str, body: &Value) -> Value {
+    let result = &body["result"];
+    let mut completion = json!({
+        "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+        "object": "chat.completion",
+        "created": chrono::Utc::now().timestamp(),
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": {
+                "role": "assistant",
+                "content": result["response"].as_str().unwrap_or_default(),
+            },
+            "finish_reason": "stop",
+        }],
+    });
+    if let Some(usage) = usage(&result["usage"]) {
+        completion["usage"] = usage;
+    }
+    completion
+}
+
+/// Rewrites a Workers AI SSE stream into OpenAI chunk events.
+struct SseTranslator {
+    id: String,
+    model: String,
+    pending: Vec<u8>,
+}
+
+impl SseTranslator {
+    fn chunk(&self, delta: Value, finish_reason: Value) -> Value {
+        json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": chrono::Utc::now().timestamp(),
+            "model": self.model,
+            "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+        })
+    }
+
+    /// Translate the complete lines received so far, keeping a partial line
+    /// for the next read.
+    fn push(&mut self, bytes: &[u8]) -> Bytes {
+        self.pending.extend_from_slice(bytes);
+        let mut out = String::new();
+        for data in take_sse_data(&mut self.pending) {
+            if data == "[DONE]" {
+                let last = self.chunk(json!({}), json!("stop"));
+                out.push_str(&format!("data: {}\n\ndata: [DONE]\n\n", last));
+                continue;
+            }
+            let Ok(event) = serde_json::from_str::<Value>(&data) else {
+                continue;
+            };
+            let mut chunk = self.chunk(
+                json!({ "content": event["response"].as_str().unwrap_or_default() }),
+                Value::Null,
+            );
+            if let Some(usage) = usage(&event["usage"]) {
+                chunk["usage"] = usage;
+            }
+            out.push_str(&format!("data: {}\n\n", chunk));
+        }
+        Bytes::from(out)
+    }
+}
+
+/// Translate a streamed Workers AI run into an OpenAI SSE stream. Workers
+/// AI ends its stream with `data: [DONE]`, which is kept.
+pub fn translate_stream<S, E>(model: String, upstream: S) -> impl Stream<Item = Result<Bytes, E>>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+{
+    let mut translator = SseTranslator {
+        id: format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+        model,
+        pending: Vec::new(),
+    };
+    upstream.map(move |bytes| bytes.map(|bytes| translator.push(&bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_keep_only_workers_ai_fields() {
+        let request = json!({
+            "model": "@cf/meta/llama-3.1-8b-instruct",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "temperature": 0.2,
+            "max_tokens": null,
+            "stream": false
+        });
+
+        let body = to_workers_ai(&request);
+        assert_eq!(body["messages"][0]["content"], "Hi");
+        assert_eq!(body["temperature"], 0.2);
+        assert!(body.get("max_tokens").is_none());
+        assert!(body.get("model").is_none());
+        assert_eq!(
+            run_url("https://cf/ai", "@cf/meta/x"),
+            "https://cf/ai/run/@cf/meta/x"
+        );
+    }
+
+    #[test]
+    fn results_become_chat_completions() {
+        let body = json!({
+            "result": {
+                "response": "Hello",
+                "usage": {"prompt_tokens": 5, "completion_tokens": 1, "total_tokens": 6}
+            },
+            "success": true,
+            "errors": []
+        });
+
+        let completion = from_workers_ai("@cf/meta/llama-3.1-8b-instruct", &body);
+        assert_eq!(completion["object"], "chat.completion");
+        assert_eq!(completion["choices"][0]["message"]["content"], "Hello");
+        assert_eq!(completion["usage"]["total_tokens"], 6);
+    }
+
+    #[tokio::test]
+    async fn streams_are_rewritten_as_openai_chunks() {
+        let upstream = futures::stream::iter(vec![
+            Ok::<_, std::io::Error>(Bytes::from_static(b"data: {\"response\":\"Hi\"}\n\nda")),
+            Ok(Bytes::from_static(
+                b"ta: {\"response\":\"!\"}\n\ndata: [DONE]\n\n",
+            )),
+        ]);
+
+        let out: Vec<Bytes> = translate_stream("m".to_string(), upstream)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        let text = String::from_utf8(out.concat()).unwrap();
+        let events: Vec<&str> = text.split("\n\n").filter(|e| !e.is_empty()).collect();
+        assert_eq!(events.len(), 4);
+        let first: Value = serde_json::from_str(&events[0]["data: ".len()..]).unwrap();
+        assert_eq!(first["choices"][0]["delta"]["content"], "Hi");
+        let last: Value = serde_json::from_str(&events[2]["data: ".len()..]).unwrap();
+        assert_eq!(last["choices"][0]["finish_reason"], "stop");
+        assert_eq!(events[3], "data: [DONE]");
+    }
+}
@@ -2,8 +2,11 @@
 //!
 //! Loads settings from `~/.config/multiai/config.toml` with environment overrides.
 
-use crate::scanner::Source;
+use crate::routing::RoutingStrategy;
+use crate::scanner::{FreeModel, Source};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
 use std::path::PathBuf;
 
 // Spending limit constants (single source of truth)
@@ -27,6 +30,99 @@ pub struct Config {
     pub app: AppConfig,
     #[serde(default)]
     pub spending: SpendingConfig,
+    #[serde(default)]
+    pub mcp: McpServerConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    #[serde(default)]
+    pub evals: EvalsConfig,
+    #[serde(default)]
+    pub ollama: OllamaConfig,
+    #[serde(default)]
+    pub lmstudio: LmStudioConfig,
+    #[serde(default)]
+    pub llamacpp: LlamaCppConfig,
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub audio: AudioConfig,
+    #[serde(default)]
+    pub embeddings: EmbeddingsConfig,
+    #[serde(default)]
+    pub providers: ProvidersConfig,
+    #[serde(default)]
+    pub scanner: ScannerConfig,
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+}
+
+/// Where budget and availability alerts are posted (`[alerts]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct AlertsConfig {
+    /// Receives a JSON POST for spending thresholds, no free models, failing
+    /// sources and providers whose circuit opens.
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+/// Settings for the MCP server (`multiai --mcp`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct McpServerConfig {
+    #[serde(default)]
+    pub judges: JudgesConfig,
+    /// Named eval suites (`[mcp.suites]`), each a list of prompts.
+    #[serde(default)]
+    pub suites: BTreeMap<String, Vec<String>>,
+    /// External MCP servers whose tools are offered to models in chat completions.
+    #[serde(default)]
+    pub servers: Vec<McpServerEntry>,
+}
+
+/// An external MCP server launched over stdio (`[[mcp.servers]]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct McpServerEntry {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Seconds a request to the server may take before it is restarted.
+    #[serde(default = "default_mcp_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// Judge panel used for quality scoring in `compare_models`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct JudgesConfig {
+    /// Judge models to use. Empty means the built-in panel.
+    #[serde(default)]
+    pub models: Vec<JudgeModelConfig>,
+    /// Only use free judge models (no spending is recorded).
+    #[serde(default)]
+    pub free_only: bool,
+    /// Custom scoring rubric replacing the built-in criteria.
+    #[serde(default)]
+    pub rubric: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JudgeModelConfig {
+    /// OpenRouter model ID.
+    pub id: String,
+    /// Display name (defaults to the model ID).
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default = "default_judge_weight")]
+    pub weight: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -81,18 +177,100 @@ impl SpendingConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct GatewayConfig {
+    /// Address to listen on; `0.0.0.0` exposes the gateway on the LAN.
+    #[serde(default = "default_host")]
+    pub host: IpAddr,
     #[serde(default = "default_port")]
     pub port: u16,
     #[serde(default)]
     pub auto_start: bool,
+    /// Seconds in-flight streams may keep running after a shutdown signal.
+    #[serde(default = "default_shutdown_grace_secs")]
+    pub shutdown_grace_secs: u64,
+    /// Seconds between background rescans of every source; 0 scans only at startup.
+    #[serde(default = "default_model_refresh_secs")]
+    pub model_refresh_secs: u64,
+    /// Inject estimated token usage into streams that lack it.
+    #[serde(default)]
+    pub synthesize_stream_usage: bool,
+    /// Share one upstream call between identical concurrent requests.
+    #[serde(default)]
+    pub dedup_inflight: bool,
+    /// Keep history for requests carrying `X-MultiAI-Chat-Id` in the chat database.
+    #[serde(default)]
+    pub conversation_memory: bool,
+    /// Keep chats in the on-disk chat database, where they survive restarts
+    /// and MCP clients can read them, instead of in memory.
+    #[serde(default)]
+    pub persist_chats: bool,
+    /// Serve UI files from this directory, falling back to the embedded ones.
+    #[serde(default)]
+    pub static_dir: Option<PathBuf>,
+    /// Reject chat completions that don't carry a gateway API key from `/api/keys`.
+    #[serde(default)]
+    pub require_api_key: bool,
+    /// Also serve the gRPC API on this port (builds with the `grpc` feature).
+    #[serde(default)]
+    pub grpc_port: Option<u16>,
+    /// Requests per minute each client may send to `/v1` routes; 0 is unlimited.
+    #[serde(default)]
+    pub client_rpm: u32,
+    /// Estimated prompt tokens per minute each client may send; 0 is unlimited.
+    #[serde(default)]
+    pub client_tpm: u32,
+    /// Bearer token required on every `/v1` and `/api` route when set.
+    #[serde(default)]
+    pub api_key: Option<String>,
 }
 
+/// Provider API keys. Each accepts one key or a list of keys; requests
+/// rotate through a list and skip keys benched after a 401 or 429.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct ApiKeysConfig {
-    #[serde(default)]
-    pub openrouter: Option<String>,
-    #[serde(default)]
-    pub opencode_zen: Option<String>,
+    #[serde(default, with = "key_list", skip_serializing_if = "Vec::is_empty")]
+    pub openrouter: Vec<String>,
+    #[serde(default, with = "key_list", skip_serializing_if = "Vec::is_empty")]
+    pub opencode_zen: Vec<String>,
+    #[serde(default, with = "key_list", skip_serializing_if = "Vec::is_empty")]
+    pub groq: Vec<String>,
+    #[serde(default, with = "key_list", skip_serializing_if = "Vec::is_empty")]
+    pub gemini: Vec<String>,
+    #[serde(default, with = "key_list", skip_serializing_if = "Vec::is_empty")]
+    pub cerebras: Vec<String>,
+    #[serde(default, with = "key_list", skip_serializing_if = "Vec::is_empty")]
+    pub mistral: Vec<String>,
+    #[serde(default, with = "key_list", skip_serializing_if = "Vec::is_empty")]
+    pub together: Vec<String>,
+    #[serde(default, with = "key_list", skip_serializing_if = "Vec::is_empty")]
+    pub sambanova: Vec<String>,
+    #[serde(default, with = "key_list", skip_serializing_if = "Vec::is_empty")]
+    pub github_models: Vec<String>,
+}
+
+/// (De)serializes a key list as a string when it holds one key.
+mod key_list {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+        Ok(match OneOrMany::deserialize(deserializer)? {
+            OneOrMany::One(key) => vec![key],
+            OneOrMany::Many(keys) => keys,
+        })
+    }
+
+    pub fn serialize<S: Serializer>(keys: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+        match keys {
+            [key] => serializer.serialize_str(key),
+            _ => keys.serialize(serializer),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -105,6 +283,30 @@ pub struct LoggingConfig {
     pub format: LogFormat,
     #[serde(default = "default_retention_days")]
     pub retention_days: u32,
+    /// Write an append-only transcript file per chat.
+    #[serde(default)]
+    pub transcripts: bool,
+    #[serde(default)]
+    pub transcript_format: TranscriptFormat,
+    /// Also send transaction summaries to syslog (unix) or the Windows Event Log.
+    #[serde(default)]
+    pub syslog: bool,
+    /// Slow and failing request alerts; off unless `[logging.alerts]` is present.
+    #[serde(default)]
+    pub alerts: Option<AlertConfig>,
+}
+
+/// Thresholds that flag slow or failing requests. A value of 0 disables a check.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlertConfig {
+    #[serde(default = "default_slow_request_ms")]
+    pub slow_request_ms: u64,
+    #[serde(default = "default_error_status")]
+    pub error_status: u16,
+    /// Optional URL that receives a JSON POST for each alert. Also used for
+    /// `[alerts]` events when `[alerts] webhook_url` is unset.
+    #[serde(default)]
+    pub webhook_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -115,6 +317,376 @@ pub enum LogFormat {
     Both,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    #[default]
+    Markdown,
+    Jsonl,
+}
+
+/// On-disk cache of non-streaming completion responses (`[cache]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CacheConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Seconds a cached response stays valid.
+    #[serde(default = "default_cache_ttl_secs")]
+    pub ttl_secs: u64,
+    #[serde(default = "default_cache_max_entries")]
+    pub max_entries: usize,
+    /// Total size of cached bodies before the oldest are evicted.
+    #[serde(default = "default_cache_max_bytes")]
+    pub max_bytes: u64,
+}
+
+/// Embedding settings (`[embeddings]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmbeddingsConfig {
+    /// Keep embedding vectors on disk, keyed by model and text hash, so
+    /// re-indexing the same documents doesn't call the model again.
+    #[serde(default = "default_true")]
+    pub cache: bool,
+    /// Vectors kept before the least recently used are evicted.
+    #[serde(default = "default_embedding_cache_max_entries")]
+    pub cache_max_entries: usize,
+}
+
+/// Per-provider request settings (`[providers.*]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ProvidersConfig {
+    #[serde(default)]
+    pub openrouter: OpenRouterConfig,
+    #[serde(default)]
+    pub cloudflare: CloudflareConfig,
+    /// Extra OpenAI-compatible endpoints (`[[providers.custom]]`).
+    #[serde(default)]
+    pub custom: Vec<CustomProviderConfig>,
+}
+
+/// App attribution for OpenRouter, which gives attributed apps better
+/// free-tier limits (`[providers.openrouter]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct OpenRouterConfig {
+    /// Sent as `HTTP-Referer`.
+    #[serde(default)]
+    pub referer: Option<String>,
+    /// Sent as `X-Title`.
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// Cloudflare Workers AI account (`[providers.cloudflare]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct CloudflareConfig {
+    /// Account whose Workers AI models are scanned and run.
+    #[serde(default)]
+    pub account_id: Option<String>,
+    /// API token with Workers AI read and edit permissions.
+    #[serde(default)]
+    pub api_token: Option<String>,
+}
+
+/// A user-defined OpenAI-compatible endpoint, such as vLLM, LocalAI or a
+/// corporate gateway (`[[providers.custom]]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CustomProviderConfig {
+    /// Shown as the models' provider and used to look up the key.
+    pub name: String,
+    /// Base URL that `/chat/completions` and `/models` hang off, e.g.
+    /// "http://10.0.0.5:8000/v1".
+    pub base_url: String,
+    /// Sent as a bearer token when set.
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Models to offer. When empty they are discovered from `/models`.
+    #[serde(default)]
+    pub models: Vec<String>,
+}
+
+impl ProvidersConfig {
+    /// Extra headers to send with upstream requests to `source`.
+    pub fn headers(&self, source: &Source) -> Vec<(&'static str, String)> {
+        match source {
+            Source::OpenRouter => {
+                let attribution = &self.openrouter;
+                [
+                    ("HTTP-Referer", attribution.referer.clone()),
+                    ("X-Title", attribution.title.clone()),
+                ]
+                .into_iter()
+                .filter_map(|(name, value)| Some((name, value?)))
+                .collect()
+            }
+            Source::OpenCodeZen
+            | Source::Ollama
+            | Source::LmStudio
+            | Source::LlamaCpp
+            | Source::Groq
+            | Source::Gemini
+            | Source::Cerebras
+            | Source::Mistral
+            | Source::Together
+            | Source::SambaNova
+            | Source::GitHubModels
+            | Source::Cloudflare
+            | Source::Custom => Vec::new(),
+        }
+    }
+}
+
+/// Connection pool settings for the upstream client (`[http]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HttpConfig {
+    /// Idle connections kept open per upstream host.
+    #[serde(default = "default_pool_max_idle_per_host")]
+    pub pool_max_idle_per_host: usize,
+    /// Seconds an idle connection is kept for reuse.
+    #[serde(default = "default_pool_idle_timeout_secs")]
+    pub pool_idle_timeout_secs: u64,
+    /// Speak HTTP/2 without negotiation; only for upstreams that all support it.
+    #[serde(default)]
+    pub http2_prior_knowledge: bool,
+    /// Attempts per upstream request when it fails to connect or answers
+    /// 429/5xx, including the first; 1 turns retries off.
+    #[serde(default = "default_retry_max_attempts")]
+    pub retry_max_attempts: u32,
+    /// Backoff before the first retry, doubled for each one after.
+    #[serde(default = "default_retry_base_delay_ms")]
+    pub retry_base_delay_ms: u64,
+    /// Longest wait between attempts; an upstream whose `Retry-After` asks
+    /// for longer is not retried, and the request falls back to another model.
+    #[serde(default = "default_retry_max_delay_ms")]
+    pub retry_max_delay_ms: u64,
+}
+
+/// How `auto` requests pick a model (`[routing]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RoutingConfig {
+    /// How `auto` picks a model.
+    #[serde(default)]
+    pub strategy: RoutingStrategy,
+    /// Other free models tried when the chosen one answers 429/5xx or
+    /// can't be reached; 0 turns fallback off.
+    #[serde(default = "default_max_fallbacks")]
+    pub max_fallbacks: usize,
+    /// Times a model without `response_format` support is re-prompted when
+    /// its reply doesn't match the requested JSON format.
+    #[serde(default = "default_json_retries")]
+    pub json_retries: u32,
+    /// Split `auto` traffic between two models.
+    #[serde(default)]
+    pub ab_test: Option<AbTestConfig>,
+    /// Model names clients may send, and the model IDs they stand for.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+/// An A/B split of `auto` traffic (`[routing.ab_test]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AbTestConfig {
+    pub model_a: String,
+    pub model_b: String,
+    /// Percentage of `auto` requests sent to `model_b`.
+    #[serde(default = "default_ab_percent_b")]
+    pub percent_b: u8,
+}
+
+/// Local Ollama server (`[ollama]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct OllamaConfig {
+    /// Ollama server to route to, e.g. "http://127.0.0.1:11435". Not detected
+    /// automatically because the gateway itself listens on Ollama's port.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// How long Ollama keeps a model loaded after a request, e.g. "30m", or
+    /// "-1" to keep it loaded. Sent with every request to an Ollama model.
+    #[serde(default)]
+    pub keep_alive: Option<String>,
+    /// Models loaded at startup so the first request doesn't pay the load time.
+    #[serde(default)]
+    pub preload: Vec<String>,
+}
+
+/// Local LM Studio server (`[lmstudio]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct LmStudioConfig {
+    /// LM Studio server to route to. When unset, the gateway looks for one
+    /// on LM Studio's default port (1234) at startup.
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// How long each source's model list is cached (`[scanner]`). A slow or
+/// failing source is rescanned on its own schedule without throwing away
+/// the others' lists.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ScannerConfig {
+    /// Receives a JSON POST when free models appear or disappear.
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Seconds a source's scan is reused unless set below (default 3600).
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub ollama_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub lmstudio_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub llamacpp_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub opencode_zen_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub openrouter_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub groq_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub gemini_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub cerebras_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub mistral_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub together_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub sambanova_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub github_models_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub cloudflare_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub custom_ttl_secs: Option<u64>,
+}
+
+impl ScannerConfig {
+    /// TTL set for one source, if any.
+    pub fn source_ttl_secs(&self, source: Source) -> Option<u64> {
+        match source {
+            Source::Ollama => self.ollama_ttl_secs,
+            Source::LmStudio => self.lmstudio_ttl_secs,
+            Source::LlamaCpp => self.llamacpp_ttl_secs,
+            Source::OpenCodeZen => self.opencode_zen_ttl_secs,
+            Source::OpenRouter => self.openrouter_ttl_secs,
+            Source::Groq => self.groq_ttl_secs,
+            Source::Gemini => self.gemini_ttl_secs,
+            Source::Cerebras => self.cerebras_ttl_secs,
+            Source::Mistral => self.mistral_ttl_secs,
+            Source::Together => self.together_ttl_secs,
+            Source::SambaNova => self.sambanova_ttl_secs,
+            Source::GitHubModels => self.github_models_ttl_secs,
+            Source::Cloudflare => self.cloudflare_ttl_secs,
+            Source::Custom => self.custom_ttl_secs,
+        }
+    }
+}
+
+/// Local llama.cpp server (`[llamacpp]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct LlamaCppConfig {
+    /// `llama-server` to route to. When unset, the gateway looks for one on
+    /// its default port (8080) at startup.
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// Audio backends (`[audio]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct AudioConfig {
+    /// OpenAI-compatible text-to-speech servers for `/v1/audio/speech`,
+    /// tried in order.
+    #[serde(default)]
+    pub speech: Vec<SpeechBackendConfig>,
+}
+
+/// One text-to-speech server (`[[audio.speech]]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SpeechBackendConfig {
+    /// Base URL of the OpenAI-compatible API, e.g. "http://127.0.0.1:8880/v1".
+    pub url: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Models this server provides. Requests for them go here first; an
+    /// empty list accepts any model.
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// Model sent in place of a requested one this server doesn't list.
+    #[serde(default)]
+    pub default_model: Option<String>,
+}
+
+/// Rhai script defining `on_request`/`on_response` hooks (`[hooks]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct HooksConfig {
+    /// Path to the script file.
+    #[serde(default)]
+    pub script: Option<PathBuf>,
+    /// The script itself, when it's short enough to keep in the config.
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// Recurring jobs run inside the server (`[scheduler]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct SchedulerConfig {
+    #[serde(default)]
+    pub jobs: Vec<ScheduledJobConfig>,
+    /// Where `chat_backup` writes its exports (default: `<logging.folder>/backups`).
+    #[serde(default)]
+    pub backup_folder: Option<PathBuf>,
+}
+
+/// One recurring job (`[[scheduler.jobs]]`). Set either `every` or `at`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScheduledJobConfig {
+    /// model_refresh, log_prune, chat_backup, health_probe or eval_run.
+    pub task: String,
+    /// Name shown in `/health`; defaults to the task.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Interval such as "30s", "15m", "6h" or "1d".
+    #[serde(default)]
+    pub every: Option<String>,
+    /// Daily at this UTC time, "HH:MM".
+    #[serde(default)]
+    pub at: Option<String>,
+}
+
+/// Scheduled runs of stored eval datasets (`[evals]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct EvalsConfig {
+    /// Hours between scheduled runs; nothing is scheduled when unset.
+    #[serde(default)]
+    pub schedule_hours: Option<u64>,
+    /// Datasets to run on the schedule (default: all of them).
+    #[serde(default)]
+    pub datasets: Vec<String>,
+    /// Models to evaluate (default: the comparator's pick of free models).
+    #[serde(default)]
+    pub models: Option<Vec<String>>,
+    #[serde(default)]
+    pub max_models: Option<usize>,
+}
+
+/// Anonymous usage reports (`[telemetry]`), off unless explicitly enabled.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TelemetryConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// URL that receives the JSON reports; nothing is sent without one.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Seconds between reports.
+    #[serde(default = "default_telemetry_interval_secs")]
+    pub interval_secs: u64,
+}
+
+impl TelemetryConfig {
+    /// Endpoint to report to, if telemetry is enabled and one is configured.
+    pub fn active_endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref().filter(|_| self.enabled)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct InspectorConfig {
     #[serde(default = "default_max_transactions")]
@@ -140,8 +712,23 @@ pub enum LogVerbosity {
     Verbose,
 }
 
+impl LogVerbosity {
+    /// The next level, wrapping from verbose back to minimal.
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Minimal => Self::Compact,
+            Self::Compact => Self::Verbose,
+            Self::Verbose => Self::Minimal,
+        }
+    }
+}
+
 // Default value functions
+fn default_host() -> IpAddr { IpAddr::from([127, 0, 0, 1]) }
 fn default_port() -> u16 { 11434 }
+fn default_mcp_timeout_secs() -> u64 { 60 }
+fn default_shutdown_grace_secs() -> u64 { 30 }
+fn default_model_refresh_secs() -> u64 { 30 * 60 }
 fn default_true() -> bool { true }
 fn default_log_folder() -> PathBuf {
     dirs::data_local_dir()
@@ -153,13 +740,55 @@ fn default_format() -> LogFormat { LogFormat::Har }
 fn default_retention_days() -> u32 { 30 }
 fn default_max_transactions() -> usize { 1000 }
 fn default_verbosity() -> LogVerbosity { LogVerbosity::Compact }
+fn default_slow_request_ms() -> u64 { 20_000 }
+fn default_judge_weight() -> f64 { 1.0 }
+fn default_error_status() -> u16 { 500 }
+fn default_cache_ttl_secs() -> u64 { 24 * 60 * 60 }
+fn default_cache_max_entries() -> usize { 10_000 }
+fn default_cache_max_bytes() -> u64 { 100 * 1024 * 1024 }
+fn default_embedding_cache_max_entries() -> usize { 100_000 }
+fn default_pool_max_idle_per_host() -> usize { crate::http::POOL_MAX_IDLE_PER_HOST }
+fn default_pool_idle_timeout_secs() -> u64 { crate::http::POOL_IDLE_TIMEOUT.as_secs() }
+fn default_retry_max_attempts() -> u32 { 2 }
+fn default_retry_base_delay_ms() -> u64 { 250 }
+fn default_retry_max_delay_ms() -> u64 { 5_000 }
+fn default_telemetry_interval_secs() -> u64 { 24 * 60 * 60 }
+fn default_ab_percent_b() -> u8 { 50 }
+
+fn default_max_fallbacks() -> usize { 2 }
+fn default_json_retries() -> u32 { 2 }
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            strategy: RoutingStrategy::default(),
+            max_fallbacks: default_max_fallbacks(),
+            json_retries: default_json_retries(),
+            ab_test: None,
+            aliases: BTreeMap::new(),
+        }
+    }
+}
 
 
 impl Default for GatewayConfig {
     fn default() -> Self {
         Self {
+            host: default_host(),
             port: default_port(),
             auto_start: false,
+            shutdown_grace_secs: default_shutdown_grace_secs(),
+            model_refresh_secs: default_model_refresh_secs(),
+            synthesize_stream_usage: false,
+            dedup_inflight: false,
+            conversation_memory: false,
+            persist_chats: false,
+            static_dir: None,
+            require_api_key: false,
+            grpc_port: None,
+            client_rpm: 0,
+            client_tpm: 0,
+            api_key: None,
         }
     }
 }
@@ -171,6 +800,63 @@ impl Default for LoggingConfig {
             folder: default_log_folder(),
             format: default_format(),
             retention_days: default_retention_days(),
+            transcripts: false,
+            transcript_format: TranscriptFormat::default(),
+            syslog: false,
+            alerts: None,
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_cache_ttl_secs(),
+            max_entries: default_cache_max_entries(),
+            max_bytes: default_cache_max_bytes(),
+        }
+    }
+}
+
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            cache: default_true(),
+            cache_max_entries: default_embedding_cache_max_entries(),
+        }
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: default_pool_max_idle_per_host(),
+            pool_idle_timeout_secs: default_pool_idle_timeout_secs(),
+            http2_prior_knowledge: false,
+            retry_max_attempts: default_retry_max_attempts(),
+            retry_base_delay_ms: default_retry_base_delay_ms(),
+            retry_max_delay_ms: default_retry_max_delay_ms(),
+        }
+    }
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: None,
+            interval_secs: default_telemetry_interval_secs(),
+        }
+    }
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            slow_request_ms: default_slow_request_ms(),
+            error_status: default_error_status(),
+            webhook_url: None,
         }
     }
 }
@@ -224,10 +910,37 @@ impl Config {
     /// Apply environment variable overrides.
     pub fn with_env_overrides(mut self) -> Self {
         if let Ok(key) = std::env::var("OPENROUTER_API_KEY") {
-            self.api_keys.openrouter = Some(key);
+            self.api_keys.openrouter = vec![key];
         }
         if let Ok(key) = std::env::var("OPENCODE_ZEN_API_KEY") {
-            self.api_keys.opencode_zen = Some(key);
+            self.api_keys.opencode_zen = vec![key];
+        }
+        if let Ok(key) = std::env::var("GROQ_API_KEY") {
+            self.api_keys.groq = vec![key];
+        }
+        if let Ok(key) = std::env::var("GEMINI_API_KEY") {
+            self.api_keys.gemini = vec![key];
+        }
+        if let Ok(key) = std::env::var("CEREBRAS_API_KEY") {
+            self.api_keys.cerebras = vec![key];
+        }
+        if let Ok(key) = std::env::var("MISTRAL_API_KEY") {
+            self.api_keys.mistral = vec![key];
+        }
+        if let Ok(key) = std::env::var("TOGETHER_API_KEY") {
+            self.api_keys.together = vec![key];
+        }
+        if let Ok(key) = std::env::var("SAMBANOVA_API_KEY") {
+            self.api_keys.sambanova = vec![key];
+        }
+        if let Ok(key) = std::env::var("GITHUB_TOKEN") {
+            self.api_keys.github_models = vec![key];
+        }
+        if let Ok(id) = std::env::var("CLOUDFLARE_ACCOUNT_ID") {
+            self.providers.cloudflare.account_id = Some(id);
+        }
+        if let Ok(token) = std::env::var("CLOUDFLARE_API_TOKEN") {
+            self.providers.cloudflare.api_token = Some(token);
         }
         // Spending caps
         if let Ok(val) = std::env::var("MULTIAI_DAILY_CAP") {
@@ -262,13 +975,85 @@ impl Config {
         std::fs::write(&path, content).map_err(ConfigError::Io)
     }
 
-    /// Get API key for a given source.
+    /// Get API key for a given source, the first of its pool.
     pub fn get_api_key(&self, source: &Source) -> Option<String> {
+        self.get_api_keys(source).into_iter().next()
+    }
+
+    /// Every API key configured for a given source, in rotation order.
+    pub fn get_api_keys(&self, source: &Source) -> Vec<String> {
         match source {
             Source::OpenRouter => self.api_keys.openrouter.clone(),
             Source::OpenCodeZen => self.api_keys.opencode_zen.clone(),
-            Source::Ollama => None,
+            Source::Groq => self.api_keys.groq.clone(),
+            Source::Gemini => self.api_keys.gemini.clone(),
+            Source::Cerebras => self.api_keys.cerebras.clone(),
+            Source::Mistral => self.api_keys.mistral.clone(),
+            Source::Together => self.api_keys.together.clone(),
+            Source::SambaNova => self.api_keys.sambanova.clone(),
+            Source::GitHubModels => self.api_keys.github_models.clone(),
+            Source::Cloudflare => self.providers.cloudflare.api_token.iter().cloned().collect(),
+            Source::Custom => self
+                .providers
+                .custom
+                .iter()
+                .filter_map(|provider| provider.api_key.clone())
+                .collect(),
+            Source::Ollama | Source::LmStudio | Source::LlamaCpp => Vec::new(),
+        }
+    }
+
+    /// API key to send to `model`'s endpoint. Custom providers each have
+    /// their own key; other models use their source's key.
+    pub fn get_model_api_key(&self, model: &FreeModel) -> Option<String> {
+        self.get_model_api_keys(model).into_iter().next()
+    }
+
+    /// Every API key `model`'s endpoint accepts, in rotation order.
+    pub fn get_model_api_keys(&self, model: &FreeModel) -> Vec<String> {
+        match model.source {
+            Source::Custom => self
+                .providers
+                .custom
+                .iter()
+                .find(|provider| provider.name == model.provider)
+                .and_then(|provider| provider.api_key.clone())
+                .into_iter()
+                .collect(),
+            _ => self.get_api_keys(&model.source),
+        }
+    }
+
+    /// Set or clear the API key for a given source.
+    /// Returns false for sources that don't take a key.
+    pub fn set_api_key(&mut self, source: &Source, key: Option<String>) -> bool {
+        match source {
+            Source::OpenRouter => self.api_keys.openrouter = key.into_iter().collect(),
+            Source::OpenCodeZen => self.api_keys.opencode_zen = key.into_iter().collect(),
+            Source::Groq => self.api_keys.groq = key.into_iter().collect(),
+            Source::Gemini => self.api_keys.gemini = key.into_iter().collect(),
+            Source::Cerebras => self.api_keys.cerebras = key.into_iter().collect(),
+            Source::Mistral => self.api_keys.mistral = key.into_iter().collect(),
+            Source::Together => self.api_keys.together = key.into_iter().collect(),
+            Source::SambaNova => self.api_keys.sambanova = key.into_iter().collect(),
+            Source::GitHubModels => self.api_keys.github_models = key.into_iter().collect(),
+            Source::Cloudflare => self.providers.cloudflare.api_token = key,
+            Source::Ollama | Source::LmStudio | Source::LlamaCpp | Source::Custom => {
+                return false
+            }
         }
+        true
+    }
+
+    /// The alert webhook: `[alerts] webhook_url`, else the older
+    /// `[logging.alerts] webhook_url`.
+    pub fn alert_webhook_url(&self) -> Option<String> {
+        self.alerts.webhook_url.clone().or_else(|| {
+            self.logging
+                .alerts
+                .as_ref()
+                .and_then(|alerts| alerts.webhook_url.clone())
+        })
     }
 }
 
@@ -317,8 +1102,8 @@ openrouter = "sk-or-test-key"
         let config = Config::load_from(config_path).unwrap();
 
         assert_eq!(config.gateway.port, 9090);
-        assert_eq!(config.gateway.auto_start, true);
-        assert_eq!(config.api_keys.openrouter, Some("sk-or-test-key".to_string()));
+        assert!(config.gateway.auto_start);
+        assert_eq!(config.api_keys.openrouter, vec!["sk-or-test-key"]);
     }
 
     #[test]
@@ -326,8 +1111,8 @@ openrouter = "sk-or-test-key"
         let config = Config::load_from(PathBuf::from("/nonexistent/path/config.toml")).unwrap();
 
         assert_eq!(config.gateway.port, 11434); // Ollama-compatible default
-        assert_eq!(config.gateway.auto_start, false);
-        assert_eq!(config.logging.enabled, true);
+        assert!(!config.gateway.auto_start);
+        assert!(config.logging.enabled);
         assert_eq!(config.inspector.max_transactions, 1000);
     }
 
@@ -338,8 +1123,8 @@ openrouter = "sk-or-test-key"
 
         let config = Config::default().with_env_overrides();
 
-        assert_eq!(config.api_keys.openrouter, Some("env-openrouter-key".to_string()));
-        assert_eq!(config.api_keys.opencode_zen, Some("env-zen-key".to_string()));
+        assert_eq!(config.api_keys.openrouter, vec!["env-openrouter-key"]);
+        assert_eq!(config.api_keys.opencode_zen, vec!["env-zen-key"]);
 
         // Cleanup
         std::env::remove_var("OPENROUTER_API_KEY");
@@ -378,7 +1163,7 @@ monthly_cap = 100.0
         let config_path = dir.path().join("config.toml");
 
         let config = Config {
-            gateway: GatewayConfig { port: 3000, auto_start: true },
+            gateway: GatewayConfig { port: 3000, auto_start: true, ..GatewayConfig::default() },
             ..Config::default()
         };
 
@@ -386,7 +1171,7 @@ monthly_cap = 100.0
 
         let loaded = Config::load_from(config_path).unwrap();
         assert_eq!(loaded.gateway.port, 3000);
-        assert_eq!(loaded.gateway.auto_start, true);
+        assert!(loaded.gateway.auto_start);
     }
 
     #[test]
@@ -420,6 +1205,13 @@ log_verbosity = "{}"
         }
     }
 
+    #[test]
+    fn log_verbosity_cycles_through_levels() {
+        assert_eq!(LogVerbosity::Minimal.next(), LogVerbosity::Compact);
+        assert_eq!(LogVerbosity::Compact.next(), LogVerbosity::Verbose);
+        assert_eq!(LogVerbosity::Verbose.next(), LogVerbosity::Minimal);
+    }
+
     #[test]
     fn parses_all_log_formats() {
         let dir = tempfile::tempdir().unwrap();
@@ -440,14 +1232,447 @@ format = "{}"
         }
     }
 
+    #[test]
+    fn parses_transcript_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[logging]
+transcripts = true
+transcript_format = "jsonl"
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        assert!(config.logging.transcripts);
+        assert_eq!(config.logging.transcript_format, TranscriptFormat::Jsonl);
+    }
+
+    #[test]
+    fn parses_alert_thresholds() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[logging.alerts]
+slow_request_ms = 5000
+webhook_url = "http://localhost:9000/hook"
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        let alerts = config.logging.alerts.unwrap();
+        assert_eq!(alerts.slow_request_ms, 5000);
+        assert_eq!(alerts.error_status, 500);
+        assert_eq!(alerts.webhook_url.as_deref(), Some("http://localhost:9000/hook"));
+
+        assert_eq!(Config::default().logging.alerts, None);
+    }
+
+    #[test]
+    fn alert_webhook_prefers_alerts_section_over_logging_alias() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[logging.alerts]
+webhook_url = "http://localhost:9000/old"
+"#).unwrap();
+        let config = Config::load_from(config_path.clone()).unwrap();
+        assert_eq!(config.alert_webhook_url().as_deref(), Some("http://localhost:9000/old"));
+
+        fs::write(&config_path, r#"
+[alerts]
+webhook_url = "http://localhost:9000/hook"
+
+[logging.alerts]
+webhook_url = "http://localhost:9000/old"
+"#).unwrap();
+        let config = Config::load_from(config_path).unwrap();
+        assert_eq!(config.alert_webhook_url().as_deref(), Some("http://localhost:9000/hook"));
+        assert_eq!(Config::default().alert_webhook_url(), None);
+    }
+
+    #[test]
+    fn parses_judge_panel_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[mcp.judges]
+free_only = true
+rubric = "Is it correct?"
+
+[[mcp.judges.models]]
+id = "openai/gpt-4o"
+weight = 2.0
+
+[[mcp.judges.models]]
+id = "anthropic/claude-sonnet-4"
+name = "Claude"
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        let judges = &config.mcp.judges;
+        assert!(judges.free_only);
+        assert_eq!(judges.rubric.as_deref(), Some("Is it correct?"));
+        assert_eq!(judges.models.len(), 2);
+        assert_eq!(judges.models[0].weight, 2.0);
+        assert_eq!(judges.models[1].weight, 1.0);
+        assert_eq!(judges.models[1].name.as_deref(), Some("Claude"));
+    }
+
+    #[test]
+    fn parses_eval_suites() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[mcp.suites]
+coding = ["Write fizzbuzz in Rust", "Explain borrowing"]
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        assert_eq!(config.mcp.suites["coding"].len(), 2);
+        assert_eq!(config.mcp.suites["coding"][0], "Write fizzbuzz in Rust");
+    }
+
+    #[test]
+    fn parses_eval_schedule() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[evals]
+schedule_hours = 24
+datasets = ["rust-basics"]
+max_models = 3
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        assert_eq!(config.evals.schedule_hours, Some(24));
+        assert_eq!(config.evals.datasets, vec!["rust-basics"]);
+        assert_eq!(config.evals.max_models, Some(3));
+        assert_eq!(Config::default().evals.schedule_hours, None);
+    }
+
+    #[test]
+    fn parses_scheduler_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[[scheduler.jobs]]
+task = "model_refresh"
+every = "30m"
+
+[[scheduler.jobs]]
+task = "chat_backup"
+name = "nightly-backup"
+at = "03:00"
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        let jobs = &config.scheduler.jobs;
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].task, "model_refresh");
+        assert_eq!(jobs[0].every.as_deref(), Some("30m"));
+        assert_eq!(jobs[1].name.as_deref(), Some("nightly-backup"));
+        assert_eq!(jobs[1].at.as_deref(), Some("03:00"));
+        assert!(Config::default().scheduler.jobs.is_empty());
+    }
+
+    #[test]
+    fn parses_inline_hook_script() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[hooks]
+source = """
+fn on_request(ctx) { #{ headers: #{ "x-team": "docs" } } }
+"""
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        assert!(config.hooks.source.unwrap().contains("fn on_request"));
+        assert_eq!(config.hooks.script, None);
+    }
+
+    #[test]
+    fn parses_speech_backends() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[[audio.speech]]
+url = "http://127.0.0.1:8880/v1"
+models = ["kokoro"]
+default_model = "kokoro"
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        let speech = &config.audio.speech;
+        assert_eq!(speech.len(), 1);
+        assert_eq!(speech[0].url, "http://127.0.0.1:8880/v1");
+        assert_eq!(speech[0].models, vec!["kokoro"]);
+        assert_eq!(speech[0].api_key, None);
+    }
+
+    #[test]
+    fn parses_embedding_cache_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[embeddings]
+cache_max_entries = 500
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        assert!(config.embeddings.cache);
+        assert_eq!(config.embeddings.cache_max_entries, 500);
+    }
+
+    #[test]
+    fn parses_grpc_port() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[gateway]
+grpc_port = 50051
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        assert_eq!(config.gateway.grpc_port, Some(50051));
+        assert_eq!(Config::default().gateway.grpc_port, None);
+    }
+
+    #[test]
+    fn parses_openrouter_attribution() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[providers.openrouter]
+referer = "https://example.com"
+title = "Example"
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        assert_eq!(
+            config.providers.headers(&Source::OpenRouter),
+            vec![
+                ("HTTP-Referer", "https://example.com".to_string()),
+                ("X-Title", "Example".to_string()),
+            ]
+        );
+        assert!(config.providers.headers(&Source::Ollama).is_empty());
+        assert!(Config::default().providers.headers(&Source::OpenRouter).is_empty());
+    }
+
+    #[test]
+    fn parses_cloudflare_account() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[providers.cloudflare]
+account_id = "abc123"
+api_token = "cf-token"
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        assert_eq!(config.providers.cloudflare.account_id.as_deref(), Some("abc123"));
+        assert_eq!(config.get_api_key(&Source::Cloudflare).as_deref(), Some("cf-token"));
+    }
+
+    #[test]
+    fn parses_lmstudio_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[lmstudio]
+url = "http://127.0.0.1:1235"
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        assert_eq!(config.lmstudio.url.as_deref(), Some("http://127.0.0.1:1235"));
+        assert_eq!(config.get_api_key(&Source::LmStudio), None);
+        assert_eq!(Config::default().lmstudio.url, None);
+    }
+
+    #[test]
+    fn parses_scanner_ttls() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[scanner]
+ttl_secs = 7200
+ollama_ttl_secs = 60
+openrouter_ttl_secs = 600
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        assert_eq!(config.scanner.ttl_secs, Some(7200));
+        assert_eq!(config.scanner.source_ttl_secs(Source::Ollama), Some(60));
+        assert_eq!(config.scanner.source_ttl_secs(Source::OpenRouter), Some(600));
+        assert_eq!(config.scanner.source_ttl_secs(Source::OpenCodeZen), None);
+    }
+
+    #[test]
+    fn parses_routing_strategy() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, "[routing]\nstrategy = \"least_latency\"\n").unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        assert_eq!(config.routing.strategy, RoutingStrategy::LeastLatency);
+        assert_eq!(Config::default().routing.strategy, RoutingStrategy::Adaptive);
+    }
+
+    #[test]
+    fn fallback_defaults_to_two_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, "[routing.aliases]\nfast = \"llama3\"\n").unwrap();
+        assert_eq!(Config::load_from(config_path.clone()).unwrap().routing.max_fallbacks, 2);
+        assert_eq!(Config::default().routing.max_fallbacks, 2);
+
+        fs::write(&config_path, "[routing]\nmax_fallbacks = 0\n").unwrap();
+        assert_eq!(Config::load_from(config_path).unwrap().routing.max_fallbacks, 0);
+    }
+
+    #[test]
+    fn parses_api_key_pools() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[api_keys]
+openrouter = ["k1", "k2"]
+groq = "gsk-one"
+"#).unwrap();
+
+        let config = Config::load_from(config_path.clone()).unwrap();
+        assert_eq!(config.get_api_keys(&Source::OpenRouter), vec!["k1", "k2"]);
+        assert_eq!(config.get_api_key(&Source::OpenRouter).as_deref(), Some("k1"));
+        assert_eq!(config.get_api_keys(&Source::Groq), vec!["gsk-one"]);
+        assert!(config.get_api_keys(&Source::Gemini).is_empty());
+
+        // A single key is written back as a string, a pool as an array
+        config.save_to(config_path.clone()).unwrap();
+        let saved = fs::read_to_string(&config_path).unwrap();
+        assert!(saved.contains(r#"groq = "gsk-one""#));
+        assert_eq!(Config::load_from(config_path).unwrap(), config);
+    }
+
+    #[test]
+    fn parses_llamacpp_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[llamacpp]
+url = "http://127.0.0.1:8081"
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        assert_eq!(config.llamacpp.url.as_deref(), Some("http://127.0.0.1:8081"));
+        assert_eq!(config.get_api_key(&Source::LlamaCpp), None);
+    }
+
+    #[test]
+    fn parses_custom_providers() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[[providers.custom]]
+name = "vllm"
+base_url = "http://10.0.0.5:8000/v1"
+models = ["meta-llama/Llama-3.1-8B-Instruct"]
+
+[[providers.custom]]
+name = "corp"
+base_url = "https://llm.corp.example/v1"
+api_key = "corp-key"
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        let custom = &config.providers.custom;
+        assert_eq!(custom.len(), 2);
+        assert_eq!(custom[0].models, vec!["meta-llama/Llama-3.1-8B-Instruct"]);
+        assert!(custom[1].models.is_empty());
+
+        let model = |provider: &str| FreeModel {
+            id: "m".to_string(),
+            provider: provider.to_string(),
+            endpoint: String::new(),
+            source: Source::Custom,
+            context_length: None,
+            modalities: Vec::new(),
+            supports_tools: None,
+            supports_response_format: None,
+            source_index: None,
+        };
+        assert_eq!(config.get_model_api_key(&model("corp")).as_deref(), Some("corp-key"));
+        assert_eq!(config.get_model_api_key(&model("vllm")), None);
+        assert_eq!(config.get_api_keys(&Source::Custom), vec!["corp-key"]);
+    }
+
+    #[test]
+    fn parses_ollama_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[ollama]
+url = "http://127.0.0.1:11435"
+keep_alive = "30m"
+preload = ["llama3", "qwen2.5"]
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        assert_eq!(config.ollama.url.as_deref(), Some("http://127.0.0.1:11435"));
+        assert_eq!(config.ollama.keep_alive.as_deref(), Some("30m"));
+        assert_eq!(config.ollama.preload, vec!["llama3", "qwen2.5"]);
+    }
+
+    #[test]
+    fn parses_external_mcp_servers() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+
+        fs::write(&config_path, r#"
+[[mcp.servers]]
+name = "files"
+command = "npx"
+args = ["-y", "@modelcontextprotocol/server-filesystem", "/tmp"]
+
+[mcp.servers.env]
+DEBUG = "1"
+"#).unwrap();
+
+        let config = Config::load_from(config_path).unwrap();
+        let server = &config.mcp.servers[0];
+        assert_eq!(server.name, "files");
+        assert_eq!(server.args.len(), 3);
+        assert_eq!(server.env.get("DEBUG").map(String::as_str), Some("1"));
+    }
+
     #[test]
     fn get_api_key_returns_openrouter_key() {
         use crate::scanner::Source;
 
         let config = Config {
             api_keys: ApiKeysConfig {
-                openrouter: Some("sk-or-test".to_string()),
-                opencode_zen: None,
+                openrouter: vec!["sk-or-test".to_string()],
+                ..ApiKeysConfig::default()
             },
             ..Config::default()
         };
@@ -461,8 +1686,8 @@ format = "{}"
 
         let config = Config {
             api_keys: ApiKeysConfig {
-                openrouter: None,
-                opencode_zen: Some("zen-key".to_string()),
+                opencode_zen: vec!["zen-key".to_string()],
+                ..ApiKeysConfig::default()
             },
             ..Config::default()
         };
@@ -476,8 +1701,9 @@ format = "{}"
 
         let config = Config {
             api_keys: ApiKeysConfig {
-                openrouter: Some("key".to_string()),
-                opencode_zen: Some("key".to_string()),
+                openrouter: vec!["key".to_string()],
+                opencode_zen: vec!["key".to_string()],
+                ..ApiKeysConfig::default()
             },
             ..Config::default()
         };
@@ -485,6 +1711,20 @@ format = "{}"
         assert_eq!(config.get_api_key(&Source::Ollama), None);
     }
 
+    #[test]
+    fn set_api_key_updates_cloud_sources_only() {
+        use crate::scanner::Source;
+
+        let mut config = Config::default();
+
+        assert!(config.set_api_key(&Source::OpenRouter, Some("sk-or-new".to_string())));
+        assert!(!config.set_api_key(&Source::Ollama, Some("key".to_string())));
+        assert_eq!(config.get_api_key(&Source::OpenRouter), Some("sk-or-new".to_string()));
+
+        assert!(config.set_api_key(&Source::OpenRouter, None));
+        assert_eq!(config.get_api_key(&Source::OpenRouter), None);
+    }
+
     #[test]
     fn load_with_env_applies_spending_overrides() {
         // Test with spending cap env var (less likely to conflict with other tests)
@@ -129,11 +129,9 @@ fn extract_text_from_docx_xml(xml: &str) -> Result<String, String> {
                     }
                 }
             }
-            Ok(Event::Text(e)) => {
-                if in_text_element {
-                    let text = e.unescape().map_err(|e| format!("XML decode error: {}", e))?;
-                    text_parts.push(text.to_string());
-                }
+            Ok(Event::Text(e)) if in_text_element => {
+                let text = e.unescape().map_err(|e| format!("XML decode error: {}", e))?;
+                text_parts.push(text.to_string());
             }
             Ok(Event::End(e)) => {
                 let name = e.name();
@@ -0,0 +1,215 @@
+//! On-disk cache of embedding vectors.
+//!
+//! Embeddings are deterministic for a given model, so vectors are kept in
+//! SQLite keyed by model and a SHA-256 of the input text. Re-indexing the
+//! same documents for RAG is then answered locally instead of spending
+//! Ollama time or remote quota. Entries don't expire; the least recently
+//! used are evicted once `max_entries` is exceeded.
+
+use crate::storage;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+/// Hex SHA-256 of an input text.
+pub fn text_hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Hit/miss counters since startup and current size.
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, ToSchema)]
+pub struct EmbeddingCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: u64,
+    /// Share of lookups answered from the cache, 0.0 to 1.0.
+    pub hit_rate: f64,
+}
+
+/// SQLite-backed embedding cache, shared across handlers.
+#[derive(Clone)]
+pub struct EmbeddingCache {
+    conn: Arc<Mutex<Connection>>,
+    max_entries: usize,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+}
+
+impl EmbeddingCache {
+    /// Open the cache in MultiAI's data directory.
+    pub fn open_default(max_entries: usize) -> SqlResult<Self> {
+        Self::with_connection(storage::open_data_db("embeddings.db")?, max_entries)
+    }
+
+    /// Open or create a cache database.
+    pub fn open<P: AsRef<Path>>(path: P, max_entries: usize) -> SqlResult<Self> {
+        Self::with_connection(Connection::open(path)?, max_entries)
+    }
+
+    /// Create an in-memory cache (for testing).
+    pub fn in_memory(max_entries: usize) -> SqlResult<Self> {
+        Self::with_connection(Connection::open_in_memory()?, max_entries)
+    }
+
+    fn with_connection(conn: Connection, max_entries: usize) -> SqlResult<Self> {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS embeddings (
+                model TEXT NOT NULL,
+                text_hash TEXT NOT NULL,
+                vector BLOB NOT NULL,
+                created_at INTEGER NOT NULL,
+                hit_at INTEGER NOT NULL,
+                PRIMARY KEY (model, text_hash)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_embeddings_hit_at ON embeddings(hit_at);
+            "#,
+        )?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            max_entries,
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Cached vector for `text` under `model`, if present.
+    pub fn get(&self, model: &str, text: &str) -> Option<Vec<f32>> {
+        let hash = text_hash(text);
+        let conn = self.conn.lock().unwrap();
+        let vector: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT vector FROM embeddings WHERE model = ? AND text_hash = ?",
+                params![model, hash],
+                |row| row.get(0),
+            )
+            .optional()
+            .ok()
+            .flatten();
+
+        match vector {
+            Some(bytes) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                let _ = conn.execute(
+                    "UPDATE embeddings SET hit_at = ? WHERE model = ? AND text_hash = ?",
+                    params![Utc::now().timestamp_millis(), model, hash],
+                );
+                Some(decode(&bytes))
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Store a vector, then evict the least recently used over the limit.
+    pub fn put(&self, model: &str, text: &str, vector: &[f32]) -> SqlResult<()> {
+        let now = Utc::now().timestamp_millis();
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO embeddings (model, text_hash, vector, created_at, hit_at)
+             VALUES (?, ?, ?, ?, ?)",
+            params![model, text_hash(text), encode(vector), now, now],
+        )?;
+        conn.execute(
+            "DELETE FROM embeddings WHERE rowid IN (
+                SELECT rowid FROM embeddings ORDER BY hit_at DESC, rowid DESC LIMIT -1 OFFSET ?
+            )",
+            params![self.max_entries as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Remove every entry, returning how many there were.
+    pub fn clear(&self) -> SqlResult<usize> {
+        self.conn.lock().unwrap().execute("DELETE FROM embeddings", [])
+    }
+
+    /// Hit/miss counts since startup and the cache's current size.
+    pub fn stats(&self) -> EmbeddingCacheStats {
+        let entries: i64 = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM embeddings", [], |row| row.get(0))
+            .unwrap_or(0);
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+        EmbeddingCacheStats {
+            hits,
+            misses,
+            entries: entries as u64,
+            hit_rate: if lookups == 0 {
+                0.0
+            } else {
+                hits as f64 / lookups as f64
+            },
+        }
+    }
+}
+
+/// Vectors are stored as little-endian f32s.
+fn encode(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_stored_vectors_and_tracks_hit_rate() {
+        let cache = EmbeddingCache::in_memory(100).unwrap();
+        let vector = vec![0.25, -1.5, 3.0];
+
+        assert_eq!(cache.get("nomic-embed-text", "hello"), None);
+        cache.put("nomic-embed-text", "hello", &vector).unwrap();
+        assert_eq!(cache.get("nomic-embed-text", "hello"), Some(vector.clone()));
+        assert_eq!(cache.get("nomic-embed-text", "hello"), Some(vector));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (2, 1, 1));
+        assert!((stats.hit_rate - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn vectors_are_per_model_and_text() {
+        let cache = EmbeddingCache::in_memory(100).unwrap();
+        cache.put("a", "hello", &[1.0]).unwrap();
+
+        assert_eq!(cache.get("b", "hello"), None);
+        assert_eq!(cache.get("a", "hello!"), None);
+        assert_ne!(text_hash("hello"), text_hash("hello!"));
+    }
+
+    #[test]
+    fn evicts_least_recently_used_over_entry_limit() {
+        let cache = EmbeddingCache::in_memory(2).unwrap();
+        cache.put("m", "a", &[1.0]).unwrap();
+        cache.put("m", "b", &[2.0]).unwrap();
+        cache.put("m", "c", &[3.0]).unwrap();
+
+        assert_eq!(cache.get("m", "a"), None);
+        assert_eq!(cache.get("m", "b"), Some(vec![2.0]));
+        assert_eq!(cache.stats().entries, 2);
+        assert_eq!(cache.clear().unwrap(), 2);
+    }
+}
@@ -23,6 +23,16 @@ pub enum MultiAiError {
     UpstreamError(String),
     /// Failed to parse upstream response.
     ParseError(String),
+    /// Upstream did not answer in time.
+    Timeout(String),
+    /// Upstream rate limit or quota hit.
+    RateLimited(String),
+    /// Upstream rejected the gateway's API key.
+    AuthFailed(String),
+    /// Model is overloaded, still loading or has no capacity.
+    ModelOverloaded(String),
+    /// Upstream refused the prompt or response on content policy grounds.
+    ContentFiltered(String),
     /// Daily or monthly spending cap exceeded.
     SpendingCapExceeded {
         cap_type: String,
@@ -30,6 +40,20 @@ pub enum MultiAiError {
         cap: f64,
         message: String,
     },
+    /// Missing or unknown gateway API key.
+    InvalidApiKey(String),
+    /// The gateway API key may not use this model.
+    ModelNotAllowed(String),
+    /// The gateway API key's rate limit or daily quota is used up.
+    KeyQuotaExceeded(String),
+    /// The client sent more requests or tokens per minute than `[gateway]` allows.
+    ClientRateLimited(String),
+    /// A request hook dropped the request.
+    RequestBlocked(String),
+    /// A page from another origin called a gateway management route.
+    ForeignOrigin(String),
+    /// The request itself is invalid.
+    InvalidRequest(String),
     /// Configuration error.
     ConfigError(String),
     /// Internal error.
@@ -46,7 +70,23 @@ impl fmt::Display for MultiAiError {
             }
             Self::UpstreamError(msg) => write!(f, "Upstream error: {}", msg),
             Self::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            Self::Timeout(msg) => write!(f, "Upstream timeout: {}", msg),
+            Self::RateLimited(msg) => write!(f, "Rate limited: {}", msg),
+            Self::AuthFailed(msg) => write!(f, "Upstream authentication failed: {}", msg),
+            Self::ModelOverloaded(msg) => write!(f, "Model overloaded: {}", msg),
+            Self::ContentFiltered(msg) => write!(f, "Content filtered: {}", msg),
             Self::SpendingCapExceeded { message, .. } => write!(f, "{}", message),
+            Self::InvalidApiKey(msg) => write!(f, "Invalid API key: {}", msg),
+            Self::ModelNotAllowed(model) => {
+                write!(f, "This API key may not use '{}'", model)
+            }
+            Self::KeyQuotaExceeded(msg) => write!(f, "{}", msg),
+            Self::ClientRateLimited(msg) => write!(f, "{}", msg),
+            Self::RequestBlocked(reason) => write!(f, "Request blocked: {}", reason),
+            Self::ForeignOrigin(origin) => {
+                write!(f, "Requests from {} may not manage this gateway", origin)
+            }
+            Self::InvalidRequest(msg) => write!(f, "{}", msg),
             Self::ConfigError(msg) => write!(f, "Configuration error: {}", msg),
             Self::Internal(msg) => write!(f, "Internal error: {}", msg),
         }
@@ -82,7 +122,19 @@ impl MultiAiError {
             Self::ApiKeyMissing(_) => StatusCode::SERVICE_UNAVAILABLE,
             Self::UpstreamError(_) => StatusCode::BAD_GATEWAY,
             Self::ParseError(_) => StatusCode::BAD_GATEWAY,
+            Self::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            Self::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            Self::AuthFailed(_) => StatusCode::BAD_GATEWAY,
+            Self::ModelOverloaded(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Self::ContentFiltered(_) => StatusCode::BAD_REQUEST,
             Self::SpendingCapExceeded { .. } => StatusCode::PAYMENT_REQUIRED,
+            Self::InvalidApiKey(_) => StatusCode::UNAUTHORIZED,
+            Self::ModelNotAllowed(_) => StatusCode::FORBIDDEN,
+            Self::KeyQuotaExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+            Self::ClientRateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            Self::RequestBlocked(_) => StatusCode::FORBIDDEN,
+            Self::ForeignOrigin(_) => StatusCode::FORBIDDEN,
+            Self::InvalidRequest(_) => StatusCode::BAD_REQUEST,
             Self::ConfigError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -96,11 +148,100 @@ impl MultiAiError {
             Self::ApiKeyMissing(_) => "configuration_error",
             Self::UpstreamError(_) => "upstream_error",
             Self::ParseError(_) => "upstream_error",
+            Self::Timeout(_) => "timeout",
+            Self::RateLimited(_) => "rate_limited",
+            Self::AuthFailed(_) => "auth_failed",
+            Self::ModelOverloaded(_) => "model_overloaded",
+            Self::ContentFiltered(_) => "content_filtered",
             Self::SpendingCapExceeded { .. } => "spending_cap_exceeded",
+            Self::InvalidApiKey(_) => "invalid_api_key",
+            Self::ModelNotAllowed(_) => "model_not_allowed",
+            Self::KeyQuotaExceeded(_) => "quota_exceeded",
+            Self::ClientRateLimited(_) => "rate_limit_exceeded",
+            Self::RequestBlocked(_) => "request_blocked",
+            Self::ForeignOrigin(_) => "forbidden_origin",
+            Self::InvalidRequest(_) => "invalid_request",
             Self::ConfigError(_) => "configuration_error",
             Self::Internal(_) => "internal_error",
         }
     }
+
+    /// Whether the same request may succeed if retried, possibly on another
+    /// model. Auth, content-policy and request errors are permanent.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::UpstreamError(_)
+                | Self::ParseError(_)
+                | Self::Timeout(_)
+                | Self::RateLimited(_)
+                | Self::ModelOverloaded(_)
+        )
+    }
+
+    /// Classify a failed request to an upstream.
+    pub fn from_request_error(e: &reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Self::Timeout(e.to_string())
+        } else {
+            Self::UpstreamError(format!("Request failed: {}", e))
+        }
+    }
+
+    /// Classify an upstream error response from its status and body. Returns
+    /// None for successful statuses.
+    ///
+    /// Providers disagree on status codes (OpenRouter reports moderation as
+    /// 403, Ollama and Hugging Face report loading models as 503 or 500,
+    /// Anthropic-style upstreams use 529), so the body is checked first.
+    pub fn from_upstream(status: u16, body: &str) -> Option<Self> {
+        if status < 400 {
+            return None;
+        }
+        let message = upstream_message(body);
+        let text = body.to_lowercase();
+        let mentions = |needles: &[&str]| needles.iter().any(|n| text.contains(n));
+
+        let error = if mentions(&["content_filter", "content_policy", "flagged", "safety"]) {
+            Self::ContentFiltered(message)
+        } else if status == 429 || mentions(&["rate_limit", "rate limit", "quota"]) {
+            Self::RateLimited(message)
+        } else if status == 401
+            || status == 403
+            || mentions(&["invalid_api_key", "invalid api key", "no auth credentials"])
+        {
+            Self::AuthFailed(message)
+        } else if status == 408 || status == 504 || mentions(&["timed out", "timeout"]) {
+            Self::Timeout(message)
+        } else if status == 503
+            || status == 529
+            || mentions(&["overloaded", "currently loading", "no available capacity"])
+        {
+            Self::ModelOverloaded(message)
+        } else if status >= 500 {
+            Self::UpstreamError(format!("HTTP {}: {}", status, message))
+        } else {
+            Self::InvalidRequest(message)
+        };
+        Some(error)
+    }
+}
+
+/// Error message from an upstream error body (`error.message`, `error`,
+/// `message` or `detail`), or the start of the raw body.
+fn upstream_message(body: &str) -> String {
+    let json: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+    let message = [
+        &json["error"]["message"],
+        &json["error"],
+        &json["message"],
+        &json["detail"],
+    ]
+    .into_iter()
+    .find_map(|v| v.as_str())
+    .map(String::from)
+    .unwrap_or_else(|| body.chars().take(200).collect());
+    message
 }
 
 impl IntoResponse for MultiAiError {
@@ -236,6 +377,13 @@ mod tests {
         assert_eq!(err.error_type(), "service_unavailable");
     }
 
+    #[test]
+    fn invalid_request_has_correct_status() {
+        let err = MultiAiError::InvalidRequest("Unknown template 'x'".to_string());
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(err.error_type(), "invalid_request");
+    }
+
     #[test]
     fn model_not_free_has_correct_status() {
         let err = MultiAiError::ModelNotFree("gpt-4".to_string());
@@ -262,12 +410,73 @@ mod tests {
         assert_eq!(err.error_type(), "spending_cap_exceeded");
     }
 
+    #[test]
+    fn gateway_key_errors_have_correct_status() {
+        let err = MultiAiError::InvalidApiKey("unknown key".to_string());
+        assert_eq!(err.status_code(), StatusCode::UNAUTHORIZED);
+        let err = MultiAiError::ModelNotAllowed("gpt-4".to_string());
+        assert_eq!(err.status_code(), StatusCode::FORBIDDEN);
+        assert_eq!(err.error_type(), "model_not_allowed");
+        let err = MultiAiError::KeyQuotaExceeded("Daily quota used".to_string());
+        assert_eq!(err.status_code(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(!err.is_retryable());
+        let err = MultiAiError::ForeignOrigin("https://evil.example".to_string());
+        assert_eq!(err.status_code(), StatusCode::FORBIDDEN);
+        assert_eq!(err.error_type(), "forbidden_origin");
+    }
+
     #[test]
     fn upstream_error_has_correct_status() {
         let err = MultiAiError::UpstreamError("Connection refused".to_string());
         assert_eq!(err.status_code(), StatusCode::BAD_GATEWAY);
     }
 
+    #[test]
+    fn classifies_upstream_error_bodies() {
+        let classify = |status, body| MultiAiError::from_upstream(status, body).unwrap();
+
+        assert!(matches!(
+            classify(429, r#"{"error": {"message": "Rate limit exceeded: free-models-per-day"}}"#),
+            MultiAiError::RateLimited(m) if m == "Rate limit exceeded: free-models-per-day"
+        ));
+        assert!(matches!(
+            classify(401, r#"{"error": {"message": "No auth credentials found", "code": 401}}"#),
+            MultiAiError::AuthFailed(_)
+        ));
+        assert!(matches!(
+            classify(403, r#"{"error": {"message": "Input was flagged by moderation"}}"#),
+            MultiAiError::ContentFiltered(_)
+        ));
+        assert!(matches!(
+            classify(500, r#"{"error": "model is currently loading"}"#),
+            MultiAiError::ModelOverloaded(_)
+        ));
+        assert!(matches!(
+            classify(529, r#"{"type": "error", "error": {"type": "overloaded_error"}}"#),
+            MultiAiError::ModelOverloaded(_)
+        ));
+        assert!(matches!(classify(504, "Gateway Timeout"), MultiAiError::Timeout(_)));
+        assert!(matches!(
+            classify(502, "Bad Gateway"),
+            MultiAiError::UpstreamError(m) if m == "HTTP 502: Bad Gateway"
+        ));
+        assert!(matches!(classify(400, r#"{"detail": "bad"}"#), MultiAiError::InvalidRequest(_)));
+        assert!(MultiAiError::from_upstream(200, "{}").is_none());
+    }
+
+    #[test]
+    fn only_transient_errors_are_retryable() {
+        assert!(MultiAiError::Timeout("slow".to_string()).is_retryable());
+        assert!(MultiAiError::RateLimited("429".to_string()).is_retryable());
+        assert!(MultiAiError::ModelOverloaded("busy".to_string()).is_retryable());
+        assert!(MultiAiError::UpstreamError("502".to_string()).is_retryable());
+
+        assert!(!MultiAiError::AuthFailed("bad key".to_string()).is_retryable());
+        assert!(!MultiAiError::ContentFiltered("flagged".to_string()).is_retryable());
+        assert!(!MultiAiError::InvalidRequest("bad".to_string()).is_retryable());
+        assert!(!MultiAiError::NoModelsAvailable.is_retryable());
+    }
+
     #[test]
     fn error_implements_display() {
         let err = MultiAiError::NoModelsAvailable;
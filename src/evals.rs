@@ -0,0 +1,413 @@
+//! Eval datasets and per-model score history.
+//!
+//! A dataset is a named list of prompts, each with optional criteria the
+//! judges check answers against. Datasets are managed through
+//! `/api/evals/datasets` and run on demand or every `[evals] schedule_hours`
+//! through the comparator and judge panel. Every run's per-model standings
+//! are kept, so a model that starts doing worse shows up in
+//! `/api/evals/history`.
+
+use crate::config::EvalsConfig;
+use crate::mcp::eval::{self, EvalParams, EvalResult};
+use crate::mcp::ModelComparator;
+use crate::storage;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// One prompt of a dataset.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct DatasetItem {
+    pub prompt: String,
+    /// What a good answer must contain, passed to the judges.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub criteria: Option<String>,
+}
+
+/// A stored eval dataset.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct Dataset {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub items: Vec<DatasetItem>,
+}
+
+impl Dataset {
+    /// Check the name is usable in a URL path and there is something to run.
+    pub fn validate(&self) -> Result<(), String> {
+        let valid_name = !self.name.is_empty()
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid_name {
+            return Err(format!(
+                "Invalid dataset name '{}': use letters, digits, '-', '_' or '.'",
+                self.name
+            ));
+        }
+        if self.items.is_empty() {
+            return Err(format!("Dataset '{}' has no prompts", self.name));
+        }
+        Ok(())
+    }
+}
+
+/// A model's standing in one run of a dataset.
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct EvalScore {
+    pub dataset: String,
+    pub model: String,
+    pub run_at: DateTime<Utc>,
+    pub runs: usize,
+    pub wins: usize,
+    pub failures: usize,
+    pub win_rate: f64,
+    pub mean_overall: f64,
+    pub mean_quality: f64,
+    pub mean_speed: f64,
+}
+
+/// SQLite-backed dataset and score store, shared across handlers.
+#[derive(Clone)]
+pub struct EvalStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl EvalStore {
+    /// Open the store in MultiAI's data directory.
+    pub fn open_default() -> SqlResult<Self> {
+        Self::with_connection(storage::open_data_db("evals.db")?)
+    }
+
+    /// Open or create an eval database.
+    pub fn open<P: AsRef<Path>>(path: P) -> SqlResult<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Create an in-memory store (for testing).
+    pub fn in_memory() -> SqlResult<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> SqlResult<Self> {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS datasets (
+                name TEXT PRIMARY KEY,
+                description TEXT,
+                items TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS scores (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                dataset TEXT NOT NULL,
+                model TEXT NOT NULL,
+                run_at TEXT NOT NULL,
+                runs INTEGER NOT NULL,
+                wins INTEGER NOT NULL,
+                failures INTEGER NOT NULL,
+                win_rate REAL NOT NULL,
+                mean_overall REAL NOT NULL,
+                mean_quality REAL NOT NULL,
+                mean_speed REAL NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_scores_model ON scores(model);
+            "#,
+        )?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// All datasets, by name.
+    pub fn list_datasets(&self) -> SqlResult<Vec<Dataset>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT name, description, items FROM datasets ORDER BY name")?;
+        let datasets = stmt.query_map([], row_to_dataset)?;
+        datasets.collect()
+    }
+
+    pub fn get_dataset(&self, name: &str) -> SqlResult<Option<Dataset>> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT name, description, items FROM datasets WHERE name = ?",
+                params![name],
+                row_to_dataset,
+            )
+            .optional()
+    }
+
+    /// Add a new dataset. Returns false if the name is taken.
+    pub fn insert_dataset(&self, dataset: &Dataset) -> SqlResult<bool> {
+        let items = serde_json::to_string(&dataset.items)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let rows = self.conn.lock().unwrap().execute(
+            "INSERT OR IGNORE INTO datasets (name, description, items) VALUES (?, ?, ?)",
+            params![dataset.name, dataset.description, items],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Delete a dataset. Its score history is kept.
+    pub fn delete_dataset(&self, name: &str) -> SqlResult<bool> {
+        let rows = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM datasets WHERE name = ?", params![name])?;
+        Ok(rows > 0)
+    }
+
+    /// Store the standings of one run of `dataset`.
+    pub fn record_run(&self, dataset: &str, result: &EvalResult) -> SqlResult<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for s in &result.standings {
+            tx.execute(
+                "INSERT INTO scores (dataset, model, run_at, runs, wins, failures,
+                     win_rate, mean_overall, mean_quality, mean_speed)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    dataset,
+                    s.model,
+                    result.evaluated_at.to_rfc3339(),
+                    s.runs as i64,
+                    s.wins as i64,
+                    s.failures as i64,
+                    s.win_rate,
+                    s.mean_overall,
+                    s.mean_quality,
+                    s.mean_speed
+                ],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Recorded scores, oldest first, optionally for one model or dataset.
+    pub fn history(&self, model: Option<&str>, dataset: Option<&str>) -> SqlResult<Vec<EvalScore>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT dataset, model, run_at, runs, wins, failures,
+                 win_rate, mean_overall, mean_quality, mean_speed
+             FROM scores
+             WHERE (?1 IS NULL OR model = ?1) AND (?2 IS NULL OR dataset = ?2)
+             ORDER BY run_at, id",
+        )?;
+        let scores = stmt.query_map(params![model, dataset], |row| {
+            let run_at: String = row.get(2)?;
+            Ok(EvalScore {
+                dataset: row.get(0)?,
+                model: row.get(1)?,
+                run_at: DateTime::parse_from_rfc3339(&run_at)
+                    .map(|t| t.with_timezone(&Utc))
+                    .unwrap_or_default(),
+                runs: row.get::<_, i64>(3)? as usize,
+                wins: row.get::<_, i64>(4)? as usize,
+                failures: row.get::<_, i64>(5)? as usize,
+                win_rate: row.get(6)?,
+                mean_overall: row.get(7)?,
+                mean_quality: row.get(8)?,
+                mean_speed: row.get(9)?,
+            })
+        })?;
+        scores.collect()
+    }
+}
+
+fn row_to_dataset(row: &rusqlite::Row) -> SqlResult<Dataset> {
+    let items: String = row.get(2)?;
+    Ok(Dataset {
+        name: row.get(0)?,
+        description: row.get(1)?,
+        items: serde_json::from_str(&items).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+    })
+}
+
+/// Run a dataset through the comparator and judges and record the standings.
+/// `params` selects the models and judges; its prompts and suite are ignored.
+pub async fn run_dataset(
+    store: &EvalStore,
+    comparator: &ModelComparator,
+    dataset: &Dataset,
+    mut params: EvalParams,
+) -> Result<EvalResult, String> {
+    params.suite = Some(dataset.name.clone());
+    let cases = dataset
+        .items
+        .iter()
+        .map(|item| (item.prompt.clone(), item.criteria.clone()))
+        .collect();
+    let result = eval::run_cases_with(comparator, params, cases, None).await?;
+    store
+        .record_run(&dataset.name, &result)
+        .map_err(|e| format!("Failed to record eval run: {}", e))?;
+    Ok(result)
+}
+
+/// Run the configured datasets every `schedule_hours`, starting one interval
+/// after startup.
+pub async fn run_schedule(store: EvalStore, comparator: ModelComparator, config: EvalsConfig) {
+    let Some(hours) = config.schedule_hours.filter(|h| *h > 0) else {
+        return;
+    };
+    let mut ticker = tokio::time::interval(Duration::from_secs(hours * 60 * 60));
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        if let Err(e) = run_configured(&store, &comparator, &config).await {
+            tracing::warn!("{}", e);
+        }
+    }
+}
+
+/// Run the datasets selected by `[evals]` once, returning how many ran.
+pub async fn run_configured(
+    store: &EvalStore,
+    comparator: &ModelComparator,
+    config: &EvalsConfig,
+) -> Result<usize, String> {
+    let datasets = store
+        .list_datasets()
+        .map_err(|e| format!("Failed to load eval datasets: {}", e))?;
+    let selected = datasets
+        .iter()
+        .filter(|d| config.datasets.is_empty() || config.datasets.contains(&d.name));
+    let mut failed = Vec::new();
+    let mut ran = 0;
+    for dataset in selected {
+        let params = EvalParams {
+            prompts: None,
+            suite: None,
+            models: config.models.clone(),
+            max_models: config.max_models,
+            include_ranking: true,
+            judges: None,
+            weights: None,
+            metrics: None,
+        };
+        match run_dataset(store, comparator, dataset, params).await {
+            Ok(result) => {
+                ran += 1;
+                tracing::info!(
+                    "Scheduled eval of '{}' ranked {} models",
+                    dataset.name,
+                    result.standings.len()
+                )
+            }
+            Err(e) => {
+                tracing::warn!("Scheduled eval of '{}' failed: {}", dataset.name, e);
+                failed.push(dataset.name.clone());
+            }
+        }
+    }
+    if failed.is_empty() {
+        Ok(ran)
+    } else {
+        Err(format!("Eval runs failed: {}", failed.join(", ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::eval::ModelStanding;
+
+    fn dataset() -> Dataset {
+        Dataset {
+            name: "rust-basics".to_string(),
+            description: Some("Ownership questions".to_string()),
+            items: vec![
+                DatasetItem {
+                    prompt: "What does the borrow checker do?".to_string(),
+                    criteria: Some("Mentions references and lifetimes".to_string()),
+                },
+                DatasetItem {
+                    prompt: "Explain Box<T>".to_string(),
+                    criteria: None,
+                },
+            ],
+        }
+    }
+
+    fn result(evaluated_at: &str, standings: &[(&str, f64)]) -> EvalResult {
+        EvalResult {
+            suite: Some("rust-basics".to_string()),
+            evaluated_at: evaluated_at.parse().unwrap(),
+            prompts: Vec::new(),
+            standings: standings
+                .iter()
+                .map(|(model, overall)| ModelStanding {
+                    model: model.to_string(),
+                    runs: 2,
+                    wins: 1,
+                    failures: 0,
+                    win_rate: 0.5,
+                    mean_overall: *overall,
+                    mean_quality: *overall,
+                    mean_speed: 5.0,
+                })
+                .collect(),
+            markdown_summary: String::new(),
+        }
+    }
+
+    #[test]
+    fn store_round_trips_datasets() {
+        let store = EvalStore::in_memory().unwrap();
+        assert!(store.insert_dataset(&dataset()).unwrap());
+        assert!(!store.insert_dataset(&dataset()).unwrap());
+
+        assert_eq!(store.get_dataset("rust-basics").unwrap(), Some(dataset()));
+        assert_eq!(store.list_datasets().unwrap().len(), 1);
+
+        assert!(store.delete_dataset("rust-basics").unwrap());
+        assert_eq!(store.get_dataset("rust-basics").unwrap(), None);
+    }
+
+    #[test]
+    fn history_filters_by_model_in_run_order() {
+        let store = EvalStore::in_memory().unwrap();
+        store
+            .record_run(
+                "rust-basics",
+                &result("2026-01-02T00:00:00Z", &[("a", 6.0), ("b", 7.0)]),
+            )
+            .unwrap();
+        store
+            .record_run(
+                "rust-basics",
+                &result("2026-01-01T00:00:00Z", &[("a", 8.0)]),
+            )
+            .unwrap();
+
+        let history = store.history(Some("a"), None).unwrap();
+        let overall: Vec<f64> = history.iter().map(|s| s.mean_overall).collect();
+        assert_eq!(overall, vec![8.0, 6.0]);
+        assert_eq!(store.history(None, Some("rust-basics")).unwrap().len(), 3);
+        assert!(store.history(None, Some("other")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn validates_datasets() {
+        assert!(dataset().validate().is_ok());
+
+        let mut bad_name = dataset();
+        bad_name.name = "a/b".to_string();
+        assert!(bad_name.validate().is_err());
+
+        let mut empty = dataset();
+        empty.items.clear();
+        assert!(empty.validate().is_err());
+    }
+}
@@ -0,0 +1,513 @@
+//! Gateway events for the app shell.
+//!
+//! The gateway publishes noteworthy conditions (a rejected API key, no free
+//! models, spending past a threshold, a failing source, a model's circuit
+//! opening) on an `EventBus` so
+//! the desktop app can surface them as native notifications and the alert
+//! webhook can page someone. Free models appearing or disappearing between
+//! scans are published too, and go to their own webhook.
+
+use crate::mcp::spending::SpendingTracker;
+use crate::scanner::{FreeModel, FreeModelScanner, Source};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+/// A condition worth telling the user about.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GatewayEvent {
+    /// An upstream provider rejected the configured API key.
+    KeyRejected { source: Source, status: u16 },
+    /// A request found no free models from any source.
+    ModelsUnavailable,
+    /// Judge spending crossed `warn_at_percent` of a cap.
+    SpendingWarning {
+        daily_percent: f64,
+        monthly_percent: f64,
+    },
+    /// Judge spending reached the daily or monthly cap.
+    SpendingCapReached { period: String, used: f64, cap: f64 },
+    /// A source's model scan started failing.
+    SourceFailing { source: Source, error: String },
+    /// A provider started rate limiting a model, so routing keeps off it
+    /// for `retry_after_secs`.
+    CircuitOpen {
+        source: Source,
+        model: String,
+        retry_after_secs: u64,
+    },
+    /// A scan found free models that weren't in the previous one, or
+    /// lost some that were.
+    ModelsChanged {
+        added: Vec<FreeModel>,
+        removed: Vec<FreeModel>,
+    },
+}
+
+impl GatewayEvent {
+    /// One-line description for logs and webhooks.
+    pub fn summary(&self) -> String {
+        match self {
+            Self::KeyRejected { source, status } => {
+                format!("{:?} rejected the API key (HTTP {})", source, status)
+            }
+            Self::ModelsUnavailable => "No free models available from any source".to_string(),
+            Self::SpendingWarning {
+                daily_percent,
+                monthly_percent,
+            } => format!(
+                "Judge spending at {:.0}% of the daily cap and {:.0}% of the monthly cap",
+                daily_percent, monthly_percent
+            ),
+            Self::SpendingCapReached { period, used, cap } => format!(
+                "Judge spending reached the {} cap (${:.2} of ${:.2})",
+                period, used, cap
+            ),
+            Self::SourceFailing { source, error } => {
+                format!("{:?} model scan failing: {}", source, error)
+            }
+            Self::CircuitOpen {
+                source,
+                model,
+                retry_after_secs,
+            } => format!(
+                "{:?} is rate limiting {}; skipping it for {}s",
+                source, model, retry_after_secs
+            ),
+            Self::ModelsChanged { added, removed } => {
+                let ids = |models: &[FreeModel]| {
+                    models.iter().map(|m| m.id.as_str()).collect::<Vec<_>>().join(", ")
+                };
+                match (added.is_empty(), removed.is_empty()) {
+                    (false, true) => format!("New free models: {}", ids(added)),
+                    (true, false) => format!("No longer free: {}", ids(removed)),
+                    _ => format!(
+                        "New free models: {}; no longer free: {}",
+                        ids(added),
+                        ids(removed)
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Whether this is a model list change rather than an alert.
+    pub fn is_model_change(&self) -> bool {
+        matches!(self, Self::ModelsChanged { .. })
+    }
+
+    /// Events sharing a key are repeats of the same condition; None for
+    /// events that are only published once per crossing.
+    pub fn cooldown_key(&self) -> Option<String> {
+        match self {
+            Self::KeyRejected { source, .. } => Some(format!("key:{:?}", source)),
+            Self::ModelsUnavailable => Some("models".to_string()),
+            Self::SpendingWarning { .. }
+            | Self::SpendingCapReached { .. }
+            | Self::SourceFailing { .. }
+            | Self::CircuitOpen { .. }
+            | Self::ModelsChanged { .. } => None,
+        }
+    }
+}
+
+/// Suppresses repeats of the same event within a window.
+pub struct Cooldown {
+    window: Duration,
+    last_seen: HashMap<String, Instant>,
+}
+
+impl Cooldown {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            last_seen: HashMap::new(),
+        }
+    }
+
+    /// Whether `event` should be delivered, remembering it if so.
+    pub fn allow(&mut self, event: &GatewayEvent) -> bool {
+        let Some(key) = event.cooldown_key() else {
+            return true;
+        };
+        if self
+            .last_seen
+            .get(&key)
+            .is_some_and(|seen| seen.elapsed() < self.window)
+        {
+            return false;
+        }
+        self.last_seen.insert(key, Instant::now());
+        true
+    }
+}
+
+/// Broadcast channel for gateway events.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<GatewayEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(64);
+        Self { sender }
+    }
+
+    /// Publish an event to current subscribers.
+    pub fn publish(&self, event: GatewayEvent) {
+        // No subscribers is not an error
+        let _ = self.sender.send(event);
+    }
+
+    /// Subscribe to events published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<GatewayEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Poll the spending tracker and publish an event each time spending
+/// crosses the warning threshold or a cap.
+pub async fn watch_spending(bus: EventBus, tracker: SpendingTracker, interval: Duration) {
+    let mut at_warning = false;
+    let mut daily_capped = false;
+    let mut monthly_capped = false;
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let status = tracker.get_status();
+        if status.at_warning && !at_warning {
+            bus.publish(GatewayEvent::SpendingWarning {
+                daily_percent: status.daily_percent,
+                monthly_percent: status.monthly_percent,
+            });
+        }
+        at_warning = status.at_warning;
+
+        let daily = status.daily_used >= status.daily_cap;
+        if daily && !daily_capped {
+            bus.publish(GatewayEvent::SpendingCapReached {
+                period: "daily".to_string(),
+                used: status.daily_used,
+                cap: status.daily_cap,
+            });
+        }
+        daily_capped = daily;
+
+        let monthly = status.monthly_used >= status.monthly_cap;
+        if monthly && !monthly_capped {
+            bus.publish(GatewayEvent::SpendingCapReached {
+                period: "monthly".to_string(),
+                used: status.monthly_used,
+                cap: status.monthly_cap,
+            });
+        }
+        monthly_capped = monthly;
+    }
+}
+
+/// Poll the scanner's per-source status and publish an event when a
+/// source's scans start failing.
+pub async fn watch_sources(bus: EventBus, scanner: FreeModelScanner, interval: Duration) {
+    let mut failing: BTreeMap<Source, bool> = BTreeMap::new();
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        for (source, status) in scanner.source_status() {
+            let was_failing = failing.insert(source, status.last_error.is_some());
+            if let Some(error) = status.last_error {
+                if was_failing != Some(true) {
+                    bus.publish(GatewayEvent::SourceFailing { source, error });
+                }
+            }
+        }
+    }
+}
+
+/// Publish the models each scan adds or removes.
+pub async fn watch_models(bus: EventBus, scanner: FreeModelScanner) {
+    let mut receiver = scanner.subscribe_changes();
+    loop {
+        match receiver.recv().await {
+            Ok(changes) => bus.publish(GatewayEvent::ModelsChanged {
+                added: changes.added,
+                removed: changes.removed,
+            }),
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+/// How often spending and source status are polled.
+pub const MONITOR_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Minimum time between webhook posts for the same condition.
+pub const WEBHOOK_COOLDOWN: Duration = Duration::from_secs(10 * 60);
+
+/// Run the spending, source and model watchers for a gateway, forwarding
+/// alerts to `webhook_url` and model changes to `models_webhook_url` when
+/// they are configured.
+pub async fn monitor(
+    bus: EventBus,
+    scanner: FreeModelScanner,
+    spending: Option<SpendingTracker>,
+    client: reqwest::Client,
+    webhook_url: Option<String>,
+    models_webhook_url: Option<String>,
+) {
+    let spending = async {
+        if let Some(tracker) = spending {
+            watch_spending(bus.clone(), tracker, MONITOR_INTERVAL).await;
+        }
+    };
+    let webhook = async {
+        if let Some(url) = webhook_url {
+            let alerts = |event: &GatewayEvent| !event.is_model_change();
+            forward_to_webhook(bus.clone(), client.clone(), url, WEBHOOK_COOLDOWN, alerts).await;
+        }
+    };
+    let models_webhook = async {
+        if let Some(url) = models_webhook_url {
+            let changes = GatewayEvent::is_model_change;
+            forward_to_webhook(bus.clone(), client.clone(), url, WEBHOOK_COOLDOWN, changes).await;
+        }
+    };
+    futures::join!(
+        spending,
+        watch_sources(bus.clone(), scanner.clone(), MONITOR_INTERVAL),
+        watch_models(bus.clone(), scanner),
+        webhook,
+        models_webhook
+    );
+}
+
+/// JSON body posted to the alert webhook. `text` and `content` carry the
+/// summary so Slack and Discord incoming webhooks accept it as-is.
+pub fn webhook_payload(event: &GatewayEvent) -> serde_json::Value {
+    let summary = format!("MultiAI: {}", event.summary());
+    serde_json::json!({
+        "event": event,
+        "text": summary,
+        "content": summary,
+    })
+}
+
+/// POST each event that passes `filter` to `url`, suppressing repeats
+/// within `cooldown`.
+pub async fn forward_to_webhook(
+    bus: EventBus,
+    client: reqwest::Client,
+    url: String,
+    cooldown: Duration,
+    filter: fn(&GatewayEvent) -> bool,
+) {
+    let mut receiver = bus.subscribe();
+    let mut cooldown = Cooldown::new(cooldown);
+    loop {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return,
+        };
+        if !filter(&event) || !cooldown.allow(&event) {
+            continue;
+        }
+        if let Err(e) = client.post(&url).json(&webhook_payload(&event)).send().await {
+            tracing::debug!("Event webhook failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SpendingConfig;
+
+    #[tokio::test]
+    async fn subscribers_receive_published_events() {
+        let bus = EventBus::new();
+        let mut receiver = bus.subscribe();
+
+        bus.publish(GatewayEvent::ModelsUnavailable);
+
+        assert_eq!(
+            receiver.recv().await.unwrap(),
+            GatewayEvent::ModelsUnavailable
+        );
+    }
+
+    #[tokio::test]
+    async fn publishes_spending_warning_once_per_crossing() {
+        let bus = EventBus::new();
+        let mut receiver = bus.subscribe();
+        let tracker = SpendingTracker::in_memory(SpendingConfig::default()).unwrap();
+        tracker.record_cost(4.5).unwrap();
+
+        let watcher = tokio::spawn(watch_spending(
+            bus.clone(),
+            tracker,
+            Duration::from_millis(5),
+        ));
+        let event = tokio::time::timeout(Duration::from_secs(1), receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        watcher.abort();
+
+        assert!(matches!(event, GatewayEvent::SpendingWarning { .. }));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn publishes_cap_reached_when_spending_hits_a_cap() {
+        let bus = EventBus::new();
+        let mut receiver = bus.subscribe();
+        let tracker = SpendingTracker::in_memory(SpendingConfig::default()).unwrap();
+        tracker.record_cost(5.0).unwrap();
+
+        let watcher = tokio::spawn(watch_spending(
+            bus.clone(),
+            tracker,
+            Duration::from_millis(5),
+        ));
+        let mut events = Vec::new();
+        for _ in 0..2 {
+            let event = tokio::time::timeout(Duration::from_secs(1), receiver.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            events.push(event);
+        }
+        watcher.abort();
+
+        assert!(events.iter().any(|e| matches!(
+            e,
+            GatewayEvent::SpendingCapReached { period, .. } if period == "daily"
+        )));
+    }
+
+    #[test]
+    fn cooldown_suppresses_repeats_of_the_same_condition() {
+        let mut cooldown = Cooldown::new(Duration::from_secs(60));
+
+        assert!(cooldown.allow(&GatewayEvent::ModelsUnavailable));
+        assert!(!cooldown.allow(&GatewayEvent::ModelsUnavailable));
+        assert!(cooldown.allow(&GatewayEvent::KeyRejected {
+            source: Source::OpenRouter,
+            status: 401,
+        }));
+        let warning = GatewayEvent::SpendingWarning {
+            daily_percent: 90.0,
+            monthly_percent: 10.0,
+        };
+        assert!(cooldown.allow(&warning));
+        assert!(cooldown.allow(&warning));
+    }
+
+    #[test]
+    fn webhook_payload_works_for_slack_and_discord() {
+        let payload = webhook_payload(&GatewayEvent::SourceFailing {
+            source: Source::OpenRouter,
+            error: "timed out".to_string(),
+        });
+
+        assert_eq!(payload["event"]["type"], "source_failing");
+        assert_eq!(payload["event"]["source"], "open_router");
+        assert!(payload["text"].as_str().unwrap().contains("timed out"));
+        assert_eq!(payload["text"], payload["content"]);
+    }
+
+    #[tokio::test]
+    async fn publishes_model_changes_from_scans() {
+        let mut server = mockito::Server::new_async().await;
+        let first = server
+            .mock("GET", "/openrouter")
+            .with_body(r#"{"data": [{"id": "a:free", "pricing": {"prompt": "0", "completion": "0"}}]}"#)
+            .create_async()
+            .await;
+        server.mock("GET", "/zen/docs").with_body("").create_async().await;
+        server
+            .mock("GET", "/zen/models")
+            .with_body(r#"{"data": []}"#)
+            .create_async()
+            .await;
+        let scanner = FreeModelScanner::new()
+            .with_openrouter_url(&format!("{}/openrouter", server.url()))
+            .with_opencode_zen_docs_url(&format!("{}/zen/docs", server.url()))
+            .with_opencode_zen_api_url(&format!("{}/zen/models", server.url()));
+        scanner.get_free_models(true).await;
+        let bus = EventBus::new();
+        let mut receiver = bus.subscribe();
+        let watcher = tokio::spawn(watch_models(bus.clone(), scanner.clone()));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        first.remove_async().await;
+        server
+            .mock("GET", "/openrouter")
+            .with_body(r#"{"data": [{"id": "b:free", "pricing": {"prompt": "0", "completion": "0"}}]}"#)
+            .create_async()
+            .await;
+        scanner.get_free_models(true).await;
+        let event = tokio::time::timeout(Duration::from_secs(1), receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        watcher.abort();
+
+        assert!(event.is_model_change());
+        assert_eq!(event.summary(), "New free models: b:free; no longer free: a:free");
+    }
+
+    #[tokio::test]
+    async fn forwards_events_to_webhook() {
+        let mut server = mockito::Server::new_async().await;
+        let hook = server
+            .mock("POST", "/hook")
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({"event": {"type": "models_unavailable"}}),
+            ))
+            .expect(1)
+            .create_async()
+            .await;
+        let changes = server
+            .mock("POST", "/hook")
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({"event": {"type": "models_changed"}}),
+            ))
+            .expect(0)
+            .create_async()
+            .await;
+        let bus = EventBus::new();
+        let forwarder = tokio::spawn(forward_to_webhook(
+            bus.clone(),
+            reqwest::Client::new(),
+            format!("{}/hook", server.url()),
+            Duration::from_secs(60),
+            |event| !event.is_model_change(),
+        ));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        bus.publish(GatewayEvent::ModelsUnavailable);
+        bus.publish(GatewayEvent::ModelsUnavailable);
+        bus.publish(GatewayEvent::ModelsChanged {
+            added: Vec::new(),
+            removed: Vec::new(),
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        forwarder.abort();
+
+        hook.assert_async().await;
+        changes.assert_async().await;
+    }
+}
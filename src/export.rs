@@ -1,12 +1,14 @@
-//! Export chat conversations to PDF and DOCX formats.
+//! Export chat conversations to PDF, DOCX, Markdown and JSON formats.
 
+use crate::chat::{Chat, Message};
 use printpdf::*;
+use serde::Serialize;
 use std::io::Write;
 use zip::write::SimpleFileOptions;
 use zip::ZipWriter;
 
 /// Chat message for export.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ExportMessage {
     pub role: String,
     pub content: String,
@@ -14,19 +16,38 @@ pub struct ExportMessage {
 }
 
 /// Chat conversation for export.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ExportChat {
     pub title: String,
     pub messages: Vec<ExportMessage>,
     pub created_at: String,
 }
 
+impl ExportChat {
+    /// Build an export from a stored chat and its messages.
+    pub fn from_chat(chat: &Chat, messages: Vec<Message>) -> Self {
+        Self {
+            title: chat.title.clone(),
+            created_at: chat.created_at.to_rfc3339(),
+            messages: messages
+                .into_iter()
+                .map(|m| ExportMessage {
+                    role: m.role.to_string(),
+                    content: m.content,
+                    created_at: m.created_at.to_rfc3339(),
+                })
+                .collect(),
+        }
+    }
+}
+
 /// Export format options.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ExportFormat {
     Pdf,
     Docx,
     Markdown,
+    Json,
 }
 
 impl ExportFormat {
@@ -35,6 +56,7 @@ impl ExportFormat {
             "pdf" => Some(Self::Pdf),
             "docx" => Some(Self::Docx),
             "md" | "markdown" => Some(Self::Markdown),
+            "json" => Some(Self::Json),
             _ => None,
         }
     }
@@ -44,6 +66,7 @@ impl ExportFormat {
             Self::Pdf => "application/pdf",
             Self::Docx => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
             Self::Markdown => "text/markdown",
+            Self::Json => "application/json",
         }
     }
 
@@ -52,6 +75,7 @@ impl ExportFormat {
             Self::Pdf => "pdf",
             Self::Docx => "docx",
             Self::Markdown => "md",
+            Self::Json => "json",
         }
     }
 }
@@ -62,6 +86,7 @@ pub fn export_chat(chat: &ExportChat, format: ExportFormat) -> Result<Vec<u8>, S
         ExportFormat::Pdf => export_to_pdf(chat),
         ExportFormat::Docx => export_to_docx(chat),
         ExportFormat::Markdown => export_to_markdown(chat),
+        ExportFormat::Json => serde_json::to_vec_pretty(chat).map_err(|e| e.to_string()),
     }
 }
 
@@ -331,6 +356,7 @@ mod tests {
         assert_eq!(ExportFormat::from_extension("pdf"), Some(ExportFormat::Pdf));
         assert_eq!(ExportFormat::from_extension("docx"), Some(ExportFormat::Docx));
         assert_eq!(ExportFormat::from_extension("md"), Some(ExportFormat::Markdown));
+        assert_eq!(ExportFormat::from_extension("json"), Some(ExportFormat::Json));
         assert_eq!(ExportFormat::from_extension("exe"), None);
     }
 
@@ -369,6 +395,20 @@ mod tests {
         assert!(content.contains("I'm doing great!"));
     }
 
+    // =========================================================================
+    // JSON Export Tests
+    // =========================================================================
+
+    #[test]
+    fn export_json_round_trips_messages() {
+        let chat = sample_chat();
+        let result = export_chat(&chat, ExportFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&result).unwrap();
+
+        assert_eq!(value["title"], "Test Conversation");
+        assert_eq!(value["messages"][0]["content"], "Hello, how are you?");
+    }
+
     // =========================================================================
     // PDF Export Tests
     // =========================================================================
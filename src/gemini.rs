@@ -0,0 +1,260 @@
+//! Google AI Studio (Gemini) translation.
+//!
+//! The Generative Language API has its own request and response shapes, so
+//! completions routed to a `Source::Gemini` model are converted on the way
+//! out and back: OpenAI messages become `contents` (with system messages
+//! lifted into `systemInstruction`), and replies and SSE chunks are turned
+//! back into `chat.completion` and `chat.completion.chunk` objects. The key
+//! is sent in `x-goog-api-key` rather than as a bearer token.
+
+use crate::http::take_sse_data;
+use axum::body::Bytes;
+use futures::{Stream, StreamExt};
+use serde_json::{json, Value};
+
+/// Header the Generative Language API takes its key in.
+pub const API_KEY_HEADER: &str = "x-goog-api-key";
+
+/// Completion URL for `model` under the API base `endpoint`.
+pub fn generate_url(endpoint: &str, model: &str, stream: bool) -> String {
+    if stream {
+        format!(
+            "{}/models/{}:streamGenerateContent?alt=sse",
+            endpoint, model
+        )
+    } else {
+        format!("{}/models/{}:generateContent", endpoint, model)
+    }
+}
+
+/// Convert an OpenAI chat completion body to a `generateContent` request.
+pub fn to_gemini(request: &Value) -> Value {
+    let mut system = Vec::new();
+    let mut contents = Vec::new();
+    for message in request["messages"].as_array().into_iter().flatten() {
+        let text = message["content"].as_str().unwrap_or_default();
+        match message["role"].as_str() {
+            Some("system") => system.push(json!({ "text": text })),
+            Some("assistant") => {
+                contents.push(json!({ "role": "model", "parts": [{ "text": text }] }))
+            }
+            _ => contents.push(json!({ "role": "user", "parts": [{ "text": text }] })),
+        }
+    }
+
+    let mut body = json!({ "contents": contents });
+    if !system.is_empty() {
+        body["systemInstruction"] = json!({ "parts": system });
+    }
+    let mut generation = serde_json::Map::new();
+    if let Some(temperature) = request["temperature"].as_f64() {
+        generation.insert("temperature".to_string(), json!(temperature));
+    }
+    if let Some(max_tokens) = request["max_tokens"].as_u64() {
+        generation.insert("maxOutputTokens".to_string(), json!(max_tokens));
+    }
+    if !generation.is_empty() {
+        body["generationConfig"] = Value::Object(generation);
+    }
+    body
+}
+
+/// OpenAI `finish_reason` for a Gemini `finishReason`.
+fn finish_reason(reason: &Value) -> Value {
+    match reason.as_str() {
+        Some("STOP") => json!("stop"),
+        Some("MAX_TOKENS") => json!("length"),
+        Some("SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT") => {
+            json!("content_filter")
+        }
+        Some(_) => json!("stop"),
+        None => Value::Null,
+    }
+}
+
+/// Text of the first candidate.
+fn candidate_text(body: &Value) -> String {
+    body["candidates"][0]["content"]["parts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|part| part["text"].as_str())
+        .collect()
+}
+
+fn usage(body: &Value) -> Option<Value> {
+    let metadata = body.get("usageMetadata")?;
+    let prompt = metadata["promptTokenCount"].as_u64().unwrap_or(0);
+    let completion = metadata["candidatesTokenCount"].as_u64().unwrap_or(0);
+    Some(json!({
+        "prompt_tokens": prompt,
+        "completion_tokens": completion,
+        "total_tokens": metadata["totalTokenCount"].as_u64().unwrap_or(prompt + completion),
+    }))
+}
+
+fn response_id(body: &Value) -> String {
+    format!(
+        "chatcmpl-{}",
+        body["responseId"].as_str().unwrap_or("gemini")
+    )
+}
+
+/// Convert a `generateContent` response to a `chat.completion`.
+pub fn from_gemini(model: &str, body: &Value) -> Value {
+    let mut completion = json!({
+        "id": response_id(body),
+        "object": "chat.completion",
+        "created": chrono::Utc::now().timestamp(),
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": candidate_text(body) },
+            "finish_reason": finish_reason(&body["candidates"][0]["finishReason"]),
+        }],
+    });
+    if let Some(usage) = usage(body) {
+        completion["usage"] = usage;
+    }
+    completion
+}
+
+/// Convert one streamed `generateContent` response to a `chat.completion.chunk`.
+pub fn chunk_from_gemini(model: &str, body: &Value) -> Value {
+    let mut chunk = json!({
+        "id": response_id(body),
+        "object": "chat.completion.chunk",
+        "created": chrono::Utc::now().timestamp(),
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "delta": { "content": candidate_text(body) },
+            "finish_reason": finish_reason(&body["candidates"][0]["finishReason"]),
+        }],
+    });
+    if let Some(usage) = usage(body) {
+        chunk["usage"] = usage;
+    }
+    chunk
+}
+
+/// Rewrites a Gemini SSE stream into OpenAI chunk events.
+struct SseTranslator {
+    model: String,
+    pending: Vec<u8>,
+}
+
+impl SseTranslator {
+    /// Translate the complete lines received so far, keeping a partial line
+    /// for the next read.
+    fn push(&mut self, bytes: &[u8]) -> Bytes {
+        self.pending.extend_from_slice(bytes);
+        let mut out = String::new();
+        for data in take_sse_data(&mut self.pending) {
+            match serde_json::from_str::<Value>(&data) {
+                // Errors are already `{"error": {"message": ...}}`
+                Ok(body) if body.get("error").is_some() => {
+                    out.push_str(&format!("data: {}\n\n", body));
+                }
+                Ok(body) => {
+                    let chunk = chunk_from_gemini(&self.model, &body);
+                    out.push_str(&format!("data: {}\n\n", chunk));
+                }
+                Err(_) => {}
+            }
+        }
+        Bytes::from(out)
+    }
+}
+
+/// Translate a `streamGenerateContent?alt=sse` body into an OpenAI SSE
+/// stream ending in `data: [DONE]`.
+pub fn translate_stream<S, E>(model: String, upstream: S) -> impl Stream<Item = Result<Bytes, E>>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+{
+    let mut translator = SseTranslator {
+        model,
+        pending: Vec::new(),
+    };
+    upstream
+        .map(move |bytes| bytes.map(|bytes| translator.push(&bytes)))
+        .chain(futures::stream::once(async {
+            Ok(Bytes::from_static(b"data: [DONE]\n\n"))
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_lift_system_messages_and_rename_roles() {
+        let request = json!({
+            "model": "gemini-2.0-flash",
+            "messages": [
+                {"role": "system", "content": "Be brief"},
+                {"role": "user", "content": "Hi"},
+                {"role": "assistant", "content": "Hello"},
+                {"role": "user", "content": "Bye"}
+            ],
+            "temperature": 0.5,
+            "max_tokens": 64,
+            "stream": false
+        });
+
+        let body = to_gemini(&request);
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "Be brief");
+        assert_eq!(body["contents"].as_array().unwrap().len(), 3);
+        assert_eq!(body["contents"][1]["role"], "model");
+        assert_eq!(body["contents"][2]["parts"][0]["text"], "Bye");
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 64);
+        assert_eq!(body["generationConfig"]["temperature"], 0.5);
+    }
+
+    #[test]
+    fn responses_become_chat_completions() {
+        let body = json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [{"text": "Hel"}, {"text": "lo"}]},
+                "finishReason": "MAX_TOKENS"
+            }],
+            "usageMetadata": {"promptTokenCount": 4, "candidatesTokenCount": 2, "totalTokenCount": 6},
+            "responseId": "abc"
+        });
+
+        let completion = from_gemini("gemini-2.0-flash", &body);
+        assert_eq!(completion["id"], "chatcmpl-abc");
+        assert_eq!(completion["model"], "gemini-2.0-flash");
+        assert_eq!(completion["choices"][0]["message"]["content"], "Hello");
+        assert_eq!(completion["choices"][0]["finish_reason"], "length");
+        assert_eq!(completion["usage"]["total_tokens"], 6);
+    }
+
+    #[tokio::test]
+    async fn streams_are_rewritten_as_openai_chunks() {
+        let upstream = futures::stream::iter(vec![
+            Ok::<_, std::io::Error>(Bytes::from_static(
+                b"data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hi\"}]}}]}\r\n\r\nda",
+            )),
+            Ok(Bytes::from_static(
+                b"ta: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"!\"}]}, \"finishReason\": \"STOP\"}]}\r\n\r\n",
+            )),
+        ]);
+
+        let out: Vec<Bytes> = translate_stream("gemini-2.0-flash".to_string(), upstream)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        let text = String::from_utf8(out.concat()).unwrap();
+        let events: Vec<&str> = text.split("\n\n").filter(|e| !e.is_empty()).collect();
+        assert_eq!(events.len(), 3);
+        let first: Value = serde_json::from_str(events[0].strip_prefix("data: ").unwrap()).unwrap();
+        assert_eq!(first["object"], "chat.completion.chunk");
+        assert_eq!(first["choices"][0]["delta"]["content"], "Hi");
+        let second: Value =
+            serde_json::from_str(events[1].strip_prefix("data: ").unwrap()).unwrap();
+        assert_eq!(second["choices"][0]["finish_reason"], "stop");
+        assert_eq!(events[2], "data: [DONE]");
+    }
+}
@@ -0,0 +1,209 @@
+//! Scriptable request and response hooks (`[hooks]`).
+//!
+//! A Rhai script can define `on_request(ctx)` and `on_response(ctx)`. Both
+//! receive a map and return either nothing, to leave the request alone, or a
+//! map with any of:
+//!
+//! - `request`: replacement chat request (`on_request` only)
+//! - `headers`: extra headers for the upstream request (`on_request` only)
+//! - `drop`: reject the request with this reason (`on_request` only)
+//! - `response`: replacement response body (`on_response` only, non-streaming)
+//! - `annotations`: string pairs added to the inspector transaction
+//!
+//! `on_request` gets `#{ request, headers, key }`, where `key` is the gateway
+//! API key's name or `()`. `on_response` gets `#{ request, status, response }`.
+//! A script that fails at runtime fails the request, so a broken policy is
+//! never skipped silently.
+
+use crate::config::HooksConfig;
+use rhai::{Dynamic, Engine, Scope, AST};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Upper bound on script work per call, so a runaway loop can't stall the gateway.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// What a hook asked for.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct HookResult {
+    pub request: Option<Value>,
+    pub headers: BTreeMap<String, String>,
+    pub drop: Option<String>,
+    pub response: Option<Value>,
+    pub annotations: BTreeMap<String, String>,
+}
+
+/// A compiled hook script, shared across requests.
+#[derive(Clone)]
+pub struct Hooks {
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+}
+
+impl Hooks {
+    /// Load the configured script, if any.
+    pub fn load(config: &HooksConfig) -> Result<Option<Self>, String> {
+        let source = match (&config.script, &config.source) {
+            (Some(path), _) => std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?,
+            (None, Some(source)) => source.clone(),
+            (None, None) => return Ok(None),
+        };
+        Self::compile(&source).map(Some)
+    }
+
+    pub fn compile(source: &str) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+        Ok(Self {
+            engine: Arc::new(engine),
+            ast: Arc::new(ast),
+        })
+    }
+
+    fn defines(&self, name: &str) -> bool {
+        self.ast.iter_functions().any(|f| f.name == name)
+    }
+
+    fn call(&self, name: &str, ctx: Value) -> Result<HookResult, String> {
+        if !self.defines(name) {
+            return Ok(HookResult::default());
+        }
+        let ctx = rhai::serde::to_dynamic(ctx).map_err(|e| e.to_string())?;
+        let result: Dynamic = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, name, (ctx,))
+            .map_err(|e| format!("{} failed: {}", name, e))?;
+        if result.is_unit() {
+            return Ok(HookResult::default());
+        }
+        rhai::serde::from_dynamic(&result)
+            .map_err(|e| format!("{} returned an invalid result: {}", name, e))
+    }
+
+    /// Run `on_request` for a chat request and the client's headers.
+    pub fn on_request(
+        &self,
+        request: &Value,
+        headers: &BTreeMap<String, String>,
+        key: Option<&str>,
+    ) -> Result<HookResult, String> {
+        let ctx = json!({ "request": request, "headers": headers, "key": key });
+        let mut result = self.call("on_request", ctx)?;
+        result.response = None;
+        Ok(result)
+    }
+
+    /// Run `on_response` for a completed non-streaming request.
+    pub fn on_response(
+        &self,
+        request: &Value,
+        status: u16,
+        response: &Value,
+    ) -> Result<HookResult, String> {
+        let ctx = json!({ "request": request, "status": status, "response": response });
+        let result = self.call("on_response", ctx)?;
+        Ok(HookResult {
+            response: result.response,
+            annotations: result.annotations,
+            ..HookResult::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"
+        fn on_request(ctx) {
+            let prompt = ctx.request.messages[0].content;
+            if prompt.contains("password") {
+                return #{ drop: "prompts may not contain passwords" };
+            }
+            ctx.request.messages[0].content = "Be brief. " + prompt;
+            #{
+                request: ctx.request,
+                headers: #{ "x-team": "docs" },
+                annotations: #{ "client": ctx.headers["user-agent"] },
+            }
+        }
+
+        fn on_response(ctx) {
+            if ctx.status != 200 { return; }
+            ctx.response.choices[0].message.content += " (reviewed)";
+            #{ response: ctx.response, annotations: #{ "reviewed": "yes" } }
+        }
+    "#;
+
+    fn chat(content: &str) -> Value {
+        json!({"model": "auto", "messages": [{"role": "user", "content": content}]})
+    }
+
+    #[test]
+    fn request_hook_rewrites_adds_headers_and_annotates() {
+        let hooks = Hooks::compile(SCRIPT).unwrap();
+        let headers = BTreeMap::from([("user-agent".to_string(), "curl".to_string())]);
+
+        let result = hooks.on_request(&chat("Hi"), &headers, None).unwrap();
+
+        assert_eq!(
+            result.request.unwrap()["messages"][0]["content"],
+            "Be brief. Hi"
+        );
+        assert_eq!(result.headers["x-team"], "docs");
+        assert_eq!(result.annotations["client"], "curl");
+        assert_eq!(result.drop, None);
+    }
+
+    #[test]
+    fn request_hook_can_drop_requests() {
+        let hooks = Hooks::compile(SCRIPT).unwrap();
+        let result = hooks
+            .on_request(&chat("my password is hunter2"), &BTreeMap::new(), None)
+            .unwrap();
+        assert_eq!(
+            result.drop.as_deref(),
+            Some("prompts may not contain passwords")
+        );
+    }
+
+    #[test]
+    fn response_hook_rewrites_successful_responses() {
+        let hooks = Hooks::compile(SCRIPT).unwrap();
+        let body = json!({"choices": [{"message": {"content": "Hello"}}]});
+
+        let result = hooks.on_response(&chat("Hi"), 200, &body).unwrap();
+        assert_eq!(
+            result.response.unwrap()["choices"][0]["message"]["content"],
+            "Hello (reviewed)"
+        );
+        assert_eq!(result.annotations["reviewed"], "yes");
+
+        let unchanged = hooks.on_response(&chat("Hi"), 500, &body).unwrap();
+        assert_eq!(unchanged, HookResult::default());
+    }
+
+    #[test]
+    fn missing_hooks_and_script_errors() {
+        let hooks = Hooks::compile("fn on_request(ctx) { ctx.request.nope.field }").unwrap();
+        assert_eq!(
+            hooks.on_response(&chat("Hi"), 200, &json!({})).unwrap(),
+            HookResult::default()
+        );
+        assert!(hooks
+            .on_request(&chat("Hi"), &BTreeMap::new(), None)
+            .unwrap_err()
+            .starts_with("on_request failed"));
+
+        assert!(Hooks::compile("fn on_request(ctx) {").is_err());
+        let looping = Hooks::compile("fn on_request(ctx) { loop {} }").unwrap();
+        assert!(looping
+            .on_request(&chat("Hi"), &BTreeMap::new(), None)
+            .is_err());
+    }
+}
@@ -1,8 +1,12 @@
 //! Shared HTTP client factory.
 //!
-//! Provides consistent HTTP client configuration across the codebase.
+//! Provides consistent HTTP client configuration across the codebase, and
+//! retries of transient upstream failures with jittered exponential backoff.
 
-use reqwest::Client;
+use crate::config::HttpConfig;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
 use std::time::Duration;
 
 /// Default timeout for API calls (30 seconds).
@@ -14,6 +18,12 @@ pub const DETECTION_TIMEOUT: Duration = Duration::from_secs(2);
 /// Long timeout for expensive operations (60 seconds).
 pub const LONG_TIMEOUT: Duration = Duration::from_secs(60);
 
+/// How long idle pooled connections are kept for reuse.
+pub const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Idle connections kept per upstream host.
+pub const POOL_MAX_IDLE_PER_HOST: usize = 32;
+
 /// Create a new HTTP client with the default timeout.
 pub fn create_client() -> Client {
     create_client_with_timeout(DEFAULT_TIMEOUT)
@@ -27,6 +37,131 @@ pub fn create_client_with_timeout(timeout: Duration) -> Client {
         .expect("Failed to create HTTP client")
 }
 
+/// Create the long-lived client shared by the gateway's handlers.
+///
+/// Keeps idle connections to upstream providers open so consecutive requests
+/// skip the TCP and TLS handshakes. Requests needing a longer deadline set
+/// their own with `RequestBuilder::timeout`.
+pub fn create_shared_client() -> Client {
+    create_shared_client_with(&HttpConfig::default())
+}
+
+/// Create the shared client with pool settings from `[http]`.
+pub fn create_shared_client_with(config: &HttpConfig) -> Client {
+    let mut builder = Client::builder()
+        .timeout(DEFAULT_TIMEOUT)
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
+        .pool_idle_timeout(Duration::from_secs(config.pool_idle_timeout_secs))
+        .tcp_keepalive(Duration::from_secs(60))
+        .http2_keep_alive_interval(Duration::from_secs(30))
+        .http2_keep_alive_while_idle(true);
+    if config.http2_prior_knowledge {
+        builder = builder.http2_prior_knowledge();
+    }
+    builder.build().expect("Failed to create HTTP client")
+}
+
+/// How transient upstream failures are retried (`[http]`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts per request, including the first; 1 turns retries off.
+    pub max_attempts: u32,
+    /// Backoff before the first retry; doubled for each one after.
+    pub base_delay: Duration,
+    /// Longest wait between attempts. Upstreams asking to wait longer are
+    /// not retried, so the caller can fall back to another model instead.
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Retries as configured in `[http]`.
+    pub fn from_config(config: &HttpConfig) -> Self {
+        Self {
+            max_attempts: config.retry_max_attempts.max(1),
+            base_delay: Duration::from_millis(config.retry_base_delay_ms),
+            max_delay: Duration::from_millis(config.retry_max_delay_ms),
+        }
+    }
+
+    /// Backoff before retry number `retry` (from 1): `base_delay * 2^(retry - 1)`
+    /// capped at `max_delay`, of which a random half is waited so clients
+    /// retrying together spread out.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let full = self
+            .base_delay
+            .saturating_mul(1 << retry.saturating_sub(1).min(16))
+            .min(self.max_delay);
+        let jitter = RandomState::new().build_hasher().finish() % 1000;
+        full / 2 + full / 2 * jitter as u32 / 1000
+    }
+
+    /// Wait before retry number `retry`: the upstream's own retry delay when
+    /// it sent one, else the backoff. None when that is over `max_delay`.
+    pub fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        let wait = retry_after.unwrap_or_else(|| self.backoff(retry));
+        (wait <= self.max_delay).then_some(wait)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::from_config(&HttpConfig::default())
+    }
+}
+
+/// Whether an upstream status is worth retrying on the same upstream.
+pub fn is_transient(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 500 | 502 | 503 | 504)
+}
+
+/// Send `request`, retrying connection failures and transient statuses as
+/// `policy` allows. `retry_after` reads how long a response asks the client
+/// to wait. The last response or error is returned when retries run out.
+pub async fn send_with_retry(
+    policy: RetryPolicy,
+    request: RequestBuilder,
+    retry_after: impl Fn(&Response) -> Option<Duration>,
+) -> Result<Response, reqwest::Error> {
+    let mut attempt = 1;
+    loop {
+        // Requests with streamed bodies can't be sent twice
+        let retryable = (attempt < policy.max_attempts)
+            .then(|| request.try_clone())
+            .flatten();
+        let Some(this_attempt) = retryable else {
+            return request.send().await;
+        };
+        let wait = match this_attempt.send().await {
+            Ok(response) if is_transient(response.status()) => {
+                match policy.delay(attempt, retry_after(&response)) {
+                    Some(wait) => wait,
+                    None => return Ok(response),
+                }
+            }
+            Err(e) if e.is_connect() => policy.backoff(attempt),
+            result => return result,
+        };
+        tracing::debug!("Retrying upstream request in {:?} (attempt {})", wait, attempt + 1);
+        tokio::time::sleep(wait).await;
+        attempt += 1;
+    }
+}
+
+/// Drain the complete lines in `buffer`, returning their `data:` payloads.
+/// A trailing partial line stays buffered for the next read, so stream
+/// translators can feed in reads as they arrive.
+pub fn take_sse_data(buffer: &mut Vec<u8>) -> Vec<String> {
+    let mut data = Vec::new();
+    while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+        let line: Vec<u8> = buffer.drain(..=end).collect();
+        let line = String::from_utf8_lossy(&line);
+        if let Some(payload) = line.trim_end().strip_prefix("data:") {
+            data.push(payload.trim_start().to_string());
+        }
+    }
+    data
+}
+
 /// Create a blocking HTTP client with a custom timeout.
 pub fn create_blocking_client(timeout: Duration) -> reqwest::blocking::Client {
     reqwest::blocking::Client::builder()
@@ -46,6 +181,108 @@ mod tests {
         assert!(std::mem::size_of_val(&client) > 0);
     }
 
+    #[test]
+    fn create_shared_client_returns_valid_client() {
+        let _client = create_shared_client();
+    }
+
+    #[test]
+    fn create_shared_client_with_accepts_pool_settings() {
+        let _client = create_shared_client_with(&HttpConfig {
+            pool_max_idle_per_host: 4,
+            pool_idle_timeout_secs: 10,
+            http2_prior_knowledge: true,
+            ..HttpConfig::default()
+        });
+    }
+
+    #[test]
+    fn default_pool_settings_match_constants() {
+        let config = HttpConfig::default();
+        assert_eq!(config.pool_max_idle_per_host, POOL_MAX_IDLE_PER_HOST);
+        assert_eq!(config.pool_idle_timeout_secs, POOL_IDLE_TIMEOUT.as_secs());
+    }
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(100),
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_with_jitter_up_to_the_cap() {
+        let policy = policy(5);
+        for (retry, full) in [(1, 10), (2, 20), (3, 40), (4, 80), (5, 100), (30, 100)] {
+            let wait = policy.backoff(retry);
+            let full = Duration::from_millis(full);
+            assert!(wait >= full / 2 && wait <= full, "retry {}: {:?}", retry, wait);
+        }
+    }
+
+    #[test]
+    fn retry_after_is_honored_up_to_the_cap() {
+        let policy = policy(3);
+        let asked = Duration::from_millis(50);
+        assert_eq!(policy.delay(1, Some(asked)), Some(asked));
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(30))), None);
+        assert!(policy.delay(1, None).is_some());
+    }
+
+    #[test]
+    fn only_transient_statuses_are_retried() {
+        assert!(is_transient(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_transient(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_transient(StatusCode::BAD_REQUEST));
+        assert!(!is_transient(StatusCode::NOT_IMPLEMENTED));
+    }
+
+    #[tokio::test]
+    async fn send_with_retry_retries_transient_failures() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("GET", "/")
+            .with_status(503)
+            .expect(2)
+            .create_async()
+            .await;
+        let ok = server.mock("GET", "/").expect(1).create_async().await;
+        let client = create_client();
+
+        let response = send_with_retry(policy(3), client.get(server.url()), |_| None)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        unavailable.assert_async().await;
+        ok.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn send_with_retry_gives_up_after_max_attempts() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("GET", "/")
+            .with_status(503)
+            .expect(2)
+            .create_async()
+            .await;
+        let client = create_client();
+
+        let response = send_with_retry(policy(2), client.get(server.url()), |_| None)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        unavailable.assert_async().await;
+
+        // A long Retry-After is returned at once
+        let slow = |_: &Response| Some(Duration::from_secs(30));
+        let response = send_with_retry(policy(2), client.get(server.url()), slow)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
     #[test]
     fn create_client_with_timeout_uses_specified_timeout() {
         // We can't directly inspect the timeout, but we can verify it compiles
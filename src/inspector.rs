@@ -8,8 +8,11 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use utoipa::IntoParams;
 use uuid::Uuid;
 
 /// A captured HTTP transaction (request + response).
@@ -20,6 +23,18 @@ pub struct CapturedTransaction {
     pub request: CapturedRequest,
     pub response: Option<CapturedResponse>,
     pub timing: TimingMetrics,
+    /// A/B test arm the request was routed to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ab_arm: Option<String>,
+    /// Name of the gateway API key the request was made with, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub virtual_key: Option<String>,
+    /// Model the request was sent to, after aliases and `auto` were resolved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routed_model: Option<String>,
+    /// Notes added by request/response hooks.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
     #[serde(skip)]
     pub(crate) start_time: Option<Instant>,
 }
@@ -54,6 +69,59 @@ pub struct TimingMetrics {
     pub prompt_tokens: Option<u32>,
     /// Completion tokens used.
     pub completion_tokens: Option<u32>,
+    /// Cadence of upstream chunks (for streaming responses).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunks: Option<ChunkTiming>,
+}
+
+/// How steadily a stream arrived: upstream chunk count and the gaps
+/// between consecutive chunks.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct ChunkTiming {
+    pub count: u32,
+    pub mean_gap_ms: f64,
+    /// Longest stall between two chunks.
+    pub max_gap_ms: u64,
+}
+
+/// Tracks chunk arrivals while a stream is forwarded.
+#[derive(Debug, Default)]
+pub struct ChunkCadence {
+    count: u32,
+    last: Option<Instant>,
+    total_gap: Duration,
+    max_gap: Duration,
+}
+
+impl ChunkCadence {
+    /// Note a chunk arriving now.
+    pub fn tick(&mut self) {
+        self.tick_at(Instant::now());
+    }
+
+    fn tick_at(&mut self, now: Instant) {
+        if let Some(last) = self.last {
+            let gap = now.saturating_duration_since(last);
+            self.total_gap += gap;
+            self.max_gap = self.max_gap.max(gap);
+        }
+        self.last = Some(now);
+        self.count += 1;
+    }
+
+    /// Summary of the chunks seen so far, if any.
+    pub fn timing(&self) -> Option<ChunkTiming> {
+        let gaps = self.count.saturating_sub(1);
+        (self.count > 0).then(|| ChunkTiming {
+            count: self.count,
+            mean_gap_ms: if gaps == 0 {
+                0.0
+            } else {
+                self.total_gap.as_secs_f64() * 1000.0 / f64::from(gaps)
+            },
+            max_gap_ms: self.max_gap.as_millis() as u64,
+        })
+    }
 }
 
 impl TimingMetrics {
@@ -75,18 +143,55 @@ impl TimingMetrics {
     }
 }
 
+/// Which transactions to show.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[serde(default)]
+pub struct TransactionFilter {
+    /// Only show requests whose model ID contains this string.
+    pub model: Option<String>,
+    /// Only show failed requests (no response or status >= 400).
+    pub errors_only: bool,
+}
+
+impl TransactionFilter {
+    /// Check whether a transaction passes the filter.
+    pub fn matches(&self, tx: &CapturedTransaction) -> bool {
+        if let Some(wanted) = &self.model {
+            let model = tx
+                .request
+                .body
+                .as_ref()
+                .and_then(|b| b["model"].as_str())
+                .unwrap_or_default();
+            if !model.contains(wanted.as_str()) {
+                return false;
+            }
+        }
+
+        if self.errors_only {
+            let status = tx.response.as_ref().map(|r| r.status).unwrap_or(0);
+            return status == 0 || status >= 400;
+        }
+
+        true
+    }
+}
+
 /// Traffic inspector for capturing and analyzing HTTP transactions.
 #[derive(Clone)]
 pub struct TrafficInspector {
     transactions: Arc<Mutex<Vec<CapturedTransaction>>>,
     enabled: Arc<Mutex<bool>>,
+    sender: broadcast::Sender<CapturedTransaction>,
 }
 
 impl TrafficInspector {
     pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(256);
         Self {
             transactions: Arc::new(Mutex::new(Vec::new())),
             enabled: Arc::new(Mutex::new(true)),
+            sender,
         }
     }
 
@@ -108,6 +213,10 @@ impl TrafficInspector {
             request,
             response: None,
             timing: TimingMetrics::default(),
+            ab_arm: None,
+            virtual_key: None,
+            routed_model: None,
+            annotations: BTreeMap::new(),
             start_time: Some(Instant::now()),
         }
     }
@@ -143,18 +252,40 @@ impl TrafficInspector {
         transaction.timing.tokens_per_sec = transaction.timing.calculate_tps();
     }
 
+    /// Record the cadence of a forwarded stream.
+    pub fn record_chunks(&self, transaction: &mut CapturedTransaction, cadence: &ChunkCadence) {
+        transaction.timing.chunks = cadence.timing();
+    }
+
     /// Store a completed transaction.
     pub fn store(&self, transaction: CapturedTransaction) {
         if self.is_enabled() {
-            self.transactions.lock().unwrap().push(transaction);
+            self.transactions.lock().unwrap().push(transaction.clone());
+            // No subscribers is not an error
+            let _ = self.sender.send(transaction);
         }
     }
 
+    /// Subscribe to transactions as they are stored.
+    pub fn subscribe(&self) -> broadcast::Receiver<CapturedTransaction> {
+        self.sender.subscribe()
+    }
+
     /// Get all stored transactions.
     pub fn get_all(&self) -> Vec<CapturedTransaction> {
         self.transactions.lock().unwrap().clone()
     }
 
+    /// Count stored transactions that started at or after `since`.
+    pub fn count_since(&self, since: DateTime<Utc>) -> usize {
+        self.transactions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|tx| tx.timestamp >= since)
+            .count()
+    }
+
     /// Clear all stored transactions.
     pub fn clear(&self) {
         self.transactions.lock().unwrap().clear();
@@ -162,65 +293,67 @@ impl TrafficInspector {
 
     /// Export transactions in HAR (HTTP Archive) format.
     pub fn export_har(&self) -> serde_json::Value {
-        let transactions = self.get_all();
+        har_log(self.get_all().iter().map(har_entry).collect())
+    }
+}
 
-        let entries: Vec<serde_json::Value> = transactions
-            .iter()
-            .map(|tx| {
+/// Convert a transaction to a HAR entry.
+pub fn har_entry(tx: &CapturedTransaction) -> serde_json::Value {
+    serde_json::json!({
+        "startedDateTime": tx.timestamp.to_rfc3339(),
+        "time": tx.timing.total_ms,
+        "request": {
+            "method": tx.request.method,
+            "url": tx.request.url,
+            "headers": tx.request.headers.iter().map(|(k, v)| {
+                serde_json::json!({"name": k, "value": v})
+            }).collect::<Vec<_>>(),
+            "postData": tx.request.body.as_ref().map(|b| {
                 serde_json::json!({
-                    "startedDateTime": tx.timestamp.to_rfc3339(),
-                    "time": tx.timing.total_ms,
-                    "request": {
-                        "method": tx.request.method,
-                        "url": tx.request.url,
-                        "headers": tx.request.headers.iter().map(|(k, v)| {
-                            serde_json::json!({"name": k, "value": v})
-                        }).collect::<Vec<_>>(),
-                        "postData": tx.request.body.as_ref().map(|b| {
-                            serde_json::json!({
-                                "mimeType": "application/json",
-                                "text": b.to_string()
-                            })
-                        }),
-                    },
-                    "response": tx.response.as_ref().map(|r| {
-                        serde_json::json!({
-                            "status": r.status,
-                            "headers": r.headers.iter().map(|(k, v)| {
-                                serde_json::json!({"name": k, "value": v})
-                            }).collect::<Vec<_>>(),
-                            "content": r.body.as_ref().map(|b| {
-                                serde_json::json!({
-                                    "mimeType": "application/json",
-                                    "text": b.to_string()
-                                })
-                            }),
-                        })
-                    }),
-                    "timings": {
-                        "total": tx.timing.total_ms,
-                        "ttfb": tx.timing.ttfb_ms,
-                    },
-                    "_llmMetrics": {
-                        "promptTokens": tx.timing.prompt_tokens,
-                        "completionTokens": tx.timing.completion_tokens,
-                        "tokensPerSecond": tx.timing.tokens_per_sec,
-                    }
+                    "mimeType": "application/json",
+                    "text": b.to_string()
                 })
+            }),
+        },
+        "response": tx.response.as_ref().map(|r| {
+            serde_json::json!({
+                "status": r.status,
+                "headers": r.headers.iter().map(|(k, v)| {
+                    serde_json::json!({"name": k, "value": v})
+                }).collect::<Vec<_>>(),
+                "content": r.body.as_ref().map(|b| {
+                    serde_json::json!({
+                        "mimeType": "application/json",
+                        "text": b.to_string()
+                    })
+                }),
             })
-            .collect();
-
-        serde_json::json!({
-            "log": {
-                "version": "1.2",
-                "creator": {
-                    "name": "free-router",
-                    "version": env!("CARGO_PKG_VERSION"),
-                },
-                "entries": entries,
-            }
-        })
-    }
+        }),
+        "timings": {
+            "total": tx.timing.total_ms,
+            "ttfb": tx.timing.ttfb_ms,
+        },
+        "_llmMetrics": {
+            "promptTokens": tx.timing.prompt_tokens,
+            "completionTokens": tx.timing.completion_tokens,
+            "tokensPerSecond": tx.timing.tokens_per_sec,
+            "chunks": tx.timing.chunks,
+        }
+    })
+}
+
+/// Wrap HAR entries in a HAR log document.
+pub fn har_log(entries: Vec<serde_json::Value>) -> serde_json::Value {
+    serde_json::json!({
+        "log": {
+            "version": "1.2",
+            "creator": {
+                "name": "free-router",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "entries": entries,
+        }
+    })
 }
 
 impl Default for TrafficInspector {
@@ -348,6 +481,7 @@ mod tests {
             tokens_per_sec: None,
             prompt_tokens: Some(100),
             completion_tokens: Some(50),
+            chunks: None,
         };
 
         // 50 completion tokens in 1.8 seconds = ~27.8 TPS
@@ -356,4 +490,55 @@ mod tests {
         let tps = tps.unwrap();
         assert!(tps > 20.0 && tps < 35.0);
     }
+
+    #[test]
+    fn chunk_cadence_tracks_gaps() {
+        let mut cadence = ChunkCadence::default();
+        assert_eq!(cadence.timing(), None);
+
+        let start = Instant::now();
+        cadence.tick_at(start);
+        assert_eq!(cadence.timing().unwrap().mean_gap_ms, 0.0);
+        cadence.tick_at(start + Duration::from_millis(100));
+        cadence.tick_at(start + Duration::from_millis(400));
+
+        let timing = cadence.timing().unwrap();
+        assert_eq!(timing.count, 3);
+        assert_eq!(timing.max_gap_ms, 300);
+        assert!((timing.mean_gap_ms - 200.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn counts_transactions_since_a_time() {
+        let inspector = TrafficInspector::new();
+        let mut old = inspector.start_transaction(CapturedRequest {
+            method: "POST".to_string(),
+            url: "/v1/chat/completions".to_string(),
+            headers: vec![],
+            body: None,
+        });
+        old.timestamp = Utc::now() - chrono::Duration::days(2);
+        let recent = inspector.start_transaction(old.request.clone());
+        inspector.store(old);
+        inspector.store(recent);
+
+        assert_eq!(inspector.count_since(Utc::now() - chrono::Duration::days(1)), 1);
+    }
+
+    #[test]
+    fn subscribers_receive_stored_transactions() {
+        let inspector = TrafficInspector::new();
+        let mut receiver = inspector.subscribe();
+
+        let tx = inspector.start_transaction(CapturedRequest {
+            method: "POST".to_string(),
+            url: "/v1/chat/completions".to_string(),
+            headers: vec![],
+            body: None,
+        });
+        let id = tx.id.clone();
+        inspector.store(tx);
+
+        assert_eq!(receiver.try_recv().unwrap().id, id);
+    }
 }
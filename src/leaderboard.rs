@@ -0,0 +1,268 @@
+//! Leaderboard of free models built from past comparisons.
+//!
+//! Every comparison the comparator finishes (`compare_models`, eval runs,
+//! benchmarks) is recorded per model: whether it won, failed, what the
+//! judges gave it and how quickly it answered. `GET /v1/leaderboard` and the
+//! `leaderboard://current` MCP resource aggregate a recent window, so "which
+//! free model is best this week" is answered from data.
+
+use crate::mcp::CompareResult;
+use crate::storage;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Result as SqlResult};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+/// Days aggregated unless a window is given.
+pub const DEFAULT_DAYS: u32 = 7;
+
+/// One model's standing over the window.
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct LeaderboardEntry {
+    pub model: String,
+    /// Comparisons the model took part in, including failed ones.
+    pub comparisons: usize,
+    pub wins: usize,
+    pub failures: usize,
+    /// Wins over answered comparisons that had more than one contender.
+    pub win_rate: f64,
+    /// Mean judge quality score, when any answers were judged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean_judge_score: Option<f64>,
+    /// Mean time to first byte of answered comparisons.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean_ttfb_ms: Option<f64>,
+}
+
+/// SQLite-backed comparison history, shared across handlers.
+#[derive(Debug, Clone)]
+pub struct Leaderboard {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Leaderboard {
+    /// Open the store in MultiAI's data directory.
+    pub fn open_default() -> SqlResult<Self> {
+        Self::with_connection(storage::open_data_db("leaderboard.db")?)
+    }
+
+    /// Open or create a leaderboard database.
+    pub fn open<P: AsRef<Path>>(path: P) -> SqlResult<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Create an in-memory store (for testing).
+    pub fn in_memory() -> SqlResult<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> SqlResult<Self> {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS results (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                compared_at TEXT NOT NULL,
+                model TEXT NOT NULL,
+                contenders INTEGER NOT NULL,
+                won INTEGER NOT NULL,
+                failed INTEGER NOT NULL,
+                judge_score REAL,
+                ttfb_ms INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS idx_results_compared_at ON results(compared_at);
+            "#,
+        )?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Record each model's outcome in a finished comparison. Models
+    /// cancelled once enough others answered are left out.
+    pub fn record(&self, result: &CompareResult) -> SqlResult<()> {
+        let compared_at = result.compared_at.to_rfc3339();
+        let contenders = result.results.len() as i64;
+        let winner = result.ranking.first().filter(|_| contenders > 1);
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for r in &result.results {
+            let judge_score = (!r.judge_scores.is_empty()).then_some(r.scores.quality);
+            tx.execute(
+                "INSERT INTO results
+                     (compared_at, model, contenders, won, failed, judge_score, ttfb_ms)
+                 VALUES (?, ?, ?, ?, 0, ?, ?)",
+                params![
+                    compared_at,
+                    r.model,
+                    contenders,
+                    winner == Some(&r.model),
+                    judge_score,
+                    r.metrics.ttft_ms as i64
+                ],
+            )?;
+        }
+        for f in result.failures.iter().filter(|f| !f.is_cancelled()) {
+            tx.execute(
+                "INSERT INTO results (compared_at, model, contenders, won, failed)
+                 VALUES (?, ?, ?, 0, 1)",
+                params![compared_at, f.model, contenders],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Standings of every model compared since `since`, best first: by win
+    /// rate, then mean judge score.
+    pub fn standings(&self, since: DateTime<Utc>) -> SqlResult<Vec<LeaderboardEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT model, COUNT(*), SUM(won), SUM(failed),
+                 SUM(contenders > 1 AND failed = 0), AVG(judge_score), AVG(ttfb_ms)
+             FROM results
+             WHERE compared_at >= ?
+             GROUP BY model",
+        )?;
+        let mut entries = stmt
+            .query_map(params![since.to_rfc3339()], |row| {
+                let wins = row.get::<_, i64>(2)? as usize;
+                let contested = row.get::<_, i64>(4)? as usize;
+                Ok(LeaderboardEntry {
+                    model: row.get(0)?,
+                    comparisons: row.get::<_, i64>(1)? as usize,
+                    wins,
+                    failures: row.get::<_, i64>(3)? as usize,
+                    win_rate: if contested == 0 {
+                        0.0
+                    } else {
+                        wins as f64 / contested as f64
+                    },
+                    mean_judge_score: row.get(5)?,
+                    mean_ttfb_ms: row.get(6)?,
+                })
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+        entries.sort_by(|a, b| {
+            b.win_rate
+                .total_cmp(&a.win_rate)
+                .then_with(|| {
+                    b.mean_judge_score
+                        .unwrap_or(f64::MIN)
+                        .total_cmp(&a.mean_judge_score.unwrap_or(f64::MIN))
+                })
+                .then_with(|| a.model.cmp(&b.model))
+        });
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::{
+        JudgeScore, ModelFailure, ModelResult, ResponseMetrics, ResponseScores, ScoreWeights,
+    };
+
+    fn answer(model: &str, ttft_ms: u64, quality: Option<f64>) -> ModelResult {
+        ModelResult {
+            model: model.to_string(),
+            source: "ollama".to_string(),
+            response: "answer".to_string(),
+            metrics: ResponseMetrics {
+                ttft_ms,
+                total_ms: ttft_ms * 2,
+                input_tokens: 5,
+                output_tokens: 20,
+                tokens_per_sec: 10.0,
+            },
+            scores: ResponseScores {
+                speed: 5.0,
+                quality: quality.unwrap_or(7.0),
+                efficiency: 5.0,
+                overall: 5.0,
+            },
+            judge_scores: quality
+                .map(|score| JudgeScore {
+                    judge: "judge".to_string(),
+                    score,
+                    reason: String::new(),
+                    criteria: Default::default(),
+                    verdict: String::new(),
+                })
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    fn comparison(results: Vec<ModelResult>, failures: Vec<ModelFailure>) -> CompareResult {
+        CompareResult {
+            prompt: "p".to_string(),
+            compared_at: Utc::now(),
+            ranking: results.iter().map(|r| r.model.clone()).collect(),
+            results,
+            failures,
+            weights: ScoreWeights::default(),
+            markdown_summary: String::new(),
+        }
+    }
+
+    fn failure(model: &str, error: &str) -> ModelFailure {
+        ModelFailure {
+            model: model.to_string(),
+            error: error.to_string(),
+            status: None,
+        }
+    }
+
+    #[test]
+    fn aggregates_wins_judge_scores_and_ttfb() {
+        let board = Leaderboard::in_memory().unwrap();
+        board
+            .record(&comparison(
+                vec![answer("a", 100, Some(9.0)), answer("b", 300, Some(6.0))],
+                vec![failure("c", "Request timeout")],
+            ))
+            .unwrap();
+        board
+            .record(&comparison(
+                vec![answer("b", 100, Some(8.0)), answer("a", 200, Some(7.0))],
+                vec![],
+            ))
+            .unwrap();
+        board
+            .record(&comparison(vec![answer("a", 300, None)], vec![]))
+            .unwrap();
+
+        let standings = board.standings(Utc::now() - chrono::Duration::days(1)).unwrap();
+        let models: Vec<&str> = standings.iter().map(|e| e.model.as_str()).collect();
+        assert_eq!(models, vec!["a", "b", "c"]);
+
+        let a = &standings[0];
+        assert_eq!((a.comparisons, a.wins, a.failures), (3, 1, 0));
+        assert_eq!(a.win_rate, 0.5);
+        assert_eq!(a.mean_judge_score, Some(8.0));
+        assert_eq!(a.mean_ttfb_ms, Some(200.0));
+
+        let c = &standings[2];
+        assert_eq!((c.comparisons, c.failures), (1, 1));
+        assert_eq!(c.mean_ttfb_ms, None);
+    }
+
+    #[test]
+    fn skips_cancelled_models_and_old_results() {
+        let board = Leaderboard::in_memory().unwrap();
+        let mut old = comparison(vec![answer("old", 100, None)], vec![]);
+        old.compared_at = Utc::now() - chrono::Duration::days(30);
+        board.record(&old).unwrap();
+        let cancelled = ModelFailure::cancelled("late".to_string());
+        board
+            .record(&comparison(vec![answer("a", 100, None)], vec![cancelled]))
+            .unwrap();
+
+        let standings = board.standings(Utc::now() - chrono::Duration::days(7)).unwrap();
+        assert_eq!(standings.len(), 1);
+        assert_eq!(standings[0].model, "a");
+    }
+}
@@ -7,15 +7,36 @@
 //! - OpenAI-compatible API
 //! - Web-based chat UI with document support
 
+pub mod ab_test;
 pub mod api;
+pub mod bench;
 pub mod chat;
 pub mod chat_api;
+pub mod cli;
+pub mod cloudflare;
 pub mod config;
 pub mod document;
+pub mod embedding_cache;
 pub mod error;
+pub mod evals;
+pub mod events;
 pub mod export;
+pub mod gemini;
+pub mod hooks;
 pub mod http;
 pub mod inspector;
+pub mod leaderboard;
 pub mod logger;
 pub mod mcp;
+pub mod ollama;
+pub mod response_cache;
+pub mod routing;
 pub mod scanner;
+pub mod scheduler;
+pub mod speech;
+pub mod storage;
+pub mod telemetry;
+pub mod templates;
+pub mod traffic_log;
+pub mod transcript;
+pub mod virtual_keys;
@@ -4,10 +4,20 @@
 //! - Minimal: One-liner nginx-style
 //! - Compact: Multi-line httpie-style
 //! - Verbose: Full mitmproxy-style
+//!
+//! Transaction summaries can also be sent to the system log (syslog or the
+//! Windows Event Log) for centralized collection, and slow or failing
+//! requests can trigger alerts.
 
-use crate::config::LogVerbosity;
+use crate::config::{AlertConfig, LogVerbosity};
 use crate::inspector::{CapturedRequest, CapturedTransaction, TimingMetrics};
+use std::collections::VecDeque;
 use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+/// Default number of formatted lines kept in the in-process log buffer.
+pub const DEFAULT_LOG_BUFFER_LINES: usize = 1000;
 
 /// Extract model name from request body.
 fn extract_model(body: &Option<serde_json::Value>) -> &str {
@@ -182,9 +192,367 @@ pub fn log_transaction<W: Write>(
     writeln!(writer, "{}", format_transaction(tx, verbosity))
 }
 
+/// In-process ring buffer of formatted log lines.
+///
+/// Backs the log tail endpoints so the UI can show a console pane
+/// without reading log files from disk.
+#[derive(Clone)]
+pub struct LogBuffer {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+    verbosity: Arc<Mutex<LogVerbosity>>,
+    sender: broadcast::Sender<String>,
+}
+
+impl LogBuffer {
+    /// Create a buffer that keeps at most `capacity` lines.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(256);
+        Self {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+            verbosity: Arc::new(Mutex::new(LogVerbosity::Compact)),
+            sender,
+        }
+    }
+
+    /// Set the verbosity used when formatting transactions.
+    pub fn with_verbosity(self, verbosity: LogVerbosity) -> Self {
+        self.set_verbosity(verbosity);
+        self
+    }
+
+    /// Get the verbosity used when formatting transactions.
+    pub fn verbosity(&self) -> LogVerbosity {
+        self.verbosity.lock().unwrap().clone()
+    }
+
+    /// Change the verbosity at runtime; applies to transactions pushed afterwards.
+    pub fn set_verbosity(&self, verbosity: LogVerbosity) {
+        *self.verbosity.lock().unwrap() = verbosity;
+    }
+
+    /// Append a single line, evicting the oldest line when full.
+    pub fn push_line(&self, line: impl Into<String>) {
+        let line = line.into();
+        {
+            let mut lines = self.lines.lock().unwrap();
+            if lines.len() >= self.capacity {
+                lines.pop_front();
+            }
+            lines.push_back(line.clone());
+        }
+        // No subscribers is not an error
+        let _ = self.sender.send(line);
+    }
+
+    /// Format a transaction and append each of its lines.
+    pub fn push_transaction(&self, tx: &CapturedTransaction) {
+        for line in format_transaction(tx, &self.verbosity()).lines() {
+            self.push_line(line);
+        }
+    }
+
+    /// Get the last `n` lines, oldest first.
+    pub fn tail(&self, n: usize) -> Vec<String> {
+        let lines = self.lines.lock().unwrap();
+        let skip = lines.len().saturating_sub(n);
+        lines.iter().skip(skip).cloned().collect()
+    }
+
+    /// Subscribe to lines as they are appended.
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOG_BUFFER_LINES)
+    }
+}
+
+/// Syslog severity levels used for transaction summaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyslogSeverity {
+    Error = 3,
+    Warning = 4,
+    Info = 6,
+}
+
+impl SyslogSeverity {
+    /// Pick a severity from a response status (0 means no response).
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            0 | 500.. => Self::Error,
+            400..=499 => Self::Warning,
+            _ => Self::Info,
+        }
+    }
+}
+
+/// Syslog facility for user-level messages.
+const SYSLOG_FACILITY_USER: u8 = 1;
+
+/// Local syslog sockets, in lookup order (Linux/journald, then macOS).
+#[cfg(unix)]
+const SYSLOG_SOCKETS: &[&str] = &["/dev/log", "/var/run/syslog"];
+
+/// Format a message in the BSD syslog wire format accepted by local daemons.
+pub fn format_syslog_message(ident: &str, severity: SyslogSeverity, message: &str) -> String {
+    let priority = SYSLOG_FACILITY_USER * 8 + severity as u8;
+    format!("<{}>{}[{}]: {}", priority, ident, std::process::id(), message)
+}
+
+/// How long the Windows sink gathers lines before writing them as one event,
+/// so a busy gateway doesn't start an `eventcreate` process per request.
+#[cfg(windows)]
+const EVENT_LOG_BATCH: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Longest event description written; lines past it are only counted.
+#[cfg(any(windows, test))]
+const MAX_EVENT_DESCRIPTION: usize = 8 * 1024;
+
+/// Combine batched lines into one event at the most severe level among them.
+#[cfg(any(windows, test))]
+fn batch_event(batch: &[(SyslogSeverity, String)]) -> (SyslogSeverity, String) {
+    let severity = batch
+        .iter()
+        .map(|(severity, _)| *severity)
+        .min_by_key(|severity| *severity as u8)
+        .unwrap_or(SyslogSeverity::Info);
+    let mut description = String::new();
+    let mut dropped = 0;
+    for (_, line) in batch {
+        if description.len() + line.len() + 1 > MAX_EVENT_DESCRIPTION {
+            dropped += 1;
+            continue;
+        }
+        if !description.is_empty() {
+            description.push('\n');
+        }
+        description.push_str(line);
+    }
+    if dropped > 0 {
+        description.push_str(&format!("\n... and {} more", dropped));
+    }
+    (severity, description)
+}
+
+/// Write batches of lines to the Application event log until the sink is
+/// dropped. Runs on its own thread and waits for each `eventcreate`, so at
+/// most one runs at a time.
+#[cfg(windows)]
+fn write_event_log(ident: &str, lines: std::sync::mpsc::Receiver<(SyslogSeverity, String)>) {
+    use std::process::{Command, Stdio};
+    use std::time::Instant;
+
+    while let Ok(first) = lines.recv() {
+        let mut batch = vec![first];
+        let deadline = Instant::now() + EVENT_LOG_BATCH;
+        while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+            match lines.recv_timeout(left) {
+                Ok(line) => batch.push(line),
+                Err(_) => break,
+            }
+        }
+
+        let (severity, description) = batch_event(&batch);
+        let event_type = match severity {
+            SyslogSeverity::Error => "ERROR",
+            SyslogSeverity::Warning => "WARNING",
+            SyslogSeverity::Info => "INFORMATION",
+        };
+        let _ = Command::new("eventcreate")
+            .args(["/T", event_type, "/ID", "1", "/L", "APPLICATION", "/SO", ident])
+            .args(["/D", &description])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+/// Sends transaction summaries to the system log.
+///
+/// Uses the local syslog socket on unix (collected by journald under systemd)
+/// and the Application event log on Windows, where the lines of every few
+/// seconds are written as one event.
+#[derive(Debug, Clone)]
+pub struct SyslogSink {
+    #[cfg(not(windows))]
+    ident: String,
+    #[cfg(windows)]
+    events: std::sync::mpsc::Sender<(SyslogSeverity, String)>,
+}
+
+impl SyslogSink {
+    #[cfg(not(windows))]
+    pub fn new(ident: impl Into<String>) -> Self {
+        Self {
+            ident: ident.into(),
+        }
+    }
+
+    #[cfg(windows)]
+    pub fn new(ident: impl Into<String>) -> Self {
+        let ident = ident.into();
+        let (events, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || write_event_log(&ident, receiver));
+        Self { events }
+    }
+
+    /// Log a one-line transaction summary. Failures are ignored so that
+    /// logging never affects request handling.
+    pub fn log_transaction(&self, tx: &CapturedTransaction) {
+        let status = tx.response.as_ref().map(|r| r.status).unwrap_or(0);
+        let line = format_transaction(tx, &LogVerbosity::Minimal);
+        let _ = self.send(SyslogSeverity::from_status(status), &line);
+    }
+
+    #[cfg(unix)]
+    fn send(&self, severity: SyslogSeverity, message: &str) -> std::io::Result<()> {
+        use std::os::unix::net::UnixDatagram;
+
+        let path = SYSLOG_SOCKETS
+            .iter()
+            .find(|p| std::path::Path::new(p).exists())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No syslog socket"))?;
+
+        let socket = UnixDatagram::unbound()?;
+        let payload = format_syslog_message(&self.ident, severity, message);
+        socket.send_to(payload.as_bytes(), path)?;
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn send(&self, severity: SyslogSeverity, message: &str) -> std::io::Result<()> {
+        self.events.send((severity, message.to_string())).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Event log writer stopped")
+        })
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn send(&self, _severity: SyslogSeverity, _message: &str) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Why a transaction triggered an alert.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertReason {
+    /// Response status at or above the error threshold (0 means no response).
+    Error { status: u16 },
+    /// Total duration above the slow-request threshold.
+    Slow { total_ms: u64, threshold_ms: u64 },
+}
+
+impl AlertReason {
+    /// Short machine-readable event name.
+    pub fn event(&self) -> &'static str {
+        match self {
+            Self::Error { .. } => "error",
+            Self::Slow { .. } => "slow_request",
+        }
+    }
+}
+
+impl std::fmt::Display for AlertReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error { status } => write!(f, "error response (status {})", status),
+            Self::Slow { total_ms, threshold_ms } => write!(
+                f,
+                "slow request ({} > {})",
+                format_duration(*total_ms),
+                format_duration(*threshold_ms)
+            ),
+        }
+    }
+}
+
+/// Flags slow or failing transactions and optionally posts them to a webhook.
+#[derive(Clone)]
+pub struct AlertHook {
+    config: AlertConfig,
+    client: reqwest::Client,
+}
+
+impl AlertHook {
+    /// A hook posting to its webhook through `client`, normally the
+    /// gateway's shared `[http]` client.
+    pub fn new(config: AlertConfig, client: reqwest::Client) -> Self {
+        Self { config, client }
+    }
+
+    /// Check a transaction against the thresholds. Errors take precedence.
+    pub fn check(&self, tx: &CapturedTransaction) -> Option<AlertReason> {
+        let status = tx.response.as_ref().map(|r| r.status).unwrap_or(0);
+        if self.config.error_status > 0 && (status == 0 || status >= self.config.error_status) {
+            return Some(AlertReason::Error { status });
+        }
+
+        if self.config.slow_request_ms > 0 && tx.timing.total_ms > self.config.slow_request_ms {
+            return Some(AlertReason::Slow {
+                total_ms: tx.timing.total_ms,
+                threshold_ms: self.config.slow_request_ms,
+            });
+        }
+
+        None
+    }
+
+    /// Emit a highlighted log line and fire the webhook if the transaction
+    /// crosses a threshold.
+    pub fn notify(&self, tx: &CapturedTransaction, logs: &LogBuffer) {
+        let Some(reason) = self.check(tx) else {
+            return;
+        };
+
+        let line = format_alert(tx, &reason);
+        tracing::warn!("{}", line);
+        logs.push_line(line);
+
+        if let Some(url) = &self.config.webhook_url {
+            // Webhooks are fire-and-forget; only possible inside a runtime
+            if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                let request = self.client.post(url).json(&alert_payload(tx, &reason));
+                handle.spawn(async move {
+                    if let Err(e) = request.send().await {
+                        tracing::debug!("Alert webhook failed: {}", e);
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Format the highlighted alert line for a transaction.
+pub fn format_alert(tx: &CapturedTransaction, reason: &AlertReason) -> String {
+    format!("⚠ ALERT {}: {}", reason, format_transaction(tx, &LogVerbosity::Minimal))
+}
+
+/// Build the JSON body posted to the alert webhook.
+pub fn alert_payload(tx: &CapturedTransaction, reason: &AlertReason) -> serde_json::Value {
+    serde_json::json!({
+        "event": reason.event(),
+        "reason": reason.to_string(),
+        "summary": format_transaction(tx, &LogVerbosity::Minimal),
+        "transaction": {
+            "id": tx.id,
+            "timestamp": tx.timestamp.to_rfc3339(),
+            "model": extract_model(&tx.request.body),
+            "path": extract_path(&tx.request.url),
+            "status": tx.response.as_ref().map(|r| r.status),
+            "total_ms": tx.timing.total_ms,
+            "ttfb_ms": tx.timing.ttfb_ms,
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::http::create_client;
     use crate::inspector::CapturedResponse;
     use chrono::Utc;
 
@@ -215,7 +583,12 @@ mod tests {
                 tokens_per_sec: Some(45.2),
                 prompt_tokens: Some(50),
                 completion_tokens: Some(70),
+                chunks: None,
             },
+            ab_arm: None,
+            virtual_key: None,
+            routed_model: None,
+            annotations: Default::default(),
             start_time: None,
         }
     }
@@ -343,6 +716,7 @@ mod tests {
             tokens_per_sec: Some(30.0),
             prompt_tokens: Some(100),
             completion_tokens: Some(50),
+            chunks: None,
         };
 
         let output = format_response_end(200, &timing, &LogVerbosity::Compact);
@@ -351,4 +725,130 @@ mod tests {
         assert!(output.contains("200"));
         assert!(output.contains("1.5s") || output.contains("1500"));
     }
+
+    #[test]
+    fn log_buffer_tail_returns_most_recent_lines() {
+        let buffer = LogBuffer::new(10);
+        for i in 0..5 {
+            buffer.push_line(format!("line {}", i));
+        }
+
+        assert_eq!(buffer.tail(2), vec!["line 3", "line 4"]);
+        assert_eq!(buffer.tail(100).len(), 5);
+    }
+
+    #[test]
+    fn log_buffer_evicts_oldest_lines_when_full() {
+        let buffer = LogBuffer::new(3);
+        for i in 0..5 {
+            buffer.push_line(format!("line {}", i));
+        }
+
+        assert_eq!(buffer.tail(10), vec!["line 2", "line 3", "line 4"]);
+    }
+
+    #[test]
+    fn log_buffer_splits_transactions_into_lines() {
+        let buffer = LogBuffer::new(10).with_verbosity(LogVerbosity::Compact);
+        buffer.push_transaction(&sample_transaction());
+
+        let lines = buffer.tail(10);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("POST"));
+    }
+
+    #[test]
+    fn log_buffer_verbosity_changes_are_shared_by_clones() {
+        let buffer = LogBuffer::new(10);
+        let handle = buffer.clone();
+
+        handle.set_verbosity(LogVerbosity::Verbose);
+
+        assert_eq!(buffer.verbosity(), LogVerbosity::Verbose);
+    }
+
+    #[test]
+    fn syslog_severity_follows_status() {
+        assert_eq!(SyslogSeverity::from_status(200), SyslogSeverity::Info);
+        assert_eq!(SyslogSeverity::from_status(404), SyslogSeverity::Warning);
+        assert_eq!(SyslogSeverity::from_status(502), SyslogSeverity::Error);
+        assert_eq!(SyslogSeverity::from_status(0), SyslogSeverity::Error);
+    }
+
+    #[test]
+    fn syslog_message_has_priority_and_ident() {
+        let output = format_syslog_message("multiai", SyslogSeverity::Info, "POST /v1/chat 200");
+
+        // user facility (1) * 8 + info (6) = 14
+        assert!(output.starts_with("<14>multiai["));
+        assert!(output.ends_with("]: POST /v1/chat 200"));
+    }
+
+    #[test]
+    fn batched_event_log_lines_share_one_event() {
+        let batch = vec![
+            (SyslogSeverity::Info, "POST /v1/chat 200".to_string()),
+            (SyslogSeverity::Error, "POST /v1/chat 502".to_string()),
+            (SyslogSeverity::Warning, "POST /v1/chat 404".to_string()),
+        ];
+        let (severity, description) = batch_event(&batch);
+        assert_eq!(severity, SyslogSeverity::Error);
+        assert_eq!(description, "POST /v1/chat 200\nPOST /v1/chat 502\nPOST /v1/chat 404");
+
+        let long = vec![(SyslogSeverity::Info, "x".repeat(MAX_EVENT_DESCRIPTION / 2)); 3];
+        let (_, description) = batch_event(&long);
+        assert!(description.len() <= MAX_EVENT_DESCRIPTION + 20);
+        assert!(description.ends_with("... and 2 more"));
+    }
+
+    #[test]
+    fn alert_hook_flags_error_status() {
+        let hook = AlertHook::new(AlertConfig::default(), create_client());
+        let mut tx = sample_transaction();
+        tx.response.as_mut().unwrap().status = 502;
+
+        assert_eq!(hook.check(&tx), Some(AlertReason::Error { status: 502 }));
+    }
+
+    #[test]
+    fn alert_hook_flags_slow_requests() {
+        let hook = AlertHook::new(AlertConfig::default(), create_client());
+        let mut tx = sample_transaction();
+        tx.timing.total_ms = 25_000;
+
+        assert_eq!(
+            hook.check(&tx),
+            Some(AlertReason::Slow { total_ms: 25_000, threshold_ms: 20_000 })
+        );
+        assert!(format_alert(&tx, &hook.check(&tx).unwrap()).contains("25.0s > 20.0s"));
+    }
+
+    #[test]
+    fn alert_hook_ignores_healthy_requests_and_disabled_checks() {
+        let tx = sample_transaction();
+        assert_eq!(AlertHook::new(AlertConfig::default(), create_client()).check(&tx), None);
+
+        let disabled = AlertHook::new(
+            AlertConfig {
+                slow_request_ms: 0,
+                error_status: 0,
+                webhook_url: None,
+            },
+            create_client(),
+        );
+        let mut failing = sample_transaction();
+        failing.response.as_mut().unwrap().status = 500;
+        failing.timing.total_ms = 60_000;
+        assert_eq!(disabled.check(&failing), None);
+    }
+
+    #[test]
+    fn alert_payload_includes_summary() {
+        let tx = sample_transaction();
+        let payload = alert_payload(&tx, &AlertReason::Error { status: 200 });
+
+        assert_eq!(payload["event"], "error");
+        assert_eq!(payload["transaction"]["model"], "grok-code-fast-1");
+        assert_eq!(payload["transaction"]["total_ms"], 1200);
+    }
 }
@@ -1,8 +1,21 @@
 //! MultiAI CLI - Compare multiple free AI models side by side.
 
-use clap::{Parser, Subcommand};
-use multiai::api::{create_router_with_state, AppState};
+use clap::{Parser, Subcommand, ValueEnum};
+use multiai::api::{create_router_with_state, serve_with_grace, AppState};
+use multiai::cli::ask::AskOptions;
+use multiai::cli::chat::ChatOptions;
+use multiai::cli::daemon;
+use multiai::cli::export::ExportOptions;
+use multiai::cli::import::{ImportFormat, ImportOptions};
+use multiai::cli::inspect::{InspectOptions, TransactionFilter};
+use multiai::cli::keys::KeysAction;
+use multiai::cli::logs::LogsOptions;
+use multiai::cli::models::ModelsOptions;
+use multiai::cli::service::ServiceAction;
 use multiai::config::{Config, LogVerbosity};
+use multiai::scanner::FreeModelScanner;
+use multiai::scheduler::{self, JobContext};
+use multiai::{evals, events, ollama, telemetry};
 use std::net::SocketAddr;
 use tokio::signal;
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
@@ -24,6 +37,10 @@ struct Cli {
 enum Commands {
     /// Start the gateway server (headless mode)
     Serve {
+        /// Address to listen on (default 127.0.0.1; 0.0.0.0 for the LAN)
+        #[arg(long)]
+        host: Option<std::net::IpAddr>,
+
         /// Port to listen on
         #[arg(short, long)]
         port: Option<u16>,
@@ -35,6 +52,24 @@ enum Commands {
         /// Config file path
         #[arg(short, long)]
         config: Option<std::path::PathBuf>,
+
+        /// Run in the background (see `multiai stop` and `multiai status`)
+        #[arg(long)]
+        daemon: bool,
+    },
+
+    /// Stop a gateway started with `serve --daemon`
+    Stop {
+        /// Port the gateway listens on
+        #[arg(short, long)]
+        port: Option<u16>,
+    },
+
+    /// Check whether a gateway is running
+    Status {
+        /// Port the gateway listens on
+        #[arg(short, long)]
+        port: Option<u16>,
     },
 
     /// Launch the menu bar app (requires Tauri build)
@@ -45,7 +80,228 @@ enum Commands {
         /// Show config file path
         #[arg(long)]
         path: bool,
+
+        #[command(subcommand)]
+        action: Option<ConfigCommand>,
+    },
+
+    /// List discovered free models
+    Models {
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+
+        /// Scan providers directly instead of using a running gateway's cache
+        #[arg(long)]
+        refresh: bool,
+
+        /// Probe each model with a one-token request to report health
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Ask a one-off question (piped stdin is appended to the prompt)
+    Ask {
+        /// The prompt
+        prompt: Vec<String>,
+
+        /// Model to ask
+        #[arg(short, long, default_value = "auto")]
+        model: String,
+    },
+
+    /// Chat with a model in the terminal (history is shared with the app)
+    Chat {
+        /// Model to start with
+        #[arg(short, long, default_value = "auto")]
+        model: String,
+
+        /// Resume an existing chat by ID
+        #[arg(long)]
+        resume: Option<String>,
+    },
+
+    /// Export a chat to md, pdf, docx or json
+    Export {
+        /// Chat ID to export
+        #[arg(required_unless_present = "latest")]
+        chat_id: Option<String>,
+
+        /// Export the most recently updated chat
+        #[arg(long, conflicts_with = "chat_id")]
+        latest: bool,
+
+        /// Output format (default: from the output extension, else md)
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// Output file, or - for stdout
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// Show traffic captured by a running gateway
+    Inspect {
+        /// Follow new transactions as they complete
+        #[arg(long)]
+        tail: bool,
+
+        /// Only show requests for models matching this string
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Only show failed requests
+        #[arg(long)]
+        errors_only: bool,
+
+        /// Log verbosity level
+        #[arg(short, long, value_enum, default_value = "minimal")]
+        log_level: LogLevel,
+    },
+
+    /// Browse the on-disk traffic logs
+    Logs {
+        /// List log files instead of printing requests
+        #[arg(long)]
+        list: bool,
+
+        /// Only include logs from this date on (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<chrono::NaiveDate>,
+
+        /// Only include logs up to this date (YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<chrono::NaiveDate>,
+
+        /// Only show requests for models matching this string
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Only show failed requests
+        #[arg(long)]
+        errors_only: bool,
+
+        /// Log verbosity level
+        #[arg(short, long, value_enum, default_value = "minimal")]
+        log_level: LogLevel,
+
+        /// Convert a HAR file to Markdown for sharing
+        #[arg(long, value_name = "FILE")]
+        har_to_md: Option<std::path::PathBuf>,
+    },
+
+    /// Live dashboard of a running gateway's traffic
+    Top,
+
+    /// Install the gateway as a per-user service that starts at login
+    Service {
+        #[command(subcommand)]
+        action: ServiceCommand,
+    },
+
+    /// Manage provider API keys
+    Keys {
+        #[command(subcommand)]
+        action: KeysCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum ServiceCommand {
+    /// Register the service (systemd, launchd or Task Scheduler)
+    Install {
+        /// Port to listen on (default: configured port)
+        #[arg(short, long)]
+        port: Option<u16>,
+
+        /// Config file the service should use
+        #[arg(short, long)]
+        config: Option<std::path::PathBuf>,
+
+        /// Print the service definition instead of installing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Stop and remove the service
+    Uninstall,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Import providers, keys and model aliases from another gateway's config
+    Import {
+        /// Format of the file
+        #[arg(long, value_enum)]
+        from: ImportFrom,
+
+        /// Config file to import
+        file: std::path::PathBuf,
+
+        /// Show what would be imported without saving
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ImportFrom {
+    /// LiteLLM proxy config.yaml
+    Litellm,
+}
+
+impl From<ImportFrom> for ImportFormat {
+    fn from(from: ImportFrom) -> Self {
+        match from {
+            ImportFrom::Litellm => ImportFormat::LiteLlm,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum KeysCommand {
+    /// Save a key to the config file (prompts when KEY is omitted)
+    Set {
+        /// Provider name (openrouter, opencode-zen)
+        provider: String,
+
+        /// API key; omit to read it from stdin
+        key: Option<String>,
+
+        /// Remove the stored key instead
+        #[arg(long, conflicts_with = "key")]
+        clear: bool,
+    },
+
+    /// Show configured keys (masked)
+    Show {
+        /// Provider name (default: all)
+        provider: Option<String>,
     },
+
+    /// Check a key against the provider
+    Test {
+        /// Provider name (openrouter, opencode-zen)
+        provider: String,
+    },
+}
+
+impl From<KeysCommand> for KeysAction {
+    fn from(command: KeysCommand) -> Self {
+        match command {
+            KeysCommand::Set {
+                provider,
+                key,
+                clear,
+            } => KeysAction::Set {
+                provider,
+                key,
+                clear,
+            },
+            KeysCommand::Show { provider } => KeysAction::Show { provider },
+            KeysCommand::Test { provider } => KeysAction::Test { provider },
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
@@ -65,29 +321,190 @@ impl From<LogLevel> for LogVerbosity {
     }
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
-    // MCP mode takes precedence over subcommands
+    // MCP mode takes precedence over subcommands and runs its own runtime
     if cli.mcp {
         return multiai::mcp::run_mcp();
     }
 
+    tokio::runtime::Runtime::new()?.block_on(run_command(cli))
+}
+
+async fn run_command(cli: Cli) -> anyhow::Result<()> {
     match cli.command {
-        Some(Commands::Serve { port, log_level, config }) => {
-            run_server(port, log_level, config).await?;
+        Some(Commands::Serve {
+            host,
+            port,
+            log_level,
+            config,
+            daemon: true,
+        }) => {
+            let gateway_port = match &config {
+                Some(path) => Config::load_from(path.clone())?.gateway.port,
+                None => Config::load_with_env().gateway.port,
+            };
+            let mut args = vec![
+                "--log-level".to_string(),
+                log_level
+                    .to_possible_value()
+                    .map(|v| v.get_name().to_string())
+                    .unwrap_or_default(),
+            ];
+            if let Some(host) = host {
+                args.extend(["--host".to_string(), host.to_string()]);
+            }
+            if let Some(port) = port {
+                args.extend(["--port".to_string(), port.to_string()]);
+            }
+            if let Some(path) = config {
+                args.extend(["--config".to_string(), path.display().to_string()]);
+            }
+            daemon::start(port.unwrap_or(gateway_port), args).await?;
+        }
+        Some(Commands::Serve {
+            host,
+            port,
+            log_level,
+            config,
+            daemon: false,
+        }) => {
+            run_server(host, port, log_level, config).await?;
+        }
+        Some(Commands::Stop { port }) => {
+            let port = port.unwrap_or(Config::load_with_env().gateway.port);
+            daemon::stop(port).await?;
+        }
+        Some(Commands::Status { port }) => {
+            let port = port.unwrap_or(Config::load_with_env().gateway.port);
+            if !daemon::status(port).await? {
+                std::process::exit(1);
+            }
         }
         Some(Commands::App) => {
             eprintln!("Menu bar app requires Tauri build. Use 'cargo tauri dev' instead.");
             std::process::exit(1);
         }
-        Some(Commands::Config { path }) => {
-            show_config(path)?;
+        Some(Commands::Config { path, action }) => match action {
+            Some(ConfigCommand::Import {
+                from,
+                file,
+                dry_run,
+            }) => {
+                multiai::cli::import::run(ImportOptions {
+                    format: from.into(),
+                    file,
+                    dry_run,
+                })?;
+            }
+            None => show_config(path)?,
+        },
+        Some(Commands::Models { json, refresh, check }) => {
+            let config = Config::load_with_env();
+            multiai::cli::models::run(ModelsOptions {
+                json,
+                refresh,
+                check,
+                port: config.gateway.port,
+            })
+            .await?;
+        }
+        Some(Commands::Ask { prompt, model }) => {
+            let config = Config::load_with_env();
+            multiai::cli::ask::run(AskOptions {
+                prompt: prompt.join(" "),
+                model,
+                port: config.gateway.port,
+            })
+            .await?;
+        }
+        Some(Commands::Chat { model, resume }) => {
+            let config = Config::load_with_env();
+            multiai::cli::chat::run(ChatOptions {
+                model,
+                chat_id: resume,
+                port: config.gateway.port,
+            })
+            .await?;
+        }
+        Some(Commands::Export {
+            chat_id,
+            latest,
+            format,
+            output,
+        }) => {
+            multiai::cli::export::run(ExportOptions {
+                chat_id,
+                latest,
+                format,
+                output,
+            })?;
+        }
+        Some(Commands::Inspect {
+            tail,
+            model,
+            errors_only,
+            log_level,
+        }) => {
+            let config = Config::load_with_env();
+            multiai::cli::inspect::run(InspectOptions {
+                tail,
+                filter: TransactionFilter { model, errors_only },
+                verbosity: log_level.into(),
+                port: config.gateway.port,
+            })
+            .await?;
+        }
+        Some(Commands::Logs {
+            list,
+            since,
+            until,
+            model,
+            errors_only,
+            log_level,
+            har_to_md,
+        }) => {
+            let config = Config::load_with_env();
+            multiai::cli::logs::run(LogsOptions {
+                folder: config.logging.folder.join("traffic"),
+                list,
+                since,
+                until,
+                filter: TransactionFilter { model, errors_only },
+                verbosity: log_level.into(),
+                har_to_md,
+            })?;
+        }
+        Some(Commands::Top) => {
+            let config = Config::load_with_env();
+            multiai::cli::top::run(config.gateway.port).await?;
+        }
+        Some(Commands::Service { action }) => {
+            let action = match action {
+                ServiceCommand::Install {
+                    port,
+                    config,
+                    dry_run,
+                } => {
+                    let config_path = config.unwrap_or_else(Config::default_path);
+                    let gateway_port = Config::load_from(config_path.clone())?.gateway.port;
+                    ServiceAction::Install {
+                        port: port.unwrap_or(gateway_port),
+                        config_path,
+                        dry_run,
+                    }
+                }
+                ServiceCommand::Uninstall => ServiceAction::Uninstall,
+            };
+            multiai::cli::service::run(action)?;
+        }
+        Some(Commands::Keys { action }) => {
+            multiai::cli::keys::run(action.into()).await?;
         }
         None => {
             // Default: run server
-            run_server(None, LogLevel::Compact, None).await?;
+            run_server(None, None, LogLevel::Compact, None).await?;
         }
     }
 
@@ -95,6 +512,7 @@ async fn main() -> anyhow::Result<()> {
 }
 
 async fn run_server(
+    host_override: Option<std::net::IpAddr>,
     port_override: Option<u16>,
     log_level: LogLevel,
     config_path: Option<std::path::PathBuf>,
@@ -110,14 +528,119 @@ async fn run_server(
         Some(path) => Config::load_from(path)?,
         None => Config::load()?,
     };
-    let config = config.with_env_overrides();
+    let mut config = config.with_env_overrides();
+    if config.lmstudio.url.is_none() {
+        let url = FreeModelScanner::DEFAULT_LMSTUDIO_URL;
+        if FreeModelScanner::detect_lmstudio(url).await {
+            tracing::info!("Detected local LM Studio server at {}", url);
+            config.lmstudio.url = Some(url.to_string());
+        }
+    }
+    if config.llamacpp.url.is_none() {
+        let url = FreeModelScanner::DEFAULT_LLAMACPP_URL;
+        if FreeModelScanner::detect_llamacpp(url).await {
+            tracing::info!("Detected local llama.cpp server at {}", url);
+            config.llamacpp.url = Some(url.to_string());
+        }
+    }
 
-    // Determine port
+    // Determine address
+    let host = host_override.unwrap_or(config.gateway.host);
     let port = port_override.unwrap_or(config.gateway.port);
-    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let addr = SocketAddr::new(host, port);
+    if !host.is_loopback() && config.gateway.api_key.is_none() {
+        tracing::warn!(
+            "Listening on {} without [gateway] api_key; anyone on the network can use this gateway",
+            host
+        );
+    }
 
     // Create app state
-    let state = AppState::default();
+    let state = AppState::from_config(&config)?;
+
+    // Scan for models up front and keep the list fresh in the background
+    state.spawn_model_refresh(config.gateway.model_refresh_secs);
+
+    // Publish spending and source events, and page the alert webhook
+    tokio::spawn(events::monitor(
+        state.events.clone(),
+        state.scanner.clone(),
+        state.spending.clone(),
+        state.http.clone(),
+        config.alert_webhook_url(),
+        config.scanner.webhook_url.clone(),
+    ));
+
+    // Anonymous usage reports, only when explicitly enabled
+    if let (Some(counters), Some(endpoint)) =
+        (state.telemetry.clone(), config.telemetry.active_endpoint())
+    {
+        tokio::spawn(telemetry::run(
+            counters,
+            state.scanner.clone(),
+            state.http.clone(),
+            endpoint.to_string(),
+            std::time::Duration::from_secs(config.telemetry.interval_secs),
+        ));
+    }
+
+    // Load local models now rather than on their first request
+    if let Some(url) = config.ollama.url.clone().filter(|_| !config.ollama.preload.is_empty()) {
+        tokio::spawn(ollama::warm_up(
+            state.http.clone(),
+            url,
+            config.ollama.preload.clone(),
+            config.ollama.keep_alive.clone(),
+        ));
+    }
+
+    // Scheduled eval dataset runs, when configured
+    if config.evals.schedule_hours.is_some() {
+        if let Some(store) = state.evals.get().cloned() {
+            let comparator = state.comparator();
+            tokio::spawn(evals::run_schedule(store, comparator, config.evals.clone()));
+        }
+    }
+
+    // Recurring jobs from [[scheduler.jobs]]; their status shows in /health
+    state.scheduler.start(
+        &config.scheduler.jobs,
+        JobContext {
+            scanner: state.scanner.clone(),
+            http: state.http.clone(),
+            chat: state.chat.clone(),
+            traffic_log: state.traffic_log.clone(),
+            retention_days: config.logging.retention_days,
+            backup_folder: scheduler::backup_folder(&config),
+            ollama_url: config.ollama.url.clone(),
+            evals: state.evals.clone(),
+            evals_config: config.evals.clone(),
+            leaderboard: state.leaderboard.clone(),
+            config: state.config.clone(),
+        },
+    );
+
+    // gRPC mirror of the chat, models and inspect APIs
+    if let Some(grpc_port) = config.gateway.grpc_port {
+        #[cfg(feature = "grpc")]
+        {
+            let grpc_addr = SocketAddr::new(host, grpc_port);
+            let grpc_state = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = multiai::api::grpc::serve(grpc_state, grpc_addr).await {
+                    tracing::error!("gRPC server failed: {}", e);
+                }
+            });
+        }
+        #[cfg(not(feature = "grpc"))]
+        tracing::warn!(
+            "grpc_port {} is set but this build lacks the grpc feature; rebuild with --features grpc",
+            grpc_port
+        );
+    }
+
+    let streams = state.streams.clone();
+    let logs = state.clone();
 
     // Build router
     let app = create_router_with_state(state);
@@ -150,9 +673,10 @@ async fn run_server(
     let listener = tokio::net::TcpListener::bind(addr).await?;
     tracing::info!("Gateway listening on {}", addr);
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    let grace = std::time::Duration::from_secs(config.gateway.shutdown_grace_secs);
+    serve_with_grace(listener, app, shutdown_signal(), grace, streams).await?;
+    // Streams cut at shutdown have recorded their transactions by now
+    logs.flush_logs();
 
     println!("\nGateway stopped.");
     Ok(())
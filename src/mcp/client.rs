@@ -0,0 +1,573 @@
+//! MCP client bridge for chat tool-calling.
+//!
+//! Connects to external MCP servers configured under `[[mcp.servers]]`,
+//! advertises their tools to models as OpenAI function tools, executes the
+//! model's tool calls and feeds the results back until the model answers.
+//!
+//! Each server has its own lock, so a slow tool only holds up calls to its
+//! own server. Requests time out after the server's `timeout_secs`, and a
+//! server that timed out, broke its pipe or exited is restarted on next use.
+
+use crate::config::McpServerEntry;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::process::Stdio;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+/// Maximum model/tool round trips before giving up on a completion.
+pub const MAX_TOOL_STEPS: usize = 8;
+
+/// Separator between server and tool name in advertised function names.
+const TOOL_NAME_SEPARATOR: &str = "__";
+
+/// How long to wait before starting a server that failed to start again.
+const RESTART_DELAY: Duration = Duration::from_secs(30);
+
+/// A tool offered by an external MCP server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteTool {
+    pub name: String,
+    pub description: String,
+    pub input_schema: Value,
+}
+
+/// A connection to one external MCP server over stdio.
+#[derive(Debug)]
+pub struct McpClient {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    next_id: u64,
+    timeout: Duration,
+    /// Set once a request timed out or the pipe failed, leaving the
+    /// connection out of step with the server.
+    broken: bool,
+    tools: Vec<RemoteTool>,
+}
+
+impl McpClient {
+    /// Spawn the server, run the initialize handshake and fetch its tools.
+    pub async fn connect(entry: &McpServerEntry) -> Result<Self, String> {
+        let mut child = Command::new(&entry.command)
+            .args(&entry.args)
+            .envs(&entry.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("{}: failed to start {}: {}", entry.name, entry.command, e))?;
+
+        let stdin = child.stdin.take().ok_or("MCP server stdin unavailable")?;
+        let stdout = child.stdout.take().ok_or("MCP server stdout unavailable")?;
+
+        let mut client = Self {
+            name: entry.name.clone(),
+            child,
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+            next_id: 1,
+            timeout: Duration::from_secs(entry.timeout_secs),
+            broken: false,
+            tools: Vec::new(),
+        };
+
+        client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": super::PROTOCOL_VERSIONS[0],
+                    "capabilities": {},
+                    "clientInfo": { "name": "multiai", "version": env!("CARGO_PKG_VERSION") }
+                }),
+            )
+            .await?;
+        client.notify("notifications/initialized").await?;
+
+        let listed = client.request("tools/list", json!({})).await?;
+        client.tools = parse_tools(&listed);
+        Ok(client)
+    }
+
+    /// Server name from the config.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Tools offered by the server.
+    pub fn tools(&self) -> &[RemoteTool] {
+        &self.tools
+    }
+
+    /// Whether the server is still running and in step with the client.
+    pub fn is_usable(&mut self) -> bool {
+        !self.broken && matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// Call a tool and return its text output.
+    pub async fn call_tool(&mut self, tool: &str, arguments: Value) -> Result<String, String> {
+        let result = self
+            .request("tools/call", json!({ "name": tool, "arguments": arguments }))
+            .await?;
+        let text = tool_result_text(&result);
+        if result["isError"].as_bool().unwrap_or(false) {
+            return Err(text);
+        }
+        Ok(text)
+    }
+
+    /// Send a request and wait for the response with the matching id, up
+    /// to the server's timeout.
+    async fn request(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let timeout = self.timeout;
+        let result = tokio::time::timeout(timeout, async {
+            self.send(message).await?;
+            self.response(id, method).await
+        })
+        .await;
+        match result {
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(RequestError::Server(e))) => Err(e),
+            Ok(Err(RequestError::Transport(e))) => {
+                self.broken = true;
+                Err(e)
+            }
+            Err(_) => {
+                self.broken = true;
+                Err(format!("{}: {} timed out after {:?}", self.name, method, timeout))
+            }
+        }
+    }
+
+    /// Read until the response to request `id`.
+    async fn response(&mut self, id: u64, method: &str) -> Result<Value, RequestError> {
+        loop {
+            let line = self
+                .stdout
+                .next_line()
+                .await
+                .map_err(|e| format!("{}: read failed: {}", self.name, e))?
+                .ok_or_else(|| format!("{}: server closed the connection", self.name))?;
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            // Skip notifications and responses to other requests
+            if message["id"].as_u64() != Some(id) {
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                let detail = error["message"].as_str().unwrap_or("unknown error");
+                let error = format!("{}: {} failed: {}", self.name, method, detail);
+                return Err(RequestError::Server(error));
+            }
+            return Ok(message["result"].clone());
+        }
+    }
+
+    async fn notify(&mut self, method: &str) -> Result<(), String> {
+        let sent = self.send(json!({ "jsonrpc": "2.0", "method": method })).await;
+        sent.map_err(|e| {
+            self.broken = true;
+            e.to_string()
+        })
+    }
+
+    async fn send(&mut self, message: Value) -> Result<(), RequestError> {
+        let line = format!("{}\n", message);
+        self.stdin
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| format!("{}: write failed: {}", self.name, e))?;
+        self.stdin
+            .flush()
+            .await
+            .map_err(|e| format!("{}: write failed: {}", self.name, e))?;
+        Ok(())
+    }
+}
+
+/// Why a request to an MCP server failed.
+enum RequestError {
+    /// The server answered with an error; the connection is fine.
+    Server(String),
+    /// Reading or writing the pipe failed.
+    Transport(String),
+}
+
+impl From<String> for RequestError {
+    fn from(error: String) -> Self {
+        Self::Transport(error)
+    }
+}
+
+impl std::fmt::Display for RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Server(e) | Self::Transport(e) => f.write_str(e),
+        }
+    }
+}
+
+/// Executes tool calls requested by a model.
+trait ToolExecutor {
+    async fn execute(&self, function: &str, arguments: Value) -> Result<String, String>;
+}
+
+/// Bridges configured MCP servers into chat completions.
+///
+/// Servers are started lazily on the first completion that needs them and
+/// restarted when they stop responding.
+#[derive(Debug)]
+pub struct ToolBridge {
+    servers: Vec<BridgedServer>,
+}
+
+/// One configured server and its connection.
+#[derive(Debug)]
+struct BridgedServer {
+    entry: McpServerEntry,
+    connection: Mutex<Connection>,
+    /// Tools from the latest connection, readable while a call holds it.
+    tools: RwLock<Vec<RemoteTool>>,
+}
+
+#[derive(Debug, Default)]
+struct Connection {
+    client: Option<McpClient>,
+    /// When the server last failed to start.
+    failed_at: Option<Instant>,
+}
+
+impl BridgedServer {
+    /// The running client, starting or restarting the server as needed.
+    /// None while the server can't be started.
+    async fn client<'a>(&self, connection: &'a mut Connection) -> Option<&'a mut McpClient> {
+        if connection.client.as_mut().is_some_and(|c| !c.is_usable()) {
+            tracing::warn!("MCP server {} stopped responding, restarting it", self.entry.name);
+            connection.client = None;
+        }
+        let waiting = connection.failed_at.is_some_and(|at| at.elapsed() < RESTART_DELAY);
+        if connection.client.is_none() && !waiting {
+            match McpClient::connect(&self.entry).await {
+                Ok(client) => {
+                    *self.tools.write().unwrap() = client.tools().to_vec();
+                    connection.client = Some(client);
+                    connection.failed_at = None;
+                }
+                Err(e) => {
+                    tracing::warn!("MCP server unavailable: {}", e);
+                    connection.failed_at = Some(Instant::now());
+                }
+            }
+        }
+        connection.client.as_mut()
+    }
+}
+
+impl ToolBridge {
+    pub fn new(servers: Vec<McpServerEntry>) -> Self {
+        let servers = servers
+            .into_iter()
+            .map(|entry| BridgedServer {
+                entry,
+                connection: Mutex::new(Connection::default()),
+                tools: RwLock::new(Vec::new()),
+            })
+            .collect();
+        Self { servers }
+    }
+
+    /// All bridged tools as OpenAI function tool definitions. Servers not
+    /// yet running are started; a server busy with a call offers the tools
+    /// it listed when it started.
+    pub async fn openai_tools(&self) -> Vec<Value> {
+        let mut tools = Vec::new();
+        for server in &self.servers {
+            if let Ok(mut connection) = server.connection.try_lock() {
+                server.client(&mut connection).await;
+            }
+            let listed = server.tools.read().unwrap();
+            tools.extend(listed.iter().map(|t| to_openai_tool(&server.entry.name, t)));
+        }
+        tools
+    }
+
+    /// Execute the bridged tool calls in a model's `reply` to `request`,
+    /// sending the results back with `send` until the model produces a
+    /// final answer. Returns the last upstream response body.
+    pub async fn resolve<F, Fut>(
+        &self,
+        request: Value,
+        reply: Value,
+        send: F,
+    ) -> Result<Value, String>
+    where
+        F: Fn(&Value) -> Fut,
+        Fut: Future<Output = Result<Value, String>>,
+    {
+        run_tool_loop(self, request, reply, send).await
+    }
+}
+
+impl ToolExecutor for ToolBridge {
+    async fn execute(&self, function: &str, arguments: Value) -> Result<String, String> {
+        let (server, tool) = split_tool_name(function)
+            .ok_or_else(|| format!("Unknown tool: {}", function))?;
+        let server = self
+            .servers
+            .iter()
+            .find(|s| sanitize_name(&s.entry.name) == server)
+            .ok_or_else(|| format!("Unknown tool: {}", function))?;
+        let mut connection = server.connection.lock().await;
+        let client = server
+            .client(&mut connection)
+            .await
+            .ok_or_else(|| format!("{}: MCP server unavailable", server.entry.name))?;
+        client.call_tool(tool, arguments).await
+    }
+}
+
+/// Resolve tool calls in a loop: answer each call in the reply, then send
+/// the conversation back for the next reply.
+async fn run_tool_loop<F, Fut>(
+    executor: &impl ToolExecutor,
+    mut request: Value,
+    mut body: Value,
+    send: F,
+) -> Result<Value, String>
+where
+    F: Fn(&Value) -> Fut,
+    Fut: Future<Output = Result<Value, String>>,
+{
+    for _ in 0..MAX_TOOL_STEPS {
+        let message = &body["choices"][0]["message"];
+        let calls = match message["tool_calls"].as_array() {
+            Some(calls) if !calls.is_empty() => calls.clone(),
+            _ => return Ok(body),
+        };
+
+        // Echo the assistant turn, then answer each call
+        request["messages"]
+            .as_array_mut()
+            .ok_or("Request has no messages")?
+            .push(message.clone());
+        for call in &calls {
+            let function = call["function"]["name"].as_str().unwrap_or("");
+            let arguments = call["function"]["arguments"]
+                .as_str()
+                .and_then(|a| serde_json::from_str(a).ok())
+                .unwrap_or_else(|| json!({}));
+            let output = match executor.execute(function, arguments).await {
+                Ok(output) => output,
+                Err(e) => format!("Error: {}", e),
+            };
+            request["messages"].as_array_mut().unwrap().push(json!({
+                "role": "tool",
+                "tool_call_id": call["id"],
+                "content": output
+            }));
+        }
+        body = send(&request).await?;
+    }
+
+    Err(format!("Model did not finish within {} tool steps", MAX_TOOL_STEPS))
+}
+
+/// Parse a `tools/list` result.
+fn parse_tools(result: &Value) -> Vec<RemoteTool> {
+    result["tools"]
+        .as_array()
+        .map(|tools| {
+            tools
+                .iter()
+                .filter_map(|t| {
+                    Some(RemoteTool {
+                        name: t["name"].as_str()?.to_string(),
+                        description: t["description"].as_str().unwrap_or("").to_string(),
+                        input_schema: t
+                            .get("inputSchema")
+                            .cloned()
+                            .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Join the text blocks of a `tools/call` result.
+fn tool_result_text(result: &Value) -> String {
+    result["content"]
+        .as_array()
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter_map(|b| b["text"].as_str())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default()
+}
+
+/// Restrict a name to the characters OpenAI allows in function names.
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect()
+}
+
+/// Describe a remote tool as an OpenAI function tool named `<server>__<tool>`.
+fn to_openai_tool(server: &str, tool: &RemoteTool) -> Value {
+    json!({
+        "type": "function",
+        "function": {
+            "name": format!("{}{}{}", sanitize_name(server), TOOL_NAME_SEPARATOR, tool.name),
+            "description": tool.description,
+            "parameters": tool.input_schema
+        }
+    })
+}
+
+/// Split an advertised function name into server and tool.
+fn split_tool_name(function: &str) -> Option<(&str, &str)> {
+    function.split_once(TOOL_NAME_SEPARATOR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoTools;
+
+    impl ToolExecutor for EchoTools {
+        async fn execute(&self, function: &str, arguments: Value) -> Result<String, String> {
+            Ok(format!("{} called with {}", function, arguments))
+        }
+    }
+
+    #[test]
+    fn parses_listed_tools() {
+        let tools = parse_tools(&json!({
+            "tools": [
+                {"name": "search", "description": "Search the web", "inputSchema": {"type": "object"}},
+                {"name": "now"},
+                {"description": "nameless"}
+            ]
+        }));
+
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools[0].name, "search");
+        assert_eq!(tools[1].input_schema["type"], "object");
+    }
+
+    #[test]
+    fn advertises_tools_with_server_prefix() {
+        let tool = RemoteTool {
+            name: "search".to_string(),
+            description: "Search the web".to_string(),
+            input_schema: json!({"type": "object"}),
+        };
+
+        let openai = to_openai_tool("web tools", &tool);
+        assert_eq!(openai["function"]["name"], "web_tools__search");
+        assert_eq!(split_tool_name("web_tools__search"), Some(("web_tools", "search")));
+    }
+
+    #[test]
+    fn joins_text_content_blocks() {
+        let text = tool_result_text(&json!({
+            "content": [
+                {"type": "text", "text": "line one"},
+                {"type": "image", "data": "..."},
+                {"type": "text", "text": "line two"}
+            ]
+        }));
+        assert_eq!(text, "line one\nline two");
+    }
+
+    fn server(command: &str, args: &[&str], timeout_secs: u64) -> McpServerEntry {
+        McpServerEntry {
+            name: "echo".to_string(),
+            command: command.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            env: Default::default(),
+            timeout_secs,
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unresponsive_servers_time_out() {
+        let bridge = ToolBridge::new(vec![server("sleep", &["30"], 1)]);
+        let started = Instant::now();
+        assert!(bridge.openai_tools().await.is_empty());
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // Not retried until the restart delay has passed
+        let error = bridge.execute("echo__anything", json!({})).await.unwrap_err();
+        assert!(error.contains("unavailable"), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn exited_servers_are_restarted() {
+        // `cat` echoes each request back, which reads as an empty result
+        let bridge = ToolBridge::new(vec![server("cat", &[], 5)]);
+        assert_eq!(bridge.execute("echo__anything", json!({})).await, Ok(String::new()));
+
+        let first_pid = {
+            let mut connection = bridge.servers[0].connection.lock().await;
+            let client = connection.client.as_mut().unwrap();
+            let pid = client.child.id();
+            client.child.kill().await.unwrap();
+            pid
+        };
+        assert_eq!(bridge.execute("echo__anything", json!({})).await, Ok(String::new()));
+        let connection = bridge.servers[0].connection.lock().await;
+        assert_ne!(connection.client.as_ref().unwrap().child.id(), first_pid);
+    }
+
+    #[tokio::test]
+    async fn tool_loop_feeds_results_back_to_model() {
+        let request = json!({
+            "model": "test",
+            "messages": [{"role": "user", "content": "What time is it?"}]
+        });
+        let reply = json!({"choices": [{"message": {
+            "role": "assistant",
+            "content": null,
+            "tool_calls": [{
+                "id": "call_1",
+                "type": "function",
+                "function": {"name": "clock__now", "arguments": "{\"tz\":\"UTC\"}"}
+            }]
+        }}]});
+        let sent = std::sync::Mutex::new(Vec::new());
+        let send = |request: &Value| {
+            sent.lock().unwrap().push(request.clone());
+            let answer = json!({"choices": [{"message": {
+                "role": "assistant",
+                "content": "It is noon."
+            }}]});
+            async { Ok(answer) }
+        };
+
+        let body = run_tool_loop(&EchoTools, request, reply, send).await.unwrap();
+
+        assert_eq!(body["choices"][0]["message"]["content"], "It is noon.");
+        let sent = sent.into_inner().unwrap();
+        assert_eq!(sent.len(), 1);
+        let messages = sent[0]["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[2]["tool_call_id"], "call_1");
+        assert_eq!(messages[2]["content"], "clock__now called with {\"tz\":\"UTC\"}");
+    }
+}
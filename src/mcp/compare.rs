@@ -1,13 +1,19 @@
 //! Model comparison logic for MCP.
 
-use super::judge::JudgePanel;
+use super::judge::{JudgePanel, JudgeScore};
+use super::progress::ProgressReporter;
+use crate::cloudflare;
 use crate::config::Config;
-use crate::http::{create_client_with_timeout, LONG_TIMEOUT};
-use crate::scanner::{FreeModel, FreeModelScanner, Source};
+use crate::gemini;
+use crate::http::{create_shared_client, LONG_TIMEOUT};
+use crate::leaderboard::Leaderboard;
+use crate::api::build_upstream_url;
+use crate::scanner::{FreeModel, FreeModelScanner, Source, SourceAuth, SourceRegistry};
 use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::time::{Duration, Instant};
 use tokio::time::timeout;
 
@@ -21,12 +27,114 @@ pub struct CompareParams {
     pub max_models: Option<usize>,
     #[serde(default = "default_include_ranking")]
     pub include_ranking: bool,
+    /// Judge model IDs to use instead of the configured panel.
+    #[serde(default)]
+    pub judges: Option<Vec<String>>,
+    /// Relative weight of each metric in the overall score.
+    #[serde(default)]
+    pub weights: Option<ScoreWeights>,
+    /// Metrics that count towards the overall score (default: all).
+    #[serde(default)]
+    pub metrics: Option<Vec<ScoreMetric>>,
+    /// What a good answer must contain, given to the judges with the rubric.
+    #[serde(default)]
+    pub criteria: Option<String>,
+    /// Models queried at once (default 4).
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    /// Seconds each model has to answer (default 30).
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Stop once this many models have answered, cancelling the rest.
+    #[serde(default)]
+    pub min_results: Option<usize>,
 }
 
 fn default_include_ranking() -> bool {
     true
 }
 
+impl CompareParams {
+    /// Resolve the effective, normalized scoring weights.
+    pub fn score_weights(&self) -> Result<ScoreWeights, String> {
+        let mut weights = self.weights.unwrap_or_default();
+        if let Some(metrics) = &self.metrics {
+            if metrics.is_empty() {
+                return Err("At least one scoring metric is required".to_string());
+            }
+            if !metrics.contains(&ScoreMetric::Speed) {
+                weights.speed = 0.0;
+            }
+            if !metrics.contains(&ScoreMetric::Quality) {
+                weights.quality = 0.0;
+            }
+            if !metrics.contains(&ScoreMetric::Efficiency) {
+                weights.efficiency = 0.0;
+            }
+        }
+        weights.normalized()
+    }
+}
+
+/// Whether a requested model name picks `model`; either may be a part of
+/// the other, so `llama-3.3` picks `meta-llama/llama-3.3-70b-instruct:free`.
+pub fn matches_requested(model: &FreeModel, requested: &str) -> bool {
+    model.id.contains(requested) || requested.contains(&model.id)
+}
+
+/// A metric that can contribute to the overall score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScoreMetric {
+    Speed,
+    Quality,
+    Efficiency,
+}
+
+/// Weights applied to each metric score when computing the overall score.
+/// Missing fields keep their default weight.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoreWeights {
+    pub speed: f64,
+    pub quality: f64,
+    pub efficiency: f64,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            speed: 0.25,
+            quality: 0.50,
+            efficiency: 0.25,
+        }
+    }
+}
+
+impl ScoreWeights {
+    /// Validate the weights and scale them to sum to 1.
+    pub fn normalized(self) -> Result<Self, String> {
+        let all = [self.speed, self.quality, self.efficiency];
+        if all.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err("Scoring weights must be finite and non-negative".to_string());
+        }
+        let total: f64 = all.iter().sum();
+        if total <= 0.0 {
+            return Err("At least one scoring weight must be positive".to_string());
+        }
+        Ok(Self {
+            speed: self.speed / total,
+            quality: self.quality / total,
+            efficiency: self.efficiency / total,
+        })
+    }
+
+    /// Weighted overall score for a response.
+    pub fn overall(&self, speed: f64, quality: f64, efficiency: f64) -> f64 {
+        (speed * self.speed) + (quality * self.quality) + (efficiency * self.efficiency)
+    }
+}
+
 /// Metrics for a single model response.
 #[derive(Debug, Clone, Serialize)]
 pub struct ResponseMetrics {
@@ -54,6 +162,35 @@ pub struct ModelResult {
     pub response: String,
     pub metrics: ResponseMetrics,
     pub scores: ResponseScores,
+    /// Individual judge verdicts behind the quality score.
+    pub judge_scores: Vec<JudgeScore>,
+}
+
+/// A model whose request failed during a comparison.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelFailure {
+    pub model: String,
+    pub error: String,
+    /// Upstream HTTP status, when the model answered with an error.
+    pub status: Option<u16>,
+}
+
+/// Error of models cancelled once `min_results` models answered.
+const CANCELLED_ERROR: &str = "Cancelled after enough models answered";
+
+impl ModelFailure {
+    /// A model that was cut off by `min_results` rather than failing.
+    pub fn cancelled(model: String) -> Self {
+        Self {
+            model,
+            error: CANCELLED_ERROR.to_string(),
+            status: None,
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.status.is_none() && self.error == CANCELLED_ERROR
+    }
 }
 
 /// Full comparison result.
@@ -62,37 +199,180 @@ pub struct CompareResult {
     pub prompt: String,
     pub compared_at: DateTime<Utc>,
     pub results: Vec<ModelResult>,
+    pub failures: Vec<ModelFailure>,
+    pub weights: ScoreWeights,
     pub ranking: Vec<String>,
     pub markdown_summary: String,
 }
 
+/// JSON Schema describing a serialized `CompareResult`.
+pub fn compare_result_schema() -> Value {
+    let number = json!({ "type": "number" });
+    let integer = json!({ "type": "integer" });
+    json!({
+        "type": "object",
+        "properties": {
+            "prompt": { "type": "string" },
+            "compared_at": { "type": "string", "format": "date-time" },
+            "results": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "model": { "type": "string" },
+                        "source": { "type": "string" },
+                        "response": { "type": "string" },
+                        "metrics": {
+                            "type": "object",
+                            "properties": {
+                                "ttft_ms": integer,
+                                "total_ms": integer,
+                                "input_tokens": integer,
+                                "output_tokens": integer,
+                                "tokens_per_sec": number
+                            },
+                            "required": ["ttft_ms", "total_ms", "input_tokens", "output_tokens", "tokens_per_sec"]
+                        },
+                        "scores": {
+                            "type": "object",
+                            "properties": {
+                                "speed": number,
+                                "quality": number,
+                                "efficiency": number,
+                                "overall": number
+                            },
+                            "required": ["speed", "quality", "efficiency", "overall"]
+                        },
+                        "judge_scores": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "judge": { "type": "string" },
+                                    "score": number,
+                                    "reason": { "type": "string" },
+                                    "criteria": {
+                                        "type": "object",
+                                        "additionalProperties": number
+                                    },
+                                    "verdict": { "type": "string" }
+                                },
+                                "required": ["judge", "score", "reason", "criteria", "verdict"]
+                            }
+                        }
+                    },
+                    "required": ["model", "source", "response", "metrics", "scores", "judge_scores"]
+                }
+            },
+            "failures": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "model": { "type": "string" },
+                        "error": { "type": "string" },
+                        "status": { "type": ["integer", "null"] }
+                    },
+                    "required": ["model", "error", "status"]
+                }
+            },
+            "weights": {
+                "type": "object",
+                "properties": {
+                    "speed": number,
+                    "quality": number,
+                    "efficiency": number
+                },
+                "required": ["speed", "quality", "efficiency"]
+            },
+            "ranking": { "type": "array", "items": { "type": "string" } },
+            "markdown_summary": { "type": "string" }
+        },
+        "required": ["prompt", "compared_at", "results", "failures", "weights", "ranking", "markdown_summary"]
+    })
+}
+
 /// Default Ollama URL to check for local models.
 const DEFAULT_OLLAMA_URL: &str = "http://127.0.0.1:11434";
 
+/// Models queried at once unless `concurrency` is given.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Time each model has to answer unless `timeout_secs` is given.
+const DEFAULT_MODEL_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Model comparator that handles parallel requests.
 pub struct ModelComparator {
     scanner: FreeModelScanner,
     client: Client,
+    config: Config,
+    judges: JudgePanel,
+    leaderboard: Option<Leaderboard>,
 }
 
 impl ModelComparator {
     /// Create a new comparator (auto-detects Ollama if available).
-    pub fn new() -> Self {
+    pub async fn new() -> Self {
+        let config = Config::load_with_env();
+        Self::from_parts(
+            Self::local_scanner(&config).await,
+            create_shared_client(),
+            &config,
+        )
+    }
+
+    /// A scanner with `config`'s keys and whichever local servers
+    /// (Ollama, LM Studio, llama.cpp) answer on their default ports.
+    pub async fn local_scanner(config: &Config) -> FreeModelScanner {
         // Check for Ollama using centralized detection
-        let scanner = if FreeModelScanner::detect_ollama_blocking(DEFAULT_OLLAMA_URL) {
-            FreeModelScanner::new().with_ollama_url(DEFAULT_OLLAMA_URL)
+        let scanner = FreeModelScanner::new().with_keys_from(config);
+        let scanner = if FreeModelScanner::detect_ollama(DEFAULT_OLLAMA_URL).await {
+            scanner.with_ollama_url(DEFAULT_OLLAMA_URL)
         } else {
-            FreeModelScanner::new()
+            scanner
         };
+        let lmstudio = FreeModelScanner::DEFAULT_LMSTUDIO_URL;
+        let scanner = if FreeModelScanner::detect_lmstudio(lmstudio).await {
+            scanner.with_lmstudio_url(lmstudio)
+        } else {
+            scanner
+        };
+        let llamacpp = FreeModelScanner::DEFAULT_LLAMACPP_URL;
+        if FreeModelScanner::detect_llamacpp(llamacpp).await {
+            scanner.with_llamacpp_url(llamacpp)
+        } else {
+            scanner
+        }
+    }
 
+    /// Create a comparator from an existing scanner and HTTP client, so it
+    /// shares their model cache and connection pool. The judge panel sends
+    /// its requests through the same client.
+    pub fn from_parts(scanner: FreeModelScanner, client: Client, config: &Config) -> Self {
         Self {
+            judges: JudgePanel::from_parts(config, client.clone()),
             scanner,
-            client: create_client_with_timeout(LONG_TIMEOUT),
+            client,
+            config: config.clone(),
+            leaderboard: None,
         }
     }
 
+    /// Record every finished comparison on the leaderboard.
+    pub fn with_leaderboard(mut self, leaderboard: Option<Leaderboard>) -> Self {
+        self.leaderboard = leaderboard;
+        self
+    }
+
     /// Compare models with the given prompt.
-    pub async fn compare(&self, params: CompareParams) -> Result<CompareResult, String> {
+    /// Reports progress as each model responds and as each response is judged.
+    pub async fn compare(
+        &self,
+        params: CompareParams,
+        progress: Option<&ProgressReporter>,
+    ) -> Result<CompareResult, String> {
+        let weights = params.score_weights()?;
+
         // Get available models
         let all_models = self.scanner.get_free_models(false).await;
         if all_models.is_empty() {
@@ -103,7 +383,7 @@ impl ModelComparator {
         let models: Vec<FreeModel> = match &params.models {
             Some(requested) => all_models
                 .into_iter()
-                .filter(|m| requested.iter().any(|r| m.id.contains(r) || r.contains(&m.id)))
+                .filter(|m| requested.iter().any(|r| matches_requested(m, r)))
                 .collect(),
             None => all_models,
         };
@@ -118,48 +398,116 @@ impl ModelComparator {
             return Err("No matching models found".to_string());
         }
 
-        // Send requests to all models in parallel
-        let config = Config::load_with_env();
-        let mut handles = Vec::new();
-
-        for model in models {
-            let client = self.client.clone();
+        // Query up to `concurrency` models at a time, each under its own timeout
+        let config = &self.config;
+        let concurrency = params.concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1);
+        let model_timeout = params
+            .timeout_secs
+            .map_or(DEFAULT_MODEL_TIMEOUT, Duration::from_secs);
+        let model_ids: Vec<String> = models.iter().map(|m| m.id.clone()).collect();
+        let model_count = models.len();
+        let queries = models.into_iter().map(|model| {
+            let api_key = config.get_model_api_key(&model);
+            let headers = config.providers.headers(&model.source);
+            let model_id = model.id.clone();
             let prompt = params.prompt.clone();
-            let api_key = config.get_api_key(&model.source);
+            let query = query_model(
+                self.client.clone(),
+                self.scanner.registry(),
+                model,
+                prompt,
+                api_key,
+                headers,
+                weights,
+            );
+            async move {
+                timeout(model_timeout, query).await.unwrap_or_else(|_| {
+                    Err(ModelFailure {
+                        model: model_id,
+                        error: format!("Timed out after {}s", model_timeout.as_secs()),
+                        status: None,
+                    })
+                })
+            }
+        });
 
-            handles.push(tokio::spawn(async move {
-                query_model(client, model, prompt, api_key).await
-            }));
+        // Judging adds one step per successful response
+        let mut judge_panel = None;
+        if params.include_ranking {
+            let mut panel = self.judges.clone();
+            if let Some(judges) = &params.judges {
+                panel = panel.with_judges(judges);
+            }
+            if let Some(criteria) = &params.criteria {
+                panel = panel.with_criteria(criteria);
+            }
+            if panel.is_available() {
+                judge_panel = Some(panel);
+            }
         }
+        let mut total_steps = if judge_panel.is_some() { model_count * 2 } else { model_count };
+        let mut completed = 0;
 
-        // Collect results
+        // Collect results in completion order
+        let mut pending = stream::iter(queries).buffer_unordered(concurrency);
         let mut results = Vec::new();
-        for handle in handles {
-            if let Ok(Ok(result)) = handle.await {
-                results.push(result);
+        let mut failures = Vec::new();
+        while let Some(outcome) = pending.next().await {
+            completed += 1;
+            match outcome {
+                Ok(result) => {
+                    if let Some(progress) = progress {
+                        progress.report(completed, total_steps, format!("{} responded", result.model));
+                    }
+                    results.push(result);
+                }
+                Err(failure) => {
+                    if let Some(progress) = progress {
+                        progress.report(completed, total_steps, format!("{} failed", failure.model));
+                    }
+                    failures.push(failure);
+                }
+            }
+            if params.min_results.is_some_and(|min| results.len() >= min) {
+                break;
             }
         }
+        // Dropping the stream cancels requests still in flight
+        drop(pending);
+        let answered = |id: &String| {
+            results.iter().any(|r: &ModelResult| &r.model == id)
+                || failures.iter().any(|f: &ModelFailure| &f.model == id)
+        };
+        let cancelled: Vec<String> = model_ids.into_iter().filter(|id| !answered(id)).collect();
+        failures.extend(cancelled.into_iter().map(ModelFailure::cancelled));
 
         if results.is_empty() {
-            return Err("All model requests failed".to_string());
+            let details: Vec<String> = failures.iter().map(describe_failure).collect();
+            return Err(format!("All model requests failed: {}", details.join("; ")));
         }
 
         // Run LLM judge panel for quality scoring if requested
-        if params.include_ranking {
-            let judge_panel = JudgePanel::new();
-            if judge_panel.is_available() {
-                // Evaluate each response with the judge panel
-                for result in &mut results {
-                    if let Ok((quality_score, _judge_scores)) = judge_panel
-                        .evaluate(&params.prompt, &result.response)
-                        .await
-                    {
-                        result.scores.quality = quality_score;
-                        // Recalculate overall score
-                        result.scores.overall = (result.scores.speed * 0.25)
-                            + (result.scores.quality * 0.50)
-                            + (result.scores.efficiency * 0.25);
-                    }
+        if let Some(judge_panel) = judge_panel {
+            // Failed models are not judged
+            total_steps = completed + results.len();
+            // Evaluate each response with the judge panel
+            for result in &mut results {
+                if let Ok((quality_score, judge_scores)) = judge_panel
+                    .evaluate(&params.prompt, &result.response)
+                    .await
+                {
+                    result.scores.quality = quality_score;
+                    result.judge_scores = judge_scores;
+                    // Recalculate overall score
+                    result.scores.overall = weights.overall(
+                        result.scores.speed,
+                        result.scores.quality,
+                        result.scores.efficiency,
+                    );
+                }
+                completed += 1;
+                if let Some(progress) = progress {
+                    progress.report(completed, total_steps, format!("Judged {}", result.model));
                 }
             }
         }
@@ -173,70 +521,101 @@ impl ModelComparator {
         });
 
         let ranking: Vec<String> = results.iter().map(|r| r.model.clone()).collect();
-        let markdown_summary = generate_markdown_summary(&params.prompt, &results);
+        let markdown_summary =
+            generate_markdown_summary(&params.prompt, &results, &failures, &weights);
 
-        Ok(CompareResult {
+        let result = CompareResult {
             prompt: params.prompt,
             compared_at: Utc::now(),
             results,
+            failures,
+            weights,
             ranking,
             markdown_summary,
-        })
-    }
-}
-
-impl Default for ModelComparator {
-    fn default() -> Self {
-        Self::new()
+        };
+        if let Some(leaderboard) = &self.leaderboard {
+            if let Err(e) = leaderboard.record(&result) {
+                tracing::warn!("Failed to record comparison on the leaderboard: {}", e);
+            }
+        }
+        Ok(result)
     }
 }
 
 /// Query a single model and collect metrics.
 async fn query_model(
     client: Client,
+    sources: &SourceRegistry,
     model: FreeModel,
     prompt: String,
     api_key: Option<String>,
-) -> Result<ModelResult, String> {
+    headers: Vec<(&'static str, String)>,
+    weights: ScoreWeights,
+) -> Result<ModelResult, ModelFailure> {
     let start = Instant::now();
+    let model_id = model.id.clone();
+    let fail = |error: String, status: Option<u16>| ModelFailure {
+        model: model_id.clone(),
+        error,
+        status,
+    };
 
-    let upstream_url = if model.source == Source::Ollama {
-        format!("{}/v1/chat/completions", model.endpoint)
-    } else {
-        format!("{}/chat/completions", model.endpoint)
+    let auth = sources.auth(&model);
+    let upstream_url = match model.source {
+        Source::Gemini => gemini::generate_url(&model.endpoint, &model.id, false),
+        Source::Cloudflare => cloudflare::run_url(&model.endpoint, &model.id),
+        _ => build_upstream_url(sources, &model),
     };
 
-    let request_body = serde_json::json!({
+    let mut request_body = serde_json::json!({
         "model": model.id,
         "messages": [{"role": "user", "content": prompt}],
         "temperature": 0.7,
         "max_tokens": 500,
         "stream": false,
     });
+    match model.source {
+        Source::Gemini => request_body = gemini::to_gemini(&request_body),
+        Source::Cloudflare => request_body = cloudflare::to_workers_ai(&request_body),
+        _ => {}
+    }
 
     let mut req = client
         .post(&upstream_url)
+        .timeout(LONG_TIMEOUT)
         .header("Content-Type", "application/json");
 
-    if let Some(key) = &api_key {
-        req = req.header("Authorization", format!("Bearer {}", key));
+    match (auth, &api_key) {
+        (Some(SourceAuth::None), _) | (_, None) => {}
+        (Some(SourceAuth::HeaderKey(name)), Some(key)) => req = req.header(name, key),
+        (_, Some(key)) => req = req.header("Authorization", format!("Bearer {}", key)),
+    }
+    for (name, value) in headers {
+        req = req.header(name, value);
     }
 
-    let response = timeout(Duration::from_secs(30), req.json(&request_body).send())
+    let response = req
+        .json(&request_body)
+        .send()
         .await
-        .map_err(|_| "Request timeout".to_string())?
-        .map_err(|e| format!("Request failed: {}", e))?;
+        .map_err(|e| fail(format!("Request failed: {}", e), None))?;
 
     let ttft_ms = start.elapsed().as_millis() as u64;
 
     if !response.status().is_success() {
-        return Err(format!("Model returned status: {}", response.status()));
+        let status = response.status();
+        return Err(fail(format!("Model returned status: {}", status), Some(status.as_u16())));
     }
 
     let body: Value = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+        .map_err(|e| fail(format!("Failed to parse response: {}", e), None))?;
+    let body = match model.source {
+        Source::Gemini => gemini::from_gemini(&model.id, &body),
+        Source::Cloudflare => cloudflare::from_workers_ai(&model.id, &body),
+        _ => body,
+    };
 
     let total_ms = start.elapsed().as_millis() as u64;
 
@@ -261,7 +640,7 @@ async fn query_model(
     let speed_score = calculate_speed_score(ttft_ms, tokens_per_sec);
     let quality_score = 7.0; // Placeholder - would need LLM judges
     let efficiency_score = calculate_efficiency_score(output_tokens);
-    let overall_score = (speed_score * 0.25) + (quality_score * 0.50) + (efficiency_score * 0.25);
+    let overall_score = weights.overall(speed_score, quality_score, efficiency_score);
 
     Ok(ModelResult {
         model: model.id,
@@ -280,6 +659,7 @@ async fn query_model(
             efficiency: efficiency_score,
             overall: overall_score,
         },
+        judge_scores: Vec::new(),
     })
 }
 
@@ -305,12 +685,31 @@ fn calculate_efficiency_score(output_tokens: u32) -> f64 {
     (output_tokens as f64 / 50.0).clamp(1.0, 10.0)
 }
 
+/// One-line description of a failed model.
+fn describe_failure(failure: &ModelFailure) -> String {
+    match failure.status {
+        Some(status) => format!("{} (HTTP {}): {}", failure.model, status, failure.error),
+        None => format!("{}: {}", failure.model, failure.error),
+    }
+}
+
 /// Generate markdown summary of comparison results.
-fn generate_markdown_summary(prompt: &str, results: &[ModelResult]) -> String {
+fn generate_markdown_summary(
+    prompt: &str,
+    results: &[ModelResult],
+    failures: &[ModelFailure],
+    weights: &ScoreWeights,
+) -> String {
     let mut md = String::new();
 
     md.push_str("## Model Comparison Results\n\n");
     md.push_str(&format!("**Prompt:** {}\n\n", prompt));
+    md.push_str(&format!(
+        "**Weights:** speed {:.0}%, quality {:.0}%, efficiency {:.0}%\n\n",
+        weights.speed * 100.0,
+        weights.quality * 100.0,
+        weights.efficiency * 100.0
+    ));
 
     md.push_str("| Model | TTFT | Total | Quality | Tokens/s | Overall |\n");
     md.push_str("|-------|------|-------|---------|----------|--------|\n");
@@ -334,6 +733,27 @@ fn generate_markdown_summary(prompt: &str, results: &[ModelResult]) -> String {
         ));
     }
 
+    if results.iter().any(|r| !r.judge_scores.is_empty()) {
+        md.push_str("\n### Judge Verdicts\n\n");
+        for r in results.iter().filter(|r| !r.judge_scores.is_empty()) {
+            md.push_str(&format!("**{}**\n", r.model));
+            for j in &r.judge_scores {
+                md.push_str(&format!("- {}: {:.1} — {}\n", j.judge, j.score, j.reason));
+            }
+            md.push('\n');
+        }
+    }
+
+    if !failures.is_empty() {
+        md.push_str("\n### Failed Models\n\n");
+        md.push_str("| Model | Status | Error |\n");
+        md.push_str("|-------|--------|-------|\n");
+        for f in failures {
+            let status = f.status.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string());
+            md.push_str(&format!("| {} | {} | {} |\n", f.model, status, f.error));
+        }
+    }
+
     md
 }
 
@@ -360,6 +780,28 @@ mod tests {
         assert!(high > low);
     }
 
+    #[test]
+    fn result_schema_requires_every_serialized_field() {
+        let schema = compare_result_schema();
+        let result = CompareResult {
+            prompt: "p".to_string(),
+            compared_at: Utc::now(),
+            results: Vec::new(),
+            failures: Vec::new(),
+            weights: ScoreWeights::default(),
+            ranking: Vec::new(),
+            markdown_summary: String::new(),
+        };
+
+        let value = serde_json::to_value(&result).unwrap();
+        for field in schema["required"].as_array().unwrap() {
+            assert!(value.get(field.as_str().unwrap()).is_some(), "missing {}", field);
+        }
+        for field in value.as_object().unwrap().keys() {
+            assert!(schema["properties"].get(field).is_some(), "schema lacks {}", field);
+        }
+    }
+
     #[test]
     fn markdown_summary_contains_prompt() {
         let results = vec![ModelResult {
@@ -379,11 +821,184 @@ mod tests {
                 efficiency: 5.0,
                 overall: 7.0,
             },
+            judge_scores: vec![JudgeScore {
+                judge: "Judge A".to_string(),
+                score: 8.0,
+                reason: "Correct".to_string(),
+                criteria: Default::default(),
+                verdict: r#"{"score": 8, "reason": "Correct"}"#.to_string(),
+            }],
         }];
 
-        let summary = generate_markdown_summary("What is 2+2?", &results);
+        let summary = generate_markdown_summary("What is 2+2?", &results, &[], &ScoreWeights::default());
         assert!(summary.contains("What is 2+2?"));
         assert!(summary.contains("test-model"));
         assert!(summary.contains("Winner"));
+        assert!(summary.contains("- Judge A: 8.0 — Correct"));
+        assert!(!summary.contains("Failed Models"));
+    }
+
+    #[test]
+    fn markdown_summary_lists_failed_models() {
+        let failures = vec![
+            ModelFailure {
+                model: "rate-limited".to_string(),
+                error: "Model returned status: 429 Too Many Requests".to_string(),
+                status: Some(429),
+            },
+            ModelFailure {
+                model: "slow-model".to_string(),
+                error: "Request timeout".to_string(),
+                status: None,
+            },
+        ];
+
+        let summary = generate_markdown_summary("p", &[], &failures, &ScoreWeights::default());
+        assert!(summary.contains("### Failed Models"));
+        assert!(summary.contains("| rate-limited | 429 |"));
+        assert!(summary.contains("| slow-model | - | Request timeout |"));
+    }
+
+    fn params(json: serde_json::Value) -> CompareParams {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn default_weights_match_original_split() {
+        let weights = params(json!({"prompt": "p"})).score_weights().unwrap();
+        assert_eq!(weights, ScoreWeights::default());
+        assert_eq!(weights.overall(8.0, 6.0, 4.0), 6.0);
+    }
+
+    #[test]
+    fn custom_weights_are_normalized() {
+        let weights = params(json!({
+            "prompt": "p",
+            "weights": {"speed": 1.0, "quality": 1.0, "efficiency": 2.0}
+        }))
+        .score_weights()
+        .unwrap();
+        assert_eq!(weights.speed, 0.25);
+        assert_eq!(weights.efficiency, 0.5);
+    }
+
+    #[test]
+    fn excluded_metrics_get_zero_weight() {
+        let weights = params(json!({"prompt": "p", "metrics": ["speed", "quality"]}))
+            .score_weights()
+            .unwrap();
+        assert_eq!(weights.efficiency, 0.0);
+        assert!((weights.speed - 1.0 / 3.0).abs() < 1e-9);
+        assert!((weights.quality - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn invalid_weights_are_rejected() {
+        let negative = params(json!({"prompt": "p", "weights": {"speed": -1.0}}));
+        assert!(negative.score_weights().is_err());
+
+        let zero = params(json!({
+            "prompt": "p",
+            "weights": {"speed": 0.0, "quality": 0.0, "efficiency": 0.0}
+        }));
+        assert!(zero.score_weights().is_err());
+
+        let no_metrics = params(json!({"prompt": "p", "metrics": []}));
+        assert!(no_metrics.score_weights().is_err());
+
+        let unknown_metric: Result<CompareParams, _> =
+            serde_json::from_value(json!({"prompt": "p", "metrics": ["vibes"]}));
+        assert!(unknown_metric.is_err());
+    }
+
+    async fn ollama_with_models(names: &[&str]) -> mockito::ServerGuard {
+        let mut server = mockito::Server::new_async().await;
+        let models: Vec<Value> = names.iter().map(|n| json!({"name": n})).collect();
+        server
+            .mock("GET", "/api/tags")
+            .with_body(json!({"models": models}).to_string())
+            .create_async()
+            .await;
+        server
+    }
+
+    fn comparator(server: &mockito::ServerGuard) -> ModelComparator {
+        let scanner = FreeModelScanner::new()
+            .with_openrouter_url(&server.url())
+            .with_opencode_zen_api_url(&server.url())
+            .with_opencode_zen_docs_url(&server.url())
+            .with_ollama_url(&server.url());
+        ModelComparator::from_parts(scanner, Client::new(), &Config::default())
+    }
+
+    #[tokio::test]
+    async fn min_results_cancels_remaining_models() {
+        let mut server = ollama_with_models(&["a", "b", "c"]).await;
+        let completions = server
+            .mock("POST", "/v1/chat/completions")
+            .with_body(r#"{"choices": [{"message": {"content": "hi"}}]}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let result = comparator(&server)
+            .compare(
+                params(json!({
+                    "prompt": "p",
+                    "include_ranking": false,
+                    "concurrency": 1,
+                    "min_results": 1
+                })),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.failures.len(), 2);
+        assert!(result.failures.iter().all(ModelFailure::is_cancelled));
+        completions.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn slow_models_time_out_individually() {
+        let mut server = ollama_with_models(&["fast", "slow"]).await;
+        server
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "fast"})))
+            .with_body(r#"{"choices": [{"message": {"content": "hi"}}]}"#)
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/v1/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "slow"})))
+            .with_chunked_body(|w| {
+                std::thread::sleep(Duration::from_millis(1500));
+                w.write_all(b"{}")
+            })
+            .create_async()
+            .await;
+
+        let result = comparator(&server)
+            .compare(
+                params(json!({"prompt": "p", "include_ranking": false, "timeout_secs": 1})),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.ranking, vec!["fast"]);
+        assert_eq!(result.failures[0].model, "slow");
+        assert_eq!(result.failures[0].error, "Timed out after 1s");
+    }
+
+    #[test]
+    fn describe_failure_includes_status_when_known() {
+        let failure = ModelFailure {
+            model: "m".to_string(),
+            error: "boom".to_string(),
+            status: Some(503),
+        };
+        assert_eq!(describe_failure(&failure), "m (HTTP 503): boom");
     }
 }
@@ -0,0 +1,474 @@
+//! Batch prompt evaluation (eval suites).
+//!
+//! Runs a list of prompts, or a named suite from `[mcp.suites]`, through
+//! `compare_models` and aggregates win-rates and mean scores per model.
+
+use super::compare::{
+    matches_requested, CompareParams, CompareResult, ModelComparator, ScoreMetric, ScoreWeights,
+};
+use super::progress::ProgressReporter;
+use crate::config::Config;
+use crate::scanner::FreeModel;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// Parameters for an eval run.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalParams {
+    /// Prompts to evaluate.
+    #[serde(default)]
+    pub prompts: Option<Vec<String>>,
+    /// Name of a stored suite to evaluate instead of `prompts`.
+    #[serde(default)]
+    pub suite: Option<String>,
+    #[serde(default)]
+    pub models: Option<Vec<String>>,
+    #[serde(default)]
+    pub max_models: Option<usize>,
+    #[serde(default = "default_include_ranking")]
+    pub include_ranking: bool,
+    #[serde(default)]
+    pub judges: Option<Vec<String>>,
+    #[serde(default)]
+    pub weights: Option<ScoreWeights>,
+    #[serde(default)]
+    pub metrics: Option<Vec<ScoreMetric>>,
+}
+
+fn default_include_ranking() -> bool {
+    true
+}
+
+impl EvalParams {
+    /// Resolve the prompts to run, looking up `suite` in the configured suites.
+    pub fn resolve_prompts(
+        &self,
+        suites: &BTreeMap<String, Vec<String>>,
+    ) -> Result<Vec<String>, String> {
+        let prompts = match (&self.prompts, &self.suite) {
+            (Some(_), Some(_)) => return Err("Pass either prompts or suite, not both".to_string()),
+            (Some(prompts), None) => prompts.clone(),
+            (None, Some(name)) => suites
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("Unknown eval suite: {}", name))?,
+            (None, None) => return Err("Either prompts or suite is required".to_string()),
+        };
+
+        if prompts.is_empty() {
+            return Err("Eval needs at least one prompt".to_string());
+        }
+        Ok(prompts)
+    }
+
+    /// Check the weights, metrics and model selection before anything runs.
+    pub fn validate(&self) -> Result<(), String> {
+        self.compare_params("", None).score_weights()?;
+        if self.models.as_ref().is_some_and(Vec::is_empty) {
+            return Err("models must name at least one model".to_string());
+        }
+        if self.max_models == Some(0) {
+            return Err("max_models must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Requested models that match no free model.
+    pub fn unknown_models(&self, free_models: &[FreeModel]) -> Vec<&str> {
+        self.models
+            .iter()
+            .flatten()
+            .filter(|r| !free_models.iter().any(|m| matches_requested(m, r)))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Comparison parameters for a single prompt of the run.
+    fn compare_params(&self, prompt: &str, criteria: Option<String>) -> CompareParams {
+        CompareParams {
+            prompt: prompt.to_string(),
+            models: self.models.clone(),
+            max_models: self.max_models,
+            include_ranking: self.include_ranking,
+            judges: self.judges.clone(),
+            weights: self.weights,
+            metrics: self.metrics.clone(),
+            criteria,
+            concurrency: None,
+            timeout_secs: None,
+            min_results: None,
+        }
+    }
+}
+
+/// Outcome of one prompt in an eval run.
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptOutcome {
+    pub prompt: String,
+    pub winner: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Aggregated performance of one model across an eval run.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ModelStanding {
+    pub model: String,
+    /// Prompts the model answered.
+    pub runs: usize,
+    /// Prompts where the model ranked first.
+    pub wins: usize,
+    /// Prompts where the model's request failed.
+    pub failures: usize,
+    pub win_rate: f64,
+    pub mean_overall: f64,
+    pub mean_quality: f64,
+    pub mean_speed: f64,
+}
+
+/// Full eval run result.
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalResult {
+    pub suite: Option<String>,
+    pub evaluated_at: DateTime<Utc>,
+    pub prompts: Vec<PromptOutcome>,
+    pub standings: Vec<ModelStanding>,
+    pub markdown_summary: String,
+}
+
+/// Run every prompt through the comparator and aggregate the results.
+/// Reports progress after each prompt.
+pub async fn run_eval(
+    params: EvalParams,
+    progress: Option<&ProgressReporter>,
+) -> Result<EvalResult, String> {
+    run_eval_with(&ModelComparator::new().await, params, progress).await
+}
+
+/// Run an eval with an existing comparator.
+pub async fn run_eval_with(
+    comparator: &ModelComparator,
+    params: EvalParams,
+    progress: Option<&ProgressReporter>,
+) -> Result<EvalResult, String> {
+    let config = Config::load_with_env();
+    let prompts = params.resolve_prompts(&config.mcp.suites)?;
+    let cases = prompts.into_iter().map(|prompt| (prompt, None)).collect();
+    run_cases_with(comparator, params, cases, progress).await
+}
+
+/// Run prompts, each with optional criteria for the judges. `params.suite`
+/// only labels the result; `params.prompts` is ignored.
+pub async fn run_cases_with(
+    comparator: &ModelComparator,
+    params: EvalParams,
+    cases: Vec<(String, Option<String>)>,
+    progress: Option<&ProgressReporter>,
+) -> Result<EvalResult, String> {
+    if cases.is_empty() {
+        return Err("Eval needs at least one prompt".to_string());
+    }
+    // Reject bad parameters once rather than failing every prompt
+    params.validate()?;
+
+    let mut outcomes = Vec::new();
+    let mut comparisons = Vec::new();
+
+    for (i, (prompt, criteria)) in cases.iter().enumerate() {
+        let compare_params = params.compare_params(prompt, criteria.clone());
+        match comparator.compare(compare_params, None).await {
+            Ok(result) => {
+                outcomes.push(PromptOutcome {
+                    prompt: prompt.clone(),
+                    winner: result.ranking.first().cloned(),
+                    error: None,
+                });
+                comparisons.push(result);
+            }
+            Err(e) => outcomes.push(PromptOutcome {
+                prompt: prompt.clone(),
+                winner: None,
+                error: Some(e),
+            }),
+        }
+        if let Some(progress) = progress {
+            let message = format!("Evaluated prompt {}/{}", i + 1, cases.len());
+            progress.report(i + 1, cases.len(), message);
+        }
+    }
+
+    if comparisons.is_empty() {
+        return Err("Every prompt in the eval failed".to_string());
+    }
+
+    let standings = aggregate(&comparisons);
+    let markdown_summary = generate_markdown_summary(params.suite.as_deref(), &outcomes, &standings);
+
+    Ok(EvalResult {
+        suite: params.suite,
+        evaluated_at: Utc::now(),
+        prompts: outcomes,
+        standings,
+        markdown_summary,
+    })
+}
+
+/// Aggregate per-model standings, best win-rate first (ties by mean score).
+fn aggregate(comparisons: &[CompareResult]) -> Vec<ModelStanding> {
+    #[derive(Default)]
+    struct Totals {
+        runs: usize,
+        wins: usize,
+        failures: usize,
+        overall: f64,
+        quality: f64,
+        speed: f64,
+    }
+
+    let mut totals: HashMap<&str, Totals> = HashMap::new();
+    for comparison in comparisons {
+        for (rank, result) in comparison.results.iter().enumerate() {
+            // Results are sorted by overall score, so the first one won
+            let entry = totals.entry(result.model.as_str()).or_default();
+            entry.runs += 1;
+            if rank == 0 {
+                entry.wins += 1;
+            }
+            entry.overall += result.scores.overall;
+            entry.quality += result.scores.quality;
+            entry.speed += result.scores.speed;
+        }
+        for failure in &comparison.failures {
+            totals.entry(failure.model.as_str()).or_default().failures += 1;
+        }
+    }
+
+    let mut standings: Vec<ModelStanding> = totals
+        .into_iter()
+        .map(|(model, t)| {
+            let mean = |sum: f64| if t.runs > 0 { sum / t.runs as f64 } else { 0.0 };
+            let attempts = t.runs + t.failures;
+            ModelStanding {
+                model: model.to_string(),
+                runs: t.runs,
+                wins: t.wins,
+                failures: t.failures,
+                win_rate: if attempts > 0 { t.wins as f64 / attempts as f64 } else { 0.0 },
+                mean_overall: mean(t.overall),
+                mean_quality: mean(t.quality),
+                mean_speed: mean(t.speed),
+            }
+        })
+        .collect();
+
+    standings.sort_by(|a, b| {
+        b.win_rate
+            .partial_cmp(&a.win_rate)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(
+                b.mean_overall
+                    .partial_cmp(&a.mean_overall)
+                    .unwrap_or(std::cmp::Ordering::Equal),
+            )
+            .then_with(|| a.model.cmp(&b.model))
+    });
+    standings
+}
+
+/// Generate markdown summary of an eval run.
+fn generate_markdown_summary(
+    suite: Option<&str>,
+    outcomes: &[PromptOutcome],
+    standings: &[ModelStanding],
+) -> String {
+    let mut md = String::new();
+
+    md.push_str("## Eval Results\n\n");
+    if let Some(suite) = suite {
+        md.push_str(&format!("**Suite:** {}\n\n", suite));
+    }
+    let failed = outcomes.iter().filter(|o| o.error.is_some()).count();
+    md.push_str(&format!("**Prompts:** {} ({} failed)\n\n", outcomes.len(), failed));
+
+    md.push_str("| Model | Wins | Win rate | Mean overall | Mean quality | Failures |\n");
+    md.push_str("|-------|------|----------|--------------|--------------|----------|\n");
+    for s in standings {
+        md.push_str(&format!(
+            "| {} | {}/{} | {:.0}% | {:.1} | {:.1} | {} |\n",
+            s.model,
+            s.wins,
+            s.runs + s.failures,
+            s.win_rate * 100.0,
+            s.mean_overall,
+            s.mean_quality,
+            s.failures
+        ));
+    }
+
+    if let Some(best) = standings.first() {
+        md.push_str(&format!(
+            "\n**Best overall:** {} ({:.0}% win rate)\n",
+            best.model,
+            best.win_rate * 100.0
+        ));
+    }
+
+    md
+}
+
+/// JSON Schema describing a serialized `EvalResult`.
+pub fn eval_result_schema() -> Value {
+    let number = json!({ "type": "number" });
+    let integer = json!({ "type": "integer" });
+    let nullable_string = json!({ "type": ["string", "null"] });
+    json!({
+        "type": "object",
+        "properties": {
+            "suite": nullable_string,
+            "evaluated_at": { "type": "string", "format": "date-time" },
+            "prompts": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "prompt": { "type": "string" },
+                        "winner": nullable_string,
+                        "error": nullable_string
+                    },
+                    "required": ["prompt", "winner", "error"]
+                }
+            },
+            "standings": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "model": { "type": "string" },
+                        "runs": integer,
+                        "wins": integer,
+                        "failures": integer,
+                        "win_rate": number,
+                        "mean_overall": number,
+                        "mean_quality": number,
+                        "mean_speed": number
+                    },
+                    "required": [
+                        "model", "runs", "wins", "failures",
+                        "win_rate", "mean_overall", "mean_quality", "mean_speed"
+                    ]
+                }
+            },
+            "markdown_summary": { "type": "string" }
+        },
+        "required": ["suite", "evaluated_at", "prompts", "standings", "markdown_summary"]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::compare::{ModelFailure, ModelResult, ResponseMetrics, ResponseScores};
+
+    fn model_result(model: &str, overall: f64) -> ModelResult {
+        ModelResult {
+            model: model.to_string(),
+            source: "openrouter".to_string(),
+            response: String::new(),
+            metrics: ResponseMetrics {
+                ttft_ms: 100,
+                total_ms: 500,
+                input_tokens: 10,
+                output_tokens: 50,
+                tokens_per_sec: 100.0,
+            },
+            scores: ResponseScores {
+                speed: 5.0,
+                quality: overall,
+                efficiency: 5.0,
+                overall,
+            },
+            judge_scores: Vec::new(),
+        }
+    }
+
+    fn comparison(results: Vec<ModelResult>, failures: Vec<ModelFailure>) -> CompareResult {
+        CompareResult {
+            prompt: "p".to_string(),
+            compared_at: Utc::now(),
+            ranking: results.iter().map(|r| r.model.clone()).collect(),
+            results,
+            failures,
+            weights: ScoreWeights::default(),
+            markdown_summary: String::new(),
+        }
+    }
+
+    #[test]
+    fn resolves_named_suite() {
+        let mut suites = BTreeMap::new();
+        suites.insert("coding".to_string(), vec!["Write fizzbuzz".to_string()]);
+        let params: EvalParams = serde_json::from_value(json!({"suite": "coding"})).unwrap();
+
+        assert_eq!(params.resolve_prompts(&suites).unwrap(), vec!["Write fizzbuzz"]);
+    }
+
+    #[test]
+    fn rejects_missing_or_ambiguous_prompts() {
+        let suites = BTreeMap::new();
+        let none: EvalParams = serde_json::from_value(json!({})).unwrap();
+        let both: EvalParams =
+            serde_json::from_value(json!({"prompts": ["a"], "suite": "s"})).unwrap();
+        let unknown: EvalParams = serde_json::from_value(json!({"suite": "missing"})).unwrap();
+        let empty: EvalParams = serde_json::from_value(json!({"prompts": []})).unwrap();
+
+        assert!(none.resolve_prompts(&suites).is_err());
+        assert!(both.resolve_prompts(&suites).is_err());
+        assert!(unknown.resolve_prompts(&suites).is_err());
+        assert!(empty.resolve_prompts(&suites).is_err());
+    }
+
+    #[test]
+    fn aggregates_win_rates_and_means() {
+        let comparisons = vec![
+            comparison(vec![model_result("a", 8.0), model_result("b", 6.0)], Vec::new()),
+            comparison(vec![model_result("b", 9.0), model_result("a", 7.0)], Vec::new()),
+            comparison(
+                vec![model_result("a", 6.0)],
+                vec![ModelFailure {
+                    model: "b".to_string(),
+                    error: "Request timeout".to_string(),
+                    status: None,
+                }],
+            ),
+        ];
+
+        let standings = aggregate(&comparisons);
+
+        assert_eq!(standings[0].model, "a");
+        assert_eq!(standings[0].wins, 2);
+        assert_eq!(standings[0].runs, 3);
+        assert!((standings[0].win_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(standings[0].mean_overall, 7.0);
+
+        assert_eq!(standings[1].model, "b");
+        assert_eq!(standings[1].wins, 1);
+        assert_eq!(standings[1].failures, 1);
+        assert_eq!(standings[1].mean_overall, 7.5);
+    }
+
+    #[test]
+    fn markdown_summary_lists_standings() {
+        let standings = aggregate(&[comparison(vec![model_result("a", 8.0)], Vec::new())]);
+        let outcomes = vec![PromptOutcome {
+            prompt: "p".to_string(),
+            winner: Some("a".to_string()),
+            error: None,
+        }];
+
+        let summary = generate_markdown_summary(Some("coding"), &outcomes, &standings);
+        assert!(summary.contains("**Suite:** coding"));
+        assert!(summary.contains("| a | 1/1 | 100% |"));
+        assert!(summary.contains("**Best overall:** a"));
+    }
+}
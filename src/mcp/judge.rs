@@ -1,14 +1,16 @@
 //! LLM Judge panel for quality scoring.
 //!
 //! Uses 7 premium models via OpenRouter to evaluate response quality
-//! with US/EU data residency guarantees.
+//! with US/EU data residency guarantees. The panel, per-judge weights and
+//! rubric can be overridden with `[mcp.judges]` in the config file.
 
 use super::spending::SpendingTracker;
-use crate::config::Config;
-use crate::http::{create_client_with_timeout, LONG_TIMEOUT};
+use crate::config::{Config, JudgesConfig};
+use crate::scanner::Source;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::time::Duration;
 use tokio::time::timeout;
 
@@ -58,6 +60,22 @@ pub const JUDGE_MODELS: &[JudgeModel] = &[
     },
 ];
 
+/// Free judge models used when `free_only` is set without an explicit panel.
+pub const FREE_JUDGE_MODELS: &[&str] = &[
+    "meta-llama/llama-3.3-70b-instruct:free",
+    "google/gemini-2.0-flash-exp:free",
+    "deepseek/deepseek-chat-v3-0324:free",
+    "mistralai/mistral-small-3.1-24b-instruct:free",
+    "qwen/qwen-2.5-72b-instruct:free",
+];
+
+/// Default scoring criteria for the judge prompt.
+pub const DEFAULT_RUBRIC: &str = "Score based on:
+- Accuracy: Is the information correct?
+- Completeness: Does it fully answer the question?
+- Clarity: Is it well-structured and easy to understand?
+- Usefulness: Would this actually help the user?";
+
 /// A judge model configuration.
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -74,60 +92,152 @@ pub struct JudgeScore {
     pub judge: String,
     pub score: f64,
     pub reason: String,
+    /// Per-criterion scores, when the judge provided them.
+    #[serde(default)]
+    pub criteria: BTreeMap<String, f64>,
+    /// The judge's raw reply, kept for auditing.
+    #[serde(default)]
+    pub verdict: String,
 }
 
-/// Estimated cost per judge call (7 judges x ~$0.01 each).
-pub const ESTIMATED_COST_PER_EVALUATION: f64 = 0.07;
+/// Approximate cost of a single premium judge call.
+pub const COST_PER_JUDGE_CALL: f64 = 0.01;
+
+/// A judge on the active panel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Judge {
+    pub id: String,
+    pub name: String,
+    pub weight: f64,
+}
+
+impl Judge {
+    fn new(id: &str, name: &str, weight: f64) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            weight,
+        }
+    }
+
+    /// Whether this judge is an OpenRouter free model.
+    pub fn is_free(&self) -> bool {
+        self.id.ends_with(":free")
+    }
+}
 
 /// Judge panel that evaluates responses.
+#[derive(Clone)]
 pub struct JudgePanel {
     client: Client,
     api_key: Option<String>,
     spending_tracker: Option<SpendingTracker>,
+    judges: Vec<Judge>,
+    rubric: String,
+    free_only: bool,
 }
 
 impl JudgePanel {
-    /// Create a new judge panel.
-    pub fn new() -> Self {
-        let config = Config::load_with_env();
-
+    /// Create the panel from config, sending judge requests through `client`
+    /// so they share its connection pool.
+    pub fn from_parts(config: &Config, client: Client) -> Self {
         // Set up spending tracker with database in config dir
-        let spending_tracker = dirs::config_dir()
-            .map(|dir| dir.join("multiai").join("spending.db"))
-            .and_then(|path| SpendingTracker::new(path, config.spending.clone()).ok());
+        let spending_tracker = SpendingTracker::open_default(config.spending.clone());
 
         Self {
-            client: create_client_with_timeout(LONG_TIMEOUT),
-            api_key: config.api_keys.openrouter,
+            client,
+            api_key: config.get_api_key(&Source::OpenRouter),
             spending_tracker,
+            judges: Self::judges_from_config(&config.mcp.judges),
+            rubric: config
+                .mcp
+                .judges
+                .rubric
+                .clone()
+                .unwrap_or_else(|| DEFAULT_RUBRIC.to_string()),
+            free_only: config.mcp.judges.free_only,
+        }
+    }
+
+    /// Resolve the judge panel from config, falling back to the built-in panels.
+    pub fn judges_from_config(config: &JudgesConfig) -> Vec<Judge> {
+        let judges: Vec<Judge> = if !config.models.is_empty() {
+            config
+                .models
+                .iter()
+                .map(|m| Judge::new(&m.id, m.name.as_deref().unwrap_or(&m.id), m.weight))
+                .collect()
+        } else if config.free_only {
+            FREE_JUDGE_MODELS.iter().map(|id| Judge::new(id, id, 1.0)).collect()
+        } else {
+            JUDGE_MODELS.iter().map(|m| Judge::new(m.id, m.name, 1.0)).collect()
+        };
+
+        if config.free_only {
+            judges.into_iter().filter(Judge::is_free).collect()
+        } else {
+            judges
         }
     }
 
-    /// Check if judging is available (requires OpenRouter API key).
+    /// Restrict the panel to the given judge model IDs for one comparison.
+    /// Weights of already-configured judges are kept; new ones get weight 1.0.
+    pub fn with_judges(mut self, ids: &[String]) -> Self {
+        self.judges = ids
+            .iter()
+            .filter(|id| !self.free_only || id.ends_with(":free"))
+            .map(|id| {
+                self.judges
+                    .iter()
+                    .find(|j| &j.id == id)
+                    .cloned()
+                    .unwrap_or_else(|| Judge::new(id, id, 1.0))
+            })
+            .collect();
+        self
+    }
+
+    /// Add expected-answer criteria to the rubric for this panel's verdicts.
+    pub fn with_criteria(mut self, criteria: &str) -> Self {
+        self.rubric = format!(
+            "{}\n\nThe response must meet these criteria:\n{}",
+            self.rubric, criteria
+        );
+        self
+    }
+
+    /// Check if judging is available (requires OpenRouter API key and judges).
     pub fn is_available(&self) -> bool {
-        self.api_key.is_some()
+        self.api_key.is_some() && !self.judges.is_empty()
+    }
+
+    /// Estimated cost of one evaluation with the current panel.
+    pub fn estimated_cost(&self) -> f64 {
+        self.judges.iter().filter(|j| !j.is_free()).count() as f64 * COST_PER_JUDGE_CALL
     }
 
     /// Check if a judge call would exceed spending caps.
     pub fn check_spending_cap(&self) -> Result<(), String> {
         if let Some(tracker) = &self.spending_tracker {
             tracker
-                .check_cap(ESTIMATED_COST_PER_EVALUATION)
+                .check_cap(self.estimated_cost())
                 .map_err(|e| e.message)
         } else {
             Ok(()) // No tracker = no spending limits
         }
     }
 
-    /// Record cost after a successful evaluation.
-    fn record_cost(&self, cost: f64) {
+    /// Record the cost of a judge call, attributed to the judge's model and
+    /// provider (the OpenRouter id prefix, e.g. `openai` for `openai/gpt-4o`).
+    fn record_cost(&self, judge_id: &str, cost: f64) {
         if let Some(tracker) = &self.spending_tracker {
-            let _ = tracker.record_cost(cost);
+            let provider = judge_id.split_once('/').map_or(judge_id, |(p, _)| p);
+            let _ = tracker.record_model_cost(judge_id, provider, cost);
         }
     }
 
     /// Evaluate a response using the judge panel.
-    /// Returns the median score and all individual scores.
+    /// Returns the weighted median score and all individual scores.
     pub async fn evaluate(
         &self,
         user_prompt: &str,
@@ -148,63 +258,78 @@ Response to evaluate:
 {}
 """
 
-Score based on:
-- Accuracy: Is the information correct?
-- Completeness: Does it fully answer the question?
-- Clarity: Is it well-structured and easy to understand?
-- Usefulness: Would this actually help the user?
+{}
 
-Reply with ONLY a JSON object: {{"score": N, "reason": "brief explanation"}}"#,
-            user_prompt, model_response
+Reply with ONLY a JSON object: {{"score": N, "criteria": {{"<criterion>": N, ...}}, "reason": "brief explanation"}}"#,
+            user_prompt, model_response, self.rubric
         );
 
         // Fan out to all judges in parallel
         let mut handles = Vec::new();
-        for judge in JUDGE_MODELS {
+        for judge in &self.judges {
             let client = self.client.clone();
             let api_key = api_key.clone();
             let prompt = judge_prompt.clone();
-            let judge_id = judge.id.to_string();
-            let judge_name = judge.name.to_string();
+            let judge_id = judge.id.clone();
+            let judge_name = judge.name.clone();
+            let weight = judge.weight;
 
             handles.push(tokio::spawn(async move {
-                query_judge(client, &api_key, &judge_id, &judge_name, &prompt).await
+                let result = query_judge(client, &api_key, &judge_id, &judge_name, &prompt).await;
+                let is_free = judge_id.ends_with(":free");
+                result.map(|score| (score, weight, judge_id, is_free))
             }));
         }
 
         // Collect results
         let mut scores: Vec<JudgeScore> = Vec::new();
+        let mut weighted: Vec<(f64, f64)> = Vec::new();
+        let mut paid_judges = Vec::new();
         for handle in handles {
-            if let Ok(Ok(score)) = handle.await {
+            if let Ok(Ok((score, weight, judge_id, is_free))) = handle.await {
+                weighted.push((score.score, weight));
+                if !is_free {
+                    paid_judges.push(judge_id);
+                }
                 scores.push(score);
             }
         }
 
-        if scores.len() < 3 {
+        let required = self.judges.len().min(3);
+        if scores.len() < required {
             return Err(format!(
-                "Not enough judges responded ({}/7)",
-                scores.len()
+                "Not enough judges responded ({}/{})",
+                scores.len(),
+                self.judges.len()
             ));
         }
 
-        // Calculate median
-        let mut score_values: Vec<f64> = scores.iter().map(|s| s.score).collect();
-        score_values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
-        let median = score_values[score_values.len() / 2];
+        let median = weighted_median(&mut weighted);
 
-        // Record cost based on number of judges that responded
-        // Approximate cost: ~$0.01 per judge call
-        let cost = scores.len() as f64 * 0.01;
-        self.record_cost(cost);
+        // Record a cost for each paid judge that responded
+        for judge_id in &paid_judges {
+            self.record_cost(judge_id, COST_PER_JUDGE_CALL);
+        }
 
         Ok((median, scores))
     }
 }
 
-impl Default for JudgePanel {
-    fn default() -> Self {
-        Self::new()
+/// Weighted median of `(score, weight)` pairs.
+/// With equal weights this matches the upper median.
+fn weighted_median(values: &mut [(f64, f64)]) -> f64 {
+    values.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let total: f64 = values.iter().map(|(_, w)| w.max(0.0)).sum();
+    let mut cumulative = 0.0;
+    for (score, weight) in values.iter() {
+        cumulative += weight.max(0.0);
+        if cumulative > total / 2.0 {
+            return *score;
+        }
     }
+
+    values.last().map(|(score, _)| *score).unwrap_or(0.0)
 }
 
 /// Query a single judge model.
@@ -249,26 +374,43 @@ async fn query_judge(
         .as_str()
         .unwrap_or("");
 
-    // Parse JSON from response
+    Ok(parse_verdict(judge_name, content))
+}
+
+/// Parse a judge's reply into a score, keeping the raw verdict.
+fn parse_verdict(judge_name: &str, content: &str) -> JudgeScore {
     let parsed: Result<Value, _> = serde_json::from_str(content);
-    let (score, reason) = match parsed {
+    let (score, reason, criteria) = match parsed {
         Ok(v) => {
             let s = v["score"].as_f64().unwrap_or(5.0);
             let r = v["reason"].as_str().unwrap_or("").to_string();
-            (s, r)
+            let c = v["criteria"]
+                .as_object()
+                .map(|criteria| {
+                    criteria
+                        .iter()
+                        .filter_map(|(name, n)| {
+                            n.as_f64().map(|n| (name.clone(), n.clamp(1.0, 10.0)))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            (s, r, c)
         }
         Err(_) => {
             // Try to extract score from text
             let score = extract_score_from_text(content).unwrap_or(5.0);
-            (score, "Could not parse structured response".to_string())
+            (score, "Could not parse structured response".to_string(), BTreeMap::new())
         }
     };
 
-    Ok(JudgeScore {
+    JudgeScore {
         judge: judge_name.to_string(),
         score: score.clamp(1.0, 10.0),
         reason,
-    })
+        criteria,
+        verdict: content.to_string(),
+    }
 }
 
 /// Extract a numeric score from unstructured text.
@@ -325,6 +467,26 @@ mod tests {
         assert_eq!(parsed["score"].as_f64().unwrap(), 8.0);
     }
 
+    #[test]
+    fn parse_verdict_keeps_criteria_and_raw_reply() {
+        let reply = r#"{"score": 8, "criteria": {"accuracy": 9, "clarity": 7, "note": "n/a"}, "reason": "Solid"}"#;
+        let verdict = parse_verdict("Judge A", reply);
+        assert_eq!(verdict.score, 8.0);
+        assert_eq!(verdict.reason, "Solid");
+        assert_eq!(verdict.criteria.get("accuracy"), Some(&9.0));
+        assert_eq!(verdict.criteria.get("clarity"), Some(&7.0));
+        assert!(!verdict.criteria.contains_key("note"));
+        assert_eq!(verdict.verdict, reply);
+    }
+
+    #[test]
+    fn parse_verdict_falls_back_to_text_score() {
+        let verdict = parse_verdict("Judge B", "I'd give this 6/10.");
+        assert_eq!(verdict.score, 6.0);
+        assert!(verdict.criteria.is_empty());
+        assert_eq!(verdict.verdict, "I'd give this 6/10.");
+    }
+
     #[test]
     fn extract_score_from_text_patterns() {
         assert_eq!(extract_score_from_text("Score: 7"), Some(7.0));
@@ -340,6 +502,9 @@ mod tests {
             client: Client::new(),
             api_key: None,
             spending_tracker: None,
+            judges: JudgePanel::judges_from_config(&JudgesConfig::default()),
+            rubric: DEFAULT_RUBRIC.to_string(),
+            free_only: false,
         };
         assert!(!panel.is_available());
     }
@@ -350,8 +515,74 @@ mod tests {
             client: Client::new(),
             api_key: Some("test-key".to_string()),
             spending_tracker: None,
+            judges: JudgePanel::judges_from_config(&JudgesConfig::default()),
+            rubric: DEFAULT_RUBRIC.to_string(),
+            free_only: false,
         };
         // Without a tracker, all calls should be allowed
         assert!(panel.check_spending_cap().is_ok());
     }
+
+    #[test]
+    fn criteria_are_appended_to_rubric() {
+        let panel = JudgePanel {
+            client: Client::new(),
+            api_key: None,
+            spending_tracker: None,
+            judges: Vec::new(),
+            rubric: DEFAULT_RUBRIC.to_string(),
+            free_only: false,
+        }
+        .with_criteria("Mentions ownership");
+        assert!(panel.rubric.starts_with(DEFAULT_RUBRIC));
+        assert!(panel.rubric.ends_with("criteria:\nMentions ownership"));
+    }
+
+    #[test]
+    fn default_config_uses_builtin_panel() {
+        let judges = JudgePanel::judges_from_config(&JudgesConfig::default());
+        assert_eq!(judges.len(), JUDGE_MODELS.len());
+        assert!(judges.iter().all(|j| j.weight == 1.0));
+    }
+
+    #[test]
+    fn configured_judges_replace_builtin_panel() {
+        use crate::config::JudgeModelConfig;
+
+        let config = JudgesConfig {
+            models: vec![JudgeModelConfig {
+                id: "openai/gpt-4o".to_string(),
+                name: None,
+                weight: 2.0,
+            }],
+            ..JudgesConfig::default()
+        };
+
+        let judges = JudgePanel::judges_from_config(&config);
+        assert_eq!(judges, vec![Judge::new("openai/gpt-4o", "openai/gpt-4o", 2.0)]);
+    }
+
+    #[test]
+    fn free_only_panel_contains_only_free_models() {
+        let config = JudgesConfig {
+            free_only: true,
+            ..JudgesConfig::default()
+        };
+
+        let judges = JudgePanel::judges_from_config(&config);
+        assert!(!judges.is_empty());
+        assert!(judges.iter().all(Judge::is_free));
+    }
+
+    #[test]
+    fn weighted_median_matches_median_for_equal_weights() {
+        let mut values = vec![(3.0, 1.0), (9.0, 1.0), (5.0, 1.0), (7.0, 1.0), (8.0, 1.0)];
+        assert_eq!(weighted_median(&mut values), 7.0);
+    }
+
+    #[test]
+    fn weighted_median_favors_heavier_judges() {
+        let mut values = vec![(3.0, 5.0), (9.0, 1.0), (8.0, 1.0)];
+        assert_eq!(weighted_median(&mut values), 3.0);
+    }
 }
@@ -1,20 +1,57 @@
 //! MCP (Model Context Protocol) server for MultiAI.
 //!
 //! Exposes a `compare_models` tool that allows Claude Desktop and other
-//! MCP-compatible clients to compare multiple LLM responses.
+//! MCP-compatible clients to compare multiple LLM responses, a `run_eval`
+//! tool for batch prompt suites, a `get_spending_status` tool for the judge
+//! budget, stored chats as `chat://<id>` resources, and the comparison
+//! leaderboard as `leaderboard://current`.
+//!
+//! The `client` module goes the other way, bridging external MCP servers'
+//! tools into gateway chat completions.
 
+pub mod client;
 mod compare;
+pub mod eval;
 mod judge;
+mod progress;
+mod resources;
 pub mod spending;
 #[cfg(test)]
 mod tests;
 
+use crate::chat::ChatDb;
 use crate::error::McpError;
-use compare::{CompareParams, ModelComparator};
+use crate::leaderboard::Leaderboard;
+use eval::EvalParams;
+use futures::stream::{FuturesUnordered, StreamExt};
+use spending::SpendingTracker;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::io::{BufRead, BufReader, Write};
+use std::io::Write;
+use std::sync::Mutex;
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::runtime::Runtime;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::sync::OnceCell;
+
+pub use compare::{
+    CompareParams, CompareResult, ModelComparator, ModelFailure, ModelResult, ResponseMetrics,
+    ResponseScores, ScoreMetric, ScoreWeights,
+};
+pub use judge::JudgeScore;
+pub use progress::ProgressReporter;
+
+/// MCP protocol versions this server speaks, newest first.
+pub const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// The version to answer `initialize` with: the client's when supported,
+/// else the newest one.
+fn negotiate_protocol_version(requested: Option<&str>) -> &'static str {
+    PROTOCOL_VERSIONS
+        .iter()
+        .find(|version| Some(**version) == requested)
+        .unwrap_or(&PROTOCOL_VERSIONS[0])
+}
 
 /// JSON-RPC request structure.
 #[derive(Debug, Deserialize)]
@@ -59,13 +96,35 @@ pub struct ToolInfo {
     pub name: String,
     /// Tool description.
     pub description: String,
+    /// JSON Schema for the tool arguments.
+    pub input_schema: Value,
+    /// JSON Schema for `structuredContent` in the tool result, if any.
+    pub output_schema: Option<Value>,
 }
 
 /// MCP server that handles JSON-RPC requests via stdio.
-#[derive(Debug)]
+///
+/// Owns a single tokio runtime that all tool calls run on, so requests
+/// can execute concurrently without spinning up a runtime per call.
+/// The model comparator (scanner, HTTP client and judge panel) is built on
+/// the first tool call and shared by every call after it.
 pub struct McpServer {
     name: String,
     tools: Vec<ToolInfo>,
+    chat_db: Option<Mutex<ChatDb>>,
+    spending: Option<SpendingTracker>,
+    leaderboard: Option<Leaderboard>,
+    comparator: OnceCell<ModelComparator>,
+    runtime: Runtime,
+}
+
+impl std::fmt::Debug for McpServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("McpServer")
+            .field("name", &self.name)
+            .field("tools", &self.tools)
+            .finish_non_exhaustive()
+    }
 }
 
 impl McpServer {
@@ -73,13 +132,68 @@ impl McpServer {
     pub fn new() -> Self {
         Self {
             name: "multiai".to_string(),
-            tools: vec![ToolInfo {
-                name: "compare_models".to_string(),
-                description: "Compare responses from multiple LLM models".to_string(),
-            }],
+            tools: vec![
+                ToolInfo {
+                    name: "compare_models".to_string(),
+                    description: "Compare responses from multiple LLM models".to_string(),
+                    input_schema: compare_models_input_schema(),
+                    output_schema: Some(compare::compare_result_schema()),
+                },
+                ToolInfo {
+                    name: "run_eval".to_string(),
+                    description: "Run a prompt suite across models and rank them by win-rate"
+                        .to_string(),
+                    input_schema: run_eval_input_schema(),
+                    output_schema: Some(eval::eval_result_schema()),
+                },
+                ToolInfo {
+                    name: "get_spending_status".to_string(),
+                    description: "Show judge spending, caps and reset times".to_string(),
+                    input_schema: json!({ "type": "object", "properties": {} }),
+                    output_schema: None,
+                },
+            ],
+            chat_db: None,
+            spending: None,
+            leaderboard: None,
+            comparator: OnceCell::new(),
+            runtime: Runtime::new().expect("Failed to create tokio runtime"),
         }
     }
 
+    /// Serve chats from the given database as MCP resources.
+    pub fn with_chat_db(mut self, db: ChatDb) -> Self {
+        self.chat_db = Some(Mutex::new(db));
+        self
+    }
+
+    /// Report judge spending from the given tracker.
+    pub fn with_spending_tracker(mut self, tracker: SpendingTracker) -> Self {
+        self.spending = Some(tracker);
+        self
+    }
+
+    /// Record comparisons in, and serve standings from, the given leaderboard.
+    pub fn with_leaderboard(mut self, leaderboard: Leaderboard) -> Self {
+        self.leaderboard = Some(leaderboard);
+        self
+    }
+
+    /// The comparator shared by all tool calls, built on first use.
+    async fn comparator(&self) -> &ModelComparator {
+        self.comparator
+            .get_or_init(|| async {
+                let config = crate::config::Config::load_with_env();
+                ModelComparator::from_parts(
+                    ModelComparator::local_scanner(&config).await,
+                    crate::http::create_shared_client(),
+                    &config,
+                )
+                .with_leaderboard(self.leaderboard.clone())
+            })
+            .await
+    }
+
     /// Get the server name.
     pub fn name(&self) -> &str {
         &self.name
@@ -105,15 +219,34 @@ impl McpServer {
     }
 
     /// Handle a JSON-RPC request and return a JSON-RPC response.
+    /// Blocks on the server runtime; use from synchronous code only.
     pub fn handle_request(&self, request: &str) -> Result<String, McpError> {
+        self.runtime.block_on(self.dispatch(request, None))
+    }
+
+    /// Handle a JSON-RPC request on the current runtime.
+    /// Progress notifications are sent to `notifier` when the client asks for them.
+    async fn dispatch(
+        &self,
+        request: &str,
+        notifier: Option<&UnboundedSender<String>>,
+    ) -> Result<String, McpError> {
         let req: JsonRpcRequest = serde_json::from_str(request)
             .map_err(|e| McpError::parse_error(format!("Parse error: {}", e)))?;
 
         let result = match req.method.as_str() {
-            "initialize" => self.handle_initialize(),
+            "initialize" => self.handle_initialize(&req.params),
             "tools/list" => self.handle_tools_list(),
+            "resources/list" => match self.handle_resources_list() {
+                Ok(result) => result,
+                Err(e) => return Ok(self.error_response(req.id, e)),
+            },
+            "resources/read" => match self.handle_resources_read(&req.params) {
+                Ok(result) => result,
+                Err(e) => return Ok(self.error_response(req.id, e)),
+            },
             "tools/call" => {
-                return self.handle_tools_call(req.id, &req.params);
+                return self.handle_tools_call(req.id, &req.params, notifier).await;
             }
             method => {
                 let error = McpError::method_not_found(method);
@@ -130,11 +263,13 @@ impl McpServer {
         .unwrap_or_else(|_| "{}".to_string()))
     }
 
-    fn handle_initialize(&self) -> Value {
+    fn handle_initialize(&self, params: &Value) -> Value {
+        let requested = params["protocolVersion"].as_str();
         json!({
-            "protocolVersion": "2024-11-05",
+            "protocolVersion": negotiate_protocol_version(requested),
             "capabilities": {
-                "tools": {}
+                "tools": {},
+                "resources": {}
             },
             "serverInfo": {
                 "name": self.name,
@@ -148,64 +283,110 @@ impl McpServer {
             .tools
             .iter()
             .map(|t| {
-                json!({
+                let mut tool = json!({
                     "name": t.name,
                     "description": t.description,
-                    "inputSchema": {
-                        "type": "object",
-                        "properties": {
-                            "prompt": {
-                                "type": "string",
-                                "description": "The prompt to test across models"
-                            },
-                            "models": {
-                                "type": "array",
-                                "items": { "type": "string" },
-                                "description": "Optional: specific models to compare"
-                            },
-                            "max_models": {
-                                "type": "integer",
-                                "description": "Optional: limit to N models"
-                            },
-                            "include_ranking": {
-                                "type": "boolean",
-                                "description": "Optional: run LLM judges (default: true)"
-                            }
-                        },
-                        "required": ["prompt"]
-                    }
-                })
+                    "inputSchema": t.input_schema
+                });
+                if let Some(schema) = &t.output_schema {
+                    tool["outputSchema"] = schema.clone();
+                }
+                tool
             })
             .collect();
 
         json!({ "tools": tools })
     }
 
-    fn handle_tools_call(&self, id: Value, params: &Value) -> Result<String, McpError> {
+    fn handle_resources_list(&self) -> Result<Value, McpError> {
+        let mut resources = match &self.chat_db {
+            Some(db) => {
+                let db = db
+                    .lock()
+                    .map_err(|e| McpError::internal_error(format!("Database lock error: {}", e)))?;
+                resources::list_chat_resources(&db)?
+            }
+            None => Vec::new(),
+        };
+        if self.spending.is_some() {
+            resources.push(resources::spending_resource());
+        }
+        if self.leaderboard.is_some() {
+            resources.push(resources::leaderboard_resource());
+        }
+
+        Ok(json!({ "resources": resources }))
+    }
+
+    fn handle_resources_read(&self, params: &Value) -> Result<Value, McpError> {
+        let uri = params["uri"]
+            .as_str()
+            .ok_or_else(|| McpError::invalid_params("Missing resource uri"))?;
+
+        if uri == resources::SPENDING_URI {
+            let tracker = self
+                .spending
+                .as_ref()
+                .ok_or_else(|| McpError::invalid_params(format!("Unknown resource: {}", uri)))?;
+            let contents = resources::read_spending_resource(tracker)?;
+            return Ok(json!({ "contents": [contents] }));
+        }
+        if uri == resources::LEADERBOARD_URI {
+            let board = self
+                .leaderboard
+                .as_ref()
+                .ok_or_else(|| McpError::invalid_params(format!("Unknown resource: {}", uri)))?;
+            let contents = resources::read_leaderboard_resource(board)?;
+            return Ok(json!({ "contents": [contents] }));
+        }
+
+        let db = self
+            .chat_db
+            .as_ref()
+            .ok_or_else(|| McpError::invalid_params(format!("Unknown resource: {}", uri)))?
+            .lock()
+            .map_err(|e| McpError::internal_error(format!("Database lock error: {}", e)))?;
+        let contents = resources::read_chat_resource(&db, uri)?;
+
+        Ok(json!({ "contents": [contents] }))
+    }
+
+    async fn handle_tools_call(
+        &self,
+        id: Value,
+        params: &Value,
+        notifier: Option<&UnboundedSender<String>>,
+    ) -> Result<String, McpError> {
         let tool_name = params["name"].as_str().unwrap_or("");
         let arguments = &params["arguments"];
 
+        let progress_token = &params["_meta"]["progressToken"];
+        let progress = match notifier {
+            Some(sender) if !progress_token.is_null() => {
+                Some(ProgressReporter::new(progress_token.clone(), sender.clone()))
+            }
+            _ => None,
+        };
+
         match tool_name {
             "compare_models" => {
                 // Parse comparison parameters
                 let compare_params: CompareParams =
                     serde_json::from_value(arguments.clone())
                         .map_err(|e| McpError::invalid_params(format!("Invalid parameters: {}", e)))?;
+                if let Err(e) = compare_params.score_weights() {
+                    return Ok(self.error_response(id, McpError::invalid_params(e)));
+                }
 
-                // Run comparison using tokio runtime
-                let rt = Runtime::new()
-                    .map_err(|e| McpError::internal_error(format!("Failed to create runtime: {}", e)))?;
-                let comparator = ModelComparator::new();
-
-                let comparison_result = rt.block_on(async {
-                    comparator.compare(compare_params).await
-                });
+                let comparator = self.comparator().await;
+                let comparison_result = comparator.compare(compare_params, progress.as_ref()).await;
 
                 match comparison_result {
                     Ok(result) => {
                         // Format as MCP tool result with both JSON and markdown
                         let json_result = serde_json::to_string_pretty(&result)
                             .unwrap_or_else(|_| "{}".to_string());
+                        let structured = serde_json::to_value(&result).unwrap_or(Value::Null);
 
                         let content = json!({
                             "content": [
@@ -217,7 +398,8 @@ impl McpServer {
                                     "type": "text",
                                     "text": format!("\n<details>\n<summary>Full JSON Result</summary>\n\n```json\n{}\n```\n</details>", json_result)
                                 }
-                            ]
+                            ],
+                            "structuredContent": structured
                         });
 
                         Ok(serde_json::to_string(&JsonRpcResponse {
@@ -234,6 +416,72 @@ impl McpServer {
                     }
                 }
             }
+            "run_eval" => {
+                let eval_params: EvalParams = match serde_json::from_value(arguments.clone()) {
+                    Ok(params) => params,
+                    Err(e) => {
+                        let error = McpError::invalid_params(format!("Invalid parameters: {}", e));
+                        return Ok(self.error_response(id, error));
+                    }
+                };
+
+                let comparator = self.comparator().await;
+                match eval::run_eval_with(comparator, eval_params, progress.as_ref()).await {
+                    Ok(result) => {
+                        let structured = serde_json::to_value(&result).unwrap_or(Value::Null);
+                        let content = json!({
+                            "content": [
+                                {
+                                    "type": "text",
+                                    "text": result.markdown_summary
+                                }
+                            ],
+                            "structuredContent": structured
+                        });
+
+                        Ok(serde_json::to_string(&JsonRpcResponse {
+                            jsonrpc: "2.0".to_string(),
+                            id,
+                            result: Some(content),
+                            error: None,
+                        })
+                        .unwrap_or_else(|_| "{}".to_string()))
+                    }
+                    Err(e) => Ok(self.error_response(id, McpError::internal_error(e))),
+                }
+            }
+            "get_spending_status" => match &self.spending {
+                Some(tracker) => {
+                    let status = tracker.get_status();
+                    let json_status = serde_json::to_string_pretty(&status)
+                        .unwrap_or_else(|_| "{}".to_string());
+
+                    let content = json!({
+                        "content": [
+                            {
+                                "type": "text",
+                                "text": status.summary()
+                            },
+                            {
+                                "type": "text",
+                                "text": json_status
+                            }
+                        ]
+                    });
+
+                    Ok(serde_json::to_string(&JsonRpcResponse {
+                        jsonrpc: "2.0".to_string(),
+                        id,
+                        result: Some(content),
+                        error: None,
+                    })
+                    .unwrap_or_else(|_| "{}".to_string()))
+                }
+                None => {
+                    let error = McpError::internal_error("Spending tracker is not available");
+                    Ok(self.error_response(id, error))
+                }
+            },
             _ => {
                 let error = McpError::invalid_params(format!("Unknown tool: {}", tool_name));
                 Ok(self.error_response(id, error))
@@ -251,43 +499,176 @@ impl Default for McpServer {
 /// Run the MCP server using stdio transport.
 /// Reads JSON-RPC requests from stdin, writes responses to stdout.
 pub fn run_mcp() -> anyhow::Result<()> {
-    let server = McpServer::new();
+    let mut server = match ChatDb::open_default() {
+        Ok(db) => McpServer::new().with_chat_db(db),
+        Err(_) => McpServer::new(),
+    };
+    let config = crate::config::Config::load_with_env();
+    if let Some(tracker) = SpendingTracker::open_default(config.spending) {
+        server = server.with_spending_tracker(tracker);
+    }
+    if let Ok(leaderboard) = Leaderboard::open_default() {
+        server = server.with_leaderboard(leaderboard);
+    }
     server.run_stdio()
 }
 
+/// Argument schema for the `run_eval` tool.
+fn run_eval_input_schema() -> Value {
+    // Same options as compare_models, with a prompt list or suite instead of one prompt
+    let mut schema = compare_models_input_schema();
+    if let Some(schema) = schema.as_object_mut() {
+        schema.remove("required");
+    }
+    if let Some(properties) = schema["properties"].as_object_mut() {
+        properties.remove("prompt");
+        properties.insert(
+            "prompts".to_string(),
+            json!({
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Prompts to evaluate (or use suite)"
+            }),
+        );
+        properties.insert(
+            "suite".to_string(),
+            json!({
+                "type": "string",
+                "description": "Name of a suite from [mcp.suites] in the config file"
+            }),
+        );
+    }
+    schema
+}
+
+/// Argument schema for the `compare_models` tool.
+fn compare_models_input_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "prompt": {
+                "type": "string",
+                "description": "The prompt to test across models"
+            },
+            "models": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Optional: specific models to compare"
+            },
+            "max_models": {
+                "type": "integer",
+                "description": "Optional: limit to N models"
+            },
+            "include_ranking": {
+                "type": "boolean",
+                "description": "Optional: run LLM judges (default: true)"
+            },
+            "judges": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Optional: judge model IDs to use instead of the configured panel"
+            },
+            "weights": {
+                "type": "object",
+                "properties": {
+                    "speed": { "type": "number", "minimum": 0 },
+                    "quality": { "type": "number", "minimum": 0 },
+                    "efficiency": { "type": "number", "minimum": 0 }
+                },
+                "description": "Optional: relative metric weights (default: speed 0.25, quality 0.5, efficiency 0.25)"
+            },
+            "metrics": {
+                "type": "array",
+                "items": { "type": "string", "enum": ["speed", "quality", "efficiency"] },
+                "description": "Optional: metrics that count towards the overall score (default: all)"
+            },
+            "concurrency": {
+                "type": "integer",
+                "minimum": 1,
+                "description": "Optional: models queried at once (default: 4)"
+            },
+            "timeout_secs": {
+                "type": "integer",
+                "minimum": 1,
+                "description": "Optional: seconds each model has to answer (default: 30)"
+            },
+            "min_results": {
+                "type": "integer",
+                "minimum": 1,
+                "description": "Optional: stop once this many models have answered, cancelling the rest"
+            }
+        },
+        "required": ["prompt"]
+    })
+}
+
 impl McpServer {
     /// Run the MCP server reading from stdin and writing to stdout.
+    /// Requests are handled concurrently; responses are written as they complete.
     pub fn run_stdio(&self) -> anyhow::Result<()> {
-        let stdin = std::io::stdin();
-        let mut stdout = std::io::stdout();
-        let reader = BufReader::new(stdin.lock());
-
-        for line in reader.lines() {
-            let line = line?;
-            if line.is_empty() {
-                continue;
-            }
+        self.runtime.block_on(async {
+            // The channel outlives `in_flight`, whose futures borrow its sender
+            let (notify_tx, mut notify_rx) = unbounded_channel::<String>();
+            let notifier = &notify_tx;
+            let mut lines = BufReader::new(tokio::io::stdin()).lines();
+            let mut in_flight = FuturesUnordered::new();
+            let mut stdout = std::io::stdout();
 
-            match self.handle_request(&line) {
-                Ok(response) => {
-                    writeln!(stdout, "{}", response)?;
-                    stdout.flush()?;
-                }
-                Err(e) => {
-                    let error_response = json!({
-                        "jsonrpc": "2.0",
-                        "id": null,
-                        "error": {
-                            "code": -32700,
-                            "message": e
+            loop {
+                tokio::select! {
+                    line = lines.next_line() => {
+                        match line? {
+                            Some(line) if line.is_empty() => continue,
+                            Some(line) => in_flight.push(async move {
+                                self.dispatch(&line, Some(notifier)).await
+                            }),
+                            None => break,
                         }
-                    });
-                    writeln!(stdout, "{}", error_response)?;
-                    stdout.flush()?;
+                    }
+                    Some(notification) = notify_rx.recv() => {
+                        writeln!(stdout, "{}", notification)?;
+                        stdout.flush()?;
+                    }
+                    Some(result) = in_flight.next(), if !in_flight.is_empty() => {
+                        write_response(&mut stdout, result)?;
+                    }
+                }
+            }
+
+            // Finish requests still running when stdin closed
+            loop {
+                tokio::select! {
+                    Some(notification) = notify_rx.recv() => {
+                        writeln!(stdout, "{}", notification)?;
+                        stdout.flush()?;
+                    }
+                    result = in_flight.next() => match result {
+                        Some(result) => write_response(&mut stdout, result)?,
+                        None => break,
+                    },
                 }
             }
-        }
 
-        Ok(())
+            Ok::<(), anyhow::Error>(())
+        })
+    }
+}
+
+/// Write a handled request (or a top-level parse error) to the output.
+fn write_response<W: Write>(out: &mut W, result: Result<String, McpError>) -> std::io::Result<()> {
+    match result {
+        Ok(response) => writeln!(out, "{}", response)?,
+        Err(e) => {
+            let error_response = json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": {
+                    "code": -32700,
+                    "message": e
+                }
+            });
+            writeln!(out, "{}", error_response)?;
+        }
     }
+    out.flush()
 }
@@ -0,0 +1,64 @@
+//! MCP progress notifications for long-running tool calls.
+
+use serde_json::{json, Value};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Sends `notifications/progress` for a request that supplied a progress token.
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    token: Value,
+    sender: UnboundedSender<String>,
+}
+
+impl ProgressReporter {
+    pub fn new(token: Value, sender: UnboundedSender<String>) -> Self {
+        Self { token, sender }
+    }
+
+    /// Report `progress` out of `total` steps with a human-readable message.
+    pub fn report(&self, progress: usize, total: usize, message: impl Into<String>) {
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/progress",
+            "params": {
+                "progressToken": self.token,
+                "progress": progress,
+                "total": total,
+                "message": message.into()
+            }
+        });
+        // The client may have gone away; progress is best-effort
+        let _ = self.sender.send(notification.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    #[test]
+    fn report_sends_progress_notification() {
+        let (sender, mut receiver) = unbounded_channel();
+        let reporter = ProgressReporter::new(json!("token-1"), sender);
+
+        reporter.report(2, 5, "model-a finished");
+
+        let sent: Value = serde_json::from_str(&receiver.try_recv().unwrap()).unwrap();
+        assert_eq!(sent["method"], "notifications/progress");
+        assert_eq!(sent["params"]["progressToken"], "token-1");
+        assert_eq!(sent["params"]["progress"], 2);
+        assert_eq!(sent["params"]["total"], 5);
+        assert_eq!(sent["params"]["message"], "model-a finished");
+        assert!(sent.get("id").is_none());
+    }
+
+    #[test]
+    fn report_ignores_closed_channel() {
+        let (sender, receiver) = unbounded_channel();
+        drop(receiver);
+        let reporter = ProgressReporter::new(json!(1), sender);
+
+        reporter.report(1, 1, "done");
+    }
+}
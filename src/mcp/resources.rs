@@ -0,0 +1,128 @@
+//! MCP resources exposing stored MultiAI chats, judge spending and the
+//! comparison leaderboard.
+//!
+//! Each chat is listed as `chat://<id>` and served as markdown; the
+//! current spending status is served as JSON at `spending://current`, and
+//! the last week's standings at `leaderboard://current`.
+
+use super::spending::SpendingTracker;
+use crate::chat::ChatDb;
+use crate::error::McpError;
+use crate::export::{export_chat, ExportChat, ExportFormat, ExportMessage};
+use crate::leaderboard::{self, Leaderboard};
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+
+/// URI scheme for chat resources.
+pub const CHAT_URI_PREFIX: &str = "chat://";
+
+/// URI of the judge spending status resource.
+pub const SPENDING_URI: &str = "spending://current";
+
+/// URI of the comparison leaderboard resource.
+pub const LEADERBOARD_URI: &str = "leaderboard://current";
+
+/// List all stored chats as MCP resource descriptors.
+pub fn list_chat_resources(db: &ChatDb) -> Result<Vec<Value>, McpError> {
+    let chats = db
+        .list_chats()
+        .map_err(|e| McpError::internal_error(format!("Failed to list chats: {}", e)))?;
+
+    Ok(chats
+        .into_iter()
+        .map(|chat| {
+            json!({
+                "uri": format!("{}{}", CHAT_URI_PREFIX, chat.id),
+                "name": chat.title,
+                "description": format!("MultiAI chat last updated {}", chat.updated_at.to_rfc3339()),
+                "mimeType": "text/markdown"
+            })
+        })
+        .collect())
+}
+
+/// Read a `chat://<id>` resource as markdown.
+pub fn read_chat_resource(db: &ChatDb, uri: &str) -> Result<Value, McpError> {
+    let chat_id = uri
+        .strip_prefix(CHAT_URI_PREFIX)
+        .ok_or_else(|| McpError::invalid_params(format!("Unknown resource: {}", uri)))?;
+
+    let chat = db
+        .get_chat(chat_id)
+        .map_err(|e| McpError::internal_error(format!("Failed to load chat: {}", e)))?
+        .ok_or_else(|| McpError::invalid_params(format!("Chat not found: {}", chat_id)))?;
+    let messages = db.get_messages(chat_id).unwrap_or_default();
+
+    let export = ExportChat {
+        title: chat.title,
+        created_at: chat.created_at.to_rfc3339(),
+        messages: messages
+            .into_iter()
+            .map(|m| ExportMessage {
+                role: m.role.to_string(),
+                content: m.content,
+                created_at: m.created_at.to_rfc3339(),
+            })
+            .collect(),
+    };
+
+    let markdown = export_chat(&export, ExportFormat::Markdown)
+        .map_err(McpError::internal_error)?;
+
+    Ok(json!({
+        "uri": uri,
+        "mimeType": "text/markdown",
+        "text": String::from_utf8_lossy(&markdown)
+    }))
+}
+
+/// Resource descriptor for the spending status.
+pub fn spending_resource() -> Value {
+    json!({
+        "uri": SPENDING_URI,
+        "name": "Judge spending",
+        "description": "Daily and monthly judge spending, caps and reset times",
+        "mimeType": "application/json"
+    })
+}
+
+/// Read the `spending://current` resource as JSON.
+pub fn read_spending_resource(tracker: &SpendingTracker) -> Result<Value, McpError> {
+    let status = serde_json::to_string_pretty(&tracker.get_status())
+        .map_err(|e| McpError::internal_error(format!("Failed to serialize spending: {}", e)))?;
+
+    Ok(json!({
+        "uri": SPENDING_URI,
+        "mimeType": "application/json",
+        "text": status
+    }))
+}
+
+/// Resource descriptor for the comparison leaderboard.
+pub fn leaderboard_resource() -> Value {
+    json!({
+        "uri": LEADERBOARD_URI,
+        "name": "Model leaderboard",
+        "description": format!(
+            "Win rate, mean judge score and mean TTFB per model over the last {} days",
+            leaderboard::DEFAULT_DAYS
+        ),
+        "mimeType": "application/json"
+    })
+}
+
+/// Read the `leaderboard://current` resource as JSON.
+pub fn read_leaderboard_resource(board: &Leaderboard) -> Result<Value, McpError> {
+    let since = Utc::now() - Duration::days(leaderboard::DEFAULT_DAYS.into());
+    let standings = board
+        .standings(since)
+        .map_err(|e| McpError::internal_error(format!("Failed to load leaderboard: {}", e)))?;
+    let text = serde_json::to_string_pretty(&json!({ "since": since, "models": standings }))
+        .map_err(|e| McpError::internal_error(format!("Failed to serialize leaderboard: {}", e)))?;
+
+    Ok(json!({
+        "uri": LEADERBOARD_URI,
+        "mimeType": "application/json",
+        "text": text
+    }))
+}
@@ -1,7 +1,8 @@
 //! Spending caps and tracking for MCP judge calls.
 //!
 //! Tracks daily and monthly spending on premium judge models
-//! to prevent runaway costs.
+//! to prevent runaway costs. A per-call ledger attributes spending to
+//! models and providers.
 
 use crate::config::SpendingConfig;
 use chrono::{DateTime, Datelike, TimeZone, Utc};
@@ -9,6 +10,7 @@ use rusqlite::{params, Connection, Result as SqlResult};
 use serde::Serialize;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
 
 /// Spending cap exceeded error.
 #[derive(Debug, Clone, Serialize)]
@@ -22,6 +24,7 @@ pub struct SpendingCapError {
 }
 
 /// Spending tracker that persists to SQLite.
+#[derive(Debug, Clone)]
 pub struct SpendingTracker {
     conn: Arc<Mutex<Connection>>,
     config: SpendingConfig,
@@ -38,6 +41,16 @@ impl SpendingTracker {
         })
     }
 
+    /// Default database location (`<config dir>/multiai/spending.db`).
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("multiai").join("spending.db"))
+    }
+
+    /// Open the tracker at the default location.
+    pub fn open_default(config: SpendingConfig) -> Option<Self> {
+        Self::default_path().and_then(|path| Self::new(path, config).ok())
+    }
+
     /// Create an in-memory tracker for testing.
     pub fn in_memory(config: SpendingConfig) -> SqlResult<Self> {
         let conn = Connection::open_in_memory()?;
@@ -58,6 +71,20 @@ impl SpendingTracker {
             )",
             [],
         )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS ledger (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                recorded_at TEXT NOT NULL,
+                model TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                amount REAL NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_ledger_recorded_at ON ledger(recorded_at)",
+            [],
+        )?;
 
         // Initialize daily and monthly rows if they don't exist
         let now = Utc::now();
@@ -203,6 +230,89 @@ impl SpendingTracker {
         Ok(())
     }
 
+    /// Record a cost attributed to a model and provider.
+    pub fn record_model_cost(&self, model: &str, provider: &str, cost: f64) -> SqlResult<()> {
+        self.record_cost(cost)?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO ledger (recorded_at, model, provider, amount) VALUES (?, ?, ?, ?)",
+            params![Utc::now().to_rfc3339(), model, provider, cost],
+        )?;
+        Ok(())
+    }
+
+    /// Attributed spending per model since `since`, highest first.
+    pub fn spending_by_model(&self, since: DateTime<Utc>) -> SqlResult<Vec<ModelSpend>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT model, provider, SUM(amount), COUNT(*) FROM ledger
+             WHERE recorded_at >= ?
+             GROUP BY model, provider
+             ORDER BY SUM(amount) DESC, model",
+        )?;
+        let rows = stmt.query_map(params![since.to_rfc3339()], |row| {
+            Ok(ModelSpend {
+                model: row.get(0)?,
+                provider: row.get(1)?,
+                amount: row.get(2)?,
+                calls: row.get::<_, i64>(3)? as u64,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Attributed spending per provider since `since`, highest first.
+    pub fn spending_by_provider(&self, since: DateTime<Utc>) -> SqlResult<Vec<ProviderSpend>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT provider, SUM(amount), COUNT(*) FROM ledger
+             WHERE recorded_at >= ?
+             GROUP BY provider
+             ORDER BY SUM(amount) DESC, provider",
+        )?;
+        let rows = stmt.query_map(params![since.to_rfc3339()], |row| {
+            Ok(ProviderSpend {
+                provider: row.get(0)?,
+                amount: row.get(1)?,
+                calls: row.get::<_, i64>(2)? as u64,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Totals, remaining budget and this month's biggest spenders.
+    pub fn get_report(&self, top: usize) -> SqlResult<SpendingReport> {
+        let status = self.get_status();
+        let now = Utc::now();
+        let today_start = Self::next_daily_reset(&now) - chrono::Duration::days(1);
+        let month_start = Utc
+            .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+            .unwrap();
+
+        let mut top_models = self.spending_by_model(month_start)?;
+        top_models.truncate(top);
+        Ok(SpendingReport {
+            daily: PeriodSpending {
+                used: status.daily_used,
+                cap: status.daily_cap,
+                remaining: (status.daily_cap - status.daily_used).max(0.0),
+                percent: status.daily_percent,
+                resets_at: status.daily_resets_at,
+                by_provider: self.spending_by_provider(today_start)?,
+            },
+            monthly: PeriodSpending {
+                used: status.monthly_used,
+                cap: status.monthly_cap,
+                remaining: (status.monthly_cap - status.monthly_used).max(0.0),
+                percent: status.monthly_percent,
+                resets_at: status.monthly_resets_at,
+                by_provider: self.spending_by_provider(month_start)?,
+            },
+            at_warning: status.at_warning,
+            top_models,
+        })
+    }
+
     /// Check if we're at or above warning threshold.
     pub fn is_at_warning(&self) -> bool {
         if let Ok((daily, monthly)) = self.get_spending() {
@@ -248,6 +358,69 @@ pub struct SpendingStatus {
     pub at_warning: bool,
 }
 
+/// Spending attributed to one model.
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct ModelSpend {
+    pub model: String,
+    pub provider: String,
+    pub amount: f64,
+    pub calls: u64,
+}
+
+/// Spending attributed to one provider.
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct ProviderSpend {
+    pub provider: String,
+    pub amount: f64,
+    pub calls: u64,
+}
+
+/// Spending for the current day or month.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PeriodSpending {
+    pub used: f64,
+    pub cap: f64,
+    pub remaining: f64,
+    pub percent: f64,
+    pub resets_at: DateTime<Utc>,
+    pub by_provider: Vec<ProviderSpend>,
+}
+
+/// Spending breakdown served by `GET /v1/spending`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SpendingReport {
+    pub daily: PeriodSpending,
+    pub monthly: PeriodSpending,
+    pub at_warning: bool,
+    /// This month's biggest spenders.
+    pub top_models: Vec<ModelSpend>,
+}
+
+impl SpendingStatus {
+    /// Markdown summary of remaining judge budget.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "## Judge Spending\n\n\
+             | Period | Used | Cap | Used % | Resets |\n\
+             |--------|------|-----|--------|--------|\n\
+             | Daily | ${:.2} | ${:.2} | {:.0}% | {} |\n\
+             | Monthly | ${:.2} | ${:.2} | {:.0}% | {} |\n",
+            self.daily_used,
+            self.daily_cap,
+            self.daily_percent,
+            self.daily_resets_at.to_rfc3339(),
+            self.monthly_used,
+            self.monthly_cap,
+            self.monthly_percent,
+            self.monthly_resets_at.to_rfc3339(),
+        );
+        if self.at_warning {
+            summary.push_str("\n⚠ Spending is above the warning threshold.\n");
+        }
+        summary
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -325,4 +498,48 @@ mod tests {
         assert_eq!(status.daily_cap, 5.0);
         assert_eq!(status.daily_percent, 50.0);
     }
+
+    #[test]
+    fn attributed_costs_count_towards_totals() {
+        let tracker = SpendingTracker::in_memory(SpendingConfig::default()).unwrap();
+        tracker.record_model_cost("openai/gpt-4o", "openai", 0.02).unwrap();
+        let (daily, monthly) = tracker.get_spending().unwrap();
+        assert_eq!(daily, 0.02);
+        assert_eq!(monthly, 0.02);
+    }
+
+    #[test]
+    fn spending_report_breaks_down_by_model_and_provider() {
+        let tracker = SpendingTracker::in_memory(SpendingConfig::default()).unwrap();
+        tracker.record_model_cost("openai/gpt-4o", "openai", 0.01).unwrap();
+        tracker.record_model_cost("openai/gpt-4o", "openai", 0.01).unwrap();
+        tracker.record_model_cost("openai/o3-mini", "openai", 0.01).unwrap();
+        tracker.record_model_cost("anthropic/claude", "anthropic", 0.05).unwrap();
+
+        let report = tracker.get_report(2).unwrap();
+
+        assert_eq!(report.top_models.len(), 2);
+        assert_eq!(report.top_models[0].model, "anthropic/claude");
+        assert_eq!(report.top_models[1].model, "openai/gpt-4o");
+        assert_eq!(report.top_models[1].calls, 2);
+        let providers: Vec<&str> = report
+            .daily
+            .by_provider
+            .iter()
+            .map(|p| p.provider.as_str())
+            .collect();
+        assert_eq!(providers, ["anthropic", "openai"]);
+        assert_eq!(report.monthly.by_provider[1].calls, 3);
+        assert!((report.daily.remaining - (5.0 - 0.08)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn spending_status_summary_lists_both_periods() {
+        let tracker = SpendingTracker::in_memory(SpendingConfig::default()).unwrap();
+        tracker.record_cost(4.50).unwrap();
+        let summary = tracker.get_status().summary();
+        assert!(summary.contains("| Daily | $4.50 | $5.00 | 90% |"));
+        assert!(summary.contains("| Monthly | $4.50 | $50.00 |"));
+        assert!(summary.contains("warning threshold"));
+    }
 }
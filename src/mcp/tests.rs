@@ -37,10 +37,26 @@ fn mcp_server_handles_initialize_request() {
 
     assert_eq!(parsed["jsonrpc"], "2.0");
     assert_eq!(parsed["id"], 1);
-    assert!(parsed["result"]["protocolVersion"].is_string());
+    assert_eq!(parsed["result"]["protocolVersion"], "2024-11-05");
     assert!(parsed["result"]["serverInfo"]["name"].is_string());
 }
 
+#[test]
+fn mcp_server_answers_unknown_protocol_versions_with_its_latest() {
+    let server = McpServer::new();
+    for params in [r#"{"protocolVersion":"1999-01-01"}"#, "{}"] {
+        let request = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}}"#,
+            params
+        );
+
+        let response = server.handle_request(&request).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&response).unwrap();
+
+        assert_eq!(parsed["result"]["protocolVersion"], PROTOCOL_VERSIONS[0]);
+    }
+}
+
 #[test]
 fn mcp_server_handles_tools_list_request() {
     let server = McpServer::new();
@@ -72,6 +88,7 @@ fn mcp_server_handles_tools_call_compare_models() {
     if let Some(content) = parsed["result"]["content"].as_array() {
         assert!(!content.is_empty());
         assert_eq!(content[0]["type"], "text");
+        assert!(parsed["result"]["structuredContent"]["ranking"].is_array());
     } else {
         // No models available error is expected in test environment
         assert!(parsed["error"].is_object());
@@ -96,3 +113,157 @@ fn mcp_server_returns_error_for_unknown_tool() {
     assert_eq!(parsed["id"], 4);
     assert!(parsed["error"].is_object());
 }
+
+fn server_with_chat() -> McpServer {
+    use crate::chat::MessageRole;
+
+    let db = ChatDb::in_memory().unwrap();
+    db.create_chat("chat-1", "Rust questions").unwrap();
+    db.add_message("msg-1", "chat-1", MessageRole::User, "What is a lifetime?")
+        .unwrap();
+    McpServer::new().with_chat_db(db)
+}
+
+#[test]
+fn mcp_server_advertises_resources_capability() {
+    let server = McpServer::new();
+    let request = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#;
+
+    let response = server.handle_request(request).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&response).unwrap();
+
+    assert!(parsed["result"]["capabilities"]["resources"].is_object());
+}
+
+#[test]
+fn mcp_server_lists_chats_as_resources() {
+    let server = server_with_chat();
+    let request = r#"{"jsonrpc":"2.0","id":5,"method":"resources/list","params":{}}"#;
+
+    let response = server.handle_request(request).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&response).unwrap();
+
+    let resources = parsed["result"]["resources"].as_array().unwrap();
+    assert_eq!(resources.len(), 1);
+    assert_eq!(resources[0]["uri"], "chat://chat-1");
+    assert_eq!(resources[0]["name"], "Rust questions");
+    assert_eq!(resources[0]["mimeType"], "text/markdown");
+}
+
+#[test]
+fn mcp_server_reads_chat_resource_as_markdown() {
+    let server = server_with_chat();
+    let request = r#"{"jsonrpc":"2.0","id":6,"method":"resources/read","params":{"uri":"chat://chat-1"}}"#;
+
+    let response = server.handle_request(request).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&response).unwrap();
+
+    let text = parsed["result"]["contents"][0]["text"].as_str().unwrap();
+    assert!(text.contains("# Rust questions"));
+    assert!(text.contains("What is a lifetime?"));
+}
+
+#[test]
+fn mcp_server_returns_error_for_unknown_chat_resource() {
+    let server = server_with_chat();
+    let request = r#"{"jsonrpc":"2.0","id":7,"method":"resources/read","params":{"uri":"chat://missing"}}"#;
+
+    let response = server.handle_request(request).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&response).unwrap();
+
+    assert!(parsed["error"].is_object());
+}
+
+fn server_with_spending() -> McpServer {
+    use crate::config::SpendingConfig;
+    use crate::mcp::spending::SpendingTracker;
+
+    let tracker = SpendingTracker::in_memory(SpendingConfig::default()).unwrap();
+    tracker.record_cost(1.25).unwrap();
+    McpServer::new().with_spending_tracker(tracker)
+}
+
+#[test]
+fn mcp_server_has_spending_status_tool() {
+    let server = McpServer::new();
+    let tools = server.list_tools();
+    assert!(tools.iter().any(|t| t.name == "get_spending_status"));
+}
+
+#[test]
+fn mcp_server_reports_spending_status() {
+    let server = server_with_spending();
+    let request = r#"{"jsonrpc":"2.0","id":8,"method":"tools/call","params":{"name":"get_spending_status","arguments":{}}}"#;
+
+    let response = server.handle_request(request).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&response).unwrap();
+
+    let text = parsed["result"]["content"][0]["text"].as_str().unwrap();
+    assert!(text.contains("| Daily | $1.25 | $5.00 |"));
+}
+
+#[test]
+fn mcp_server_reads_spending_resource() {
+    let server = server_with_spending();
+    let request = r#"{"jsonrpc":"2.0","id":9,"method":"resources/read","params":{"uri":"spending://current"}}"#;
+
+    let response = server.handle_request(request).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&response).unwrap();
+
+    let contents = &parsed["result"]["contents"][0];
+    assert_eq!(contents["mimeType"], "application/json");
+    let status: serde_json::Value =
+        serde_json::from_str(contents["text"].as_str().unwrap()).unwrap();
+    assert_eq!(status["daily_used"], 1.25);
+    assert_eq!(status["monthly_cap"], 50.0);
+}
+
+#[test]
+fn mcp_server_lists_and_reads_leaderboard_resource() {
+    let server = McpServer::new().with_leaderboard(Leaderboard::in_memory().unwrap());
+    let request = r#"{"jsonrpc":"2.0","id":12,"method":"resources/list","params":{}}"#;
+
+    let response = server.handle_request(request).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&response).unwrap();
+    let resources = parsed["result"]["resources"].as_array().unwrap();
+    assert!(resources.iter().any(|r| r["uri"] == "leaderboard://current"));
+
+    let request = r#"{"jsonrpc":"2.0","id":13,"method":"resources/read","params":{"uri":"leaderboard://current"}}"#;
+    let response = server.handle_request(request).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&response).unwrap();
+
+    let contents = &parsed["result"]["contents"][0];
+    assert_eq!(contents["mimeType"], "application/json");
+    let board: serde_json::Value =
+        serde_json::from_str(contents["text"].as_str().unwrap()).unwrap();
+    assert_eq!(board["models"], serde_json::json!([]));
+}
+
+#[test]
+fn mcp_server_advertises_compare_models_output_schema() {
+    let server = McpServer::new();
+    let request = r#"{"jsonrpc":"2.0","id":10,"method":"tools/list","params":{}}"#;
+
+    let response = server.handle_request(request).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&response).unwrap();
+
+    let tools = parsed["result"]["tools"].as_array().unwrap();
+    let compare = tools.iter().find(|t| t["name"] == "compare_models").unwrap();
+    assert_eq!(compare["outputSchema"]["type"], "object");
+    assert!(compare["outputSchema"]["properties"]["results"].is_object());
+
+    let spending = tools.iter().find(|t| t["name"] == "get_spending_status").unwrap();
+    assert!(spending.get("outputSchema").is_none());
+}
+
+#[test]
+fn mcp_server_rejects_invalid_score_weights() {
+    let server = McpServer::new();
+    let request = r#"{"jsonrpc":"2.0","id":11,"method":"tools/call","params":{"name":"compare_models","arguments":{"prompt":"p","weights":{"speed":-1}}}}"#;
+
+    let response = server.handle_request(request).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&response).unwrap();
+
+    assert_eq!(parsed["id"], 11);
+    assert_eq!(parsed["error"]["code"], -32602);
+}
@@ -0,0 +1,106 @@
+//! Ollama model warm-up and keep-alive.
+//!
+//! Ollama loads a model into memory on its first request, which can take
+//! several seconds and makes local models look slow next to cloud ones.
+//! Models listed in `[ollama] preload` are loaded at startup with an empty
+//! `/api/generate` request, and `[ollama] keep_alive` is sent with every
+//! forwarded request so they stay loaded between requests.
+
+use reqwest::Client;
+use serde_json::{json, Value};
+
+/// `keep_alive` as Ollama expects it: whole numbers are seconds (`-1` keeps
+/// the model loaded), anything else is a duration string like `"30m"`.
+pub fn keep_alive_value(keep_alive: &str) -> Value {
+    match keep_alive.trim().parse::<i64>() {
+        Ok(secs) => json!(secs),
+        Err(_) => json!(keep_alive),
+    }
+}
+
+/// Load one model without generating anything.
+pub async fn load_model(
+    client: &Client,
+    base_url: &str,
+    model: &str,
+    keep_alive: Option<&str>,
+) -> Result<(), String> {
+    let mut body = json!({ "model": model });
+    if let Some(keep_alive) = keep_alive {
+        body["keep_alive"] = keep_alive_value(keep_alive);
+    }
+    let response = client
+        .post(format!("{}/api/generate", base_url))
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {}", response.status()))
+    }
+}
+
+/// Load each model in turn, logging the outcome. Models are loaded one at a
+/// time so they don't compete for memory while loading.
+pub async fn warm_up(
+    client: Client,
+    base_url: String,
+    models: Vec<String>,
+    keep_alive: Option<String>,
+) {
+    for model in models {
+        let started = std::time::Instant::now();
+        match load_model(&client, &base_url, &model, keep_alive.as_deref()).await {
+            Ok(()) => tracing::info!(
+                "Preloaded Ollama model {} in {:?}",
+                model,
+                started.elapsed()
+            ),
+            Err(e) => tracing::warn!("Failed to preload Ollama model {}: {}", model, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keep_alive_numbers_are_seconds() {
+        assert_eq!(keep_alive_value("-1"), json!(-1));
+        assert_eq!(keep_alive_value("300"), json!(300));
+        assert_eq!(keep_alive_value("30m"), json!("30m"));
+    }
+
+    #[tokio::test]
+    async fn load_model_posts_empty_generate_request() {
+        let mut server = mockito::Server::new_async().await;
+        let generate = server
+            .mock("POST", "/api/generate")
+            .match_body(mockito::Matcher::Json(
+                json!({"model": "llama3", "keep_alive": "30m"}),
+            ))
+            .with_body(r#"{"model": "llama3", "done": true}"#)
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/api/generate")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "missing"})))
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let client = Client::new();
+        load_model(&client, &server.url(), "llama3", Some("30m"))
+            .await
+            .unwrap();
+        generate.assert_async().await;
+
+        let err = load_model(&client, &server.url(), "missing", None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("404"), "{}", err);
+    }
+}
@@ -0,0 +1,258 @@
+//! On-disk cache of completion responses.
+//!
+//! Identical non-streaming requests to the same model are answered from a
+//! SQLite cache, so repeated prompts don't spend free-tier quota and the
+//! cache survives restarts. Entries expire after a TTL, and the least
+//! recently used ones are evicted once the entry or byte limit is exceeded.
+
+use crate::config::CacheConfig;
+use crate::storage;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Cache key for a request: the target model plus the exact body sent upstream.
+pub fn cache_key(model: &str, upstream_request: &serde_json::Value) -> String {
+    format!("{}\n{}", model, upstream_request)
+}
+
+/// Hit/miss counters and current size.
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: u64,
+    pub bytes: u64,
+}
+
+/// SQLite-backed response cache, shared across handlers.
+#[derive(Clone)]
+pub struct ResponseCache {
+    conn: Arc<Mutex<Connection>>,
+    config: CacheConfig,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+}
+
+impl ResponseCache {
+    /// Open the cache in MultiAI's data directory.
+    pub fn open_default(config: CacheConfig) -> SqlResult<Self> {
+        Self::with_connection(storage::open_data_db("cache.db")?, config)
+    }
+
+    /// Open or create a cache database.
+    pub fn open<P: AsRef<Path>>(path: P, config: CacheConfig) -> SqlResult<Self> {
+        Self::with_connection(Connection::open(path)?, config)
+    }
+
+    /// Create an in-memory cache (for testing).
+    pub fn in_memory(config: CacheConfig) -> SqlResult<Self> {
+        Self::with_connection(Connection::open_in_memory()?, config)
+    }
+
+    fn with_connection(conn: Connection, config: CacheConfig) -> SqlResult<Self> {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS responses (
+                key TEXT PRIMARY KEY,
+                model TEXT NOT NULL,
+                body TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                hit_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_responses_hit_at ON responses(hit_at);
+            "#,
+        )?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            config,
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Cached response for `key`, if present and not expired.
+    pub fn get(&self, key: &str) -> Option<serde_json::Value> {
+        let now = Utc::now().timestamp_millis();
+        let conn = self.conn.lock().unwrap();
+        let cached: Option<(String, i64)> = conn
+            .query_row(
+                "SELECT body, created_at FROM responses WHERE key = ?",
+                params![key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .ok()
+            .flatten();
+
+        let body = cached
+            .filter(|(_, created_at)| now - created_at < self.ttl_millis())
+            .and_then(|(body, _)| serde_json::from_str(&body).ok());
+        match &body {
+            Some(_) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                let _ = conn.execute(
+                    "UPDATE responses SET hit_at = ? WHERE key = ?",
+                    params![now, key],
+                );
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        body
+    }
+
+    /// Store a response, then evict expired and excess entries.
+    pub fn put(&self, key: &str, model: &str, body: &serde_json::Value) -> SqlResult<()> {
+        let now = Utc::now().timestamp_millis();
+        let body = body.to_string();
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO responses (key, model, body, size, created_at, hit_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![key, model, body, body.len() as i64, now, now],
+        )?;
+
+        conn.execute(
+            "DELETE FROM responses WHERE created_at <= ?",
+            params![now - self.ttl_millis()],
+        )?;
+        // Least recently used first to go; rowid breaks ties in insertion order
+        conn.execute(
+            "DELETE FROM responses WHERE key IN (
+                SELECT key FROM responses ORDER BY hit_at DESC, rowid DESC LIMIT -1 OFFSET ?
+            )",
+            params![self.config.max_entries as i64],
+        )?;
+        conn.execute(
+            "DELETE FROM responses WHERE key IN (
+                SELECT key FROM (
+                    SELECT key, SUM(size) OVER (ORDER BY hit_at DESC, rowid DESC) AS running
+                    FROM responses
+                ) WHERE running > ?
+            )",
+            params![self.config.max_bytes as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Remove every entry, returning how many there were.
+    pub fn clear(&self) -> SqlResult<usize> {
+        self.conn.lock().unwrap().execute("DELETE FROM responses", [])
+    }
+
+    /// Hit/miss counts since startup and the cache's current size.
+    pub fn stats(&self) -> CacheStats {
+        let (entries, bytes) = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(size), 0) FROM responses",
+                [],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+            )
+            .unwrap_or((0, 0));
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: entries as u64,
+            bytes: bytes as u64,
+        }
+    }
+
+    fn ttl_millis(&self) -> i64 {
+        self.config.ttl_secs.saturating_mul(1000) as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cache(config: CacheConfig) -> ResponseCache {
+        ResponseCache::in_memory(CacheConfig {
+            enabled: true,
+            ..config
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn returns_stored_responses_and_counts_hits() {
+        let cache = cache(CacheConfig::default());
+        let body = json!({"choices": [{"message": {"content": "hi"}}]});
+
+        assert_eq!(cache.get("k"), None);
+        cache.put("k", "m", &body).unwrap();
+        assert_eq!(cache.get("k"), Some(body));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+    }
+
+    #[test]
+    fn expired_entries_are_misses() {
+        let cache = cache(CacheConfig {
+            ttl_secs: 0,
+            ..CacheConfig::default()
+        });
+        cache.put("k", "m", &json!({})).unwrap();
+
+        assert_eq!(cache.get("k"), None);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_over_entry_limit() {
+        let cache = cache(CacheConfig {
+            max_entries: 2,
+            ..CacheConfig::default()
+        });
+        cache.put("a", "m", &json!(1)).unwrap();
+        cache.put("b", "m", &json!(2)).unwrap();
+        cache.put("c", "m", &json!(3)).unwrap();
+
+        assert_eq!(cache.get("a"), None);
+        assert!(cache.get("b").is_some());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn evicts_oldest_over_byte_limit() {
+        let cache = cache(CacheConfig {
+            max_bytes: 25,
+            ..CacheConfig::default()
+        });
+        let body = json!("0123456789");
+        cache.put("a", "m", &body).unwrap();
+        cache.put("b", "m", &body).unwrap();
+        cache.put("c", "m", &body).unwrap();
+
+        assert_eq!(cache.stats().entries, 2);
+        assert_eq!(cache.get("a"), None);
+    }
+
+    #[test]
+    fn clear_removes_everything() {
+        let cache = cache(CacheConfig::default());
+        cache.put("a", "m", &json!(1)).unwrap();
+        cache.put("b", "m", &json!(2)).unwrap();
+
+        assert_eq!(cache.clear().unwrap(), 2);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn cache_key_depends_on_model_and_body() {
+        let body = json!({"messages": []});
+        assert_ne!(cache_key("a", &body), cache_key("b", &body));
+        assert_eq!(cache_key("a", &body), cache_key("a", &body));
+    }
+}
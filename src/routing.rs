@@ -0,0 +1,347 @@
+//! Model routing informed by recent outcomes.
+//!
+//! `ModelStats` keeps a rolling window of success and latency per model,
+//! fed as completions finish. `auto` selection ranks the free models by it,
+//! so models that keep failing sink and fast, reliable ones rise, with the
+//! scanner's source priority breaking ties.
+//!
+//! A model that answers 429 is put in a cooldown for as long as its
+//! `Retry-After` asks, and ranks after every other model until it ends.
+//!
+//! How `auto` orders the models is a [`RoutingStrategy`], set with
+//! `[routing] strategy` or per request.
+
+use crate::scanner::FreeModel;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// Outcomes kept per model.
+const WINDOW_SIZE: usize = 50;
+
+/// Outcomes older than this are forgotten.
+const WINDOW_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Cooldown after a 429 that didn't say when to retry.
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Longest cooldown a `Retry-After` can ask for.
+const MAX_COOLDOWN: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Clone, Copy)]
+struct Outcome {
+    at: Instant,
+    success: bool,
+    latency_ms: u64,
+}
+
+/// How `auto` picks among the free models. Whatever the strategy, models in
+/// a rate-limit cooldown come last.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingStrategy {
+    /// Most reliable recent performers first, then the fastest of them.
+    #[default]
+    Adaptive,
+    /// Source priority, local models first.
+    Priority,
+    /// A different model for each request, in turn.
+    RoundRobin,
+    /// Lowest recent mean latency first; models without history follow.
+    LeastLatency,
+    /// A random model first.
+    Random,
+}
+
+/// Rolling success rate and latency for one model.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+pub struct ModelScore {
+    pub requests: usize,
+    pub success_rate: f64,
+    /// Mean latency of successful requests.
+    pub avg_latency_ms: Option<u64>,
+}
+
+/// Rolling per-model success and latency windows, shared across handlers.
+#[derive(Clone, Default)]
+pub struct ModelStats {
+    windows: Arc<Mutex<HashMap<String, VecDeque<Outcome>>>>,
+    /// When each rate-limited model's cooldown ends.
+    cooldowns: Arc<Mutex<HashMap<String, Instant>>>,
+    /// Requests routed round-robin so far.
+    turns: Arc<AtomicUsize>,
+}
+
+impl ModelStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the outcome of a request to `model`.
+    pub fn record(&self, model: &str, success: bool, latency: Duration) {
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.entry(model.to_string()).or_default();
+        window.push_back(Outcome {
+            at: Instant::now(),
+            success,
+            latency_ms: latency.as_millis() as u64,
+        });
+        while window.len() > WINDOW_SIZE {
+            window.pop_front();
+        }
+    }
+
+    /// Recent score for `model`, or None if it has no recent requests.
+    pub fn score(&self, model: &str) -> Option<ModelScore> {
+        let windows = self.windows.lock().unwrap();
+        let recent: Vec<Outcome> = windows
+            .get(model)?
+            .iter()
+            .filter(|o| o.at.elapsed() < WINDOW_AGE)
+            .copied()
+            .collect();
+        if recent.is_empty() {
+            return None;
+        }
+
+        let successes: Vec<u64> = recent
+            .iter()
+            .filter(|o| o.success)
+            .map(|o| o.latency_ms)
+            .collect();
+        Some(ModelScore {
+            requests: recent.len(),
+            success_rate: successes.len() as f64 / recent.len() as f64,
+            avg_latency_ms: (!successes.is_empty())
+                .then(|| successes.iter().sum::<u64>() / successes.len() as u64),
+        })
+    }
+
+    /// Put `model` in a cooldown after a 429, for `retry_after` when the
+    /// upstream gave one. Returns the cooldown when this starts one rather
+    /// than extending it.
+    pub fn rate_limited(&self, model: &str, retry_after: Option<Duration>) -> Option<Duration> {
+        let cooldown = retry_after.unwrap_or(DEFAULT_COOLDOWN).min(MAX_COOLDOWN);
+        let now = Instant::now();
+        let previous = self
+            .cooldowns
+            .lock()
+            .unwrap()
+            .insert(model.to_string(), now + cooldown);
+        previous.is_none_or(|until| until <= now).then_some(cooldown)
+    }
+
+    /// Time left in `model`'s rate-limit cooldown, if it is in one.
+    pub fn cooldown(&self, model: &str) -> Option<Duration> {
+        let mut cooldowns = self.cooldowns.lock().unwrap();
+        let until = *cooldowns.get(model)?;
+        let left = until
+            .checked_duration_since(Instant::now())
+            .filter(|left| !left.is_zero());
+        if left.is_none() {
+            cooldowns.remove(model);
+        }
+        left
+    }
+
+    /// Order `models` for `strategy`, the first being the one to route to.
+    pub fn order(&self, models: &[FreeModel], strategy: RoutingStrategy) -> Vec<FreeModel> {
+        let mut ordered = match strategy {
+            RoutingStrategy::Adaptive => return self.rank(models),
+            RoutingStrategy::Priority => models.to_vec(),
+            RoutingStrategy::RoundRobin => {
+                let turn = self.turns.fetch_add(1, Ordering::Relaxed);
+                rotated(models, turn)
+            }
+            RoutingStrategy::LeastLatency => {
+                let mut models = models.to_vec();
+                models.sort_by_cached_key(|m| {
+                    self.score(&m.id)
+                        .and_then(|s| s.avg_latency_ms)
+                        .unwrap_or(u64::MAX)
+                });
+                models
+            }
+            RoutingStrategy::Random => {
+                let random = RandomState::new().build_hasher().finish() as usize;
+                rotated(models, random)
+            }
+        };
+        ordered.sort_by_cached_key(|m| self.cooldown(&m.id).is_some());
+        ordered
+    }
+
+    /// Order `models` best first, with rate-limited models last. The sort
+    /// is stable, so models without history keep their source priority.
+    pub fn rank(&self, models: &[FreeModel]) -> Vec<FreeModel> {
+        let mut ranked: Vec<(FreeModel, bool, u32, u64)> = models
+            .iter()
+            .map(|m| {
+                let score = self.score(&m.id);
+                // Smoothed so one lucky request doesn't outrank a long record;
+                // models without history sit at 0.5
+                let reliability = score
+                    .map(|s| {
+                        let successes = s.success_rate * s.requests as f64;
+                        (successes + 1.0) / (s.requests as f64 + 2.0)
+                    })
+                    .unwrap_or(0.5);
+                // Bucket reliability so latency only decides between similar models
+                let bucket = (reliability * 10.0).round() as u32;
+                let latency = score.and_then(|s| s.avg_latency_ms).unwrap_or(u64::MAX);
+                (m.clone(), self.cooldown(&m.id).is_some(), bucket, latency)
+            })
+            .collect();
+        ranked.sort_by(|a, b| a.1.cmp(&b.1).then(b.2.cmp(&a.2)).then(a.3.cmp(&b.3)));
+        ranked.into_iter().map(|(m, ..)| m).collect()
+    }
+}
+
+/// `models` starting from the `n`th, wrapping around.
+fn rotated(models: &[FreeModel], n: usize) -> Vec<FreeModel> {
+    let mut models = models.to_vec();
+    if !models.is_empty() {
+        let len = models.len();
+        models.rotate_left(n % len);
+    }
+    models
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Source;
+
+    fn model(id: &str) -> FreeModel {
+        FreeModel {
+            id: id.to_string(),
+            provider: "test".to_string(),
+            endpoint: "http://localhost".to_string(),
+            source: Source::OpenRouter,
+            context_length: None,
+            modalities: Vec::new(),
+            supports_tools: None,
+            supports_response_format: None,
+            source_index: None,
+        }
+    }
+
+    fn ids(models: &[FreeModel]) -> Vec<&str> {
+        models.iter().map(|m| m.id.as_str()).collect()
+    }
+
+    #[test]
+    fn scores_success_rate_and_latency() {
+        let stats = ModelStats::new();
+        stats.record("a", true, Duration::from_millis(100));
+        stats.record("a", true, Duration::from_millis(300));
+        stats.record("a", false, Duration::from_millis(5000));
+
+        let score = stats.score("a").unwrap();
+        assert_eq!(score.requests, 3);
+        assert!((score.success_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(score.avg_latency_ms, Some(200));
+        assert!(stats.score("b").is_none());
+    }
+
+    #[test]
+    fn window_keeps_recent_outcomes_only() {
+        let stats = ModelStats::new();
+        for _ in 0..WINDOW_SIZE {
+            stats.record("a", false, Duration::ZERO);
+        }
+        stats.record("a", true, Duration::ZERO);
+
+        assert_eq!(stats.score("a").unwrap().requests, WINDOW_SIZE);
+    }
+
+    #[test]
+    fn failing_models_sink_below_untried_ones() {
+        let stats = ModelStats::new();
+        for _ in 0..3 {
+            stats.record("first", false, Duration::from_millis(100));
+        }
+
+        let ranked = stats.rank(&[model("first"), model("second"), model("third")]);
+        assert_eq!(ids(&ranked), ["second", "third", "first"]);
+    }
+
+    #[test]
+    fn faster_model_wins_between_equally_reliable_ones() {
+        let stats = ModelStats::new();
+        for _ in 0..5 {
+            stats.record("slow", true, Duration::from_millis(2000));
+            stats.record("fast", true, Duration::from_millis(200));
+        }
+
+        let ranked = stats.rank(&[model("slow"), model("fast")]);
+        assert_eq!(ids(&ranked), ["fast", "slow"]);
+    }
+
+    #[test]
+    fn untried_models_keep_priority_order() {
+        let stats = ModelStats::new();
+        let ranked = stats.rank(&[model("a"), model("b"), model("c")]);
+        assert_eq!(ids(&ranked), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn rate_limited_models_rank_last_until_cooldown_ends() {
+        let stats = ModelStats::new();
+        for _ in 0..5 {
+            stats.record("a", true, Duration::from_millis(100));
+        }
+        stats.rate_limited("a", Some(Duration::from_secs(30)));
+        stats.rate_limited("b", Some(Duration::ZERO));
+
+        assert!(stats.cooldown("a").unwrap() <= Duration::from_secs(30));
+        assert!(stats.cooldown("b").is_none());
+        let ranked = stats.rank(&[model("a"), model("b"), model("c")]);
+        assert_eq!(ids(&ranked), ["b", "c", "a"]);
+    }
+
+    #[test]
+    fn cooldowns_are_capped() {
+        let stats = ModelStats::new();
+        stats.rate_limited("a", Some(Duration::from_secs(24 * 60 * 60)));
+        assert!(stats.cooldown("a").unwrap() <= MAX_COOLDOWN);
+    }
+
+    #[test]
+    fn strategies_pick_the_first_model_differently() {
+        let models = vec![model("a"), model("b"), model("c")];
+        let stats = ModelStats::new();
+        stats.record("a", true, Duration::from_millis(900));
+        stats.record("b", true, Duration::from_millis(100));
+
+        assert_eq!(ids(&stats.order(&models, RoutingStrategy::Priority)), ["a", "b", "c"]);
+        assert_eq!(ids(&stats.order(&models, RoutingStrategy::LeastLatency)), ["b", "a", "c"]);
+        let firsts: Vec<String> = (0..4)
+            .map(|_| stats.order(&models, RoutingStrategy::RoundRobin)[0].id.clone())
+            .collect();
+        assert_eq!(firsts, ["a", "b", "c", "a"]);
+        assert_eq!(stats.order(&models, RoutingStrategy::Random).len(), 3);
+    }
+
+    #[test]
+    fn every_strategy_puts_rate_limited_models_last() {
+        let models = vec![model("a"), model("b")];
+        let stats = ModelStats::new();
+        stats.rate_limited("a", None);
+
+        for strategy in [
+            RoutingStrategy::Adaptive,
+            RoutingStrategy::Priority,
+            RoutingStrategy::RoundRobin,
+            RoutingStrategy::LeastLatency,
+            RoutingStrategy::Random,
+        ] {
+            assert_eq!(stats.order(&models, strategy)[0].id, "b", "{:?}", strategy);
+        }
+    }
+}
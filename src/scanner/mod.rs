@@ -1,20 +1,52 @@
 //! FreeModelScanner - Discovers free LLM models from multiple sources.
 //!
 //! Sources:
+//! - Ollama, LM Studio and llama.cpp: local servers (every model is free)
 //! - OpenRouter: /api/v1/models (pricing.prompt=0 means free)
-//! - OpenCode Zen: /zen/v1/models (parses pricing table for "Free" models)
+//! - OpenCode Zen: /zen/v1/models (free by API pricing, else the docs pricing
+//!   table, else a bundled list)
+//! - Groq: /openai/v1/models (every chat model is on the free tier; needs a key)
+//! - Google AI Studio: /v1beta/models (Gemini flash and Gemma models; needs a key)
+//! - Cerebras: /v1/models (every model is on the free tier; needs a key)
+//! - Mistral: /v1/models (zero-priced or open-weight chat models; needs a key)
+//! - Together AI: /v1/models (zero-priced chat models; needs a key)
+//! - SambaNova Cloud: /v1/models (Llama family models on the free tier; needs a key)
+//! - GitHub Models: /models (chat models in the catalog, free with a PAT)
+//! - Custom: user-defined OpenAI-compatible endpoints (`[[providers.custom]]`)
+//! - Cloudflare Workers AI: /accounts/{id}/ai/models/search (beta or unpriced
+//!   text generation models; needs an account id and API token)
+//!
+//! With a disk cache set, the last good list is saved after every scan and
+//! loaded on startup, so models can be served before the first scan
+//! finishes and a source that can't be reached keeps its last known models.
+//!
+//! The sources scanned are the [`ModelSource`]s in the scanner's
+//! [`SourceRegistry`]; see the `source` module for adding one.
+//!
+//! Each scan is compared with the one before it, and the free models that
+//! appeared or disappeared are broadcast to `subscribe_changes` receivers.
 
+mod source;
 #[cfg(test)]
 mod tests;
 
+pub use source::{FetchFuture, ModelSource, SourceAuth, SourceRegistry};
+
+use crate::config::{Config, CustomProviderConfig, ScannerConfig};
 use crate::http::{create_blocking_client, create_client, create_client_with_timeout, DETECTION_TIMEOUT};
+use chrono::{DateTime, Utc};
 use moka::future::Cache;
 use reqwest::Client;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
 
 /// A free model discovered from an API source.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -23,20 +55,217 @@ pub struct FreeModel {
     pub provider: String,
     pub endpoint: String,
     pub source: Source,
+    /// Maximum context window in tokens, when the source reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_length: Option<u64>,
+    /// Input modalities such as "text" and "image", when the source reports them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modalities: Vec<String>,
+    /// Whether the model accepts `tools`, when the source reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supports_tools: Option<bool>,
+    /// Whether the model accepts `response_format`, when the source reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supports_response_format: Option<bool>,
+    /// Position in the [`SourceRegistry`] of the source that found the
+    /// model, set by the scan so requests use that source's auth.
+    #[serde(skip)]
+    pub source_index: Option<usize>,
 }
 
 /// Source of the free model information.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord, ToSchema,
+)]
 #[serde(rename_all = "snake_case")]
 pub enum Source {
     /// Local Ollama instance (highest priority)
     Ollama,
+    /// Local LM Studio server (local priority, after Ollama)
+    LmStudio,
+    /// Local llama.cpp `llama-server` (local priority)
+    LlamaCpp,
     /// OpenCode Zen cloud API
     OpenCodeZen,
     /// OpenRouter cloud API
     OpenRouter,
+    /// Groq cloud API (free tier, rate limited)
+    Groq,
+    /// Google AI Studio Gemini API (free tier, rate limited)
+    Gemini,
+    /// Cerebras cloud API (free tier, rate limited)
+    Cerebras,
+    /// Mistral La Plateforme (free experiment tier)
+    Mistral,
+    /// Together AI (free and promotional models)
+    Together,
+    /// SambaNova Cloud (free tier, rate limited)
+    SambaNova,
+    /// GitHub Models catalog (free with a personal access token)
+    GitHubModels,
+    /// Cloudflare Workers AI (free daily allocation)
+    Cloudflare,
+    /// User-defined OpenAI-compatible endpoint (`[[providers.custom]]`)
+    Custom,
+}
+
+impl Source {
+    /// Every source, in priority order.
+    pub const ALL: [Source; 14] = [
+        Source::Ollama,
+        Source::LmStudio,
+        Source::LlamaCpp,
+        Source::OpenCodeZen,
+        Source::OpenRouter,
+        Source::Groq,
+        Source::Gemini,
+        Source::Cerebras,
+        Source::Mistral,
+        Source::Together,
+        Source::SambaNova,
+        Source::GitHubModels,
+        Source::Cloudflare,
+        Source::Custom,
+    ];
+}
+
+/// Input modalities, tool support and `response_format` support from a
+/// model listing entry. Reads OpenRouter's `architecture.input_modalities`
+/// and `supported_parameters`, falling back to the `modalities.input` and
+/// `tool_call` fields other listings use.
+fn model_capabilities(model: &Value) -> (Vec<String>, Option<bool>, Option<bool>) {
+    let modalities = model["architecture"]["input_modalities"]
+        .as_array()
+        .or_else(|| model["modalities"]["input"].as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|m| m.as_str().map(str::to_string))
+        .collect();
+    let supports_tools = model["supported_parameters"]
+        .as_array()
+        .map(|params| params.iter().any(|p| p == "tools"))
+        .or_else(|| model["tool_call"].as_bool());
+    let supports_response_format = model["supported_parameters"]
+        .as_array()
+        .map(|params| params.iter().any(|p| p == "response_format" || p == "structured_outputs"));
+    (modalities, supports_tools, supports_response_format)
 }
 
+/// Whether an Ollama `/api/tags` entry is an embedding model, by its name
+/// (`nomic-embed-text`, `bge-m3`, `all-minilm`) or its BERT model family.
+fn is_ollama_embedding(model: &Value) -> bool {
+    let name = model["name"].as_str().unwrap_or_default().to_lowercase();
+    let details = &model["details"];
+    let bert_family = details["family"]
+        .as_str()
+        .into_iter()
+        .chain(
+            details["families"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str),
+        )
+        .any(|family| family.contains("bert"));
+    bert_family || ["embed", "bge", "minilm"].iter().any(|n| name.contains(n))
+}
+
+/// Models OpenCode Zen lists as free, for when neither the API nor the docs
+/// say which are. Matched against the API's model ids like the docs table.
+const BUNDLED_ZEN_FREE_MODELS: &[&str] = &[
+    "Big Pickle",
+    "Grok Code Fast 1",
+    "MiniMax M2.1",
+    "GLM 4.7",
+    "GPT 5 Nano",
+];
+
+/// Whether an OpenCode Zen API model costs nothing, from either a models.dev
+/// style `cost` object or an OpenRouter style `pricing` object. `None` when
+/// the model carries no pricing.
+fn zen_api_is_free(model: &Value) -> Option<bool> {
+    let price = |value: &Value| {
+        value
+            .as_f64()
+            .or_else(|| value.as_str().and_then(|p| p.parse::<f64>().ok()))
+    };
+    let (input, output) = if model["cost"].is_object() {
+        (&model["cost"]["input"], &model["cost"]["output"])
+    } else if model["pricing"].is_object() {
+        (&model["pricing"]["prompt"], &model["pricing"]["completion"])
+    } else {
+        return None;
+    };
+    Some(price(input)? == 0.0 && price(output)? == 0.0)
+}
+
+fn opencode_zen_model(model: &Value) -> Option<FreeModel> {
+    let (modalities, supports_tools, supports_response_format) = model_capabilities(model);
+    Some(FreeModel {
+        id: model["id"].as_str()?.to_string(),
+        provider: "opencode-zen".to_string(),
+        endpoint: "https://opencode.ai/zen/v1".to_string(),
+        source: Source::OpenCodeZen,
+        context_length: model["context_length"]
+            .as_u64()
+            .or_else(|| model["limit"]["context"].as_u64()),
+        modalities,
+        supports_tools,
+        supports_response_format,
+        source_index: None,
+    })
+}
+
+/// How a source's free models were told apart from paid ones.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FreeDetection {
+    /// Pricing fields in the source's model list.
+    Pricing,
+    /// The pricing table on the source's docs page.
+    DocsTable,
+    /// The list of free models bundled with multiai.
+    Bundled,
+}
+
+/// Outcome of the most recent scan of one source.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, ToSchema)]
+pub struct SourceStatus {
+    pub last_refresh: Option<DateTime<Utc>>,
+    /// When a scan of the source last succeeded.
+    pub last_success: Option<DateTime<Utc>>,
+    /// Error from the last scan, if it failed.
+    pub last_error: Option<String>,
+    pub model_count: usize,
+    /// How free models were detected, for sources that have to work it out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detection: Option<FreeDetection>,
+}
+
+
+/// Read a model list saved by [`save_models`]; `None` if there is no file.
+fn load_saved_models(path: &Path) -> std::io::Result<Option<SavedModels>> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Save a model list, writing a temporary file first so a crash can't
+/// leave a truncated cache behind.
+fn save_models(path: &Path, models: &[FreeModel]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let saved = SavedModels {
+        saved_at: Utc::now(),
+        models: models.to_vec(),
+    };
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(&saved)?)?;
+    std::fs::rename(&tmp, path)
+}
 
 /// Scanner configuration.
 #[derive(Clone)]
@@ -46,18 +275,143 @@ pub struct FreeModelScanner {
     opencode_zen_api_url: String,
     opencode_zen_docs_url: String,
     ollama_url: Option<String>,
-    cache: Cache<String, Arc<Vec<FreeModel>>>,
+    lmstudio_url: Option<String>,
+    llamacpp_url: Option<String>,
+    groq_url: String,
+    /// Groq lists models only for authenticated requests, so it is scanned
+    /// only when a key is set.
+    groq_api_key: Option<String>,
+    gemini_url: String,
+    /// Scanned only when a Google AI Studio key is set.
+    gemini_api_key: Option<String>,
+    cerebras_url: String,
+    /// Scanned only when a Cerebras key is set.
+    cerebras_api_key: Option<String>,
+    mistral_url: String,
+    /// Scanned only when a Mistral key is set.
+    mistral_api_key: Option<String>,
+    together_url: String,
+    /// Scanned only when a Together AI key is set.
+    together_api_key: Option<String>,
+    sambanova_url: String,
+    /// Scanned only when a SambaNova key is set.
+    sambanova_api_key: Option<String>,
+    github_models_url: String,
+    /// Scanned only when a GitHub token is set.
+    github_models_api_key: Option<String>,
+    cloudflare_url: String,
+    /// Workers AI catalogs are per account; scanned only when both the
+    /// account id and API token are set.
+    cloudflare_account_id: Option<String>,
+    cloudflare_api_key: Option<String>,
+    custom: Vec<CustomProviderConfig>,
+    /// Each source's last successful scan, expiring after its TTL.
+    cache: Cache<Source, Arc<Vec<FreeModel>>>,
+    /// Embedding models found by each source's last scan, with the same TTLs.
+    embedding_cache: Cache<Source, Arc<Vec<FreeModel>>>,
+    ttls: SourceTtls,
+    status: Arc<Mutex<BTreeMap<Source, SourceStatus>>>,
+    /// Where the last good list is saved, if anywhere.
+    disk_cache: Option<PathBuf>,
+    /// Last good list, from the previous scan or the disk cache.
+    last_known: Arc<Mutex<Vec<FreeModel>>>,
+    changes: broadcast::Sender<ModelChanges>,
+    /// Strategy the last OpenCode Zen scan used to find free models.
+    zen_detection: Arc<Mutex<Option<FreeDetection>>>,
+    /// Sources to scan, the built-in ones unless replaced.
+    registry: SourceRegistry,
+}
+
+/// Free models that appeared or disappeared between two scans.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct ModelChanges {
+    pub added: Vec<FreeModel>,
+    pub removed: Vec<FreeModel>,
+}
+
+impl ModelChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Models in `after` but not `before`, and the other way round. A model is
+/// identified by its source and id.
+pub fn diff_models(before: &[FreeModel], after: &[FreeModel]) -> ModelChanges {
+    let missing_from = |list: &[FreeModel], model: &FreeModel| {
+        !list.iter().any(|m| m.source == model.source && m.id == model.id)
+    };
+    ModelChanges {
+        added: after.iter().filter(|m| missing_from(before, m)).cloned().collect(),
+        removed: before.iter().filter(|m| missing_from(after, m)).cloned().collect(),
+    }
+}
+
+/// How long each source's scan stays cached.
+#[derive(Debug, Clone)]
+struct SourceTtls {
+    default: Duration,
+    per_source: BTreeMap<Source, Duration>,
+}
+
+impl SourceTtls {
+    fn build_cache(&self) -> Cache<Source, Arc<Vec<FreeModel>>> {
+        Cache::builder().expire_after(self.clone()).build()
+    }
+}
+
+impl moka::Expiry<Source, Arc<Vec<FreeModel>>> for SourceTtls {
+    fn expire_after_create(
+        &self,
+        source: &Source,
+        _models: &Arc<Vec<FreeModel>>,
+        _created_at: std::time::Instant,
+    ) -> Option<Duration> {
+        Some(self.per_source.get(source).copied().unwrap_or(self.default))
+    }
+
+    /// A rescan starts the TTL over.
+    fn expire_after_update(
+        &self,
+        source: &Source,
+        models: &Arc<Vec<FreeModel>>,
+        updated_at: std::time::Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        self.expire_after_create(source, models, updated_at)
+    }
+}
+
+/// Contents of the on-disk model cache.
+#[derive(Serialize, Deserialize)]
+struct SavedModels {
+    saved_at: DateTime<Utc>,
+    models: Vec<FreeModel>,
 }
 
 impl FreeModelScanner {
+    /// Where LM Studio's local server listens unless configured otherwise.
+    pub const DEFAULT_LMSTUDIO_URL: &'static str = "http://127.0.0.1:1234";
+    /// Where `llama-server` listens unless configured otherwise.
+    pub const DEFAULT_LLAMACPP_URL: &'static str = "http://127.0.0.1:8080";
     const DEFAULT_OPENROUTER_URL: &'static str = "https://openrouter.ai/api/v1/models";
     const DEFAULT_OPENCODE_ZEN_API_URL: &'static str = "https://opencode.ai/zen/v1/models";
     const DEFAULT_OPENCODE_ZEN_DOCS_URL: &'static str = "https://opencode.ai/docs/zen";
+    const DEFAULT_GROQ_URL: &'static str = "https://api.groq.com/openai/v1/models";
+    const DEFAULT_GEMINI_URL: &'static str =
+        "https://generativelanguage.googleapis.com/v1beta/models";
+    const DEFAULT_CEREBRAS_URL: &'static str = "https://api.cerebras.ai/v1/models";
+    const DEFAULT_MISTRAL_URL: &'static str = "https://api.mistral.ai/v1/models";
+    const DEFAULT_TOGETHER_URL: &'static str = "https://api.together.xyz/v1/models";
+    const DEFAULT_SAMBANOVA_URL: &'static str = "https://api.sambanova.ai/v1/models";
+    const DEFAULT_GITHUB_MODELS_URL: &'static str = "https://models.inference.ai.azure.com/models";
+    const DEFAULT_CLOUDFLARE_URL: &'static str = "https://api.cloudflare.com/client/v4";
 
     pub fn new() -> Self {
-        let cache = Cache::builder()
-            .time_to_live(Duration::from_secs(3600)) // 1 hour - model lists rarely change
-            .build();
+        let ttls = SourceTtls {
+            default: Duration::from_secs(3600), // 1 hour - model lists rarely change
+            per_source: BTreeMap::new(),
+        };
 
         Self {
             client: create_client(),
@@ -65,8 +419,64 @@ impl FreeModelScanner {
             opencode_zen_api_url: Self::DEFAULT_OPENCODE_ZEN_API_URL.to_string(),
             opencode_zen_docs_url: Self::DEFAULT_OPENCODE_ZEN_DOCS_URL.to_string(),
             ollama_url: None,
-            cache,
+            lmstudio_url: None,
+            llamacpp_url: None,
+            groq_url: Self::DEFAULT_GROQ_URL.to_string(),
+            groq_api_key: None,
+            gemini_url: Self::DEFAULT_GEMINI_URL.to_string(),
+            gemini_api_key: None,
+            cerebras_url: Self::DEFAULT_CEREBRAS_URL.to_string(),
+            cerebras_api_key: None,
+            mistral_url: Self::DEFAULT_MISTRAL_URL.to_string(),
+            mistral_api_key: None,
+            together_url: Self::DEFAULT_TOGETHER_URL.to_string(),
+            together_api_key: None,
+            sambanova_url: Self::DEFAULT_SAMBANOVA_URL.to_string(),
+            sambanova_api_key: None,
+            github_models_url: Self::DEFAULT_GITHUB_MODELS_URL.to_string(),
+            github_models_api_key: None,
+            cloudflare_url: Self::DEFAULT_CLOUDFLARE_URL.to_string(),
+            cloudflare_account_id: None,
+            cloudflare_api_key: None,
+            custom: Vec::new(),
+            cache: ttls.build_cache(),
+            embedding_cache: ttls.build_cache(),
+            ttls,
+            status: Arc::new(Mutex::new(BTreeMap::new())),
+            disk_cache: None,
+            last_known: Arc::new(Mutex::new(Vec::new())),
+            changes: broadcast::channel(16).0,
+            zen_detection: Arc::new(Mutex::new(None)),
+            registry: SourceRegistry::builtin(),
+        }
+    }
+
+    /// Default location of the on-disk model cache.
+    pub fn default_disk_cache_path() -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("multiai")
+            .join("models.json")
+    }
+
+    /// Save each scan's results to `path`, and start from the list saved
+    /// there by a previous run.
+    pub fn with_disk_cache(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        match load_saved_models(&path) {
+            Ok(Some(saved)) => {
+                tracing::info!(
+                    "Loaded {} cached models saved at {}",
+                    saved.models.len(),
+                    saved.saved_at
+                );
+                *self.last_known.lock().unwrap() = saved.models;
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Ignoring model cache {}: {}", path.display(), e),
         }
+        self.disk_cache = Some(path);
+        self
     }
 
     /// Set the Ollama endpoint URL (e.g., "http://127.0.0.1:11434")
@@ -89,6 +499,49 @@ impl FreeModelScanner {
         false
     }
 
+    /// Set the LM Studio server URL (e.g., "http://127.0.0.1:1234")
+    pub fn with_lmstudio_url(mut self, url: &str) -> Self {
+        self.lmstudio_url = Some(url.to_string());
+        self
+    }
+
+    /// Check if a URL is an LM Studio server by calling /v1/models. LM Studio
+    /// answers with an OpenAI model list; MultiAI gateways are excluded since
+    /// they serve the same route.
+    pub async fn detect_lmstudio(url: &str) -> bool {
+        let client = create_client_with_timeout(DETECTION_TIMEOUT);
+        let models_url = format!("{}/v1/models", url);
+        if let Ok(response) = client.get(&models_url).send().await {
+            if response.status().is_success() {
+                if let Ok(data) = response.json::<Value>().await {
+                    return data["data"].is_array() && !Self::detect_multiai(url).await;
+                }
+            }
+        }
+        false
+    }
+
+    /// Set the llama.cpp server URL (e.g., "http://127.0.0.1:8080")
+    pub fn with_llamacpp_url(mut self, url: &str) -> Self {
+        self.llamacpp_url = Some(url.to_string());
+        self
+    }
+
+    /// Check if a URL is a llama.cpp `llama-server` by calling /props, which
+    /// only it serves.
+    pub async fn detect_llamacpp(url: &str) -> bool {
+        let client = create_client_with_timeout(DETECTION_TIMEOUT);
+        let props_url = format!("{}/props", url);
+        if let Ok(response) = client.get(&props_url).send().await {
+            if response.status().is_success() {
+                if let Ok(data) = response.json::<Value>().await {
+                    return data.get("default_generation_settings").is_some();
+                }
+            }
+        }
+        false
+    }
+
     /// Check if a URL is an Ollama instance (blocking version for sync contexts).
     pub fn detect_ollama_blocking(url: &str) -> bool {
         let client = create_blocking_client(Duration::from_secs(1));
@@ -117,6 +570,28 @@ impl FreeModelScanner {
         false
     }
 
+    /// Use an existing HTTP client, sharing its connection pool.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// HTTP client for scans, for sources fetching their own models.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Scan the sources in `registry` instead of the built-in ones.
+    pub fn with_registry(mut self, registry: SourceRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// The sources this scanner scans.
+    pub fn registry(&self) -> &SourceRegistry {
+        &self.registry
+    }
+
     pub fn with_openrouter_url(mut self, url: &str) -> Self {
         self.openrouter_url = url.to_string();
         self
@@ -132,15 +607,170 @@ impl FreeModelScanner {
         self
     }
 
+    pub fn with_groq_url(mut self, url: &str) -> Self {
+        self.groq_url = url.to_string();
+        self
+    }
+
+    /// Scan Groq with this key.
+    pub fn with_groq_api_key(mut self, key: &str) -> Self {
+        self.groq_api_key = Some(key.to_string());
+        self
+    }
+
+    pub fn with_gemini_url(mut self, url: &str) -> Self {
+        self.gemini_url = url.to_string();
+        self
+    }
+
+    /// Scan Google AI Studio with this key.
+    pub fn with_gemini_api_key(mut self, key: &str) -> Self {
+        self.gemini_api_key = Some(key.to_string());
+        self
+    }
+
+    pub fn with_cerebras_url(mut self, url: &str) -> Self {
+        self.cerebras_url = url.to_string();
+        self
+    }
+
+    /// Scan Cerebras with this key.
+    pub fn with_cerebras_api_key(mut self, key: &str) -> Self {
+        self.cerebras_api_key = Some(key.to_string());
+        self
+    }
+
+    pub fn with_mistral_url(mut self, url: &str) -> Self {
+        self.mistral_url = url.to_string();
+        self
+    }
+
+    /// Scan Mistral with this key.
+    pub fn with_mistral_api_key(mut self, key: &str) -> Self {
+        self.mistral_api_key = Some(key.to_string());
+        self
+    }
+
+    pub fn with_together_url(mut self, url: &str) -> Self {
+        self.together_url = url.to_string();
+        self
+    }
+
+    /// Scan Together AI with this key.
+    pub fn with_together_api_key(mut self, key: &str) -> Self {
+        self.together_api_key = Some(key.to_string());
+        self
+    }
+
+    pub fn with_sambanova_url(mut self, url: &str) -> Self {
+        self.sambanova_url = url.to_string();
+        self
+    }
+
+    /// Scan SambaNova with this key.
+    pub fn with_sambanova_api_key(mut self, key: &str) -> Self {
+        self.sambanova_api_key = Some(key.to_string());
+        self
+    }
+
+    pub fn with_github_models_url(mut self, url: &str) -> Self {
+        self.github_models_url = url.to_string();
+        self
+    }
+
+    /// Scan GitHub Models with this token.
+    pub fn with_github_models_api_key(mut self, key: &str) -> Self {
+        self.github_models_api_key = Some(key.to_string());
+        self
+    }
+
+    /// Set the Cloudflare API base (e.g., "https://api.cloudflare.com/client/v4").
+    pub fn with_cloudflare_url(mut self, url: &str) -> Self {
+        self.cloudflare_url = url.to_string();
+        self
+    }
+
+    /// Scan this account's Workers AI catalog with an API token.
+    pub fn with_cloudflare_account(mut self, account_id: &str, api_token: &str) -> Self {
+        self.cloudflare_account_id = Some(account_id.to_string());
+        self.cloudflare_api_key = Some(api_token.to_string());
+        self
+    }
+
+    /// Offer models from these user-defined endpoints.
+    pub fn with_custom_providers(mut self, providers: Vec<CustomProviderConfig>) -> Self {
+        self.custom = providers;
+        self
+    }
+
+    /// Scan the key-gated sources whose keys are set in `config`, and the
+    /// custom endpoints it defines.
+    pub fn with_keys_from(mut self, config: &Config) -> Self {
+        if let Some(key) = config.get_api_key(&Source::Groq) {
+            self = self.with_groq_api_key(&key);
+        }
+        if let Some(key) = config.get_api_key(&Source::Gemini) {
+            self = self.with_gemini_api_key(&key);
+        }
+        if let Some(key) = config.get_api_key(&Source::Cerebras) {
+            self = self.with_cerebras_api_key(&key);
+        }
+        if let Some(key) = config.get_api_key(&Source::Mistral) {
+            self = self.with_mistral_api_key(&key);
+        }
+        if let Some(key) = config.get_api_key(&Source::Together) {
+            self = self.with_together_api_key(&key);
+        }
+        if let Some(key) = config.get_api_key(&Source::SambaNova) {
+            self = self.with_sambanova_api_key(&key);
+        }
+        if let Some(key) = config.get_api_key(&Source::GitHubModels) {
+            self = self.with_github_models_api_key(&key);
+        }
+        if let (Some(account_id), Some(token)) = (
+            config.providers.cloudflare.account_id.as_deref(),
+            config.get_api_key(&Source::Cloudflare),
+        ) {
+            self = self.with_cloudflare_account(account_id, &token);
+        }
+        if !config.providers.custom.is_empty() {
+            self = self.with_custom_providers(config.providers.custom.clone());
+        }
+        self
+    }
+
+    /// How long a source's scan is reused, unless set for that source.
     pub fn with_cache_ttl_secs(mut self, secs: u64) -> Self {
-        self.cache = Cache::builder()
-            .time_to_live(Duration::from_secs(secs))
-            .build();
+        self.ttls.default = Duration::from_secs(secs);
+        self.cache = self.ttls.build_cache();
+        self.embedding_cache = self.ttls.build_cache();
+        self
+    }
+
+    /// How long scans of `source` are reused.
+    pub fn with_source_ttl_secs(mut self, source: Source, secs: u64) -> Self {
+        self.ttls.per_source.insert(source, Duration::from_secs(secs));
+        self.cache = self.ttls.build_cache();
+        self.embedding_cache = self.ttls.build_cache();
+        self
+    }
+
+    /// Apply the cache TTLs from `[scanner]`.
+    pub fn with_ttls_from(mut self, config: &ScannerConfig) -> Self {
+        if let Some(secs) = config.ttl_secs {
+            self = self.with_cache_ttl_secs(secs);
+        }
+        for source in Source::ALL {
+            if let Some(secs) = config.source_ttl_secs(source) {
+                self = self.with_source_ttl_secs(source, secs);
+            }
+        }
         self
     }
 
     /// Fetch models from local Ollama instance.
-    /// All Ollama models are "free" (local inference).
+    /// All Ollama models are "free" (local inference); embedding models are
+    /// listed by [`Self::get_embedding_models`] instead.
     pub async fn fetch_ollama(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
         let Some(base_url) = &self.ollama_url else {
             return Ok(Vec::new());
@@ -158,6 +788,7 @@ impl FreeModelScanner {
 
         Ok(models
             .iter()
+            .filter(|model| !is_ollama_embedding(model))
             .filter_map(|model| {
                 let name = model["name"].as_str()?;
                 Some(FreeModel {
@@ -165,6 +796,156 @@ impl FreeModelScanner {
                     provider: "ollama".to_string(),
                     endpoint: base_url.clone(),
                     source: Source::Ollama,
+                    context_length: None,
+                    modalities: Vec::new(),
+                    supports_tools: None,
+                    supports_response_format: None,
+                    source_index: None,
+                })
+            })
+            .collect())
+    }
+
+    /// Fetch the embedding models of the local Ollama instance.
+    pub async fn fetch_ollama_embeddings(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
+        let Some(base_url) = &self.ollama_url else {
+            return Ok(Vec::new());
+        };
+
+        let response = self.client.get(format!("{}/api/tags", base_url)).send().await?;
+        if !response.status().is_success() {
+            return Err(response.error_for_status().unwrap_err());
+        }
+
+        let data: Value = response.json().await?;
+        let models = data["models"].as_array().cloned().unwrap_or_default();
+
+        Ok(models
+            .iter()
+            .filter(|model| is_ollama_embedding(model))
+            .filter_map(|model| {
+                Some(FreeModel {
+                    id: model["name"].as_str()?.to_string(),
+                    provider: "ollama".to_string(),
+                    endpoint: base_url.clone(),
+                    source: Source::Ollama,
+                    context_length: None,
+                    modalities: Vec::new(),
+                    supports_tools: None,
+                    supports_response_format: None,
+                    source_index: None,
+                })
+            })
+            .collect())
+    }
+
+    /// Fetch OpenRouter's zero-priced embedding models, listed apart from
+    /// its chat models at `/embeddings/models`.
+    pub async fn fetch_openrouter_embeddings(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
+        let base_url = self.openrouter_url.trim_end_matches("/models");
+        let response = self
+            .client
+            .get(format!("{}/embeddings/models", base_url))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(response.error_for_status().unwrap_err());
+        }
+
+        let data: Value = response.json().await?;
+        let models = data["data"].as_array().cloned().unwrap_or_default();
+
+        Ok(models
+            .iter()
+            .filter_map(|model| {
+                let id = model["id"].as_str()?;
+                let price = model["pricing"]["prompt"]
+                    .as_str()
+                    .and_then(|p| p.parse::<f64>().ok());
+                if price != Some(0.0) && !id.ends_with(":free") {
+                    return None;
+                }
+                Some(FreeModel {
+                    id: id.to_string(),
+                    provider: "openrouter".to_string(),
+                    endpoint: base_url.to_string(),
+                    source: Source::OpenRouter,
+                    context_length: model["context_length"].as_u64(),
+                    modalities: Vec::new(),
+                    supports_tools: None,
+                    supports_response_format: None,
+                    source_index: None,
+                })
+            })
+            .collect())
+    }
+
+    /// Fetch models from a local LM Studio server. Like Ollama, everything
+    /// it serves is free; embedding models are left out.
+    pub async fn fetch_lmstudio(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
+        let Some(base_url) = &self.lmstudio_url else {
+            return Ok(Vec::new());
+        };
+
+        let models_url = format!("{}/v1/models", base_url);
+        let response = self.client.get(&models_url).send().await?;
+        if !response.status().is_success() {
+            return Err(response.error_for_status().unwrap_err());
+        }
+
+        let data: Value = response.json().await?;
+        let models = data["data"].as_array().cloned().unwrap_or_default();
+
+        Ok(models
+            .iter()
+            .filter_map(|model| {
+                let id = model["id"].as_str()?;
+                if id.to_lowercase().contains("embed") {
+                    return None;
+                }
+                Some(FreeModel {
+                    id: id.to_string(),
+                    provider: "lmstudio".to_string(),
+                    endpoint: base_url.clone(),
+                    source: Source::LmStudio,
+                    context_length: None,
+                    modalities: Vec::new(),
+                    supports_tools: None,
+                    supports_response_format: None,
+                    source_index: None,
+                })
+            })
+            .collect())
+    }
+
+    /// Fetch the models loaded in a local `llama-server`.
+    pub async fn fetch_llamacpp(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
+        let Some(base_url) = &self.llamacpp_url else {
+            return Ok(Vec::new());
+        };
+
+        let models_url = format!("{}/v1/models", base_url);
+        let response = self.client.get(&models_url).send().await?;
+        if !response.status().is_success() {
+            return Err(response.error_for_status().unwrap_err());
+        }
+
+        let data: Value = response.json().await?;
+        let models = data["data"].as_array().cloned().unwrap_or_default();
+
+        Ok(models
+            .iter()
+            .filter_map(|model| {
+                Some(FreeModel {
+                    id: model["id"].as_str()?.to_string(),
+                    provider: "llamacpp".to_string(),
+                    endpoint: base_url.clone(),
+                    source: Source::LlamaCpp,
+                    context_length: model["meta"]["n_ctx_train"].as_u64(),
+                    modalities: Vec::new(),
+                    supports_tools: None,
+                    supports_response_format: None,
+                    source_index: None,
                 })
             })
             .collect())
@@ -185,18 +966,447 @@ impl FreeModelScanner {
         Ok(self.filter_openrouter_free(&models))
     }
 
-    /// Fetch free models from OpenCode Zen by parsing their pricing table.
-    /// Dynamically discovers which models have "Free" in INPUT/OUTPUT columns.
-    pub async fn fetch_opencode_zen(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
-        // Step 1: Fetch docs page and parse pricing table
-        let docs_response = self.client.get(&self.opencode_zen_docs_url).send().await?;
-        if !docs_response.status().is_success() {
-            return Err(docs_response.error_for_status().unwrap_err());
+    /// Fetch models from Groq. Every chat model is usable on Groq's free
+    /// tier; speech-to-text and text-to-speech models are left out.
+    pub async fn fetch_groq(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
+        let Some(key) = &self.groq_api_key else {
+            return Ok(Vec::new());
+        };
+
+        let response = self.client.get(&self.groq_url).bearer_auth(key).send().await?;
+        if !response.status().is_success() {
+            return Err(response.error_for_status().unwrap_err());
+        }
+
+        let data: Value = response.json().await?;
+        let models = data["data"].as_array().cloned().unwrap_or_default();
+        let endpoint = self.groq_url.trim_end_matches("/models").to_string();
+
+        Ok(models
+            .iter()
+            .filter(|model| model["active"].as_bool() != Some(false))
+            .filter_map(|model| {
+                let id = model["id"].as_str()?;
+                let lower = id.to_lowercase();
+                if lower.contains("whisper") || lower.contains("tts") {
+                    return None;
+                }
+                Some(FreeModel {
+                    id: id.to_string(),
+                    provider: "groq".to_string(),
+                    endpoint: endpoint.clone(),
+                    source: Source::Groq,
+                    context_length: model["context_window"].as_u64(),
+                    modalities: Vec::new(),
+                    supports_tools: None,
+                    supports_response_format: None,
+                    source_index: None,
+                })
+            })
+            .collect())
+    }
+
+    /// Fetch models from Google AI Studio. The free tier covers the Gemini
+    /// flash models and Gemma; pro, speech, image and live models are left out.
+    pub async fn fetch_gemini(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
+        let Some(key) = &self.gemini_api_key else {
+            return Ok(Vec::new());
+        };
+
+        let response = self
+            .client
+            .get(&self.gemini_url)
+            .header(crate::gemini::API_KEY_HEADER, key)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(response.error_for_status().unwrap_err());
+        }
+
+        let data: Value = response.json().await?;
+        let models = data["models"].as_array().cloned().unwrap_or_default();
+        let endpoint = self.gemini_url.trim_end_matches("/models").to_string();
+
+        Ok(models
+            .iter()
+            .filter(|model| {
+                model["supportedGenerationMethods"]
+                    .as_array()
+                    .is_some_and(|methods| methods.iter().any(|m| m == "generateContent"))
+            })
+            .filter_map(|model| {
+                let id = model["name"].as_str()?.trim_start_matches("models/");
+                let lower = id.to_lowercase();
+                if !(lower.contains("flash") || lower.contains("gemma"))
+                    || ["tts", "image", "live"].iter().any(|s| lower.contains(s))
+                {
+                    return None;
+                }
+                Some(FreeModel {
+                    id: id.to_string(),
+                    provider: "google".to_string(),
+                    endpoint: endpoint.clone(),
+                    source: Source::Gemini,
+                    context_length: model["inputTokenLimit"].as_u64(),
+                    modalities: Vec::new(),
+                    supports_tools: None,
+                    supports_response_format: None,
+                    source_index: None,
+                })
+            })
+            .collect())
+    }
+
+    /// Fetch models from Cerebras. Every model it serves is usable on the
+    /// free tier.
+    pub async fn fetch_cerebras(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
+        let Some(key) = &self.cerebras_api_key else {
+            return Ok(Vec::new());
+        };
+
+        let response = self.client.get(&self.cerebras_url).bearer_auth(key).send().await?;
+        if !response.status().is_success() {
+            return Err(response.error_for_status().unwrap_err());
+        }
+
+        let data: Value = response.json().await?;
+        let models = data["data"].as_array().cloned().unwrap_or_default();
+        let endpoint = self.cerebras_url.trim_end_matches("/models").to_string();
+
+        Ok(models
+            .iter()
+            .filter_map(|model| {
+                Some(FreeModel {
+                    id: model["id"].as_str()?.to_string(),
+                    provider: "cerebras".to_string(),
+                    endpoint: endpoint.clone(),
+                    source: Source::Cerebras,
+                    context_length: None,
+                    modalities: Vec::new(),
+                    supports_tools: None,
+                    supports_response_format: None,
+                    source_index: None,
+                })
+            })
+            .collect())
+    }
+
+    /// Fetch free chat models from Mistral La Plateforme.
+    pub async fn fetch_mistral(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
+        let Some(key) = &self.mistral_api_key else {
+            return Ok(Vec::new());
+        };
+
+        let response = self.client.get(&self.mistral_url).bearer_auth(key).send().await?;
+        if !response.status().is_success() {
+            return Err(response.error_for_status().unwrap_err());
+        }
+
+        let data: Value = response.json().await?;
+        let models = data["data"].as_array().cloned().unwrap_or_default();
+        let endpoint = self.mistral_url.trim_end_matches("/models");
+
+        Ok(Self::filter_mistral_free(&models, endpoint))
+    }
+
+    /// Filter Mistral models to the free experiment tier. Models listing a
+    /// price are free when both input and output cost nothing; unpriced
+    /// models are free when they are open-weight (`open-*`). Deprecated and
+    /// non-chat models are left out.
+    pub fn filter_mistral_free(models: &[Value], endpoint: &str) -> Vec<FreeModel> {
+        let price = |value: &Value| {
+            value
+                .as_f64()
+                .or_else(|| value.as_str().and_then(|p| p.parse().ok()))
+                .unwrap_or(1.0)
+        };
+        models
+            .iter()
+            .filter(|model| model["capabilities"]["completion_chat"].as_bool() != Some(false))
+            .filter(|model| model["deprecation"].is_null())
+            .filter_map(|model| {
+                let id = model["id"].as_str()?;
+                let pricing = &model["pricing"];
+                let free = if pricing.is_object() {
+                    price(&pricing["input"]) == 0.0 && price(&pricing["output"]) == 0.0
+                } else {
+                    id.starts_with("open-")
+                };
+                free.then(|| FreeModel {
+                    id: id.to_string(),
+                    provider: "mistral".to_string(),
+                    endpoint: endpoint.to_string(),
+                    source: Source::Mistral,
+                    context_length: model["max_context_length"].as_u64(),
+                    modalities: Vec::new(),
+                    supports_tools: None,
+                    supports_response_format: None,
+                    source_index: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Fetch free and promotional chat models from Together AI.
+    pub async fn fetch_together(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
+        let Some(key) = &self.together_api_key else {
+            return Ok(Vec::new());
+        };
+
+        let response = self.client.get(&self.together_url).bearer_auth(key).send().await?;
+        if !response.status().is_success() {
+            return Err(response.error_for_status().unwrap_err());
+        }
+
+        // Together answers with a bare array; accept an OpenAI `data` list too
+        let data: Value = response.json().await?;
+        let models = data
+            .as_array()
+            .or_else(|| data["data"].as_array())
+            .cloned()
+            .unwrap_or_default();
+        let endpoint = self.together_url.trim_end_matches("/models");
+
+        Ok(Self::filter_together_free(&models, endpoint))
+    }
+
+    /// Filter Together AI models to chat models whose input and output
+    /// prices are both zero. Unpriced models are assumed to be paid.
+    pub fn filter_together_free(models: &[Value], endpoint: &str) -> Vec<FreeModel> {
+        let price = |value: &Value| {
+            value
+                .as_f64()
+                .or_else(|| value.as_str().and_then(|p| p.parse().ok()))
+                .unwrap_or(1.0)
+        };
+        models
+            .iter()
+            .filter(|model| model["type"].as_str().is_none_or(|kind| kind == "chat"))
+            .filter(|model| {
+                price(&model["pricing"]["input"]) == 0.0 && price(&model["pricing"]["output"]) == 0.0
+            })
+            .filter_map(|model| {
+                let (modalities, supports_tools, supports_response_format) =
+                    model_capabilities(model);
+                Some(FreeModel {
+                    id: model["id"].as_str()?.to_string(),
+                    provider: "together".to_string(),
+                    endpoint: endpoint.to_string(),
+                    source: Source::Together,
+                    context_length: model["context_length"].as_u64(),
+                    modalities,
+                    supports_tools,
+                    supports_response_format,
+                    source_index: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Fetch Llama family models from SambaNova Cloud's free tier.
+    pub async fn fetch_sambanova(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
+        let Some(key) = &self.sambanova_api_key else {
+            return Ok(Vec::new());
+        };
+
+        let response = self.client.get(&self.sambanova_url).bearer_auth(key).send().await?;
+        if !response.status().is_success() {
+            return Err(response.error_for_status().unwrap_err());
+        }
+
+        let data: Value = response.json().await?;
+        let models = data["data"].as_array().cloned().unwrap_or_default();
+        let endpoint = self.sambanova_url.trim_end_matches("/models").to_string();
+
+        Ok(models
+            .iter()
+            .filter_map(|model| {
+                let id = model["id"].as_str()?;
+                if !id.to_lowercase().contains("llama") {
+                    return None;
+                }
+                let (modalities, supports_tools, supports_response_format) =
+                    model_capabilities(model);
+                Some(FreeModel {
+                    id: id.to_string(),
+                    provider: "sambanova".to_string(),
+                    endpoint: endpoint.clone(),
+                    source: Source::SambaNova,
+                    context_length: model["context_length"].as_u64(),
+                    modalities,
+                    supports_tools,
+                    supports_response_format,
+                    source_index: None,
+                })
+            })
+            .collect())
+    }
+
+    /// Fetch chat models from the GitHub Models catalog. Every catalog model
+    /// is free within GitHub's rate limits; embedding models are left out.
+    pub async fn fetch_github_models(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
+        let Some(key) = &self.github_models_api_key else {
+            return Ok(Vec::new());
+        };
+
+        let response = self.client.get(&self.github_models_url).bearer_auth(key).send().await?;
+        if !response.status().is_success() {
+            return Err(response.error_for_status().unwrap_err());
         }
-        let docs_html = docs_response.text().await?;
-        let free_model_names = Self::parse_free_models_from_pricing_table(&docs_html);
 
-        // Step 2: Fetch API to get actual model IDs
+        // The catalog is a bare array rather than an OpenAI `data` list
+        let data: Value = response.json().await?;
+        let models = data.as_array().cloned().unwrap_or_default();
+        let endpoint = self.github_models_url.trim_end_matches("/models").to_string();
+
+        Ok(models
+            .iter()
+            .filter(|model| model["task"].as_str() == Some("chat-completion"))
+            .filter_map(|model| {
+                Some(FreeModel {
+                    id: model["name"].as_str()?.to_string(),
+                    provider: "github".to_string(),
+                    endpoint: endpoint.clone(),
+                    source: Source::GitHubModels,
+                    context_length: None,
+                    modalities: Vec::new(),
+                    supports_tools: None,
+                    supports_response_format: None,
+                    source_index: None,
+                })
+            })
+            .collect())
+    }
+
+    /// Fetch free text generation models from Cloudflare Workers AI.
+    pub async fn fetch_cloudflare(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
+        let (Some(account_id), Some(key)) = (&self.cloudflare_account_id, &self.cloudflare_api_key)
+        else {
+            return Ok(Vec::new());
+        };
+
+        let endpoint = format!("{}/accounts/{}/ai", self.cloudflare_url, account_id);
+        let response = self
+            .client
+            .get(format!("{}/models/search", endpoint))
+            .query(&[("task", "Text Generation")])
+            .bearer_auth(key)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(response.error_for_status().unwrap_err());
+        }
+
+        let data: Value = response.json().await?;
+        let models = data["result"].as_array().cloned().unwrap_or_default();
+
+        Ok(Self::filter_cloudflare_free(&models, &endpoint))
+    }
+
+    /// Filter Workers AI models to those that cost nothing: beta models and
+    /// models listing no (or only zero) prices. Priced models draw down the
+    /// daily free allocation and are left out.
+    pub fn filter_cloudflare_free(models: &[Value], endpoint: &str) -> Vec<FreeModel> {
+        models
+            .iter()
+            .filter(|model| {
+                model["task"]["name"].as_str().is_none_or(|task| task == "Text Generation")
+            })
+            .filter_map(|model| {
+                let properties = model["properties"].as_array().cloned().unwrap_or_default();
+                let property = |id: &str| {
+                    properties
+                        .iter()
+                        .find(|p| p["property_id"] == id)
+                        .map(|p| p["value"].clone())
+                };
+                let beta = property("beta").is_some_and(|v| v == "true" || v == true);
+                let priced = property("price")
+                    .and_then(|v| v.as_array().cloned())
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|price| price["price"].as_f64().unwrap_or(0.0) > 0.0);
+                if priced && !beta {
+                    return None;
+                }
+                Some(FreeModel {
+                    id: model["name"].as_str()?.to_string(),
+                    provider: "cloudflare".to_string(),
+                    endpoint: endpoint.to_string(),
+                    source: Source::Cloudflare,
+                    context_length: property("context_window")
+                        .and_then(|v| v.as_u64().or_else(|| v.as_str()?.parse().ok())),
+                    modalities: Vec::new(),
+                    supports_tools: None,
+                    supports_response_format: None,
+                    source_index: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Models from the custom endpoints: the configured list, or whatever
+    /// `/models` reports when none is given. An endpoint that can't be
+    /// reached is skipped; the scan only fails when none answered.
+    pub async fn fetch_custom(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
+        let mut models = Vec::new();
+        let mut failure = None;
+        for provider in &self.custom {
+            let endpoint = provider.base_url.trim_end_matches('/').to_string();
+            let ids = if provider.models.is_empty() {
+                match self.discover_custom(&endpoint, provider.api_key.as_deref()).await {
+                    Ok(ids) => ids,
+                    Err(e) => {
+                        tracing::warn!("Custom provider {} failed: {}", provider.name, e);
+                        failure = Some(e);
+                        continue;
+                    }
+                }
+            } else {
+                provider.models.clone()
+            };
+            models.extend(ids.into_iter().map(|id| FreeModel {
+                id,
+                provider: provider.name.clone(),
+                endpoint: endpoint.clone(),
+                source: Source::Custom,
+                context_length: None,
+                modalities: Vec::new(),
+                supports_tools: None,
+                supports_response_format: None,
+                source_index: None,
+            }));
+        }
+        match failure {
+            Some(e) if models.is_empty() => Err(e),
+            _ => Ok(models),
+        }
+    }
+
+    async fn discover_custom(
+        &self,
+        endpoint: &str,
+        api_key: Option<&str>,
+    ) -> Result<Vec<String>, reqwest::Error> {
+        let mut request = self.client.get(format!("{}/models", endpoint));
+        if let Some(key) = api_key {
+            request = request.bearer_auth(key);
+        }
+        let data: Value = request.send().await?.error_for_status()?.json().await?;
+        Ok(data["data"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|model| model["id"].as_str().map(str::to_string))
+            .collect())
+    }
+
+    /// Fetch free models from OpenCode Zen.
+    ///
+    /// Free models are detected by the first strategy that finds any: pricing
+    /// fields in the API response, then the docs pricing table, then a
+    /// bundled list. The strategy used is reported in [`SourceStatus`].
+    pub async fn fetch_opencode_zen(&self) -> Result<Vec<FreeModel>, reqwest::Error> {
         let api_response = self.client.get(&self.opencode_zen_api_url).send().await?;
         if !api_response.status().is_success() {
             return Err(api_response.error_for_status().unwrap_err());
@@ -204,8 +1414,58 @@ impl FreeModelScanner {
         let data: Value = api_response.json().await?;
         let models = data["data"].as_array().cloned().unwrap_or_default();
 
-        // Step 3: Match free model names to API model IDs
-        Ok(self.filter_opencode_zen_free(&models, &free_model_names))
+        let (detection, free_models) = match Self::filter_opencode_zen_priced(&models) {
+            Some(free_models) => (FreeDetection::Pricing, free_models),
+            None => match self.fetch_zen_docs_free_names().await {
+                Ok(names) if !names.is_empty() => {
+                    let free_models = Self::filter_opencode_zen_free(&models, &names);
+                    (FreeDetection::DocsTable, free_models)
+                }
+                result => {
+                    if let Err(e) = result {
+                        tracing::warn!("OpenCode Zen docs unavailable, using bundled free list: {}", e);
+                    } else {
+                        tracing::warn!("No free models in the OpenCode Zen docs table, using bundled free list");
+                    }
+                    let names: Vec<String> =
+                        BUNDLED_ZEN_FREE_MODELS.iter().map(|name| name.to_string()).collect();
+                    (FreeDetection::Bundled, Self::filter_opencode_zen_free(&models, &names))
+                }
+            },
+        };
+        *self.zen_detection.lock().unwrap() = Some(detection);
+        Ok(free_models)
+    }
+
+    /// Names of the models marked free in the OpenCode Zen docs pricing table.
+    async fn fetch_zen_docs_free_names(&self) -> Result<Vec<String>, reqwest::Error> {
+        let docs_html = self
+            .client
+            .get(&self.opencode_zen_docs_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        Ok(Self::parse_free_models_from_pricing_table(&docs_html))
+    }
+
+    /// Free OpenCode Zen models by the pricing fields in the API response.
+    /// `None` if no model carries pricing, so another strategy must decide.
+    fn filter_opencode_zen_priced(models: &[Value]) -> Option<Vec<FreeModel>> {
+        let mut priced = false;
+        let free_models = models
+            .iter()
+            .filter(|model| match zen_api_is_free(model) {
+                Some(free) => {
+                    priced = true;
+                    free
+                }
+                None => false,
+            })
+            .filter_map(opencode_zen_model)
+            .collect();
+        priced.then_some(free_models)
     }
 
     /// Parse the OpenCode Zen pricing table to find free models.
@@ -249,16 +1509,18 @@ impl FreeModelScanner {
 
     /// Filter OpenCode Zen models to only free ones based on parsed pricing table.
     /// Matches model names from pricing table to API model IDs using flexible matching.
-    fn filter_opencode_zen_free(&self, models: &[Value], free_model_names: &[String]) -> Vec<FreeModel> {
+    fn filter_opencode_zen_free(models: &[Value], free_model_names: &[String]) -> Vec<FreeModel> {
         models
             .iter()
-            .filter_map(|model| {
-                let id = model["id"].as_str()?;
+            .filter(|model| {
+                let Some(id) = model["id"].as_str() else {
+                    return false;
+                };
                 let id_lower = id.to_lowercase();
 
                 // Check if this model ID matches any free model from pricing table
                 // Use flexible matching: normalize both names for comparison
-                let is_free = free_model_names.iter().any(|free_name| {
+                free_model_names.iter().any(|free_name| {
                     let name_normalized = free_name.to_lowercase().replace([' ', '.'], "-");
                     let id_normalized = id_lower.replace("-free", "");
 
@@ -266,19 +1528,9 @@ impl FreeModelScanner {
                     id_normalized.contains(&name_normalized)
                         || name_normalized.contains(&id_normalized.replace("opencode/", ""))
                         || Self::fuzzy_model_match(&id_lower, &free_name.to_lowercase())
-                });
-
-                if is_free {
-                    Some(FreeModel {
-                        id: id.to_string(),
-                        provider: "opencode-zen".to_string(),
-                        endpoint: "https://opencode.ai/zen/v1".to_string(),
-                        source: Source::OpenCodeZen,
-                    })
-                } else {
-                    None
-                }
+                })
             })
+            .filter_map(opencode_zen_model)
             .collect()
     }
 
@@ -324,11 +1576,18 @@ impl FreeModelScanner {
                     .unwrap_or(1.0);
 
                 if prompt_price == 0.0 && completion_price == 0.0 {
+                    let (modalities, supports_tools, supports_response_format) =
+                        model_capabilities(model);
                     Some(FreeModel {
                         id: id.to_string(),
                         provider: "openrouter".to_string(),
                         endpoint: "https://openrouter.ai/api/v1".to_string(),
                         source: Source::OpenRouter,
+                        context_length: model["context_length"].as_u64(),
+                        modalities,
+                        supports_tools,
+                        supports_response_format,
+                        source_index: None,
                     })
                 } else {
                     None
@@ -337,46 +1596,219 @@ impl FreeModelScanner {
             .collect()
     }
 
+    /// Free embedding models from Ollama and OpenRouter, local ones first.
+    /// Each source is cached with the TTL of its chat models; a source that
+    /// fails is left out and asked again on the next call.
+    pub async fn get_embedding_models(&self, force_refresh: bool) -> Vec<FreeModel> {
+        if force_refresh {
+            self.embedding_cache.invalidate_all();
+        }
+        let scans = [Source::Ollama, Source::OpenRouter].map(|source| async move {
+            let scan = async {
+                match source {
+                    Source::Ollama => self.fetch_ollama_embeddings().await,
+                    _ => self.fetch_openrouter_embeddings().await,
+                }
+                .map(Arc::new)
+            };
+            match self.embedding_cache.try_get_with(source, scan).await {
+                Ok(models) => models,
+                Err(e) => {
+                    tracing::warn!("Embedding model scan of {:?} failed: {}", source, e);
+                    Arc::new(Vec::new())
+                }
+            }
+        });
+        futures::future::join_all(scans)
+            .await
+            .iter()
+            .flat_map(|models| models.iter().cloned())
+            .collect()
+    }
+
     /// Get all free models from all sources (with caching).
-    /// Models are sorted by source priority: Ollama > OpenCodeZen > OpenRouter
+    /// Each source is cached separately, so only sources whose TTL has run
+    /// out are scanned again.
+    /// Models are sorted by source priority (`Source` order), local Ollama first.
     pub async fn get_free_models(&self, force_refresh: bool) -> Vec<FreeModel> {
-        const CACHE_KEY: &str = "all_free_models";
-
-        if !force_refresh {
-            if let Some(cached) = self.cache.get(CACHE_KEY).await {
-                return (*cached).clone();
+        // Until the first scan finishes, serve what the disk cache had
+        if !force_refresh && self.disk_cache.is_some() && !self.has_scanned() {
+            let saved = self.last_known.lock().unwrap().clone();
+            if !saved.is_empty() {
+                return saved;
             }
         }
 
         // Fetch from all sources in parallel for faster startup
-        let (ollama_result, openrouter_result, opencode_zen_result) = tokio::join!(
-            self.fetch_ollama(),
-            self.fetch_openrouter(),
-            self.fetch_opencode_zen()
-        );
+        let scans = self.registry.groups().into_iter().filter_map(|(source, members)| {
+            let members: Vec<_> =
+                members.into_iter().filter(|(_, member)| member.is_configured(self)).collect();
+            (!members.is_empty())
+                .then(|| self.scan_source(source, force_refresh, self.fetch_members(members)))
+        });
+        let results = futures::future::join_all(scans).await;
 
         let mut all_free = Vec::new();
-
-        // Add in priority order: Ollama first (local), then cloud providers
-        if let Ok(models) = ollama_result {
+        let mut scanned = false;
+        for (models, fresh) in results {
+            scanned |= fresh;
             all_free.extend(models);
         }
 
-        if let Ok(models) = opencode_zen_result {
-            all_free.extend(models);
+        // Sort by source priority (Ollama < ... < Gemini in enum order)
+        all_free.sort_by_key(|m| m.source);
+
+        // Keep the list for failing sources and the next startup
+        if !scanned {
+            return all_free;
+        }
+        let previous = std::mem::replace(&mut *self.last_known.lock().unwrap(), all_free.clone());
+        // The first scan has nothing to compare with unless the disk cache had a list
+        if !previous.is_empty() {
+            let changes = diff_models(&previous, &all_free);
+            if !changes.is_empty() {
+                // No subscribers is not an error
+                let _ = self.changes.send(changes);
+            }
+        }
+        if let Some(path) = &self.disk_cache {
+            if let Err(e) = save_models(path, &all_free) {
+                tracing::warn!("Failed to save model cache {}: {}", path.display(), e);
+            }
         }
 
-        if let Ok(models) = openrouter_result {
-            all_free.extend(models);
+        all_free
+    }
+
+    /// Models from one source: its cached scan unless that has expired or
+    /// `force_refresh` is set, otherwise a fresh scan. A failed scan falls
+    /// back to what the source had when it last answered, and that is
+    /// cached too so the source isn't retried before its TTL is up. The flag
+    /// is true when the source was scanned.
+    async fn scan_source(
+        &self,
+        source: Source,
+        force_refresh: bool,
+        fetch: impl Future<Output = Result<Vec<FreeModel>, reqwest::Error>>,
+    ) -> (Vec<FreeModel>, bool) {
+        if !force_refresh {
+            if let Some(cached) = self.cache.get(&source).await {
+                return ((*cached).clone(), false);
+            }
+        }
+        let result = fetch.await;
+        self.record_scan(source, &result);
+        let models = result.unwrap_or_else(|_| {
+            let last_known = self.last_known.lock().unwrap();
+            last_known.iter().filter(|m| m.source == source).cloned().collect()
+        });
+        self.cache.insert(source, Arc::new(models.clone())).await;
+        (models, true)
+    }
+
+    /// Models from every member reporting under one source, in priority
+    /// order. A member that fails is skipped; the scan only fails when none
+    /// answered.
+    async fn fetch_members(
+        &self,
+        members: Vec<(usize, Arc<dyn ModelSource>)>,
+    ) -> Result<Vec<FreeModel>, reqwest::Error> {
+        let results =
+            futures::future::join_all(members.iter().map(|(_, member)| member.fetch(self))).await;
+        let mut models = Vec::new();
+        let mut failure = None;
+        for ((index, member), result) in members.iter().zip(results) {
+            match result {
+                Ok(found) => models.extend(found.into_iter().map(|model| FreeModel {
+                    source_index: Some(*index),
+                    ..model
+                })),
+                Err(e) => {
+                    if members.len() > 1 {
+                        tracing::warn!("Model source {} failed: {}", member.name(), e);
+                    }
+                    failure = Some(e);
+                }
+            }
         }
+        match failure {
+            Some(e) if models.is_empty() => Err(e),
+            _ => Ok(models),
+        }
+    }
 
-        // Sort by source priority (Ollama < OpenCodeZen < OpenRouter in enum order)
-        all_free.sort_by(|a, b| a.source.cmp(&b.source));
+    /// Whether a built-in source has the URL or key it needs to be scanned.
+    fn is_configured(&self, source: Source) -> bool {
+        match source {
+            Source::Ollama => self.ollama_url.is_some(),
+            Source::LmStudio => self.lmstudio_url.is_some(),
+            Source::LlamaCpp => self.llamacpp_url.is_some(),
+            Source::OpenCodeZen | Source::OpenRouter => true,
+            Source::Groq => self.groq_api_key.is_some(),
+            Source::Gemini => self.gemini_api_key.is_some(),
+            Source::Cerebras => self.cerebras_api_key.is_some(),
+            Source::Mistral => self.mistral_api_key.is_some(),
+            Source::Together => self.together_api_key.is_some(),
+            Source::SambaNova => self.sambanova_api_key.is_some(),
+            Source::GitHubModels => self.github_models_api_key.is_some(),
+            Source::Cloudflare => {
+                self.cloudflare_account_id.is_some() && self.cloudflare_api_key.is_some()
+            }
+            Source::Custom => !self.custom.is_empty(),
+        }
+    }
 
-        // Cache results
-        self.cache.insert(CACHE_KEY.to_string(), Arc::new(all_free.clone())).await;
+    /// Scan every source now, then again every `interval` so the cache is
+    /// replaced before it expires and no request waits on a scan. Runs
+    /// until the task is dropped.
+    pub async fn keep_warm(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let models = self.get_free_models(true).await;
+            tracing::debug!("Refreshed model list: {} free models", models.len());
+        }
+    }
 
-        all_free
+    /// Receive the models added and removed by each scan from now on.
+    pub fn subscribe_changes(&self) -> broadcast::Receiver<ModelChanges> {
+        self.changes.subscribe()
+    }
+
+    /// Status of each source as of its last scan. Sources not scanned yet are absent.
+    pub fn source_status(&self) -> BTreeMap<Source, SourceStatus> {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Whether a full scan has completed since startup.
+    pub fn has_scanned(&self) -> bool {
+        !self.status.lock().unwrap().is_empty()
+    }
+
+    fn record_scan(&self, source: Source, result: &Result<Vec<FreeModel>, reqwest::Error>) {
+        let now = Utc::now();
+        let mut statuses = self.status.lock().unwrap();
+        let status = match result {
+            Ok(models) => SourceStatus {
+                last_refresh: Some(now),
+                last_success: Some(now),
+                last_error: None,
+                model_count: models.len(),
+                detection: match source {
+                    Source::OpenCodeZen => *self.zen_detection.lock().unwrap(),
+                    _ => None,
+                },
+            },
+            Err(e) => SourceStatus {
+                last_refresh: Some(now),
+                last_success: statuses.get(&source).and_then(|s| s.last_success),
+                last_error: Some(e.to_string()),
+                model_count: 0,
+                detection: None,
+            },
+        };
+        statuses.insert(source, status);
     }
 }
 
@@ -0,0 +1,206 @@
+//! Model sources as plugins.
+//!
+//! Every source the scanner knows is a [`ModelSource`] in a
+//! [`SourceRegistry`]. The built-in sources are registered by
+//! [`SourceRegistry::builtin`]; a third-party provider lives in its own
+//! module, implements the trait and is registered on
+//! `AppState::sources` without touching the scanner.
+//!
+//! Sources report their models under a [`Source`], which sets the TTL,
+//! scan status and priority. A plugin for a provider without a variant of
+//! its own reports under [`Source::Custom`] and is scanned alongside the
+//! custom endpoints. Each model remembers which registered source found
+//! it, so requests to it use that source's own [`SourceAuth`].
+
+use super::{FreeModel, FreeModelScanner, Source};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+
+/// Result of fetching one source's models.
+pub type FetchFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Vec<FreeModel>, reqwest::Error>> + Send + 'a>>;
+
+/// How a source's upstream authenticates requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceAuth {
+    /// No credentials, e.g. a local server.
+    None,
+    /// An API key sent as a bearer token.
+    BearerKey,
+    /// An API key sent as a bearer token when one is configured, e.g. for
+    /// custom endpoints that may be open.
+    OptionalBearerKey,
+    /// An API key sent in a named header, e.g. `x-goog-api-key`.
+    HeaderKey(&'static str),
+}
+
+impl SourceAuth {
+    /// Whether requests fail without a configured key.
+    pub fn requires_key(self) -> bool {
+        matches!(self, Self::BearerKey | Self::HeaderKey(_))
+    }
+}
+
+/// A provider of free models.
+pub trait ModelSource: Send + Sync {
+    /// Source the models are reported under.
+    fn source(&self) -> Source;
+
+    /// Name for logs.
+    fn name(&self) -> &str;
+
+    /// Order among the members reporting under the same [`Source`]; lower
+    /// values are listed first. Built-in sources use 0.
+    fn priority(&self) -> u32 {
+        100
+    }
+
+    /// Credentials the upstream expects.
+    fn auth(&self) -> SourceAuth;
+
+    /// Whether the source has the URL or key it needs to be scanned.
+    fn is_configured(&self, _scanner: &FreeModelScanner) -> bool {
+        true
+    }
+
+    /// Fetch the source's free models. `scanner.client()` is the pooled
+    /// upstream client.
+    fn fetch<'a>(&'a self, scanner: &'a FreeModelScanner) -> FetchFuture<'a>;
+}
+
+/// One of the sources built into the scanner.
+struct Builtin(Source);
+
+impl ModelSource for Builtin {
+    fn source(&self) -> Source {
+        self.0
+    }
+
+    fn name(&self) -> &str {
+        match self.0 {
+            Source::Ollama => "ollama",
+            Source::LmStudio => "lmstudio",
+            Source::LlamaCpp => "llamacpp",
+            Source::OpenCodeZen => "opencode-zen",
+            Source::OpenRouter => "openrouter",
+            Source::Groq => "groq",
+            Source::Gemini => "gemini",
+            Source::Cerebras => "cerebras",
+            Source::Mistral => "mistral",
+            Source::Together => "together",
+            Source::SambaNova => "sambanova",
+            Source::GitHubModels => "github",
+            Source::Cloudflare => "cloudflare",
+            Source::Custom => "custom",
+        }
+    }
+
+    fn priority(&self) -> u32 {
+        0
+    }
+
+    fn auth(&self) -> SourceAuth {
+        match self.0 {
+            Source::Ollama | Source::LmStudio | Source::LlamaCpp => SourceAuth::None,
+            Source::Gemini => SourceAuth::HeaderKey(crate::gemini::API_KEY_HEADER),
+            Source::Custom => SourceAuth::OptionalBearerKey,
+            _ => SourceAuth::BearerKey,
+        }
+    }
+
+    fn is_configured(&self, scanner: &FreeModelScanner) -> bool {
+        scanner.is_configured(self.0)
+    }
+
+    fn fetch<'a>(&'a self, scanner: &'a FreeModelScanner) -> FetchFuture<'a> {
+        match self.0 {
+            Source::Ollama => Box::pin(scanner.fetch_ollama()),
+            Source::LmStudio => Box::pin(scanner.fetch_lmstudio()),
+            Source::LlamaCpp => Box::pin(scanner.fetch_llamacpp()),
+            Source::OpenCodeZen => Box::pin(scanner.fetch_opencode_zen()),
+            Source::OpenRouter => Box::pin(scanner.fetch_openrouter()),
+            Source::Groq => Box::pin(scanner.fetch_groq()),
+            Source::Gemini => Box::pin(scanner.fetch_gemini()),
+            Source::Cerebras => Box::pin(scanner.fetch_cerebras()),
+            Source::Mistral => Box::pin(scanner.fetch_mistral()),
+            Source::Together => Box::pin(scanner.fetch_together()),
+            Source::SambaNova => Box::pin(scanner.fetch_sambanova()),
+            Source::GitHubModels => Box::pin(scanner.fetch_github_models()),
+            Source::Cloudflare => Box::pin(scanner.fetch_cloudflare()),
+            Source::Custom => Box::pin(scanner.fetch_custom()),
+        }
+    }
+}
+
+/// The sources the scanner scans. Clones share one list, so a source
+/// registered on `AppState::sources` is picked up by the next scan.
+#[derive(Clone, Default)]
+pub struct SourceRegistry {
+    sources: Arc<RwLock<Vec<Arc<dyn ModelSource>>>>,
+}
+
+impl SourceRegistry {
+    /// A registry with no sources.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with every built-in source.
+    pub fn builtin() -> Self {
+        let registry = Self::new();
+        for source in Source::ALL {
+            registry.register(Builtin(source));
+        }
+        registry
+    }
+
+    /// Add a source to be scanned from the next scan on.
+    pub fn register(&self, source: impl ModelSource + 'static) {
+        self.sources.write().unwrap().push(Arc::new(source));
+    }
+
+    /// Registered sources, with their positions in the registry, grouped by
+    /// the [`Source`] they report under, in priority order.
+    pub fn groups(&self) -> Vec<(Source, Vec<(usize, Arc<dyn ModelSource>)>)> {
+        let sources = self.sources.read().unwrap();
+        let mut groups = Vec::new();
+        for source in Source::ALL {
+            let mut members: Vec<(usize, Arc<dyn ModelSource>)> = sources
+                .iter()
+                .enumerate()
+                .filter(|(_, member)| member.source() == source)
+                .map(|(index, member)| (index, member.clone()))
+                .collect();
+            if !members.is_empty() {
+                members.sort_by_key(|(_, member)| member.priority());
+                groups.push((source, members));
+            }
+        }
+        groups
+    }
+
+    /// Names and sources of the registered sources that send an API key.
+    pub fn keyed(&self) -> Vec<(String, Source)> {
+        self.sources
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|member| member.auth() != SourceAuth::None)
+            .map(|member| (member.name().to_string(), member.source()))
+            .collect()
+    }
+
+    /// Credentials `model`'s upstream expects: those of the source that
+    /// found it, else (for models read from the disk cache) of the first
+    /// member registered under its [`Source`].
+    pub fn auth(&self, model: &FreeModel) -> Option<SourceAuth> {
+        let sources = self.sources.read().unwrap();
+        model
+            .source_index
+            .and_then(|index| sources.get(index))
+            .filter(|member| member.source() == model.source)
+            .or_else(|| sources.iter().find(|member| member.source() == model.source))
+            .map(|member| member.auth())
+    }
+}
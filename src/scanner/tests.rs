@@ -8,7 +8,7 @@ async fn fetches_free_models_from_openrouter() {
 
     let openrouter_response = serde_json::json!({
         "data": [
-            {"id": "meta-llama/llama-3:free", "pricing": {"prompt": "0", "completion": "0"}},
+            {"id": "meta-llama/llama-3:free", "context_length": 8192, "pricing": {"prompt": "0", "completion": "0"}},
             {"id": "openai/gpt-4", "pricing": {"prompt": "0.03", "completion": "0.06"}},
         ]
     });
@@ -30,6 +30,7 @@ async fn fetches_free_models_from_openrouter() {
     assert_eq!(free_models.len(), 1);
     assert_eq!(free_models[0].id, "meta-llama/llama-3:free");
     assert_eq!(free_models[0].source, Source::OpenRouter);
+    assert_eq!(free_models[0].context_length, Some(8192));
 }
 
 #[tokio::test]
@@ -198,3 +199,895 @@ async fn fetches_free_models_from_opencode_zen() {
     assert!(free_models.iter().any(|m| m.id == "grok-code-fast-1"), "Should find Grok Code Fast 1");
     assert!(free_models.iter().all(|m| m.source == Source::OpenCodeZen));
 }
+
+#[tokio::test]
+async fn records_per_source_scan_status() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/openrouter")
+        .with_status(500)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/zen/docs")
+        .with_body("<table></table>")
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/zen/models")
+        .with_body(r#"{"data": []}"#)
+        .create_async()
+        .await;
+
+    let scanner = FreeModelScanner::new()
+        .with_openrouter_url(&format!("{}/openrouter", server.url()))
+        .with_opencode_zen_docs_url(&format!("{}/zen/docs", server.url()))
+        .with_opencode_zen_api_url(&format!("{}/zen/models", server.url()));
+    assert!(scanner.source_status().is_empty());
+
+    scanner.get_free_models(true).await;
+
+    let status = scanner.source_status();
+    assert!(status[&Source::OpenRouter].last_error.is_some());
+    assert!(status[&Source::OpenCodeZen].last_error.is_none());
+    assert!(status[&Source::OpenCodeZen].last_refresh.is_some());
+    assert!(!status.contains_key(&Source::Ollama));
+}
+
+#[tokio::test]
+async fn fetches_groq_chat_models_with_key() {
+    let mut server = mockito::Server::new_async().await;
+
+    let groq_response = serde_json::json!({
+        "object": "list",
+        "data": [
+            {"id": "llama-3.3-70b-versatile", "active": true, "context_window": 131072},
+            {"id": "whisper-large-v3", "active": true, "context_window": 448},
+            {"id": "playai-tts", "active": true, "context_window": 8192},
+            {"id": "retired-model", "active": false, "context_window": 8192},
+        ]
+    });
+
+    let mock = server
+        .mock("GET", "/openai/v1/models")
+        .match_header("authorization", "Bearer gsk-test")
+        .with_header("content-type", "application/json")
+        .with_body(groq_response.to_string())
+        .create_async()
+        .await;
+
+    let url = format!("{}/openai/v1/models", server.url());
+    assert!(FreeModelScanner::new().with_groq_url(&url).fetch_groq().await.unwrap().is_empty());
+
+    let scanner = FreeModelScanner::new()
+        .with_groq_url(&url)
+        .with_groq_api_key("gsk-test");
+    let free_models = scanner.fetch_groq().await.unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(free_models.len(), 1);
+    assert_eq!(free_models[0].id, "llama-3.3-70b-versatile");
+    assert_eq!(free_models[0].source, Source::Groq);
+    assert_eq!(free_models[0].endpoint, format!("{}/openai/v1", server.url()));
+    assert_eq!(free_models[0].context_length, Some(131072));
+}
+
+#[tokio::test]
+async fn fetches_gemini_free_tier_models_with_key() {
+    let mut server = mockito::Server::new_async().await;
+
+    let gemini_response = serde_json::json!({
+        "models": [
+            {
+                "name": "models/gemini-2.0-flash",
+                "inputTokenLimit": 1048576,
+                "supportedGenerationMethods": ["generateContent", "countTokens"]
+            },
+            {
+                "name": "models/gemini-2.5-pro",
+                "inputTokenLimit": 1048576,
+                "supportedGenerationMethods": ["generateContent"]
+            },
+            {
+                "name": "models/gemini-2.5-flash-preview-tts",
+                "inputTokenLimit": 8192,
+                "supportedGenerationMethods": ["generateContent"]
+            },
+            {
+                "name": "models/text-embedding-004",
+                "inputTokenLimit": 2048,
+                "supportedGenerationMethods": ["embedContent"]
+            },
+        ]
+    });
+
+    let mock = server
+        .mock("GET", "/v1beta/models")
+        .match_header("x-goog-api-key", "AIza-test")
+        .with_header("content-type", "application/json")
+        .with_body(gemini_response.to_string())
+        .create_async()
+        .await;
+
+    let url = format!("{}/v1beta/models", server.url());
+    assert!(FreeModelScanner::new().with_gemini_url(&url).fetch_gemini().await.unwrap().is_empty());
+
+    let scanner = FreeModelScanner::new()
+        .with_gemini_url(&url)
+        .with_gemini_api_key("AIza-test");
+    let free_models = scanner.fetch_gemini().await.unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(free_models.len(), 1);
+    assert_eq!(free_models[0].id, "gemini-2.0-flash");
+    assert_eq!(free_models[0].source, Source::Gemini);
+    assert_eq!(free_models[0].endpoint, format!("{}/v1beta", server.url()));
+    assert_eq!(free_models[0].context_length, Some(1048576));
+}
+
+#[tokio::test]
+async fn fetches_cerebras_models_with_key() {
+    let mut server = mockito::Server::new_async().await;
+
+    let cerebras_response = serde_json::json!({
+        "object": "list",
+        "data": [
+            {"id": "llama3.1-8b", "object": "model", "owned_by": "Meta"},
+            {"id": "qwen-3-32b", "object": "model", "owned_by": "Qwen"},
+        ]
+    });
+
+    let mock = server
+        .mock("GET", "/v1/models")
+        .match_header("authorization", "Bearer csk-test")
+        .with_header("content-type", "application/json")
+        .with_body(cerebras_response.to_string())
+        .create_async()
+        .await;
+
+    let url = format!("{}/v1/models", server.url());
+    assert!(FreeModelScanner::new()
+        .with_cerebras_url(&url)
+        .fetch_cerebras()
+        .await
+        .unwrap()
+        .is_empty());
+
+    let scanner = FreeModelScanner::new()
+        .with_cerebras_url(&url)
+        .with_cerebras_api_key("csk-test");
+    let free_models = scanner.fetch_cerebras().await.unwrap();
+
+    mock.assert_async().await;
+    let ids: Vec<&str> = free_models.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, vec!["llama3.1-8b", "qwen-3-32b"]);
+    assert_eq!(free_models[0].source, Source::Cerebras);
+    assert_eq!(free_models[0].endpoint, format!("{}/v1", server.url()));
+}
+
+#[test]
+fn filters_mistral_free_tier_by_pricing() {
+    let models = vec![
+        serde_json::json!({
+            "id": "open-mistral-nemo",
+            "capabilities": {"completion_chat": true},
+            "max_context_length": 131072,
+            "deprecation": null
+        }),
+        serde_json::json!({
+            "id": "mistral-small-latest",
+            "capabilities": {"completion_chat": true},
+            "pricing": {"input": "0", "output": "0"}
+        }),
+        serde_json::json!({
+            "id": "mistral-large-latest",
+            "capabilities": {"completion_chat": true},
+            "pricing": {"input": 2.0, "output": 6.0}
+        }),
+        serde_json::json!({"id": "mistral-large-2402", "deprecation": "2024-11-30T12:00:00Z"}),
+        serde_json::json!({"id": "open-embed", "capabilities": {"completion_chat": false}}),
+    ];
+
+    let free = FreeModelScanner::filter_mistral_free(&models, "https://api.mistral.ai/v1");
+
+    let ids: Vec<&str> = free.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, vec!["open-mistral-nemo", "mistral-small-latest"]);
+    assert_eq!(free[0].source, Source::Mistral);
+    assert_eq!(free[0].endpoint, "https://api.mistral.ai/v1");
+    assert_eq!(free[0].context_length, Some(131072));
+}
+
+#[tokio::test]
+async fn fetches_zero_priced_together_chat_models_with_key() {
+    let mut server = mockito::Server::new_async().await;
+
+    let catalog = serde_json::json!([
+        {
+            "id": "meta-llama/Llama-3.3-70B-Instruct-Turbo-Free",
+            "type": "chat",
+            "context_length": 131072,
+            "pricing": {"input": 0, "output": 0, "hourly": 0}
+        },
+        {
+            "id": "meta-llama/Llama-3.3-70B-Instruct-Turbo",
+            "type": "chat",
+            "pricing": {"input": 0.88, "output": 0.88}
+        },
+        {"id": "BAAI/bge-large-en-v1.5", "type": "embedding", "pricing": {"input": 0, "output": 0}},
+        {"id": "unpriced/model", "type": "chat"},
+    ]);
+    let mock = server
+        .mock("GET", "/v1/models")
+        .match_header("authorization", "Bearer test-key")
+        .with_body(catalog.to_string())
+        .create_async()
+        .await;
+
+    let scanner = FreeModelScanner::new()
+        .with_together_url(&format!("{}/v1/models", server.url()))
+        .with_together_api_key("test-key");
+    let free = scanner.fetch_together().await.unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(free.len(), 1);
+    assert_eq!(free[0].id, "meta-llama/Llama-3.3-70B-Instruct-Turbo-Free");
+    assert_eq!(free[0].source, Source::Together);
+    assert_eq!(free[0].endpoint, format!("{}/v1", server.url()));
+    assert_eq!(free[0].context_length, Some(131072));
+}
+
+#[tokio::test]
+async fn fetches_sambanova_llama_models_with_key() {
+    let mut server = mockito::Server::new_async().await;
+
+    let mock = server
+        .mock("GET", "/v1/models")
+        .match_header("authorization", "Bearer test-key")
+        .with_body(
+            serde_json::json!({"data": [
+                {"id": "Meta-Llama-3.3-70B-Instruct", "context_length": 131072},
+                {"id": "Llama-4-Maverick-17B-128E-Instruct"},
+                {"id": "DeepSeek-R1"},
+            ]})
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let scanner = FreeModelScanner::new()
+        .with_sambanova_url(&format!("{}/v1/models", server.url()))
+        .with_sambanova_api_key("test-key");
+    let free = scanner.fetch_sambanova().await.unwrap();
+
+    mock.assert_async().await;
+    let ids: Vec<&str> = free.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, ["Meta-Llama-3.3-70B-Instruct", "Llama-4-Maverick-17B-128E-Instruct"]);
+    assert_eq!(free[0].source, Source::SambaNova);
+    assert_eq!(free[0].endpoint, format!("{}/v1", server.url()));
+    assert_eq!(free[0].context_length, Some(131072));
+}
+
+#[tokio::test]
+async fn fetches_github_models_chat_catalog_with_token() {
+    let mut server = mockito::Server::new_async().await;
+
+    let catalog = serde_json::json!([
+        {"name": "gpt-4o-mini", "publisher": "OpenAI", "task": "chat-completion"},
+        {"name": "Meta-Llama-3.1-8B-Instruct", "publisher": "Meta", "task": "chat-completion"},
+        {"name": "text-embedding-3-small", "publisher": "OpenAI", "task": "embeddings"},
+    ]);
+
+    let mock = server
+        .mock("GET", "/models")
+        .match_header("authorization", "Bearer ghp-test")
+        .with_header("content-type", "application/json")
+        .with_body(catalog.to_string())
+        .create_async()
+        .await;
+
+    let url = format!("{}/models", server.url());
+    assert!(FreeModelScanner::new()
+        .with_github_models_url(&url)
+        .fetch_github_models()
+        .await
+        .unwrap()
+        .is_empty());
+
+    let scanner = FreeModelScanner::new()
+        .with_github_models_url(&url)
+        .with_github_models_api_key("ghp-test");
+    let free_models = scanner.fetch_github_models().await.unwrap();
+
+    mock.assert_async().await;
+    let ids: Vec<&str> = free_models.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, vec!["gpt-4o-mini", "Meta-Llama-3.1-8B-Instruct"]);
+    assert_eq!(free_models[0].source, Source::GitHubModels);
+    assert_eq!(free_models[0].endpoint, server.url());
+}
+
+#[tokio::test]
+async fn fetches_cloudflare_free_text_generation_models() {
+    let mut server = mockito::Server::new_async().await;
+
+    let catalog = serde_json::json!({
+        "success": true,
+        "result": [
+            {
+                "name": "@cf/meta/llama-3.2-1b-instruct",
+                "task": {"name": "Text Generation"},
+                "properties": [{"property_id": "context_window", "value": "60000"}]
+            },
+            {
+                "name": "@cf/qwen/qwq-32b",
+                "task": {"name": "Text Generation"},
+                "properties": [
+                    {"property_id": "beta", "value": "true"},
+                    {"property_id": "price", "value": [{"unit": "per M input tokens", "price": 0.66}]}
+                ]
+            },
+            {
+                "name": "@cf/meta/llama-3.3-70b-instruct-fp8-fast",
+                "task": {"name": "Text Generation"},
+                "properties": [
+                    {"property_id": "price", "value": [{"unit": "per M input tokens", "price": 0.29}]}
+                ]
+            },
+        ]
+    });
+
+    let mock = server
+        .mock("GET", "/accounts/acct/ai/models/search")
+        .match_query(mockito::Matcher::UrlEncoded("task".into(), "Text Generation".into()))
+        .match_header("authorization", "Bearer cf-token")
+        .with_header("content-type", "application/json")
+        .with_body(catalog.to_string())
+        .create_async()
+        .await;
+
+    let scanner = FreeModelScanner::new()
+        .with_cloudflare_url(&server.url())
+        .with_cloudflare_account("acct", "cf-token");
+    let free_models = scanner.fetch_cloudflare().await.unwrap();
+
+    mock.assert_async().await;
+    let ids: Vec<&str> = free_models.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, vec!["@cf/meta/llama-3.2-1b-instruct", "@cf/qwen/qwq-32b"]);
+    assert_eq!(free_models[0].source, Source::Cloudflare);
+    assert_eq!(free_models[0].endpoint, format!("{}/accounts/acct/ai", server.url()));
+    assert_eq!(free_models[0].context_length, Some(60000));
+}
+
+#[tokio::test]
+async fn detects_and_fetches_lmstudio_models() {
+    let mut server = mockito::Server::new_async().await;
+
+    let models = serde_json::json!({
+        "object": "list",
+        "data": [
+            {"id": "qwen2.5-7b-instruct", "object": "model", "owned_by": "organization_owner"},
+            {"id": "text-embedding-nomic-embed-text-v1.5", "object": "model"},
+        ]
+    });
+
+    server
+        .mock("GET", "/v1/models")
+        .with_header("content-type", "application/json")
+        .with_body(models.to_string())
+        .expect_at_least(2)
+        .create_async()
+        .await;
+    server.mock("GET", "/health").with_status(404).create_async().await;
+
+    assert!(FreeModelScanner::detect_lmstudio(&server.url()).await);
+
+    let scanner = FreeModelScanner::new().with_lmstudio_url(&server.url());
+    let free_models = scanner.fetch_lmstudio().await.unwrap();
+
+    assert_eq!(free_models.len(), 1);
+    assert_eq!(free_models[0].id, "qwen2.5-7b-instruct");
+    assert_eq!(free_models[0].source, Source::LmStudio);
+    assert_eq!(free_models[0].endpoint, server.url());
+    assert!(Source::LmStudio < Source::OpenCodeZen);
+}
+
+#[tokio::test]
+async fn multiai_gateways_are_not_detected_as_lmstudio() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/v1/models")
+        .with_body(r#"{"object": "list", "data": []}"#)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/health")
+        .with_body(r#"{"status": "ok", "app": "multiai"}"#)
+        .create_async()
+        .await;
+
+    assert!(!FreeModelScanner::detect_lmstudio(&server.url()).await);
+}
+
+#[tokio::test]
+async fn detects_and_fetches_llamacpp_models() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/props")
+        .with_body(r#"{"default_generation_settings": {"n_ctx": 4096}, "total_slots": 1}"#)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/v1/models")
+        .with_body(
+            serde_json::json!({
+                "object": "list",
+                "data": [{"id": "qwen2.5-0.5b-instruct-q8_0.gguf", "owned_by": "llamacpp",
+                          "meta": {"n_ctx_train": 32768}}]
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    assert!(FreeModelScanner::detect_llamacpp(&server.url()).await);
+
+    let scanner = FreeModelScanner::new().with_llamacpp_url(&server.url());
+    let free_models = scanner.fetch_llamacpp().await.unwrap();
+
+    assert_eq!(free_models.len(), 1);
+    assert_eq!(free_models[0].id, "qwen2.5-0.5b-instruct-q8_0.gguf");
+    assert_eq!(free_models[0].source, Source::LlamaCpp);
+    assert_eq!(free_models[0].endpoint, server.url());
+    assert_eq!(free_models[0].context_length, Some(32768));
+}
+
+#[tokio::test]
+async fn merges_listed_and_discovered_custom_models() {
+    use crate::config::CustomProviderConfig;
+
+    let mut server = mockito::Server::new_async().await;
+    let discovery = server
+        .mock("GET", "/corp/v1/models")
+        .match_header("authorization", "Bearer corp-key")
+        .with_body(r#"{"object": "list", "data": [{"id": "corp-chat"}, {"id": "corp-code"}]}"#)
+        .create_async()
+        .await;
+    server.mock("GET", "/down/v1/models").with_status(503).create_async().await;
+
+    let scanner = FreeModelScanner::new().with_custom_providers(vec![
+        CustomProviderConfig {
+            name: "vllm".to_string(),
+            base_url: "http://10.0.0.5:8000/v1/".to_string(),
+            api_key: None,
+            models: vec!["meta-llama/Llama-3.1-8B-Instruct".to_string()],
+        },
+        CustomProviderConfig {
+            name: "corp".to_string(),
+            base_url: format!("{}/corp/v1", server.url()),
+            api_key: Some("corp-key".to_string()),
+            models: vec![],
+        },
+        CustomProviderConfig {
+            name: "down".to_string(),
+            base_url: format!("{}/down/v1", server.url()),
+            api_key: None,
+            models: vec![],
+        },
+    ]);
+    let free_models = scanner.fetch_custom().await.unwrap();
+
+    discovery.assert_async().await;
+    let ids: Vec<(&str, &str)> = free_models
+        .iter()
+        .map(|m| (m.provider.as_str(), m.id.as_str()))
+        .collect();
+    assert_eq!(
+        ids,
+        vec![
+            ("vllm", "meta-llama/Llama-3.1-8B-Instruct"),
+            ("corp", "corp-chat"),
+            ("corp", "corp-code"),
+        ]
+    );
+    assert!(free_models.iter().all(|m| m.source == Source::Custom));
+    assert_eq!(free_models[0].endpoint, "http://10.0.0.5:8000/v1");
+}
+
+#[test]
+fn captures_modalities_and_parameter_support() {
+    let scanner = FreeModelScanner::new();
+    let models = vec![
+        serde_json::json!({
+            "id": "google/gemma-3-27b-it:free",
+            "pricing": {"prompt": "0", "completion": "0"},
+            "context_length": 96000,
+            "architecture": {"input_modalities": ["text", "image"], "output_modalities": ["text"]},
+            "supported_parameters": ["max_tokens", "temperature", "tools", "tool_choice", "response_format"]
+        }),
+        serde_json::json!({
+            "id": "mistralai/mistral-7b-instruct:free",
+            "pricing": {"prompt": "0", "completion": "0"},
+            "architecture": {"input_modalities": ["text"]},
+            "supported_parameters": ["max_tokens"]
+        }),
+        serde_json::json!({
+            "id": "meta-llama/llama-3-8b-instruct:free",
+            "pricing": {"prompt": "0", "completion": "0"}
+        }),
+    ];
+
+    let free = scanner.filter_openrouter_free(&models);
+
+    assert_eq!(free[0].modalities, vec!["text", "image"]);
+    assert_eq!(free[0].supports_tools, Some(true));
+    assert_eq!(free[1].supports_tools, Some(false));
+    assert!(free[2].modalities.is_empty());
+    assert_eq!(free[2].supports_tools, None);
+    assert_eq!(free[0].supports_response_format, Some(true));
+    assert_eq!(free[1].supports_response_format, Some(false));
+    assert_eq!(free[2].supports_response_format, None);
+}
+
+#[tokio::test]
+async fn keep_warm_rescans_on_an_interval() {
+    let mut server = mockito::Server::new_async().await;
+    let openrouter = server
+        .mock("GET", "/openrouter")
+        .with_body(r#"{"data": [{"id": "test:free", "pricing": {"prompt": "0", "completion": "0"}}]}"#)
+        .expect_at_least(2)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/zen/docs")
+        .with_body("<table></table>")
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/zen/models")
+        .with_body(r#"{"data": []}"#)
+        .create_async()
+        .await;
+
+    let scanner = FreeModelScanner::new()
+        .with_openrouter_url(&format!("{}/openrouter", server.url()))
+        .with_opencode_zen_docs_url(&format!("{}/zen/docs", server.url()))
+        .with_opencode_zen_api_url(&format!("{}/zen/models", server.url()));
+    let task = tokio::spawn(scanner.clone().keep_warm(Duration::from_millis(50)));
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    task.abort();
+
+    assert!(scanner.has_scanned());
+    assert_eq!(scanner.get_free_models(false).await.len(), 1);
+    openrouter.assert_async().await;
+}
+
+#[tokio::test]
+async fn disk_cache_serves_saved_models_and_covers_failing_sources() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("models.json");
+    let mut server = mockito::Server::new_async().await;
+    let openrouter = server
+        .mock("GET", "/openrouter")
+        .with_body(r#"{"data": [{"id": "test:free", "pricing": {"prompt": "0", "completion": "0"}}]}"#)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/zen/docs")
+        .with_body("<table></table>")
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/zen/models")
+        .with_body(r#"{"data": []}"#)
+        .create_async()
+        .await;
+    let scanner = || {
+        FreeModelScanner::new()
+            .with_openrouter_url(&format!("{}/openrouter", server.url()))
+            .with_opencode_zen_docs_url(&format!("{}/zen/docs", server.url()))
+            .with_opencode_zen_api_url(&format!("{}/zen/models", server.url()))
+            .with_disk_cache(&path)
+    };
+
+    assert_eq!(scanner().get_free_models(true).await.len(), 1);
+    assert!(path.exists());
+
+    // A new process starts from the saved list, before scanning anything
+    openrouter.remove_async().await;
+    let restarted = scanner();
+    let saved = restarted.get_free_models(false).await;
+    assert_eq!(saved.len(), 1);
+    assert!(!restarted.has_scanned());
+
+    // OpenRouter is now failing, but its last known models are kept
+    server
+        .mock("GET", "/openrouter")
+        .with_status(503)
+        .create_async()
+        .await;
+    let models = restarted.get_free_models(true).await;
+    assert!(restarted.source_status()[&Source::OpenRouter].last_error.is_some());
+    assert_eq!(models.len(), 1);
+    assert_eq!(models[0].id, "test:free");
+}
+
+#[tokio::test]
+async fn sources_are_cached_with_their_own_ttls() {
+    let mut server = mockito::Server::new_async().await;
+    let openrouter = server
+        .mock("GET", "/openrouter")
+        .with_body(r#"{"data": [{"id": "test:free", "pricing": {"prompt": "0", "completion": "0"}}]}"#)
+        .expect(2)
+        .create_async()
+        .await;
+    // A failing source is not retried before its own TTL is up
+    let zen = server
+        .mock("GET", "/zen/models")
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let scanner = FreeModelScanner::new()
+        .with_openrouter_url(&format!("{}/openrouter", server.url()))
+        .with_opencode_zen_docs_url(&format!("{}/zen/docs", server.url()))
+        .with_opencode_zen_api_url(&format!("{}/zen/models", server.url()))
+        .with_cache_ttl_secs(300)
+        .with_source_ttl_secs(Source::OpenRouter, 1);
+
+    assert_eq!(scanner.get_free_models(false).await.len(), 1);
+    assert_eq!(scanner.get_free_models(false).await.len(), 1);
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(scanner.get_free_models(false).await.len(), 1);
+
+    openrouter.assert_async().await;
+    zen.assert_async().await;
+}
+
+#[test]
+fn diffs_models_by_source_and_id() {
+    let model = |id: &str, source: Source| FreeModel {
+        id: id.to_string(),
+        provider: "test".to_string(),
+        endpoint: "http://localhost".to_string(),
+        source,
+        context_length: None,
+        modalities: Vec::new(),
+        supports_tools: None,
+        supports_response_format: None,
+        source_index: None,
+    };
+    let before = vec![model("a", Source::OpenRouter), model("b", Source::OpenRouter)];
+    let after = vec![model("a", Source::OpenRouter), model("b", Source::Groq)];
+
+    let changes = diff_models(&before, &after);
+    assert_eq!(changes.added, vec![model("b", Source::Groq)]);
+    assert_eq!(changes.removed, vec![model("b", Source::OpenRouter)]);
+    assert!(diff_models(&after, &after).is_empty());
+}
+
+#[tokio::test]
+async fn broadcasts_models_added_and_removed_between_scans() {
+    let mut server = mockito::Server::new_async().await;
+    let first = server
+        .mock("GET", "/openrouter")
+        .with_body(r#"{"data": [{"id": "old:free", "pricing": {"prompt": "0", "completion": "0"}}]}"#)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/zen/docs")
+        .with_body("<table></table>")
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/zen/models")
+        .with_body(r#"{"data": []}"#)
+        .create_async()
+        .await;
+    let scanner = FreeModelScanner::new()
+        .with_openrouter_url(&format!("{}/openrouter", server.url()))
+        .with_opencode_zen_docs_url(&format!("{}/zen/docs", server.url()))
+        .with_opencode_zen_api_url(&format!("{}/zen/models", server.url()));
+    let mut changes = scanner.subscribe_changes();
+
+    // The first scan has nothing to compare with
+    scanner.get_free_models(true).await;
+    assert!(changes.try_recv().is_err());
+
+    first.remove_async().await;
+    server
+        .mock("GET", "/openrouter")
+        .with_body(r#"{"data": [{"id": "new:free", "pricing": {"prompt": "0", "completion": "0"}}]}"#)
+        .create_async()
+        .await;
+    scanner.get_free_models(true).await;
+
+    let change = changes.try_recv().unwrap();
+    assert_eq!(change.added[0].id, "new:free");
+    assert_eq!(change.removed[0].id, "old:free");
+    // An unchanged rescan is quiet
+    scanner.get_free_models(true).await;
+    assert!(changes.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn zen_prefers_api_pricing_over_the_docs_table() {
+    let mut server = mockito::Server::new_async().await;
+    let docs = server
+        .mock("GET", "/zen/docs")
+        .expect(0)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/zen/models")
+        .with_body(
+            serde_json::json!({"data": [
+                {"id": "big-pickle", "cost": {"input": 0, "output": 0}},
+                {"id": "claude-opus-4-5", "cost": {"input": 5.0, "output": 25.0}},
+                {"id": "gpt-5-nano", "pricing": {"prompt": "0", "completion": "0"}},
+            ]})
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let scanner = FreeModelScanner::new()
+        .with_openrouter_url(&format!("{}/openrouter", server.url()))
+        .with_opencode_zen_docs_url(&format!("{}/zen/docs", server.url()))
+        .with_opencode_zen_api_url(&format!("{}/zen/models", server.url()));
+    let models = scanner.get_free_models(true).await;
+
+    let ids: Vec<&str> = models.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, ["big-pickle", "gpt-5-nano"]);
+    assert_eq!(
+        scanner.source_status()[&Source::OpenCodeZen].detection,
+        Some(FreeDetection::Pricing)
+    );
+    docs.assert_async().await;
+}
+
+#[tokio::test]
+async fn zen_falls_back_to_the_bundled_list_when_the_docs_change() {
+    let mut server = mockito::Server::new_async().await;
+    let models = r#"{"data": [{"id": "big-pickle"}, {"id": "claude-opus-4-5"}]}"#;
+    server
+        .mock("GET", "/zen/models")
+        .with_body(models)
+        .create_async()
+        .await;
+    let docs = server
+        .mock("GET", "/zen/docs")
+        .with_body("<div>Pricing has moved</div>")
+        .create_async()
+        .await;
+
+    let scanner = FreeModelScanner::new()
+        .with_openrouter_url(&format!("{}/openrouter", server.url()))
+        .with_opencode_zen_docs_url(&format!("{}/zen/docs", server.url()))
+        .with_opencode_zen_api_url(&format!("{}/zen/models", server.url()));
+    let free_models = scanner.fetch_opencode_zen().await.unwrap();
+    assert_eq!(free_models.len(), 1);
+    assert_eq!(free_models[0].id, "big-pickle");
+
+    // An unreachable docs page doesn't fail the source either
+    docs.remove_async().await;
+    server
+        .mock("GET", "/zen/docs")
+        .with_status(500)
+        .create_async()
+        .await;
+    scanner.get_free_models(true).await;
+    let status = &scanner.source_status()[&Source::OpenCodeZen];
+    assert!(status.last_error.is_none());
+    assert_eq!(status.model_count, 1);
+    assert_eq!(status.detection, Some(FreeDetection::Bundled));
+}
+
+/// A plugin source serving a fixed list.
+struct StaticSource(Vec<FreeModel>);
+
+impl ModelSource for StaticSource {
+    fn source(&self) -> Source {
+        Source::Custom
+    }
+
+    fn name(&self) -> &str {
+        "static"
+    }
+
+    fn auth(&self) -> SourceAuth {
+        SourceAuth::None
+    }
+
+    fn fetch<'a>(&'a self, _scanner: &'a FreeModelScanner) -> FetchFuture<'a> {
+        Box::pin(async move { Ok(self.0.clone()) })
+    }
+}
+
+#[tokio::test]
+async fn registered_sources_are_scanned_with_the_builtin_ones() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/openrouter")
+        .with_body(r#"{"data": [{"id": "test:free", "pricing": {"prompt": "0", "completion": "0"}}]}"#)
+        .create_async()
+        .await;
+
+    let scanner = FreeModelScanner::new()
+        .with_openrouter_url(&format!("{}/openrouter", server.url()))
+        .with_opencode_zen_docs_url(&format!("{}/zen/docs", server.url()))
+        .with_opencode_zen_api_url(&format!("{}/zen/models", server.url()));
+    scanner.registry().register(StaticSource(vec![FreeModel {
+        id: "plugin-model".to_string(),
+        provider: "static".to_string(),
+        endpoint: "http://localhost:9999/v1".to_string(),
+        source: Source::Custom,
+        context_length: None,
+        modalities: Vec::new(),
+        supports_tools: None,
+        supports_response_format: None,
+        source_index: None,
+    }]));
+
+    let models = scanner.get_free_models(true).await;
+
+    let ids: Vec<&str> = models.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, ["test:free", "plugin-model"]);
+    assert_eq!(scanner.source_status()[&Source::Custom].model_count, 1);
+    // The plugin's own auth applies rather than the custom endpoints'
+    let plugin = &models[1];
+    assert_eq!(scanner.registry().auth(plugin), Some(SourceAuth::None));
+    let from_disk = FreeModel {
+        source_index: None,
+        ..plugin.clone()
+    };
+    assert_eq!(
+        scanner.registry().auth(&from_disk),
+        Some(SourceAuth::OptionalBearerKey)
+    );
+}
+
+#[tokio::test]
+async fn embedding_models_are_listed_apart_from_chat_models() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/api/tags")
+        .with_body(
+            serde_json::json!({"models": [
+                {"name": "llama3:8b", "details": {"family": "llama"}},
+                {"name": "nomic-embed-text:latest", "details": {"family": "nomic-bert"}},
+                {"name": "bge-m3:latest"},
+            ]})
+            .to_string(),
+        )
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/openrouter/models")
+        .with_body(r#"{"data": []}"#)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/openrouter/embeddings/models")
+        .with_body(
+            serde_json::json!({"data": [
+                {"id": "thenlper/gte-base", "pricing": {"prompt": "0"}},
+                {"id": "openai/text-embedding-3-small", "pricing": {"prompt": "0.00000002"}},
+            ]})
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let scanner = FreeModelScanner::new()
+        .with_ollama_url(&server.url())
+        .with_openrouter_url(&format!("{}/openrouter/models", server.url()));
+
+    let chat = scanner.fetch_ollama().await.unwrap();
+    let chat_ids: Vec<&str> = chat.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(chat_ids, ["llama3:8b"]);
+
+    let embeddings = scanner.get_embedding_models(false).await;
+    let ids: Vec<&str> = embeddings.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, ["nomic-embed-text:latest", "bge-m3:latest", "thenlper/gte-base"]);
+    assert_eq!(embeddings[2].endpoint, format!("{}/openrouter", server.url()));
+}
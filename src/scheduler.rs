@@ -0,0 +1,445 @@
+//! Recurring jobs run inside the server (`[[scheduler.jobs]]`).
+//!
+//! Each job runs one task on an interval (`every = "30m"`) or daily at a UTC
+//! time (`at = "03:00"`). The outcome of every job's last run is kept and
+//! reported under `scheduler` in `/health`, so a backup that stopped working
+//! is visible without reading the logs.
+
+use crate::chat_api::ChatState;
+use crate::config::{Config, EvalsConfig, ScheduledJobConfig};
+use crate::evals::{self, EvalStore};
+use crate::export::ExportChat;
+use crate::leaderboard::Leaderboard;
+use crate::mcp::ModelComparator;
+use crate::scanner::FreeModelScanner;
+use crate::storage::LazyStore;
+use crate::traffic_log::TrafficLogWriter;
+use chrono::{DateTime, NaiveTime, Utc};
+use reqwest::Client;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// What a job does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Task {
+    /// Rescan every source for free models.
+    ModelRefresh,
+    /// Delete traffic logs older than `[logging] retention_days`.
+    LogPrune,
+    /// Export every chat to a dated JSON file.
+    ChatBackup,
+    /// Check the chat DB, the Ollama server and the last source scans.
+    HealthProbe,
+    /// Run the datasets selected by `[evals]`.
+    EvalRun,
+}
+
+impl Task {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "model_refresh" => Some(Self::ModelRefresh),
+            "log_prune" => Some(Self::LogPrune),
+            "chat_backup" => Some(Self::ChatBackup),
+            "health_probe" => Some(Self::HealthProbe),
+            "eval_run" => Some(Self::EvalRun),
+            _ => None,
+        }
+    }
+}
+
+/// When a job runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    Every(Duration),
+    /// Once a day at this UTC time.
+    Daily(NaiveTime),
+}
+
+impl Schedule {
+    /// Build the schedule from a job's `every` or `at`.
+    pub fn from_job(job: &ScheduledJobConfig) -> Result<Self, String> {
+        match (job.every.as_deref(), job.at.as_deref()) {
+            (Some(every), None) => parse_interval(every).map(Self::Every),
+            (None, Some(at)) => NaiveTime::parse_from_str(at, "%H:%M")
+                .map(Self::Daily)
+                .map_err(|_| format!("Invalid time '{}', expected HH:MM", at)),
+            _ => Err("Set exactly one of 'every' or 'at'".to_string()),
+        }
+    }
+
+    /// The first run strictly after `now`.
+    pub fn next_after(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Self::Every(interval) => now + *interval,
+            Self::Daily(time) => {
+                let today = now.date_naive().and_time(*time).and_utc();
+                if today > now {
+                    today
+                } else {
+                    today + chrono::Days::new(1)
+                }
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Every(interval) => format!("every {}s", interval.as_secs()),
+            Self::Daily(time) => format!("daily at {} UTC", time.format("%H:%M")),
+        }
+    }
+}
+
+/// Parse "30s", "15m", "6h" or "1d".
+fn parse_interval(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid interval '{}', expected e.g. 30m or 6h", value);
+    let value = value.trim();
+    let unit = value.chars().last().ok_or_else(invalid)?;
+    let count: u64 = value[..value.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+    let secs = match unit {
+        's' => count,
+        'm' => count * 60,
+        'h' => count * 60 * 60,
+        'd' => count * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    if secs == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// Last-run status of one job, as shown in `/health`.
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct JobStatus {
+    pub task: Task,
+    pub schedule: String,
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_run: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<u64>,
+    pub last_ok: Option<bool>,
+    /// Summary of the last run, or its error.
+    pub last_result: Option<String>,
+}
+
+/// What jobs need from the running server.
+#[derive(Clone)]
+pub struct JobContext {
+    pub scanner: FreeModelScanner,
+    pub http: Client,
+    pub chat: Arc<ChatState>,
+    pub traffic_log: Option<TrafficLogWriter>,
+    pub retention_days: u32,
+    pub backup_folder: PathBuf,
+    pub ollama_url: Option<String>,
+    pub evals: LazyStore<EvalStore>,
+    pub evals_config: EvalsConfig,
+    pub leaderboard: LazyStore<Leaderboard>,
+    /// The server's config, for the keys eval runs query and judge with.
+    pub config: Arc<RwLock<Config>>,
+}
+
+impl JobContext {
+    /// Run one task, returning a short summary or the error.
+    pub async fn run(&self, task: Task) -> Result<String, String> {
+        match task {
+            Task::ModelRefresh => {
+                let models = self.scanner.get_free_models(true).await;
+                Ok(format!("{} models", models.len()))
+            }
+            Task::LogPrune => {
+                let Some(traffic_log) = &self.traffic_log else {
+                    return Ok("traffic logging is disabled".to_string());
+                };
+                let removed = traffic_log
+                    .prune(self.retention_days)
+                    .map_err(|e| e.to_string())?;
+                Ok(format!("{} files removed", removed))
+            }
+            Task::ChatBackup => self.backup_chats(),
+            Task::HealthProbe => self.probe().await,
+            Task::EvalRun => {
+                let Some(store) = self.evals.get() else {
+                    return Err("Eval store is unavailable".to_string());
+                };
+                let comparator = {
+                    let config = self.config.read().unwrap();
+                    ModelComparator::from_parts(self.scanner.clone(), self.http.clone(), &config)
+                        .with_leaderboard(self.leaderboard.get().cloned())
+                };
+                let ran = evals::run_configured(store, &comparator, &self.evals_config).await?;
+                Ok(format!("{} datasets run", ran))
+            }
+        }
+    }
+
+    fn backup_chats(&self) -> Result<String, String> {
+        let chats: Vec<ExportChat> = {
+            let db = self.chat.db.lock().unwrap();
+            let chats = db.list_chats().map_err(|e| e.to_string())?;
+            chats
+                .iter()
+                .map(|chat| {
+                    let messages = db.get_messages(&chat.id)?;
+                    Ok(ExportChat::from_chat(chat, messages))
+                })
+                .collect::<rusqlite::Result<_>>()
+                .map_err(|e| e.to_string())?
+        };
+        std::fs::create_dir_all(&self.backup_folder).map_err(|e| e.to_string())?;
+        let path = self
+            .backup_folder
+            .join(format!("chats-{}.json", Utc::now().format("%Y-%m-%d")));
+        let data = serde_json::to_vec_pretty(&chats).map_err(|e| e.to_string())?;
+        std::fs::write(&path, data).map_err(|e| e.to_string())?;
+        Ok(format!("{} chats to {}", chats.len(), path.display()))
+    }
+
+    async fn probe(&self) -> Result<String, String> {
+        let mut problems = Vec::new();
+        let ping = self.chat.db.lock().unwrap().ping();
+        if let Err(e) = ping {
+            problems.push(format!("chat DB: {}", e));
+        }
+        if let Some(url) = &self.ollama_url {
+            if !FreeModelScanner::detect_ollama(url).await {
+                problems.push(format!("Ollama at {} is unreachable", url));
+            }
+        }
+        for (source, status) in self.scanner.source_status() {
+            if let Some(error) = status.last_error {
+                problems.push(format!("{:?}: {}", source, error));
+            }
+        }
+        if problems.is_empty() {
+            Ok("healthy".to_string())
+        } else {
+            Err(problems.join("; "))
+        }
+    }
+}
+
+/// Runs the configured jobs and records their status.
+#[derive(Clone, Default)]
+pub struct Scheduler {
+    status: Arc<Mutex<BTreeMap<String, JobStatus>>>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Status of every job, by name.
+    pub fn status(&self) -> BTreeMap<String, JobStatus> {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Spawn a loop for each valid job. Invalid jobs are logged and skipped.
+    pub fn start(&self, jobs: &[ScheduledJobConfig], context: JobContext) {
+        for job in jobs {
+            let name = job.name.clone().unwrap_or_else(|| job.task.clone());
+            let Some(task) = Task::parse(&job.task) else {
+                tracing::warn!("Scheduled job '{}': unknown task '{}'", name, job.task);
+                continue;
+            };
+            let schedule = match Schedule::from_job(job) {
+                Ok(schedule) => schedule,
+                Err(e) => {
+                    tracing::warn!("Scheduled job '{}': {}", name, e);
+                    continue;
+                }
+            };
+            self.status.lock().unwrap().insert(
+                name.clone(),
+                JobStatus {
+                    task,
+                    schedule: schedule.describe(),
+                    next_run: Some(schedule.next_after(Utc::now())),
+                    last_run: None,
+                    last_duration_ms: None,
+                    last_ok: None,
+                    last_result: None,
+                },
+            );
+            tokio::spawn(self.clone().run_job(name, task, schedule, context.clone()));
+        }
+    }
+
+    async fn run_job(self, name: String, task: Task, schedule: Schedule, context: JobContext) {
+        loop {
+            let next = schedule.next_after(Utc::now());
+            let wait = (next - Utc::now()).to_std().unwrap_or_default();
+            tokio::time::sleep(wait).await;
+
+            let started_at = Utc::now();
+            let started = Instant::now();
+            let result = context.run(task).await;
+            match &result {
+                Ok(summary) => tracing::info!("Scheduled job '{}': {}", name, summary),
+                Err(e) => tracing::warn!("Scheduled job '{}' failed: {}", name, e),
+            }
+            self.record(&name, started_at, started.elapsed(), result, schedule);
+        }
+    }
+
+    fn record(
+        &self,
+        name: &str,
+        started_at: DateTime<Utc>,
+        elapsed: Duration,
+        result: Result<String, String>,
+        schedule: Schedule,
+    ) {
+        if let Some(status) = self.status.lock().unwrap().get_mut(name) {
+            status.last_run = Some(started_at);
+            status.last_duration_ms = Some(elapsed.as_millis() as u64);
+            status.last_ok = Some(result.is_ok());
+            status.last_result = Some(result.unwrap_or_else(|e| e));
+            status.next_run = Some(schedule.next_after(Utc::now()));
+        }
+    }
+}
+
+/// Folder `chat_backup` writes to.
+pub fn backup_folder(config: &Config) -> PathBuf {
+    config
+        .scheduler
+        .backup_folder
+        .clone()
+        .unwrap_or_else(|| config.logging.folder.join("backups"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::ChatDb;
+    use chrono::TimeZone;
+
+    fn job(every: Option<&str>, at: Option<&str>) -> ScheduledJobConfig {
+        ScheduledJobConfig {
+            task: "model_refresh".to_string(),
+            name: None,
+            every: every.map(String::from),
+            at: at.map(String::from),
+        }
+    }
+
+    #[test]
+    fn parses_intervals_and_daily_times() {
+        assert_eq!(
+            Schedule::from_job(&job(Some("30m"), None)),
+            Ok(Schedule::Every(Duration::from_secs(30 * 60)))
+        );
+        assert_eq!(
+            Schedule::from_job(&job(Some("1d"), None)),
+            Ok(Schedule::Every(Duration::from_secs(24 * 60 * 60)))
+        );
+        assert_eq!(
+            Schedule::from_job(&job(None, Some("03:15"))),
+            Ok(Schedule::Daily(NaiveTime::from_hms_opt(3, 15, 0).unwrap()))
+        );
+        assert!(Schedule::from_job(&job(Some("0m"), None)).is_err());
+        assert!(Schedule::from_job(&job(Some("10x"), None)).is_err());
+        assert!(Schedule::from_job(&job(None, Some("25:00"))).is_err());
+        assert!(Schedule::from_job(&job(Some("1h"), Some("03:00"))).is_err());
+        assert!(Schedule::from_job(&job(None, None)).is_err());
+    }
+
+    #[test]
+    fn daily_schedule_runs_next_occurrence() {
+        let daily = Schedule::Daily(NaiveTime::from_hms_opt(3, 0, 0).unwrap());
+        let before = Utc.with_ymd_and_hms(2026, 1, 2, 1, 0, 0).unwrap();
+        let after = Utc.with_ymd_and_hms(2026, 1, 2, 3, 0, 0).unwrap();
+
+        assert_eq!(
+            daily.next_after(before),
+            Utc.with_ymd_and_hms(2026, 1, 2, 3, 0, 0).unwrap()
+        );
+        assert_eq!(
+            daily.next_after(after),
+            Utc.with_ymd_and_hms(2026, 1, 3, 3, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_tasks_are_not_started() {
+        assert_eq!(Task::parse("eval_run"), Some(Task::EvalRun));
+        assert_eq!(Task::parse("reboot"), None);
+    }
+
+    fn context(backup_folder: PathBuf) -> JobContext {
+        let db = ChatDb::in_memory().unwrap();
+        db.create_chat("c1", "Backups").unwrap();
+        JobContext {
+            scanner: FreeModelScanner::new(),
+            http: Client::new(),
+            chat: Arc::new(ChatState::new(db)),
+            traffic_log: None,
+            retention_days: 30,
+            backup_folder,
+            ollama_url: None,
+            evals: None.into(),
+            evals_config: EvalsConfig::default(),
+            leaderboard: None.into(),
+            config: Arc::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn chat_backup_writes_dated_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let context = context(dir.path().join("backups"));
+
+        let summary = context.run(Task::ChatBackup).await.unwrap();
+        assert!(summary.starts_with("1 chats"), "{}", summary);
+
+        let file = std::fs::read_dir(dir.path().join("backups"))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let chats: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(file.path()).unwrap()).unwrap();
+        assert_eq!(chats[0]["title"], "Backups");
+    }
+
+    #[test]
+    fn records_last_run_status() {
+        let scheduler = Scheduler::new();
+        let schedule = Schedule::Every(Duration::from_secs(60));
+        scheduler.status.lock().unwrap().insert(
+            "probe".to_string(),
+            JobStatus {
+                task: Task::HealthProbe,
+                schedule: schedule.describe(),
+                next_run: None,
+                last_run: None,
+                last_duration_ms: None,
+                last_ok: None,
+                last_result: None,
+            },
+        );
+
+        scheduler.record(
+            "probe",
+            Utc::now(),
+            Duration::from_millis(5),
+            Err("chat DB: locked".to_string()),
+            schedule,
+        );
+
+        let status = &scheduler.status()["probe"];
+        assert_eq!(status.last_ok, Some(false));
+        assert_eq!(status.last_result.as_deref(), Some("chat DB: locked"));
+        assert_eq!(status.last_duration_ms, Some(5));
+        assert!(status.next_run.is_some());
+    }
+}
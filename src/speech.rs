@@ -0,0 +1,242 @@
+//! Text-to-speech routing for `/v1/audio/speech`.
+//!
+//! Requests in OpenAI's audio/speech shape are forwarded to the
+//! OpenAI-compatible servers in `[[audio.speech]]`, such as a local
+//! Kokoro-FastAPI, openedai-speech or LocalAI. Servers that list the
+//! requested model are tried first, then ones that accept any model, then
+//! ones with a `default_model` to substitute. A server that fails with a
+//! retryable error is skipped for the next one.
+
+use crate::config::SpeechBackendConfig;
+use crate::error::MultiAiError;
+use reqwest::{Client, Response};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Longest input accepted, matching OpenAI's limit.
+pub const MAX_INPUT_CHARS: usize = 4096;
+
+/// Body of `POST /v1/audio/speech`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct SpeechRequest {
+    #[serde(default = "default_model")]
+    pub model: String,
+    /// Text to read aloud.
+    pub input: String,
+    #[serde(default = "default_voice")]
+    pub voice: String,
+    /// mp3, opus, aac, flac, wav or pcm; the server's default when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f32>,
+}
+
+fn default_model() -> String {
+    "tts-1".to_string()
+}
+
+fn default_voice() -> String {
+    "alloy".to_string()
+}
+
+impl SpeechRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        let chars = self.input.chars().count();
+        if self.input.trim().is_empty() {
+            Err("input must not be empty".to_string())
+        } else if chars > MAX_INPUT_CHARS {
+            Err(format!(
+                "input is {} characters; the limit is {}",
+                chars, MAX_INPUT_CHARS
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Servers to try for `model`, in order, with the model to send to each.
+pub fn candidates<'a>(
+    backends: &'a [SpeechBackendConfig],
+    model: &'a str,
+) -> Vec<(&'a SpeechBackendConfig, &'a str)> {
+    let listing = backends
+        .iter()
+        .filter(|b| b.models.iter().any(|m| m == model))
+        .map(|b| (b, model));
+    let open = backends
+        .iter()
+        .filter(|b| b.models.is_empty())
+        .map(|b| (b, model));
+    let substituting = backends
+        .iter()
+        .filter(|b| !b.models.is_empty() && !b.models.iter().any(|m| m == model))
+        .filter_map(|b| Some((b, b.default_model.as_deref()?)));
+    listing.chain(open).chain(substituting).collect()
+}
+
+/// Send the request to the first server that accepts it. The returned
+/// response has a success status and the audio as its body.
+pub async fn synthesize(
+    client: &Client,
+    backends: &[SpeechBackendConfig],
+    request: &SpeechRequest,
+) -> Result<Response, MultiAiError> {
+    let candidates = candidates(backends, &request.model);
+    if candidates.is_empty() {
+        return Err(if backends.is_empty() {
+            MultiAiError::ConfigError(
+                "No speech backend configured; add one under [[audio.speech]]".to_string(),
+            )
+        } else {
+            MultiAiError::InvalidRequest(format!(
+                "No speech backend provides '{}'",
+                request.model
+            ))
+        });
+    }
+
+    let mut last_error = None;
+    for (backend, model) in candidates {
+        let body = SpeechRequest {
+            model: model.to_string(),
+            ..request.clone()
+        };
+        let url = format!("{}/audio/speech", backend.url.trim_end_matches('/'));
+        let mut req = client.post(&url).json(&body);
+        if let Some(key) = &backend.api_key {
+            req = req.bearer_auth(key);
+        }
+        let error = match req.send().await {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) => {
+                let status = response.status().as_u16();
+                let text = response.text().await.unwrap_or_default();
+                MultiAiError::from_upstream(status, &text)
+                    .unwrap_or_else(|| MultiAiError::UpstreamError(format!("HTTP {}", status)))
+            }
+            Err(e) => MultiAiError::from_request_error(&e),
+        };
+        tracing::warn!("Speech backend {} failed: {}", backend.url, error);
+        if !error.is_retryable() {
+            return Err(error);
+        }
+        last_error = Some(error);
+    }
+    Err(last_error.unwrap_or(MultiAiError::NoModelsAvailable))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backend(url: &str, models: &[&str], default_model: Option<&str>) -> SpeechBackendConfig {
+        SpeechBackendConfig {
+            url: url.to_string(),
+            api_key: None,
+            models: models.iter().map(|m| m.to_string()).collect(),
+            default_model: default_model.map(String::from),
+        }
+    }
+
+    fn request(model: &str) -> SpeechRequest {
+        serde_json::from_value(serde_json::json!({"model": model, "input": "Hello"})).unwrap()
+    }
+
+    #[test]
+    fn request_defaults_and_validation() {
+        let req: SpeechRequest = serde_json::from_str(r#"{"input": "Hi"}"#).unwrap();
+        assert_eq!(req.model, "tts-1");
+        assert_eq!(req.voice, "alloy");
+        assert!(req.validate().is_ok());
+
+        let empty = SpeechRequest {
+            input: "  ".to_string(),
+            ..req.clone()
+        };
+        assert!(empty.validate().is_err());
+        let long = SpeechRequest {
+            input: "a".repeat(MAX_INPUT_CHARS + 1),
+            ..req
+        };
+        assert!(long.validate().unwrap_err().contains("limit is 4096"));
+    }
+
+    #[test]
+    fn candidates_prefer_listing_then_open_then_substituting() {
+        let backends = [
+            backend("http://kokoro", &["kokoro"], Some("kokoro")),
+            backend("http://any", &[], None),
+            backend("http://piper", &["piper"], None),
+        ];
+
+        let urls = |model| -> Vec<(String, String)> {
+            candidates(&backends, model)
+                .into_iter()
+                .map(|(b, m)| (b.url.clone(), m.to_string()))
+                .collect()
+        };
+        assert_eq!(
+            urls("kokoro"),
+            vec![
+                ("http://kokoro".to_string(), "kokoro".to_string()),
+                ("http://any".to_string(), "kokoro".to_string()),
+            ]
+        );
+        assert_eq!(
+            urls("tts-1"),
+            vec![
+                ("http://any".to_string(), "tts-1".to_string()),
+                ("http://kokoro".to_string(), "kokoro".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_next_backend_on_retryable_errors() {
+        let mut down = mockito::Server::new_async().await;
+        down.mock("POST", "/v1/audio/speech")
+            .with_status(503)
+            .with_body("loading")
+            .create_async()
+            .await;
+        let mut up = mockito::Server::new_async().await;
+        let speech = up
+            .mock("POST", "/v1/audio/speech")
+            .match_header("authorization", "Bearer local")
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({"model": "kokoro", "input": "Hello"}),
+            ))
+            .with_header("content-type", "audio/mpeg")
+            .with_body([0xffu8, 0xf3, 0x44])
+            .create_async()
+            .await;
+
+        let mut fallback = backend(&format!("{}/v1", up.url()), &["kokoro"], Some("kokoro"));
+        fallback.api_key = Some("local".to_string());
+        let backends = [backend(&format!("{}/v1/", down.url()), &[], None), fallback];
+
+        let response = synthesize(&Client::new(), &backends, &request("tts-1"))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["content-type"], "audio/mpeg");
+        assert_eq!(response.bytes().await.unwrap().as_ref(), [0xff, 0xf3, 0x44]);
+        speech.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn unconfigured_and_unknown_models_are_errors() {
+        let client = Client::new();
+        let err = synthesize(&client, &[], &request("tts-1"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MultiAiError::ConfigError(_)));
+
+        let backends = [backend("http://piper", &["piper"], None)];
+        let err = synthesize(&client, &backends, &request("tts-1"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MultiAiError::InvalidRequest(_)));
+    }
+}
@@ -0,0 +1,115 @@
+//! Where MultiAI keeps its local SQLite databases.
+//!
+//! Chats, caches, keys and the other stores each live in their own file
+//! under `<local data dir>/multiai`. Stores for optional features are
+//! wrapped in a [`LazyStore`], so their file only appears once used.
+
+use rusqlite::{ffi, Connection, Error, Result as SqlResult};
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+
+/// Path of the database file `name` in MultiAI's data directory.
+pub fn data_db_path(name: &str) -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("multiai")
+        .join(name)
+}
+
+/// Open or create the database file `name` in MultiAI's data directory,
+/// creating the directory first. A directory that can't be created is
+/// reported as the reason the database can't be opened.
+pub fn open_data_db(name: &str) -> SqlResult<Connection> {
+    let path = data_db_path(name);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            Error::SqliteFailure(
+                ffi::Error::new(ffi::SQLITE_CANTOPEN),
+                Some(format!("can't create {}: {}", parent.display(), e)),
+            )
+        })?;
+    }
+    Connection::open(path)
+}
+
+/// A store opened on first use. Clones share the one store; a store that
+/// fails to open is logged once and stays unavailable.
+pub struct LazyStore<T> {
+    store: Arc<OnceLock<Option<T>>>,
+    open: fn() -> SqlResult<T>,
+    name: &'static str,
+}
+
+impl<T> LazyStore<T> {
+    /// A store `open` opens on first use; `name` labels a failure to open.
+    pub fn new(name: &'static str, open: fn() -> SqlResult<T>) -> Self {
+        Self {
+            store: Arc::new(OnceLock::new()),
+            open,
+            name,
+        }
+    }
+
+    /// The store, opening it if this is the first use.
+    pub fn get(&self) -> Option<&T> {
+        self.store
+            .get_or_init(|| {
+                (self.open)()
+                    .map_err(|e| tracing::warn!("Failed to open {}: {}", self.name, e))
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    /// Whether the store has been opened, or tried to be.
+    pub fn is_opened(&self) -> bool {
+        self.store.get().is_some()
+    }
+}
+
+impl<T> Clone for LazyStore<T> {
+    fn clone(&self) -> Self {
+        Self {
+            store: Arc::clone(&self.store),
+            open: self.open,
+            name: self.name,
+        }
+    }
+}
+
+/// A store that is already open, or with `None`, one that never is.
+impl<T> From<Option<T>> for LazyStore<T> {
+    fn from(store: Option<T>) -> Self {
+        Self {
+            store: Arc::new(OnceLock::from(store)),
+            open: || Err(Error::InvalidQuery),
+            name: "store",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn databases_live_in_the_multiai_data_dir() {
+        let path = data_db_path("keys.db");
+        assert!(path.ends_with("multiai/keys.db"));
+    }
+
+    #[test]
+    fn lazy_stores_open_once_on_first_use() {
+        let store = LazyStore::new("test store", Connection::open_in_memory);
+        assert!(!store.is_opened());
+        let shared = store.clone();
+        assert!(store.get().is_some());
+        assert!(shared.is_opened());
+
+        let failing: LazyStore<Connection> =
+            LazyStore::new("test store", || Err(Error::InvalidQuery));
+        assert!(failing.get().is_none());
+        assert!(failing.is_opened());
+        assert!(LazyStore::<Connection>::from(None).get().is_none());
+    }
+}
@@ -0,0 +1,137 @@
+//! Opt-in anonymous usage telemetry.
+//!
+//! With `[telemetry] enabled = true` and an `endpoint` set, the gateway
+//! periodically POSTs aggregate counts: models found per source, requests
+//! and errors since the last report, and the app version. No prompts,
+//! responses, model names, keys or identifiers are ever included.
+
+use crate::scanner::{FreeModelScanner, Source};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Request counters accumulated between reports.
+#[derive(Clone, Default)]
+pub struct Telemetry {
+    requests: Arc<AtomicU64>,
+    errors: Arc<AtomicU64>,
+}
+
+/// Body of one telemetry report.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct TelemetryReport {
+    pub version: &'static str,
+    pub os: &'static str,
+    pub models_by_source: BTreeMap<Source, usize>,
+    pub requests: u64,
+    pub errors: u64,
+    /// Fraction of requests that failed, 0 when there were none.
+    pub error_rate: f64,
+}
+
+impl Telemetry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count one completion request.
+    pub fn record(&self, success: bool) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if !success {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Build a report and reset the counters.
+    pub fn take_report(&self, scanner: &FreeModelScanner) -> TelemetryReport {
+        let requests = self.requests.swap(0, Ordering::Relaxed);
+        let errors = self.errors.swap(0, Ordering::Relaxed);
+        TelemetryReport {
+            version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            models_by_source: scanner
+                .source_status()
+                .into_iter()
+                .map(|(source, status)| (source, status.model_count))
+                .collect(),
+            requests,
+            errors,
+            error_rate: if requests == 0 {
+                0.0
+            } else {
+                errors as f64 / requests as f64
+            },
+        }
+    }
+}
+
+/// Post a report to `endpoint` every `interval`, starting after the first one.
+pub async fn run(
+    telemetry: Telemetry,
+    scanner: FreeModelScanner,
+    client: reqwest::Client,
+    endpoint: String,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    // The first tick completes immediately; skip it so a report covers a full interval
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        let report = telemetry.take_report(&scanner);
+        if let Err(e) = client.post(&endpoint).json(&report).send().await {
+            tracing::debug!("Telemetry report failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_counts_requests_and_resets() {
+        let telemetry = Telemetry::new();
+        telemetry.record(true);
+        telemetry.record(true);
+        telemetry.record(true);
+        telemetry.record(false);
+
+        let report = telemetry.take_report(&FreeModelScanner::new());
+        assert_eq!((report.requests, report.errors), (4, 1));
+        assert_eq!(report.error_rate, 0.25);
+
+        let next = telemetry.take_report(&FreeModelScanner::new());
+        assert_eq!((next.requests, next.error_rate), (0, 0.0));
+    }
+
+    #[tokio::test]
+    async fn posts_reports_to_endpoint() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/report")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "version": env!("CARGO_PKG_VERSION"),
+                "requests": 1,
+            })))
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        let telemetry = Telemetry::new();
+        telemetry.record(true);
+        let task = tokio::spawn(run(
+            telemetry,
+            FreeModelScanner::new(),
+            reqwest::Client::new(),
+            format!("{}/report", server.url()),
+            Duration::from_millis(20),
+        ));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        task.abort();
+
+        mock.assert_async().await;
+    }
+}
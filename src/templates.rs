@@ -0,0 +1,264 @@
+//! Named prompt templates with `{{variable}}` placeholders.
+//!
+//! Templates are managed through `/api/templates` and stored in SQLite next
+//! to the chat database. A chat completion request can name one with
+//! `"template"` and fill it with `"variables"`; the expanded system prompt
+//! and user prompt are placed in front of the request's own messages.
+
+use crate::storage;
+use regex::Regex;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex};
+use utoipa::ToSchema;
+
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*([A-Za-z0-9_.-]+)\s*\}\}").unwrap());
+
+/// A stored prompt template.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct PromptTemplate {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Optional system prompt, which may also use placeholders.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    /// User prompt with `{{variable}}` placeholders.
+    pub prompt: String,
+}
+
+/// A template with its variables filled in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expansion {
+    pub system: Option<String>,
+    pub prompt: String,
+}
+
+impl PromptTemplate {
+    /// Names of the variables the template uses, in order of first use.
+    pub fn variables(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        let texts = self.system.iter().chain(std::iter::once(&self.prompt));
+        for caps in texts.flat_map(|text| PLACEHOLDER.captures_iter(text)) {
+            let name = caps[1].to_string();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Fill in the placeholders. Fails with the names of any missing variables.
+    pub fn expand(&self, variables: &BTreeMap<String, String>) -> Result<Expansion, String> {
+        let missing: Vec<String> = self
+            .variables()
+            .into_iter()
+            .filter(|name| !variables.contains_key(name))
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "Template '{}' is missing variables: {}",
+                self.name,
+                missing.join(", ")
+            ));
+        }
+        let fill = |text: &str| {
+            PLACEHOLDER
+                .replace_all(text, |caps: &regex::Captures| variables[&caps[1]].clone())
+                .into_owned()
+        };
+        Ok(Expansion {
+            system: self.system.as_deref().map(fill),
+            prompt: fill(&self.prompt),
+        })
+    }
+}
+
+/// Check a template name is usable in a URL path.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid template name '{}': use letters, digits, '-', '_' or '.'",
+            name
+        ))
+    }
+}
+
+/// SQLite-backed template store, shared across handlers.
+#[derive(Clone)]
+pub struct TemplateStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl TemplateStore {
+    /// Open the store in MultiAI's data directory.
+    pub fn open_default() -> SqlResult<Self> {
+        Self::with_connection(storage::open_data_db("templates.db")?)
+    }
+
+    /// Open or create a template database.
+    pub fn open<P: AsRef<Path>>(path: P) -> SqlResult<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Create an in-memory store (for testing).
+    pub fn in_memory() -> SqlResult<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> SqlResult<Self> {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS templates (
+                name TEXT PRIMARY KEY,
+                description TEXT,
+                system TEXT,
+                prompt TEXT NOT NULL
+            );
+            "#,
+        )?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// All templates, by name.
+    pub fn list(&self) -> SqlResult<Vec<PromptTemplate>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT name, description, system, prompt FROM templates ORDER BY name")?;
+        let templates = stmt.query_map([], row_to_template)?;
+        templates.collect()
+    }
+
+    pub fn get(&self, name: &str) -> SqlResult<Option<PromptTemplate>> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT name, description, system, prompt FROM templates WHERE name = ?",
+                params![name],
+                row_to_template,
+            )
+            .optional()
+    }
+
+    /// Add a new template. Returns false if the name is taken.
+    pub fn insert(&self, template: &PromptTemplate) -> SqlResult<bool> {
+        let rows = self.conn.lock().unwrap().execute(
+            "INSERT OR IGNORE INTO templates (name, description, system, prompt) VALUES (?, ?, ?, ?)",
+            params![
+                template.name,
+                template.description,
+                template.system,
+                template.prompt
+            ],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Replace an existing template. Returns false if there is none.
+    pub fn update(&self, template: &PromptTemplate) -> SqlResult<bool> {
+        let rows = self.conn.lock().unwrap().execute(
+            "UPDATE templates SET description = ?, system = ?, prompt = ? WHERE name = ?",
+            params![
+                template.description,
+                template.system,
+                template.prompt,
+                template.name
+            ],
+        )?;
+        Ok(rows > 0)
+    }
+
+    pub fn delete(&self, name: &str) -> SqlResult<bool> {
+        let rows = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM templates WHERE name = ?", params![name])?;
+        Ok(rows > 0)
+    }
+}
+
+fn row_to_template(row: &rusqlite::Row) -> SqlResult<PromptTemplate> {
+    Ok(PromptTemplate {
+        name: row.get(0)?,
+        description: row.get(1)?,
+        system: row.get(2)?,
+        prompt: row.get(3)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review() -> PromptTemplate {
+        PromptTemplate {
+            name: "code-review".to_string(),
+            description: None,
+            system: Some("You review {{language}} code.".to_string()),
+            prompt: "Review this:\n{{ code }}\nFocus on {{language}}.".to_string(),
+        }
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn expands_placeholders() {
+        let expansion = review()
+            .expand(&vars(&[("language", "Rust"), ("code", "fn main() {}")]))
+            .unwrap();
+
+        assert_eq!(expansion.system.as_deref(), Some("You review Rust code."));
+        assert_eq!(
+            expansion.prompt,
+            "Review this:\nfn main() {}\nFocus on Rust."
+        );
+    }
+
+    #[test]
+    fn reports_missing_variables() {
+        assert_eq!(review().variables(), vec!["language", "code"]);
+        let err = review().expand(&vars(&[("language", "Rust")])).unwrap_err();
+        assert!(err.ends_with("missing variables: code"), "{}", err);
+    }
+
+    #[test]
+    fn store_round_trips_templates() {
+        let store = TemplateStore::in_memory().unwrap();
+        assert!(store.insert(&review()).unwrap());
+        assert!(!store.insert(&review()).unwrap());
+
+        let mut changed = review();
+        changed.prompt = "{{code}}".to_string();
+        assert!(store.update(&changed).unwrap());
+        assert_eq!(store.get("code-review").unwrap(), Some(changed));
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        assert!(store.delete("code-review").unwrap());
+        assert_eq!(store.get("code-review").unwrap(), None);
+    }
+
+    #[test]
+    fn validates_names() {
+        assert!(validate_name("code-review_v2.1").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("a/b").is_err());
+    }
+}
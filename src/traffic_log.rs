@@ -0,0 +1,322 @@
+//! On-disk traffic logs.
+//!
+//! Writes each completed transaction to a daily file under
+//! `<logging folder>/traffic/`: `<date>.jsonl` (one transaction per line),
+//! `<date>.har` (a HAR 1.2 document), or both, per `LoggingConfig.format`.
+//! Files older than the retention period are pruned at startup.
+
+use crate::config::LogFormat;
+use crate::inspector::{
+    har_entry, har_log, CapturedRequest, CapturedResponse, CapturedTransaction, TimingMetrics,
+};
+use chrono::{NaiveDate, Utc};
+use serde_json::Value;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Kind of traffic log file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFileKind {
+    Jsonl,
+    Har,
+}
+
+/// A daily traffic log file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFile {
+    pub path: PathBuf,
+    pub date: NaiveDate,
+    pub kind: LogFileKind,
+}
+
+/// Writes transactions to daily traffic log files.
+#[derive(Debug, Clone)]
+pub struct TrafficLogWriter {
+    folder: PathBuf,
+    format: LogFormat,
+    // HAR files are rewritten on append; serialize writers
+    lock: Arc<Mutex<()>>,
+}
+
+impl TrafficLogWriter {
+    /// Create a writer that stores logs under `folder`.
+    pub fn new(folder: impl Into<PathBuf>, format: LogFormat) -> Self {
+        Self {
+            folder: folder.into(),
+            format,
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Get the folder logs are written to.
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// Wait for an append in progress to finish.
+    pub fn flush(&self) {
+        drop(self.lock.lock().unwrap());
+    }
+
+    /// Append a transaction to today's log file(s).
+    pub fn append(&self, tx: &CapturedTransaction) -> std::io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        std::fs::create_dir_all(&self.folder)?;
+        let date = tx.timestamp.date_naive();
+
+        if matches!(self.format, LogFormat::Json | LogFormat::Both) {
+            let line = serde_json::to_string(tx).map_err(std::io::Error::other)?;
+            let mut file = OpenOptions::new().create(true).append(true).open(log_path(
+                &self.folder,
+                date,
+                LogFileKind::Jsonl,
+            ))?;
+            writeln!(file, "{}", line)?;
+        }
+
+        if matches!(self.format, LogFormat::Har | LogFormat::Both) {
+            let path = log_path(&self.folder, date, LogFileKind::Har);
+            let mut entries = match std::fs::read(&path) {
+                Ok(data) => serde_json::from_slice::<Value>(&data)
+                    .ok()
+                    .and_then(|har| har["log"]["entries"].as_array().cloned())
+                    .unwrap_or_default(),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(e),
+            };
+            entries.push(har_entry(tx));
+            let data =
+                serde_json::to_vec_pretty(&har_log(entries)).map_err(std::io::Error::other)?;
+            std::fs::write(&path, data)?;
+        }
+
+        Ok(())
+    }
+
+    /// Delete log files older than `retention_days`. Returns how many were removed.
+    pub fn prune(&self, retention_days: u32) -> std::io::Result<usize> {
+        let cutoff = Utc::now().date_naive() - chrono::Days::new(retention_days as u64);
+        let mut removed = 0;
+        for file in list_files(&self.folder)? {
+            if file.date < cutoff {
+                std::fs::remove_file(&file.path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Path of the log file for a date.
+pub fn log_path(folder: &Path, date: NaiveDate, kind: LogFileKind) -> PathBuf {
+    let extension = match kind {
+        LogFileKind::Jsonl => "jsonl",
+        LogFileKind::Har => "har",
+    };
+    folder.join(format!("{}.{}", date.format("%Y-%m-%d"), extension))
+}
+
+/// List traffic log files in `folder`, oldest first.
+pub fn list_files(folder: &Path) -> std::io::Result<Vec<LogFile>> {
+    let entries = match std::fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut files: Vec<LogFile> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let kind = match path.extension()?.to_str()? {
+                "jsonl" => LogFileKind::Jsonl,
+                "har" => LogFileKind::Har,
+                _ => return None,
+            };
+            let date = NaiveDate::parse_from_str(path.file_stem()?.to_str()?, "%Y-%m-%d").ok()?;
+            Some(LogFile { path, date, kind })
+        })
+        .collect();
+    files.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.path.cmp(&b.path)));
+    Ok(files)
+}
+
+/// Read the transactions stored in a log file.
+pub fn read_transactions(file: &LogFile) -> std::io::Result<Vec<CapturedTransaction>> {
+    let content = std::fs::read_to_string(&file.path)?;
+    match file.kind {
+        LogFileKind::Jsonl => Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()),
+        LogFileKind::Har => {
+            let har: Value = serde_json::from_str(&content).map_err(std::io::Error::other)?;
+            Ok(har["log"]["entries"]
+                .as_array()
+                .map(|entries| entries.iter().filter_map(transaction_from_har).collect())
+                .unwrap_or_default())
+        }
+    }
+}
+
+/// Rebuild a transaction from a HAR entry written by `har_entry`.
+pub fn transaction_from_har(entry: &Value) -> Option<CapturedTransaction> {
+    let started = entry["startedDateTime"].as_str()?;
+    let timestamp = chrono::DateTime::parse_from_rfc3339(started)
+        .ok()?
+        .with_timezone(&Utc);
+
+    let headers = |value: &Value| -> Vec<(String, String)> {
+        value
+            .as_array()
+            .map(|headers| {
+                headers
+                    .iter()
+                    .filter_map(|h| {
+                        Some((
+                            h["name"].as_str()?.to_string(),
+                            h["value"].as_str()?.to_string(),
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let body = |value: &Value| -> Option<Value> {
+        value["text"]
+            .as_str()
+            .and_then(|t| serde_json::from_str(t).ok())
+    };
+
+    let request = &entry["request"];
+    let response = &entry["response"];
+    Some(CapturedTransaction {
+        id: started.to_string(),
+        timestamp,
+        request: CapturedRequest {
+            method: request["method"].as_str().unwrap_or("GET").to_string(),
+            url: request["url"].as_str().unwrap_or_default().to_string(),
+            headers: headers(&request["headers"]),
+            body: body(&request["postData"]),
+        },
+        response: response["status"].as_u64().map(|status| CapturedResponse {
+            status: status as u16,
+            headers: headers(&response["headers"]),
+            body: body(&response["content"]),
+        }),
+        timing: TimingMetrics {
+            total_ms: entry["time"].as_u64().unwrap_or(0),
+            ttfb_ms: entry["timings"]["ttfb"].as_u64(),
+            tokens_per_sec: entry["_llmMetrics"]["tokensPerSecond"].as_f64(),
+            prompt_tokens: entry["_llmMetrics"]["promptTokens"]
+                .as_u64()
+                .map(|t| t as u32),
+            completion_tokens: entry["_llmMetrics"]["completionTokens"]
+                .as_u64()
+                .map(|t| t as u32),
+            chunks: serde_json::from_value(entry["_llmMetrics"]["chunks"].clone()).ok(),
+        },
+        ab_arm: None,
+        virtual_key: None,
+        routed_model: None,
+        annotations: Default::default(),
+        start_time: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_transaction(status: u16) -> CapturedTransaction {
+        CapturedTransaction {
+            id: "tx-1".to_string(),
+            timestamp: Utc::now(),
+            request: CapturedRequest {
+                method: "POST".to_string(),
+                url: "http://127.0.0.1:11434/v1/chat/completions".to_string(),
+                headers: vec![("content-type".to_string(), "application/json".to_string())],
+                body: Some(serde_json::json!({"model": "llama3", "messages": []})),
+            },
+            response: Some(CapturedResponse {
+                status,
+                headers: vec![],
+                body: Some(serde_json::json!({"choices": []})),
+            }),
+            timing: TimingMetrics {
+                total_ms: 1200,
+                ttfb_ms: Some(150),
+                tokens_per_sec: None,
+                prompt_tokens: Some(10),
+                completion_tokens: Some(20),
+                chunks: None,
+            },
+            ab_arm: None,
+            virtual_key: None,
+            routed_model: None,
+            annotations: Default::default(),
+            start_time: None,
+        }
+    }
+
+    #[test]
+    fn appends_jsonl_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let writer = TrafficLogWriter::new(dir.path(), LogFormat::Json);
+
+        writer.append(&sample_transaction(200)).unwrap();
+        writer.append(&sample_transaction(502)).unwrap();
+
+        let files = list_files(dir.path()).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].kind, LogFileKind::Jsonl);
+        let transactions = read_transactions(&files[0]).unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[1].response.as_ref().unwrap().status, 502);
+    }
+
+    #[test]
+    fn har_entries_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let writer = TrafficLogWriter::new(dir.path(), LogFormat::Har);
+
+        writer.append(&sample_transaction(200)).unwrap();
+        writer.append(&sample_transaction(200)).unwrap();
+
+        let files = list_files(dir.path()).unwrap();
+        assert_eq!(files[0].kind, LogFileKind::Har);
+        let transactions = read_transactions(&files[0]).unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(
+            transactions[0].request.body.as_ref().unwrap()["model"],
+            "llama3"
+        );
+        assert_eq!(transactions[0].timing.ttfb_ms, Some(150));
+        assert_eq!(transactions[0].timing.completion_tokens, Some(20));
+    }
+
+    #[test]
+    fn both_format_writes_two_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let writer = TrafficLogWriter::new(dir.path(), LogFormat::Both);
+
+        writer.append(&sample_transaction(200)).unwrap();
+
+        assert_eq!(list_files(dir.path()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn prunes_files_past_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        std::fs::write(log_path(dir.path(), old, LogFileKind::Jsonl), "").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "keep").unwrap();
+        let writer = TrafficLogWriter::new(dir.path(), LogFormat::Json);
+        writer.append(&sample_transaction(200)).unwrap();
+
+        assert_eq!(writer.prune(30).unwrap(), 1);
+        assert_eq!(list_files(dir.path()).unwrap().len(), 1);
+        assert!(dir.path().join("notes.txt").exists());
+    }
+}
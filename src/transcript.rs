@@ -0,0 +1,132 @@
+//! Append-only per-chat transcript files.
+//!
+//! Writes each chat message to `<logging folder>/transcripts/<chat id>.<ext>`
+//! as `ChatDb` stores it, so the web UI, chat memory and `multiai chat` all
+//! keep transcripts that match the database.
+
+use crate::chat::Message;
+use crate::config::{LoggingConfig, TranscriptFormat};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Writes chat messages to per-chat transcript files.
+#[derive(Debug, Clone)]
+pub struct TranscriptWriter {
+    folder: PathBuf,
+    format: TranscriptFormat,
+}
+
+impl TranscriptWriter {
+    /// Create a writer that stores transcripts under `folder`.
+    pub fn new(folder: impl Into<PathBuf>, format: TranscriptFormat) -> Self {
+        Self {
+            folder: folder.into(),
+            format,
+        }
+    }
+
+    /// The writer `[logging] transcripts` asks for, if any.
+    pub fn from_config(logging: &LoggingConfig) -> Option<Self> {
+        logging
+            .transcripts
+            .then(|| Self::new(logging.folder.join("transcripts"), logging.transcript_format))
+    }
+
+    /// Get the transcript file path for a chat.
+    pub fn path_for(&self, chat_id: &str) -> PathBuf {
+        let extension = match self.format {
+            TranscriptFormat::Markdown => "md",
+            TranscriptFormat::Jsonl => "jsonl",
+        };
+        self.folder.join(format!("{}.{}", sanitize_id(chat_id), extension))
+    }
+
+    /// Append a message to its chat's transcript.
+    pub fn append(&self, message: &Message) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.folder)?;
+
+        let entry = match self.format {
+            TranscriptFormat::Markdown => format!(
+                "### {} ({})\n\n{}\n\n",
+                message.role,
+                message.created_at.to_rfc3339(),
+                message.content
+            ),
+            TranscriptFormat::Jsonl => {
+                let line = serde_json::to_string(message).map_err(std::io::Error::other)?;
+                format!("{}\n", line)
+            }
+        };
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path_for(&message.chat_id))?;
+        file.write_all(entry.as_bytes())
+    }
+
+    /// Get the folder transcripts are written to.
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+}
+
+/// Keep chat IDs from escaping the transcript folder.
+fn sanitize_id(id: &str) -> String {
+    id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::MessageRole;
+    use chrono::Utc;
+
+    fn sample_message(content: &str) -> Message {
+        Message {
+            id: "msg-1".to_string(),
+            chat_id: "chat-1".to_string(),
+            role: MessageRole::User,
+            content: content.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn appends_markdown_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let writer = TranscriptWriter::new(dir.path(), TranscriptFormat::Markdown);
+
+        writer.append(&sample_message("Hello")).unwrap();
+        writer.append(&sample_message("Again")).unwrap();
+
+        let content = std::fs::read_to_string(writer.path_for("chat-1")).unwrap();
+        assert!(content.contains("### user"));
+        assert!(content.find("Hello").unwrap() < content.find("Again").unwrap());
+    }
+
+    #[test]
+    fn appends_one_json_object_per_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let writer = TranscriptWriter::new(dir.path(), TranscriptFormat::Jsonl);
+
+        writer.append(&sample_message("Hello")).unwrap();
+        writer.append(&sample_message("Again")).unwrap();
+
+        let content = std::fs::read_to_string(writer.path_for("chat-1")).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let parsed: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(parsed["content"], "Again");
+    }
+
+    #[test]
+    fn path_for_sanitizes_chat_id() {
+        let writer = TranscriptWriter::new("/tmp/transcripts", TranscriptFormat::Markdown);
+        let path = writer.path_for("../../etc/passwd");
+        assert_eq!(path, PathBuf::from("/tmp/transcripts/______etc_passwd.md"));
+    }
+}
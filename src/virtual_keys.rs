@@ -0,0 +1,454 @@
+//! Virtual API keys for handing out access to the gateway.
+//!
+//! Keys are minted through `/api/keys`, which needs `[gateway] api_key` set
+//! so only the gateway's owner can hand them out, and stored in SQLite next
+//! to the chat database; only a SHA-256 hash of each secret is kept. A key can carry a
+//! requests-per-minute limit, a model allowlist and a daily token quota.
+//! Requests that present one (`Authorization: Bearer mai-...`) are checked
+//! against its limits, tagged with its name in the inspector, and counted
+//! in its daily usage.
+
+use crate::storage;
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// Prefix that marks a bearer token as a gateway key rather than a provider
+/// key a client happens to send.
+pub const KEY_PREFIX: &str = "mai-";
+
+/// Settings for a new key.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct KeyLimits {
+    /// Requests per minute; unlimited when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit_rpm: Option<u32>,
+    /// Model IDs the key may use; a trailing `*` matches a prefix. Empty
+    /// allows every model.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_models: Vec<String>,
+    /// Prompt plus completion tokens per UTC day; unlimited when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_token_quota: Option<u64>,
+}
+
+/// Request body for minting a key.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct NewKey {
+    pub name: String,
+    #[serde(flatten)]
+    pub limits: KeyLimits,
+}
+
+/// A stored key, without its secret.
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct VirtualKey {
+    pub name: String,
+    /// Start of the secret, to tell keys apart.
+    pub prefix: String,
+    #[serde(flatten)]
+    pub limits: KeyLimits,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A newly minted key. The secret is only ever returned here.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MintedKey {
+    pub key: String,
+    #[serde(flatten)]
+    pub info: VirtualKey,
+}
+
+/// One key's usage on one UTC day.
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct KeyUsage {
+    pub day: NaiveDate,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// Why a request with a key was refused.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyDenied {
+    Unknown,
+    RateLimited { rpm: u32 },
+    QuotaExhausted { quota: u64 },
+}
+
+impl VirtualKey {
+    /// Whether the key may use `model`.
+    pub fn allows(&self, model: &str) -> bool {
+        let allowed = &self.limits.allowed_models;
+        allowed.is_empty()
+            || allowed
+                .iter()
+                .any(|pattern| match pattern.strip_suffix('*') {
+                    Some(prefix) => model.starts_with(prefix),
+                    None => model == pattern,
+                })
+    }
+}
+
+/// Check a key name is usable in a URL path.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid key name '{}': use letters, digits, '-', '_' or '.'",
+            name
+        ))
+    }
+}
+
+fn hash_secret(secret: &str) -> String {
+    Sha256::digest(secret.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// SQLite-backed key store, shared across handlers. Request windows for
+/// the per-minute limits are kept in memory.
+#[derive(Clone)]
+pub struct KeyStore {
+    conn: Arc<Mutex<Connection>>,
+    windows: Arc<Mutex<HashMap<String, VecDeque<Instant>>>>,
+}
+
+impl KeyStore {
+    /// Open the store in MultiAI's data directory.
+    pub fn open_default() -> SqlResult<Self> {
+        Self::with_connection(storage::open_data_db("keys.db")?)
+    }
+
+    /// Open or create a key database.
+    pub fn open<P: AsRef<Path>>(path: P) -> SqlResult<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Create an in-memory store (for testing).
+    pub fn in_memory() -> SqlResult<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> SqlResult<Self> {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS keys (
+                name TEXT PRIMARY KEY,
+                hash TEXT NOT NULL UNIQUE,
+                prefix TEXT NOT NULL,
+                limits TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS key_usage (
+                name TEXT NOT NULL,
+                day TEXT NOT NULL,
+                requests INTEGER NOT NULL DEFAULT 0,
+                prompt_tokens INTEGER NOT NULL DEFAULT 0,
+                completion_tokens INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (name, day)
+            );
+            "#,
+        )?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            windows: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// All keys, by name.
+    pub fn list(&self) -> SqlResult<Vec<VirtualKey>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT name, prefix, limits, created_at FROM keys ORDER BY name")?;
+        let keys = stmt.query_map([], row_to_key)?;
+        keys.collect()
+    }
+
+    pub fn get(&self, name: &str) -> SqlResult<Option<VirtualKey>> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT name, prefix, limits, created_at FROM keys WHERE name = ?",
+                params![name],
+                row_to_key,
+            )
+            .optional()
+    }
+
+    /// Mint a key. Returns None if the name is taken.
+    pub fn mint(&self, new_key: &NewKey) -> SqlResult<Option<MintedKey>> {
+        let secret = format!("{}{}", KEY_PREFIX, uuid::Uuid::new_v4().simple());
+        let info = VirtualKey {
+            name: new_key.name.clone(),
+            prefix: secret[..KEY_PREFIX.len() + 6].to_string(),
+            limits: new_key.limits.clone(),
+            created_at: Utc::now(),
+        };
+        let limits = serde_json::to_string(&info.limits).unwrap_or_default();
+        let rows = self.conn.lock().unwrap().execute(
+            "INSERT OR IGNORE INTO keys (name, hash, prefix, limits, created_at) \
+             VALUES (?, ?, ?, ?, ?)",
+            params![
+                info.name,
+                hash_secret(&secret),
+                info.prefix,
+                limits,
+                info.created_at.to_rfc3339()
+            ],
+        )?;
+        Ok((rows > 0).then_some(MintedKey { key: secret, info }))
+    }
+
+    /// Revoke a key. Its usage history is kept.
+    pub fn revoke(&self, name: &str) -> SqlResult<bool> {
+        let rows = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM keys WHERE name = ?", params![name])?;
+        self.windows.lock().unwrap().remove(name);
+        Ok(rows > 0)
+    }
+
+    /// Look up the key for a secret without counting a request.
+    pub fn lookup(&self, secret: &str) -> SqlResult<Option<VirtualKey>> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT name, prefix, limits, created_at FROM keys WHERE hash = ?",
+                params![hash_secret(secret)],
+                row_to_key,
+            )
+            .optional()
+    }
+
+    /// Look up the key for a secret and check its rate limit and quota.
+    /// An admitted request counts toward the per-minute limit.
+    pub fn authorize(&self, secret: &str) -> SqlResult<Result<VirtualKey, KeyDenied>> {
+        let Some(key) = self.lookup(secret)? else {
+            return Ok(Err(KeyDenied::Unknown));
+        };
+
+        if let Some(quota) = key.limits.daily_token_quota {
+            let today = self.usage_on(&key.name, Utc::now().date_naive())?;
+            if today.prompt_tokens + today.completion_tokens >= quota {
+                return Ok(Err(KeyDenied::QuotaExhausted { quota }));
+            }
+        }
+        if let Some(rpm) = key.limits.rate_limit_rpm {
+            let now = Instant::now();
+            let mut windows = self.windows.lock().unwrap();
+            let window = windows.entry(key.name.clone()).or_default();
+            while window
+                .front()
+                .is_some_and(|t| now.duration_since(*t) >= Duration::from_secs(60))
+            {
+                window.pop_front();
+            }
+            if window.len() >= rpm as usize {
+                return Ok(Err(KeyDenied::RateLimited { rpm }));
+            }
+            window.push_back(now);
+        }
+        Ok(Ok(key))
+    }
+
+    /// Count one request and its tokens against a key's usage for today.
+    pub fn record_usage(
+        &self,
+        name: &str,
+        prompt_tokens: u64,
+        completion_tokens: u64,
+    ) -> SqlResult<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO key_usage (name, day, requests, prompt_tokens, completion_tokens) \
+             VALUES (?1, ?2, 1, ?3, ?4) \
+             ON CONFLICT (name, day) DO UPDATE SET requests = requests + 1, \
+             prompt_tokens = prompt_tokens + ?3, completion_tokens = completion_tokens + ?4",
+            params![
+                name,
+                Utc::now().date_naive().to_string(),
+                prompt_tokens as i64,
+                completion_tokens as i64
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// A key's daily usage over the last `days` days, newest first.
+    pub fn usage(&self, name: &str, days: u32) -> SqlResult<Vec<KeyUsage>> {
+        let since = Utc::now().date_naive() - chrono::Days::new(days.saturating_sub(1) as u64);
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT day, requests, prompt_tokens, completion_tokens FROM key_usage \
+             WHERE name = ? AND day >= ? ORDER BY day DESC",
+        )?;
+        let rows = stmt.query_map(params![name, since.to_string()], row_to_usage)?;
+        rows.collect()
+    }
+
+    fn usage_on(&self, name: &str, day: NaiveDate) -> SqlResult<KeyUsage> {
+        let usage = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT day, requests, prompt_tokens, completion_tokens FROM key_usage \
+                 WHERE name = ? AND day = ?",
+                params![name, day.to_string()],
+                row_to_usage,
+            )
+            .optional()?;
+        Ok(usage.unwrap_or(KeyUsage {
+            day,
+            requests: 0,
+            prompt_tokens: 0,
+            completion_tokens: 0,
+        }))
+    }
+}
+
+fn row_to_key(row: &rusqlite::Row) -> SqlResult<VirtualKey> {
+    let limits: String = row.get(2)?;
+    let created_at: String = row.get(3)?;
+    Ok(VirtualKey {
+        name: row.get(0)?,
+        prefix: row.get(1)?,
+        limits: serde_json::from_str(&limits).unwrap_or_default(),
+        created_at: DateTime::parse_from_rfc3339(&created_at)
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_default(),
+    })
+}
+
+fn row_to_usage(row: &rusqlite::Row) -> SqlResult<KeyUsage> {
+    let day: String = row.get(0)?;
+    Ok(KeyUsage {
+        day: day.parse().unwrap_or_default(),
+        requests: row.get::<_, i64>(1)? as u64,
+        prompt_tokens: row.get::<_, i64>(2)? as u64,
+        completion_tokens: row.get::<_, i64>(3)? as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_key(name: &str, limits: KeyLimits) -> NewKey {
+        NewKey {
+            name: name.to_string(),
+            limits,
+        }
+    }
+
+    #[test]
+    fn minted_key_authorizes_and_is_stored_hashed() {
+        let store = KeyStore::in_memory().unwrap();
+        let minted = store
+            .mint(&new_key("editor", KeyLimits::default()))
+            .unwrap()
+            .unwrap();
+        assert!(minted.key.starts_with(KEY_PREFIX));
+        assert!(minted.key.starts_with(&minted.info.prefix));
+
+        let key = store.authorize(&minted.key).unwrap().unwrap();
+        assert_eq!(key.name, "editor");
+        assert_eq!(
+            store.authorize("mai-wrong").unwrap(),
+            Err(KeyDenied::Unknown)
+        );
+
+        let stored: String = store
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT hash FROM keys", [], |row| row.get(0))
+            .unwrap();
+        assert_ne!(stored, minted.key);
+
+        assert!(store
+            .mint(&new_key("editor", KeyLimits::default()))
+            .unwrap()
+            .is_none());
+        assert!(store.revoke("editor").unwrap());
+        assert_eq!(
+            store.authorize(&minted.key).unwrap(),
+            Err(KeyDenied::Unknown)
+        );
+    }
+
+    #[test]
+    fn rate_limit_counts_requests_per_minute() {
+        let store = KeyStore::in_memory().unwrap();
+        let limits = KeyLimits {
+            rate_limit_rpm: Some(2),
+            ..KeyLimits::default()
+        };
+        let minted = store.mint(&new_key("ci", limits)).unwrap().unwrap();
+
+        assert!(store.authorize(&minted.key).unwrap().is_ok());
+        assert!(store.authorize(&minted.key).unwrap().is_ok());
+        assert_eq!(
+            store.authorize(&minted.key).unwrap(),
+            Err(KeyDenied::RateLimited { rpm: 2 })
+        );
+    }
+
+    #[test]
+    fn daily_quota_is_enforced_from_recorded_usage() {
+        let store = KeyStore::in_memory().unwrap();
+        let limits = KeyLimits {
+            daily_token_quota: Some(100),
+            ..KeyLimits::default()
+        };
+        let minted = store.mint(&new_key("teammate", limits)).unwrap().unwrap();
+
+        store.record_usage("teammate", 40, 30).unwrap();
+        assert!(store.authorize(&minted.key).unwrap().is_ok());
+        store.record_usage("teammate", 20, 10).unwrap();
+        assert_eq!(
+            store.authorize(&minted.key).unwrap(),
+            Err(KeyDenied::QuotaExhausted { quota: 100 })
+        );
+
+        let usage = store.usage("teammate", 7).unwrap();
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].requests, 2);
+        assert_eq!(usage[0].prompt_tokens, 60);
+        assert_eq!(usage[0].completion_tokens, 40);
+    }
+
+    #[test]
+    fn allowlist_matches_exact_ids_and_prefixes() {
+        let key = VirtualKey {
+            name: "k".to_string(),
+            prefix: "mai-abc".to_string(),
+            limits: KeyLimits {
+                allowed_models: vec!["llama3".to_string(), "qwen/*".to_string()],
+                ..KeyLimits::default()
+            },
+            created_at: Utc::now(),
+        };
+        assert!(key.allows("llama3"));
+        assert!(key.allows("qwen/qwen-2.5-7b-instruct:free"));
+        assert!(!key.allows("llama3.1"));
+        assert!(!key.allows("mistral"));
+    }
+}